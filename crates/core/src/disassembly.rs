@@ -0,0 +1,255 @@
+//! Not part of the original implementation.
+//!
+//! Human-readable listings of compiled programs, similar to `Program.DumpCode` of the original implementation,
+//! for debugging `.yarnc` files without the C# tooling.
+
+use crate::prelude::*;
+use core::fmt::Write;
+use hashbrown::HashSet;
+use instruction::*;
+
+impl Program {
+    /// Lists the instructions of all nodes in alphabetical order, see [`Node::disassemble`].
+    pub fn disassemble(&self) -> String {
+        self.disassemble_with_text(|_| None)
+    }
+
+    /// Lists the instructions of all nodes in alphabetical order, annotating lines and options with the text returned by `text`,
+    /// see [`Node::disassemble_with_text`].
+    pub fn disassemble_with_text(&self, text: impl Fn(u32) -> Option<String>) -> String {
+        let nodes: Vec<_> = self
+            .nodes
+            .values()
+            .map(|node| node.disassemble_with_text(&text))
+            .collect();
+        nodes.join("\n")
+    }
+}
+
+impl Node {
+    /// Lists the headers and instructions of this node, one per line.
+    /// Every instruction is prefixed with its index, and the targets of jumps and options are labeled as `L<index>`.
+    ///
+    /// Nodes of a program loaded into a dialogue have their instructions moved out, so call this before loading it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_core::prelude::*;
+    /// # use yarnspinner_core::prelude::instruction::*;
+    /// let node = Node {
+    ///     name: "Start".to_owned(),
+    ///     instructions: vec![
+    ///         Instruction {
+    ///             instruction_type: Some(InstructionType::RunLine(RunLineInstruction {
+    ///                 line_id: 7,
+    ///                 substitution_count: 0,
+    ///             })),
+    ///         },
+    ///         Instruction {
+    ///             instruction_type: Some(InstructionType::Stop(StopInstruction {})),
+    ///         },
+    ///     ],
+    ///     headers: vec![],
+    /// };
+    /// assert_eq!(
+    ///     "Node Start:\n     0  RunLine             7 0\n     1  Stop\n",
+    ///     node.disassemble()
+    /// );
+    /// ```
+    pub fn disassemble(&self) -> String {
+        self.disassemble_with_text(|_| None)
+    }
+
+    /// Like [`Node::disassemble`], but appends the text returned by `text` for the line ID of a `RunLine` or `AddOption`
+    /// instruction as a comment, e.g. looked up in the string table of the base language.
+    pub fn disassemble_with_text(&self, text: impl Fn(u32) -> Option<String>) -> String {
+        let labels: HashSet<i32> = self
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction.instruction_type.as_ref()? {
+                InstructionType::JumpTo(JumpToInstruction { destination })
+                | InstructionType::JumpIfFalse(JumpIfFalseInstruction { destination })
+                | InstructionType::AddOption(AddOptionInstruction { destination, .. })
+                | InstructionType::AddSaliencyCandidate(AddSaliencyCandidateInstruction {
+                    destination,
+                    ..
+                })
+                | InstructionType::AddSaliencyCandidateFromNode(
+                    AddSaliencyCandidateFromNodeInstruction { destination, .. },
+                ) => Some(*destination),
+                _ => None,
+            })
+            .collect();
+
+        let mut listing = format!("Node {}:\n", self.name);
+        for header in &self.headers {
+            let _ = writeln!(listing, "    ; {}: {}", header.key, header.value);
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if i32::try_from(index).is_ok_and(|index| labels.contains(&index)) {
+                let _ = writeln!(listing, "L{index}:");
+            }
+            let (opcode, operands, line_id) = describe(instruction);
+            let mut line = format!("{index:>6}  {opcode:<20}{operands}");
+            if let Some(text) = line_id.and_then(&text) {
+                let _ = write!(line, "  ; {text:?}");
+            }
+            listing.push_str(line.trim_end());
+            listing.push('\n');
+        }
+        listing
+    }
+}
+
+/// Returns the name of the instruction, its formatted operands and the line ID it refers to, if any.
+fn describe(instruction: &Instruction) -> (&'static str, String, Option<u32>) {
+    let label = |destination: &i32| format!("L{destination}");
+    let Some(instruction_type) = &instruction.instruction_type else {
+        return ("Invalid", String::new(), None);
+    };
+    match instruction_type {
+        InstructionType::JumpTo(JumpToInstruction { destination }) => {
+            ("JumpTo", label(destination), None)
+        }
+        InstructionType::PeekAndJump(_) => ("PeekAndJump", String::new(), None),
+        InstructionType::RunLine(RunLineInstruction {
+            line_id,
+            substitution_count,
+        }) => (
+            "RunLine",
+            format!("{line_id} {substitution_count}"),
+            Some(*line_id),
+        ),
+        InstructionType::RunCommand(RunCommandInstruction {
+            command_text,
+            substitution_count,
+        }) => (
+            "RunCommand",
+            format!("{command_text:?} {substitution_count}"),
+            None,
+        ),
+        InstructionType::AddOption(AddOptionInstruction {
+            tag_id,
+            destination,
+            substitution_count,
+            has_condition,
+        }) => (
+            "AddOption",
+            format!(
+                "{tag_id} {} {substitution_count}{}",
+                label(destination),
+                if *has_condition { " conditional" } else { "" }
+            ),
+            Some(*tag_id),
+        ),
+        InstructionType::ShowOptions(_) => ("ShowOptions", String::new(), None),
+        InstructionType::PushString(PushStringInstruction { value }) => {
+            ("PushString", format!("{value:?}"), None)
+        }
+        InstructionType::PushFloat(PushFloatInstruction { value }) => {
+            ("PushFloat", value.to_string(), None)
+        }
+        InstructionType::PushBool(PushBoolInstruction { value }) => {
+            ("PushBool", value.to_string(), None)
+        }
+        InstructionType::JumpIfFalse(JumpIfFalseInstruction { destination }) => {
+            ("JumpIfFalse", label(destination), None)
+        }
+        InstructionType::Pop(_) => ("Pop", String::new(), None),
+        InstructionType::CallFunc(CallFunctionInstruction { function_name }) => {
+            ("CallFunc", function_name.clone(), None)
+        }
+        InstructionType::PushVariable(PushVariableInstruction { variable_name }) => {
+            ("PushVariable", variable_name.clone(), None)
+        }
+        InstructionType::StoreVariable(StoreVariableInstruction { variable_name }) => {
+            ("StoreVariable", variable_name.clone(), None)
+        }
+        InstructionType::Stop(_) => ("Stop", String::new(), None),
+        InstructionType::RunNode(RunNodeInstruction { node_name }) => {
+            ("RunNode", node_name.clone(), None)
+        }
+        InstructionType::PeekAndRunNode(_) => ("PeekAndRunNode", String::new(), None),
+        InstructionType::DetourToNode(DetourToNodeInstruction { node_name }) => {
+            ("DetourToNode", node_name.clone(), None)
+        }
+        InstructionType::PeekAndDetourToNode(_) => ("PeekAndDetourToNode", String::new(), None),
+        InstructionType::Return(_) => ("Return", String::new(), None),
+        InstructionType::AddSaliencyCandidate(AddSaliencyCandidateInstruction {
+            content_id,
+            complexity_score,
+            destination,
+        }) => (
+            "AddSaliencyCandidate",
+            format!("{content_id:?} {complexity_score} {}", label(destination)),
+            None,
+        ),
+        InstructionType::AddSaliencyCandidateFromNode(
+            AddSaliencyCandidateFromNodeInstruction {
+                node_name,
+                destination,
+            },
+        ) => (
+            "AddSaliencyCandidateFromNode",
+            format!("{node_name} {}", label(destination)),
+            None,
+        ),
+        InstructionType::SelectSaliencyCandidate(_) => {
+            ("SelectSaliencyCandidate", String::new(), None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_jump_targets_and_annotates_lines() {
+        let node = Node {
+            name: "Start".to_owned(),
+            headers: vec![Header {
+                key: "title".to_owned(),
+                value: "Start".to_owned(),
+            }],
+            instructions: [
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 1,
+                    destination: 2,
+                    substitution_count: 0,
+                    has_condition: true,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 2,
+                    substitution_count: 1,
+                }),
+                InstructionType::RunCommand(RunCommandInstruction {
+                    command_text: "wait 1".to_owned(),
+                    substitution_count: 0,
+                }),
+                InstructionType::JumpTo(JumpToInstruction { destination: 0 }),
+            ]
+            .into_iter()
+            .map(|instruction_type| Instruction {
+                instruction_type: Some(instruction_type),
+            })
+            .collect(),
+        };
+        let text = |line_id| (line_id == 1).then(|| "Say \"hi\"".to_owned());
+        assert_eq!(
+            "Node Start:
+    ; title: Start
+L0:
+     0  AddOption           1 L2 0 conditional  ; \"Say \\\"hi\\\"\"
+     1  ShowOptions
+L2:
+     2  RunLine             2 1
+     3  RunCommand          \"wait 1\" 0
+     4  JumpTo              L0
+",
+            node.disassemble_with_text(text)
+        );
+    }
+}
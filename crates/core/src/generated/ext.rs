@@ -112,7 +112,57 @@ impl Display for InvalidOpCodeError {
     }
 }
 
+/// An error returned by [`Program::from_yarnc_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramDecodeError {
+    /// The bytes are not an encoded [`Program`].
+    InvalidEncoding(prost::DecodeError),
+    /// The initial value of the given variable is empty.
+    MissingInitialValue {
+        /// The name of the variable.
+        variable_name: String,
+    },
+}
+
+impl Error for ProgramDecodeError {}
+
+impl Display for ProgramDecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ProgramDecodeError::InvalidEncoding(e) => write!(f, "Failed to decode program: {e}"),
+            ProgramDecodeError::MissingInitialValue { variable_name } => write!(
+                f,
+                "The initial value of the variable {variable_name} is empty"
+            ),
+        }
+    }
+}
+
 impl Program {
+    /// Decodes a program compiled by the Yarn Spinner compiler, i.e. the contents of a `.yarnc` file.
+    ///
+    /// Unlike [`prost::Message::decode`], this also rejects programs whose initial values are empty, which cannot be loaded.
+    /// The instructions are not validated: running a malformed program from an untrusted source such as a mod
+    /// fails with the runtime's `DialogueError::InvalidInstruction` instead.
+    ///
+    /// ## Errors
+    ///
+    /// Returns a [`ProgramDecodeError`] if the bytes are not an encoded program or an initial value is empty.
+    pub fn from_yarnc_bytes(bytes: &[u8]) -> Result<Self, ProgramDecodeError> {
+        let program =
+            <Self as prost::Message>::decode(bytes).map_err(ProgramDecodeError::InvalidEncoding)?;
+        if let Some((variable_name, _)) = program
+            .initial_values
+            .iter()
+            .find(|(_, value)| value.value.is_none())
+        {
+            return Err(ProgramDecodeError::MissingInitialValue {
+                variable_name: variable_name.clone(),
+            });
+        }
+        Ok(program)
+    }
+
     /// Creates a new Program by merging multiple Programs together.
     ///
     /// The new program will contain every node from every input program.
@@ -134,4 +184,4 @@ impl Program {
         }
         Some(output)
     }
-}
\ No newline at end of file
+}
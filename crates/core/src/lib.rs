@@ -13,12 +13,16 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod disassembly;
 mod generated;
 mod internal_value;
 mod library;
 mod line_id;
+mod node_graph;
 mod operator;
 mod position;
+mod program_memory;
+mod stable_hash;
 pub mod types;
 mod yarn_fn;
 mod yarn_value;
@@ -35,17 +39,21 @@ pub mod prelude {
         alloc::vec,
         alloc::vec::Vec,
     };
+    #[cfg(feature = "serde")]
+    pub(crate) use serde::{Deserialize, Serialize};
 
     pub use crate::{
         generated::{
-            instruction, operand::Value as OperandValue, Header, Instruction,
-            InvalidOpCodeError, Node, Operand, Program,
+            instruction, operand::Value as OperandValue, Header, Instruction, InvalidOpCodeError,
+            Node, Operand, Program, ProgramDecodeError,
         },
         internal_value::*,
         library::*,
         line_id::*,
+        node_graph::*,
         operator::*,
         position::*,
+        stable_hash::*,
         types::Type,
         yarn_fn::*,
         yarn_value::*,
@@ -4,20 +4,28 @@ use crate::prelude::*;
 use alloc::borrow::Cow;
 use core::fmt::Display;
 
-use hashbrown::hash_map;
+use hashbrown::{hash_map, HashMap};
+
+mod merge;
+mod metadata;
+
+pub use {merge::*, metadata::*};
 
 /// A collection of functions that can be called from Yarn scripts.
 ///
 /// Can be conveniently created with the [`yarn_library!`] macro.
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
-pub struct Library(YarnFnRegistry);
+pub struct Library {
+    functions: YarnFnRegistry,
+    metadata: HashMap<Cow<'static, str>, FunctionMetadata>,
+}
 
 impl Extend<<YarnFnRegistry as IntoIterator>::Item> for Library {
     fn extend<T: IntoIterator<Item = (Cow<'static, str>, Box<dyn UntypedYarnFn>)>>(
         &mut self,
         iter: T,
     ) {
-        self.0.extend(iter);
+        self.functions.extend(iter);
     }
 }
 
@@ -26,7 +34,7 @@ impl IntoIterator for Library {
     type IntoIter = hash_map::IntoIter<Cow<'static, str>, Box<dyn UntypedYarnFn>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.functions.into_iter()
     }
 }
 
@@ -38,23 +46,25 @@ impl Library {
 
     /// Loads functions from another [`Library`].
     ///
-    /// Will overwrite any functions that have the same name.
+    /// Will overwrite any functions and [`FunctionMetadata`] that have the same name.
     ///
     /// ## Implementation Notes
     ///
     /// The original implementation throws an exception if a function with the same name already exists.
+    /// See [`Library::extend_with`] for control over what happens in that case.
     pub fn import(&mut self, other: Self) {
-        self.0.extend(other.0 .0);
+        self.functions.extend(other.functions.0);
+        self.metadata.extend(other.metadata);
     }
 
     /// Iterates over the names and functions in the library.
-    pub fn iter(&self) -> impl Iterator<Item = (&str, &(dyn UntypedYarnFn))> {
-        self.0.iter()
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &dyn UntypedYarnFn)> {
+        self.functions.iter()
     }
 
     /// Gets a function by name.
-    pub fn get(&self, name: &str) -> Option<&(dyn UntypedYarnFn)> {
-        self.0.get(name)
+    pub fn get(&self, name: &str) -> Option<&dyn UntypedYarnFn> {
+        self.functions.get(name)
     }
 
     /// Generates a unique tracking variable name.
@@ -65,6 +75,34 @@ impl Library {
         format!("$Yarn.Internal.Visiting.{node_name}")
     }
 
+    /// The prefix of the variables generated by [`Library::generate_unique_once_variable`].
+    pub const ONCE_VARIABLE_PREFIX: &'static str = "$Yarn.Internal.Once.";
+
+    /// Generates the name of the boolean variable that tracks whether the `<<once>>` content with the given ID was already seen,
+    /// following the convention of Yarn Spinner 3. The variable is unset, i.e. `false`, until the content is run for the first time.
+    /// Characters not allowed in variable names, such as the `:` of a line ID, are replaced by `_`.
+    pub fn generate_unique_once_variable(content_id: &str) -> String {
+        format!(
+            "{}{}",
+            Self::ONCE_VARIABLE_PREFIX,
+            sanitize_content_id(content_id)
+        )
+    }
+
+    /// The prefix of the variables generated by [`Library::generate_unique_view_count_variable`].
+    pub const VIEW_COUNT_VARIABLE_PREFIX: &'static str = "$Yarn.Internal.ViewCount.";
+
+    /// Generates the name of the number variable that counts how often the line group candidate or node group member
+    /// with the given content ID was selected. The variable is unset, i.e. `0`, until it is selected for the first time.
+    /// Characters not allowed in variable names, such as the `:` of a line ID, are replaced by `_`.
+    pub fn generate_unique_view_count_variable(content_id: &str) -> String {
+        format!(
+            "{}{}",
+            Self::VIEW_COUNT_VARIABLE_PREFIX,
+            sanitize_content_id(content_id)
+        )
+    }
+
     /// Creates a [`Library`] with the standard functions that are included in Yarn Spinner.
     /// These are:
     /// - `string`: Converts a value to a string.
@@ -77,6 +115,25 @@ impl Library {
             "number" => |value: YarnValue| f32::try_from(value).expect("Failed to convert a Yarn value to a number"),
             "bool" => |value: YarnValue| bool::try_from(value).expect("Failed to convert a Yarn value to a bool"),
         );
+        library
+            .set_function_metadata(
+                "string",
+                FunctionMetadata::new()
+                    .with_description("Converts a value to a string.")
+                    .with_parameter("value", "The value to convert."),
+            )
+            .set_function_metadata(
+                "number",
+                FunctionMetadata::new()
+                    .with_description("Converts a value to a number.")
+                    .with_parameter("value", "The value to convert."),
+            )
+            .set_function_metadata(
+                "bool",
+                FunctionMetadata::new()
+                    .with_description("Converts a value to a boolean.")
+                    .with_parameter("value", "The value to convert."),
+            );
         for r#type in [Type::Number, Type::String, Type::Boolean] {
             library.add_methods(r#type);
         }
@@ -120,37 +177,99 @@ impl Library {
         F: YarnFn<Marker> + 'static + Clone,
         F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
     {
-        self.0.register_function(name, function);
+        self.functions.register_function(name, function);
+        self
+    }
+
+    /// Adds a new function returning an [`Option`] to the registry, which returns the given default instead of failing when the function returns `None`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use yarnspinner_core::prelude::*;
+    /// # let mut library = Library::default();
+    /// library.add_function_with_default("item_count", |item: &str| (item == "apple").then_some(3), 0);
+    ///
+    /// let item_count = library.get("item_count").unwrap();
+    /// assert_eq!(YarnValue::Integer(3), item_count.try_call(vec!["apple".into()]).unwrap());
+    /// assert_eq!(YarnValue::Integer(0), item_count.try_call(vec!["pear".into()]).unwrap());
+    /// ```
+    pub fn add_function_with_default<Marker, F, T>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        function: F,
+        default: T,
+    ) -> &mut Self
+    where
+        Marker: 'static,
+        F: YarnFn<Marker, Out = Option<T>> + 'static + Clone,
+        T: IntoYarnValueFromNonYarnValue + 'static + Clone,
+    {
+        let wrapped = YarnFnWrapper::from(function).with_default(default.into_yarn_value());
+        self.functions.add_boxed(name, Box::new(wrapped));
+        self
+    }
+
+    /// Adds a new function that receives a mutable context as its first parameter, see [`YarnContextFn`].
+    ///
+    /// The context is supplied by the game whenever it runs the dialogue, e.g. with `Dialogue::continue_with_context` of the runtime.
+    /// Calling the function without a context of type `Ctx` fails with [`YarnFnError::MissingContext`].
+    pub fn add_function_with_context<Ctx, Marker, F>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        function: F,
+    ) -> &mut Self
+    where
+        Ctx: 'static,
+        Marker: 'static,
+        F: YarnContextFn<Ctx, Marker> + 'static,
+    {
+        let wrapped = YarnContextFnWrapper::from(function);
+        self.functions.add_boxed(name, Box::new(wrapped));
         self
     }
 
     /// Returns `true` if the library contains a function with the given name.
     pub fn contains_function(&self, name: &str) -> bool {
-        self.0.contains_function(name)
+        self.functions.contains_function(name)
     }
 
     /// Iterates over the names of all functions in the library.
     pub fn names(&self) -> impl Iterator<Item = &str> {
-        self.0.names()
+        self.functions.names()
     }
 
     /// Iterates over all functions in the library.
-    pub fn functions(&self) -> impl Iterator<Item = &(dyn UntypedYarnFn)> {
-        self.0.functions()
+    pub fn functions(&self) -> impl Iterator<Item = &dyn UntypedYarnFn> {
+        self.functions.functions()
     }
 
     /// Registers the methods found inside a type.
     fn add_methods(&mut self, r#type: Type) {
         for (name, function) in r#type.methods().into_iter() {
             let canonical_name = r#type.get_canonical_name_for_method(name.as_ref());
-            self.0.add_boxed(canonical_name, function.clone());
+            self.functions.add_boxed(canonical_name, function.clone());
         }
     }
 }
 
+/// Replaces the characters of a content ID that are not allowed in variable names by `_`.
+fn sanitize_content_id(content_id: &str) -> String {
+    content_id
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 impl Display for Library {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        let mut functions: Vec<_> = self.0.iter().collect();
+        let mut functions: Vec<_> = self.functions.iter().collect();
         functions.sort_by_key(|(name, _)| name.to_string());
         writeln!(f, "{{")?;
         for (name, function) in functions {
@@ -0,0 +1,174 @@
+//! Not part of the original implementation.
+//!
+//! Composes libraries from several layers, e.g. the standard library, an engine integration and the game itself,
+//! without silently overwriting functions of an earlier layer.
+
+use crate::prelude::*;
+use core::error::Error;
+use core::fmt::{self, Display};
+
+/// What [`Library::extend_with`] does with a function whose name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConflictPolicy {
+    /// Leaves the library untouched and returns a [`LibraryMergeError`] listing all conflicts.
+    #[default]
+    Error,
+    /// Keeps the existing function.
+    Skip,
+    /// Replaces the existing function, like [`Library::import`] does.
+    Replace,
+}
+
+/// The outcome of a successful [`Library::extend_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LibraryMergeReport {
+    /// The names of the functions that were added without a conflict, in alphabetical order.
+    pub added: Vec<String>,
+    /// The names of the functions that already existed and were skipped or replaced according to the [`ConflictPolicy`], in alphabetical order.
+    pub conflicts: Vec<String>,
+}
+
+/// Returned by [`Library::extend_with`] with [`ConflictPolicy::Error`] if any function name is already taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LibraryMergeError {
+    /// The names of the conflicting functions, in alphabetical order.
+    pub conflicts: Vec<String>,
+}
+
+impl Error for LibraryMergeError {}
+
+impl Display for LibraryMergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The library already contains functions named {}",
+            self.conflicts.join(", ")
+        )
+    }
+}
+
+impl Library {
+    /// Adds the functions of another library, resolving functions of the same name according to the given policy.
+    /// The [`FunctionMetadata`] of a function is merged along with it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns a [`LibraryMergeError`] without changing this library if the policy is [`ConflictPolicy::Error`]
+    /// and any function of the other library already exists in this one.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_core::prelude::*;
+    /// let mut library = Library::standard_library();
+    /// let engine = yarn_library! {
+    ///     "string" => |value: YarnValue| value.to_string(),
+    ///     "random" => || 0.5,
+    /// };
+    /// assert!(library.extend_with(engine.clone(), ConflictPolicy::Error).is_err());
+    ///
+    /// let report = library.extend_with(engine, ConflictPolicy::Skip).unwrap();
+    /// assert_eq!(vec!["random"], report.added);
+    /// assert_eq!(vec!["string"], report.conflicts);
+    /// ```
+    pub fn extend_with(
+        &mut self,
+        other: Library,
+        policy: ConflictPolicy,
+    ) -> Result<LibraryMergeReport, LibraryMergeError> {
+        let mut report = LibraryMergeReport::default();
+        for name in other.names() {
+            if self.contains_function(name) {
+                report.conflicts.push(name.to_owned());
+            } else {
+                report.added.push(name.to_owned());
+            }
+        }
+        report.added.sort();
+        report.conflicts.sort();
+        if policy == ConflictPolicy::Error && !report.conflicts.is_empty() {
+            return Err(LibraryMergeError {
+                conflicts: report.conflicts,
+            });
+        }
+
+        let Library {
+            functions,
+            mut metadata,
+        } = other;
+        for (name, function) in functions {
+            if policy == ConflictPolicy::Skip && self.contains_function(&name) {
+                metadata.remove(&name);
+                continue;
+            }
+            self.functions.add_boxed(name, function);
+        }
+        for (name, metadata) in metadata {
+            if policy == ConflictPolicy::Replace || !self.metadata.contains_key(&name) {
+                self.metadata.insert(name, metadata);
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_conflicts_according_to_policy() {
+        let base = || {
+            let mut library = yarn_library! { "gold" => || 1, };
+            library.set_function_metadata("gold", FunctionMetadata::new().with_description("Base"));
+            library
+        };
+        let game = || {
+            let mut library = yarn_library! {
+                "gold" => || 2,
+                "level" => || 3,
+            };
+            library.set_function_metadata("gold", FunctionMetadata::new().with_description("Game"));
+            library
+        };
+        let gold = |library: &Library| library.get("gold").unwrap().call(vec![]);
+        let description = |library: &Library| {
+            library
+                .function_metadata("gold")
+                .unwrap()
+                .description
+                .clone()
+                .unwrap()
+        };
+
+        let mut library = base();
+        let error = library
+            .extend_with(game(), ConflictPolicy::Error)
+            .unwrap_err();
+        assert_eq!(vec!["gold"], error.conflicts);
+        assert!(!library.contains_function("level"));
+
+        let mut library = base();
+        library.extend_with(game(), ConflictPolicy::Skip).unwrap();
+        assert_eq!(YarnValue::Integer(1), gold(&library));
+        assert_eq!("Base", description(&library));
+        assert!(library.contains_function("level"));
+
+        let mut library = base();
+        let report = library
+            .extend_with(game(), ConflictPolicy::Replace)
+            .unwrap();
+        assert_eq!(YarnValue::Integer(2), gold(&library));
+        assert_eq!("Game", description(&library));
+        assert_eq!(
+            LibraryMergeReport {
+                added: vec!["level".to_owned()],
+                conflicts: vec!["gold".to_owned()],
+            },
+            report
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Not part of the original implementation.
+//!
+//! Describes the functions of a [`Library`] for editors and language servers, including the `.ysls.json` format
+//! read by the official Yarn Spinner extension for Visual Studio Code.
+
+use crate::prelude::*;
+use crate::types::FunctionType;
+use alloc::borrow::Cow;
+use core::fmt::Write;
+
+/// Human-readable documentation of a function in a [`Library`], set via [`Library::set_function_metadata`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionMetadata {
+    /// What the function does.
+    pub description: Option<String>,
+    /// The parameters of the function, in order. May be shorter than the actual parameter list if not all are documented.
+    pub parameters: Vec<ParameterMetadata>,
+    /// What the function returns.
+    pub return_description: Option<String>,
+}
+
+/// Human-readable documentation of a parameter of a function, see [`FunctionMetadata`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParameterMetadata {
+    /// The name of the parameter.
+    pub name: String,
+    /// What the parameter means.
+    pub description: Option<String>,
+}
+
+impl FunctionMetadata {
+    /// Creates metadata without any documentation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the description of the function.
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Appends a documented parameter.
+    #[must_use]
+    pub fn with_parameter(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.parameters.push(ParameterMetadata {
+            name: name.into(),
+            description: Some(description.into()),
+        });
+        self
+    }
+
+    /// Sets the description of the returned value.
+    #[must_use]
+    pub fn with_return_description(mut self, description: impl Into<String>) -> Self {
+        self.return_description = Some(description.into());
+        self
+    }
+}
+
+/// A function of a [`Library`] together with its Yarn signature and metadata, see [`Library::registrations`].
+#[derive(Debug, Clone)]
+pub struct FunctionRegistration<'a> {
+    /// The name the function is called by in Yarn.
+    pub name: &'a str,
+    /// The function itself.
+    pub function: &'a dyn UntypedYarnFn,
+    /// The Yarn types of the parameters and the return value. Types without a Yarn equivalent, e.g. of optional parameters, are `None`.
+    pub signature: FunctionType,
+    /// The documentation set via [`Library::set_function_metadata`], if any.
+    pub metadata: Option<&'a FunctionMetadata>,
+}
+
+impl Library {
+    /// Sets the documentation of the function with the given name, which is reported by [`Library::registrations`] and
+    /// [`Library::generate_ysls`]. May be called before or after the function is added. Replaces previously set metadata.
+    pub fn set_function_metadata(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        metadata: FunctionMetadata,
+    ) -> &mut Self {
+        self.metadata.insert(name.into(), metadata);
+        self
+    }
+
+    /// Gets the documentation of the function with the given name.
+    pub fn function_metadata(&self, name: &str) -> Option<&FunctionMetadata> {
+        self.metadata.get(name)
+    }
+
+    /// Gets the Yarn types of the parameters and the return value of the function with the given name.
+    pub fn signature(&self, name: &str) -> Option<FunctionType> {
+        self.get(name).map(signature)
+    }
+
+    /// Iterates over all functions in the library in alphabetical order, with their signatures and metadata.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_core::prelude::*;
+    /// let mut library = Library::new();
+    /// library
+    ///     .add_function("dice", |sides: u32| sides)
+    ///     .set_function_metadata(
+    ///         "dice",
+    ///         FunctionMetadata::new()
+    ///             .with_description("Rolls a die.")
+    ///             .with_parameter("sides", "The number of sides of the die."),
+    ///     );
+    ///
+    /// let dice = library.registrations().next().unwrap();
+    /// assert_eq!("dice", dice.name);
+    /// assert_eq!("Fn(Number) -> Number", dice.signature.to_string());
+    /// assert_eq!("sides", dice.metadata.unwrap().parameters[0].name);
+    /// ```
+    pub fn registrations(&self) -> impl Iterator<Item = FunctionRegistration<'_>> {
+        let mut registrations: Vec<_> = self
+            .iter()
+            .map(|(name, function)| FunctionRegistration {
+                name,
+                function,
+                signature: signature(function),
+                metadata: self.function_metadata(name),
+            })
+            .collect();
+        registrations.sort_by_key(|registration| registration.name);
+        registrations.into_iter()
+    }
+
+    /// Generates a Yarn Spinner Language Server definition file, usually saved as `<name>.ysls.json`,
+    /// which lets the official Visual Studio Code extension autocomplete and type-check calls to the functions of this library.
+    ///
+    /// Methods of the built-in types, such as `Number.Add`, are implicitly called by operators and therefore left out.
+    pub fn generate_ysls(&self) -> String {
+        let functions: Vec<_> = self
+            .registrations()
+            .filter(|registration| !registration.name.contains('.'))
+            .map(|registration| ysls_function(&registration))
+            .collect();
+        let mut json = String::from("{\n  \"Commands\": [],\n  \"Functions\": [");
+        if !functions.is_empty() {
+            json.push('\n');
+            json.push_str(&functions.join(",\n"));
+            json.push_str("\n  ");
+        }
+        json.push_str("]\n}\n");
+        json
+    }
+}
+
+fn signature(function: &dyn UntypedYarnFn) -> FunctionType {
+    let mut signature = FunctionType::default();
+    for parameter in function.parameter_types() {
+        signature.add_parameter(Type::try_from(parameter).ok());
+    }
+    signature.set_return_type(Type::try_from(function.return_type()).ok());
+    signature
+}
+
+fn ysls_function(registration: &FunctionRegistration<'_>) -> String {
+    let metadata = registration.metadata.cloned().unwrap_or_default();
+    let mut json = String::new();
+    let _ = write!(
+        json,
+        "    {{\n      \"YarnName\": {name},\n      \"DefinitionName\": {name},\n      \"Language\": \"rust\",\n      \"Documentation\": {},\n      \"ReturnType\": \"{}\",\n      \"Parameters\": [",
+        json_string(metadata.description.as_deref().unwrap_or_default()),
+        ysls_type(registration.signature.return_type.as_ref()),
+        name = json_string(registration.name),
+    );
+    let parameters: Vec<_> = registration
+        .signature
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(index, r#type)| {
+            let documented = metadata.parameters.get(index);
+            let name = documented
+                .map_or_else(|| format!("arg{index}"), |parameter| parameter.name.clone());
+            let description = documented
+                .and_then(|parameter| parameter.description.as_deref())
+                .unwrap_or_default();
+            format!(
+                "        {{ \"Name\": {}, \"Type\": \"{}\", \"Documentation\": {} }}",
+                json_string(&name),
+                ysls_type(r#type),
+                json_string(description)
+            )
+        })
+        .collect();
+    if !parameters.is_empty() {
+        json.push('\n');
+        json.push_str(&parameters.join(",\n"));
+        json.push_str("\n      ");
+    }
+    json.push_str("]\n    }");
+    json
+}
+
+/// The type names used by the language server.
+fn ysls_type(r#type: &Option<Type>) -> &'static str {
+    match r#type {
+        Some(Type::Number) => "number",
+        Some(Type::String) => "string",
+        Some(Type::Boolean) => "bool",
+        _ => "any",
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_ysls() {
+        let mut library = Library::standard_library();
+        library
+            .add_function("greet", |name: &str, _: YarnValue| format!("Hi {name}"))
+            .set_function_metadata(
+                "greet",
+                FunctionMetadata::new()
+                    .with_description("Greets \"someone\".")
+                    .with_parameter("name", "Who to greet."),
+            );
+        let ysls = library.generate_ysls();
+        assert!(!ysls.contains("Number.Add"));
+        assert!(ysls.starts_with(
+            "{\n  \"Commands\": [],\n  \"Functions\": [\n    {\n      \"YarnName\": \"bool\","
+        ));
+        assert!(ysls.contains(
+            r#"      "YarnName": "greet",
+      "DefinitionName": "greet",
+      "Language": "rust",
+      "Documentation": "Greets \"someone\".",
+      "ReturnType": "string",
+      "Parameters": [
+        { "Name": "name", "Type": "string", "Documentation": "Who to greet." },
+        { "Name": "arg1", "Type": "any", "Documentation": "" }
+      ]
+    },"#
+        ));
+
+        assert_eq!(
+            "{\n  \"Commands\": [],\n  \"Functions\": []\n}\n",
+            Library::new().generate_ysls()
+        );
+    }
+}
@@ -30,3 +30,136 @@ impl Display for LineId {
         self.0.fmt(f)
     }
 }
+
+/// The prefix the Yarn Spinner compiler uses for line IDs.
+const LINE_ID_PREFIX: &str = "line:";
+
+/// Marks the string form of a [`CompactLineId`] that could only be stored as a hash.
+const HASHED_MARKER: char = '~';
+
+/// An opt-in, allocation-free alternative to [`LineId`] that is [`Copy`] and compares as a single integer.
+///
+/// IDs of the form `line:` followed by 1 to 16 lowercase hex digits, as generated by the Yarn Spinner compiler,
+/// are stored exactly and convert back into the same [`LineId`]. All other IDs, e.g. hand-written ones like `line:nooooo`,
+/// are stored as the [`stable_hash`] of the whole ID. Their string form is `line:~` followed by the hash,
+/// which parses back into the same `CompactLineId`, but not the original [`LineId`].
+///
+/// With the `serde` feature, this is serialized as its string form, so data written for a [`LineId`] can be read as a `CompactLineId`.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// let line_id = LineId::from("line:0a1b2c");
+/// let compact = CompactLineId::new(&line_id.0);
+/// assert_eq!(compact, line_id);
+/// assert_eq!(Some(line_id), compact.to_line_id());
+///
+/// let hand_written = CompactLineId::new("line:nooooo");
+/// assert_eq!(hand_written, LineId::from("line:nooooo"));
+/// assert_eq!(None, hand_written.to_line_id());
+/// assert_eq!(hand_written, CompactLineId::new(&hand_written.to_string()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactLineId {
+    value: u64,
+    /// The number of hex digits of an exactly stored ID, or 0 if `value` is a hash.
+    digits: u8,
+}
+
+impl CompactLineId {
+    /// Creates the compact form of the given line ID without allocating.
+    pub fn new(line_id: &str) -> Self {
+        if let Some(hex) = line_id.strip_prefix(LINE_ID_PREFIX) {
+            if let Some(value) = parse_lowercase_hex(hex) {
+                return Self {
+                    value,
+                    digits: hex.len() as u8,
+                };
+            }
+            if let Some(value) = hex
+                .strip_prefix(HASHED_MARKER)
+                .filter(|hash| hash.len() == 16)
+                .and_then(parse_lowercase_hex)
+            {
+                return Self { value, digits: 0 };
+            }
+        }
+        Self {
+            value: stable_hash(line_id.as_bytes()),
+            digits: 0,
+        }
+    }
+
+    /// Returns `true` if the ID was stored exactly, i.e. [`CompactLineId::to_line_id`] returns `Some`.
+    pub fn is_exact(&self) -> bool {
+        self.digits > 0
+    }
+
+    /// Converts this back into the [`LineId`] it was created from. Returns `None` if only a hash of the ID was stored.
+    pub fn to_line_id(&self) -> Option<LineId> {
+        self.is_exact().then(|| LineId(self.to_string()))
+    }
+}
+
+impl Display for CompactLineId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_exact() {
+            write!(
+                f,
+                "{LINE_ID_PREFIX}{:0width$x}",
+                self.value,
+                width = self.digits as usize
+            )
+        } else {
+            write!(f, "{LINE_ID_PREFIX}{HASHED_MARKER}{:016x}", self.value)
+        }
+    }
+}
+
+impl From<&LineId> for CompactLineId {
+    fn from(line_id: &LineId) -> Self {
+        Self::new(&line_id.0)
+    }
+}
+
+impl From<&str> for CompactLineId {
+    fn from(line_id: &str) -> Self {
+        Self::new(line_id)
+    }
+}
+
+impl PartialEq<LineId> for CompactLineId {
+    fn eq(&self, other: &LineId) -> bool {
+        *self == Self::new(&other.0)
+    }
+}
+
+impl PartialEq<CompactLineId> for LineId {
+    fn eq(&self, other: &CompactLineId) -> bool {
+        other == self
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CompactLineId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CompactLineId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let line_id = <alloc::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Self::new(&line_id))
+    }
+}
+
+fn parse_lowercase_hex(hex: &str) -> Option<u64> {
+    let is_lowercase_hex = |c: u8| c.is_ascii_digit() || (b'a'..=b'f').contains(&c);
+    if hex.is_empty() || hex.len() > 16 || !hex.bytes().all(is_lowercase_hex) {
+        return None;
+    }
+    u64::from_str_radix(hex, 16).ok()
+}
@@ -0,0 +1,352 @@
+//! Not part of the original implementation.
+//!
+//! Extracts which nodes lead to which from the instructions of a [`Program`], e.g. to visualize the structure of a story
+//! or to find nodes that nothing leads to.
+
+use crate::prelude::*;
+use core::fmt::Write;
+use hashbrown::HashSet;
+use instruction::{
+    AddOptionInstruction, AddSaliencyCandidateFromNodeInstruction, DetourToNodeInstruction,
+    InstructionType, JumpToInstruction, RunNodeInstruction,
+};
+
+/// The nodes of a [`Program`] and the edges between them, created by [`Program::to_node_graph`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeGraph {
+    /// The names of all nodes of the program, in alphabetical order.
+    pub nodes: Vec<String>,
+    /// The edges between the nodes, ordered by the node they start at and their position in it.
+    /// An edge may lead to a node that is not part of the program, e.g. one of another program that is loaded later.
+    pub edges: Vec<NodeGraphEdge>,
+}
+
+/// A way to get from one node to another, see [`NodeGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeGraphEdge {
+    /// The name of the node the edge starts at.
+    pub from: String,
+    /// The name of the node the edge leads to.
+    pub to: String,
+    /// How the edge is taken.
+    pub kind: NodeGraphEdgeKind,
+}
+
+/// How a [`NodeGraphEdge`] is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NodeGraphEdgeKind {
+    /// A `<<jump>>` that is not the immediate consequence of selecting an option.
+    Jump,
+    /// A `<<detour>>`, which returns to the node it started at.
+    Detour,
+    /// Selecting the option with the given line ID, whose content ends in a `<<jump>>` or `<<detour>>` without further branching.
+    Option {
+        /// The line ID of the option.
+        line_id: u32,
+    },
+}
+
+impl Program {
+    /// Extracts the [`NodeGraph`] of this program from the `RunNode`, `DetourToNode` and `AddOption` instructions of its nodes.
+    ///
+    /// Jumps to a node whose name is only known at runtime, e.g. `<<jump {$destination}>>`, are not included.
+    /// Programs loaded into a dialogue have their instructions moved out, so call this before loading them.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_core::prelude::*;
+    /// # let program = Program::default();
+    /// let graph = program.to_node_graph();
+    /// std::fs::write(std::env::temp_dir().join("story.dot"), graph.to_dot()).unwrap();
+    /// let orphans: Vec<_> = graph.orphaned_nodes(["Start"]).collect();
+    /// assert!(orphans.is_empty(), "Nothing leads to {orphans:?}");
+    /// ```
+    pub fn to_node_graph(&self) -> NodeGraph {
+        let nodes: Vec<_> = self.nodes.keys().cloned().collect();
+        let mut edges = Vec::new();
+        for node_name in &nodes {
+            let instructions = &self.nodes[node_name].instructions;
+            let mut option_targets = HashSet::new();
+            for instruction in instructions {
+                if let Some(InstructionType::AddOption(AddOptionInstruction {
+                    tag_id,
+                    destination,
+                    ..
+                })) = &instruction.instruction_type
+                {
+                    if let Some((index, to, kind)) = follow_option(instructions, *destination) {
+                        option_targets.insert(index);
+                        let kind = match kind {
+                            NodeGraphEdgeKind::Detour => NodeGraphEdgeKind::Detour,
+                            _ => NodeGraphEdgeKind::Option { line_id: *tag_id },
+                        };
+                        push_edge(&mut edges, node_name, to, kind);
+                    }
+                }
+            }
+            for (index, instruction) in instructions.iter().enumerate() {
+                if option_targets.contains(&index) {
+                    continue;
+                }
+                if let Some((to, kind)) = node_transition(instruction) {
+                    push_edge(&mut edges, node_name, to, kind);
+                }
+            }
+        }
+        NodeGraph { nodes, edges }
+    }
+
+    /// Returns the names of all nodes that can be run after starting at `from`, including `from` itself, in alphabetical order.
+    /// Follows `<<jump>>`s, `<<detour>>`s and the nodes of node groups. Returns an empty list if `from` is not part of the program.
+    ///
+    /// Like [`Program::to_node_graph`], this does not know where jumps to a node whose name is only known at runtime lead,
+    /// and programs loaded into a dialogue have their instructions moved out, so call this before loading them.
+    pub fn reachable_nodes(&self, from: &str) -> Vec<String> {
+        reachable_nodes(from, |node_name| {
+            let node = self.nodes.get(node_name)?;
+            Some(node.instructions.iter().filter_map(|instruction| {
+                match instruction.instruction_type.as_ref()? {
+                    InstructionType::RunNode(RunNodeInstruction { node_name })
+                    | InstructionType::DetourToNode(DetourToNodeInstruction { node_name })
+                    | InstructionType::AddSaliencyCandidateFromNode(
+                        AddSaliencyCandidateFromNodeInstruction { node_name, .. },
+                    ) => Some(node_name.as_str()),
+                    _ => None,
+                }
+            }))
+        })
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect()
+    }
+}
+
+/// Walks the nodes starting at `from`, where `successors` returns the nodes a node leads to, or `None` if it doesn't exist.
+/// Returns the visited nodes that exist in alphabetical order.
+#[doc(hidden)]
+pub fn reachable_nodes<'a, I>(from: &'a str, successors: impl Fn(&str) -> Option<I>) -> Vec<&'a str>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut reached = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![from];
+    while let Some(node_name) = pending.pop() {
+        if !visited.insert(node_name) {
+            continue;
+        }
+        if let Some(next) = successors(node_name) {
+            reached.push(node_name);
+            pending.extend(next);
+        }
+    }
+    reached.sort_unstable();
+    reached
+}
+
+fn push_edge(edges: &mut Vec<NodeGraphEdge>, from: &str, to: &str, kind: NodeGraphEdgeKind) {
+    let edge = NodeGraphEdge {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        kind,
+    };
+    if !edges.contains(&edge) {
+        edges.push(edge);
+    }
+}
+
+fn node_transition(instruction: &Instruction) -> Option<(&str, NodeGraphEdgeKind)> {
+    match instruction.instruction_type.as_ref()? {
+        InstructionType::RunNode(RunNodeInstruction { node_name }) => {
+            Some((node_name, NodeGraphEdgeKind::Jump))
+        }
+        InstructionType::DetourToNode(DetourToNodeInstruction { node_name }) => {
+            Some((node_name, NodeGraphEdgeKind::Detour))
+        }
+        _ => None,
+    }
+}
+
+/// Follows the instructions run after selecting an option until they leave the node, returning the index of the instruction doing so.
+/// Gives up at anything that branches or waits for the player to choose again.
+fn follow_option(
+    instructions: &[Instruction],
+    destination: i32,
+) -> Option<(usize, &str, NodeGraphEdgeKind)> {
+    let mut index = usize::try_from(destination).ok()?;
+    let mut visited = HashSet::new();
+    while visited.insert(index) {
+        let instruction = instructions.get(index)?;
+        if let Some((to, kind)) = node_transition(instruction) {
+            return Some((index, to, kind));
+        }
+        match instruction.instruction_type.as_ref()? {
+            InstructionType::JumpTo(JumpToInstruction { destination }) => {
+                index = usize::try_from(*destination).ok()?;
+            }
+            InstructionType::RunLine(_)
+            | InstructionType::RunCommand(_)
+            | InstructionType::PushString(_)
+            | InstructionType::PushFloat(_)
+            | InstructionType::PushBool(_)
+            | InstructionType::Pop(_)
+            | InstructionType::CallFunc(_)
+            | InstructionType::PushVariable(_)
+            | InstructionType::StoreVariable(_) => index += 1,
+            _ => return None,
+        }
+    }
+    None
+}
+
+impl NodeGraph {
+    /// Iterates over the nodes that no edge leads to, except for the given entry points, in alphabetical order.
+    /// Such nodes can only be run if the game starts them directly or jumps to them with a name only known at runtime.
+    pub fn orphaned_nodes<'a>(
+        &'a self,
+        entry_points: impl IntoIterator<Item = &'a str>,
+    ) -> impl Iterator<Item = &'a str> {
+        let reached: HashSet<&str> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.from != edge.to)
+            .map(|edge| edge.to.as_str())
+            .chain(entry_points)
+            .collect();
+        self.nodes
+            .iter()
+            .map(String::as_str)
+            .filter(move |node| !reached.contains(node))
+    }
+
+    /// Formats the graph in the DOT language of [Graphviz](https://graphviz.org).
+    /// Detours are dashed, and options are labeled with their line ID.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for node in &self.nodes {
+            let _ = writeln!(dot, "    {};", dot_id(node));
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                NodeGraphEdgeKind::Jump => String::new(),
+                NodeGraphEdgeKind::Detour => " [style=dashed]".to_owned(),
+                NodeGraphEdgeKind::Option { line_id } => format!(" [label=\"{line_id}\"]"),
+            };
+            let _ = writeln!(
+                dot,
+                "    {} -> {}{attributes};",
+                dot_id(&edge.from),
+                dot_id(&edge.to)
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instruction::*;
+
+    fn node(name: &str, instructions: impl IntoIterator<Item = InstructionType>) -> (String, Node) {
+        let node = Node {
+            name: name.to_owned(),
+            instructions: instructions
+                .into_iter()
+                .map(|instruction_type| Instruction {
+                    instruction_type: Some(instruction_type),
+                })
+                .collect(),
+            headers: vec![],
+        };
+        (name.to_owned(), node)
+    }
+
+    fn run_node(node_name: &str) -> InstructionType {
+        InstructionType::RunNode(RunNodeInstruction {
+            node_name: node_name.to_owned(),
+        })
+    }
+
+    #[test]
+    fn extracts_jumps_detours_and_options() {
+        let option = |tag_id, destination| {
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id,
+                destination,
+                substitution_count: 0,
+                has_condition: false,
+            })
+        };
+        let program = Program {
+            nodes: [
+                node(
+                    "Start",
+                    [
+                        InstructionType::DetourToNode(DetourToNodeInstruction {
+                            node_name: "Intro".to_owned(),
+                        }),
+                        option(1, 4),
+                        option(2, 6),
+                        InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                        InstructionType::RunLine(RunLineInstruction {
+                            line_id: 3,
+                            substitution_count: 0,
+                        }),
+                        run_node("Shop"),
+                        InstructionType::JumpTo(JumpToInstruction { destination: 8 }),
+                        InstructionType::Stop(StopInstruction {}),
+                        run_node("End"),
+                    ],
+                ),
+                node("Intro", [InstructionType::Return(ReturnInstruction {})]),
+                node("Shop", [run_node("End")]),
+                node("End", [InstructionType::Stop(StopInstruction {})]),
+                node("Secret", [run_node("Secret")]),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let graph = program.to_node_graph();
+        assert_eq!(vec!["End", "Intro", "Secret", "Shop", "Start"], graph.nodes);
+        let edge = |from: &str, to: &str, kind| NodeGraphEdge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            kind,
+        };
+        assert_eq!(
+            vec![
+                edge("Secret", "Secret", NodeGraphEdgeKind::Jump),
+                edge("Shop", "End", NodeGraphEdgeKind::Jump),
+                edge("Start", "Shop", NodeGraphEdgeKind::Option { line_id: 1 }),
+                edge("Start", "End", NodeGraphEdgeKind::Option { line_id: 2 }),
+                edge("Start", "Intro", NodeGraphEdgeKind::Detour),
+            ],
+            graph.edges
+        );
+        assert_eq!(
+            vec!["Secret"],
+            graph.orphaned_nodes(["Start"]).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["End", "Intro", "Shop", "Start"],
+            program.reachable_nodes("Start")
+        );
+        assert_eq!(vec!["Secret"], program.reachable_nodes("Secret"));
+        assert!(program.reachable_nodes("Missing").is_empty());
+        assert!(graph
+            .to_dot()
+            .contains("    \"Start\" -> \"Intro\" [style=dashed];\n"));
+    }
+}
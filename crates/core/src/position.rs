@@ -1,3 +1,6 @@
+#[cfg(feature = "serde")]
+use crate::prelude::*;
+
 /// Represents a position in a multi-line string.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
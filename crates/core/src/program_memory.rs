@@ -0,0 +1,158 @@
+//! Not part of the original implementation.
+//!
+//! Programs decoded from protobuf keep the spare capacity their vectors grew while decoding,
+//! which adds up to a noticeable amount of memory for large games on embedded targets.
+
+use crate::prelude::*;
+use core::mem::size_of;
+use instruction::InstructionType;
+
+impl Program {
+    /// Releases the spare capacity of every string and vector in the program.
+    ///
+    /// Cheap to call on a program that was already shrunk. The runtime's `Dialogue::add_program` calls this on every program it loads,
+    /// after moving the instructions out and deduplicating their strings, e.g. the name of a variable used by many instructions,
+    /// into one shared allocation each. See the runtime's `ProgramHandle`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_core::prelude::*;
+    /// let mut node = Node::default();
+    /// node.instructions.reserve(64);
+    /// let mut program = Program::default();
+    /// program.nodes.insert("Start".to_owned(), node);
+    ///
+    /// let before = program.heap_size();
+    /// program.shrink_to_fit();
+    /// assert!(program.heap_size() < before);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.name.shrink_to_fit();
+        for node in self.nodes.values_mut() {
+            node.name.shrink_to_fit();
+            node.instructions.shrink_to_fit();
+            for instruction in &mut node.instructions {
+                if let Some(string) = instruction
+                    .instruction_type
+                    .as_mut()
+                    .and_then(instruction_string_mut)
+                {
+                    string.shrink_to_fit();
+                }
+            }
+            node.headers.shrink_to_fit();
+            for header in &mut node.headers {
+                header.key.shrink_to_fit();
+                header.value.shrink_to_fit();
+            }
+        }
+        for value in self.initial_values.values_mut() {
+            if let Some(OperandValue::StringValue(string)) = value.value.as_mut() {
+                string.shrink_to_fit();
+            }
+        }
+    }
+
+    /// The approximate number of bytes allocated on the heap by this program, including spare capacity.
+    /// Map keys are counted with their node and value, but the bookkeeping of the maps themselves is not.
+    pub fn heap_size(&self) -> usize {
+        let nodes: usize = self
+            .nodes
+            .iter()
+            .map(|(key, node)| key.capacity() + size_of::<Node>() + node_heap_size(node))
+            .sum();
+        let initial_values: usize = self
+            .initial_values
+            .iter()
+            .map(|(key, value)| {
+                let string = match value.value.as_ref() {
+                    Some(OperandValue::StringValue(string)) => string.capacity(),
+                    _ => 0,
+                };
+                key.capacity() + size_of::<Operand>() + string
+            })
+            .sum();
+        self.name.capacity() + nodes + initial_values
+    }
+}
+
+fn node_heap_size(node: &Node) -> usize {
+    let instructions: usize = node
+        .instructions
+        .iter()
+        .filter_map(|instruction| instruction.instruction_type.as_ref())
+        .filter_map(instruction_string)
+        .map(String::capacity)
+        .sum();
+    let headers: usize = node
+        .headers
+        .iter()
+        .map(|header| header.key.capacity() + header.value.capacity())
+        .sum();
+    node.name.capacity()
+        + node.instructions.capacity() * size_of::<Instruction>()
+        + instructions
+        + node.headers.capacity() * size_of::<Header>()
+        + headers
+}
+
+/// The string operand of an instruction, if it has one.
+fn instruction_string(instruction_type: &InstructionType) -> Option<&String> {
+    use InstructionType::*;
+    match instruction_type {
+        PushString(instruction) => Some(&instruction.value),
+        RunCommand(instruction) => Some(&instruction.command_text),
+        CallFunc(instruction) => Some(&instruction.function_name),
+        PushVariable(instruction) => Some(&instruction.variable_name),
+        StoreVariable(instruction) => Some(&instruction.variable_name),
+        RunNode(instruction) => Some(&instruction.node_name),
+        DetourToNode(instruction) => Some(&instruction.node_name),
+        AddSaliencyCandidate(instruction) => Some(&instruction.content_id),
+        AddSaliencyCandidateFromNode(instruction) => Some(&instruction.node_name),
+        JumpTo(_)
+        | PeekAndJump(_)
+        | RunLine(_)
+        | AddOption(_)
+        | ShowOptions(_)
+        | PushFloat(_)
+        | PushBool(_)
+        | JumpIfFalse(_)
+        | Pop(_)
+        | Stop(_)
+        | PeekAndRunNode(_)
+        | PeekAndDetourToNode(_)
+        | Return(_)
+        | SelectSaliencyCandidate(_) => None,
+    }
+}
+
+/// See [`instruction_string`].
+fn instruction_string_mut(instruction_type: &mut InstructionType) -> Option<&mut String> {
+    use InstructionType::*;
+    match instruction_type {
+        PushString(instruction) => Some(&mut instruction.value),
+        RunCommand(instruction) => Some(&mut instruction.command_text),
+        CallFunc(instruction) => Some(&mut instruction.function_name),
+        PushVariable(instruction) => Some(&mut instruction.variable_name),
+        StoreVariable(instruction) => Some(&mut instruction.variable_name),
+        RunNode(instruction) => Some(&mut instruction.node_name),
+        DetourToNode(instruction) => Some(&mut instruction.node_name),
+        AddSaliencyCandidate(instruction) => Some(&mut instruction.content_id),
+        AddSaliencyCandidateFromNode(instruction) => Some(&mut instruction.node_name),
+        JumpTo(_)
+        | PeekAndJump(_)
+        | RunLine(_)
+        | AddOption(_)
+        | ShowOptions(_)
+        | PushFloat(_)
+        | PushBool(_)
+        | JumpIfFalse(_)
+        | Pop(_)
+        | Stop(_)
+        | PeekAndRunNode(_)
+        | PeekAndDetourToNode(_)
+        | Return(_)
+        | SelectSaliencyCandidate(_) => None,
+    }
+}
@@ -0,0 +1,121 @@
+//! Not part of the original implementation.
+//!
+//! Compact identifiers for content, e.g. for analytics pipelines that should not ship raw line IDs or node names.
+
+use crate::prelude::*;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes the given bytes with 64-bit [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/index.html#FNV-1a).
+///
+/// Unlike [`core::hash::Hash`], the result is guaranteed to be the same on every platform and in every release of this crate,
+/// so it can be stored or sent to a server. It is not cryptographically secure.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// const SHOP_INTRO: u64 = stable_hash(b"ShopIntro");
+/// assert_eq!(0xaf63_dc4c_8601_ec8c, stable_hash(b"a"));
+/// assert_eq!(SHOP_INTRO, "ShopIntro".stable_hash());
+/// ```
+pub const fn stable_hash(bytes: &[u8]) -> u64 {
+    StableHasher::new().write(bytes).finish()
+}
+
+/// Incrementally computes a [`stable_hash`] over several values.
+///
+/// Feeding the bytes of two values one after the other is the same as hashing their concatenation,
+/// so separate variable-length values with [`StableHasher::write_separator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StableHasher {
+    /// Creates a hasher in its initial state.
+    pub const fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    /// Feeds the given bytes into the hash.
+    #[must_use]
+    pub const fn write(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() {
+            self.0 = (self.0 ^ bytes[i] as u64).wrapping_mul(FNV_PRIME);
+            i += 1;
+        }
+        self
+    }
+
+    /// Feeds the little-endian bytes of the given number into the hash.
+    #[must_use]
+    pub const fn write_u32(self, value: u32) -> Self {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// Feeds the byte `0xff` into the hash, which never occurs in UTF-8 and so cleanly separates two strings.
+    #[must_use]
+    pub const fn write_separator(self) -> Self {
+        self.write(&[0xff])
+    }
+
+    /// The hash of all bytes fed so far.
+    pub const fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+/// Types that have a [`stable_hash`], e.g. to identify them in analytics.
+pub trait StableHash {
+    /// The stable 64-bit hash of this value.
+    fn stable_hash(&self) -> u64;
+}
+
+/// The [`stable_hash`] of the UTF-8 bytes.
+impl StableHash for str {
+    fn stable_hash(&self) -> u64 {
+        stable_hash(self.as_bytes())
+    }
+}
+
+/// The [`stable_hash`] of the UTF-8 bytes.
+impl StableHash for String {
+    fn stable_hash(&self) -> u64 {
+        self.as_str().stable_hash()
+    }
+}
+
+/// The [`stable_hash`] of the whole ID including the `line:` prefix.
+/// This is the same as the hash stored by a [`CompactLineId`] that is not [exact](CompactLineId::is_exact).
+impl StableHash for LineId {
+    fn stable_hash(&self) -> u64 {
+        self.0.stable_hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reference_fnv1a() {
+        assert_eq!(0xcbf2_9ce4_8422_2325, stable_hash(b""));
+        assert_eq!(0x8594_4171_f739_67e8, stable_hash(b"foobar"));
+        assert_eq!(
+            stable_hash(b"foobar"),
+            StableHasher::new().write(b"foo").write(b"bar").finish()
+        );
+        let line_id = LineId::from("line:nooooo");
+        assert_eq!(
+            CompactLineId::new(&line_id.0).to_string(),
+            format!("line:~{:016x}", line_id.stable_hash())
+        );
+    }
+}
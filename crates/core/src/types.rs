@@ -2,6 +2,7 @@
 //! ## Implementation Notes
 //! - `IBridgeableType` is not implemented because it is not actually used anywhere.
 
+pub(crate) use number::NumberValue;
 pub use {function::*, r#type::*, type_util::*};
 
 mod any;
@@ -2,9 +2,10 @@
 
 use crate::prelude::*;
 use crate::types::TypeProperties;
+use core::cmp::Ordering;
 use core::ops::*;
 
-/// A type that bridges to [`f32`]
+/// A type that bridges to [`f32`] and [`i64`]
 pub(crate) fn number_type_properties() -> TypeProperties {
     TypeProperties::from_name("Number").with_methods(yarn_library! {
         Operator::EqualTo => <RustType as PartialEq>::eq,
@@ -22,4 +23,140 @@ pub(crate) fn number_type_properties() -> TypeProperties {
     })
 }
 
-type RustType = f32;
+type RustType = NumberValue;
+
+/// The operand of the number operators. Operations on two [`YarnValue::Integer`]s stay exact and return an integer as long as
+/// the result is a whole number that fits into an `i64`. Everything else is calculated with `f32`s, like in the original implementation.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum NumberValue {
+    Float(f32),
+    Integer(i64),
+}
+
+impl NumberValue {
+    fn to_f32(self) -> f32 {
+        match self {
+            Self::Float(value) => value,
+            Self::Integer(value) => value as f32,
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        match self {
+            Self::Float(value) => value as f64,
+            Self::Integer(value) => value as f64,
+        }
+    }
+
+    fn integer_op(
+        self,
+        other: Self,
+        integer_op: impl FnOnce(i64, i64) -> Option<i64>,
+        float_op: impl FnOnce(f32, f32) -> f32,
+    ) -> Self {
+        if let (Self::Integer(a), Self::Integer(b)) = (self, other) {
+            if let Some(result) = integer_op(a, b) {
+                return Self::Integer(result);
+            }
+        }
+        Self::Float(float_op(self.to_f32(), other.to_f32()))
+    }
+}
+
+impl PartialEq for NumberValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            _ => self.to_f64() == other.to_f64(),
+        }
+    }
+}
+
+impl PartialOrd for NumberValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a.partial_cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl Add for NumberValue {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.integer_op(rhs, i64::checked_add, f32::add)
+    }
+}
+
+impl Sub for NumberValue {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.integer_op(rhs, i64::checked_sub, f32::sub)
+    }
+}
+
+impl Mul for NumberValue {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.integer_op(rhs, i64::checked_mul, f32::mul)
+    }
+}
+
+impl Div for NumberValue {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        // `5 / 2` is `2.5` in Yarn, so only exact divisions stay integers
+        self.integer_op(
+            rhs,
+            |a, b| (a.checked_rem(b) == Some(0)).then(|| a.checked_div(b))?,
+            f32::div,
+        )
+    }
+}
+
+impl Rem for NumberValue {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        self.integer_op(rhs, i64::checked_rem, f32::rem)
+    }
+}
+
+impl Neg for NumberValue {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Self::Integer(value) => value
+                .checked_neg()
+                .map_or(Self::Float(-(value as f32)), Self::Integer),
+            Self::Float(value) => Self::Float(-value),
+        }
+    }
+}
+
+impl TryFrom<YarnValue> for NumberValue {
+    type Error = YarnValueCastError;
+
+    fn try_from(value: YarnValue) -> Result<Self, Self::Error> {
+        match value {
+            YarnValue::Integer(value) => Ok(Self::Integer(value)),
+            value => f32::try_from(value).map(Self::Float),
+        }
+    }
+}
+
+impl IntoYarnValueFromNonYarnValue for NumberValue {
+    fn into_yarn_value(self) -> YarnValue {
+        match self {
+            Self::Float(value) => YarnValue::Number(value),
+            Self::Integer(value) => YarnValue::Integer(value),
+        }
+    }
+}
@@ -183,7 +183,12 @@ macro_rules! impl_typed_value {
 
 impl_typed_value! {
     [f32, f64, i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, usize, isize] => Type::Number,
-    [String, str] => Type::String,
+    [
+        Strict<f32>, Strict<f64>, Strict<i8>, Strict<i16>, Strict<i32>, Strict<i64>, Strict<i128>,
+        Strict<u8>, Strict<u16>, Strict<u32>, Strict<u64>, Strict<u128>, Strict<usize>, Strict<isize>,
+        NumberValue,
+    ] => Type::Number,
+    [String, str, Strict<String>] => Type::String,
     [bool] => Type::Boolean,
 }
 
@@ -200,11 +205,40 @@ impl TryFrom<TypeId> for Type {
     type Error = InvalidDowncastError;
 
     fn try_from(type_id: TypeId) -> Result<Self, Self::Error> {
-        let string_types = type_ids![String, &str];
+        let string_types = type_ids![String, &str, Strict<String>];
         let bool_types = type_ids![bool];
         let value_types = type_ids![YarnValue];
-        let number_types =
-            type_ids![f32, f64, i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, usize, isize,];
+        let number_types = type_ids![
+            f32,
+            f64,
+            i8,
+            i16,
+            i32,
+            i64,
+            i128,
+            u8,
+            u16,
+            u32,
+            u64,
+            u128,
+            usize,
+            isize,
+            Strict<f32>,
+            Strict<f64>,
+            Strict<i8>,
+            Strict<i16>,
+            Strict<i32>,
+            Strict<i64>,
+            Strict<i128>,
+            Strict<u8>,
+            Strict<u16>,
+            Strict<u32>,
+            Strict<u64>,
+            Strict<u128>,
+            Strict<usize>,
+            Strict<isize>,
+            NumberValue,
+        ];
 
         [
             (string_types, Type::String),
@@ -221,7 +255,7 @@ impl TryFrom<TypeId> for Type {
 impl TypedValue for YarnValue {
     fn r#type(&self) -> Type {
         match self {
-            YarnValue::Number(_) => Type::Number,
+            YarnValue::Number(_) | YarnValue::Integer(_) => Type::Number,
             YarnValue::String(_) => Type::String,
             YarnValue::Boolean(_) => Type::Boolean,
         }
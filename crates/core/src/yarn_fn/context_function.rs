@@ -0,0 +1,230 @@
+//! Not part of the original implementation.
+//!
+//! Functions that receive a mutable context supplied by the game whenever the dialogue runs, so they can read live game data
+//! without capturing it behind locks.
+
+use super::optionality::AllowedOptionalityChain;
+use crate::prelude::*;
+use core::any::{Any, TypeId};
+use core::fmt::{Debug, Display, Formatter};
+use core::marker::PhantomData;
+use variadics_please::all_tuples;
+
+/// A function that can be registered with [`Library::add_function_with_context`] and receives a `&mut Ctx` as its first parameter,
+/// followed by zero or more [`YarnFnParam`]s. The same rules as for [`YarnFn`] apply to the parameters and the return type.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// struct Inventory {
+///     apples: usize,
+/// }
+///
+/// fn take_apples(inventory: &mut Inventory, count: usize) -> bool {
+///     if inventory.apples < count {
+///         return false;
+///     }
+///     inventory.apples -= count;
+///     true
+/// }
+///
+/// let mut library = Library::new();
+/// library.add_function_with_context("take_apples", take_apples);
+///
+/// let mut inventory = Inventory { apples: 3 };
+/// let take_apples = library.get("take_apples").unwrap();
+/// let taken = take_apples.try_call_with_context(vec![2.into()], &mut inventory);
+/// assert_eq!(YarnValue::Boolean(true), taken.unwrap());
+/// assert_eq!(1, inventory.apples);
+/// ```
+pub trait YarnContextFn<Ctx, Marker>: Clone + Send + Sync {
+    /// The type of the value returned by this function. See [`YarnFn`] for more information about what is allowed.
+    type Out: IntoYarnValueFromNonYarnValue + 'static;
+    #[doc(hidden)]
+    fn call(&self, context: &mut Ctx, input: Vec<YarnValue>) -> Self::Out;
+    /// The [`TypeId`]s of the parameters of this function, not including the context.
+    fn parameter_types(&self) -> Vec<TypeId>;
+    /// The [`TypeId`] of the return type of this function. For a [`Result`] or [`Option`], this is the type of the contained value.
+    fn return_type(&self) -> TypeId {
+        Self::Out::yarn_value_type_id()
+    }
+}
+
+pub(crate) struct YarnContextFnWrapper<Ctx, Marker, F>
+where
+    F: YarnContextFn<Ctx, Marker>,
+{
+    function: F,
+
+    // NOTE: PhantomData<fn()-> T> gives this safe Send/Sync impls
+    _marker: PhantomData<fn() -> (Ctx, Marker)>,
+}
+
+impl<Ctx, Marker, F> Clone for YarnContextFnWrapper<Ctx, Marker, F>
+where
+    F: YarnContextFn<Ctx, Marker>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            function: self.function.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Ctx, Marker, F> From<F> for YarnContextFnWrapper<Ctx, Marker, F>
+where
+    F: YarnContextFn<Ctx, Marker>,
+{
+    fn from(function: F) -> Self {
+        Self {
+            function,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Ctx, Marker, F> Debug for YarnContextFnWrapper<Ctx, Marker, F>
+where
+    F: YarnContextFn<Ctx, Marker>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let signature = core::any::type_name::<Marker>();
+        let function_path = core::any::type_name::<F>();
+        let debug_message = format!("{signature} {{{function_path}}}");
+        f.debug_struct(&debug_message).finish()
+    }
+}
+
+impl<Ctx, Marker, F> Display for YarnContextFnWrapper<Ctx, Marker, F>
+where
+    F: YarnContextFn<Ctx, Marker>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let signature = core::any::type_name::<Marker>();
+        f.write_str(signature)
+    }
+}
+
+impl<Ctx, Marker, F> UntypedYarnFn for YarnContextFnWrapper<Ctx, Marker, F>
+where
+    Ctx: 'static,
+    Marker: 'static,
+    F: YarnContextFn<Ctx, Marker> + 'static,
+{
+    fn call(&self, input: Vec<YarnValue>) -> YarnValue {
+        self.try_call(input)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_call(&self, _input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError> {
+        Err(YarnFnError::MissingContext {
+            expected: core::any::type_name::<Ctx>(),
+        })
+    }
+
+    fn try_call_with_context(
+        &self,
+        input: Vec<YarnValue>,
+        context: &mut dyn Any,
+    ) -> Result<YarnValue, YarnFnError> {
+        let context = context
+            .downcast_mut::<Ctx>()
+            .ok_or(YarnFnError::MissingContext {
+                expected: core::any::type_name::<Ctx>(),
+            })?;
+        self.function.call(context, input).try_into_yarn_value()
+    }
+
+    fn clone_box(&self) -> Box<dyn UntypedYarnFn> {
+        Box::new(self.clone())
+    }
+
+    fn parameter_types(&self) -> Vec<TypeId> {
+        self.function.parameter_types()
+    }
+
+    fn return_type(&self) -> TypeId {
+        self.function.return_type()
+    }
+}
+
+macro_rules! impl_yarn_context_fn_tuple {
+    ($($param: ident),*) => {
+        #[allow(non_snake_case)]
+        impl<F, Ctx, O, $($param,)*> YarnContextFn<Ctx, fn(&mut Ctx, $($param,)*) -> O> for F
+            where
+            for<'a> F:
+                Send + Sync + Clone +
+                Fn(&mut Ctx, $($param,)*) -> O +
+                Fn(&mut Ctx, $(<$param as YarnFnParam>::Item<'a>,)*) -> O,
+            Ctx: 'static,
+            O: IntoYarnValueFromNonYarnValue + 'static,
+            $($param: YarnFnParam + 'static,)*
+            ($(<$param as YarnFnParam>::Optionality,)*): AllowedOptionalityChain,
+            {
+                type Out = O;
+                #[allow(non_snake_case)]
+                fn call(
+                    &self, context: &mut Ctx, input: Vec<YarnValue>,
+                ) -> Self::Out {
+                    let input_len = input.len();
+                    let mut params: Vec<_> = input.into_iter().map(YarnValueWrapper::from).collect();
+
+                    #[allow(unused_variables, unused_mut)] // for n = 0 tuples
+                    let mut iter = params.iter_mut().peekable();
+
+                    // $param is the type implementing YarnFnParam
+                    let input = (
+                        $($param::retrieve(&mut iter),)*
+                    );
+                    assert!(iter.next().is_none(), "YarnFn expected {} arguments but received {}", <[&str]>::len(&[$(stringify!($param)),*]), input_len);
+
+                    let ($($param,)*) = input;
+                    self(context, $($param,)*)
+                }
+
+                fn parameter_types(&self) -> Vec<TypeId> {
+                    vec![$(TypeId::of::<$param>()),*]
+                }
+            }
+    };
+}
+
+all_tuples!(impl_yarn_context_fn_tuple, 0, 15, P);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Clock {
+        ticks: u32,
+    }
+
+    #[test]
+    fn passes_the_context_to_the_function() {
+        let wrapped = YarnContextFnWrapper::from(|clock: &mut Clock, step: u32| {
+            clock.ticks += step;
+            clock.ticks
+        });
+        let mut clock = Clock { ticks: 1 };
+        assert_eq!(
+            YarnValue::Integer(3),
+            wrapped
+                .try_call_with_context(vec![2.into()], &mut clock)
+                .unwrap()
+        );
+        assert_eq!(3, clock.ticks);
+        assert_eq!(vec![TypeId::of::<u32>()], wrapped.parameter_types());
+
+        assert!(matches!(
+            wrapped.try_call(vec![2.into()]),
+            Err(YarnFnError::MissingContext { .. })
+        ));
+        assert!(matches!(
+            wrapped.try_call_with_context(vec![2.into()], &mut "not a clock"),
+            Err(YarnFnError::MissingContext { .. })
+        ));
+    }
+}
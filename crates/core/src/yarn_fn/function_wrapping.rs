@@ -1,6 +1,6 @@
 use super::optionality::AllowedOptionalityChain;
 use crate::prelude::*;
-use core::any::TypeId;
+use core::any::{Any, TypeId};
 use core::fmt::{Debug, Display, Formatter};
 use core::marker::PhantomData;
 use variadics_please::all_tuples;
@@ -19,6 +19,11 @@ use variadics_please::all_tuples;
 ///   - [`bool`]
 ///   - A numeric type, i.e. one of [`f32`], [`f64`], [`i8`], [`i16`], [`i32`], [`i64`], [`i128`], [`u8`], [`u16`], [`u32`], [`u64`], [`u128`], [`usize`], [`isize`]
 ///   - [`String`]
+///   - A [`Result`] of one of the above types and an error implementing [`Display`], which fails the call with the error's message on `Err`.
+///   - An [`Option`] of one of the above types, which returns the default declared with [`Library::add_function_with_default`] on `None`,
+///     or fails the call if none was declared.
+///
+/// A failed call is surfaced by the runtime as an error of the dialogue instead of a panic.
 ///
 /// If the `bevy` feature is active then it is also possible to register a Bevy `System` and call it from Yarn. The `System` will receive the parameters passed to the yarn
 /// as it's input. The `System`'s input must adhere to the same rules as given above for regular function parameters with the exception that System functions cannot accept
@@ -47,17 +52,66 @@ pub trait YarnFn<Marker>: Clone + Send + Sync {
     fn call(&self, input: Vec<YarnValue>) -> Self::Out;
     /// The [`TypeId`]s of the parameters of this function.
     fn parameter_types(&self) -> Vec<TypeId>;
-    /// The [`TypeId`] of the return type of this function.
+    /// The [`TypeId`] of the return type of this function. For a [`Result`] or [`Option`], this is the type of the contained value.
     fn return_type(&self) -> TypeId {
-        TypeId::of::<Self::Out>()
+        Self::Out::yarn_value_type_id()
+    }
+}
+
+/// The reason a [`YarnFn`] returning a [`Result`] or [`Option`] did not produce a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YarnFnError {
+    /// The function returned an `Err` with the contained message.
+    Failed(String),
+    /// The function returned `None` and has no declared default.
+    NoValue,
+    /// The function was registered with [`Library::add_function_with_context`], but called without a context of the expected type.
+    MissingContext {
+        /// The name of the expected context type.
+        expected: &'static str,
+    },
+}
+
+impl core::error::Error for YarnFnError {}
+
+impl Display for YarnFnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            YarnFnError::Failed(message) => f.write_str(message),
+            YarnFnError::NoValue => f.write_str("The function returned no value"),
+            YarnFnError::MissingContext { expected } => {
+                write!(
+                    f,
+                    "The function needs a context of type {expected}, but was called without one"
+                )
+            }
+        }
     }
 }
 
 /// A [`YarnFn`] with the `Marker` type parameter erased.
 /// See its documentation for more information about what kind of functions are allowed.
 pub trait UntypedYarnFn: Debug + Display + Send + Sync {
+    /// Calls the function.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the function fails, see [`UntypedYarnFn::try_call`].
     #[doc(hidden)]
     fn call(&self, input: Vec<YarnValue>) -> YarnValue;
+    /// Calls the function, returning an error if it returned an `Err` or a `None` without a declared default.
+    #[doc(hidden)]
+    fn try_call(&self, input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError>;
+    /// Like [`UntypedYarnFn::try_call`], but passes the given context to functions registered with [`Library::add_function_with_context`].
+    /// Other functions ignore it.
+    #[doc(hidden)]
+    fn try_call_with_context(
+        &self,
+        input: Vec<YarnValue>,
+        _context: &mut dyn Any,
+    ) -> Result<YarnValue, YarnFnError> {
+        self.try_call(input)
+    }
     #[doc(hidden)]
     fn clone_box(&self) -> Box<dyn UntypedYarnFn>;
     /// The [`TypeId`]s of the parameters of this function.
@@ -79,7 +133,18 @@ where
     F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
 {
     fn call(&self, input: Vec<YarnValue>) -> YarnValue {
-        self.function.call(input).into_yarn_value()
+        self.try_call(input)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_call(&self, input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError> {
+        self.function
+            .call(input)
+            .try_into_yarn_value()
+            .or_else(|error| match (error, &self.default) {
+                (YarnFnError::NoValue, Some(default)) => Ok(default.clone()),
+                (error, _) => Err(error),
+            })
     }
 
     fn clone_box(&self) -> Box<dyn UntypedYarnFn> {
@@ -100,6 +165,8 @@ where
     F: YarnFn<Marker>,
 {
     function: F,
+    /// Returned instead of failing when the function returns `None`.
+    default: Option<YarnValue>,
 
     // NOTE: PhantomData<fn()-> T> gives this safe Send/Sync impls
     _marker: PhantomData<fn() -> Marker>,
@@ -112,6 +179,7 @@ where
     fn clone(&self) -> Self {
         Self {
             function: self.function.clone(),
+            default: self.default.clone(),
             _marker: PhantomData,
         }
     }
@@ -124,11 +192,22 @@ where
     fn from(function: F) -> Self {
         Self {
             function,
+            default: None,
             _marker: PhantomData,
         }
     }
 }
 
+impl<Marker, F> YarnFnWrapper<Marker, F>
+where
+    F: YarnFn<Marker>,
+{
+    pub(crate) fn with_default(mut self, default: YarnValue) -> Self {
+        self.default = Some(default);
+        self
+    }
+}
+
 impl<Marker, F> Debug for YarnFnWrapper<Marker, F>
 where
     F: YarnFn<Marker>,
@@ -374,14 +453,47 @@ mod tests {
         accept_yarn_fn(f);
     }
 
+    #[test]
+    fn accepts_fallible_return_types() {
+        fn f(count: usize) -> Result<usize, String> {
+            count
+                .checked_sub(1)
+                .ok_or_else(|| "Nothing left".to_owned())
+        }
+        fn g(_: &str) -> Option<bool> {
+            None
+        }
+        assert_eq!(TypeId::of::<usize>(), f.return_type());
+        assert_eq!(TypeId::of::<bool>(), g.return_type());
+
+        let wrapped = YarnFnWrapper::from(f);
+        assert_eq!(
+            YarnValue::Integer(1),
+            wrapped.try_call(vec![2.into()]).unwrap()
+        );
+        assert_eq!(
+            YarnFnError::Failed("Nothing left".to_owned()),
+            wrapped.try_call(vec![0.into()]).unwrap_err()
+        );
+        let wrapped = YarnFnWrapper::from(g);
+        assert_eq!(
+            YarnFnError::NoValue,
+            wrapped.try_call(vec!["".into()]).unwrap_err()
+        );
+        let wrapped = wrapped.with_default(true.into());
+        assert_eq!(
+            YarnValue::Boolean(true),
+            wrapped.try_call(vec!["".into()]).unwrap()
+        );
+    }
+
     fn accept_yarn_fn<Marker>(_: impl YarnFn<Marker>) {}
 
     fn apply_yarn_fn<T, Marker>(f: T, input: Vec<YarnValue>) -> T::Out
     where
         T: YarnFn<Marker>,
     {
-        let out = f.call(input);
-        out
+        f.call(input)
     }
 
     mod optionality {
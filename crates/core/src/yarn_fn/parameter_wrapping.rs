@@ -4,6 +4,7 @@
 
 use super::optionality::{AllowedOptionalityChain, Optional, Optionality, Required};
 use crate::prelude::*;
+use crate::types::NumberValue;
 use core::any::Any;
 use core::any::TypeId;
 use core::borrow::Borrow;
@@ -52,6 +53,7 @@ impl YarnValueWrapper {
 /// - Numeric type, i.e. one of [`f32`], [`f64`], [`i8`], [`i16`], [`i32`], [`i64`], [`i128`], [`u8`], [`u16`], [`u32`], [`u64`], [`u128`], [`usize`], [`isize`]
 /// - [`String`] (for a reference, [`&str`] may be used instead of `&String`)
 /// - [`YarnValue`], which means that a parameter may be any of the above types
+/// - [`Strict`] of a numeric type or [`String`], which panics instead of losing information when converting the passed value
 /// - Tuples of the above types.
 pub trait YarnFnParam {
     /// The item type returned when constructing this [`YarnFn`] param. The value of this associated type should be `Self`, instantiated with a new lifetime.
@@ -299,3 +301,11 @@ macro_rules! impl_yarn_fn_param_inner {
 impl_yarn_fn_param! {
     [str => String, YarnValue, bool, f32, f64, i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, usize, isize]: YarnFnParam
 }
+
+impl_yarn_fn_param! {
+    [
+        Strict<String>, Strict<f32>, Strict<f64>, Strict<i8>, Strict<i16>, Strict<i32>, Strict<i64>, Strict<i128>,
+        Strict<u8>, Strict<u16>, Strict<u32>, Strict<u64>, Strict<u128>, Strict<usize>, Strict<isize>,
+        NumberValue
+    ]: YarnFnParam
+}
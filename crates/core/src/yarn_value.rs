@@ -1,5 +1,6 @@
 //! Implements a subset of dotnet's [`Convert`](https://learn.microsoft.com/en-us/dotnet/api/system.convert?view=net-8.0) type.
 use crate::prelude::*;
+use core::any::TypeId;
 use core::error::Error;
 use core::fmt::{Display, Formatter};
 
@@ -9,15 +10,33 @@ use core::fmt::{Display, Formatter};
 /// The type implements meaningful conversions between types through [`TryFrom`] and [`From`].
 /// A failure to convert one variant to another will result in an [`YarnValueCastError`].
 ///
+/// ## Conversion rules
+///
+/// - Rust integers that fit into an `i64` become a [`YarnValue::Integer`], all other numbers a [`YarnValue::Number`].
+/// - Converting a number to a Rust number behaves like an `as` cast, i.e. fractions are truncated and values out of range saturate.
+/// - Converting a string to a Rust number parses it, first as the target type and then as an `f32`.
+/// - Converting a boolean to a Rust number yields `1` or `0`, and converting a number to a boolean yields `true` for everything but `0`.
+/// - Wrapping the target type in [`Strict`] turns every conversion that would lose information into a [`YarnValueCastError::LossyConversion`].
+///
+/// A [`YarnValue::Integer`] and a [`YarnValue::Number`] are equal if they represent exactly the same number.
+///
 /// ## Implementation Notes
 ///
 /// Corresponds to C#'s [`Convert`](https://docs.microsoft.com/en-us/dotnet/api/system.convert?view=net-5.0) class.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum YarnValue {
-    /// Any kind of Rust number, i.e. one of `f32`, `f64`, `i8`, `i16`, `i32`, `i64`, `i128`, `u8`, `u16`, `u32`, `u64`, `u128`, `usize`, `isize`.
-    /// They are internally stored as `f32` through simple type casts.
+    /// A floating point number, or a Rust integer that does not fit into an `i64`, stored as `f32` through a simple type cast.
     Number(f32),
+    /// A Rust integer, i.e. one of `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `isize`,
+    /// or one of `i128`, `u64`, `u128`, `usize` if it fits into an `i64`. Has the Yarn type [`Type::Number`].
+    ///
+    /// ## Implementation Notes
+    ///
+    /// Does not exist in the original implementation, which stores every number as a `float`.
+    /// Keeps large integers like IDs intact when they are passed to and returned from functions.
+    Integer(i64),
     /// An owned Rust string.
     String(String),
     /// A Rust boolean.
@@ -31,6 +50,64 @@ pub enum YarnValue {
 pub trait IntoYarnValueFromNonYarnValue {
     #[doc(hidden)]
     fn into_yarn_value(self) -> YarnValue;
+
+    #[doc(hidden)]
+    fn try_into_yarn_value(self) -> Result<YarnValue, YarnFnError>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_yarn_value())
+    }
+
+    /// The [`TypeId`] of the type whose [`Type`] the returned [`YarnValue`] has, i.e. `T` for a `Result<T, E>` or `Option<T>`.
+    #[doc(hidden)]
+    fn yarn_value_type_id() -> TypeId
+    where
+        Self: Sized + 'static,
+    {
+        TypeId::of::<Self>()
+    }
+}
+
+/// Returning `Err` makes the [`YarnFn`] fail with the error's message instead of producing a value.
+impl<T, E> IntoYarnValueFromNonYarnValue for Result<T, E>
+where
+    T: IntoYarnValueFromNonYarnValue + 'static,
+    E: Display,
+{
+    fn into_yarn_value(self) -> YarnValue {
+        self.try_into_yarn_value()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_into_yarn_value(self) -> Result<YarnValue, YarnFnError> {
+        self.map_err(|error| YarnFnError::Failed(error.to_string()))?
+            .try_into_yarn_value()
+    }
+
+    fn yarn_value_type_id() -> TypeId {
+        T::yarn_value_type_id()
+    }
+}
+
+/// Returning `None` makes the [`YarnFn`] return the default declared with [`Library::add_function_with_default`],
+/// or fail if none was declared.
+impl<T> IntoYarnValueFromNonYarnValue for Option<T>
+where
+    T: IntoYarnValueFromNonYarnValue + 'static,
+{
+    fn into_yarn_value(self) -> YarnValue {
+        self.try_into_yarn_value()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_into_yarn_value(self) -> Result<YarnValue, YarnFnError> {
+        self.ok_or(YarnFnError::NoValue)?.try_into_yarn_value()
+    }
+
+    fn yarn_value_type_id() -> TypeId {
+        T::yarn_value_type_id()
+    }
 }
 
 impl YarnValue {
@@ -39,11 +116,84 @@ impl YarnValue {
     pub fn eq(&self, other: &Self, epsilon: f32) -> bool {
         match (self, other) {
             (Self::Number(a), Self::Number(b)) => (a - b).abs() < epsilon,
+            (Self::Integer(a), Self::Number(b)) | (Self::Number(b), Self::Integer(a)) => {
+                (*a as f64 - *b as f64).abs() < epsilon as f64
+            }
             (a, b) => a == b,
         }
     }
+
+    /// Returns the value if this is a [`YarnValue::Number`] or [`YarnValue::Integer`], without converting other variants.
+    /// Integers are cast to `f32`, see [`YarnValue::as_integer`] for a lossless alternative.
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Self::Number(value) => Some(*value),
+            Self::Integer(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a [`YarnValue::Integer`] or a [`YarnValue::Number`] without a fractional part that fits into an `i64`.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(value) => Some(*value),
+            Self::Number(_) => Strict::<i64>::try_from(self).ok().map(|value| value.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a [`YarnValue::String`], without converting other variants.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a [`YarnValue::Boolean`], without converting other variants.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for YarnValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Integer(a), Self::Number(b)) | (Self::Number(b), Self::Integer(a)) => {
+                Strict::<i64>::try_from(YarnValue::Number(*b)).is_ok_and(|b| b.0 == *a)
+            }
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
+/// Wraps a Rust number or string that a [`YarnValue`] is converted to without losing information.
+///
+/// Where a plain conversion truncates fractions, saturates out-of-range values or rounds integers that an `f32` cannot represent,
+/// a conversion to [`Strict`] returns a [`YarnValueCastError::LossyConversion`] instead. Strings must parse as the target type.
+/// Can be used as a parameter of a [`YarnFn`], which panics when called with a value that cannot be converted losslessly.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// let id = YarnValue::from(9_007_199_254_740_993_u64);
+/// assert_eq!(9_007_199_254_740_993, u64::try_from(&id).unwrap());
+/// assert!(Strict::<u32>::try_from(&id).is_err());
+/// assert!(Strict::<f32>::try_from(&id).is_err());
+/// assert!(Strict::<i64>::try_from(YarnValue::Number(1.5)).is_err());
+/// assert_eq!(Strict(2_i64), Strict::try_from(YarnValue::Number(2.0)).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Strict<T>(pub T);
+
 impl<T> From<&T> for YarnValue
 where
     T: Copy,
@@ -54,6 +204,31 @@ where
     }
 }
 
+macro_rules! impl_strict {
+    ($target_type:ty) => {
+        impl TryFrom<YarnValue> for Strict<$target_type> {
+            type Error = YarnValueCastError;
+
+            fn try_from(value: YarnValue) -> Result<Self, Self::Error> {
+                Self::try_from(&value)
+            }
+        }
+
+        impl IntoYarnValueFromNonYarnValue for Strict<$target_type> {
+            fn into_yarn_value(self) -> YarnValue {
+                self.0.into()
+            }
+        }
+    };
+}
+
+fn lossy<T>(value: &YarnValue) -> YarnValueCastError {
+    YarnValueCastError::LossyConversion {
+        value: value.clone(),
+        target_type: core::any::type_name::<T>(),
+    }
+}
+
 macro_rules! impl_floating_point {
         ($($from_type:ty,)*) => {
         $(
@@ -77,12 +252,35 @@ macro_rules! impl_floating_point {
                 fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
                     match value {
                         YarnValue::Number(value) => Ok(*value as $from_type),
+                        YarnValue::Integer(value) => Ok(*value as $from_type),
                         YarnValue::String(value) => value.parse().map_err(Into::into),
                         YarnValue::Boolean(value) => Ok(if *value { 1.0 as $from_type } else { 0.0 }),
                     }
                 }
             }
 
+            impl_strict!($from_type);
+
+            impl TryFrom<&YarnValue> for Strict<$from_type> {
+                type Error = YarnValueCastError;
+
+                fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
+                    let converted = match value {
+                        YarnValue::Integer(integer) => {
+                            let converted = *integer as $from_type;
+                            // Compare in `i128` since casting back to `i64` saturates
+                            (converted as i128 == *integer as i128).then_some(converted)
+                        }
+                        YarnValue::Number(number) => {
+                            let converted = *number as $from_type;
+                            (converted as f64 == *number as f64 || number.is_nan()).then_some(converted)
+                        }
+                        YarnValue::String(string) => Some(string.parse()?),
+                        YarnValue::Boolean(_) => Some(<$from_type>::try_from(value)?),
+                    };
+                    converted.map(Strict).ok_or_else(|| lossy::<$from_type>(value))
+                }
+            }
 
             impl IntoYarnValueFromNonYarnValue for $from_type {
                 fn into_yarn_value(self) -> YarnValue {
@@ -100,7 +298,13 @@ macro_rules! impl_whole_number {
         $(
             impl From<$from_type> for YarnValue {
                 fn from(value: $from_type) -> Self {
-                    Self::Number(value as f32)
+                    // Only `i128`, `u64`, `u128` and `usize` can fail this
+                    #[allow(irrefutable_let_patterns, clippy::unnecessary_fallible_conversions)]
+                    if let Ok(value) = i64::try_from(value) {
+                        Self::Integer(value)
+                    } else {
+                        Self::Number(value as f32)
+                    }
                 }
             }
 
@@ -116,7 +320,34 @@ macro_rules! impl_whole_number {
                 type Error = YarnValueCastError;
 
                 fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
-                    f32::try_from(value).map(|value| value as $from_type)
+                    match value {
+                        YarnValue::Integer(value) => Ok(Self::try_from(*value)
+                            .unwrap_or(if *value < 0 { Self::MIN } else { Self::MAX })),
+                        YarnValue::String(string) => string
+                            .parse()
+                            .or_else(|_| f32::try_from(value).map(|value| value as $from_type)),
+                        _ => f32::try_from(value).map(|value| value as $from_type),
+                    }
+                }
+            }
+
+            impl_strict!($from_type);
+
+            impl TryFrom<&YarnValue> for Strict<$from_type> {
+                type Error = YarnValueCastError;
+
+                fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
+                    let converted = match value {
+                        YarnValue::Integer(integer) => <$from_type>::try_from(*integer).ok(),
+                        YarnValue::Number(number) => {
+                            let converted = *number as $from_type;
+                            // Every integer representable by an `f32` is exactly representable by an `f64`
+                            (converted as f64 == *number as f64).then_some(converted)
+                        }
+                        YarnValue::String(string) => Some(string.parse()?),
+                        YarnValue::Boolean(_) => Some(<$from_type>::try_from(value)?),
+                    };
+                    converted.map(Strict).ok_or_else(|| lossy::<$from_type>(value))
                 }
             }
 
@@ -135,6 +366,7 @@ impl From<YarnValue> for String {
     fn from(value: YarnValue) -> Self {
         match value {
             YarnValue::Number(value) => value.to_string(),
+            YarnValue::Integer(value) => value.to_string(),
             YarnValue::String(value) => value,
             YarnValue::Boolean(value) => value.to_string(),
         }
@@ -165,6 +397,19 @@ impl IntoYarnValueFromNonYarnValue for String {
     }
 }
 
+impl_strict!(String);
+
+impl TryFrom<&YarnValue> for Strict<String> {
+    type Error = YarnValueCastError;
+
+    fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
+        match value {
+            YarnValue::String(string) => Ok(Strict(string.clone())),
+            _ => Err(lossy::<String>(value)),
+        }
+    }
+}
+
 impl TryFrom<YarnValue> for bool {
     type Error = YarnValueCastError;
 
@@ -179,6 +424,7 @@ impl TryFrom<&YarnValue> for bool {
     fn try_from(value: &YarnValue) -> Result<Self, Self::Error> {
         match value {
             YarnValue::Number(value) => Ok(*value != 0.0),
+            YarnValue::Integer(value) => Ok(*value != 0),
             YarnValue::String(value) => value.parse().map_err(Into::into),
             YarnValue::Boolean(value) => Ok(*value),
         }
@@ -204,6 +450,11 @@ pub enum YarnValueCastError {
     ParseFloatError(core::num::ParseFloatError),
     ParseIntError(core::num::ParseIntError),
     ParseBoolError(core::str::ParseBoolError),
+    /// A conversion to [`Strict`] would have lost information.
+    LossyConversion {
+        value: YarnValue,
+        target_type: &'static str,
+    },
 }
 
 impl Error for YarnValueCastError {
@@ -212,6 +463,7 @@ impl Error for YarnValueCastError {
             YarnValueCastError::ParseFloatError(e) => Some(e),
             YarnValueCastError::ParseIntError(e) => Some(e),
             YarnValueCastError::ParseBoolError(e) => Some(e),
+            YarnValueCastError::LossyConversion { .. } => None,
         }
     }
 }
@@ -222,6 +474,12 @@ impl Display for YarnValueCastError {
             YarnValueCastError::ParseFloatError(e) => Display::fmt(e, f),
             YarnValueCastError::ParseIntError(e) => Display::fmt(e, f),
             YarnValueCastError::ParseBoolError(e) => Display::fmt(e, f),
+            YarnValueCastError::LossyConversion { value, target_type } => {
+                write!(
+                    f,
+                    "Cannot convert {value:?} to {target_type} without losing information"
+                )
+            }
         }
     }
 }
@@ -248,8 +506,83 @@ impl Display for YarnValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Number(value) => write!(f, "{value}"),
+            Self::Integer(value) => write!(f, "{value}"),
             Self::String(value) => write!(f, "{value}"),
             Self::Boolean(value) => write!(f, "{value}"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_integers_losslessly() {
+        let id = u64::MAX >> 2;
+        let value = YarnValue::from(id);
+        assert_eq!(YarnValue::Integer(id as i64), value);
+        assert_eq!(id, u64::try_from(&value).unwrap());
+        assert_eq!(id, Strict::<u64>::try_from(&value).unwrap().0);
+        assert_eq!(u8::MAX, u8::try_from(&value).unwrap());
+        assert!(Strict::<u8>::try_from(&value).is_err());
+        assert!(Strict::<f32>::try_from(&value).is_err());
+        assert_eq!(
+            YarnValue::Number(u128::MAX as f32),
+            YarnValue::from(u128::MAX)
+        );
+
+        assert_eq!(YarnValue::Integer(2), YarnValue::Number(2.0));
+        assert_ne!(YarnValue::Integer(2), YarnValue::Number(2.5));
+        assert_eq!(Some(2), YarnValue::Number(2.0).as_integer());
+        assert_eq!(None, YarnValue::Number(2.5).as_integer());
+        assert_eq!(3, i32::try_from(YarnValue::Number(3.9)).unwrap());
+        assert!(Strict::<i32>::try_from(YarnValue::Number(3.9)).is_err());
+        assert_eq!(id, u64::try_from(YarnValue::from(id.to_string())).unwrap());
+        assert!(Strict::<String>::try_from(YarnValue::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn number_operators_keep_integers_exact() {
+        let library = Library::standard_library();
+        let call = |operator: Operator, parameters: Vec<YarnValue>| {
+            let name = Type::Number.get_canonical_name_for_method(&operator.to_string());
+            library.get(&name).unwrap().call(parameters)
+        };
+        let big = YarnValue::Integer(1 << 40);
+        let bigger = YarnValue::Integer((1 << 40) + 1);
+        assert_eq!(
+            YarnValue::Boolean(false),
+            call(Operator::EqualTo, vec![big.clone(), bigger.clone()])
+        );
+        assert_eq!(
+            YarnValue::Boolean(true),
+            call(Operator::LessThan, vec![big.clone(), bigger.clone()])
+        );
+        assert_eq!(
+            YarnValue::Integer(1),
+            call(Operator::Subtract, vec![bigger, big.clone()])
+        );
+        assert_eq!(
+            YarnValue::Number(2.5),
+            call(
+                Operator::Divide,
+                vec![YarnValue::Integer(5), YarnValue::Integer(2)]
+            )
+        );
+        assert_eq!(
+            YarnValue::Number(1.5),
+            call(
+                Operator::Add,
+                vec![YarnValue::Integer(1), YarnValue::Number(0.5)]
+            )
+        );
+        assert_eq!(
+            YarnValue::Number(i64::MAX as f32 * 2.0),
+            call(
+                Operator::Add,
+                vec![YarnValue::Integer(i64::MAX), YarnValue::Integer(i64::MAX)]
+            )
+        );
+    }
+}
@@ -0,0 +1,149 @@
+/*
+ * C bindings for the Yarn Spinner for Rust runtime, see `crates/ffi/src/lib.rs` for the documentation of every declaration.
+ *
+ * Link against the `yarnspinner_ffi` cdylib or staticlib built with `cargo build -p yarnspinner_ffi --release`.
+ */
+
+#ifndef YARNSPINNER_H
+#define YARNSPINNER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct YsDialogue YsDialogue;
+
+typedef enum YsStatus {
+    YS_STATUS_OK = 0,
+    YS_STATUS_NULL_POINTER = 1,
+    YS_STATUS_INVALID_UTF8 = 2,
+    YS_STATUS_INVALID_PROGRAM = 3,
+    YS_STATUS_INVALID_ARGUMENT = 4,
+    YS_STATUS_DIALOGUE_ERROR = 5,
+    YS_STATUS_PANIC = 6,
+} YsStatus;
+
+typedef enum YsValueKind {
+    YS_VALUE_NUMBER = 0,
+    YS_VALUE_INTEGER = 1,
+    YS_VALUE_STRING = 2,
+    YS_VALUE_BOOLEAN = 3,
+} YsValueKind;
+
+typedef struct YsValue {
+    /* One of the YsValueKinds. */
+    uint32_t kind;
+    union {
+        float number;
+        int64_t integer;
+        const char *string;
+        /* 0 for false, 1 for true. */
+        uint8_t boolean;
+    } data;
+} YsValue;
+
+typedef enum YsEventKind {
+    YS_EVENT_LINE = 0,
+    YS_EVENT_LINE_HINTS = 1,
+    YS_EVENT_OPTIONS = 2,
+    YS_EVENT_COMMAND = 3,
+    YS_EVENT_WAIT = 4,
+    YS_EVENT_NODE_COMPLETE = 5,
+    YS_EVENT_NODE_START = 6,
+    YS_EVENT_BREAKPOINT_HIT = 7,
+    YS_EVENT_DIALOGUE_COMPLETE = 8,
+    YS_EVENT_LINE_INTERRUPTED = 9,
+    YS_EVENT_LINE_FILTERED = 10,
+} YsEventKind;
+
+typedef struct YsLineHints {
+    bool is_last_line_before_options;
+    bool is_final_line_of_node;
+    bool is_final_line_of_dialogue;
+} YsLineHints;
+
+typedef struct YsLine {
+    uint32_t line_id;
+    size_t substitution_count;
+} YsLine;
+
+typedef struct YsCommand {
+    const char *name;
+    const char *raw;
+    size_t parameter_count;
+} YsCommand;
+
+typedef struct YsBreakpoint {
+    const char *node_name;
+    bool is_line;
+    uint64_t location;
+} YsBreakpoint;
+
+typedef enum YsLineFilterAction {
+    YS_LINE_FILTER_SUPPRESS = 0,
+    YS_LINE_FILTER_REPLACE = 1,
+    YS_LINE_FILTER_ANNOTATE = 2,
+} YsLineFilterAction;
+
+typedef struct YsLineFiltered {
+    uint32_t line_id;
+    const char *hashtag;
+    YsLineFilterAction action;
+    uint32_t replacement_line_id;
+} YsLineFiltered;
+
+typedef struct YsEvent {
+    YsEventKind kind;
+    union {
+        YsLine line;
+        YsLineHints line_hints;
+        size_t option_count;
+        YsCommand command;
+        uint64_t wait_milliseconds;
+        const char *node_name;
+        YsBreakpoint breakpoint;
+        uint32_t interrupted_line_id;
+        YsLineFiltered line_filtered;
+    } data;
+} YsEvent;
+
+typedef struct YsOption {
+    size_t id;
+    uint32_t line_id;
+    bool is_available;
+    size_t substitution_count;
+} YsOption;
+
+typedef bool (*YsFunction)(void *user_data, const YsValue *parameters, size_t parameter_count, YsValue *result);
+
+YsDialogue *ys_dialogue_new(void);
+void ys_dialogue_free(YsDialogue *dialogue);
+const char *ys_dialogue_last_error(const YsDialogue *dialogue);
+
+YsStatus ys_dialogue_load_program(YsDialogue *dialogue, const uint8_t *bytes, size_t length);
+YsStatus ys_dialogue_register_function(YsDialogue *dialogue, const char *name, size_t parameter_count,
+                                       uint32_t return_kind, YsFunction callback, void *user_data);
+YsStatus ys_dialogue_set_node(YsDialogue *dialogue, const char *node_name);
+
+YsStatus ys_dialogue_continue(YsDialogue *dialogue);
+bool ys_dialogue_can_continue(const YsDialogue *dialogue);
+YsStatus ys_dialogue_interrupt_line(YsDialogue *dialogue, bool auto_advance);
+bool ys_dialogue_poll_event(YsDialogue *dialogue, YsEvent *event);
+YsStatus ys_dialogue_option(YsDialogue *dialogue, size_t index, YsOption *option);
+YsStatus ys_dialogue_option_substitution(YsDialogue *dialogue, size_t option_index, size_t index, YsValue *value);
+YsStatus ys_dialogue_command_parameter(YsDialogue *dialogue, size_t index, YsValue *value);
+YsStatus ys_dialogue_line_substitution(YsDialogue *dialogue, size_t index, YsValue *value);
+YsStatus ys_dialogue_select_option(YsDialogue *dialogue, size_t option_id);
+
+YsStatus ys_dialogue_set_variable(YsDialogue *dialogue, const char *name, const YsValue *value);
+YsStatus ys_dialogue_variable(YsDialogue *dialogue, const char *name, YsValue *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* YARNSPINNER_H */
@@ -0,0 +1,1268 @@
+//! C bindings for the Yarn Spinner runtime, so that engines which cannot depend on Rust crates directly,
+//! e.g. custom C++ engines, Godot through GDExtension or Defold through native extensions, can embed it.
+//! The declarations for C and C++ are in `include/yarnspinner.h`.
+//!
+//! A [`YsDialogue`] is created with [`ys_dialogue_new`] and must be freed with [`ys_dialogue_free`].
+//! Functions that can fail return a [`YsStatus`], and [`ys_dialogue_last_error`] describes the last failure.
+//! Strings passed in must be null-terminated UTF-8. Strings handed out are owned by the dialogue
+//! and stay valid until the call documented on the function that returned them.
+//!
+//! A [`YsDialogue`] must not be used from multiple threads at the same time.
+
+#![warn(missing_docs, missing_debug_implementations)]
+
+use core::any::TypeId;
+use core::ffi::{c_char, c_void, CStr};
+use core::fmt::{self, Debug, Display};
+use core::result::Result;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use yarnspinner_core::prelude::*;
+use yarnspinner_runtime::prelude::*;
+
+/// The result of a fallible function. Everything but [`YsStatus::Ok`] comes with a message from [`ys_dialogue_last_error`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YsStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// A pointer that must not be null was null.
+    NullPointer = 1,
+    /// A string was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The bytes passed to [`ys_dialogue_load_program`] are not a compiled Yarn program, or its nodes are already loaded.
+    InvalidProgram = 3,
+    /// An argument was out of range or does not fit the current event.
+    InvalidArgument = 4,
+    /// The runtime returned an error, e.g. because a node does not exist.
+    DialogueError = 5,
+    /// The runtime panicked. This is a bug in the runtime.
+    /// The dialogue may be left in an inconsistent state and should only be freed.
+    Panic = 6,
+}
+
+/// The type of a [`YsValue`]. Passed as a `u32` so that the values C code hands in can be validated.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YsValueKind {
+    /// A number stored in [`YsValueData::number`].
+    Number = 0,
+    /// A number stored in [`YsValueData::integer`].
+    Integer = 1,
+    /// A string stored in [`YsValueData::string`].
+    String = 2,
+    /// A boolean stored in [`YsValueData::boolean`].
+    Boolean = 3,
+}
+
+impl YsValueKind {
+    /// Validates a kind passed in by C code.
+    fn from_raw(kind: u32) -> Result<Self, Failure> {
+        match kind {
+            0 => Ok(Self::Number),
+            1 => Ok(Self::Integer),
+            2 => Ok(Self::String),
+            3 => Ok(Self::Boolean),
+            _ => Err(Failure::new(
+                YsStatus::InvalidArgument,
+                format!("{kind} is not a YsValueKind"),
+            )),
+        }
+    }
+}
+
+/// The payload of a [`YsValue`]. Only the field matching its [`YsValueKind`] may be read.
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(missing_docs)]
+pub union YsValueData {
+    pub number: f32,
+    pub integer: i64,
+    pub string: *const c_char,
+    /// `0` for `false` and `1` for `true`. Other values are rejected with [`YsStatus::InvalidArgument`].
+    pub boolean: u8,
+}
+
+impl Debug for YsValueData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("YsValueData").finish_non_exhaustive()
+    }
+}
+
+/// A Yarn value, i.e. a [`YarnValue`] as a tagged union.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct YsValue {
+    /// Which field of [`YsValue::data`] is set, one of the [`YsValueKind`]s. Other values are rejected with [`YsStatus::InvalidArgument`].
+    pub kind: u32,
+    /// The value.
+    pub data: YsValueData,
+}
+
+impl Debug for YsValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // SAFETY: The kind says which field is initialized. Strings are only read by `to_yarn_value`.
+        unsafe {
+            match YsValueKind::from_raw(self.kind) {
+                Ok(YsValueKind::Number) => write!(f, "YsValue::Number({})", self.data.number),
+                Ok(YsValueKind::Integer) => write!(f, "YsValue::Integer({})", self.data.integer),
+                Ok(YsValueKind::String) => write!(f, "YsValue::String({:?})", self.data.string),
+                Ok(YsValueKind::Boolean) => write!(f, "YsValue::Boolean({})", self.data.boolean),
+                Err(_) => write!(f, "YsValue::Invalid({})", self.kind),
+            }
+        }
+    }
+}
+
+impl YsValue {
+    /// Converts a value, keeping its string alive in `strings`.
+    fn new(value: &YarnValue, strings: &mut Vec<CString>) -> Self {
+        let (kind, data) = match value {
+            YarnValue::Number(number) => (YsValueKind::Number, YsValueData { number: *number }),
+            YarnValue::Integer(integer) => {
+                (YsValueKind::Integer, YsValueData { integer: *integer })
+            }
+            YarnValue::String(string) => {
+                let string = c_string(string);
+                let pointer = string.as_ptr();
+                strings.push(string);
+                (YsValueKind::String, YsValueData { string: pointer })
+            }
+            YarnValue::Boolean(boolean) => (
+                YsValueKind::Boolean,
+                YsValueData {
+                    boolean: u8::from(*boolean),
+                },
+            ),
+        };
+        Self {
+            kind: kind as u32,
+            data,
+        }
+    }
+
+    /// ## Safety
+    ///
+    /// The field of `data` matching `kind` must be initialized, and a string must be a valid pointer to a null-terminated string.
+    unsafe fn to_yarn_value(self) -> Result<YarnValue, Failure> {
+        let value = match YsValueKind::from_raw(self.kind)? {
+            YsValueKind::Number => YarnValue::Number(self.data.number),
+            YsValueKind::Integer => YarnValue::Integer(self.data.integer),
+            YsValueKind::String => YarnValue::String(read_str(self.data.string)?.to_owned()),
+            YsValueKind::Boolean => match self.data.boolean {
+                0 => YarnValue::Boolean(false),
+                1 => YarnValue::Boolean(true),
+                byte => {
+                    return Err(Failure::new(
+                        YsStatus::InvalidArgument,
+                        format!("{byte} is not a boolean, expected 0 or 1"),
+                    ))
+                }
+            },
+        };
+        Ok(value)
+    }
+}
+
+/// The type of a [`YsEvent`], see [`DialogueEvent`] for what they mean.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum YsEventKind {
+    Line = 0,
+    LineHints = 1,
+    Options = 2,
+    Command = 3,
+    Wait = 4,
+    NodeComplete = 5,
+    NodeStart = 6,
+    BreakpointHit = 7,
+    DialogueComplete = 8,
+    LineInterrupted = 9,
+    LineFiltered = 10,
+}
+
+/// The payload of a [`YsEvent`]. Only the field matching its [`YsEventKind`] may be read,
+/// a [`YsEventKind::DialogueComplete`] has none.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union YsEventData {
+    /// The line of a [`YsEventKind::Line`].
+    pub line: YsLine,
+    /// The hints of a [`YsEventKind::LineHints`].
+    pub line_hints: YsLineHints,
+    /// The number of options of a [`YsEventKind::Options`], which are read with [`ys_dialogue_option`].
+    pub option_count: usize,
+    /// The command of a [`YsEventKind::Command`].
+    pub command: YsCommand,
+    /// The duration of a [`YsEventKind::Wait`].
+    pub wait_milliseconds: u64,
+    /// The node of a [`YsEventKind::NodeStart`] or [`YsEventKind::NodeComplete`].
+    pub node_name: *const c_char,
+    /// The breakpoint of a [`YsEventKind::BreakpointHit`].
+    pub breakpoint: YsBreakpoint,
+    /// The ID of the line of a [`YsEventKind::LineInterrupted`].
+    pub interrupted_line_id: u32,
+    /// The record of a [`YsEventKind::LineFiltered`].
+    pub line_filtered: YsLineFiltered,
+}
+
+impl Debug for YsEventData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("YsEventData").finish_non_exhaustive()
+    }
+}
+
+/// A [`DialogueEvent`] as a tagged union.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct YsEvent {
+    /// Which field of [`YsEvent::data`] is set.
+    pub kind: YsEventKind,
+    /// The payload.
+    pub data: YsEventData,
+}
+
+impl Debug for YsEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("YsEvent")
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// See [`LineHints`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct YsLineHints {
+    pub is_last_line_before_options: bool,
+    pub is_final_line_of_node: bool,
+    pub is_final_line_of_dialogue: bool,
+}
+
+/// The line of a [`DialogueEvent::Line`]. Its substitutions are read with [`ys_dialogue_line_substitution`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct YsLine {
+    pub line_id: u32,
+    pub substitution_count: usize,
+}
+
+/// See [`Command`]. Its parameters are read with [`ys_dialogue_command_parameter`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct YsCommand {
+    pub name: *const c_char,
+    pub raw: *const c_char,
+    pub parameter_count: usize,
+}
+
+/// See [`Breakpoint`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct YsBreakpoint {
+    /// The node the breakpoint is in.
+    pub node_name: *const c_char,
+    /// Whether [`YsBreakpoint::location`] is a line ID rather than an instruction index.
+    pub is_line: bool,
+    /// The line ID or instruction index, see [`BreakpointLocation`].
+    pub location: u64,
+}
+
+/// See [`LineFilterAction`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum YsLineFilterAction {
+    Suppress = 0,
+    Replace = 1,
+    Annotate = 2,
+}
+
+/// See [`LineFiltered`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct YsLineFiltered {
+    /// The ID of the line the rule was applied to.
+    pub line_id: u32,
+    /// The hashtag the rule matched, without the leading `#`.
+    pub hashtag: *const c_char,
+    /// What was done with the line.
+    pub action: YsLineFilterAction,
+    /// The ID of the line delivered instead, only set for [`YsLineFilterAction::Replace`].
+    pub replacement_line_id: u32,
+}
+
+/// See [`DialogueOption`]. Its substitutions are read with [`ys_dialogue_option_substitution`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub struct YsOption {
+    /// The ID to pass to [`ys_dialogue_select_option`].
+    pub id: usize,
+    pub line_id: u32,
+    pub is_available: bool,
+    pub substitution_count: usize,
+}
+
+/// A function called from Yarn scripts, see [`ys_dialogue_register_function`].
+///
+/// Receives the `user_data` it was registered with and the parameters, which are only valid during the call.
+/// Writes its return value to `result` and returns `true`, or returns `false` to fail.
+/// A returned string must stay valid until the function is called again or the dialogue is freed.
+pub type YsFunction = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    parameters: *const YsValue,
+    parameter_count: usize,
+    result: *mut YsValue,
+) -> bool;
+
+/// A dialogue created with [`ys_dialogue_new`].
+#[derive(Debug)]
+pub struct YsDialogue {
+    dialogue: Dialogue,
+    events: VecDeque<DialogueEvent>,
+    /// The event last returned by [`ys_dialogue_poll_event`].
+    current_event: Option<DialogueEvent>,
+    /// The strings and values, i.e. line substitutions or command parameters, of the current event.
+    event_strings: Vec<CString>,
+    event_values: Vec<YsValue>,
+    variable_strings: Vec<CString>,
+    last_error: Option<CString>,
+}
+
+struct Failure {
+    status: YsStatus,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: YsStatus, message: impl Display) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<DialogueError> for Failure {
+    fn from(error: DialogueError) -> Self {
+        Self::new(YsStatus::DialogueError, error)
+    }
+}
+
+/// A [`YsFunction`] registered in the [`Library`] of a dialogue.
+#[derive(Debug, Clone)]
+struct ForeignFunction {
+    name: String,
+    callback: YsFunction,
+    user_data: *mut c_void,
+    parameter_count: usize,
+    return_kind: YsValueKind,
+}
+
+// SAFETY: The caller of `ys_dialogue_register_function` guarantees that the callback can be called with the user data
+// from whichever thread uses the dialogue.
+unsafe impl Send for ForeignFunction {}
+unsafe impl Sync for ForeignFunction {}
+
+impl Display for ForeignFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (C function)", self.name)
+    }
+}
+
+impl UntypedYarnFn for ForeignFunction {
+    fn call(&self, input: Vec<YarnValue>) -> YarnValue {
+        self.try_call(input)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_call(&self, input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError> {
+        let mut strings = Vec::new();
+        let parameters: Vec<_> = input
+            .iter()
+            .map(|value| YsValue::new(value, &mut strings))
+            .collect();
+        let mut result = YsValue {
+            kind: self.return_kind as u32,
+            data: YsValueData { integer: 0 },
+        };
+        // SAFETY: The registration guarantees that the callback is valid, the parameters live until the end of this function.
+        let succeeded = unsafe {
+            (self.callback)(
+                self.user_data,
+                parameters.as_ptr(),
+                parameters.len(),
+                &mut result,
+            )
+        };
+        if !succeeded {
+            return Err(YarnFnError::Failed(format!("{} failed", self.name)));
+        }
+        let kind = YsValueKind::from_raw(result.kind).map_err(|failure| {
+            YarnFnError::Failed(format!(
+                "{} returned a value of which {}",
+                self.name, failure.message
+            ))
+        })?;
+        let returns_number = |kind| matches!(kind, YsValueKind::Number | YsValueKind::Integer);
+        if kind != self.return_kind && !(returns_number(kind) && returns_number(self.return_kind)) {
+            return Err(YarnFnError::Failed(format!(
+                "{} returned a {:?} instead of a {:?}",
+                self.name, kind, self.return_kind
+            )));
+        }
+        // SAFETY: The callback wrote a value of the kind it reports.
+        unsafe { result.to_yarn_value() }.map_err(|failure| YarnFnError::Failed(failure.message))
+    }
+
+    fn clone_box(&self) -> Box<dyn UntypedYarnFn> {
+        Box::new(self.clone())
+    }
+
+    fn parameter_types(&self) -> Vec<TypeId> {
+        vec![TypeId::of::<YarnValue>(); self.parameter_count]
+    }
+
+    fn return_type(&self) -> TypeId {
+        match self.return_kind {
+            YsValueKind::Number => TypeId::of::<f32>(),
+            YsValueKind::Integer => TypeId::of::<i64>(),
+            YsValueKind::String => TypeId::of::<String>(),
+            YsValueKind::Boolean => TypeId::of::<bool>(),
+        }
+    }
+}
+
+/// Creates a dialogue with an in-memory variable storage and the standard library. Free it with [`ys_dialogue_free`].
+#[no_mangle]
+pub extern "C" fn ys_dialogue_new() -> *mut YsDialogue {
+    Box::into_raw(Box::new(YsDialogue {
+        dialogue: Dialogue::new(Box::new(MemoryVariableStorage::new())),
+        events: VecDeque::new(),
+        current_event: None,
+        event_strings: Vec::new(),
+        event_values: Vec::new(),
+        variable_strings: Vec::new(),
+        last_error: None,
+    }))
+}
+
+/// Frees a dialogue. Does nothing if it is null.
+///
+/// ## Safety
+///
+/// The dialogue must have been created by [`ys_dialogue_new`] and not been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_free(dialogue: *mut YsDialogue) {
+    if !dialogue.is_null() {
+        drop(Box::from_raw(dialogue));
+    }
+}
+
+/// Returns the message of the last failed call on this dialogue, or null if the last call succeeded.
+/// The message is valid until the next call on the dialogue.
+///
+/// ## Safety
+///
+/// The dialogue must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_last_error(dialogue: *const YsDialogue) -> *const c_char {
+    dialogue
+        .as_ref()
+        .and_then(|dialogue| dialogue.last_error.as_ref())
+        .map_or(core::ptr::null(), |message| message.as_ptr())
+}
+
+/// Loads a compiled program, i.e. the contents of a `.yarnc` file, adding its nodes to the ones loaded before.
+/// Fails with [`YsStatus::InvalidProgram`] if the bytes are not a valid program or one of its nodes is already loaded.
+///
+/// ## Safety
+///
+/// The dialogue must be null or valid, and `bytes` must point to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_load_program(
+    dialogue: *mut YsDialogue,
+    bytes: *const u8,
+    length: usize,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        if bytes.is_null() {
+            return Err(Failure::new(YsStatus::NullPointer, "The program is null"));
+        }
+        let bytes = core::slice::from_raw_parts(bytes, length);
+        let program = Program::from_yarnc_bytes(bytes)
+            .map_err(|error| Failure::new(YsStatus::InvalidProgram, error))?;
+        if let Some(node_name) = dialogue
+            .dialogue
+            .node_names()
+            .into_iter()
+            .flatten()
+            .find(|node_name| program.nodes.contains_key(*node_name))
+        {
+            return Err(Failure::new(
+                YsStatus::InvalidProgram,
+                format!("A node named {node_name} is already loaded"),
+            ));
+        }
+        dialogue.dialogue.add_program(program);
+        Ok(())
+    })
+}
+
+/// Registers a function that Yarn scripts can call with `parameter_count` parameters of any type,
+/// returning a value of `return_kind`, one of the [`YsValueKind`]s, see [`YsFunction`].
+///
+/// ## Safety
+///
+/// The dialogue and `name` must be valid. `callback` must be safe to call with `user_data` for as long as the dialogue exists,
+/// from whichever thread uses the dialogue.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_register_function(
+    dialogue: *mut YsDialogue,
+    name: *const c_char,
+    parameter_count: usize,
+    return_kind: u32,
+    callback: YsFunction,
+    user_data: *mut c_void,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let name = read_str(name)?.to_owned();
+        let return_kind = YsValueKind::from_raw(return_kind)?;
+        let function = ForeignFunction {
+            name: name.clone(),
+            callback,
+            user_data,
+            parameter_count,
+            return_kind,
+        };
+        let function: Box<dyn UntypedYarnFn> = Box::new(function);
+        dialogue
+            .dialogue
+            .library_mut()
+            .extend([(name.into(), function)]);
+        Ok(())
+    })
+}
+
+/// Prepares the dialogue to run the given node on the next [`ys_dialogue_continue`].
+///
+/// ## Safety
+///
+/// The dialogue and `node_name` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_set_node(
+    dialogue: *mut YsDialogue,
+    node_name: *const c_char,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        dialogue.dialogue.set_node(read_str(node_name)?)?;
+        Ok(())
+    })
+}
+
+/// Runs the dialogue until it needs input from the game, queuing the events for [`ys_dialogue_poll_event`].
+///
+/// ## Safety
+///
+/// The dialogue must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_continue(dialogue: *mut YsDialogue) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let events = dialogue.dialogue.continue_()?;
+        dialogue.events.extend(events);
+        Ok(())
+    })
+}
+
+/// Returns whether [`ys_dialogue_continue`] can be called, i.e. the dialogue is not waiting for an option to be selected.
+///
+/// ## Safety
+///
+/// The dialogue must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_can_continue(dialogue: *const YsDialogue) -> bool {
+    dialogue
+        .as_ref()
+        .is_some_and(|dialogue| dialogue.dialogue.can_continue())
+}
+
+/// Interrupts the line last returned by [`ys_dialogue_poll_event`], queuing a [`YsEventKind::LineInterrupted`].
+/// If `auto_advance` is `true`, the dialogue continues right away like [`ys_dialogue_continue`].
+///
+/// ## Safety
+///
+/// The dialogue must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_interrupt_line(
+    dialogue: *mut YsDialogue,
+    auto_advance: bool,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let events = dialogue.dialogue.interrupt_line(auto_advance)?;
+        dialogue.events.extend(events);
+        Ok(())
+    })
+}
+
+/// Writes the next queued event to `event` and returns `true`, or returns `false` if there is none.
+/// The strings of the event are valid until the next call of this function.
+///
+/// ## Safety
+///
+/// The dialogue and `event` must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_poll_event(
+    dialogue: *mut YsDialogue,
+    event: *mut YsEvent,
+) -> bool {
+    let (Some(dialogue), Some(event)) = (dialogue.as_mut(), event.as_mut()) else {
+        return false;
+    };
+    dialogue.event_strings.clear();
+    dialogue.event_values.clear();
+    dialogue.current_event = dialogue.events.pop_front();
+    let Some(current_event) = &dialogue.current_event else {
+        return false;
+    };
+    let strings = &mut dialogue.event_strings;
+    let mut string = |string: &str| {
+        let string = c_string(string);
+        let pointer = string.as_ptr();
+        strings.push(string);
+        pointer
+    };
+    let (kind, data) = match current_event {
+        DialogueEvent::Line(line_id, substitutions) => {
+            dialogue.event_values = substitutions
+                .iter()
+                .map(|substitution| YsValue::new(substitution, &mut dialogue.event_strings))
+                .collect();
+            (
+                YsEventKind::Line,
+                YsEventData {
+                    line: YsLine {
+                        line_id: *line_id,
+                        substitution_count: substitutions.len(),
+                    },
+                },
+            )
+        }
+        DialogueEvent::LineHints(hints) => (
+            YsEventKind::LineHints,
+            YsEventData {
+                line_hints: YsLineHints {
+                    is_last_line_before_options: hints.is_last_line_before_options,
+                    is_final_line_of_node: hints.is_final_line_of_node,
+                    is_final_line_of_dialogue: hints.is_final_line_of_dialogue,
+                },
+            },
+        ),
+        DialogueEvent::Options(options) => (
+            YsEventKind::Options,
+            YsEventData {
+                option_count: options.len(),
+            },
+        ),
+        DialogueEvent::Command(command) => {
+            let name = string(&command.name);
+            let raw = string(&command.raw);
+            dialogue.event_values = command
+                .parameters
+                .iter()
+                .map(|parameter| YsValue::new(parameter, &mut dialogue.event_strings))
+                .collect();
+            (
+                YsEventKind::Command,
+                YsEventData {
+                    command: YsCommand {
+                        name,
+                        raw,
+                        parameter_count: command.parameters.len(),
+                    },
+                },
+            )
+        }
+        DialogueEvent::Wait(duration) => (
+            YsEventKind::Wait,
+            YsEventData {
+                wait_milliseconds: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            },
+        ),
+        DialogueEvent::NodeComplete(node_name) => (
+            YsEventKind::NodeComplete,
+            YsEventData {
+                node_name: string(node_name),
+            },
+        ),
+        DialogueEvent::NodeStart(node_name) => (
+            YsEventKind::NodeStart,
+            YsEventData {
+                node_name: string(node_name),
+            },
+        ),
+        DialogueEvent::BreakpointHit(breakpoint) => {
+            let (is_line, location) = match breakpoint.location {
+                BreakpointLocation::Line(line_id) => (true, u64::from(line_id)),
+                BreakpointLocation::Instruction(index) => (false, index as u64),
+            };
+            (
+                YsEventKind::BreakpointHit,
+                YsEventData {
+                    breakpoint: YsBreakpoint {
+                        node_name: string(&breakpoint.node_name),
+                        is_line,
+                        location,
+                    },
+                },
+            )
+        }
+        DialogueEvent::DialogueComplete => (
+            YsEventKind::DialogueComplete,
+            YsEventData { option_count: 0 },
+        ),
+        DialogueEvent::LineInterrupted(line_id) => (
+            YsEventKind::LineInterrupted,
+            YsEventData {
+                interrupted_line_id: *line_id,
+            },
+        ),
+        DialogueEvent::LineFiltered(record) => {
+            let (action, replacement_line_id) = match record.action {
+                LineFilterAction::Suppress => (YsLineFilterAction::Suppress, 0),
+                LineFilterAction::Replace(replacement) => {
+                    (YsLineFilterAction::Replace, replacement)
+                }
+                LineFilterAction::Annotate => (YsLineFilterAction::Annotate, 0),
+            };
+            (
+                YsEventKind::LineFiltered,
+                YsEventData {
+                    line_filtered: YsLineFiltered {
+                        line_id: record.line_id,
+                        hashtag: string(&record.hashtag),
+                        action,
+                        replacement_line_id,
+                    },
+                },
+            )
+        }
+    };
+    *event = YsEvent { kind, data };
+    true
+}
+
+/// Writes the option at `index` of the [`YsEventKind::Options`] last returned by [`ys_dialogue_poll_event`] to `option`.
+///
+/// ## Safety
+///
+/// The dialogue and `option` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_option(
+    dialogue: *mut YsDialogue,
+    index: usize,
+    option: *mut YsOption,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let option = output(option)?;
+        let Some(DialogueEvent::Options(options)) = &dialogue.current_event else {
+            return Err(Failure::new(
+                YsStatus::InvalidArgument,
+                "The current event is not an options event",
+            ));
+        };
+        let dialogue_option = options.get(index).ok_or_else(|| {
+            Failure::new(
+                YsStatus::InvalidArgument,
+                format!("There is no option {index}, there are {}", options.len()),
+            )
+        })?;
+        *option = YsOption {
+            id: dialogue_option.id.0,
+            line_id: dialogue_option.tag_id,
+            is_available: dialogue_option.is_available,
+            substitution_count: dialogue_option.substitutions.len(),
+        };
+        Ok(())
+    })
+}
+
+/// Writes the substitution at `index` of the option at `option_index` of the [`YsEventKind::Options`] last returned by [`ys_dialogue_poll_event`] to `value`.
+/// A string is valid until the next call of [`ys_dialogue_poll_event`].
+///
+/// ## Safety
+///
+/// The dialogue and `value` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_option_substitution(
+    dialogue: *mut YsDialogue,
+    option_index: usize,
+    index: usize,
+    value: *mut YsValue,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let value = output(value)?;
+        let Some(DialogueEvent::Options(options)) = &dialogue.current_event else {
+            return Err(Failure::new(
+                YsStatus::InvalidArgument,
+                "The current event is not an options event",
+            ));
+        };
+        let substitutions = &options
+            .get(option_index)
+            .ok_or_else(|| {
+                Failure::new(
+                    YsStatus::InvalidArgument,
+                    format!(
+                        "There is no option {option_index}, there are {}",
+                        options.len()
+                    ),
+                )
+            })?
+            .substitutions;
+        let substitution = substitutions.get(index).ok_or_else(|| {
+            Failure::new(
+                YsStatus::InvalidArgument,
+                format!(
+                    "There is no substitution {index}, there are {}",
+                    substitutions.len()
+                ),
+            )
+        })?;
+        *value = YsValue::new(substitution, &mut dialogue.event_strings);
+        Ok(())
+    })
+}
+
+/// Writes the parameter at `index` of the [`YsEventKind::Command`] last returned by [`ys_dialogue_poll_event`] to `value`.
+/// A string is valid until the next call of [`ys_dialogue_poll_event`].
+///
+/// ## Safety
+///
+/// The dialogue and `value` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_command_parameter(
+    dialogue: *mut YsDialogue,
+    index: usize,
+    value: *mut YsValue,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let value = output(value)?;
+        if !matches!(dialogue.current_event, Some(DialogueEvent::Command(_))) {
+            return Err(Failure::new(
+                YsStatus::InvalidArgument,
+                "The current event is not a command",
+            ));
+        }
+        *value = *dialogue.event_values.get(index).ok_or_else(|| {
+            Failure::new(
+                YsStatus::InvalidArgument,
+                format!(
+                    "There is no parameter {index}, there are {}",
+                    dialogue.event_values.len()
+                ),
+            )
+        })?;
+        Ok(())
+    })
+}
+
+/// Writes the substitution at `index` of the [`YsEventKind::Line`] last returned by [`ys_dialogue_poll_event`] to `value`,
+/// i.e. the value for the placeholder `{index}` of the line's text.
+/// A string is valid until the next call of [`ys_dialogue_poll_event`].
+///
+/// ## Safety
+///
+/// The dialogue and `value` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_line_substitution(
+    dialogue: *mut YsDialogue,
+    index: usize,
+    value: *mut YsValue,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let value = output(value)?;
+        if !matches!(dialogue.current_event, Some(DialogueEvent::Line(..))) {
+            return Err(Failure::new(
+                YsStatus::InvalidArgument,
+                "The current event is not a line",
+            ));
+        }
+        *value = *dialogue.event_values.get(index).ok_or_else(|| {
+            Failure::new(
+                YsStatus::InvalidArgument,
+                format!(
+                    "There is no substitution {index}, there are {}",
+                    dialogue.event_values.len()
+                ),
+            )
+        })?;
+        Ok(())
+    })
+}
+
+/// Selects the option with the given [`YsOption::id`] after a [`YsEventKind::Options`].
+///
+/// ## Safety
+///
+/// The dialogue must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_select_option(
+    dialogue: *mut YsDialogue,
+    option_id: usize,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        dialogue.dialogue.set_selected_option(OptionId(option_id))?;
+        Ok(())
+    })
+}
+
+/// Sets a variable, e.g. `$gold`.
+///
+/// ## Safety
+///
+/// The dialogue, `name` and `value` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_set_variable(
+    dialogue: *mut YsDialogue,
+    name: *const c_char,
+    value: *const YsValue,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let value = value
+            .as_ref()
+            .ok_or_else(|| Failure::new(YsStatus::NullPointer, "The value is null"))?
+            .to_yarn_value()?;
+        dialogue
+            .dialogue
+            .set_variable(read_str(name)?, value)
+            .map_err(|error| Failure::new(YsStatus::DialogueError, error))?;
+        Ok(())
+    })
+}
+
+/// Writes the value of a variable to `value`. A string is valid until the next call of this function.
+///
+/// ## Safety
+///
+/// The dialogue, `name` and `value` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn ys_dialogue_variable(
+    dialogue: *mut YsDialogue,
+    name: *const c_char,
+    value: *mut YsValue,
+) -> YsStatus {
+    with_dialogue(dialogue, |dialogue| {
+        let value = output(value)?;
+        let variable = dialogue
+            .dialogue
+            .variable(read_str(name)?)
+            .map_err(|error| Failure::new(YsStatus::DialogueError, error))?;
+        dialogue.variable_strings.clear();
+        *value = YsValue::new(&variable, &mut dialogue.variable_strings);
+        Ok(())
+    })
+}
+
+/// Runs `f` on the dialogue, recording its failure for [`ys_dialogue_last_error`].
+/// Panics are caught and reported as [`YsStatus::Panic`], since unwinding into C would abort the host.
+unsafe fn with_dialogue(
+    dialogue: *mut YsDialogue,
+    f: impl FnOnce(&mut YsDialogue) -> Result<(), Failure>,
+) -> YsStatus {
+    let Some(dialogue) = dialogue.as_mut() else {
+        return YsStatus::NullPointer;
+    };
+    dialogue.last_error = None;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(dialogue))).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        Err(Failure::new(
+            YsStatus::Panic,
+            format!("The runtime panicked: {message}"),
+        ))
+    });
+    match result {
+        Ok(()) => YsStatus::Ok,
+        Err(Failure { status, message }) => {
+            dialogue.last_error = Some(c_string(&message));
+            status
+        }
+    }
+}
+
+unsafe fn read_str<'a>(string: *const c_char) -> Result<&'a str, Failure> {
+    if string.is_null() {
+        return Err(Failure::new(YsStatus::NullPointer, "A string is null"));
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|error| Failure::new(YsStatus::InvalidUtf8, error))
+}
+
+unsafe fn output<'a, T>(pointer: *mut T) -> Result<&'a mut T, Failure> {
+    pointer
+        .as_mut()
+        .ok_or_else(|| Failure::new(YsStatus::NullPointer, "The output pointer is null"))
+}
+
+/// Converts a string for C, dropping the null characters it cannot contain.
+fn c_string(string: &str) -> CString {
+    CString::new(string.replace('\0', "")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use yarnspinner_core::prelude::instruction::*;
+
+    unsafe extern "C" fn double(
+        _user_data: *mut c_void,
+        parameters: *const YsValue,
+        parameter_count: usize,
+        result: *mut YsValue,
+    ) -> bool {
+        let parameters = core::slice::from_raw_parts(parameters, parameter_count);
+        *result = YsValue {
+            kind: YsValueKind::Number as u32,
+            data: YsValueData {
+                number: parameters[0].data.number * 2.0,
+            },
+        };
+        true
+    }
+
+    fn program() -> Vec<u8> {
+        let instructions = [
+            InstructionType::PushFloat(PushFloatInstruction { value: 21.0 }),
+            InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+            InstructionType::CallFunc(CallFunctionInstruction {
+                function_name: "double".to_owned(),
+            }),
+            InstructionType::StoreVariable(StoreVariableInstruction {
+                variable_name: "$answer".to_owned(),
+            }),
+            InstructionType::Pop(PopInstruction {}),
+            InstructionType::PushString(PushStringInstruction {
+                value: "Ada".to_owned(),
+            }),
+            InstructionType::RunLine(RunLineInstruction {
+                line_id: 1,
+                substitution_count: 1,
+            }),
+            InstructionType::RunCommand(RunCommandInstruction {
+                command_text: "greet Ada 2".to_owned(),
+                substitution_count: 0,
+            }),
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id: 2,
+                destination: 11,
+                substitution_count: 0,
+                has_condition: false,
+            }),
+            InstructionType::ShowOptions(ShowOptionsInstruction {}),
+            InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+            InstructionType::Pop(PopInstruction {}),
+            InstructionType::Stop(StopInstruction {}),
+        ];
+        let node = Node {
+            name: "Start".to_owned(),
+            instructions: instructions
+                .into_iter()
+                .map(|instruction_type| Instruction {
+                    instruction_type: Some(instruction_type),
+                })
+                .collect(),
+            headers: vec![],
+        };
+        Program {
+            name: "Test".to_owned(),
+            nodes: [("Start".to_owned(), node)].into(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    unsafe fn poll_all(dialogue: *mut YsDialogue) -> Vec<YsEvent> {
+        let mut event = YsEvent {
+            kind: YsEventKind::DialogueComplete,
+            data: YsEventData { option_count: 0 },
+        };
+        let mut events = Vec::new();
+        while ys_dialogue_poll_event(dialogue, &mut event) {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn runs_a_program_through_the_c_api() {
+        unsafe {
+            let dialogue = ys_dialogue_new();
+            let program = program();
+            assert_eq!(
+                YsStatus::InvalidProgram,
+                ys_dialogue_load_program(dialogue, [0xff].as_ptr(), 1)
+            );
+            assert!(!ys_dialogue_last_error(dialogue).is_null());
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_load_program(dialogue, program.as_ptr(), program.len())
+            );
+            assert_eq!(
+                YsStatus::InvalidProgram,
+                ys_dialogue_load_program(dialogue, program.as_ptr(), program.len())
+            );
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_register_function(
+                    dialogue,
+                    c"double".as_ptr(),
+                    1,
+                    YsValueKind::Number as u32,
+                    double,
+                    core::ptr::null_mut(),
+                )
+            );
+            assert_eq!(
+                YsStatus::DialogueError,
+                ys_dialogue_set_node(dialogue, c"Missing".as_ptr())
+            );
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_set_node(dialogue, c"Start".as_ptr())
+            );
+            assert!(ys_dialogue_last_error(dialogue).is_null());
+
+            assert_eq!(YsStatus::Ok, ys_dialogue_continue(dialogue));
+            let mut event = YsEvent {
+                kind: YsEventKind::DialogueComplete,
+                data: YsEventData { option_count: 0 },
+            };
+            assert!(ys_dialogue_poll_event(dialogue, &mut event));
+            assert_eq!(YsEventKind::NodeStart, event.kind);
+            assert!(ys_dialogue_poll_event(dialogue, &mut event));
+            assert_eq!(YsEventKind::Line, event.kind);
+            let line = event.data.line;
+            assert_eq!(1, line.line_id);
+            assert_eq!(1, line.substitution_count);
+            let mut value = YsValue {
+                kind: YsValueKind::Boolean as u32,
+                data: YsValueData { boolean: 0 },
+            };
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_line_substitution(dialogue, 0, &mut value)
+            );
+            assert_eq!(YsValueKind::String as u32, value.kind);
+            assert_eq!(c"Ada", CStr::from_ptr(value.data.string));
+            assert_eq!(
+                YsStatus::InvalidArgument,
+                ys_dialogue_line_substitution(dialogue, 1, &mut value)
+            );
+            assert!(!ys_dialogue_poll_event(dialogue, &mut event));
+
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_variable(dialogue, c"$answer".as_ptr(), &mut value)
+            );
+            assert_eq!(YsValueKind::Number as u32, value.kind);
+            assert_eq!(42.0, value.data.number);
+
+            assert_eq!(YsStatus::Ok, ys_dialogue_continue(dialogue));
+            assert!(ys_dialogue_poll_event(dialogue, &mut event));
+            assert_eq!(YsEventKind::Command, event.kind);
+            let command = event.data.command;
+            assert_eq!(c"greet", CStr::from_ptr(command.name));
+            assert_eq!(2, command.parameter_count);
+            assert_eq!(
+                YsStatus::Ok,
+                ys_dialogue_command_parameter(dialogue, 1, &mut value)
+            );
+            assert_eq!(YsValueKind::Number as u32, value.kind);
+            assert_eq!(2.0, value.data.number);
+
+            assert_eq!(YsStatus::Ok, ys_dialogue_continue(dialogue));
+            assert!(ys_dialogue_poll_event(dialogue, &mut event));
+            assert_eq!(YsEventKind::Options, event.kind);
+            assert_eq!(1, event.data.option_count);
+            let mut option = YsOption {
+                id: 0,
+                line_id: 0,
+                is_available: false,
+                substitution_count: 0,
+            };
+            assert_eq!(YsStatus::Ok, ys_dialogue_option(dialogue, 0, &mut option));
+            assert_eq!(2, option.line_id);
+            assert!(option.is_available);
+            assert_eq!(
+                YsStatus::InvalidArgument,
+                ys_dialogue_option(dialogue, 1, &mut option)
+            );
+            assert!(!ys_dialogue_can_continue(dialogue));
+
+            assert_eq!(YsStatus::Ok, ys_dialogue_select_option(dialogue, option.id));
+            assert_eq!(YsStatus::Ok, ys_dialogue_continue(dialogue));
+            let kinds: Vec<_> = poll_all(dialogue).iter().map(|event| event.kind).collect();
+            assert_eq!(Some(&YsEventKind::DialogueComplete), kinds.last());
+            ys_dialogue_free(dialogue);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        unsafe {
+            let dialogue = ys_dialogue_new();
+            let program = program();
+            ys_dialogue_load_program(dialogue, program.as_ptr(), program.len());
+            assert_eq!(
+                YsStatus::InvalidArgument,
+                ys_dialogue_register_function(
+                    dialogue,
+                    c"double".as_ptr(),
+                    1,
+                    7,
+                    double,
+                    core::ptr::null_mut(),
+                )
+            );
+            let value = YsValue {
+                kind: YsValueKind::Boolean as u32,
+                data: YsValueData { boolean: 2 },
+            };
+            assert_eq!(
+                YsStatus::InvalidArgument,
+                ys_dialogue_set_variable(dialogue, c"$flag".as_ptr(), &value)
+            );
+            let value = YsValue { kind: 9, ..value };
+            assert_eq!(
+                YsStatus::InvalidArgument,
+                ys_dialogue_set_variable(dialogue, c"$flag".as_ptr(), &value)
+            );
+
+            // The program calls `double` with one parameter
+            ys_dialogue_register_function(
+                dialogue,
+                c"double".as_ptr(),
+                2,
+                YsValueKind::Number as u32,
+                double,
+                core::ptr::null_mut(),
+            );
+            ys_dialogue_set_node(dialogue, c"Start".as_ptr());
+            assert_eq!(YsStatus::DialogueError, ys_dialogue_continue(dialogue));
+            let message = CStr::from_ptr(ys_dialogue_last_error(dialogue));
+            assert!(message.to_str().unwrap().contains("expected 2 parameters"));
+            ys_dialogue_free(dialogue);
+        }
+    }
+}
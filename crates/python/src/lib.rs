@@ -0,0 +1,544 @@
+//! Python bindings for the Yarn Spinner runtime, so that writers' pipelines such as test harnesses, batch transcript generation
+//! and localization QA scripts can drive the exact runtime the game ships with.
+//!
+//! Build the `yarnspinner` Python module with `maturin build --release` in this directory. Events are dictionaries with a `type`:
+//!
+//! ```python
+//! import yarnspinner
+//!
+//! variables = yarnspinner.MemoryVariableStorage()
+//! dialogue = yarnspinner.Dialogue(variables)
+//! dialogue.load_program(open("game.yarnc", "rb").read())
+//! dialogue.add_function("roll", 1, "number", lambda sides: 4)
+//! dialogue.set_node("Start")
+//! for event in dialogue:
+//!     if event["type"] == "line":
+//!         print(event["line_id"])
+//!     elif event["type"] == "options":
+//!         dialogue.select_option(event["options"][0]["id"])
+//! print(variables.variables())
+//! ```
+
+#![warn(missing_docs, missing_debug_implementations)]
+
+use core::any::TypeId;
+use core::fmt::{self, Display};
+use core::result::Result;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString};
+use std::collections::VecDeque;
+use yarnspinner_core::prelude::*;
+use yarnspinner_runtime::prelude::*;
+
+create_exception!(
+    yarnspinner,
+    YarnSpinnerError,
+    PyException,
+    "An error returned by the Yarn Spinner runtime."
+);
+
+/// The `yarnspinner` Python module.
+#[pymodule]
+fn yarnspinner(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDialogue>()?;
+    module.add_class::<PyMemoryVariableStorage>()?;
+    module.add(
+        "YarnSpinnerError",
+        module.py().get_type::<YarnSpinnerError>(),
+    )?;
+    Ok(())
+}
+
+/// A [`Dialogue`] for Python, exported as `Dialogue`.
+///
+/// Iterating over it yields events, continuing the dialogue as needed, until it waits for an option to be selected or is complete.
+#[pyclass(name = "Dialogue", module = "yarnspinner")]
+#[derive(Debug)]
+pub struct PyDialogue {
+    dialogue: Dialogue,
+    events: VecDeque<DialogueEvent>,
+}
+
+#[pymethods]
+impl PyDialogue {
+    /// Creates a dialogue with the standard library, storing its variables in the given storage or a new one.
+    #[new]
+    #[pyo3(signature = (variable_storage = None))]
+    fn new(variable_storage: Option<PyRef<'_, PyMemoryVariableStorage>>) -> Self {
+        let variable_storage = variable_storage
+            .map(|storage| storage.0.clone())
+            .unwrap_or_default();
+        Self {
+            dialogue: Dialogue::new(Box::new(variable_storage)),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Loads a compiled program, i.e. the contents of a `.yarnc` file, adding its nodes to the ones loaded before.
+    /// Raises a `YarnSpinnerError` if the bytes are not a valid program or one of its nodes is already loaded.
+    fn load_program(&mut self, program: &[u8]) -> PyResult<()> {
+        let program = Program::from_yarnc_bytes(program).map_err(error)?;
+        if let Some(node_name) = self
+            .dialogue
+            .node_names()
+            .into_iter()
+            .flatten()
+            .find(|node_name| program.nodes.contains_key(*node_name))
+        {
+            return Err(error(format!("A node named {node_name} is already loaded")));
+        }
+        self.dialogue.add_program(program);
+        Ok(())
+    }
+
+    /// Registers a callable that Yarn scripts can call with `parameter_count` parameters.
+    /// `return_type` is `"number"`, `"string"` or `"boolean"`.
+    fn add_function(
+        &mut self,
+        name: String,
+        parameter_count: usize,
+        return_type: &str,
+        function: PyObject,
+    ) -> PyResult<()> {
+        let return_type = match return_type {
+            "number" => TypeId::of::<f32>(),
+            "string" => TypeId::of::<String>(),
+            "boolean" => TypeId::of::<bool>(),
+            _ => {
+                return Err(error(format!(
+                    "Unknown return type \"{return_type}\", expected \"number\", \"string\" or \"boolean\""
+                )))
+            }
+        };
+        let function: Box<dyn UntypedYarnFn> = Box::new(PythonFunction {
+            name: name.clone(),
+            function,
+            parameter_count,
+            return_type,
+        });
+        self.dialogue
+            .library_mut()
+            .extend([(name.into(), function)]);
+        Ok(())
+    }
+
+    /// See [`Dialogue::set_node`].
+    fn set_node(&mut self, node_name: &str) -> PyResult<()> {
+        self.events.clear();
+        self.dialogue.set_node(node_name).map_err(error)?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::continue_`]. Returns the events not yet yielded by iterating over the dialogue, followed by the new ones.
+    fn continue_(&mut self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let events = self.dialogue.continue_().map_err(error)?;
+        self.events.extend(events);
+        self.events
+            .drain(..)
+            .map(|event| event_to_python(py, &event))
+            .collect()
+    }
+
+    /// See [`Dialogue::interrupt_line`]. Returns the `line_interrupted` event, followed by the new events if `auto_advance` is `True`.
+    #[pyo3(signature = (auto_advance = false))]
+    fn interrupt_line(&mut self, py: Python<'_>, auto_advance: bool) -> PyResult<Vec<PyObject>> {
+        let events = self.dialogue.interrupt_line(auto_advance).map_err(error)?;
+        events
+            .iter()
+            .map(|event| event_to_python(py, event))
+            .collect()
+    }
+
+    /// See [`Dialogue::can_continue`].
+    #[getter]
+    fn can_continue(&self) -> bool {
+        self.dialogue.can_continue()
+    }
+
+    /// Selects the option with the given `id` after an `options` event, see [`Dialogue::set_selected_option`].
+    fn select_option(&mut self, option_id: usize) -> PyResult<()> {
+        self.dialogue
+            .set_selected_option(OptionId(option_id))
+            .map_err(error)?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::set_variable`]. The value must be a `bool`, `int`, `float` or `str`.
+    fn set_variable(&mut self, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.dialogue
+            .set_variable(name, yarn_value_from_python(value)?)
+            .map_err(error)?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::variable`].
+    fn variable(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let value = self.dialogue.variable(name).map_err(error)?;
+        yarn_value_to_python(py, &value)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return event_to_python(py, &event).map(Some);
+            }
+            if !self.dialogue.can_continue() {
+                return Ok(None);
+            }
+            let events = self.dialogue.continue_().map_err(error)?;
+            self.events.extend(events);
+        }
+    }
+}
+
+/// A [`MemoryVariableStorage`] for Python, exported as `MemoryVariableStorage`.
+/// Passing it to a `Dialogue` shares the variables, so they can be inspected while and after the dialogue runs.
+#[pyclass(name = "MemoryVariableStorage", module = "yarnspinner")]
+#[derive(Debug, Clone, Default)]
+pub struct PyMemoryVariableStorage(MemoryVariableStorage);
+
+#[pymethods]
+impl PyMemoryVariableStorage {
+    /// Creates an empty storage.
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`VariableStorage::set`].
+    fn set(&mut self, name: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.0
+            .set(name, yarn_value_from_python(value)?)
+            .map_err(error)
+    }
+
+    /// See [`VariableStorage::get`].
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let value = self.0.get(name).map_err(error)?;
+        yarn_value_to_python(py, &value)
+    }
+
+    /// Returns all variables as a `dict`.
+    fn variables<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let variables = PyDict::new(py);
+        for (name, value) in self.0.variables() {
+            variables.set_item(name, yarn_value_to_python(py, &value)?)?;
+        }
+        Ok(variables)
+    }
+
+    /// See [`VariableStorage::clear`].
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.0.contains(name)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.variables().len()
+    }
+}
+
+/// A Python callable registered with `Dialogue.add_function`.
+#[derive(Debug)]
+struct PythonFunction {
+    name: String,
+    function: PyObject,
+    parameter_count: usize,
+    return_type: TypeId,
+}
+
+impl Clone for PythonFunction {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Self {
+            name: self.name.clone(),
+            function: self.function.clone_ref(py),
+            parameter_count: self.parameter_count,
+            return_type: self.return_type,
+        })
+    }
+}
+
+impl Display for PythonFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (Python function)", self.name)
+    }
+}
+
+impl UntypedYarnFn for PythonFunction {
+    fn call(&self, input: Vec<YarnValue>) -> YarnValue {
+        self.try_call(input)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_call(&self, input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError> {
+        Python::with_gil(|py| {
+            let failed =
+                |error: PyErr| YarnFnError::Failed(format!("{} raised {error}", self.name));
+            let arguments = input
+                .iter()
+                .map(|value| yarn_value_to_python(py, value))
+                .collect::<PyResult<Vec<_>>>()
+                .map_err(failed)?;
+            let arguments = pyo3::types::PyTuple::new(py, arguments).map_err(failed)?;
+            let result = self.function.call1(py, arguments).map_err(failed)?;
+            let value = yarn_value_from_python(result.bind(py)).map_err(failed)?;
+            let actual = match value {
+                YarnValue::Number(_) | YarnValue::Integer(_) => TypeId::of::<f32>(),
+                YarnValue::String(_) => TypeId::of::<String>(),
+                YarnValue::Boolean(_) => TypeId::of::<bool>(),
+            };
+            if actual != self.return_type {
+                return Err(YarnFnError::Failed(format!(
+                    "{} returned {value}, which does not match its return type",
+                    self.name
+                )));
+            }
+            Ok(value)
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn UntypedYarnFn> {
+        Box::new(self.clone())
+    }
+
+    fn parameter_types(&self) -> Vec<TypeId> {
+        vec![TypeId::of::<YarnValue>(); self.parameter_count]
+    }
+
+    fn return_type(&self) -> TypeId {
+        self.return_type
+    }
+}
+
+fn error(error: impl Display) -> PyErr {
+    YarnSpinnerError::new_err(error.to_string())
+}
+
+fn yarn_value_to_python(py: Python<'_>, value: &YarnValue) -> PyResult<PyObject> {
+    let object = match value {
+        YarnValue::Number(number) => number.into_pyobject(py)?.into_any().unbind(),
+        YarnValue::Integer(integer) => integer.into_pyobject(py)?.into_any().unbind(),
+        YarnValue::String(string) => string.into_pyobject(py)?.into_any().unbind(),
+        YarnValue::Boolean(boolean) => boolean.into_pyobject(py)?.to_owned().into_any().unbind(),
+    };
+    Ok(object)
+}
+
+/// Converts a `bool`, `int`, `float` or `str`.
+fn yarn_value_from_python(value: &Bound<'_, PyAny>) -> PyResult<YarnValue> {
+    // `bool` is a subclass of `int`, so it must be checked first
+    if value.is_instance_of::<PyBool>() {
+        Ok(YarnValue::Boolean(value.extract()?))
+    } else if value.is_instance_of::<PyInt>() {
+        Ok(YarnValue::Integer(value.extract()?))
+    } else if value.is_instance_of::<PyFloat>() {
+        Ok(YarnValue::Number(value.extract()?))
+    } else if value.is_instance_of::<PyString>() {
+        Ok(YarnValue::String(value.extract()?))
+    } else {
+        Err(error(format!(
+            "Yarn values must be a bool, int, float or str, not {}",
+            value.get_type().name()?
+        )))
+    }
+}
+
+fn event_to_python(py: Python<'_>, event: &DialogueEvent) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    let event_type = match event {
+        DialogueEvent::Line(line_id, substitutions) => {
+            dict.set_item("line_id", line_id)?;
+            let substitutions = substitutions
+                .iter()
+                .map(|substitution| yarn_value_to_python(py, substitution))
+                .collect::<PyResult<Vec<_>>>()?;
+            dict.set_item("substitutions", substitutions)?;
+            "line"
+        }
+        DialogueEvent::LineInterrupted(line_id) => {
+            dict.set_item("line_id", line_id)?;
+            "line_interrupted"
+        }
+        DialogueEvent::LineFiltered(record) => {
+            dict.set_item("line_id", record.line_id)?;
+            dict.set_item("hashtag", record.hashtag.as_ref())?;
+            let action = match record.action {
+                LineFilterAction::Suppress => "suppress",
+                LineFilterAction::Replace(replacement) => {
+                    dict.set_item("replacement_line_id", replacement)?;
+                    "replace"
+                }
+                LineFilterAction::Annotate => "annotate",
+            };
+            dict.set_item("action", action)?;
+            "line_filtered"
+        }
+        DialogueEvent::LineHints(hints) => {
+            dict.set_item(
+                "is_last_line_before_options",
+                hints.is_last_line_before_options,
+            )?;
+            dict.set_item("is_final_line_of_node", hints.is_final_line_of_node)?;
+            dict.set_item("is_final_line_of_dialogue", hints.is_final_line_of_dialogue)?;
+            "line_hints"
+        }
+        DialogueEvent::Options(options) => {
+            let list = PyList::empty(py);
+            for option in options {
+                let option_dict = PyDict::new(py);
+                option_dict.set_item("id", option.id.0)?;
+                option_dict.set_item("line_id", option.tag_id)?;
+                option_dict.set_item("is_available", option.is_available)?;
+                let substitutions = option
+                    .substitutions
+                    .iter()
+                    .map(|substitution| yarn_value_to_python(py, substitution))
+                    .collect::<PyResult<Vec<_>>>()?;
+                option_dict.set_item("substitutions", substitutions)?;
+                list.append(option_dict)?;
+            }
+            dict.set_item("options", list)?;
+            "options"
+        }
+        DialogueEvent::Command(command) => {
+            dict.set_item("name", &command.name)?;
+            let parameters = command
+                .parameters
+                .iter()
+                .map(|parameter| yarn_value_to_python(py, parameter))
+                .collect::<PyResult<Vec<_>>>()?;
+            dict.set_item("parameters", parameters)?;
+            dict.set_item("raw", &command.raw)?;
+            "command"
+        }
+        DialogueEvent::Wait(duration) => {
+            dict.set_item("seconds", duration.as_secs_f64())?;
+            "wait"
+        }
+        DialogueEvent::NodeComplete(node_name) => {
+            dict.set_item("node_name", node_name.as_ref())?;
+            "node_complete"
+        }
+        DialogueEvent::NodeStart(node_name) => {
+            dict.set_item("node_name", node_name.as_ref())?;
+            "node_start"
+        }
+        DialogueEvent::BreakpointHit(breakpoint) => {
+            dict.set_item("node_name", breakpoint.node_name.as_ref())?;
+            match breakpoint.location {
+                BreakpointLocation::Line(line_id) => dict.set_item("line_id", line_id)?,
+                BreakpointLocation::Instruction(index) => dict.set_item("instruction", index)?,
+            }
+            "breakpoint_hit"
+        }
+        DialogueEvent::DialogueComplete => "dialogue_complete",
+    };
+    dict.set_item("type", event_type)?;
+    Ok(dict.into_any().unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use pyo3::ffi::c_str;
+    use yarnspinner_core::prelude::instruction::*;
+
+    fn program() -> Vec<u8> {
+        let instructions = [
+            InstructionType::PushFloat(PushFloatInstruction { value: 2.0 }),
+            InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+            InstructionType::CallFunc(CallFunctionInstruction {
+                function_name: "gold_for".to_owned(),
+            }),
+            InstructionType::StoreVariable(StoreVariableInstruction {
+                variable_name: "$gold".to_owned(),
+            }),
+            InstructionType::Pop(PopInstruction {}),
+            InstructionType::RunLine(RunLineInstruction {
+                line_id: 1,
+                substitution_count: 0,
+            }),
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id: 2,
+                destination: 9,
+                substitution_count: 0,
+                has_condition: false,
+            }),
+            InstructionType::ShowOptions(ShowOptionsInstruction {}),
+            InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+            InstructionType::Pop(PopInstruction {}),
+            InstructionType::Stop(StopInstruction {}),
+        ];
+        let node = Node {
+            name: "Start".to_owned(),
+            instructions: instructions
+                .into_iter()
+                .map(|instruction_type| Instruction {
+                    instruction_type: Some(instruction_type),
+                })
+                .collect(),
+            headers: vec![],
+        };
+        Program {
+            name: "Test".to_owned(),
+            nodes: [("Start".to_owned(), node)].into(),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn iterates_events_from_python() {
+        pyo3::append_to_inittab!(yarnspinner);
+        Python::with_gil(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("program", program()).unwrap();
+            py.run(
+                c_str!(
+                    r#"
+import yarnspinner
+
+variables = yarnspinner.MemoryVariableStorage()
+dialogue = yarnspinner.Dialogue(variables)
+dialogue.load_program(bytes(program))
+try:
+    dialogue.load_program(bytes(program))
+except yarnspinner.YarnSpinnerError:
+    pass
+else:
+    raise AssertionError("Expected an error")
+dialogue.add_function("gold_for", 1, "number", lambda chests: chests * 50)
+try:
+    dialogue.set_node("Missing")
+except yarnspinner.YarnSpinnerError:
+    pass
+else:
+    raise AssertionError("Expected an error")
+dialogue.set_node("Start")
+
+types = [event["type"] for event in dialogue]
+assert types == ["node_start", "line", "options"], types
+assert not dialogue.can_continue
+dialogue.select_option(0)
+assert [event["type"] for event in dialogue][-1] == "dialogue_complete"
+assert variables.get("$gold") == 100.0
+assert variables.variables() == {"$gold": 100.0}
+"#
+                ),
+                None,
+                Some(&locals),
+            )
+            .unwrap_or_else(|error| panic!("{error}"));
+        });
+    }
+}
@@ -0,0 +1,125 @@
+//! Benchmarks of the hot paths of the runtime. Run with `cargo bench -p yarnspinner_runtime --features synthetic_programs`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use yarnspinner_runtime::markup::parse_markup;
+use yarnspinner_runtime::prelude::*;
+
+/// Runs the program from its first node until the dialogue completes, continuing after every line.
+fn run_to_completion(dialogue: &mut Dialogue) {
+    dialogue.set_node(SyntheticProgram::node_name(0)).unwrap();
+    loop {
+        let events = black_box(dialogue.continue_().unwrap());
+        if events.contains(&DialogueEvent::DialogueComplete) {
+            break;
+        }
+    }
+}
+
+fn dialogue_for(shape: SyntheticProgram) -> Dialogue {
+    let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    dialogue.add_program(shape.generate());
+    dialogue
+}
+
+fn continue_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("continue_");
+    for (name, node_count, lines_per_node) in
+        [("one long node", 1, 2_000), ("many short nodes", 200, 10)]
+    {
+        let shape = SyntheticProgram {
+            node_count,
+            lines_per_node,
+            ..Default::default()
+        };
+        let mut dialogue = dialogue_for(shape);
+        group.throughput(Throughput::Elements(shape.line_count() as u64));
+        group.bench_function(name, |b| b.iter(|| run_to_completion(&mut dialogue)));
+    }
+    group.finish();
+}
+
+fn call_func_dispatch(c: &mut Criterion) {
+    let shape = SyntheticProgram {
+        lines_per_node: 100,
+        function_calls_per_line: 20,
+        variable_count: 0,
+        ..Default::default()
+    };
+    let mut dialogue = dialogue_for(shape);
+    let mut group = c.benchmark_group("CallFunc");
+    group.throughput(Throughput::Elements(
+        (shape.line_count() * shape.function_calls_per_line) as u64,
+    ));
+    group.bench_function("Number.Add", |b| {
+        b.iter(|| run_to_completion(&mut dialogue))
+    });
+    group.finish();
+}
+
+fn variable_storage_access(c: &mut Criterion) {
+    const VARIABLE_COUNT: usize = 1_000;
+    let names: Vec<_> = (0..VARIABLE_COUNT)
+        .map(SyntheticProgram::variable_name)
+        .collect();
+    let mut storage = MemoryVariableStorage::new();
+    for name in &names {
+        storage.set(name.clone(), 1.0.into()).unwrap();
+    }
+
+    let mut group = c.benchmark_group("MemoryVariableStorage");
+    group.throughput(Throughput::Elements(VARIABLE_COUNT as u64));
+    group.bench_function("get", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(storage.get(name).unwrap());
+            }
+        })
+    });
+    group.bench_function("set", |b| {
+        b.iter_batched(
+            || names.clone(),
+            |names| {
+                for name in names {
+                    storage.set(name, 2.0.into()).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("program updating every variable", |b| {
+        let shape = SyntheticProgram {
+            lines_per_node: 100,
+            function_calls_per_line: 10,
+            variable_count: VARIABLE_COUNT,
+            ..Default::default()
+        };
+        let mut dialogue = dialogue_for(shape);
+        b.iter(|| run_to_completion(&mut dialogue))
+    });
+    group.finish();
+}
+
+fn markup_parsing(c: &mut Criterion) {
+    let plain = "The quick brown fox jumps over the lazy dog. ".repeat(40);
+    let marked_up = format!(
+        "Narrator: {}",
+        (0..100)
+            .map(|i| format!("[wave size={i}]Wavy text {i}[/wave] [b]bold[/b] {{$value}} "))
+            .collect::<String>()
+    );
+    let mut group = c.benchmark_group("parse_markup");
+    for (name, line) in [("plain", &plain), ("marked up", &marked_up)] {
+        group.throughput(Throughput::Bytes(line.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse_markup(black_box(line)).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    continue_throughput,
+    call_func_dispatch,
+    variable_storage_access,
+    markup_parsing
+);
+criterion_main!(benches);
@@ -0,0 +1,316 @@
+//! Not part of the original implementation.
+//!
+//! The integration surface for engine adapters: instead of wiring up text lookup, command handling, observation and time
+//! through separate setters, an adapter implements [`RuntimeAdapter`] once and hands it to a [`DialogueRunner`] or [`DialogueBuilder`].
+
+use crate::prelude::*;
+use crate::Result;
+use core::fmt::Debug;
+use core::time::Duration;
+
+/// A monotonic clock used by [`DialogueRunner::update`] to advance pending [`DialogueEvent::Wait`]s.
+pub trait DialogueClock: Debug + Send + Sync {
+    /// The time elapsed since an arbitrary but fixed point, e.g. the start of the game. Must never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// A [`DialogueClock`] backed by [`std::time::Instant`], starting at zero when created.
+/// Not available on `wasm32-unknown-unknown`, which has no clock, see `JsClock` of the `wasm-bindgen` feature instead.
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock(std::time::Instant);
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self(std::time::Instant::now())
+    }
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+impl DialogueClock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// A source of randomness, e.g. the engine's seeded RNG so that dialogue choices are reproducible in replays.
+pub trait DialogueRng: Debug + Send + Sync {
+    /// Returns the next random number.
+    fn next_u64(&mut self) -> u64;
+}
+
+impl DialogueRng for SimulationRng {
+    fn next_u64(&mut self) -> u64 {
+        SimulationRng::next_u64(self)
+    }
+}
+
+/// Executes [`Command`]s on behalf of a [`DialogueRunner`].
+/// Like a [`DialogueObserver`], it is borrowed immutably, so keep state that changes behind a lock.
+pub trait CommandDispatcher: Debug + Send + Sync {
+    /// Executes the command and returns `true` if it was handled.
+    /// Commands that were not handled are passed on to the handlers of the [`DialogueRunner`].
+    fn dispatch(&self, command: &Command, context: &mut DialogueRunnerContext<'_>) -> bool;
+}
+
+/// Everything an engine adapter provides to run dialogue, bundled into one trait, passed to [`DialogueRunner::with_adapter`]
+/// or [`DialogueBuilder::with_adapter`].
+///
+/// Every part is optional, so implement only the methods for the parts the engine provides.
+///
+/// | Part | Used for |
+/// |---|---|
+/// | [`RuntimeAdapter::text_provider`] | Looking up the text of lines via [`DialogueRunnerContext::text`] |
+/// | [`RuntimeAdapter::asset_provider`] | Looking up the assets of lines, e.g. voice-over, via [`DialogueRunnerContext::assets`] |
+/// | [`RuntimeAdapter::command_dispatcher`] | Executing [`DialogueEvent::Command`]s instead of the handlers |
+/// | [`RuntimeAdapter::observer`] | Watching every event before it is passed to the handlers |
+/// | [`RuntimeAdapter::clock`] | Advancing [`DialogueEvent::Wait`]s via [`DialogueRunner::update`] |
+///
+/// Randomness is owned by the features that pick between content, see [`DialogueBuilder::with_rng`] and [`OptionsPresentationPolicy::with_shuffle`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// #[derive(Debug, Default)]
+/// struct MyEngineAdapter {
+///     text_provider: StringTableTextProvider,
+///     clock: SystemClock,
+/// }
+///
+/// impl RuntimeAdapter for MyEngineAdapter {
+///     fn text_provider(&self) -> Option<&dyn TextProvider> {
+///         Some(&self.text_provider)
+///     }
+///
+///     fn clock(&self) -> Option<&dyn DialogueClock> {
+///         Some(&self.clock)
+///     }
+/// }
+///
+/// let mut runner = DialogueRunner::new(Dialogue::new(Box::new(MemoryVariableStorage::new())))
+///     .with_adapter(MyEngineAdapter::default());
+/// // Once per frame:
+/// runner.update().unwrap();
+/// ```
+pub trait RuntimeAdapter: Debug + Send + Sync {
+    /// The provider for the text of lines and options.
+    fn text_provider(&self) -> Option<&dyn TextProvider> {
+        None
+    }
+
+    /// The provider for the assets of lines, such as voice-over clips.
+    fn asset_provider(&self) -> Option<&dyn AssetProvider> {
+        None
+    }
+
+    /// The dispatcher that executes commands.
+    fn command_dispatcher(&self) -> Option<&dyn CommandDispatcher> {
+        None
+    }
+
+    /// An observer that is notified of every event.
+    fn observer(&self) -> Option<&dyn DialogueObserver> {
+        None
+    }
+
+    /// The clock that drives [`DialogueRunner::update`].
+    fn clock(&self) -> Option<&dyn DialogueClock> {
+        None
+    }
+}
+
+impl DialogueRunner {
+    /// Sets the [`RuntimeAdapter`] that provides the engine integration.
+    #[must_use]
+    pub fn with_adapter(mut self, adapter: impl RuntimeAdapter + 'static) -> Self {
+        self.set_adapter(adapter);
+        self
+    }
+
+    /// Sets the [`RuntimeAdapter`] that provides the engine integration, replacing the previous one.
+    pub fn set_adapter(&mut self, adapter: impl RuntimeAdapter + 'static) -> &mut Self {
+        self.adapter = Some(Box::new(adapter));
+        self.last_update = None;
+        self
+    }
+
+    /// The [`RuntimeAdapter`], if one was set.
+    pub fn adapter(&self) -> Option<&dyn RuntimeAdapter> {
+        self.adapter.as_deref()
+    }
+
+    /// See [`DialogueRunner::adapter`].
+    pub fn adapter_mut(&mut self) -> Option<&mut (dyn RuntimeAdapter + 'static)> {
+        self.adapter.as_deref_mut()
+    }
+
+    /// Advances a pending [`DialogueEvent::Wait`] by the time that passed on the adapter's [`DialogueClock`] since the last call,
+    /// see [`DialogueRunner::tick`]. Call this once per frame.
+    /// Does nothing on the first call or if there is no adapter or clock.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error of the [`Dialogue`]. Pending requests are discarded in that case.
+    pub fn update(&mut self) -> Result<()> {
+        let Some(now) = self
+            .adapter
+            .as_deref()
+            .and_then(RuntimeAdapter::clock)
+            .map(DialogueClock::now)
+        else {
+            return Ok(());
+        };
+        let Some(last_update) = self.last_update.replace(now) else {
+            return Ok(());
+        };
+        self.tick(now.saturating_sub(last_update))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use crate::sync::Mutex;
+    use alloc::sync::Arc;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[derive(Debug, Default)]
+    struct ManualClock(Mutex<Duration>);
+
+    impl DialogueClock for ManualClock {
+        fn now(&self) -> Duration {
+            *self.0.lock()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct FadeDispatcher(Arc<Mutex<Vec<String>>>);
+
+    impl CommandDispatcher for FadeDispatcher {
+        fn dispatch(&self, command: &Command, context: &mut DialogueRunnerContext<'_>) -> bool {
+            if command.name != "fade" {
+                return false;
+            }
+            let line_id = LineId::from("line:1");
+            self.0.lock().push(format!(
+                "{} before {}",
+                command.raw,
+                context.text(&line_id).unwrap()
+            ));
+            context.continue_();
+            true
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct TestAdapter {
+        clock: Arc<ManualClock>,
+        dispatcher: FadeDispatcher,
+        text_provider: StringTableTextProvider,
+        asset_provider: MemoryAssetProvider,
+    }
+
+    impl RuntimeAdapter for TestAdapter {
+        fn text_provider(&self) -> Option<&dyn TextProvider> {
+            Some(&self.text_provider)
+        }
+
+        fn asset_provider(&self) -> Option<&dyn AssetProvider> {
+            Some(&self.asset_provider)
+        }
+
+        fn command_dispatcher(&self) -> Option<&dyn CommandDispatcher> {
+            Some(&self.dispatcher)
+        }
+
+        fn clock(&self) -> Option<&dyn DialogueClock> {
+            Some(self.clock.as_ref())
+        }
+    }
+
+    #[test]
+    fn runs_dialogue_through_adapter() {
+        let run_command = |text: &str| {
+            InstructionType::RunCommand(RunCommandInstruction {
+                command_text: text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.set_wait_command_handling(true);
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_command("fade out"),
+                run_command("wait 1"),
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 0,
+                }),
+            ],
+        ));
+        let clock = Arc::new(ManualClock::default());
+        let fades = Arc::new(Mutex::new(Vec::new()));
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([(LineId::from("line:1"), "Hello".to_owned())]);
+        text_provider.extend_translation("de", [(LineId::from("line:1"), "Hallo".to_owned())]);
+        text_provider.set_language(Some("de".into()));
+        let mut asset_provider = MemoryAssetProvider::new();
+        asset_provider.insert("en", "line:1", AssetHandle::new("voice", "en/1.ogg"));
+        asset_provider.insert("de", "line:1", AssetHandle::new("voice", "de/1.ogg"));
+        let texts = Arc::new(Mutex::new(Vec::new()));
+        let handler_texts = texts.clone();
+        let mut runner = DialogueRunner::new(dialogue)
+            .with_adapter(TestAdapter {
+                clock: clock.clone(),
+                dispatcher: FadeDispatcher(fades.clone()),
+                text_provider,
+                asset_provider,
+            })
+            .with_handler(move |event, context| match event {
+                DialogueEvent::Line(line_id, ..) => {
+                    let line_id = LineId::from(format!("line:{line_id}"));
+                    handler_texts
+                        .lock()
+                        .push((context.text(&line_id), context.assets(&line_id)));
+                }
+                DialogueEvent::Command(command) => {
+                    assert_ne!("fade", command.name, "handled by the dispatcher");
+                }
+                _ => {}
+            });
+
+        runner.start("Start").unwrap();
+        assert_eq!(vec!["fade out before Hallo".to_owned()], *fades.lock());
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            runner.dialogue().remaining_wait()
+        );
+
+        runner.update().unwrap();
+        *clock.0.lock() = Duration::from_millis(600);
+        runner.update().unwrap();
+        assert!(texts.lock().is_empty());
+        *clock.0.lock() = Duration::from_millis(1200);
+        runner.update().unwrap();
+        assert_eq!(
+            vec![(
+                Some("Hallo".to_owned()),
+                vec![AssetHandle::new("voice", "de/1.ogg")]
+            )],
+            *texts.lock()
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Not part of the original implementation, but inspired by the voice-over support of Yarn Spinner for Unity.
+//!
+//! Resolves the assets of lines, such as voice-over clips and lip sync data, per language.
+
+use crate::prelude::*;
+use core::fmt::Debug;
+use hashbrown::HashMap;
+
+/// An opaque reference to an asset of a line, e.g. the file name of a voice-over clip, resolved by an [`AssetProvider`].
+/// The runtime never interprets it, so engines can use whatever identifies assets in their asset pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AssetHandle {
+    /// What the asset is used for, e.g. `voice` or `lipsync`, to tell several assets of the same line apart.
+    pub kind: String,
+    /// The engine-specific identifier of the asset, e.g. `VO/de/Start-1.ogg`.
+    pub id: String,
+}
+
+impl AssetHandle {
+    /// Creates a handle of the given kind.
+    pub fn new(kind: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+        }
+    }
+}
+
+/// Looks up the assets of lines, e.g. voice-over clips, in a given language.
+/// Engine adapters provide one via [`RuntimeAdapter::asset_provider`], so handlers of a [`DialogueRunner`] can
+/// resolve the assets of a [`DialogueEvent::Line`] alongside its text via [`DialogueRunnerContext::assets`].
+pub trait AssetProvider: Debug + Send + Sync {
+    /// Returns the assets of the given line in the given language, or an empty list if it has none.
+    fn get_assets(&self, line_id: &LineId, language: &Language) -> Vec<AssetHandle>;
+}
+
+/// An [`AssetProvider`] that holds the assets of all languages in memory.
+///
+/// Assets are looked up along the [`Language::fallback_chain`], so e.g. `de-AT` uses the assets registered for `de`
+/// unless there are assets for `de-AT` itself. Lines without assets in any language of the chain fall back to the base language, if set,
+/// which lets untranslated voice-over play in the original language.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut assets = MemoryAssetProvider::new().with_base_language("en");
+/// assets.insert("en", "line:1", AssetHandle::new("voice", "VO/en/Start-1.ogg"));
+/// assets.insert("de", "line:1", AssetHandle::new("voice", "VO/de/Start-1.ogg"));
+/// assets.insert("en", "line:2", AssetHandle::new("voice", "VO/en/Start-2.ogg"));
+///
+/// let german = Language::new("de-AT");
+/// assert_eq!("VO/de/Start-1.ogg", assets.get_assets(&"line:1".into(), &german)[0].id);
+/// assert_eq!("VO/en/Start-2.ogg", assets.get_assets(&"line:2".into(), &german)[0].id);
+/// assert!(assets.get_assets(&"line:3".into(), &german).is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryAssetProvider {
+    base_language: Option<Language>,
+    assets: HashMap<Language, HashMap<LineId, Vec<AssetHandle>>>,
+}
+
+impl MemoryAssetProvider {
+    /// Creates a provider without any assets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the language whose assets are used for lines that have none in the requested language.
+    #[must_use]
+    pub fn with_base_language(mut self, language: impl Into<Language>) -> Self {
+        self.base_language = Some(language.into());
+        self
+    }
+
+    /// Adds an asset to a line in the given language. A line may have any number of assets.
+    pub fn insert(
+        &mut self,
+        language: impl Into<Language>,
+        line_id: impl Into<LineId>,
+        asset: AssetHandle,
+    ) -> &mut Self {
+        self.assets
+            .entry(language.into())
+            .or_default()
+            .entry(line_id.into())
+            .or_default()
+            .push(asset);
+        self
+    }
+
+    /// Removes all assets of the given language.
+    pub fn remove_language(&mut self, language: &Language) -> &mut Self {
+        self.assets.remove(language);
+        self
+    }
+}
+
+impl AssetProvider for MemoryAssetProvider {
+    fn get_assets(&self, line_id: &LineId, language: &Language) -> Vec<AssetHandle> {
+        language
+            .fallback_chain()
+            .iter()
+            .chain(&self.base_language)
+            .find_map(|language| self.assets.get(language)?.get(line_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,294 @@
+//! Not part of the original implementation.
+//!
+//! Generates Rust constants for the names used in a compiled Yarn program from a build script,
+//! so that a typo in a node or variable name becomes a compile error in the game instead of a runtime error.
+
+use crate::prelude::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::fmt::Write;
+use yarnspinner_core::prelude::instruction::{
+    DetourToNodeInstruction, InstructionType, PushVariableInstruction, RunNodeInstruction,
+    StoreVariableInstruction,
+};
+
+/// Generates a Rust source file with constants for the node names, variable names and line IDs of one or more programs.
+///
+/// The generated file contains the modules `nodes`, `vars` and `lines`. Each holds one `&str` constant per name
+/// in `SCREAMING_SNAKE_CASE`, e.g. `nodes::SHOP_INTRO` for the node `ShopIntro`, `vars::GOLD` for `$gold` and `lines::GREETING` for `line:greeting`,
+/// plus an `ALL` slice containing every name of the module. Names that map to the same identifier are numbered, e.g. `GOLD` and `GOLD_2`.
+/// Internal names, i.e. the ones starting with `$Yarn.Internal`, are skipped.
+///
+/// Since compiled programs only refer to lines by number, line IDs have to be passed separately, e.g. from the string table.
+///
+/// ## Example
+///
+/// In the game's `build.rs`, after decoding the compiled program:
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # let mut program = Program::default();
+/// # program.nodes.insert("ShopIntro".to_owned(), Node { name: "ShopIntro".to_owned(), ..Default::default() });
+/// # program.initial_values.insert("$gold".to_owned(), 0.0.into());
+/// let source = DialogueBindings::from_program(&program)
+///     .with_line_ids(["line:greeting"])
+///     .generate();
+/// assert!(source.contains(r#"pub const SHOP_INTRO: &str = "ShopIntro";"#));
+/// assert!(source.contains(r#"pub const GOLD: &str = "$gold";"#));
+/// assert!(source.contains(r#"pub const GREETING: &str = "line:greeting";"#));
+///
+/// // DialogueBindings::from_program(&program).write_to_out_dir("yarn_bindings.rs").unwrap();
+/// // Then, in the game: include!(concat!(env!("OUT_DIR"), "/yarn_bindings.rs"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DialogueBindings {
+    node_names: BTreeSet<String>,
+    variable_names: BTreeSet<String>,
+    line_ids: BTreeSet<String>,
+}
+
+impl DialogueBindings {
+    const INTERNAL_PREFIX: &'static str = "$Yarn.Internal";
+
+    /// Creates empty bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates bindings for the node and variable names of the given program.
+    pub fn from_program(program: &Program) -> Self {
+        let mut bindings = Self::new();
+        bindings.add_program(program);
+        bindings
+    }
+
+    /// Adds the node and variable names of the given program.
+    #[must_use]
+    pub fn with_program(mut self, program: &Program) -> Self {
+        self.add_program(program);
+        self
+    }
+
+    /// Adds the node and variable names of the given program.
+    ///
+    /// Variables are collected from the program's initial values and from every instruction that reads or stores one.
+    /// Nodes are collected from the program itself and from every instruction that jumps or detours to one by name.
+    pub fn add_program(&mut self, program: &Program) -> &mut Self {
+        self.node_names.extend(program.nodes.keys().cloned());
+        self.variable_names
+            .extend(program.initial_values.keys().cloned());
+        let instruction_types = program
+            .nodes
+            .values()
+            .flat_map(|node| &node.instructions)
+            .filter_map(|instruction| instruction.instruction_type.as_ref());
+        for instruction_type in instruction_types {
+            match instruction_type {
+                InstructionType::PushVariable(PushVariableInstruction { variable_name })
+                | InstructionType::StoreVariable(StoreVariableInstruction { variable_name }) => {
+                    self.variable_names.insert(variable_name.clone());
+                }
+                InstructionType::RunNode(RunNodeInstruction { node_name })
+                | InstructionType::DetourToNode(DetourToNodeInstruction { node_name }) => {
+                    self.node_names.insert(node_name.clone());
+                }
+                _ => {}
+            }
+        }
+        self.node_names
+            .retain(|name| !name.starts_with(Self::INTERNAL_PREFIX));
+        self.variable_names
+            .retain(|name| !name.starts_with(Self::INTERNAL_PREFIX));
+        self
+    }
+
+    /// Adds the given line IDs, e.g. the keys of the string table.
+    #[must_use]
+    pub fn with_line_ids(mut self, line_ids: impl IntoIterator<Item = impl Into<LineId>>) -> Self {
+        self.add_line_ids(line_ids);
+        self
+    }
+
+    /// Adds the given line IDs, e.g. the keys of the string table.
+    pub fn add_line_ids(
+        &mut self,
+        line_ids: impl IntoIterator<Item = impl Into<LineId>>,
+    ) -> &mut Self {
+        self.line_ids
+            .extend(line_ids.into_iter().map(|line_id| line_id.into().0));
+        self
+    }
+
+    /// The collected node names, in sorted order.
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.node_names.iter().map(String::as_str)
+    }
+
+    /// The collected variable names including the leading `$`, in sorted order.
+    pub fn variable_names(&self) -> impl Iterator<Item = &str> {
+        self.variable_names.iter().map(String::as_str)
+    }
+
+    /// The collected line IDs, in sorted order.
+    pub fn line_ids(&self) -> impl Iterator<Item = &str> {
+        self.line_ids.iter().map(String::as_str)
+    }
+
+    /// Generates the Rust source described in [`DialogueBindings`].
+    pub fn generate(&self) -> String {
+        let mut source = String::from(
+            "// Generated by yarnspinner's `DialogueBindings`. Do not edit this file manually.\n",
+        );
+        write_module(&mut source, "nodes", "Node names.", &self.node_names, "");
+        write_module(
+            &mut source,
+            "vars",
+            "Variable names, including the leading `$`.",
+            &self.variable_names,
+            "$",
+        );
+        write_module(&mut source, "lines", "Line IDs.", &self.line_ids, "line:");
+        source
+    }
+
+    /// Writes the generated source to the given file in the `OUT_DIR` of the build script and returns its path.
+    /// Include it in the game via `include!(concat!(env!("OUT_DIR"), "/<file_name>"))`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if `OUT_DIR` is not set, i.e. when not called from a build script, or if the file could not be written.
+    #[cfg(feature = "std")]
+    pub fn write_to_out_dir(
+        &self,
+        file_name: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<std::path::PathBuf> {
+        let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "OUT_DIR is not set. DialogueBindings::write_to_out_dir must be called from a build script.",
+            )
+        })?;
+        let path = std::path::Path::new(&out_dir).join(file_name);
+        std::fs::write(&path, self.generate())?;
+        Ok(path)
+    }
+}
+
+fn write_module(
+    source: &mut String,
+    module: &str,
+    doc: &str,
+    names: &BTreeSet<String>,
+    strip_prefix: &str,
+) {
+    let mut identifiers = BTreeMap::new();
+    // Reserved for the list of all names
+    identifiers.insert("ALL".to_owned(), None);
+    for name in names {
+        let base = to_identifier(name.strip_prefix(strip_prefix).unwrap_or(name));
+        let mut identifier = base.clone();
+        let mut suffix = 2;
+        while identifiers.contains_key(&identifier) {
+            identifier = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        identifiers.insert(identifier, Some(name));
+    }
+
+    // Writing to a `String` cannot fail
+    let _ = writeln!(
+        source,
+        "\n/// {doc}\n#[allow(dead_code)]\npub mod {module} {{"
+    );
+    for (identifier, name) in &identifiers {
+        if let Some(name) = name {
+            let _ = writeln!(source, "    pub const {identifier}: &str = {name:?};");
+        }
+    }
+    let _ = writeln!(source, "    /// Every name of this module.");
+    let all: Vec<_> = names.iter().map(|name| format!("{name:?}")).collect();
+    let _ = writeln!(
+        source,
+        "    pub const ALL: &[&str] = &[{}];\n}}",
+        all.join(", ")
+    );
+}
+
+/// Converts a name such as `ShopIntro`, `shop_intro` or `Shop.Intro` into `SHOP_INTRO`.
+fn to_identifier(name: &str) -> String {
+    let mut identifier = String::with_capacity(name.len());
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            let starts_word = c.is_ascii_uppercase()
+                && previous.is_some_and(|previous| {
+                    previous.is_ascii_lowercase() || previous.is_ascii_digit()
+                });
+            if starts_word && !identifier.ends_with('_') {
+                identifier.push('_');
+            }
+            identifier.push(c.to_ascii_uppercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+        previous = Some(c);
+    }
+    while identifier.ends_with('_') {
+        identifier.pop();
+    }
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+
+    #[test]
+    fn generates_unique_identifiers() {
+        let mut program = program_with_instructions(
+            "ShopIntro",
+            [
+                InstructionType::PushVariable(PushVariableInstruction {
+                    variable_name: "$gold".to_owned(),
+                }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$Yarn.Internal.Visiting.ShopIntro".to_owned(),
+                }),
+                InstructionType::RunNode(RunNodeInstruction {
+                    node_name: "shop_intro".to_owned(),
+                }),
+            ],
+        );
+        program
+            .initial_values
+            .insert("$Gold".to_owned(), 0.0.into());
+        let source = DialogueBindings::from_program(&program)
+            .with_line_ids(["line:1a", "line:all"])
+            .generate();
+
+        for expected in [
+            r#"pub const SHOP_INTRO: &str = "ShopIntro";"#,
+            r#"pub const SHOP_INTRO_2: &str = "shop_intro";"#,
+            r#"pub const ALL: &[&str] = &["ShopIntro", "shop_intro"];"#,
+            r#"pub const GOLD: &str = "$Gold";"#,
+            r#"pub const GOLD_2: &str = "$gold";"#,
+            r#"pub const _1A: &str = "line:1a";"#,
+            r#"pub const ALL_2: &str = "line:all";"#,
+        ] {
+            assert!(source.contains(expected), "{expected} not in {source}");
+        }
+        assert!(!source.contains("Yarn.Internal"));
+    }
+
+    #[test]
+    fn converts_names_to_identifiers() {
+        assert_eq!("SHOP_INTRO", to_identifier("ShopIntro"));
+        assert_eq!("SHOP_INTRO", to_identifier("Shop.Intro"));
+        assert_eq!("SHOP_INTRO2_FINAL", to_identifier("shop--intro2Final"));
+        assert_eq!("_", to_identifier("$"));
+    }
+}
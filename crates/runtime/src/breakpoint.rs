@@ -0,0 +1,73 @@
+//! Not part of the original implementation.
+//!
+//! Breakpoints pause a [`Dialogue`] at a given point of a node, e.g. for an in-editor dialogue debugger.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt::{self, Display};
+
+/// Where in a node a [`Breakpoint`] pauses the dialogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(C, u8)]
+pub enum BreakpointLocation {
+    /// Right before the line with the given ID, as in [`DialogueEvent::Line`], is run.
+    Line(u32),
+    /// Right before the instruction with the given index within the node is run.
+    Instruction(usize),
+}
+
+/// A point in a node at which the [`Dialogue`] pauses, set via [`Dialogue::set_breakpoint`].
+///
+/// When a breakpoint is reached, the dialogue sends a [`DialogueEvent::BreakpointHit`] and stops executing before the instruction at the breakpoint.
+/// [`Dialogue::is_paused`] then returns `true` until [`Dialogue::continue_`] resumes execution, which starts at that instruction without hitting the breakpoint again.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Breakpoint {
+    /// The node the breakpoint is in.
+    pub node_name: Arc<str>,
+    /// Where in the node the breakpoint is.
+    pub location: BreakpointLocation,
+}
+
+impl Breakpoint {
+    /// Creates a breakpoint in the given node.
+    pub fn new(node_name: impl AsRef<str>, location: BreakpointLocation) -> Self {
+        Self {
+            node_name: node_name.as_ref().into(),
+            location,
+        }
+    }
+
+    /// Returns `true` if this breakpoint is at the given instruction of the given node.
+    pub(crate) fn matches(
+        &self,
+        node_name: &str,
+        program_counter: usize,
+        instruction: &LinkedInstruction,
+    ) -> bool {
+        if *self.node_name != *node_name {
+            return false;
+        }
+        match self.location {
+            BreakpointLocation::Line(line_id) => matches!(
+                instruction,
+                LinkedInstruction::RunLine { line_id: id, .. } if *id == line_id
+            ),
+            BreakpointLocation::Instruction(index) => index == program_counter,
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            BreakpointLocation::Line(line_id) => write!(f, "{}, line {line_id}", self.node_name),
+            BreakpointLocation::Instruction(index) => {
+                write!(f, "{}, instruction {index}", self.node_name)
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Not part of the original implementation.
+//!
+//! With the `heapless` feature, the operand stack, the options being collected and the events of a single
+//! [`Dialogue::continue_`] live in fixed-capacity buffers that never reallocate, so their memory use is known up front.
+//! Overflowing one fails with [`DialogueError::CapacityExceeded`] instead of growing.
+
+use crate::prelude::*;
+use core::fmt::{self, Display};
+use core::ops::{Deref, DerefMut};
+
+/// The maximum number of values on the operand stack with the `heapless` feature.
+/// Expressions compiled from Yarn rarely need more than a handful.
+pub const STACK_CAPACITY: usize = 64;
+
+/// The maximum number of options collected before they are shown with the `heapless` feature.
+pub const OPTIONS_CAPACITY: usize = 16;
+
+/// The maximum number of events returned by a single [`Dialogue::continue_`] with the `heapless` feature.
+pub const EVENT_BATCH_CAPACITY: usize = 64;
+
+/// A buffer of the runtime that has a fixed capacity with the `heapless` feature, see [`DialogueError::CapacityExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CapacityBuffer {
+    /// The operand stack, holding at most [`STACK_CAPACITY`] values.
+    Stack,
+    /// The options added before they are shown, holding at most [`OPTIONS_CAPACITY`] options.
+    Options,
+    /// The events of a single [`Dialogue::continue_`], holding at most [`EVENT_BATCH_CAPACITY`] events.
+    Events,
+}
+
+impl CapacityBuffer {
+    /// The number of elements the buffer can hold with the `heapless` feature.
+    pub fn capacity(&self) -> usize {
+        match self {
+            Self::Stack => STACK_CAPACITY,
+            Self::Options => OPTIONS_CAPACITY,
+            Self::Events => EVENT_BATCH_CAPACITY,
+        }
+    }
+}
+
+impl Display for CapacityBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Stack => "operand stack",
+            Self::Options => "option buffer",
+            Self::Events => "event batch",
+        })
+    }
+}
+
+/// A [`Vec`] that holds at most `N` elements with the `heapless` feature and grows as usual without it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub(crate) struct BoundedVec<T, const N: usize> {
+    #[cfg(feature = "heapless")]
+    items: heapless::Vec<T, N>,
+    #[cfg(not(feature = "heapless"))]
+    items: Vec<T>,
+}
+
+impl<T, const N: usize> Default for BoundedVec<T, N> {
+    fn default() -> Self {
+        Self {
+            items: Default::default(),
+        }
+    }
+}
+
+impl<T, const N: usize> BoundedVec<T, N> {
+    /// Appends an element, handing it back if the buffer is full.
+    pub(crate) fn try_push(&mut self, item: T) -> core::result::Result<(), T> {
+        #[cfg(feature = "heapless")]
+        return self.items.push(item);
+        #[cfg(not(feature = "heapless"))]
+        {
+            self.items.push(item);
+            Ok(())
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.items.truncate(len)
+    }
+
+    pub(crate) fn retain(&mut self, predicate: impl FnMut(&T) -> bool) {
+        self.items.retain(predicate)
+    }
+
+    /// Moves all elements out, leaving the buffer empty.
+    pub(crate) fn take(&mut self) -> Vec<T> {
+        core::mem::take(&mut self.items).into_iter().collect()
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T, const N: usize> DerefMut for BoundedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
+
+#[cfg(all(test, feature = "heapless"))]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn fails_gracefully_when_a_buffer_is_full() {
+        let option = InstructionType::AddOption(AddOptionInstruction {
+            tag_id: 1,
+            destination: 0,
+            substitution_count: 0,
+            has_condition: false,
+        });
+        let run = |instructions: Vec<InstructionType>| {
+            let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+            dialogue
+                .add_program(program_with_instructions("Start", instructions))
+                .set_node("Start")
+                .unwrap();
+            dialogue.continue_()
+        };
+
+        let options = vec![option; OPTIONS_CAPACITY + 1];
+        assert!(matches!(
+            run(options),
+            Err(DialogueError::CapacityExceeded {
+                buffer: CapacityBuffer::Options
+            })
+        ));
+
+        let pushes = vec![
+            InstructionType::PushBool(PushBoolInstruction { value: true });
+            STACK_CAPACITY + 1
+        ];
+        let error = run(pushes).unwrap_err();
+        assert_eq!(DialogueErrorCode::CapacityExceeded, error.code());
+        assert_eq!(
+            "The operand stack is full, it can hold at most 64 elements.",
+            error.to_string()
+        );
+    }
+}
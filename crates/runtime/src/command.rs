@@ -5,10 +5,15 @@
 
 use crate::markup::normalize;
 use crate::prelude::*;
+use core::time::Duration;
+
+/// The name of the command that pauses the dialogue, e.g. `<<wait 1.5>>`. See [`DialogueEvent::Wait`].
+pub const WAIT_COMMAND: &str = "wait";
 
 /// A custom command found in a Yarn file within the `<<` and `>>` characters.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Command {
     /// The command name, i.e. the first identifier that was passed in the command.
     /// For example, in the command `<<set_sprite ship "happy">>`, the command name is `set_sprite`.
@@ -16,46 +21,112 @@ pub struct Command {
 
     /// The parameters passed to the command. Strings that are surrounded by quotes are passed as a single parameter.
     ///
+    /// Parameters are typed as follows:
+    /// - Quoted parameters are always [`YarnValue::String`]s. Inside of quotes, `\\` is converted to `\`, `\"` to `"`, `\n` to a line break and `\t` to a tab.
+    /// - Unquoted `true` and `false`, ignoring case, are [`YarnValue::Boolean`]s.
+    /// - Unquoted numbers like `12`, `-0.5` or `1e3` are [`YarnValue::Number`]s. Numbers are always written with a `.` as decimal separator,
+    ///   independent of the locale, which is also how numbers are formatted when they are substituted into a command, e.g. in `<<wait {$delay}>>`.
+    /// - Everything else is a [`YarnValue::String`].
+    ///
     /// ## Examples
     ///
     /// - The command `<<set_sprite ship "happy">>` has the parameters `["ship", "happy"]`.
     /// - The command `<<set_sprite ship "very happy">>`, the parameters are `["ship", "very happy"]`.
+    /// - The command `<<wait 1.5 true "2">>` has the parameters `[1.5, true, "2"]`.
     ///
     /// ## Return value
     ///
-    /// The parameters are returned without underlying type information, so you will have to convert them using `YarnValue::try_into`.
+    /// Use `YarnValue::try_into` to convert the parameters. Conversions between the types work as well,
+    /// so a [`YarnValue::Number`] can still be converted into a `String`.
     pub parameters: Vec<YarnValue>,
 
-    /// The raw, unprocessed command as it appeared in the Yarn file between the `<<` and `>>` characters.
+    /// The raw, unprocessed command as it appeared in the Yarn file between the `<<` and `>>` characters,
+    /// after substituting inline expressions. Use this to parse parameters yourself.
     pub raw: String,
+
+    /// The token to mark this command as complete with, if [`Dialogue::set_command_completion`] is enabled and the command is not
+    /// one of the [`Dialogue::non_blocking_commands`]. The dialogue cannot continue until the token is complete.
+    ///
+    /// The token is neither serialized nor sent by an [`EventEncoder`], so it is always `None` for received commands.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub completion_token: Option<CommandCompletionToken>,
 }
 
 impl Command {
-    pub(crate) fn parse(input: String) -> Self {
-        assert!(!input.trim().is_empty(), "Failed to parse the command \"{input}\" because it is composed entirely of whitespace. \
-            Help: You might have passed an expression that evaluates to whitespace, e.g. `{{0}} {{\"  \"}}`. \
-            If you think this is a bug, please report it at https://github.com/YarnSpinnerTool/YarnSpinner-Rust/issues/new");
-
-        let mut components = split_command_text(&input);
-        assert!(
-            !components.is_empty(),
-            "Parsing the command \"{}\" resulted in an empty list of components. \
-            This is a bug. Please report it at https://github.com/YarnSpinnerTool/YarnSpinner-Rust/issues/new",
-            input
-        );
-        let name = components.remove(0);
-        let parameters = components.into_iter().map(YarnValue::from).collect();
-        Self {
+    /// Parses the text of a command, or returns `None` if it is composed entirely of whitespace,
+    /// e.g. because it consists of an expression that evaluates to whitespace like `<<{$command}>>`.
+    pub(crate) fn parse(input: String) -> Option<Self> {
+        let mut tokens = tokenize_command_text(&input);
+        if tokens.is_empty() {
+            return None;
+        }
+        let name = tokens.remove(0).text;
+        let parameters = tokens.into_iter().map(CommandToken::into_value).collect();
+        Some(Self {
             name,
             parameters,
             raw: input,
+            completion_token: None,
+        })
+    }
+
+    /// Returns the duration of a `<<wait seconds>>` command, or `None` if this is a different command or the duration is invalid.
+    pub(crate) fn wait_duration(&self) -> Option<Duration> {
+        if self.name != WAIT_COMMAND {
+            return None;
+        }
+        let duration = match self.parameters.as_slice() {
+            [seconds] => f32::try_from(seconds.clone())
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f32(seconds).ok()),
+            _ => None,
+        };
+        if duration.is_none() {
+            log::warn!("Passing \"<<{}>>\" on as a regular command because it does not have a single non-negative number of seconds as parameter", self.raw);
+        }
+        duration
+    }
+}
+
+/// A parameter of a command before it is converted into a [`YarnValue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CommandToken {
+    text: String,
+    /// Whether the token was surrounded by quotes, which makes it a string regardless of its content.
+    quoted: bool,
+}
+
+impl CommandToken {
+    fn into_value(self) -> YarnValue {
+        if self.quoted {
+            return self.text.into();
+        }
+        if self.text.eq_ignore_ascii_case("true") {
+            return true.into();
+        }
+        if self.text.eq_ignore_ascii_case("false") {
+            return false.into();
+        }
+        match parse_number(&self.text) {
+            Some(number) => number.into(),
+            None => self.text.into(),
         }
     }
 }
 
-/// Splits input into a number of non-empty sub-strings, separated
+/// Parses numbers in the invariant format. Unlike [`str::parse`], this rejects `inf` and `NaN`, which are more likely meant as strings.
+fn parse_number(text: &str) -> Option<f32> {
+    let is_numeric = text.chars().any(|c| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+    is_numeric.then(|| text.parse().ok()).flatten()
+}
+
+/// Splits input into a number of non-empty tokens, separated
 /// by whitespace, and grouping double-quoted strings into a single
-/// sub-string.
+/// token.
 ///
 /// This method behaves similarly to the [`String::split`] method with
 /// the empty results filtered out, with the following differences:
@@ -66,12 +137,20 @@ impl Command {
 ///   unterminated double-quoted string will be treated as though it
 ///   had been terminated at the end of the input.)
 /// - When inside a pair of double-quote characters, the string
-///   `\\` will be converted to `\`, and the string `\"` will be converted to `"`.
-fn split_command_text(input: &str) -> Vec<String> {
+///   `\\` will be converted to `\`, the string `\"` will be converted to `"`,
+///   and `\n` and `\t` will be converted to a line break and a tab.
+fn tokenize_command_text(input: &str) -> Vec<CommandToken> {
     let input = normalize(input);
     let mut chars = input.chars().peekable();
     let mut results = Vec::new();
     let mut current_component = String::new();
+    let mut quoted = false;
+    let mut push = |component: &mut String, quoted: &mut bool| {
+        results.push(CommandToken {
+            text: core::mem::take(component),
+            quoted: core::mem::take(quoted),
+        });
+    };
     while let Some(mut char) = chars.next() {
         match char {
             _ if char.is_whitespace() => {
@@ -79,7 +158,7 @@ fn split_command_text(input: &str) -> Vec<String> {
                     // We've reached the end of a run of visible
                     // characters. Add this run to the result list and
                     // prepare for the next one.
-                    results.push(core::mem::take(&mut current_component));
+                    push(&mut current_component, &mut quoted);
                 } else {
                     // We encountered a whitespace character, but
                     // didn't have any characters queued up. Skip this
@@ -88,6 +167,7 @@ fn split_command_text(input: &str) -> Vec<String> {
             }
             '\"' => {
                 // We've entered a quoted string!
+                quoted = true;
                 loop {
                     char = match chars.next() {
                         Some(c) => c,
@@ -95,7 +175,7 @@ fn split_command_text(input: &str) -> Vec<String> {
                             // Oops, we ended the input while parsing a
                             // quoted string! Dump our current word
                             // immediately and return.
-                            results.push(current_component);
+                            push(&mut current_component, &mut quoted);
                             return results;
                         }
                     };
@@ -108,6 +188,14 @@ fn split_command_text(input: &str) -> Vec<String> {
                                     let next = chars.next().unwrap();
                                     current_component.push(next);
                                 }
+                                Some('n') => {
+                                    chars.next();
+                                    current_component.push('\n');
+                                }
+                                Some('t') => {
+                                    chars.next();
+                                    current_component.push('\t');
+                                }
                                 _ => {
                                     // Oops, an invalid escape. Add the \ and
                                     // whatever is after it.
@@ -125,7 +213,7 @@ fn split_command_text(input: &str) -> Vec<String> {
                         }
                     }
                 }
-                results.push(core::mem::take(&mut current_component));
+                push(&mut current_component, &mut quoted);
             }
             _ => {
                 current_component.push(char);
@@ -133,7 +221,7 @@ fn split_command_text(input: &str) -> Vec<String> {
         }
     }
     if !current_component.is_empty() {
-        results.push(current_component);
+        push(&mut current_component, &mut quoted);
     }
     results
 }
@@ -144,7 +232,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn split_command_text_splits_text_correctly() {
+    fn tokenize_command_text_splits_text_correctly() {
         for (input, expected_components) in [
             ("one two three four", vec!["one", "two", "three", "four"]),
             ("one \"two three\" four", vec!["one", "two three", "four"]),
@@ -167,7 +255,10 @@ mod tests {
             ),
             ("one      two", vec!["one", "two"]),
         ] {
-            let parsed_components = split_command_text(input);
+            let parsed_components: Vec<_> = tokenize_command_text(input)
+                .into_iter()
+                .map(|token| token.text)
+                .collect();
 
             assert_eq!(expected_components, parsed_components);
         }
@@ -182,6 +273,7 @@ mod tests {
                     name: "foo".to_string(),
                     parameters: vec!["bar".into()],
                     raw: "foo bar".to_string(),
+                    completion_token: None,
                 },
             ),
             (
@@ -190,6 +282,7 @@ mod tests {
                     name: "ayy".to_string(),
                     parameters: vec![],
                     raw: "ayy".to_string(),
+                    completion_token: None,
                 },
             ),
             (
@@ -198,14 +291,16 @@ mod tests {
                     name: "foo".to_string(),
                     parameters: vec!["bar baz".into()],
                     raw: "foo \"bar baz\"".to_string(),
+                    completion_token: None,
                 },
             ),
             (
                 "set_sprite ship \"very happy\" 12.3",
                 Command {
                     name: "set_sprite".to_string(),
-                    parameters: vec!["ship".into(), "very happy".into(), "12.3".into()],
+                    parameters: vec!["ship".into(), "very happy".into(), 12.3.into()],
                     raw: "set_sprite ship \"very happy\" 12.3".to_string(),
+                    completion_token: None,
                 },
             ),
             (
@@ -214,6 +309,7 @@ mod tests {
                     name: "!@#$%^&*()⁄€‹›ﬁﬂ‡°·‚‘-=_+".to_string(),
                     parameters: vec![],
                     raw: "!@#$%^&*()⁄€‹›ﬁﬂ‡°·‚‘-=_+".to_string(),
+                    completion_token: None,
                 },
             ),
             (
@@ -222,12 +318,47 @@ mod tests {
                     name: "A long name".to_string(),
                     parameters: vec![],
                     raw: "\"A long name\"".to_string(),
+                    completion_token: None,
                 },
             ),
         ] {
             let parsed_command = Command::parse(input.to_string());
 
-            assert_eq!(expected_command, parsed_command);
+            assert_eq!(Some(expected_command), parsed_command);
         }
+        assert_eq!(None, Command::parse(" \t\n".to_string()));
+    }
+
+    #[test]
+    fn parses_typed_parameters() {
+        let command = Command::parse(
+            r#"wait "two words" 2 -0.5 1e3 TRUE false "true" "12" inf 1.2.3 "" two\ words "a\tb\nc""#
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!("wait", command.name);
+        assert_eq!(
+            vec![
+                YarnValue::from("two words"),
+                2.into(),
+                (-0.5).into(),
+                1000.into(),
+                true.into(),
+                false.into(),
+                "true".into(),
+                "12".into(),
+                "inf".into(),
+                "1.2.3".into(),
+                "".into(),
+                "two\\".into(),
+                "words".into(),
+                "a\tb\nc".into(),
+            ],
+            command.parameters
+        );
+        let delay: f32 = command.parameters[1].clone().try_into().unwrap();
+        assert_eq!(2.0, delay);
+        let delay: String = command.parameters[1].clone().into();
+        assert_eq!("2", delay);
     }
 }
@@ -0,0 +1,49 @@
+//! Not part of the original implementation, but inspired by the coroutine-based commands of Yarn Spinner for Unity,
+//! which block the dialogue until they finish.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Handed out with a [`Command`](crate::prelude::Command) when [`Dialogue::set_command_completion`](crate::prelude::Dialogue::set_command_completion) is enabled.
+/// The dialogue refuses to continue until the token is marked as complete, e.g. once the fade of a `<<fade_out>>` finished.
+///
+/// Tokens are cheap to clone and all clones share the same state, so a clone can be moved into the animation system that runs the command.
+/// Tokens are compared by identity.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let token = CommandCompletionToken::new();
+/// let animation = token.clone();
+/// assert!(!token.is_complete());
+/// animation.complete();
+/// assert!(token.is_complete());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CommandCompletionToken(Arc<AtomicBool>);
+
+impl CommandCompletionToken {
+    /// Creates a token that is not complete yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the command as complete, allowing the dialogue to continue.
+    pub fn complete(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once [`CommandCompletionToken::complete`] was called on this token or one of its clones.
+    pub fn is_complete(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl PartialEq for CommandCompletionToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CommandCompletionToken {}
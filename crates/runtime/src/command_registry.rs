@@ -0,0 +1,151 @@
+//! Not part of the original implementation, but inspired by the `[YarnCommand]` attributes of Yarn Spinner for Unity,
+//! which let the editor check commands against the methods that implement them.
+
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// A parameter of a [`CommandSchema`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CommandParameter {
+    /// The name of the parameter, used in [`ProgramValidationIssue`]s.
+    pub name: String,
+    /// The type the parameter must have, see [`Command::parameters`] for how parameters are typed.
+    /// [`Type::String`] and [`Type::Any`] accept every parameter, since every parameter can be converted into a string.
+    pub r#type: Type,
+    /// Whether the parameter may be left out.
+    pub optional: bool,
+}
+
+/// Describes the name and parameters of a command, so commands of a program can be checked by [`Dialogue::validate_program`]
+/// before they are run. Register schemas in a [`CommandRegistry`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let schema = CommandSchema::new("set_sprite")
+///     .with_parameter("character", Type::String)
+///     .with_optional_parameter("fade", Type::Boolean);
+/// assert_eq!(1..=2, schema.parameter_count());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CommandSchema {
+    /// The name of the command, e.g. `set_sprite` for `<<set_sprite ship "happy">>`.
+    pub name: String,
+    /// The parameters of the command, in order. Optional parameters follow the required ones.
+    pub parameters: Vec<CommandParameter>,
+}
+
+impl CommandSchema {
+    /// Creates a schema for a command without parameters.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Adds a required parameter.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if an optional parameter was added before, since a required parameter cannot follow an optional one.
+    #[must_use]
+    pub fn with_parameter(mut self, name: impl Into<String>, r#type: Type) -> Self {
+        assert!(
+            self.parameters.iter().all(|parameter| !parameter.optional),
+            "The required parameter of command \"{}\" must not follow an optional parameter",
+            self.name
+        );
+        self.parameters.push(CommandParameter {
+            name: name.into(),
+            r#type,
+            optional: false,
+        });
+        self
+    }
+
+    /// Adds a parameter that may be left out.
+    #[must_use]
+    pub fn with_optional_parameter(mut self, name: impl Into<String>, r#type: Type) -> Self {
+        self.parameters.push(CommandParameter {
+            name: name.into(),
+            r#type,
+            optional: true,
+        });
+        self
+    }
+
+    /// The smallest and largest number of parameters the command can be called with.
+    pub fn parameter_count(&self) -> core::ops::RangeInclusive<usize> {
+        let required = self
+            .parameters
+            .iter()
+            .filter(|parameter| !parameter.optional)
+            .count();
+        required..=self.parameters.len()
+    }
+}
+
+/// The [`CommandSchema`]s of all commands a game implements. Set one via [`Dialogue::set_command_registry`] to have
+/// [`Dialogue::validate_program`] report commands that are unknown or called with the wrong parameters.
+///
+/// The registry created by [`CommandRegistry::new`] already knows the `<<wait seconds>>` command handled by the dialogue itself.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut registry = CommandRegistry::new();
+/// registry.register(CommandSchema::new("shake_camera").with_optional_parameter("strength", Type::Number));
+/// assert!(registry.get("wait").is_some());
+/// assert!(registry.get("shake_camera").is_some());
+/// assert!(registry.get("jump").is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CommandRegistry {
+    schemas: HashMap<String, CommandSchema>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRegistry {
+    /// Creates a registry that contains the schema of the `<<wait seconds>>` command.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            schemas: HashMap::new(),
+        };
+        registry.register(CommandSchema::new(WAIT_COMMAND).with_parameter("seconds", Type::Number));
+        registry
+    }
+
+    /// Adds a schema, replacing the one of a command with the same name.
+    pub fn register(&mut self, schema: CommandSchema) -> &mut Self {
+        self.schemas.insert(schema.name.clone(), schema);
+        self
+    }
+
+    /// Removes the schema of the command with the given name and returns it.
+    pub fn unregister(&mut self, command_name: &str) -> Option<CommandSchema> {
+        self.schemas.remove(command_name)
+    }
+
+    /// Gets the schema of the command with the given name.
+    pub fn get(&self, command_name: &str) -> Option<&CommandSchema> {
+        self.schemas.get(command_name)
+    }
+
+    /// Iterates over all registered schemas in no particular order.
+    pub fn schemas(&self) -> impl Iterator<Item = &CommandSchema> {
+        self.schemas.values()
+    }
+}
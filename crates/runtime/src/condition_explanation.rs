@@ -0,0 +1,165 @@
+//! Not part of the original implementation.
+//!
+//! Explains why the condition of an option failed, so writers can find out why a choice is greyed out.
+
+use crate::prelude::*;
+use core::fmt::{self, Display};
+
+/// A sub-expression evaluated while checking the condition of an option, see [`UnavailabilityReason`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConditionStep {
+    /// A variable was read.
+    Variable {
+        /// The name of the variable, including the `$`.
+        name: String,
+        /// The value the variable had.
+        value: YarnValue,
+    },
+    /// A function was called. Operators like `>=` are functions as well, e.g. `Number.GreaterThanOrEqualTo`.
+    FunctionCall {
+        /// The name of the function.
+        function_name: String,
+        /// The parameters the function was called with.
+        parameters: Vec<YarnValue>,
+        /// The value the function returned.
+        result: YarnValue,
+    },
+}
+
+impl Display for ConditionStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Variable { name, value } => write!(f, "{name} = {value}"),
+            Self::FunctionCall {
+                function_name,
+                parameters,
+                result,
+            } => {
+                write!(f, "{function_name}(")?;
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{parameter}")?;
+                }
+                write!(f, ") = {result}")
+            }
+        }
+    }
+}
+
+/// Why an option is unavailable, i.e. its [`DialogueOption::is_available`] is `false`. See [`DialogueOption::unavailability_reason`].
+///
+/// Holds every variable read and function called while evaluating the condition, in evaluation order.
+/// Since the inline expressions of the option's text are evaluated right after its condition, their steps follow those of the condition.
+///
+/// ## Example
+///
+/// For the option `-> Buy the sword <<if $gold >= 10>>` with `$gold` being `5`, the reason is displayed as:
+///
+/// ```text
+/// $gold = 5
+/// Number.GreaterThanOrEqualTo(5, 10) = false
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnavailabilityReason {
+    /// The evaluated sub-expressions.
+    pub steps: Vec<ConditionStep>,
+}
+
+impl UnavailabilityReason {
+    /// The variables read by the condition with the values they had.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &YarnValue)> {
+        self.steps.iter().filter_map(|step| match step {
+            ConditionStep::Variable { name, value } => Some((name.as_str(), value)),
+            ConditionStep::FunctionCall { .. } => None,
+        })
+    }
+
+    /// The function calls that returned `false`, which are usually the comparisons that made the condition fail.
+    pub fn failed_calls(&self) -> impl Iterator<Item = &ConditionStep> {
+        self.steps.iter().filter(|step| {
+            matches!(
+                step,
+                ConditionStep::FunctionCall {
+                    result: YarnValue::Boolean(false),
+                    ..
+                }
+            )
+        })
+    }
+}
+
+impl Display for UnavailabilityReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{step}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn explains_failed_option_conditions() {
+        let mut program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushVariable(PushVariableInstruction {
+                    variable_name: "$gold".to_owned(),
+                }),
+                InstructionType::PushFloat(PushFloatInstruction { value: 10.0 }),
+                InstructionType::PushFloat(PushFloatInstruction { value: 2.0 }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "Number.GreaterThanOrEqualTo".to_owned(),
+                }),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 1,
+                    destination: 7,
+                    substitution_count: 0,
+                    has_condition: true,
+                }),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 7,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::Stop(StopInstruction {}),
+            ],
+        );
+        program
+            .initial_values
+            .insert("$gold".to_owned(), 5.0.into());
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program);
+        dialogue.set_node("Start").unwrap();
+
+        let events = dialogue.continue_().unwrap();
+        let Some(DialogueEvent::Options(options)) = events.last() else {
+            panic!("Expected options, got {events:?}");
+        };
+        let reason = options[0].unavailability_reason().unwrap();
+        assert_eq!(
+            vec![("$gold", &YarnValue::Number(5.0))],
+            reason.variables().collect::<Vec<_>>()
+        );
+        assert_eq!(1, reason.failed_calls().count());
+        assert_eq!(
+            "$gold = 5\nNumber.GreaterThanOrEqualTo(5, 10) = false",
+            reason.to_string()
+        );
+        assert!(options[1].unavailability_reason().is_none());
+    }
+}
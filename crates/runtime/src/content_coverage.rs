@@ -0,0 +1,300 @@
+//! Not part of the original implementation.
+//!
+//! Tracks which lines, options and nodes of a [`Program`] were seen, across play sessions, to find content that was never tested.
+
+use crate::prelude::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use core::fmt::Write;
+use yarnspinner_core::prelude::instruction::InstructionType;
+
+/// How often an option was offered and selected, see [`ContentCoverage::option`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptionCoverage {
+    /// How often the option was part of a [`DialogueEvent::Options`].
+    pub offered: u64,
+    /// How often the option was selected via [`Dialogue::set_selected_option`].
+    pub selected: u64,
+}
+
+/// Records which lines were delivered, which options were offered and selected, and which nodes were visited.
+///
+/// Set one via [`Dialogue::set_content_coverage`] and the dialogue feeds it with every event it returns and every option selected.
+/// Coverage of several play sessions, e.g. loaded from disk, can be combined with [`ContentCoverage::merge`].
+/// Compare it against a [`Program`] via [`ContentCoverage::unseen_lines`] and [`ContentCoverage::unseen_nodes`],
+/// or export it for a spreadsheet via [`ContentCoverage::to_csv`].
+///
+/// Options are identified by the node they are presented in and their [`DialogueOption::tag_id`], like [`DialogueOption::stable_hash`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.set_content_coverage(Some(ContentCoverage::new()));
+/// // Play the dialogue, then merge the coverage into the one of previous sessions:
+/// let mut total = ContentCoverage::new();
+/// total.merge(dialogue.content_coverage().unwrap());
+/// assert_eq!("kind,node,id,seen,selected\n", total.to_csv());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContentCoverage {
+    lines: BTreeMap<u32, u64>,
+    options: BTreeMap<String, BTreeMap<u32, OptionCoverage>>,
+    nodes: BTreeMap<String, u64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    current_node: Option<Arc<str>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending_options: Vec<DialogueOption>,
+}
+
+impl ContentCoverage {
+    /// Creates an empty coverage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the relevant parts of an event. Called by the [`Dialogue`] for every event it returns.
+    pub fn record_event(&mut self, event: &DialogueEvent) {
+        match event {
+            DialogueEvent::Line(line_id, ..) => *self.lines.entry(*line_id).or_default() += 1,
+            DialogueEvent::Options(options) => {
+                let node_options = self
+                    .options
+                    .entry(self.current_node.as_deref().unwrap_or_default().to_owned())
+                    .or_default();
+                for option in options {
+                    node_options.entry(option.tag_id).or_default().offered += 1;
+                }
+                self.pending_options = options.clone();
+            }
+            DialogueEvent::NodeStart(node_name) => {
+                *self.nodes.entry(node_name.to_string()).or_default() += 1;
+                self.current_node = Some(node_name.clone());
+            }
+            DialogueEvent::DialogueComplete => self.current_node = None,
+            DialogueEvent::LineInterrupted(_)
+            | DialogueEvent::LineFiltered(_)
+            | DialogueEvent::LineHints(_)
+            | DialogueEvent::Command(_)
+            | DialogueEvent::Wait(_)
+            | DialogueEvent::BreakpointHit(_)
+            | DialogueEvent::NodeComplete(_) => {}
+        }
+    }
+
+    /// Records the selection of one of the options of the last [`DialogueEvent::Options`]. Called by [`Dialogue::set_selected_option`].
+    pub fn record_selection(&mut self, option_id: OptionId) {
+        let Some(option) = self
+            .pending_options
+            .iter()
+            .find(|option| option.id == option_id)
+        else {
+            return;
+        };
+        self.options
+            .entry(self.current_node.as_deref().unwrap_or_default().to_owned())
+            .or_default()
+            .entry(option.tag_id)
+            .or_default()
+            .selected += 1;
+        self.pending_options.clear();
+    }
+
+    /// Adds the counts of another coverage, e.g. of a different play session, to this one.
+    pub fn merge(&mut self, other: &ContentCoverage) {
+        for (line_id, count) in &other.lines {
+            *self.lines.entry(*line_id).or_default() += count;
+        }
+        for (node_name, options) in &other.options {
+            let node_options = self.options.entry(node_name.clone()).or_default();
+            for (tag_id, coverage) in options {
+                let entry = node_options.entry(*tag_id).or_default();
+                entry.offered += coverage.offered;
+                entry.selected += coverage.selected;
+            }
+        }
+        for (node_name, count) in &other.nodes {
+            *self.nodes.entry(node_name.clone()).or_default() += count;
+        }
+    }
+
+    /// Removes all recorded coverage.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// How often the line with the given ID was delivered.
+    pub fn line_count(&self, line_id: u32) -> u64 {
+        self.lines.get(&line_id).copied().unwrap_or_default()
+    }
+
+    /// How often the node with the given name was entered.
+    pub fn node_count(&self, node_name: &str) -> u64 {
+        self.nodes.get(node_name).copied().unwrap_or_default()
+    }
+
+    /// How often the option with the given tag was offered and selected in the given node.
+    pub fn option(&self, node_name: &str, tag_id: u32) -> OptionCoverage {
+        self.options
+            .get(node_name)
+            .and_then(|options| options.get(&tag_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The IDs of all delivered lines with how often they were delivered, in ascending order.
+    pub fn lines(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.lines.iter().map(|(line_id, count)| (*line_id, *count))
+    }
+
+    /// The names of all entered nodes with how often they were entered, in alphabetical order.
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.nodes
+            .iter()
+            .map(|(node_name, count)| (node_name.as_str(), *count))
+    }
+
+    /// All offered options as node name, tag and coverage, ordered by node name and tag.
+    pub fn options(&self) -> impl Iterator<Item = (&str, u32, OptionCoverage)> {
+        self.options.iter().flat_map(|(node_name, options)| {
+            options
+                .iter()
+                .map(move |(tag_id, coverage)| (node_name.as_str(), *tag_id, *coverage))
+        })
+    }
+
+    /// The IDs of the lines and options of the program that were never delivered or offered, in ascending order.
+    pub fn unseen_lines(&self, program: &Program) -> Vec<u32> {
+        let offered: BTreeSet<_> = self.options().map(|(_, tag_id, _)| tag_id).collect();
+        let line_ids: BTreeSet<_> = program
+            .nodes
+            .values()
+            .flat_map(|node| &node.instructions)
+            .filter_map(|instruction| match instruction.instruction_type.as_ref()? {
+                InstructionType::RunLine(run_line) => {
+                    (!self.lines.contains_key(&run_line.line_id)).then_some(run_line.line_id)
+                }
+                InstructionType::AddOption(add_option) => {
+                    (!offered.contains(&add_option.tag_id)).then_some(add_option.tag_id)
+                }
+                _ => None,
+            })
+            .collect();
+        line_ids.into_iter().collect()
+    }
+
+    /// The names of the nodes of the program that were never entered, in alphabetical order.
+    pub fn unseen_nodes<'a>(&self, program: &'a Program) -> Vec<&'a str> {
+        program
+            .nodes
+            .keys()
+            .filter(|node_name| !self.nodes.contains_key(node_name.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Exports the coverage as CSV with the columns `kind`, `node`, `id`, `seen` and `selected`.
+    ///
+    /// There is one row of kind `node` per entered node, `line` per delivered line and `option` per offered option.
+    /// `seen` is how often the node was entered, the line delivered or the option offered, and `selected` how often an option was selected.
+    /// Columns that don't apply to a kind are left empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("kind,node,id,seen,selected\n");
+        for (node_name, count) in self.nodes() {
+            let _ = writeln!(csv, "node,{},,{count},", csv_field(node_name));
+        }
+        for (line_id, count) in self.lines() {
+            let _ = writeln!(csv, "line,,{line_id},{count},");
+        }
+        for (node_name, tag_id, coverage) in self.options() {
+            let _ = writeln!(
+                csv,
+                "option,{},{tag_id},{},{}",
+                csv_field(node_name),
+                coverage.offered,
+                coverage.selected
+            );
+        }
+        csv
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+
+    #[test]
+    fn records_merges_and_exports_coverage() {
+        let add_option = |tag_id| {
+            InstructionType::AddOption(instruction::AddOptionInstruction {
+                tag_id,
+                destination: 0,
+                substitution_count: 0,
+                has_condition: false,
+            })
+        };
+        let program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 0,
+                }),
+                add_option(2),
+                add_option(3),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 4,
+                    substitution_count: 0,
+                }),
+                InstructionType::Stop(instruction::StopInstruction {}),
+            ],
+        );
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program.clone());
+        dialogue.set_content_coverage(Some(ContentCoverage::new()));
+        dialogue.set_node("Start").unwrap();
+        while !dialogue
+            .continue_()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, DialogueEvent::Options(_)))
+        {}
+        dialogue.set_selected_option(OptionId(1)).unwrap();
+
+        let session = dialogue.content_coverage().unwrap().clone();
+        assert_eq!(vec![4], session.unseen_lines(&program));
+        assert!(session.unseen_nodes(&program).is_empty());
+
+        let mut total = session.clone();
+        total.merge(&session);
+        assert_eq!(
+            OptionCoverage {
+                offered: 2,
+                selected: 2
+            },
+            total.option("Start", 3)
+        );
+        assert_eq!(
+            "kind,node,id,seen,selected\n\
+             node,Start,,2,\n\
+             line,,1,2,\n\
+             option,Start,2,2,0\n\
+             option,Start,3,2,2\n",
+            total.to_csv()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Not part of the original implementation.
+//!
+//! Lets games filter lines by the hashtags their authors tagged them with, e.g. `#violence` or `#spoiler`,
+//! according to content settings chosen by the player.
+
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+/// What a [`ContentFilter`] does with a line that has a hashtag the filter has a rule for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(C, u8)]
+pub enum LineFilterAction {
+    /// The line is not delivered, and the dialogue continues with whatever follows it.
+    Suppress,
+    /// The line with the given ID is delivered instead, e.g. a toned-down version of the line. Its substitutions are kept.
+    Replace(u32),
+    /// The line is delivered, e.g. so the game can show a content warning next to it.
+    Annotate,
+}
+
+/// A rule of a [`ContentFilter`] that was applied to a line, sent as [`DialogueEvent::LineFiltered`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LineFiltered {
+    /// The ID of the line the rule was applied to, as in [`DialogueEvent::Line`].
+    pub line_id: u32,
+    /// The hashtag of the line the rule matched, without the leading `#`.
+    pub hashtag: Arc<str>,
+    /// What was done with the line.
+    pub action: LineFilterAction,
+}
+
+/// Suppresses, replaces or annotates lines based on their hashtags, see [`Dialogue::set_content_filter`].
+///
+/// The hashtags are read from the [`LineMetadata`] of the given [`TextProvider`], e.g. from the `tags` column of a string table.
+/// Rules can be changed at any time via [`Dialogue::content_filter_mut`], e.g. when the player changes their content settings.
+///
+/// For every line with hashtags the filter has rules for, a [`DialogueEvent::LineFiltered`] is sent right before where the line would be:
+/// - If any rule suppresses the line, only the record of the first such hashtag is sent and the line is dropped,
+///   together with the [`DialogueEvent::LineHints`] describing it.
+///   The dialogue then continues on its own, so the player never has to acknowledge a line they didn't see.
+/// - Otherwise, the record of the first hashtag whose rule replaces the line is sent and the replacement is delivered instead,
+///   followed by the records of all hashtags whose rule annotates the line.
+///
+/// Options are not filtered, so use conditions to hide them.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # use std::sync::Arc;
+/// let mut text_provider = StringTableTextProvider::new();
+/// text_provider.extend_base_language_metadata([(
+///     LineId::from("line:7"),
+///     LineMetadata {
+///         hashtags: vec!["spoiler".to_owned()],
+///         ..Default::default()
+///     },
+/// )]);
+/// let filter = ContentFilter::new(Arc::new(text_provider))
+///     .with_rule("#spoiler", LineFilterAction::Replace(8));
+/// assert_eq!(
+///     vec![LineFiltered {
+///         line_id: 7,
+///         hashtag: "spoiler".into(),
+///         action: LineFilterAction::Replace(8),
+///     }],
+///     filter.filter_line(7)
+/// );
+/// assert!(filter.filter_line(6).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContentFilter {
+    text_provider: Arc<dyn TextProvider>,
+    rules: BTreeMap<String, LineFilterAction>,
+}
+
+impl ContentFilter {
+    /// Creates a filter without rules that reads the hashtags of lines from the given text provider.
+    pub fn new(text_provider: Arc<dyn TextProvider>) -> Self {
+        Self {
+            text_provider,
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Applies the action to all lines with the given hashtag. The leading `#` may be omitted.
+    #[must_use]
+    pub fn with_rule(mut self, hashtag: impl Into<String>, action: LineFilterAction) -> Self {
+        self.set_rule(hashtag, Some(action));
+        self
+    }
+
+    /// Sets the action to apply to all lines with the given hashtag, or removes the rule with `None`. The leading `#` may be omitted.
+    pub fn set_rule(
+        &mut self,
+        hashtag: impl Into<String>,
+        action: Option<LineFilterAction>,
+    ) -> &mut Self {
+        let mut hashtag = hashtag.into();
+        if hashtag.starts_with('#') {
+            hashtag.remove(0);
+        }
+        match action {
+            Some(action) => self.rules.insert(hashtag, action),
+            None => self.rules.remove(&hashtag),
+        };
+        self
+    }
+
+    /// The action applied to lines with the given hashtag, if any. The leading `#` may be omitted.
+    pub fn rule(&self, hashtag: &str) -> Option<LineFilterAction> {
+        let hashtag = hashtag.strip_prefix('#').unwrap_or(hashtag);
+        self.rules.get(hashtag).copied()
+    }
+
+    /// Removes all rules.
+    pub fn clear_rules(&mut self) -> &mut Self {
+        self.rules.clear();
+        self
+    }
+
+    /// Replaces the text provider the hashtags are read from.
+    pub fn set_text_provider(&mut self, text_provider: Arc<dyn TextProvider>) -> &mut Self {
+        self.text_provider = text_provider;
+        self
+    }
+
+    /// The rules that apply to the given line, in the order described in [`ContentFilter`]. Empty if the line is delivered unchanged.
+    pub fn filter_line(&self, line_id: u32) -> Vec<LineFiltered> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let Some(metadata) = self
+            .text_provider
+            .get_metadata(&LineId::from(format!("line:{line_id}")))
+        else {
+            return Vec::new();
+        };
+        let matches = metadata
+            .hashtags
+            .iter()
+            .filter_map(|hashtag| Some((hashtag.as_str(), self.rule(hashtag)?)));
+        let record = |(hashtag, action): (&str, LineFilterAction)| LineFiltered {
+            line_id,
+            hashtag: hashtag.into(),
+            action,
+        };
+
+        if let Some(suppress) = matches
+            .clone()
+            .find(|(_, action)| *action == LineFilterAction::Suppress)
+        {
+            return vec![record(suppress)];
+        }
+        let replace = matches
+            .clone()
+            .find(|(_, action)| matches!(action, LineFilterAction::Replace(_)));
+        replace
+            .into_iter()
+            .chain(matches.filter(|(_, action)| *action == LineFilterAction::Annotate))
+            .map(record)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_lines;
+
+    #[test]
+    fn suppresses_replaces_and_annotates_delivered_lines() {
+        let tagged = |line_id: &str, hashtags: &[&str]| {
+            (
+                LineId::from(line_id),
+                LineMetadata {
+                    hashtags: hashtags.iter().map(|hashtag| hashtag.to_string()).collect(),
+                    ..Default::default()
+                },
+            )
+        };
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language_metadata([
+            tagged("line:1", &["violence", "spoiler"]),
+            tagged("line:2", &["gore", "violence", "lastline"]),
+        ]);
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program_with_lines("Start", [1, 2]))
+            .set_line_hints(true)
+            .set_content_filter(Some(
+                ContentFilter::new(Arc::new(text_provider))
+                    .with_rule("#violence", LineFilterAction::Annotate)
+                    .with_rule("gore", LineFilterAction::Replace(3))
+                    .with_rule("spoiler", LineFilterAction::Suppress),
+            ))
+            .set_node("Start")
+            .unwrap();
+
+        let record = |line_id, hashtag: &str, action| {
+            DialogueEvent::LineFiltered(LineFiltered {
+                line_id,
+                hashtag: hashtag.into(),
+                action,
+            })
+        };
+        let events = dialogue.continue_().unwrap();
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Start".into()),
+                record(1, "spoiler", LineFilterAction::Suppress),
+                DialogueEvent::LineHints(LineHints {
+                    is_final_line_of_node: true,
+                    is_final_line_of_dialogue: true,
+                    ..Default::default()
+                }),
+                record(2, "gore", LineFilterAction::Replace(3)),
+                record(2, "violence", LineFilterAction::Annotate),
+                DialogueEvent::Line(3, vec![]),
+            ],
+            events
+        );
+
+        dialogue
+            .content_filter_mut()
+            .unwrap()
+            .set_rule("spoiler", None);
+        assert_eq!(None, dialogue.content_filter().unwrap().rule("spoiler"));
+    }
+}
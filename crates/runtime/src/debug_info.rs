@@ -0,0 +1,37 @@
+//! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/DebugInfo.cs>
+//!
+//! ## Implementation notes
+//! The compiler is not part of this crate, so the debug info has to be handed to the [`Dialogue`] by the host via [`Dialogue::add_debug_info`].
+
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// Contains debug information for a node in a Yarn file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeDebugInfo {
+    /// The file that this node was contained in.
+    pub file_name: String,
+
+    /// The name of this node.
+    pub node_name: String,
+
+    /// The mapping of instruction numbers to [`Position`]s in the file.
+    pub line_positions: HashMap<usize, Position>,
+}
+
+impl NodeDebugInfo {
+    /// Creates a new [`NodeDebugInfo`] without any positions.
+    pub fn new(file_name: impl Into<String>, node_name: impl Into<String>) -> Self {
+        Self {
+            file_name: file_name.into(),
+            node_name: node_name.into(),
+            line_positions: Default::default(),
+        }
+    }
+
+    /// Gets the [`Position`] of the source code that corresponds to the given instruction number in this node.
+    pub fn get_position(&self, instruction_number: usize) -> Option<Position> {
+        self.line_positions.get(&instruction_number).copied()
+    }
+}
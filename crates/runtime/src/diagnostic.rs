@@ -0,0 +1,154 @@
+//! Machine-readable details about [`DialogueError`]s for tooling.
+//!
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use core::fmt::{self, Display};
+
+/// A stable, machine-readable code identifying the kind of a [`DialogueError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DialogueErrorCode {
+    /// See [`DialogueError::MarkupParseError`].
+    MarkupParse,
+    /// See [`DialogueError::InvalidOptionIdError`].
+    InvalidOptionId,
+    /// See [`DialogueError::UnexpectedOptionSelectionError`].
+    UnexpectedOptionSelection,
+    /// See [`DialogueError::ContinueOnOptionSelectionError`].
+    ContinueOnOptionSelection,
+    /// See [`DialogueError::NoNodeSelectedOnContinue`].
+    NoNodeSelectedOnContinue,
+    /// See [`DialogueError::NoProgramLoaded`].
+    NoProgramLoaded,
+    /// See [`DialogueError::InvalidNode`].
+    InvalidNode,
+    /// See [`DialogueError::CurrentNodeRemovedOnReload`].
+    CurrentNodeRemovedOnReload,
+    /// See [`DialogueError::OptionsChangedOnReload`].
+    OptionsChangedOnReload,
+    /// See [`DialogueError::VariableStorageError`].
+    VariableStorage,
+    /// See [`DialogueError::FunctionNotFound`].
+    FunctionNotFound,
+    /// See [`DialogueError::InvalidExpression`].
+    InvalidExpression,
+    /// See [`DialogueError::ReplayDiverged`].
+    ReplayDiverged,
+    /// See [`DialogueError::EmptyCommand`].
+    EmptyCommand,
+    /// See [`DialogueError::FunctionNotPermitted`].
+    FunctionNotPermitted,
+    /// See [`DialogueError::CommandNotPermitted`].
+    CommandNotPermitted,
+    /// See [`DialogueError::FunctionFailed`].
+    FunctionFailed,
+    /// See [`DialogueError::CapacityExceeded`].
+    CapacityExceeded,
+    /// See [`DialogueError::CommandNotComplete`].
+    CommandNotComplete,
+    /// See [`DialogueError::NoLineToInterrupt`].
+    NoLineToInterrupt,
+    /// See [`DialogueError::NoStartNode`].
+    NoStartNode,
+    /// See [`DialogueError::InvalidInstruction`].
+    InvalidInstruction,
+}
+
+impl DialogueErrorCode {
+    /// Returns the code as a string of the form `YS1xxx`. The number of a given code never changes.
+    pub fn as_str(&self) -> &'static str {
+        use DialogueErrorCode::*;
+        match self {
+            MarkupParse => "YS1001",
+            InvalidOptionId => "YS1002",
+            UnexpectedOptionSelection => "YS1003",
+            ContinueOnOptionSelection => "YS1004",
+            NoNodeSelectedOnContinue => "YS1005",
+            NoProgramLoaded => "YS1006",
+            InvalidNode => "YS1007",
+            CurrentNodeRemovedOnReload => "YS1008",
+            VariableStorage => "YS1009",
+            FunctionNotFound => "YS1010",
+            InvalidExpression => "YS1011",
+            ReplayDiverged => "YS1012",
+            EmptyCommand => "YS1013",
+            FunctionNotPermitted => "YS1014",
+            CommandNotPermitted => "YS1015",
+            FunctionFailed => "YS1016",
+            CapacityExceeded => "YS1017",
+            CommandNotComplete => "YS1018",
+            NoLineToInterrupt => "YS1019",
+            NoStartNode => "YS1020",
+            OptionsChangedOnReload => "YS1021",
+            InvalidInstruction => "YS1022",
+        }
+    }
+}
+
+impl Display for DialogueErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The instruction that was being executed when an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InstructionLocation {
+    /// The name of the node containing the instruction.
+    pub node_name: String,
+    /// The index of the instruction inside the node.
+    pub instruction: usize,
+}
+
+/// A [`DialogueError`] enriched with where it happened. Created by [`Dialogue::diagnose`].
+///
+/// The [`Display`] implementation formats it like a `rustc` diagnostic:
+/// ```text
+/// error[YS1010]: Function "foo" not found in library: {...}
+///   --> Start.yarn:4:3
+///    |
+///    = note: while running node `Start`, instruction 7
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DialogueDiagnostic {
+    /// The code of the error.
+    pub code: DialogueErrorCode,
+    /// The human-readable description of the error.
+    pub message: String,
+    /// The instruction that caused the error, if it occurred while running a node.
+    pub location: Option<InstructionLocation>,
+    /// The file containing the node, if debug info for it was provided.
+    pub file_name: Option<String>,
+    /// The position in the original Yarn source, if debug info for the instruction was provided.
+    pub position: Option<Position>,
+}
+
+impl Display for DialogueDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error[{}]: {}", self.code, self.message)?;
+        if let (Some(file_name), Some(position)) = (&self.file_name, self.position) {
+            // Positions are zero-indexed, but editors and rustc show them one-indexed
+            writeln!(
+                f,
+                "  --> {file_name}:{}:{}",
+                position.line + 1,
+                position.character + 1
+            )?;
+        } else if let Some(file_name) = &self.file_name {
+            writeln!(f, "  --> {file_name}")?;
+        }
+        if let Some(location) = &self.location {
+            writeln!(f, "   |")?;
+            writeln!(
+                f,
+                "   = note: while running node `{}`, instruction {}",
+                location.node_name, location.instruction
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,17 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>
 
+use crate::expression::{evaluate, is_when_condition_met, when_conditions, ExpressionContext};
 use crate::markup::MarkupParseError;
+use crate::node_statistics::{entered_variable, last_visited_variable, read_integer};
 use crate::prelude::*;
+use crate::program_validation::{validate_commands, validate_function_calls};
+use alloc::sync::Arc;
+use core::any::Any;
 use core::error::Error;
 use core::fmt::{self, Debug, Display};
-use std::collections::HashMap;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use hashbrown::{HashMap, HashSet};
 use log::error;
 use yarnspinner_core::prelude::*;
 
@@ -14,6 +21,18 @@ use yarnspinner_core::prelude::*;
 #[derive(Debug, Clone)]
 pub struct Dialogue {
     vm: VirtualMachine,
+    debug_info: HashMap<String, NodeDebugInfo>,
+    line_parser: LineParser,
+    variable_name_mode: VariableNameMode,
+    observers: Vec<(DialogueObserverId, Arc<dyn DialogueObserver>)>,
+    next_observer_id: usize,
+    transcript_recorder: Option<TranscriptRecorder>,
+    content_coverage: Option<ContentCoverage>,
+    value_formatter: Arc<dyn ValueFormatter>,
+    blocking_commands: HashSet<String>,
+    command_registry: Option<CommandRegistry>,
+    start_node: Option<String>,
+    content_filter: Option<ContentFilter>,
 }
 
 #[allow(missing_docs)]
@@ -21,6 +40,7 @@ pub type Result<T> = core::result::Result<T, DialogueError>;
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DialogueError {
     MarkupParseError(MarkupParseError),
     InvalidOptionIdError {
@@ -34,11 +54,49 @@ pub enum DialogueError {
     InvalidNode {
         node_name: String,
     },
+    CurrentNodeRemovedOnReload {
+        node_name: String,
+    },
+    OptionsChangedOnReload {
+        node_name: String,
+        options: Vec<DialogueOption>,
+    },
     VariableStorageError(VariableStorageError),
     FunctionNotFound {
         function_name: String,
+        #[cfg_attr(feature = "defmt", defmt(Display2Format))]
         library: Library,
     },
+    InvalidExpression {
+        expression: String,
+        message: String,
+    },
+    ReplayDiverged {
+        entry_index: usize,
+        message: String,
+    },
+    EmptyCommand,
+    FunctionNotPermitted {
+        function_name: String,
+    },
+    CommandNotPermitted {
+        command_name: String,
+    },
+    FunctionFailed {
+        function_name: String,
+        message: String,
+    },
+    CapacityExceeded {
+        buffer: CapacityBuffer,
+    },
+    CommandNotComplete {
+        command_name: String,
+    },
+    NoLineToInterrupt,
+    NoStartNode,
+    InvalidInstruction {
+        message: String,
+    },
 }
 
 impl Error for DialogueError {
@@ -63,8 +121,53 @@ impl Display for DialogueError {
             NoNodeSelectedOnContinue => f.write_str("Cannot continue running dialogue. No node has been selected."),
             NoProgramLoaded => f.write_str("No program has been loaded. Cannot continue running dialogue."),
             InvalidNode { node_name } => write!(f, "No node named \"{node_name}\" has been loaded."),
+            CurrentNodeRemovedOnReload { node_name } => write!(f, "The node \"{node_name}\" was being run, but is not present in the replacement program. The dialogue has been stopped."),
+            OptionsChangedOnReload { node_name, options } if options.is_empty() => write!(f, "The options presented by the node \"{node_name}\" point past its end in the replacement program. None are left, so the dialogue has been stopped."),
+            OptionsChangedOnReload { node_name, options } => write!(f, "Some options presented by the node \"{node_name}\" point past its end in the replacement program. The remaining {} options must be presented again.", options.len()),
             VariableStorageError(e) => Display::fmt(e, f),
             FunctionNotFound { function_name, library } => write!(f, "Function \"{function_name}\" not found in library: {library}"),
+            InvalidExpression { expression, message } => write!(f, "Invalid expression \"{expression}\": {message}"),
+            ReplayDiverged { entry_index, message } => write!(f, "The replay diverged from the recording at entry {entry_index}: {message}"),
+            FunctionNotPermitted { function_name } => write!(f, "The function \"{function_name}\" is not permitted by the sandbox policy of the dialogue."),
+            CommandNotPermitted { command_name } => write!(f, "The command \"{command_name}\" is not permitted by the sandbox policy of the dialogue."),
+            FunctionFailed { function_name, message } => write!(f, "The function \"{function_name}\" failed: {message}"),
+            CapacityExceeded { buffer } => write!(f, "The {buffer} is full, it can hold at most {} elements.", buffer.capacity()),
+            CommandNotComplete { command_name } => write!(f, "Dialogue was asked to continue running, but the command \"{command_name}\" has not been marked as complete via its completion token yet."),
+            NoLineToInterrupt => f.write_str("A line was interrupted, but the dialogue wasn't waiting for a line to be continued. This method should only be called after a line was delivered."),
+            NoStartNode => f.write_str("Cannot start the dialogue. No start node has been set and the program has neither a node with a `start` header nor a node named \"Start\"."),
+            EmptyCommand => f.write_str("A command is composed entirely of whitespace. You might have run an expression that evaluates to whitespace, e.g. `<<{$command}>>`."),
+            InvalidInstruction { message } => write!(f, "The program is malformed: {message}. Use `Dialogue::diagnose` to find the failing instruction."),
+        }
+    }
+}
+
+impl DialogueError {
+    /// Returns the machine-readable [`DialogueErrorCode`] of this error.
+    pub fn code(&self) -> DialogueErrorCode {
+        use DialogueError::*;
+        match self {
+            MarkupParseError(_) => DialogueErrorCode::MarkupParse,
+            InvalidOptionIdError { .. } => DialogueErrorCode::InvalidOptionId,
+            UnexpectedOptionSelectionError => DialogueErrorCode::UnexpectedOptionSelection,
+            ContinueOnOptionSelectionError => DialogueErrorCode::ContinueOnOptionSelection,
+            NoNodeSelectedOnContinue => DialogueErrorCode::NoNodeSelectedOnContinue,
+            NoProgramLoaded => DialogueErrorCode::NoProgramLoaded,
+            InvalidNode { .. } => DialogueErrorCode::InvalidNode,
+            CurrentNodeRemovedOnReload { .. } => DialogueErrorCode::CurrentNodeRemovedOnReload,
+            OptionsChangedOnReload { .. } => DialogueErrorCode::OptionsChangedOnReload,
+            VariableStorageError(_) => DialogueErrorCode::VariableStorage,
+            FunctionNotFound { .. } => DialogueErrorCode::FunctionNotFound,
+            InvalidExpression { .. } => DialogueErrorCode::InvalidExpression,
+            ReplayDiverged { .. } => DialogueErrorCode::ReplayDiverged,
+            EmptyCommand => DialogueErrorCode::EmptyCommand,
+            FunctionNotPermitted { .. } => DialogueErrorCode::FunctionNotPermitted,
+            CommandNotPermitted { .. } => DialogueErrorCode::CommandNotPermitted,
+            FunctionFailed { .. } => DialogueErrorCode::FunctionFailed,
+            CapacityExceeded { .. } => DialogueErrorCode::CapacityExceeded,
+            CommandNotComplete { .. } => DialogueErrorCode::CommandNotComplete,
+            NoLineToInterrupt => DialogueErrorCode::NoLineToInterrupt,
+            NoStartNode => DialogueErrorCode::NoStartNode,
+            InvalidInstruction { .. } => DialogueErrorCode::InvalidInstruction,
         }
     }
 }
@@ -88,16 +191,49 @@ impl Dialogue {
     ///
     /// If you don't need any fancy behavior, you can use [`StringTableTextProvider`] and [`MemoryVariableStorage`].
     #[must_use]
-    pub fn new(
+    pub fn new(variable_storage: Box<dyn VariableStorage>) -> Self {
+        Self::with_shared_library(Arc::new(Library::standard_library()), variable_storage)
+    }
+
+    /// Creates a dialogue whose [`Library`] is shared with other dialogues until one of them calls [`Dialogue::library_mut`].
+    pub(crate) fn with_shared_library(
+        library: Arc<Library>,
         variable_storage: Box<dyn VariableStorage>,
     ) -> Self {
-        let mut library = Library::standard_library();
-        library
+        // These are bound to this dialogue's variable storage, so they cannot be part of a library shared by several dialogues
+        let node_statistics_tracking = Arc::new(AtomicBool::new(false));
+        let mut storage_functions = Library::new();
+        storage_functions
             .add_function("visited", visited(variable_storage.clone()))
-            .add_function("visited_count", visited_count(variable_storage.clone()));
+            .add_function("visited_count", visited_count(variable_storage.clone()))
+            .add_function(
+                "times_visited",
+                times_visited(variable_storage.clone(), node_statistics_tracking.clone()),
+            )
+            .add_function(
+                "last_visited",
+                last_visited(variable_storage.clone(), node_statistics_tracking.clone()),
+            );
 
         Self {
-            vm: VirtualMachine::new(library, variable_storage),
+            vm: VirtualMachine::new(
+                library,
+                storage_functions,
+                variable_storage,
+                node_statistics_tracking,
+            ),
+            debug_info: Default::default(),
+            line_parser: LineParser::new(),
+            variable_name_mode: Default::default(),
+            observers: Default::default(),
+            next_observer_id: Default::default(),
+            transcript_recorder: Default::default(),
+            content_coverage: Default::default(),
+            value_formatter: Arc::new(LocaleValueFormatter),
+            blocking_commands: Default::default(),
+            command_registry: Default::default(),
+            start_node: Default::default(),
+            content_filter: Default::default(),
         }
     }
 }
@@ -105,22 +241,56 @@ impl Dialogue {
 fn visited(storage: Box<dyn VariableStorage>) -> yarn_fn_type! { impl Fn(String) -> bool } {
     move |node: String| -> bool {
         let name = Library::generate_unique_visited_variable_for_node(&node);
-        if let Ok(YarnValue::Number(count)) = storage.get(&name) {
-            count > 0.0
-        } else {
-            false
-        }
+        storage
+            .get(&name)
+            .ok()
+            .and_then(|count| count.as_number())
+            .is_some_and(|count| count > 0.0)
     }
 }
 
 fn visited_count(storage: Box<dyn VariableStorage>) -> yarn_fn_type! { impl Fn(String) -> f32 } {
     move |node: String| {
         let name = Library::generate_unique_visited_variable_for_node(&node);
-        if let Ok(YarnValue::Number(count)) = storage.get(&name) {
-            count
-        } else {
-            0.0
-        }
+        storage
+            .get(&name)
+            .ok()
+            .and_then(|count| count.as_number())
+            .unwrap_or_default()
+    }
+}
+
+fn times_visited(
+    storage: Box<dyn VariableStorage>,
+    tracking: Arc<AtomicBool>,
+) -> yarn_fn_type! { impl Fn(String) -> core::result::Result<i64, String> } {
+    move |node: String| {
+        ensure_node_statistics_tracking(&tracking, "times_visited")?;
+        Ok(read_integer(storage.as_ref(), &entered_variable(&node)))
+    }
+}
+
+fn last_visited(
+    storage: Box<dyn VariableStorage>,
+    tracking: Arc<AtomicBool>,
+) -> yarn_fn_type! { impl Fn(String) -> core::result::Result<i64, String> } {
+    move |node: String| {
+        ensure_node_statistics_tracking(&tracking, "last_visited")?;
+        Ok(read_integer(
+            storage.as_ref(),
+            &last_visited_variable(&node),
+        ))
+    }
+}
+
+fn ensure_node_statistics_tracking(
+    tracking: &AtomicBool,
+    function_name: &str,
+) -> core::result::Result<(), String> {
+    if tracking.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(format!("{function_name} requires node statistics, which are not tracked. Enable them via `Dialogue::set_node_statistics_tracking`."))
     }
 }
 
@@ -130,15 +300,16 @@ impl Dialogue {
     ///
     /// When the Dialogue is constructed, the Library is initialized with
     /// the built-in operators like `+`, `-`, and so on.
+    /// The `visited`, `visited_count`, `times_visited` and `last_visited` functions are bound to the [`VariableStorage`] of this dialogue and not part of the library.
     #[must_use]
     pub fn library(&self) -> &Library {
         &self.vm.library
     }
 
-    /// See [`Dialogue::library`].
+    /// See [`Dialogue::library`]. If the library is shared with other dialogues of a [`DialogueRuntime`], this dialogue gets its own copy first.
     #[must_use]
     pub fn library_mut(&mut self) -> &mut Library {
-        &mut self.vm.library
+        Arc::make_mut(&mut self.vm.library)
     }
 
     /// Gets the currently registered [`VariableStorage`].
@@ -150,6 +321,219 @@ impl Dialogue {
     pub fn variable_storage_mut(&mut self) -> &mut dyn VariableStorage {
         self.vm.variable_storage_mut()
     }
+
+    /// Sets how names passed to [`Dialogue::set_variable`] and [`Dialogue::variable`] are canonicalized.
+    /// Use [`VariableNameMode::Lenient`] to let game code omit the leading `$`.
+    pub fn set_variable_name_mode(&mut self, mode: VariableNameMode) -> &mut Self {
+        self.variable_name_mode = mode;
+        self
+    }
+
+    /// Gets how variable names are canonicalized. See [`Dialogue::set_variable_name_mode`].
+    #[must_use]
+    pub fn variable_name_mode(&self) -> VariableNameMode {
+        self.variable_name_mode
+    }
+
+    /// Stores a variable in the [`VariableStorage`] after canonicalizing its name according to the [`VariableNameMode`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`VariableStorageError::InvalidVariableName`] if the name is invalid, or the errors of [`VariableStorage::set`].
+    pub fn set_variable(&mut self, name: &str, value: impl Into<YarnValue>) -> Result<&mut Self> {
+        let name = canonicalize_variable_name(name, self.variable_name_mode)?.into_owned();
+        let value = value.into();
+        self.variable_storage_mut()
+            .set(name.clone(), value.clone())?;
+        self.vm
+            .record_replay_entry(|| ReplayEntry::VariableSet { name, value });
+        Ok(self)
+    }
+
+    /// Gets a variable from the [`VariableStorage`] after canonicalizing its name according to the [`VariableNameMode`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`VariableStorageError::InvalidVariableName`] if the name is invalid, or the errors of [`VariableStorage::get`].
+    pub fn variable(&self, name: &str) -> Result<YarnValue> {
+        let name = canonicalize_variable_name(name, self.variable_name_mode)?;
+        Ok(self.variable_storage().get(&name)?)
+    }
+
+    /// Evaluates a Yarn expression outside of any node, e.g. for quest triggers and UI bindings.
+    ///
+    /// Supports everything expressions inside nodes support: number, string and boolean literals, variables,
+    /// calls to the functions of the [`Dialogue::library`] and all operators, including their keyword forms like `and` and `gte`.
+    /// Variables are looked up like in [`Dialogue::variable`]. Variables are never written, but functions may have side effects.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::InvalidExpression`] if the expression cannot be parsed or its operands have the wrong types,
+    /// [`DialogueError::FunctionNotFound`] if it calls an unknown function, [`DialogueError::FunctionFailed`] if a called function fails,
+    /// and the errors of [`Dialogue::variable`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # use yarnspinner_core::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue.set_variable("$gold", 120).unwrap();
+    /// let can_enter = dialogue
+    ///     .evaluate_expression(r#"$gold >= 100 and not visited("Shop")"#)
+    ///     .unwrap();
+    /// assert_eq!(YarnValue::Boolean(true), can_enter);
+    /// ```
+    pub fn evaluate_expression(&self, expression: &str) -> Result<YarnValue> {
+        let variable = |name: &str| self.variable(name);
+        let context = ExpressionContext {
+            library: self.library(),
+            storage_functions: &self.vm.storage_functions,
+            variable: &variable,
+        };
+        evaluate(context, expression)
+    }
+
+    /// Unsets the flags of all `<<once>>` content, so it is shown again, e.g. for a New Game+.
+    /// Other variables, including the visit counts of nodes, are kept.
+    ///
+    /// The flags are the variables named by [`Library::generate_unique_once_variable`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of the [`VariableStorage`].
+    pub fn reset_once_flags(&mut self) -> Result<&mut Self> {
+        self.vm
+            .variable_storage
+            .retain(&mut |name, _| !name.starts_with(Library::ONCE_VARIABLE_PREFIX))?;
+        Ok(self)
+    }
+
+    /// Iterates over the names and values of all variables in the [`VariableStorage`].
+    pub fn variables(&self) -> impl Iterator<Item = (String, YarnValue)> {
+        self.variable_storage().variables().into_iter()
+    }
+
+    /// Gets the [`DialogueLogger`] that receives structured records of what this dialogue is doing.
+    #[must_use]
+    pub fn logger(&self) -> &dyn DialogueLogger {
+        self.vm.logger.as_ref()
+    }
+
+    /// Sets the [`DialogueLogger`] that receives structured records of what this dialogue is doing.
+    /// The default is [`LogCrateLogger`], use [`NoopLogger`] to discard the records.
+    pub fn set_logger(&mut self, logger: impl DialogueLogger + 'static) -> &mut Self {
+        self.vm.logger = Arc::new(logger);
+        self
+    }
+
+    /// Gets the [`SaliencyStrategy`] that selects which line of a line group or node of a node group is run.
+    #[must_use]
+    pub fn saliency_strategy(&self) -> &dyn SaliencyStrategy {
+        self.vm.saliency_strategy.as_ref()
+    }
+
+    /// Sets the [`SaliencyStrategy`] that selects which line of a line group or node of a node group is run.
+    /// The default is [`BestLeastRecentlyViewedSaliencyStrategy`].
+    pub fn set_saliency_strategy(
+        &mut self,
+        strategy: impl SaliencyStrategy + 'static,
+    ) -> &mut Self {
+        self.vm.saliency_strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Like [`Dialogue::set_saliency_strategy`], but for a strategy that was already type-erased, e.g. by a [`DialogueBuilder`].
+    pub(crate) fn set_shared_saliency_strategy(
+        &mut self,
+        strategy: Arc<dyn SaliencyStrategy>,
+    ) -> &mut Self {
+        self.vm.saliency_strategy = strategy;
+        self
+    }
+
+    /// Gets the [`OptionsPresentationPolicy`] that orders and limits the options of a [`DialogueEvent::Options`].
+    #[must_use]
+    pub fn options_presentation_policy(&self) -> &OptionsPresentationPolicy {
+        &self.vm.options_presentation_policy
+    }
+
+    /// Sets the [`OptionsPresentationPolicy`] that orders and limits the options of a [`DialogueEvent::Options`].
+    /// The default, [`OptionsPresentationPolicy::stable`], presents the options as written.
+    pub fn set_options_presentation_policy(
+        &mut self,
+        policy: OptionsPresentationPolicy,
+    ) -> &mut Self {
+        self.vm.options_presentation_policy = policy;
+        self
+    }
+
+    /// Gets the [`LineParser`] used by [`Dialogue::parse_markup`].
+    #[must_use]
+    pub fn line_parser(&self) -> &LineParser {
+        &self.line_parser
+    }
+
+    /// See [`Dialogue::line_parser`]. Use this to set the language of replacement markers
+    /// or to override marker processors like the one for `[select]` for this dialogue only.
+    #[must_use]
+    pub fn line_parser_mut(&mut self) -> &mut LineParser {
+        &mut self.line_parser
+    }
+
+    /// Pins all locale-dependent behavior of this dialogue, such as the plural rules used by [`Dialogue::parse_markup`], to the given language
+    /// regardless of the display language, e.g. for deterministic tests. Pass `None` to follow the display language again.
+    /// See [`LineParser::with_invariant_locale`].
+    pub fn set_invariant_locale(&mut self, locale: Option<Language>) -> &mut Self {
+        self.line_parser.set_invariant_locale(locale);
+        self
+    }
+
+    /// The language all locale-dependent behavior of this dialogue is pinned to, if any. See [`Dialogue::set_invariant_locale`].
+    #[must_use]
+    pub fn invariant_locale(&self) -> Option<&Language> {
+        self.line_parser.invariant_locale()
+    }
+
+    /// Gets the [`TextNormalizer`] that lines parsed by [`Dialogue::parse_markup`] are passed through.
+    #[must_use]
+    pub fn text_normalizer(&self) -> &TextNormalizer {
+        self.line_parser.text_normalizer()
+    }
+
+    /// Sets the [`TextNormalizer`] that lines parsed by [`Dialogue::parse_markup`] are passed through,
+    /// e.g. to enable smart quotes for all lines of this dialogue.
+    pub fn set_text_normalizer(&mut self, text_normalizer: TextNormalizer) -> &mut Self {
+        self.line_parser.set_text_normalizer(text_normalizer);
+        self
+    }
+
+    /// Gets the [`ValueFormatter`] used by [`Dialogue::expand_substitutions`]. Defaults to a [`LocaleValueFormatter`].
+    #[must_use]
+    pub fn value_formatter(&self) -> &dyn ValueFormatter {
+        self.value_formatter.as_ref()
+    }
+
+    /// Sets the [`ValueFormatter`] used by [`Dialogue::expand_substitutions`], e.g. [`LanguageValueFormatters`] with game-defined formats.
+    pub fn set_value_formatter(
+        &mut self,
+        value_formatter: impl ValueFormatter + 'static,
+    ) -> &mut Self {
+        self.value_formatter = Arc::new(value_formatter);
+        self
+    }
+
+    /// Replaces the `{0}`-style placeholders of a line's text with the given substitutions, formatted by the [`Dialogue::value_formatter`]
+    /// for the locale of the [`Dialogue::line_parser`]. See [`expand_substitutions`].
+    #[must_use]
+    pub fn expand_substitutions(&self, text: &str, substitutions: &[YarnValue]) -> String {
+        expand_substitutions(
+            text,
+            substitutions,
+            self.line_parser.locale(),
+            self.value_formatter.as_ref(),
+        )
+    }
 }
 
 // VM proxy
@@ -173,150 +557,1163 @@ impl Dialogue {
     /// Specifically, we cannot guarantee [`Send`] and [`Sync`] properly without a lot of [`std::sync::RwLock`] boilerplate. The original implementation
     /// also allows unsound parallel mutation of [`Dialogue`]'s state, which would result in a deadlock in our case.
     pub fn continue_(&mut self) -> Result<Vec<DialogueEvent>> {
-        self.vm.continue_(|vm, instruction| {
-            vm.run_instruction(instruction, |function, parameters| {
-                function.call(parameters)
-            })
-        })
+        self.continue_with_limit(None, None)
     }
 
-    /// Returns true if the [`Dialogue`] is in a state where [`Dialogue::continue_`] can be called.
-    pub fn can_continue(&self) -> bool {
-        self.vm.assert_can_continue().is_ok()
+    /// Like [`Dialogue::continue_`], but executes at most `max_instructions` instructions, e.g. to bound the time spent per frame.
+    ///
+    /// If the budget runs out before the dialogue reaches a line, options, a command or its end, the events produced so far are returned
+    /// and the [`Dialogue::state`] is [`DialogueState::Yielded`]. Call [`Dialogue::continue_`] or [`Dialogue::continue_with_budget`] again to resume,
+    /// e.g. on the next frame, so that long runs of `<<set>>` statements don't cause frame hitches on low-power hardware.
+    /// See [`DialogueScheduler`] for sharing a budget between many dialogues.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`Dialogue::continue_`].
+    pub fn continue_with_budget(&mut self, max_instructions: usize) -> Result<Vec<DialogueEvent>> {
+        self.continue_with_limit(Some(max_instructions), None)
     }
 
-    fn extend_variable_storage_from(&mut self, program: &Program) {
-        let initial: HashMap<String, YarnValue> = program
-            .initial_values
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone().into()))
-            .collect();
+    /// Like [`Dialogue::continue_`], but passes the given context to the functions registered with [`Library::add_function_with_context`],
+    /// e.g. to let them read the live state of the game.
+    ///
+    /// Functions expecting a context of a different type fail, just like they do when called by [`Dialogue::continue_`].
+    /// When replaying a [`ReplayLog`], no context is available, so set functions using the context
+    /// via [`Dialogue::set_non_deterministic_functions`] to replay their recorded return values instead.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`Dialogue::continue_`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # use yarnspinner_core::prelude::*;
+    /// struct Game {
+    ///     gold: u32,
+    /// }
+    ///
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue
+    ///     .library_mut()
+    ///     .add_function_with_context("gold", |game: &mut Game| game.gold);
+    ///
+    ///
+    /// fn update(dialogue: &mut Dialogue, game: &mut Game) -> yarnspinner_runtime::Result<Vec<DialogueEvent>> {
+    ///     dialogue.continue_with_context(game)
+    /// }
+    /// ```
+    pub fn continue_with_context(&mut self, context: &mut dyn Any) -> Result<Vec<DialogueEvent>> {
+        self.continue_with_limit(None, Some(context))
+    }
 
-        // Extend the VariableStorage with the initial values from the program
-        if let Err(e) = self.variable_storage_mut().extend(initial) {
-            error!(
-                "Failed to populate VariableStorage with initial values: {}",
-                e
-            );
+    fn continue_with_limit(
+        &mut self,
+        max_instructions: Option<usize>,
+        mut context: Option<&mut dyn Any>,
+    ) -> Result<Vec<DialogueEvent>> {
+        self.vm
+            .record_replay_entry(|| ReplayEntry::Continue { max_instructions });
+        let instructions_executed = self.vm.instructions_executed;
+        let mut events = Vec::new();
+        loop {
+            // Lines suppressed by the content filter are skipped within the same budget
+            let remaining_instructions = max_instructions.map(|max_instructions| {
+                let executed = self.vm.instructions_executed - instructions_executed;
+                max_instructions.saturating_sub(executed as usize)
+            });
+            let batch = self
+                .vm
+                .continue_(remaining_instructions, |vm, instruction| {
+                    vm.run_instruction(instruction, |function, parameters| {
+                        match context.as_deref_mut() {
+                            Some(context) => function.try_call_with_context(parameters, context),
+                            None => function.try_call(parameters),
+                        }
+                    })
+                })?;
+            let suppressed = self.apply_content_filter(batch, &mut events);
+            if !suppressed || self.vm.execution_state() != ExecutionState::WaitingForContinue {
+                break;
+            }
         }
+        self.notify_observers(&events);
+        Ok(events)
     }
 
-    /// Sets or replaces the [`Dialogue`]'s current [`Program`]. The program is replaced, all current state is reset.
-    pub fn replace_program(&mut self, program: Program) -> &mut Self {
-        self.vm.program.replace(program.clone());
-        self.vm.reset_state();
-        self.extend_variable_storage_from(&program);
-        self
+    /// Moves the events to `filtered`, applying the [`ContentFilter`] to the lines among them.
+    /// Returns `true` if a line was suppressed.
+    fn apply_content_filter(
+        &self,
+        events: Vec<DialogueEvent>,
+        filtered: &mut Vec<DialogueEvent>,
+    ) -> bool {
+        let Some(content_filter) = &self.content_filter else {
+            filtered.extend(events);
+            return false;
+        };
+        let mut suppressed = false;
+        for event in events {
+            let DialogueEvent::Line(mut line_id, substitutions) = event else {
+                filtered.push(event);
+                continue;
+            };
+            let mut suppress = false;
+            let records = content_filter.filter_line(line_id);
+            if records
+                .first()
+                .is_some_and(|record| record.action == LineFilterAction::Suppress)
+                && matches!(filtered.last(), Some(DialogueEvent::LineHints(_)))
+            {
+                filtered.pop();
+            }
+            for record in records {
+                match record.action {
+                    LineFilterAction::Suppress => suppress = true,
+                    LineFilterAction::Replace(replacement) => line_id = replacement,
+                    LineFilterAction::Annotate => {}
+                }
+                filtered.push(DialogueEvent::LineFiltered(record));
+            }
+            if suppress {
+                suppressed = true;
+            } else {
+                filtered.push(DialogueEvent::Line(line_id, substitutions));
+            }
+        }
+        suppressed
     }
 
-    /// Merges the currently set [`Program`] with the given one. If there is no program set, the given one is set.
-    pub fn add_program(&mut self, program: Program) -> &mut Self {
-        if let Some(existing_program) = self.vm.program.as_mut() {
-            *existing_program =
-                Program::combine(vec![existing_program.clone(), program.clone()]).unwrap();
-        } else {
-            self.vm.program.replace(program.clone());
-            self.vm.reset_state();
+    /// Registers a [`DialogueObserver`] that is notified of every event this dialogue returns from now on,
+    /// and returns the ID to remove it again. Any number of observers can be registered.
+    ///
+    /// Observers are shared with clones of this dialogue.
+    pub fn add_observer(
+        &mut self,
+        observer: impl DialogueObserver + 'static,
+    ) -> DialogueObserverId {
+        let id = DialogueObserverId(self.next_observer_id);
+        self.next_observer_id += 1;
+        self.observers.push((id, Arc::new(observer)));
+        id
+    }
+
+    /// Unregisters the observer with the given ID. Returns `false` if there is no such observer.
+    pub fn remove_observer(&mut self, id: DialogueObserverId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|(observer_id, _)| *observer_id != id);
+        self.observers.len() != len
+    }
+
+    /// Unregisters all observers.
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    fn notify_observers(&mut self, events: &[DialogueEvent]) {
+        for (_, observer) in &self.observers {
+            for event in events {
+                observer.on_event(event);
+            }
         }
-        self.extend_variable_storage_from(&program);
+        if let Some(recorder) = self.transcript_recorder.as_mut() {
+            for event in events {
+                recorder.record_event(event);
+            }
+        }
+        if let Some(coverage) = self.content_coverage.as_mut() {
+            for event in events {
+                coverage.record_event(event);
+            }
+        }
+    }
 
+    /// Sets the [`TranscriptRecorder`] that is fed with the lines, chosen options and commands of this dialogue, or removes it with `None`.
+    pub fn set_transcript_recorder(&mut self, recorder: Option<TranscriptRecorder>) -> &mut Self {
+        self.transcript_recorder = recorder;
         self
     }
 
-    /// Prepares the [`Dialogue`] that the user intends to start running a node.
-    ///
-    /// After this method is called, you call [`Dialogue::continue_`] to start executing it.
-    ///
-    /// If [`Dialogue::line_hints_enabled`] has been set, the next [`Dialogue::continue_`] call will return a [`DialogueEvent::LineHints`],
-    /// as the Dialogue determines which lines may be delivered during the `node_name` node's execution.
-    ///
-    /// ## Errors
-    ///
-    /// Returns an error if no node with the value of `node_name` has been loaded.
-    pub fn set_node(&mut self, node_name: impl Into<String>) -> Result<&mut Self> {
-        self.vm.set_node(node_name)?;
-        Ok(self)
+    /// Gets the [`TranscriptRecorder`], if one was set via [`Dialogue::set_transcript_recorder`].
+    #[must_use]
+    pub fn transcript_recorder(&self) -> Option<&TranscriptRecorder> {
+        self.transcript_recorder.as_ref()
     }
 
-    /// Immediately stops the [`Dialogue`]
-    ///
-    /// Returns unfinished [`DialogueEvent`]s that should be handled by the caller. The last is guaranteed to be [`DialogueEvent::DialogueComplete`].
-    pub fn stop(&mut self) -> Vec<DialogueEvent> {
-        self.vm.stop()
+    /// See [`Dialogue::transcript_recorder`]. Use this to set the final text of presented lines via [`TranscriptRecorder::set_line_text`].
+    pub fn transcript_recorder_mut(&mut self) -> Option<&mut TranscriptRecorder> {
+        self.transcript_recorder.as_mut()
     }
 
-    /// Unloads all nodes from the Dialogue.
-    pub fn unload_all(&mut self) {
-        self.vm.unload_programs()
+    /// Sets the [`ContentCoverage`] that is fed with the lines, options and nodes of this dialogue, or removes it with `None`.
+    pub fn set_content_coverage(&mut self, coverage: Option<ContentCoverage>) -> &mut Self {
+        self.content_coverage = coverage;
+        self
     }
 
-    /// Gets the names of the nodes in the currently loaded Program, if there is one.
+    /// Gets the [`ContentCoverage`], if one was set via [`Dialogue::set_content_coverage`].
     #[must_use]
-    pub fn node_names(&self) -> Option<impl Iterator<Item = &str>> {
-        self.vm
-            .program
-            .as_ref()
-            .map(|program| program.nodes.keys().map(|s| s.as_str()))
+    pub fn content_coverage(&self) -> Option<&ContentCoverage> {
+        self.content_coverage.as_ref()
     }
 
-    /// Returns the line ID that contains the original, uncompiled source
-    /// text for a node.
-    ///
-    /// A node's source text will only be present in the string table if its
-    /// `tags` header contains `rawText`.
-    ///
-    /// Because the [`Dialogue`] API is designed to be unaware
-    /// of the contents of the string table, this method does not test to
-    /// see if the string table contains an entry with the line ID. You will
-    /// need to test for that yourself.
-    #[must_use]
-    pub fn get_line_id_for_node(&self, node_name: &str) -> Option<LineId> {
-        self.get_node_logging_errors(node_name)
-            .map(|_| format!("line:{node_name}").into())
+    /// See [`Dialogue::content_coverage`].
+    pub fn content_coverage_mut(&mut self) -> Option<&mut ContentCoverage> {
+        self.content_coverage.as_mut()
     }
 
-    /// Returns the headers for the node `node_name`.
-    ///
-    /// The headers are all the key-value pairs defined in the node's source code
-    /// including the `tags` and `title` headers.
-    ///
-    /// Returns [`None`] if the node is not present in the program.
+    /// Returns `true` if the last call to [`Dialogue::continue_with_budget`] ran out of instructions
+    /// before the dialogue produced an event that needs to be handled, so it should be continued again. Same as checking for [`DialogueState::Yielded`].
     #[must_use]
-    pub fn get_headers_for_node(&self, node_name: &str) -> Option<HashMap<String, String>> {
-        self.get_node_logging_errors(node_name).map(|node| {
-            node.headers
-                .iter()
-                .map(|header| (header.key.clone(), header.value.clone()))
-                .collect()
-        })
+    pub fn is_suspended(&self) -> bool {
+        self.vm.is_suspended()
     }
 
-    /// Gets a value indicating whether a specified node exists in the [`Program`].
-    #[must_use]
-    pub fn node_exists(&self, node_name: &str) -> bool {
-        // Not calling `get_node_logging_errors` because this method does not write errors when there are no nodes.
-        if let Some(program) = self.vm.program.as_ref() {
-            program.nodes.contains_key(node_name)
-        } else {
-            error!("Tried to call NodeExists, but no program has been loaded");
-            false
-        }
+    /// The total number of instructions executed by this dialogue.
+    pub(crate) fn instructions_executed(&self) -> u64 {
+        self.vm.instructions_executed
     }
 
-    /// Gets the name of the node that this Dialogue is currently executing.
+    /// Returns true if the [`Dialogue`] is in a state where [`Dialogue::continue_`] can be called.
+    pub fn can_continue(&self) -> bool {
+        self.vm.assert_can_continue().is_ok()
+    }
+
+    /// Sets whether `<<wait seconds>>` commands are handled by the [`Dialogue`] itself.
+    /// If enabled, such commands are sent as [`DialogueEvent::Wait`] instead of [`DialogueEvent::Command`],
+    /// and [`Dialogue::tick`] continues the dialogue once the duration has elapsed. Disabled by default.
     ///
-    /// If [`Dialogue::continue_`] has never been called, this value will be [`None`].
-    #[must_use]
-    pub fn current_node(&self) -> Option<String> {
-        self.vm.current_node()
+    /// `wait` commands whose parameter is not a single non-negative number are still sent as [`DialogueEvent::Command`].
+    pub fn set_wait_command_handling(&mut self, enabled: bool) -> &mut Self {
+        self.vm.wait_command_handling = enabled;
+        self
     }
 
-    fn get_node_logging_errors(&self, node_name: &str) -> Option<Node> {
-        if let Some(program) = self.vm.program.as_ref() {
-            if program.nodes.is_empty() {
-                error!("No nodes are loaded");
+    /// Sets whether every [`DialogueEvent::Command`] carries a [`CommandCompletionToken`] in [`Command::completion_token`]
+    /// that must be marked as complete before the dialogue continues, e.g. once the animation started by `<<fade_out>>` finished.
+    /// Until then, [`Dialogue::continue_`] fails with [`DialogueError::CommandNotComplete`] and [`Dialogue::can_continue`] returns `false`.
+    /// Commands named in [`Dialogue::set_non_blocking_commands`] carry no token. Disabled by default.
+    ///
+    /// [`Dialogue::fast_forward`], [`Dialogue::step_back`] and [`Dialogue::set_node`] don't wait for pending commands.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue
+    ///     .set_command_completion(true)
+    ///     .set_non_blocking_commands(["play_sound"]);
+    /// # if dialogue.can_continue() {
+    /// for event in dialogue.continue_().unwrap() {
+    ///     if let DialogueEvent::Command(command) = event {
+    ///         if let Some(token) = command.completion_token {
+    ///             // Hand the token to the system running the command, which calls `token.complete()` once it is done
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn set_command_completion(&mut self, enabled: bool) -> &mut Self {
+        self.vm.command_completion = enabled;
+        self
+    }
+
+    /// Gets whether commands must be completed before the dialogue continues. See [`Dialogue::set_command_completion`].
+    #[must_use]
+    pub fn command_completion(&self) -> bool {
+        self.vm.command_completion
+    }
+
+    /// Sets the names of the commands that the dialogue does not wait for when [`Dialogue::set_command_completion`] is enabled,
+    /// e.g. commands that only play a sound. Replaces the previously set names.
+    pub fn set_non_blocking_commands(
+        &mut self,
+        command_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.vm.non_blocking_commands = command_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Gets the names of the commands that the dialogue does not wait for. See [`Dialogue::set_non_blocking_commands`].
+    pub fn non_blocking_commands(&self) -> impl Iterator<Item = &str> {
+        self.vm.non_blocking_commands.iter().map(String::as_str)
+    }
+
+    /// Sets the [`SandboxPolicy`] restricting the functions and commands that the loaded programs may use,
+    /// e.g. when running community-authored content. Pass `None` to permit everything, which is the default.
+    pub fn set_sandbox_policy(&mut self, sandbox_policy: Option<SandboxPolicy>) -> &mut Self {
+        self.vm.sandbox_policy = sandbox_policy;
+        self
+    }
+
+    /// Gets the [`SandboxPolicy`], if any. See [`Dialogue::set_sandbox_policy`].
+    #[must_use]
+    pub fn sandbox_policy(&self) -> Option<&SandboxPolicy> {
+        self.vm.sandbox_policy.as_ref()
+    }
+
+    /// Gets whether `<<wait>>` commands are handled by the [`Dialogue`]. See [`Dialogue::set_wait_command_handling`].
+    #[must_use]
+    pub fn wait_command_handling(&self) -> bool {
+        self.vm.wait_command_handling
+    }
+
+    /// Sets whether the [`NodeStatistics`] of every node are tracked, which scripts read via the functions
+    /// `times_visited(node)`, returning [`NodeStatistics::times_entered`], and `last_visited(node)`, returning [`NodeStatistics::last_visited`] or `0`.
+    /// Disabled by default, in which case calling either function fails with [`DialogueError::FunctionFailed`].
+    ///
+    /// `times_visited` differs from the `visited_count` of the original: `visited_count` counts how often a node was completed,
+    /// and only for the nodes the compiler tracks, while `times_visited` counts every entry of any node,
+    /// including each return to it from a `<<detour>>`.
+    ///
+    /// The statistics are stored as [`YarnValue::Integer`]s in the [`VariableStorage`] under the reserved prefix [`NODE_STATISTICS_VARIABLE_PREFIX`],
+    /// so they are saved and restored together with the other variables. Since every node that is entered writes them,
+    /// tracking cannot be used with a [`ReadOnlyVariableStorage`].
+    pub fn set_node_statistics_tracking(&mut self, enabled: bool) -> &mut Self {
+        self.vm
+            .node_statistics_tracking
+            .store(enabled, Ordering::Relaxed);
+        self
+    }
+
+    /// Gets whether the [`NodeStatistics`] of nodes are tracked. See [`Dialogue::set_node_statistics_tracking`].
+    #[must_use]
+    pub fn node_statistics_tracking(&self) -> bool {
+        self.vm.node_statistics_tracking.load(Ordering::Relaxed)
+    }
+
+    /// Reads the [`NodeStatistics`] of the given node, as tracked while [`Dialogue::set_node_statistics_tracking`] was enabled.
+    #[must_use]
+    pub fn node_statistics(&self, node_name: &str) -> NodeStatistics {
+        NodeStatistics::read(self.vm.variable_storage(), node_name)
+    }
+
+    /// Sets the [`ContentFilter`] that suppresses, replaces or annotates lines based on their hashtags, or removes it with `None`.
+    pub fn set_content_filter(&mut self, content_filter: Option<ContentFilter>) -> &mut Self {
+        self.content_filter = content_filter;
+        self
+    }
+
+    /// Gets the [`ContentFilter`], if one was set via [`Dialogue::set_content_filter`].
+    #[must_use]
+    pub fn content_filter(&self) -> Option<&ContentFilter> {
+        self.content_filter.as_ref()
+    }
+
+    /// See [`Dialogue::content_filter`]. Use this to change the rules when the player changes their content settings.
+    pub fn content_filter_mut(&mut self) -> Option<&mut ContentFilter> {
+        self.content_filter.as_mut()
+    }
+
+    /// Sets whether every [`DialogueEvent::Line`] is preceded by a [`DialogueEvent::LineHints`] describing what follows it,
+    /// e.g. to auto-advance into options or to show a different prompt on the last line of a conversation. Disabled by default.
+    pub fn set_line_hints(&mut self, enabled: bool) -> &mut Self {
+        self.vm.line_hints = enabled;
+        self
+    }
+
+    /// Gets whether lines are preceded by [`DialogueEvent::LineHints`]. See [`Dialogue::set_line_hints`].
+    #[must_use]
+    pub fn line_hints(&self) -> bool {
+        self.vm.line_hints
+    }
+
+    /// Enables [`Dialogue::step_back`] by keeping up to `max_checkpoints` checkpoints, one for every line and set of options presented.
+    /// Older checkpoints are discarded. Zero disables checkpointing, which is the default, and discards all checkpoints.
+    pub fn set_max_checkpoints(&mut self, max_checkpoints: usize) -> &mut Self {
+        self.vm.max_checkpoints = max_checkpoints;
+        if max_checkpoints == 0 {
+            self.vm.clear_checkpoints();
+        }
+        self
+    }
+
+    /// Gets the maximum number of checkpoints kept for [`Dialogue::step_back`]. See [`Dialogue::set_max_checkpoints`].
+    #[must_use]
+    pub fn max_checkpoints(&self) -> usize {
+        self.vm.max_checkpoints
+    }
+
+    /// Returns `true` if [`Dialogue::step_back`] has a checkpoint to return to.
+    #[must_use]
+    pub fn can_step_back(&self) -> bool {
+        self.vm.can_step_back()
+    }
+
+    /// Returns to the line or options presented before the most recent ones, e.g. for a "back" button.
+    ///
+    /// Variables written since then are restored to their previous values, and the next call to [`Dialogue::continue_`]
+    /// presents the line or options again. No [`DialogueEvent::NodeStart`] is sent if the line is in a different node than the current one.
+    /// Requires checkpointing to be enabled via [`Dialogue::set_max_checkpoints`]. Checkpoints are discarded by [`Dialogue::set_node`],
+    /// so this never returns into a previous conversation. They are also discarded when the program is unloaded or replaced.
+    ///
+    /// Returns `false` if there is no checkpoint to return to, in which case nothing changes.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of the [`VariableStorage`] when restoring variables.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue.set_max_checkpoints(50);
+    /// // After presenting some lines:
+    /// if dialogue.can_step_back() {
+    ///     dialogue.step_back().unwrap();
+    ///     let events = dialogue.continue_().unwrap();
+    ///     // Present the previous line again
+    /// }
+    /// ```
+    pub fn step_back(&mut self) -> Result<bool> {
+        let stepped_back = self.vm.step_back()?;
+        if stepped_back {
+            self.vm.record_replay_entry(|| ReplayEntry::SteppedBack);
+        }
+        Ok(stepped_back)
+    }
+
+    /// Interrupts the delivery of the current line, e.g. because the player skipped it, and returns a [`DialogueEvent::LineInterrupted`]
+    /// so view layers can stop typewriter effects and voice-over. Mirrors the interruption of dialogue views in Yarn Spinner for Unity.
+    ///
+    /// If `auto_advance` is `true`, the dialogue continues right away and the events of [`Dialogue::continue_`] follow the interruption.
+    /// Otherwise, the dialogue keeps waiting for [`Dialogue::continue_`] as usual. A line can only be interrupted once.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoLineToInterrupt`] if the dialogue is not waiting to continue after a [`DialogueEvent::Line`],
+    /// and the errors of [`Dialogue::continue_`] if `auto_advance` is `true`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// assert!(dialogue.interrupt_line(false).is_err());
+    /// ```
+    pub fn interrupt_line(&mut self, auto_advance: bool) -> Result<Vec<DialogueEvent>> {
+        let line_id = self
+            .vm
+            .current_line
+            .take()
+            .ok_or(DialogueError::NoLineToInterrupt)?;
+        let mut events = vec![DialogueEvent::LineInterrupted(line_id)];
+        self.notify_observers(&events);
+        if auto_advance {
+            events.extend(self.continue_()?);
+        }
+        Ok(events)
+    }
+
+    /// Sets the [`CommandRegistry`] that [`Dialogue::validate_program`] checks the commands of the program against,
+    /// or removes it with `None`. Without a registry, commands are not validated.
+    pub fn set_command_registry(&mut self, registry: Option<CommandRegistry>) -> &mut Self {
+        self.command_registry = registry;
+        self
+    }
+
+    /// Gets the [`CommandRegistry`], if one was set via [`Dialogue::set_command_registry`].
+    #[must_use]
+    pub fn command_registry(&self) -> Option<&CommandRegistry> {
+        self.command_registry.as_ref()
+    }
+
+    /// Sets the names of the commands that [`Dialogue::fast_forward`] stops at, e.g. commands that start a minigame
+    /// or otherwise need the player's attention. Replaces the previously set names. No command blocks by default.
+    pub fn set_blocking_commands(
+        &mut self,
+        command_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.blocking_commands = command_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Gets the names of the commands that [`Dialogue::fast_forward`] stops at. See [`Dialogue::set_blocking_commands`].
+    pub fn blocking_commands(&self) -> impl Iterator<Item = &str> {
+        self.blocking_commands.iter().map(String::as_str)
+    }
+
+    /// Skips ahead like the skip mode of a visual novel: continues the dialogue until it presents options,
+    /// runs a command set via [`Dialogue::set_blocking_commands`], hits a [`Breakpoint`] or completes.
+    ///
+    /// Lines are left out of the returned events, together with their [`DialogueEvent::LineHints`], but are still passed to
+    /// the observers and the [`TranscriptRecorder`]. Pending and encountered [`DialogueEvent::Wait`]s are skipped.
+    /// All other events are returned, so the caller must still run the commands that were skipped over for their side effects.
+    /// The event that stopped the skip is the last returned event.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`Dialogue::continue_`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue.set_blocking_commands(["minigame"]);
+    /// // When the player presses the skip button:
+    /// # if dialogue.can_continue() {
+    /// for event in dialogue.fast_forward().unwrap() {
+    ///     // Handle commands, options and the end of the dialogue as usual
+    /// }
+    /// # }
+    /// ```
+    pub fn fast_forward(&mut self) -> Result<Vec<DialogueEvent>> {
+        let mut skipped_events = Vec::new();
+        loop {
+            let mut stopped = false;
+            // Skipped commands are not waited for
+            self.vm.pending_command = None;
+            for event in self.continue_()? {
+                match &event {
+                    DialogueEvent::Line(..)
+                    | DialogueEvent::LineInterrupted(_)
+                    | DialogueEvent::LineFiltered(_)
+                    | DialogueEvent::LineHints(_)
+                    | DialogueEvent::Wait(_) => continue,
+                    DialogueEvent::Options(_)
+                    | DialogueEvent::BreakpointHit(_)
+                    | DialogueEvent::DialogueComplete => stopped = true,
+                    DialogueEvent::Command(command) => {
+                        stopped = self.blocking_commands.contains(&command.name);
+                    }
+                    DialogueEvent::NodeStart(_) | DialogueEvent::NodeComplete(_) => {}
+                }
+                skipped_events.push(event);
+            }
+            if stopped {
+                return Ok(skipped_events);
+            }
+        }
+    }
+
+    /// Sets a [`Breakpoint`] at the given location of the given node, e.g. for an in-editor dialogue debugger.
+    /// Reaching it pauses the dialogue with a [`DialogueEvent::BreakpointHit`] until [`Dialogue::continue_`] is called again.
+    /// The node does not need to be loaded yet. Setting the same breakpoint twice has no effect.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue
+    ///     .set_breakpoint("Start", BreakpointLocation::Line(3))
+    ///     .set_breakpoint("Shop", BreakpointLocation::Instruction(0));
+    /// assert_eq!(2, dialogue.breakpoints().count());
+    /// ```
+    pub fn set_breakpoint(
+        &mut self,
+        node_name: impl AsRef<str>,
+        location: BreakpointLocation,
+    ) -> &mut Self {
+        let breakpoint = Breakpoint::new(node_name, location);
+        if !self.vm.breakpoints.contains(&breakpoint) {
+            self.vm.breakpoints.push(breakpoint);
+        }
+        self
+    }
+
+    /// Removes the [`Breakpoint`] at the given location of the given node. Returns `false` if there is no such breakpoint.
+    /// Removing the breakpoint the dialogue is paused at does not resume it.
+    pub fn remove_breakpoint(&mut self, node_name: &str, location: BreakpointLocation) -> bool {
+        let len = self.vm.breakpoints.len();
+        self.vm.breakpoints.retain(|breakpoint| {
+            *breakpoint.node_name != *node_name || breakpoint.location != location
+        });
+        self.vm.breakpoints.len() != len
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.vm.breakpoints.clear();
+    }
+
+    /// The breakpoints set via [`Dialogue::set_breakpoint`], in the order they were set.
+    pub fn breakpoints(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.vm.breakpoints.iter()
+    }
+
+    /// Starts recording a [`ReplayLog`] of this dialogue, replacing the previous recording.
+    /// Start recording before calling [`Dialogue::set_node`] so that the log covers the whole session.
+    ///
+    /// Together with the variables at this point, the log captures the selected options, the values returned by functions
+    /// set via [`Dialogue::set_non_deterministic_functions`] and the candidates picked by the [`SaliencyStrategy`],
+    /// which covers any randomness the strategy uses.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue
+    ///     .set_non_deterministic_functions(["dice"])
+    ///     .record_replay();
+    /// // Run the dialogue as usual, then attach the log to the bug report:
+    /// let log = dialogue.take_replay_log().unwrap();
+    /// # assert!(log.is_empty());
+    /// ```
+    pub fn record_replay(&mut self) -> &mut Self {
+        self.vm.replay_log = Some(ReplayLog::new(self.vm.variable_storage.variables()));
+        self
+    }
+
+    /// The [`ReplayLog`] being recorded, if [`Dialogue::record_replay`] was called.
+    #[must_use]
+    pub fn replay_log(&self) -> Option<&ReplayLog> {
+        self.vm.replay_log.as_ref()
+    }
+
+    /// Stops recording and returns the [`ReplayLog`], if [`Dialogue::record_replay`] was called.
+    pub fn take_replay_log(&mut self) -> Option<ReplayLog> {
+        self.vm.replay_log.take()
+    }
+
+    /// Sets the names of the library functions whose return values are recorded in [`ReplayLog`]s, e.g. functions that roll dice
+    /// or read the system time. Replaces the previously set names. No function is recorded by default.
+    pub fn set_non_deterministic_functions(
+        &mut self,
+        function_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.vm.non_deterministic_functions = function_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Gets the names of the functions set via [`Dialogue::set_non_deterministic_functions`].
+    pub fn non_deterministic_functions(&self) -> impl Iterator<Item = &str> {
+        self.vm
+            .non_deterministic_functions
+            .iter()
+            .map(String::as_str)
+    }
+
+    /// Reproduces the session recorded in the given [`ReplayLog`]: restores the variables from the start of the recording
+    /// and passes every recorded input to the dialogue again. Functions set via [`Dialogue::set_non_deterministic_functions`]
+    /// are not called; their recorded values are used instead. The same programs must be loaded as during the recording.
+    ///
+    /// Returns the events of all recorded calls to [`Dialogue::continue_`]. Observers are notified as usual.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::ReplayDiverged`] if the dialogue asks for different inputs than the recorded ones,
+    /// e.g. because the program was changed since the recording, and the errors of the replayed calls.
+    pub fn replay(&mut self, log: &ReplayLog) -> Result<Vec<DialogueEvent>> {
+        self.variable_storage_mut().clear();
+        self.variable_storage_mut()
+            .extend(log.variables().clone())?;
+        self.vm.replay_cursor = Some(ReplayCursor::new(log));
+        let result = self.replay_calls();
+        self.vm.replay_cursor = None;
+        result
+    }
+
+    fn replay_calls(&mut self) -> Result<Vec<DialogueEvent>> {
+        let mut events = Vec::new();
+        while let Some((_, entry)) = self
+            .vm
+            .replay_cursor
+            .as_mut()
+            .map_or(Ok(None), ReplayCursor::next_call)?
+        {
+            match entry {
+                ReplayEntry::SetNode(node_name) => {
+                    self.set_node(node_name)?;
+                }
+                ReplayEntry::JumpToNode(node_name) => {
+                    self.jump_to_node(node_name)?;
+                }
+                ReplayEntry::DetourToNode(node_name) => {
+                    self.detour_to_node(node_name)?;
+                }
+                ReplayEntry::Continue { max_instructions } => {
+                    events.extend(self.continue_with_limit(max_instructions, None)?);
+                }
+                ReplayEntry::OptionSelected(option_id) => {
+                    self.set_selected_option(option_id)?;
+                }
+                ReplayEntry::VariableSet { name, value } => {
+                    self.set_variable(&name, value)?;
+                }
+                ReplayEntry::SteppedBack => {
+                    self.step_back()?;
+                }
+                ReplayEntry::Stopped => events.extend(self.stop()),
+                ReplayEntry::FunctionReturned { .. } | ReplayEntry::ContentSelected(_) => {
+                    unreachable!("Runtime inputs are rejected by the cursor")
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Advances the timer of a pending [`DialogueEvent::Wait`] by `delta`, e.g. the time since the last frame.
+    ///
+    /// Once the wait has elapsed, this calls [`Dialogue::continue_`] and returns its events.
+    /// Otherwise, or if there is no pending wait, an empty list is returned.
+    /// Calling [`Dialogue::continue_`] directly skips the rest of the wait.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`Dialogue::continue_`].
+    pub fn tick(&mut self, delta: Duration) -> Result<Vec<DialogueEvent>> {
+        let Some(remaining) = self.vm.remaining_wait else {
+            return Ok(Vec::new());
+        };
+        match remaining.checked_sub(delta).filter(|left| !left.is_zero()) {
+            Some(left) => {
+                self.vm.remaining_wait = Some(left);
+                Ok(Vec::new())
+            }
+            None => self.continue_(),
+        }
+    }
+
+    /// Returns the time left until [`Dialogue::tick`] continues the dialogue, if a [`DialogueEvent::Wait`] is pending.
+    #[must_use]
+    pub fn remaining_wait(&self) -> Option<Duration> {
+        self.vm.remaining_wait
+    }
+
+    fn extend_variable_storage_from(&mut self, program: &Program) {
+        let initial: HashMap<String, YarnValue> = program
+            .initial_values
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone().into()))
+            .collect();
+
+        // Extend the VariableStorage with the initial values from the program
+        if let Err(e) = self.variable_storage_mut().extend(initial) {
+            error!(
+                "Failed to populate VariableStorage with initial values: {}",
+                e
+            );
+        }
+    }
+
+    /// Sets or replaces the [`Dialogue`]'s current [`Program`], e.g. after the Yarn files were recompiled during development.
+    ///
+    /// If a node is currently being run, execution continues in the node of the same name in the new program.
+    /// The program counter is clamped to the new node's length, and pending options that point past its end are discarded.
+    /// The remaining options are renumbered, so their [`OptionId`]s match their position again.
+    /// Variables are carried over. Only variables that are not yet stored receive the initial values of the new program.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::CurrentNodeRemovedOnReload`] if the node currently being run does not exist in the new program.
+    /// The new program is loaded regardless, but the dialogue is stopped and [`Dialogue::set_node`] must be called before continuing.
+    ///
+    /// Returns [`DialogueError::OptionsChangedOnReload`] with the remaining options if the dialogue was waiting for an option to be selected
+    /// and some of the presented options were discarded. The new program is loaded regardless, and the remaining options must be presented again
+    /// since the IDs of the old ones are no longer valid. If no option remains, the dialogue is stopped.
+    pub fn replace_program(&mut self, program: Program) -> Result<&mut Self> {
+        let initial_values = program.initial_values.clone();
+        let result = self.vm.replace_program(program);
+        let missing: HashMap<String, YarnValue> = initial_values
+            .into_iter()
+            .filter(|(name, _)| !self.variable_storage().contains(name))
+            .map(|(name, value)| (name, value.into()))
+            .collect();
+        if let Err(e) = self.variable_storage_mut().extend(missing) {
+            error!(
+                "Failed to populate VariableStorage with initial values: {}",
+                e
+            );
+        }
+        result?;
+        Ok(self)
+    }
+
+    /// Merges the currently set [`Program`] with the given one. If there is no program set, the given one is set.
+    ///
+    /// The program is linked when it is loaded, see [`ProgramHandle`], so afterwards its instructions can only be run, not read back.
+    /// Call [`Dialogue::validate_program`] once the [`Library`] is set up to find calls to missing functions before running it.
+    pub fn add_program(&mut self, program: Program) -> &mut Self {
+        self.extend_variable_storage_from(&program);
+        if let Some(existing_program) = self.vm.program.as_mut() {
+            existing_program.merge(program);
+        } else {
+            self.vm.set_program(ProgramHandle::new(program));
+            self.vm.reset_state();
+        }
+
+        self
+    }
+
+    /// Sets the [`Program`] behind the given handle, replacing the current one, without copying it.
+    /// Use this to run the same program in many dialogues at once, see [`DialogueRuntime`].
+    /// If [`Dialogue::add_program`] is called afterwards, this dialogue gets its own copy of the program to merge into.
+    pub fn set_shared_program(&mut self, program: &ProgramHandle) -> &mut Self {
+        self.extend_variable_storage_from(&program.program);
+        self.vm.set_program(program.clone());
+        self.vm.reset_state();
+        self
+    }
+
+    /// Gets a handle to the current [`Program`] that other dialogues can share via [`Dialogue::set_shared_program`].
+    #[must_use]
+    pub fn program_handle(&self) -> Option<ProgramHandle> {
+        self.vm.program.clone()
+    }
+
+    /// Checks every function call of the current program against the [`Dialogue::library`] and the functions the dialogue provides itself,
+    /// reporting all missing functions and calls with the wrong number of parameters at once.
+    /// If a [`CommandRegistry`] is set via [`Dialogue::set_command_registry`], commands are checked against its schemas as well.
+    /// Returns an empty report if no program is loaded.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # use yarnspinner_core::prelude::*;
+    /// # let program = Program::default();
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// dialogue.add_program(program);
+    /// let report = dialogue.validate_program();
+    /// for issue in &report.issues {
+    ///     eprintln!("{issue}");
+    /// }
+    /// assert!(report.is_valid());
+    /// ```
+    #[must_use]
+    pub fn validate_program(&self) -> ProgramValidationReport {
+        let Some(program) = self.vm.program.as_ref() else {
+            return ProgramValidationReport::default();
+        };
+        let mut report = validate_function_calls(&program.linked, |function_name| {
+            self.vm
+                .library
+                .get(function_name)
+                .or_else(|| self.vm.storage_functions.get(function_name))
+        });
+        if let Some(registry) = &self.command_registry {
+            report
+                .issues
+                .extend(validate_commands(&program.linked, registry).issues);
+            report.issues.sort_by(|a, b| {
+                let (a, b) = (a.location(), b.location());
+                (&a.node_name, a.instruction).cmp(&(&b.node_name, b.instruction))
+            });
+        }
+        report
+    }
+
+    /// Prepares the [`Dialogue`] that the user intends to start running a node.
+    ///
+    /// After this method is called, you call [`Dialogue::continue_`] to start executing it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no node with the value of `node_name` has been loaded.
+    /// Pass a [`NodeHandle`] from [`Dialogue::node`] to rule this out.
+    pub fn set_node(&mut self, node_name: impl Into<String>) -> Result<&mut Self> {
+        let node_name = node_name.into();
+        self.vm.set_node(&node_name)?;
+        self.vm.clear_checkpoints();
+        self.vm
+            .record_replay_entry(|| ReplayEntry::SetNode(node_name));
+        Ok(self)
+    }
+
+    /// Sets the node that [`Dialogue::start`] starts at, or `None` to pick it from the program, see [`Dialogue::start_node`].
+    pub fn set_start_node(&mut self, node_name: Option<String>) -> &mut Self {
+        self.start_node = node_name;
+        self
+    }
+
+    /// Gets the node that [`Dialogue::start`] starts at:
+    /// 1. The node set via [`Dialogue::set_start_node`], if any, whether it exists or not.
+    /// 2. Otherwise, the node with a `start` header that is empty or `true`, e.g. `start: true`. If several nodes have one, the first by name.
+    ///    Nodes with any other value, e.g. `start: false`, are not start nodes.
+    /// 3. Otherwise, the node named `Start`, if it exists.
+    #[must_use]
+    pub fn start_node(&self) -> Option<&str> {
+        if let Some(start_node) = &self.start_node {
+            return Some(start_node);
+        }
+        let program = self.vm.program.as_ref()?;
+        program
+            .program
+            .nodes
+            .values()
+            .filter(|node| {
+                node.headers.iter().any(|header| {
+                    header.key == "start" && matches!(header.value.trim(), "" | "true")
+                })
+            })
+            .map(|node| node.name.as_str())
+            .min()
+            .or_else(|| {
+                program
+                    .program
+                    .nodes
+                    .get_key_value("Start")
+                    .map(|(name, _)| name.as_str())
+            })
+    }
+
+    /// Starts the dialogue at its [`Dialogue::start_node`] and returns the first batch of events,
+    /// i.e. the same as calling [`Dialogue::set_node`] followed by [`Dialogue::continue_`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoStartNode`] if there is no start node, and otherwise the errors of [`Dialogue::start_at`].
+    pub fn start(&mut self) -> Result<Vec<DialogueEvent>> {
+        if self.vm.program.is_none() {
+            return Err(DialogueError::NoProgramLoaded);
+        }
+        let start_node = self
+            .start_node()
+            .ok_or(DialogueError::NoStartNode)?
+            .to_owned();
+        self.start_at(start_node)
+    }
+
+    /// Starts the dialogue at the given node and returns the first batch of events,
+    /// i.e. the same as calling [`Dialogue::set_node`] followed by [`Dialogue::continue_`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`Dialogue::set_node`] and [`Dialogue::continue_`].
+    pub fn start_at(&mut self, node_name: impl Into<String>) -> Result<Vec<DialogueEvent>> {
+        self.set_node(node_name)?;
+        self.continue_()
+    }
+
+    /// Jumps to the given node like the `<<jump>>` statement, e.g. from a debug console.
+    ///
+    /// Unlike [`Dialogue::set_node`], this completes the node currently being run, so the next call to [`Dialogue::continue_`]
+    /// returns a [`DialogueEvent::NodeComplete`] for it before the [`DialogueEvent::NodeStart`] of the new node.
+    /// Any pending detours are discarded, just like when a detoured node jumps.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no node with the value of `node_name` has been loaded. The dialogue is left unchanged in that case.
+    pub fn jump_to_node(&mut self, node_name: impl Into<String>) -> Result<&mut Self> {
+        let node_name = node_name.into();
+        self.vm.jump_to_node(&node_name)?;
+        self.vm
+            .record_replay_entry(|| ReplayEntry::JumpToNode(node_name));
+        Ok(self)
+    }
+
+    /// Detours to the given node like the `<<detour>>` statement, e.g. from a debug console.
+    ///
+    /// The next call to [`Dialogue::continue_`] starts running the given node. Once it ends or reaches a `<<return>>`,
+    /// execution resumes in the current node where it left off, with a [`DialogueEvent::NodeStart`] for it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no node is currently being run or if no node with the value of `node_name` has been loaded.
+    /// The dialogue is left unchanged in that case.
+    pub fn detour_to_node(&mut self, node_name: impl Into<String>) -> Result<&mut Self> {
+        let return_program_counter = self.vm.program_counter();
+        let node_name = node_name.into();
+        self.vm.detour_to_node(&node_name, return_program_counter)?;
+        self.vm
+            .record_replay_entry(|| ReplayEntry::DetourToNode(node_name));
+        Ok(self)
+    }
+
+    /// Immediately stops the [`Dialogue`]
+    ///
+    /// Returns unfinished [`DialogueEvent`]s that should be handled by the caller. The last is guaranteed to be [`DialogueEvent::DialogueComplete`].
+    pub fn stop(&mut self) -> Vec<DialogueEvent> {
+        let events = self.vm.stop();
+        self.vm.record_replay_entry(|| ReplayEntry::Stopped);
+        self.notify_observers(&events);
+        events
+    }
+
+    /// Unloads all nodes from the Dialogue.
+    pub fn unload_all(&mut self) {
+        self.vm.unload_programs()
+    }
+
+    /// Gets the names of the nodes in the currently loaded Program, if there is one.
+    #[must_use]
+    pub fn node_names(&self) -> Option<impl Iterator<Item = &str>> {
+        self.vm
+            .program
+            .as_ref()
+            .map(|program| program.program.nodes.keys().map(|s| s.as_str()))
+    }
+
+    /// Returns the line ID that contains the original, uncompiled source
+    /// text for a node.
+    ///
+    /// A node's source text will only be present in the string table if its
+    /// `tags` header contains `rawText`.
+    ///
+    /// Because the [`Dialogue`] API is designed to be unaware
+    /// of the contents of the string table, this method does not test to
+    /// see if the string table contains an entry with the line ID. You will
+    /// need to test for that yourself.
+    #[must_use]
+    pub fn get_line_id_for_node(&self, node_name: &str) -> Option<LineId> {
+        self.get_node_logging_errors(node_name)
+            .map(|_| format!("line:{node_name}").into())
+    }
+
+    /// Returns the headers for the node `node_name`.
+    ///
+    /// The headers are all the key-value pairs defined in the node's source code
+    /// including the `tags` and `title` headers.
+    ///
+    /// Returns [`None`] if the node is not present in the program.
+    #[must_use]
+    pub fn get_headers_for_node(&self, node_name: &str) -> Option<HashMap<String, String>> {
+        self.get_node_logging_errors(node_name).map(|node| {
+            node.headers
+                .iter()
+                .map(|header| (header.key.clone(), header.value.clone()))
+                .collect()
+        })
+    }
+
+    /// Returns the names of all loaded nodes that can be run after starting at the given node, including the node itself, in alphabetical order.
+    /// See [`Program::reachable_nodes`], which does the same for a program that is not loaded yet.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoProgramLoaded`] if no program is loaded, or [`DialogueError::InvalidNode`] if it has no node with that name.
+    pub fn reachable_nodes(&self, from: &str) -> Result<Vec<String>> {
+        let node_name = self.vm.node_name(from)?;
+        let program = self
+            .vm
+            .program
+            .as_ref()
+            .ok_or(DialogueError::NoProgramLoaded)?;
+        Ok(program
+            .linked
+            .reachable_nodes(&node_name)
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    /// Returns the names of all loaded nodes that cannot be reached from the [`Dialogue::start_node`], in alphabetical order,
+    /// e.g. to fail a build when content was orphaned by a refactor.
+    ///
+    /// Nodes that are only run via a jump to a name known at runtime, e.g. `<<jump {$destination}>>`, or that the game starts directly are included.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoStartNode`] if there is no start node, and otherwise the errors of [`Dialogue::reachable_nodes`].
+    pub fn unreachable_nodes(&self) -> Result<Vec<String>> {
+        let start_node = self.start_node().ok_or(DialogueError::NoStartNode)?;
+        let reachable = self.reachable_nodes(start_node)?;
+        let mut unreachable: Vec<_> = self
+            .node_names()
+            .into_iter()
+            .flatten()
+            .filter(|node_name| !reachable.iter().any(|reachable| reachable == node_name))
+            .map(ToOwned::to_owned)
+            .collect();
+        unreachable.sort_unstable();
+        Ok(unreachable)
+    }
+
+    /// Gets a [`NodeHandle`] for the node with the given name, which can be passed to [`Dialogue::set_node`] without the risk of a typo.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoProgramLoaded`] if no program is loaded, or [`DialogueError::InvalidNode`] if it has no node with that name.
+    pub fn node(&self, node_name: &str) -> Result<NodeHandle> {
+        self.vm.node_name(node_name).map(NodeHandle::new)
+    }
+
+    /// Gets [`NodeHandle`]s for all nodes of the loaded program, sorted by name.
+    pub fn node_handles(&self) -> impl Iterator<Item = NodeHandle> + '_ {
+        self.vm
+            .program
+            .iter()
+            .flat_map(|program| program.program.nodes.keys())
+            .filter_map(|node_name| self.vm.node_name(node_name).ok())
+            .map(NodeHandle::new)
+    }
+
+    /// Gets a value indicating whether a specified node exists in the [`Program`].
+    #[must_use]
+    pub fn node_exists(&self, node_name: &str) -> bool {
+        // Not calling `get_node_logging_errors` because this method does not write errors when there are no nodes.
+        if let Some(program) = self.vm.program.as_ref() {
+            program.program.nodes.contains_key(node_name)
+        } else {
+            error!("Tried to call NodeExists, but no program has been loaded");
+            false
+        }
+    }
+
+    /// Returns `true` if all `when:` headers of the given node hold for the current variables, without running the node,
+    /// e.g. to show quest markers or pick a bark. Nodes without `when:` headers are always available.
+    ///
+    /// Supported conditions are `always`, `once`, i.e. the node was not visited yet, `once if <expression>`,
+    /// and any boolean expression as supported by [`Dialogue::evaluate_expression`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::NoProgramLoaded`] or [`DialogueError::InvalidNode`] if the node does not exist,
+    /// [`DialogueError::InvalidExpression`] if a condition is not a boolean expression, and the errors of [`Dialogue::evaluate_expression`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # use yarnspinner_core::prelude::*;
+    /// # let mut program = Program::default();
+    /// # let headers = vec![Header { key: "when".to_owned(), value: "once if $gold >= 100".to_owned() }];
+    /// # program.nodes.insert("Shop".to_owned(), Node { name: "Shop".to_owned(), headers, ..Default::default() });
+    /// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    /// // Shop has the header `when: once if $gold >= 100`
+    /// dialogue.add_program(program);
+    /// dialogue.set_variable("$gold", 150).unwrap();
+    /// assert!(dialogue.is_node_available("Shop").unwrap());
+    /// ```
+    pub fn is_node_available(&self, node_name: &str) -> Result<bool> {
+        let program = self
+            .vm
+            .program
+            .as_ref()
+            .ok_or(DialogueError::NoProgramLoaded)?;
+        let node =
+            program
+                .program
+                .nodes
+                .get(node_name)
+                .ok_or_else(|| DialogueError::InvalidNode {
+                    node_name: node_name.to_owned(),
+                })?;
+        let variable = |name: &str| self.variable(name);
+        let context = ExpressionContext {
+            library: self.library(),
+            storage_functions: &self.vm.storage_functions,
+            variable: &variable,
+        };
+        for condition in when_conditions(node) {
+            if !is_when_condition_met(context, node_name, condition)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Gets the name of the node that this Dialogue is currently executing.
+    ///
+    /// If [`Dialogue::continue_`] has never been called, this value will be [`None`].
+    /// The name is shared with the [`DialogueEvent::NodeStart`] that announced the node, so this does not allocate.
+    #[must_use]
+    pub fn current_node(&self) -> Option<Arc<str>> {
+        self.vm.current_node()
+    }
+
+    fn get_node_logging_errors(&self, node_name: &str) -> Option<Node> {
+        if let Some(program) = self.vm.program.as_ref() {
+            if program.program.nodes.is_empty() {
+                error!("No nodes are loaded");
                 None
-            } else if let Some(node) = program.nodes.get(node_name) {
+            } else if let Some(node) = program.program.nodes.get(node_name) {
                 Some(node.clone())
             } else {
                 error!("No node named {node_name}");
@@ -343,9 +1740,106 @@ impl Dialogue {
     /// - [`Dialogue::continue_`]
     pub fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<&mut Self> {
         self.vm.set_selected_option(selected_option_id)?;
+        self.vm
+            .record_replay_entry(|| ReplayEntry::OptionSelected(selected_option_id));
+        if let Some(recorder) = self.transcript_recorder.as_mut() {
+            recorder.record_selection(selected_option_id);
+        }
+        if let Some(coverage) = self.content_coverage.as_mut() {
+            coverage.record_selection(selected_option_id);
+        }
         Ok(self)
     }
 
+    /// Parses the markup of a line, e.g. the text fetched for a [`DialogueEvent::Line`] from a [`TextProvider`],
+    /// using this dialogue's [`LineParser`].
+    pub fn parse_markup(&self, line: &str) -> Result<ParsedMarkup> {
+        Ok(self.line_parser.parse_markup(line)?)
+    }
+
+    /// Parses the markup of a line like [`Dialogue::parse_markup`], but with the given options instead of the ones of the [`LineParser`],
+    /// e.g. to opt a single line out of `[nomarkup]` handling.
+    pub fn parse_markup_with_options(
+        &self,
+        line: &str,
+        options: MarkupParseOptions,
+    ) -> Result<ParsedMarkup> {
+        Ok(self.line_parser.parse_markup_with_options(line, options)?)
+    }
+
+    /// Renders the text of a line as accessible plain text, e.g. for screen readers: the substitutions are expanded
+    /// via [`Dialogue::expand_substitutions`], the markup is parsed via [`Dialogue::parse_markup`] and the result is passed to the [`PlainTextRenderer`].
+    pub fn render_plain_text(
+        &self,
+        text: &str,
+        substitutions: &[YarnValue],
+        renderer: &PlainTextRenderer,
+    ) -> Result<PlainTextLine> {
+        let text = self.expand_substitutions(text, substitutions);
+        Ok(renderer.render(&self.parse_markup(&text)?))
+    }
+
+    /// Registers debug info produced by the compiler, which is used by [`Dialogue::diagnose`] to point errors at the original Yarn source.
+    /// Replaces existing debug info for the same nodes.
+    pub fn add_debug_info(
+        &mut self,
+        debug_info: impl IntoIterator<Item = NodeDebugInfo>,
+    ) -> &mut Self {
+        self.debug_info.extend(
+            debug_info
+                .into_iter()
+                .map(|info| (info.node_name.clone(), info)),
+        );
+        self
+    }
+
+    /// Enriches an error returned by this [`Dialogue`] with a [`DialogueErrorCode`], the offending node and instruction,
+    /// and, if registered via [`Dialogue::add_debug_info`], the position in the original Yarn source.
+    ///
+    /// The location is taken from the last call to [`Dialogue::continue_`], so call this right after receiving the error.
+    #[must_use]
+    pub fn diagnose(&self, error: &DialogueError) -> DialogueDiagnostic {
+        let location = self.vm.last_error_location().cloned();
+        let debug_info = location
+            .as_ref()
+            .and_then(|location| self.debug_info.get(&location.node_name));
+        DialogueDiagnostic {
+            code: error.code(),
+            message: error.to_string(),
+            file_name: debug_info.map(|info| info.file_name.clone()),
+            position: debug_info
+                .zip(location.as_ref())
+                .and_then(|(info, location)| info.get_position(location.instruction)),
+            location,
+        }
+    }
+
+    /// Gets the statistics about the instructions executed by this dialogue. Requires the `vm_profiling` feature.
+    #[cfg(feature = "vm_profiling")]
+    #[must_use]
+    pub fn profile_report(&self) -> &ProfileReport {
+        &self.vm.profile
+    }
+
+    /// Clears the statistics returned by [`Dialogue::profile_report`]. Requires the `vm_profiling` feature.
+    #[cfg(feature = "vm_profiling")]
+    pub fn reset_profile(&mut self) -> &mut Self {
+        self.vm.profile = ProfileReport::default();
+        self
+    }
+
+    /// Gets the current [`DialogueState`], e.g. to decide whether to show a "continue" prompt.
+    #[must_use]
+    pub fn state(&self) -> DialogueState {
+        self.vm.execution_state().into()
+    }
+
+    /// Returns `true` if the dialogue reached a [`Breakpoint`] and has not been continued since.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.vm.is_paused()
+    }
+
     /// Gets a value indicating whether the Dialogue is currently executing Yarn instructions.
     #[must_use]
     pub fn is_active(&self) -> bool {
@@ -360,8 +1854,9 @@ impl Dialogue {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use yarnspinner_core::prelude::instruction::InstructionType;
 
     #[test]
     fn is_send_sync() {
@@ -371,4 +1866,1367 @@ mod tests {
     }
 
     fn accept_send_sync(_: impl Send + Sync) {}
+
+    #[test]
+    fn canonicalizes_variable_names() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        assert!(matches!(
+            dialogue.set_variable("gold", 1.0),
+            Err(DialogueError::VariableStorageError(
+                VariableStorageError::InvalidVariableName { .. }
+            ))
+        ));
+
+        dialogue.set_variable_name_mode(VariableNameMode::Lenient);
+        dialogue.set_variable("gold", 1.0).unwrap();
+        assert_eq!(YarnValue::Number(1.0), dialogue.variable("$gold").unwrap());
+        assert_eq!(YarnValue::Number(1.0), dialogue.variable(" gold").unwrap());
+        assert!(dialogue.set_variable("$gold!", 1.0).is_err());
+        assert!(dialogue
+            .variable_storage_mut()
+            .set("$2gold".to_owned(), 1.0.into())
+            .is_err());
+    }
+
+    #[test]
+    fn node_names_are_shared_between_events() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1]));
+        dialogue.set_node("Start").unwrap();
+        let first_visit = dialogue.continue_().unwrap();
+        dialogue.set_node("Start").unwrap();
+        let second_visit = dialogue.continue_().unwrap();
+
+        let (DialogueEvent::NodeStart(first), DialogueEvent::NodeStart(second)) =
+            (&first_visit[0], &second_visit[0])
+        else {
+            panic!("Expected both visits to start with a NodeStart event");
+        };
+        assert!(Arc::ptr_eq(first, second));
+        assert!(Arc::ptr_eq(first, &dialogue.current_node().unwrap()));
+    }
+
+    #[test]
+    fn replacing_program_resumes_in_node_with_same_name() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2]));
+        dialogue.set_node("Start").unwrap();
+        let events = dialogue.continue_().unwrap();
+        assert_eq!(DialogueEvent::Line(1, vec![]), events[1]);
+
+        dialogue
+            .replace_program(program_with_lines("Start", [1, 3]))
+            .unwrap();
+        let events = dialogue.continue_().unwrap();
+        assert_eq!(vec![DialogueEvent::Line(3, vec![])], events);
+    }
+
+    #[test]
+    fn replacing_program_keeps_variables() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        let mut program = program_with_lines("Start", [1]);
+        program
+            .initial_values
+            .insert("$gold".to_owned(), 1.0_f32.into());
+        dialogue.add_program(program.clone());
+        dialogue
+            .variable_storage_mut()
+            .set("$gold".to_owned(), 5.0.into())
+            .unwrap();
+
+        dialogue.replace_program(program).unwrap();
+        assert_eq!(
+            YarnValue::Number(5.0),
+            dialogue.variable_storage().get("$gold").unwrap()
+        );
+    }
+
+    #[test]
+    fn replacing_program_errors_when_current_node_is_removed() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2]));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        let result = dialogue.replace_program(program_with_lines("Other", [1]));
+        assert!(matches!(
+            result,
+            Err(DialogueError::CurrentNodeRemovedOnReload { node_name }) if node_name == "Start"
+        ));
+        assert!(!dialogue.can_continue());
+        assert!(dialogue.node_exists("Other"));
+    }
+
+    #[test]
+    fn replacing_program_renumbers_remaining_options() {
+        let add_option = |tag_id, destination| {
+            InstructionType::AddOption(instruction::AddOptionInstruction {
+                tag_id,
+                destination,
+                substitution_count: 0,
+                has_condition: false,
+            })
+        };
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let instructions = [
+            add_option(1, 6),
+            add_option(2, 4),
+            InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+            InstructionType::PeekAndJump(instruction::PeekAndJumpInstruction {}),
+            run_line(20),
+            InstructionType::Stop(instruction::StopInstruction {}),
+            run_line(10),
+            InstructionType::Stop(instruction::StopInstruction {}),
+        ];
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions("Start", instructions.clone()));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        // The first option now points past the end of the node
+        let result = dialogue.replace_program(program_with_instructions(
+            "Start",
+            instructions[..6].to_vec(),
+        ));
+        let Err(DialogueError::OptionsChangedOnReload { options, .. }) = result else {
+            panic!("Expected the options to change, got {result:?}");
+        };
+        assert_eq!(1, options.len());
+        assert_eq!((OptionId(0), 2), (options[0].id, options[0].tag_id));
+
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        assert_eq!(
+            vec![DialogueEvent::Line(20, vec![])],
+            dialogue.continue_().unwrap()
+        );
+
+        dialogue
+            .replace_program(program_with_instructions("Start", instructions.clone()))
+            .unwrap();
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+        let result = dialogue.replace_program(program_with_instructions(
+            "Start",
+            instructions[..4].to_vec(),
+        ));
+        assert!(matches!(
+            result,
+            Err(DialogueError::OptionsChangedOnReload { options, .. }) if options.is_empty()
+        ));
+        assert!(!dialogue.can_continue());
+    }
+
+    #[test]
+    fn marker_processors_can_be_overridden_per_dialogue() {
+        #[derive(Debug, Clone)]
+        struct Unselect;
+
+        impl AttributeMarkerProcessor for Unselect {
+            fn replacement_text_for_marker(
+                &self,
+                _marker: &MarkupAttribute,
+                _language: &Language,
+            ) -> core::result::Result<String, String> {
+                Ok("nobody".to_owned())
+            }
+
+            fn clone_box(&self) -> Box<dyn AttributeMarkerProcessor> {
+                Box::new(self.clone())
+            }
+        }
+
+        let line = "[select value=f m=he f=she/] waved";
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        assert_eq!("she waved", dialogue.parse_markup(line).unwrap().text);
+
+        dialogue
+            .line_parser_mut()
+            .register_marker_processor("select", Box::new(Unselect));
+        assert_eq!("nobody waved", dialogue.parse_markup(line).unwrap().text);
+        assert_eq!("she waved", parse_markup(line).unwrap().text);
+    }
+
+    #[test]
+    fn diagnoses_errors_with_source_position() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 0,
+                }),
+                InstructionType::PushFloat(instruction::PushFloatInstruction { value: 0.0 }),
+                InstructionType::CallFunc(instruction::CallFunctionInstruction {
+                    function_name: "missing".to_owned(),
+                }),
+            ],
+        ));
+        let mut debug_info = NodeDebugInfo::new("Start.yarn", "Start");
+        debug_info.line_positions.insert(
+            2,
+            Position {
+                line: 3,
+                character: 2,
+            },
+        );
+        dialogue.add_debug_info([debug_info]);
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        let error = dialogue.continue_().unwrap_err();
+        let diagnostic = dialogue.diagnose(&error);
+        assert_eq!(DialogueErrorCode::FunctionNotFound, diagnostic.code);
+        assert_eq!(
+            Some(InstructionLocation {
+                node_name: "Start".to_owned(),
+                instruction: 2,
+            }),
+            diagnostic.location
+        );
+        let rendered = diagnostic.to_string();
+        assert!(rendered.starts_with("error[YS1010]: Function \"missing\" not found"));
+        assert!(rendered.contains("  --> Start.yarn:4:3\n"));
+        assert!(rendered.ends_with("   = note: while running node `Start`, instruction 2\n"));
+    }
+
+    #[test]
+    fn handles_wait_commands_when_enabled() {
+        let run_command = |text: &str| {
+            InstructionType::RunCommand(instruction::RunCommandInstruction {
+                command_text: text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_command("wait 0.5"),
+                run_command("wait soon"),
+                InstructionType::Stop(instruction::StopInstruction {}),
+            ],
+        ));
+        dialogue.set_node("Start").unwrap();
+        let events = dialogue.continue_().unwrap();
+        assert!(
+            matches!(&events[..], [DialogueEvent::NodeStart(_), DialogueEvent::Command(command)] if command.name == WAIT_COMMAND)
+        );
+        assert!(dialogue.tick(Duration::from_secs(1)).unwrap().is_empty());
+
+        dialogue.set_node("Start").unwrap();
+        dialogue.set_wait_command_handling(true);
+        let events = dialogue.continue_().unwrap();
+        assert_eq!(
+            Some(&DialogueEvent::Wait(Duration::from_millis(500))),
+            events.last()
+        );
+        assert!(dialogue
+            .tick(Duration::from_millis(300))
+            .unwrap()
+            .is_empty());
+        assert_eq!(Some(Duration::from_millis(200)), dialogue.remaining_wait());
+
+        // Invalid durations are passed on as regular commands
+        let events = dialogue.tick(Duration::from_millis(300)).unwrap();
+        assert!(
+            matches!(&events[..], [DialogueEvent::Command(command)] if command.raw == "wait soon")
+        );
+        assert_eq!(None, dialogue.remaining_wait());
+    }
+
+    #[test]
+    fn waits_for_commands_to_complete_when_enabled() {
+        let run_command = |text: &str| {
+            InstructionType::RunCommand(instruction::RunCommandInstruction {
+                command_text: text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_command("fade_out"),
+                run_command("play_sound door"),
+                run_command("fade_in"),
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 0,
+                }),
+            ],
+        ));
+        dialogue
+            .set_command_completion(true)
+            .set_non_blocking_commands(["play_sound"])
+            .set_node("Start")
+            .unwrap();
+        let events = dialogue.continue_().unwrap();
+        let Some(DialogueEvent::Command(command)) = events.last() else {
+            panic!("Expected a command, got {events:?}");
+        };
+        let token = command.completion_token.clone().unwrap();
+
+        assert!(!dialogue.can_continue());
+        let error = dialogue.continue_().unwrap_err();
+        assert_eq!(DialogueErrorCode::CommandNotComplete, error.code());
+        token.complete();
+        assert!(dialogue.can_continue());
+        let events = dialogue.continue_().unwrap();
+        assert!(matches!(
+            &events[..],
+            [DialogueEvent::Command(Command {
+                completion_token: None,
+                ..
+            })]
+        ));
+        let events = dialogue.continue_().unwrap();
+        assert!(matches!(
+            &events[..],
+            [DialogueEvent::Command(Command {
+                completion_token: Some(_),
+                ..
+            })]
+        ));
+        assert!(!dialogue.can_continue());
+        dialogue.set_command_completion(false);
+        assert!(
+            !dialogue.can_continue(),
+            "pending commands are still waited for"
+        );
+    }
+
+    #[test]
+    fn interrupts_the_current_line() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2, 3]));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        assert_eq!(
+            vec![DialogueEvent::LineInterrupted(1)],
+            dialogue.interrupt_line(false).unwrap()
+        );
+        let error = dialogue.interrupt_line(false).unwrap_err();
+        assert_eq!(DialogueErrorCode::NoLineToInterrupt, error.code());
+
+        assert_eq!(
+            vec![DialogueEvent::Line(2, vec![])],
+            dialogue.continue_().unwrap()
+        );
+        assert_eq!(
+            vec![
+                DialogueEvent::LineInterrupted(2),
+                DialogueEvent::Line(3, vec![])
+            ],
+            dialogue.interrupt_line(true).unwrap()
+        );
+        dialogue.continue_().unwrap();
+        assert!(dialogue.interrupt_line(false).is_err());
+    }
+
+    #[test]
+    fn errors_on_commands_of_only_whitespace() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushString(instruction::PushStringInstruction {
+                    value: " ".to_owned(),
+                }),
+                InstructionType::RunCommand(instruction::RunCommandInstruction {
+                    command_text: "{0}".to_owned(),
+                    substitution_count: 1,
+                }),
+            ],
+        ));
+        dialogue.set_node("Start").unwrap();
+        let error = dialogue.continue_().unwrap_err();
+        assert_eq!(DialogueErrorCode::EmptyCommand, error.code());
+    }
+
+    #[test]
+    fn attaches_substitutions_to_lines_and_options() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushString(instruction::PushStringInstruction {
+                    value: "Ada".to_owned(),
+                }),
+                InstructionType::PushFloat(instruction::PushFloatInstruction { value: 2.5 }),
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 2,
+                }),
+                InstructionType::PushBool(instruction::PushBoolInstruction { value: false }),
+                InstructionType::PushFloat(instruction::PushFloatInstruction { value: 30.0 }),
+                InstructionType::AddOption(instruction::AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 0,
+                    substitution_count: 1,
+                    has_condition: true,
+                }),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+            ],
+        ));
+        dialogue.set_node("Start").unwrap();
+        let events = dialogue.continue_().unwrap();
+        let Some(DialogueEvent::Line(1, substitutions)) = events.get(1) else {
+            panic!("Expected a line, got {events:?}");
+        };
+        assert_eq!(&vec!["Ada".into(), YarnValue::Number(2.5)], substitutions);
+        assert_eq!(
+            "Ada ran 2.5 km.",
+            dialogue.expand_substitutions("{0} ran {1} km.", substitutions)
+        );
+
+        let events = dialogue.continue_().unwrap();
+        let Some(DialogueEvent::Options(options)) = events.first() else {
+            panic!("Expected options, got {events:?}");
+        };
+        assert!(!options[0].is_available);
+        assert_eq!(vec![YarnValue::Number(30.0)], options[0].substitutions);
+    }
+
+    #[test]
+    fn sends_line_hints_when_enabled() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                run_line(2),
+                InstructionType::AddOption(instruction::AddOptionInstruction {
+                    tag_id: 4,
+                    destination: 5,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(instruction::PeekAndJumpInstruction {}),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                run_line(3),
+                InstructionType::RunCommand(instruction::RunCommandInstruction {
+                    command_text: "fade_out".to_owned(),
+                    substitution_count: 0,
+                }),
+            ],
+        ));
+        dialogue.set_node("Start").unwrap();
+        assert!(!dialogue
+            .continue_()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, DialogueEvent::LineHints(_))));
+
+        dialogue.set_node("Start").unwrap();
+        dialogue.set_line_hints(true);
+        let mut hints = Vec::new();
+        while dialogue.can_continue() {
+            for event in dialogue.continue_().unwrap() {
+                match event {
+                    DialogueEvent::LineHints(line_hints) => hints.push(line_hints),
+                    DialogueEvent::Options(_) => {
+                        dialogue.set_selected_option(OptionId(0)).unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert_eq!(
+            vec![
+                LineHints::default(),
+                LineHints {
+                    is_last_line_before_options: true,
+                    ..Default::default()
+                },
+                LineHints {
+                    is_final_line_of_node: true,
+                    is_final_line_of_dialogue: true,
+                    ..Default::default()
+                },
+            ],
+            hints
+        );
+    }
+
+    #[test]
+    fn node_handles_stay_valid_when_adding_programs() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        assert!(matches!(
+            dialogue.node("Start"),
+            Err(DialogueError::NoProgramLoaded)
+        ));
+        dialogue.add_program(program_with_lines("Start", [1]));
+        let start = dialogue.node("Start").unwrap();
+        assert!(matches!(
+            dialogue.node("start"),
+            Err(DialogueError::InvalidNode { .. })
+        ));
+
+        dialogue.add_program(program_with_lines("End", [2]));
+        assert_eq!(
+            vec!["End", "Start"],
+            dialogue
+                .node_handles()
+                .map(|handle| handle.to_string())
+                .collect::<Vec<_>>()
+        );
+        dialogue.set_node(&start).unwrap();
+        assert_eq!(Some(start.name()), dialogue.current_node().as_deref());
+
+        dialogue.unload_all();
+        assert!(dialogue.set_node(start).is_err());
+    }
+
+    #[test]
+    fn steps_back_to_previous_lines_and_options() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                InstructionType::PushFloat(instruction::PushFloatInstruction { value: 1.0 }),
+                InstructionType::StoreVariable(instruction::StoreVariableInstruction {
+                    variable_name: "$gold".to_owned(),
+                }),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                InstructionType::AddOption(instruction::AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 7,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(instruction::PeekAndJumpInstruction {}),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                run_line(3),
+            ],
+        ));
+        let lines_and_options = |events: Vec<DialogueEvent>| -> Vec<u32> {
+            events
+                .into_iter()
+                .filter_map(|event| match event {
+                    DialogueEvent::Line(line_id, ..) => Some(line_id),
+                    DialogueEvent::Options(options) => Some(options[0].tag_id),
+                    _ => None,
+                })
+                .collect()
+        };
+        dialogue.set_max_checkpoints(10);
+        dialogue.set_node("Start").unwrap();
+        assert_eq!(vec![1], lines_and_options(dialogue.continue_().unwrap()));
+        assert!(!dialogue.can_step_back());
+        assert_eq!(vec![2], lines_and_options(dialogue.continue_().unwrap()));
+        assert_eq!(
+            1.0,
+            dialogue.variable_storage().get_as::<f32>("$gold").unwrap()
+        );
+
+        // Back to the first line, which undoes the write
+        assert!(dialogue.step_back().unwrap());
+        assert!(!dialogue.variable_storage().contains("$gold"));
+        assert_eq!(vec![1], lines_and_options(dialogue.continue_().unwrap()));
+        assert_eq!(vec![2], lines_and_options(dialogue.continue_().unwrap()));
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        assert_eq!(vec![3], lines_and_options(dialogue.continue_().unwrap()));
+
+        // Back to the options, keeping the write before them
+        assert!(dialogue.step_back().unwrap());
+        assert_eq!(vec![2], lines_and_options(dialogue.continue_().unwrap()));
+        assert_eq!(
+            1.0,
+            dialogue.variable_storage().get_as::<f32>("$gold").unwrap()
+        );
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        assert_eq!(vec![3], lines_and_options(dialogue.continue_().unwrap()));
+        assert!(!dialogue.is_active());
+
+        dialogue.set_node("Start").unwrap();
+        assert!(!dialogue.can_step_back());
+    }
+
+    #[test]
+    fn fast_forwards_to_blocking_commands_and_options() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let run_command = |command_text: &str| {
+            InstructionType::RunCommand(instruction::RunCommandInstruction {
+                command_text: command_text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                run_command("play_sfx door"),
+                run_command("wait 2"),
+                run_line(2),
+                run_command("minigame"),
+                run_line(3),
+                InstructionType::AddOption(instruction::AddOptionInstruction {
+                    tag_id: 4,
+                    destination: 0,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(instruction::PeekAndJumpInstruction {}),
+            ],
+        ));
+        dialogue
+            .set_wait_command_handling(true)
+            .set_line_hints(true)
+            .set_blocking_commands(["minigame"])
+            .set_transcript_recorder(Some(TranscriptRecorder::new()));
+        dialogue.set_node("Start").unwrap();
+
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Start".into()),
+                DialogueEvent::Command(Command::parse("play_sfx door".to_owned()).unwrap()),
+                DialogueEvent::Command(Command::parse("minigame".to_owned()).unwrap()),
+            ],
+            dialogue.fast_forward().unwrap()
+        );
+        assert_eq!(
+            vec![DialogueEvent::Options(vec![DialogueOption {
+                tag_id: 4,
+                id: OptionId(0),
+                destination_node: 0,
+                is_available: true,
+                substitutions: vec![],
+                #[cfg(feature = "condition_explanations")]
+                unavailability_reason: None,
+            }])],
+            dialogue.fast_forward().unwrap()
+        );
+        assert!(dialogue.is_waiting_for_option_selection());
+        assert_eq!(
+            "[line 1]\n<<play_sfx door>>\n[line 2]\n<<minigame>>\n[line 3]\n",
+            dialogue.transcript_recorder().unwrap().to_plain_text()
+        );
+    }
+
+    #[test]
+    fn pauses_at_breakpoints() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2, 3]));
+        dialogue
+            .set_breakpoint("Start", BreakpointLocation::Line(2))
+            .set_breakpoint("Start", BreakpointLocation::Instruction(2))
+            .set_breakpoint("Other", BreakpointLocation::Line(1));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        assert_eq!(
+            vec![DialogueEvent::BreakpointHit(Breakpoint::new(
+                "Start",
+                BreakpointLocation::Line(2)
+            ))],
+            dialogue.continue_().unwrap()
+        );
+        assert!(dialogue.is_paused());
+        assert!(dialogue.is_active());
+        assert_eq!(
+            vec![DialogueEvent::Line(2, vec![])],
+            dialogue.continue_().unwrap()
+        );
+        assert!(!dialogue.is_paused());
+
+        assert!(dialogue.remove_breakpoint("Start", BreakpointLocation::Instruction(2)));
+        assert!(!dialogue.remove_breakpoint("Start", BreakpointLocation::Instruction(2)));
+        assert_eq!(
+            vec![DialogueEvent::Line(3, vec![])],
+            dialogue.continue_().unwrap()
+        );
+    }
+
+    #[test]
+    fn checks_when_headers_for_node_availability() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        let mut program = program_with_lines("Start", [1]);
+        for (node_name, conditions) in [
+            ("Shop", &["once if $gold >= 100"][..]),
+            ("Rich", &["always", "$gold > 1000"][..]),
+            ("Broken", &["$gold"][..]),
+        ] {
+            let mut node = program.nodes["Start"].clone();
+            node.name = node_name.to_owned();
+            node.headers = conditions
+                .iter()
+                .map(|condition| Header {
+                    key: "when".to_owned(),
+                    value: (*condition).to_owned(),
+                })
+                .collect();
+            program.nodes.insert(node_name.to_owned(), node);
+        }
+        dialogue.add_program(program);
+        dialogue.set_variable("$gold", 150).unwrap();
+
+        assert!(dialogue.is_node_available("Start").unwrap());
+        assert!(dialogue.is_node_available("Shop").unwrap());
+        assert!(!dialogue.is_node_available("Rich").unwrap());
+        dialogue
+            .set_variable("$Yarn.Internal.Visiting.Shop", 1)
+            .unwrap();
+        assert!(!dialogue.is_node_available("Shop").unwrap());
+        assert!(matches!(
+            dialogue.is_node_available("Broken"),
+            Err(DialogueError::InvalidExpression { .. })
+        ));
+        assert!(matches!(
+            dialogue.is_node_available("Missing"),
+            Err(DialogueError::InvalidNode { .. })
+        ));
+    }
+
+    #[test]
+    fn skips_once_content_until_flags_are_reset() {
+        let once_variable = Library::generate_unique_once_variable("line:1");
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let pop = || InstructionType::Pop(instruction::PopInstruction {});
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        // What the compiler emits for `<<once>> Line 1 <<endonce>>` followed by line 2
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushVariable(instruction::PushVariableInstruction {
+                    variable_name: once_variable.clone(),
+                }),
+                InstructionType::PushFloat(instruction::PushFloatInstruction { value: 1.0 }),
+                InstructionType::CallFunc(instruction::CallFunctionInstruction {
+                    function_name: "Bool.Not".to_owned(),
+                }),
+                InstructionType::JumpIfFalse(instruction::JumpIfFalseInstruction {
+                    destination: 10,
+                }),
+                pop(),
+                InstructionType::PushBool(instruction::PushBoolInstruction { value: true }),
+                InstructionType::StoreVariable(instruction::StoreVariableInstruction {
+                    variable_name: once_variable.clone(),
+                }),
+                pop(),
+                run_line(1),
+                InstructionType::JumpTo(instruction::JumpToInstruction { destination: 11 }),
+                pop(),
+                run_line(2),
+            ],
+        ));
+        let lines = |dialogue: &mut Dialogue| {
+            dialogue.set_node("Start").unwrap();
+            let mut lines = Vec::new();
+            loop {
+                for event in dialogue.continue_().unwrap() {
+                    match event {
+                        DialogueEvent::Line(line_id, ..) => lines.push(line_id),
+                        DialogueEvent::DialogueComplete => return lines,
+                        _ => {}
+                    }
+                }
+            }
+        };
+        dialogue
+            .set_variable("$Yarn.Internal.Visiting.Start", 0)
+            .unwrap();
+
+        assert_eq!(vec![1, 2], lines(&mut dialogue));
+        assert_eq!(vec![2], lines(&mut dialogue));
+        dialogue.reset_once_flags().unwrap();
+        assert!(!dialogue.variable_storage().contains(&once_variable));
+        assert!(dialogue
+            .variable_storage()
+            .contains("$Yarn.Internal.Visiting.Start"));
+        assert_eq!(vec![1, 2], lines(&mut dialogue));
+    }
+
+    #[test]
+    fn selects_one_line_of_line_groups() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let candidate = |content_id: &str, destination| {
+            InstructionType::AddSaliencyCandidate(instruction::AddSaliencyCandidateInstruction {
+                content_id: content_id.to_owned(),
+                complexity_score: 0,
+                destination,
+            })
+        };
+        let push_bool =
+            |value| InstructionType::PushBool(instruction::PushBoolInstruction { value });
+        let pop = || InstructionType::Pop(instruction::PopInstruction {});
+        let jump_to_end =
+            || InstructionType::JumpTo(instruction::JumpToInstruction { destination: 20 });
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        // What the compiler emits for a line group of three lines, the second one with a failing condition, followed by line 4
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                push_bool(true),
+                candidate("line:a", 10),
+                push_bool(false),
+                candidate("line:b", 13),
+                push_bool(true),
+                candidate("line:c", 16),
+                InstructionType::SelectSaliencyCandidate(
+                    instruction::SelectSaliencyCandidateInstruction {},
+                ),
+                InstructionType::JumpIfFalse(instruction::JumpIfFalseInstruction {
+                    destination: 19,
+                }),
+                pop(),
+                InstructionType::PeekAndJump(instruction::PeekAndJumpInstruction {}),
+                pop(),
+                run_line(1),
+                jump_to_end(),
+                pop(),
+                run_line(2),
+                jump_to_end(),
+                pop(),
+                run_line(3),
+                jump_to_end(),
+                pop(),
+                run_line(4),
+            ],
+        ));
+        let lines = |dialogue: &mut Dialogue| {
+            dialogue.set_node("Start").unwrap();
+            let mut lines = Vec::new();
+            loop {
+                for event in dialogue.continue_().unwrap() {
+                    match event {
+                        DialogueEvent::Line(line_id, ..) => lines.push(line_id),
+                        DialogueEvent::DialogueComplete => return lines,
+                        _ => {}
+                    }
+                }
+            }
+        };
+
+        assert_eq!(vec![1, 4], lines(&mut dialogue));
+        assert_eq!(vec![3, 4], lines(&mut dialogue));
+        assert_eq!(vec![1, 4], lines(&mut dialogue));
+        assert_eq!(
+            2.0,
+            dialogue
+                .variable_storage()
+                .get_as::<f32>(&Library::generate_unique_view_count_variable("line:a"))
+                .unwrap()
+        );
+
+        dialogue.set_saliency_strategy(FirstSaliencyStrategy);
+        assert_eq!(vec![1, 4], lines(&mut dialogue));
+        dialogue.set_saliency_strategy(RandomBestLeastRecentlyViewedSaliencyStrategy::new(
+            SimulationRng::new(7),
+        ));
+        assert_eq!(vec![3, 4], lines(&mut dialogue));
+    }
+
+    #[test]
+    fn jumps_and_detours_from_game_code() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2]));
+        dialogue.add_program(program_with_instructions(
+            "Detour",
+            [
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 10,
+                    substitution_count: 0,
+                }),
+                InstructionType::Return(instruction::ReturnInstruction {}),
+                InstructionType::RunLine(instruction::RunLineInstruction {
+                    line_id: 11,
+                    substitution_count: 0,
+                }),
+            ],
+        ));
+        assert!(matches!(
+            dialogue.detour_to_node("Detour"),
+            Err(DialogueError::NoNodeSelectedOnContinue)
+        ));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        assert!(matches!(
+            dialogue.detour_to_node("Missing"),
+            Err(DialogueError::InvalidNode { .. })
+        ));
+        dialogue.detour_to_node("Detour").unwrap();
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Detour".into()),
+                DialogueEvent::Line(10, vec![]),
+            ],
+            dialogue.continue_().unwrap()
+        );
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeComplete("Detour".into()),
+                DialogueEvent::NodeStart("Start".into()),
+                DialogueEvent::Line(2, vec![]),
+            ],
+            dialogue.continue_().unwrap()
+        );
+
+        dialogue.detour_to_node("Detour").unwrap();
+        dialogue.jump_to_node("Start").unwrap();
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Detour".into()),
+                DialogueEvent::NodeComplete("Detour".into()),
+                DialogueEvent::NodeStart("Start".into()),
+                DialogueEvent::Line(1, vec![]),
+            ],
+            dialogue.continue_().unwrap()
+        );
+        dialogue.continue_().unwrap();
+        // The jump discarded the detour, so the dialogue ends with the node
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeComplete("Start".into()),
+                DialogueEvent::DialogueComplete,
+            ],
+            dialogue.continue_().unwrap()
+        );
+    }
+
+    #[test]
+    fn passes_integers_to_functions_without_losing_precision() {
+        use yarnspinner_core::prelude::instruction::*;
+        let player_id = (1_u64 << 53) + 1;
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .library_mut()
+            .add_function("player_id", move || player_id)
+            .add_function("is_admin", move |id: Strict<u64>| id.0 == player_id);
+        let parameter_count =
+            |count: f32| InstructionType::PushFloat(PushFloatInstruction { value: count });
+        let call = |function_name: &str| {
+            InstructionType::CallFunc(CallFunctionInstruction {
+                function_name: function_name.to_owned(),
+            })
+        };
+        let store = |variable_name: &str| {
+            InstructionType::StoreVariable(StoreVariableInstruction {
+                variable_name: variable_name.to_owned(),
+            })
+        };
+        dialogue
+            .add_program(program_with_instructions(
+                "Start",
+                [
+                    parameter_count(0.0),
+                    call("player_id"),
+                    store("$id"),
+                    parameter_count(1.0),
+                    call("is_admin"),
+                    store("$is_admin"),
+                    InstructionType::Stop(StopInstruction {}),
+                ],
+            ))
+            .set_node("Start")
+            .unwrap();
+        dialogue.continue_().unwrap();
+
+        assert_eq!(
+            YarnValue::Boolean(true),
+            dialogue.variable("$is_admin").unwrap()
+        );
+        assert_eq!(
+            player_id,
+            u64::try_from(dialogue.variable("$id").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn surfaces_failed_function_calls_as_errors() {
+        use yarnspinner_core::prelude::instruction::*;
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .library_mut()
+            .add_function("item_count", |item: &str| match item {
+                "apple" => Ok(3),
+                _ => Err(format!("Unknown item \"{item}\"")),
+            })
+            .add_function("equipped_weapon", || None::<String>)
+            .add_function_with_default("quest_stage", || None::<u32>, 0);
+        let call = |function_name: &str, parameters: &[&str]| {
+            parameters
+                .iter()
+                .map(|parameter| {
+                    InstructionType::PushString(PushStringInstruction {
+                        value: (*parameter).to_owned(),
+                    })
+                })
+                .chain([
+                    InstructionType::PushFloat(PushFloatInstruction {
+                        value: parameters.len() as f32,
+                    }),
+                    InstructionType::CallFunc(CallFunctionInstruction {
+                        function_name: function_name.to_owned(),
+                    }),
+                ])
+                .collect::<Vec<_>>()
+        };
+        dialogue
+            .add_program(program_with_instructions(
+                "Start",
+                [
+                    call("item_count", &["apple"]),
+                    call("quest_stage", &[]),
+                    call("item_count", &["pear"]),
+                ]
+                .concat(),
+            ))
+            .add_program(program_with_instructions(
+                "Weapon",
+                call("equipped_weapon", &[]),
+            ))
+            .set_node("Start")
+            .unwrap();
+
+        let error = dialogue.continue_().unwrap_err();
+        assert_eq!(
+            "The function \"item_count\" failed: Unknown item \"pear\"",
+            error.to_string()
+        );
+        assert_eq!(DialogueErrorCode::FunctionFailed, error.code());
+        assert_eq!(
+            YarnValue::Integer(3),
+            dialogue
+                .evaluate_expression(r#"item_count("apple")"#)
+                .unwrap()
+        );
+        assert_eq!(
+            YarnValue::Integer(0),
+            dialogue.evaluate_expression("quest_stage()").unwrap()
+        );
+
+        dialogue.set_node("Weapon").unwrap();
+        assert!(matches!(
+            dialogue.continue_(),
+            Err(DialogueError::FunctionFailed { function_name, .. }) if function_name == "equipped_weapon"
+        ));
+    }
+
+    #[test]
+    fn passes_context_to_functions() {
+        use yarnspinner_core::prelude::instruction::*;
+        struct Game {
+            gold: u32,
+        }
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .library_mut()
+            .add_function_with_context("pay_toll", |game: &mut Game| {
+                game.gold -= 10;
+                game.gold
+            });
+        let program = || {
+            program_with_instructions(
+                "Toll",
+                [
+                    InstructionType::PushFloat(PushFloatInstruction { value: 0.0 }),
+                    InstructionType::CallFunc(CallFunctionInstruction {
+                        function_name: "pay_toll".to_owned(),
+                    }),
+                    InstructionType::StoreVariable(StoreVariableInstruction {
+                        variable_name: "$gold".to_owned(),
+                    }),
+                    InstructionType::Stop(StopInstruction {}),
+                ],
+            )
+        };
+        dialogue.add_program(program()).set_node("Toll").unwrap();
+
+        let mut game = Game { gold: 25 };
+        dialogue.continue_with_context(&mut game).unwrap();
+        assert_eq!(15, game.gold);
+        assert_eq!(YarnValue::Integer(15), dialogue.variable("$gold").unwrap());
+
+        dialogue.set_node("Toll").unwrap();
+        assert!(matches!(
+            dialogue.continue_(),
+            Err(DialogueError::FunctionFailed { function_name, .. }) if function_name == "pay_toll"
+        ));
+        assert_eq!(15, game.gold);
+    }
+
+    #[test]
+    fn malformed_programs_fail_instead_of_panicking() {
+        use yarnspinner_core::prelude::instruction::*;
+        let pop = || InstructionType::Pop(PopInstruction {});
+        let call = |parameter_count| {
+            [
+                InstructionType::PushFloat(PushFloatInstruction {
+                    value: parameter_count,
+                }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "visited".to_owned(),
+                }),
+            ]
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program_with_instructions("EmptyStack", [pop()]))
+            .add_program(program_with_instructions("WrongParameterCount", call(0.0)))
+            .add_program(program_with_instructions(
+                "UndeclaredVariable",
+                [InstructionType::PushVariable(PushVariableInstruction {
+                    variable_name: "$gold".to_owned(),
+                })],
+            ));
+        let mut missing_type = program_with_instructions("MissingType", [pop()]);
+        missing_type
+            .nodes
+            .get_mut("MissingType")
+            .unwrap()
+            .instructions[0]
+            .instruction_type = None;
+        dialogue.add_program(missing_type);
+
+        for node_name in [
+            "EmptyStack",
+            "WrongParameterCount",
+            "UndeclaredVariable",
+            "MissingType",
+        ] {
+            dialogue.set_node(node_name).unwrap();
+            let error = dialogue.continue_().unwrap_err();
+            assert!(
+                matches!(error, DialogueError::InvalidInstruction { .. }),
+                "{node_name}: {error}"
+            );
+            assert_eq!(DialogueErrorCode::InvalidInstruction, error.code());
+        }
+        assert_eq!(
+            "The program is malformed: Function visited expected 1 parameters, but received 0. \
+             Use `Dialogue::diagnose` to find the failing instruction.",
+            {
+                dialogue.set_node("WrongParameterCount").unwrap();
+                dialogue.continue_().unwrap_err().to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn empty_nodes_complete() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program_with_instructions("Start", []))
+            .set_node("Start")
+            .unwrap();
+
+        let events = dialogue.continue_().unwrap();
+        assert!(matches!(
+            events.last(),
+            Some(DialogueEvent::DialogueComplete)
+        ));
+        assert!(!dialogue.is_active());
+    }
+
+    #[test]
+    fn detour_returns_to_position_beyond_length_of_detoured_node() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2, 3, 4]));
+        dialogue.add_program(program_with_instructions(
+            "Detour",
+            [InstructionType::Return(instruction::ReturnInstruction {})],
+        ));
+        dialogue.set_node("Start").unwrap();
+        for _ in 0..3 {
+            dialogue.continue_().unwrap();
+        }
+        dialogue.detour_to_node("Detour").unwrap();
+        assert_eq!(
+            Some(&DialogueEvent::Line(4, vec![])),
+            dialogue.continue_().unwrap().last()
+        );
+    }
+
+    #[test]
+    fn stopping_at_the_end_of_a_node_does_not_return_from_it() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [InstructionType::Stop(instruction::StopInstruction {})],
+        ));
+        dialogue.set_node("Start").unwrap();
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Start".into()),
+                DialogueEvent::NodeComplete("Start".into()),
+                DialogueEvent::DialogueComplete,
+            ],
+            dialogue.continue_().unwrap()
+        );
+        assert_eq!(DialogueState::Stopped, dialogue.state());
+    }
+
+    #[test]
+    fn starts_at_the_configured_or_marked_start_node() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        assert!(matches!(
+            dialogue.start(),
+            Err(DialogueError::NoProgramLoaded)
+        ));
+        dialogue.add_program(program_with_lines("Intro", [1]));
+        assert!(matches!(dialogue.start(), Err(DialogueError::NoStartNode)));
+
+        dialogue.add_program(program_with_lines("Start", [2]));
+        assert_eq!(Some("Start"), dialogue.start_node());
+        let mut unmarked = program_with_lines("Credits", [4]);
+        unmarked
+            .nodes
+            .get_mut("Credits")
+            .unwrap()
+            .headers
+            .push(Header {
+                key: "start".to_owned(),
+                value: "false".to_owned(),
+            });
+        dialogue.add_program(unmarked);
+        assert_eq!(Some("Start"), dialogue.start_node());
+        let mut marked = program_with_lines("Prologue", [3]);
+        marked
+            .nodes
+            .get_mut("Prologue")
+            .unwrap()
+            .headers
+            .push(Header {
+                key: "start".to_owned(),
+                value: "true".to_owned(),
+            });
+        dialogue.add_program(marked);
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Prologue".into()),
+                DialogueEvent::Line(3, vec![]),
+            ],
+            dialogue.start().unwrap()
+        );
+
+        dialogue.set_start_node(Some("Intro".to_owned()));
+        assert_eq!(
+            Some(&DialogueEvent::Line(1, vec![])),
+            dialogue.start().unwrap().last()
+        );
+        assert_eq!(
+            Some(&DialogueEvent::Line(2, vec![])),
+            dialogue.start_at("Start").unwrap().last()
+        );
+
+        dialogue.set_start_node(None);
+        let mut marked = program_with_lines("Aside", [5]);
+        marked.nodes.get_mut("Aside").unwrap().headers.push(Header {
+            key: "start".to_owned(),
+            value: String::new(),
+        });
+        dialogue.add_program(marked);
+        assert_eq!(Some("Aside"), dialogue.start_node());
+    }
+
+    #[test]
+    fn finds_nodes_unreachable_from_the_start_node() {
+        let run_node = |node_name: &str| {
+            InstructionType::RunNode(instruction::RunNodeInstruction {
+                node_name: node_name.to_owned(),
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program_with_instructions("Start", [run_node("Shop")]))
+            .add_program(program_with_instructions(
+                "Shop",
+                [
+                    InstructionType::DetourToNode(instruction::DetourToNodeInstruction {
+                        node_name: "Shopkeeper".to_owned(),
+                    }),
+                    run_node("Start"),
+                ],
+            ))
+            .add_program(program_with_lines("Shopkeeper", [1]))
+            .add_program(program_with_instructions("Secret", [run_node("Shop")]));
+
+        assert_eq!(
+            vec!["Secret", "Shop", "Shopkeeper", "Start"],
+            dialogue.reachable_nodes("Secret").unwrap()
+        );
+        assert_eq!(vec!["Secret"], dialogue.unreachable_nodes().unwrap());
+        assert!(matches!(
+            dialogue.reachable_nodes("Missing"),
+            Err(DialogueError::InvalidNode { .. })
+        ));
+    }
+
+    pub(crate) fn program_with_lines(
+        node_name: &str,
+        line_ids: impl IntoIterator<Item = u32>,
+    ) -> Program {
+        program_with_instructions(
+            node_name,
+            line_ids
+                .into_iter()
+                .map(|line_id| {
+                    InstructionType::RunLine(instruction::RunLineInstruction {
+                        line_id,
+                        substitution_count: 0,
+                    })
+                })
+                .chain([InstructionType::Stop(instruction::StopInstruction {})]),
+        )
+    }
+
+    pub(crate) fn program_with_instructions(
+        node_name: &str,
+        instructions: impl IntoIterator<Item = InstructionType>,
+    ) -> Program {
+        let node = Node {
+            name: node_name.to_owned(),
+            instructions: instructions
+                .into_iter()
+                .map(|instruction_type| Instruction {
+                    instruction_type: Some(instruction_type),
+                })
+                .collect(),
+            headers: vec![],
+        };
+        Program {
+            nodes: [(node_name.to_owned(), node)].into_iter().collect(),
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,466 @@
+//! Not part of the original implementation.
+//!
+//! Configures a [`Dialogue`] in one go and checks the configuration as a whole before the first call to [`Dialogue::continue_`],
+//! instead of letting mistakes like a misspelled start node surface while the game is running.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+use yarnspinner_core::prelude::instruction::InstructionType;
+
+/// A problem with the configuration of a [`DialogueBuilder`], see [`DialogueBuildError`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DialogueBuildIssue {
+    /// No program was added via [`DialogueBuilder::with_program`].
+    NoProgram,
+    /// The node set via [`DialogueBuilder::with_start_node`] is not part of the programs.
+    StartNodeNotFound {
+        /// The name of the start node.
+        node_name: String,
+    },
+    /// A function call or command of the programs is invalid, see [`Dialogue::validate_program`].
+    InvalidProgram(ProgramValidationIssue),
+    /// The [`TextProvider`] set via [`DialogueBuilder::with_text_provider`] or of the [`RuntimeAdapter`] has no text for a line or option of the programs.
+    MissingText {
+        /// The ID of the line, in the `line:<id>` format of [`DialogueEvent::Line`] IDs.
+        line_id: LineId,
+    },
+    /// Both an RNG and a [`SaliencyStrategy`] were set, but the RNG is only used to create a saliency strategy.
+    RngWithSaliencyStrategy,
+    /// The [`TextProvider`] provides text in a language that cannot be used for the language set via [`DialogueBuilder::with_language`].
+    LanguageMismatch {
+        /// The language set via [`DialogueBuilder::with_language`].
+        language: Language,
+        /// The language of the text provider.
+        text_provider_language: Language,
+    },
+}
+
+impl Display for DialogueBuildIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoProgram => write!(f, "No program was added"),
+            Self::StartNodeNotFound { node_name } => {
+                write!(f, "The start node \"{node_name}\" is not part of the program")
+            }
+            Self::InvalidProgram(issue) => write!(f, "{issue}"),
+            Self::MissingText { line_id } => {
+                write!(f, "The text provider has no text for \"{}\"", line_id.0)
+            }
+            Self::RngWithSaliencyStrategy => write!(
+                f,
+                "An RNG and a saliency strategy were both set, but the RNG is only used for the saliency strategy"
+            ),
+            Self::LanguageMismatch {
+                language,
+                text_provider_language,
+            } => write!(
+                f,
+                "The text provider provides text in {text_provider_language}, which cannot be used for {language}"
+            ),
+        }
+    }
+}
+
+/// All [`DialogueBuildIssue`]s found by [`DialogueBuilder::build`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DialogueBuildError {
+    /// The issues found.
+    pub issues: Vec<DialogueBuildIssue>,
+}
+
+impl Error for DialogueBuildError {}
+
+impl Display for DialogueBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} problems with the dialogue configuration",
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n- {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates a [`Dialogue`] and checks its configuration as a whole, see [`DialogueBuilder::build`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # let program = Program::default();
+/// let mut text_provider = StringTableTextProvider::new();
+/// text_provider.extend_base_language([(LineId::from("line:1"), "Hello!".to_owned())]);
+/// let result = DialogueBuilder::new()
+///     .with_program(program)
+///     .with_text_provider(&text_provider)
+///     .with_language("en")
+///     .with_start_node("Start")
+///     .build();
+/// assert!(result.unwrap_err().issues.contains(&DialogueBuildIssue::StartNodeNotFound {
+///     node_name: "Start".to_owned(),
+/// }));
+/// ```
+pub struct DialogueBuilder<'a> {
+    programs: Vec<Program>,
+    library: Option<Library>,
+    variable_storage: Option<Box<dyn VariableStorage>>,
+    text_provider: Option<&'a dyn TextProvider>,
+    language: Option<Language>,
+    rng: Option<RandomBestLeastRecentlyViewedSaliencyStrategy>,
+    saliency_strategy: Option<Arc<dyn SaliencyStrategy>>,
+    options_presentation_policy: Option<OptionsPresentationPolicy>,
+    command_registry: Option<CommandRegistry>,
+    start_node: Option<String>,
+    adapter: Option<Box<dyn RuntimeAdapter>>,
+}
+
+impl Debug for DialogueBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogueBuilder")
+            .field("programs", &self.programs.len())
+            .field("library", &self.library)
+            .field("variable_storage", &self.variable_storage)
+            .field("text_provider", &self.text_provider)
+            .field("language", &self.language)
+            .field("rng", &self.rng)
+            .field("saliency_strategy", &self.saliency_strategy)
+            .field(
+                "options_presentation_policy",
+                &self.options_presentation_policy,
+            )
+            .field("command_registry", &self.command_registry)
+            .field("start_node", &self.start_node)
+            .field("adapter", &self.adapter)
+            .finish()
+    }
+}
+
+impl Default for DialogueBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> DialogueBuilder<'a> {
+    /// Creates a builder without any configuration.
+    pub fn new() -> Self {
+        Self {
+            programs: Vec::new(),
+            library: None,
+            variable_storage: None,
+            text_provider: None,
+            language: None,
+            rng: None,
+            saliency_strategy: None,
+            options_presentation_policy: None,
+            command_registry: None,
+            start_node: None,
+            adapter: None,
+        }
+    }
+
+    /// Adds a program, see [`Dialogue::add_program`]. At least one program is required.
+    #[must_use]
+    pub fn with_program(mut self, program: Program) -> Self {
+        self.programs.push(program);
+        self
+    }
+
+    /// Adds the functions of the library to the standard library of the dialogue, see [`Dialogue::library_mut`].
+    #[must_use]
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library
+            .get_or_insert_with(Library::new)
+            .import(library);
+        self
+    }
+
+    /// Sets the [`VariableStorage`]. Defaults to a [`MemoryVariableStorage`].
+    #[must_use]
+    pub fn with_variable_storage(mut self, variable_storage: Box<dyn VariableStorage>) -> Self {
+        self.variable_storage = Some(variable_storage);
+        self
+    }
+
+    /// Sets the [`TextProvider`] the game looks up lines with. The dialogue does not own it, it is only used to check
+    /// that every line and option of the programs has a text and that its language fits the one set via [`DialogueBuilder::with_language`].
+    #[must_use]
+    pub fn with_text_provider(mut self, text_provider: &'a dyn TextProvider) -> Self {
+        self.text_provider = Some(text_provider);
+        self
+    }
+
+    /// Sets the language of the [`LineParser`] of the dialogue, see [`LineParser::set_language`].
+    #[must_use]
+    pub fn with_language(mut self, language: impl Into<Language>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Sets the source of randomness, which is used for a [`RandomBestLeastRecentlyViewedSaliencyStrategy`].
+    /// Conflicts with [`DialogueBuilder::with_saliency_strategy`].
+    #[must_use]
+    pub fn with_rng(mut self, rng: impl DialogueRng + 'static) -> Self {
+        self.rng = Some(RandomBestLeastRecentlyViewedSaliencyStrategy::new(rng));
+        self
+    }
+
+    /// Sets the [`SaliencyStrategy`], see [`Dialogue::set_saliency_strategy`]. Conflicts with [`DialogueBuilder::with_rng`].
+    #[must_use]
+    pub fn with_saliency_strategy(mut self, strategy: impl SaliencyStrategy + 'static) -> Self {
+        self.saliency_strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Sets the [`OptionsPresentationPolicy`], see [`Dialogue::set_options_presentation_policy`].
+    #[must_use]
+    pub fn with_options_presentation_policy(mut self, policy: OptionsPresentationPolicy) -> Self {
+        self.options_presentation_policy = Some(policy);
+        self
+    }
+
+    /// Sets the [`CommandRegistry`] the commands of the programs are validated against, see [`Dialogue::set_command_registry`].
+    #[must_use]
+    pub fn with_command_registry(mut self, registry: CommandRegistry) -> Self {
+        self.command_registry = Some(registry);
+        self
+    }
+
+    /// Sets the node [`Dialogue::start`] starts at, see [`Dialogue::set_start_node`].
+    #[must_use]
+    pub fn with_start_node(mut self, node_name: impl Into<String>) -> Self {
+        self.start_node = Some(node_name.into());
+        self
+    }
+
+    /// Sets the [`RuntimeAdapter`] of the [`DialogueRunner`] created by [`DialogueBuilder::build_runner`].
+    /// Its [`TextProvider`] is checked like the one set via [`DialogueBuilder::with_text_provider`], which takes precedence.
+    #[must_use]
+    pub fn with_adapter(mut self, adapter: impl RuntimeAdapter + 'static) -> Self {
+        self.adapter = Some(Box::new(adapter));
+        self
+    }
+
+    /// Creates the [`Dialogue`] after checking that
+    /// - at least one program was added,
+    /// - the start node, if set, exists,
+    /// - all function calls and commands of the programs are valid, see [`Dialogue::validate_program`],
+    /// - the text provider, if set, has text for all lines and options and provides it in the configured language,
+    /// - not both an RNG and a saliency strategy were set.
+    ///
+    /// The adapter, if set, is only used for the checks. Use [`DialogueBuilder::build_runner`] to keep it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns all problems found, or none of them if the configuration is valid.
+    pub fn build(self) -> core::result::Result<Dialogue, DialogueBuildError> {
+        self.build_with_adapter().map(|(dialogue, _)| dialogue)
+    }
+
+    /// Creates the [`Dialogue`] like [`DialogueBuilder::build`] and wraps it in a [`DialogueRunner`] with the adapter, if one was set.
+    ///
+    /// ## Errors
+    ///
+    /// Returns all problems found, see [`DialogueBuilder::build`].
+    pub fn build_runner(self) -> core::result::Result<DialogueRunner, DialogueBuildError> {
+        let (dialogue, adapter) = self.build_with_adapter()?;
+        let mut runner = DialogueRunner::new(dialogue);
+        runner.adapter = adapter;
+        Ok(runner)
+    }
+
+    fn build_with_adapter(
+        self,
+    ) -> core::result::Result<(Dialogue, Option<Box<dyn RuntimeAdapter>>), DialogueBuildError> {
+        let mut issues = Vec::new();
+        if self.programs.is_empty() {
+            issues.push(DialogueBuildIssue::NoProgram);
+        }
+        if self.rng.is_some() && self.saliency_strategy.is_some() {
+            issues.push(DialogueBuildIssue::RngWithSaliencyStrategy);
+        }
+        let text_provider = self.text_provider.or_else(|| {
+            self.adapter
+                .as_deref()
+                .and_then(RuntimeAdapter::text_provider)
+        });
+        if let Some(text_provider) = text_provider {
+            if let Some((language, text_provider_language)) = self
+                .language
+                .clone()
+                .zip(text_provider.get_language())
+                .filter(|(language, provided)| !provided.matches(language))
+            {
+                issues.push(DialogueBuildIssue::LanguageMismatch {
+                    language,
+                    text_provider_language,
+                });
+            }
+            issues.extend(
+                line_ids(&self.programs)
+                    .into_iter()
+                    .map(|line_id| LineId::from(format!("line:{line_id}")))
+                    .filter(|line_id| text_provider.get_text(line_id).is_none())
+                    .map(|line_id| DialogueBuildIssue::MissingText { line_id }),
+            );
+        }
+
+        let variable_storage = self
+            .variable_storage
+            .unwrap_or_else(|| Box::new(MemoryVariableStorage::new()));
+        let mut dialogue = Dialogue::new(variable_storage);
+        if let Some(library) = self.library {
+            dialogue.library_mut().import(library);
+        }
+        for program in self.programs {
+            dialogue.add_program(program);
+        }
+        if let Some(language) = self.language {
+            dialogue.line_parser_mut().set_language(language);
+        }
+        if let Some(strategy) = self.rng {
+            dialogue.set_saliency_strategy(strategy);
+        }
+        if let Some(strategy) = self.saliency_strategy {
+            dialogue.set_shared_saliency_strategy(strategy);
+        }
+        if let Some(policy) = self.options_presentation_policy {
+            dialogue.set_options_presentation_policy(policy);
+        }
+        dialogue.set_command_registry(self.command_registry);
+        issues.extend(
+            dialogue
+                .validate_program()
+                .issues
+                .into_iter()
+                .map(DialogueBuildIssue::InvalidProgram),
+        );
+        if let Some(start_node) = self.start_node {
+            if !dialogue.node_exists(&start_node) {
+                issues.push(DialogueBuildIssue::StartNodeNotFound {
+                    node_name: start_node.clone(),
+                });
+            }
+            dialogue.set_start_node(Some(start_node));
+        }
+
+        if issues.is_empty() {
+            Ok((dialogue, self.adapter))
+        } else {
+            Err(DialogueBuildError { issues })
+        }
+    }
+}
+
+/// The IDs of all lines and options of the programs, sorted and without duplicates.
+fn line_ids(programs: &[Program]) -> Vec<u32> {
+    let mut line_ids: Vec<_> = programs
+        .iter()
+        .flat_map(|program| program.nodes.values())
+        .flat_map(|node| &node.instructions)
+        .filter_map(|instruction| match instruction.instruction_type.as_ref()? {
+            InstructionType::RunLine(instruction) => Some(instruction.line_id),
+            InstructionType::AddOption(instruction) => Some(instruction.tag_id),
+            _ => None,
+        })
+        .collect();
+    line_ids.sort_unstable();
+    line_ids.dedup();
+    line_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_lines;
+
+    #[test]
+    fn reports_all_issues_at_once() {
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([(LineId::from("line:1"), "Hello".to_owned())]);
+        text_provider.extend_translation("fr", [(LineId::from("line:1"), "Salut".to_owned())]);
+        text_provider.set_language(Some("fr".into()));
+
+        let error = DialogueBuilder::new()
+            .with_program(program_with_lines("Start", [1, 2]))
+            .with_text_provider(&text_provider)
+            .with_language("de")
+            .with_rng(SimulationRng::new(1))
+            .with_saliency_strategy(FirstSaliencyStrategy)
+            .with_start_node("Begin")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            vec![
+                DialogueBuildIssue::RngWithSaliencyStrategy,
+                DialogueBuildIssue::LanguageMismatch {
+                    language: "de".into(),
+                    text_provider_language: "fr".into(),
+                },
+                DialogueBuildIssue::MissingText {
+                    line_id: "line:2".into(),
+                },
+                DialogueBuildIssue::StartNodeNotFound {
+                    node_name: "Begin".to_owned(),
+                },
+            ],
+            error.issues
+        );
+        assert_eq!(
+            vec![DialogueBuildIssue::NoProgram],
+            DialogueBuilder::new().build().unwrap_err().issues
+        );
+
+        let mut dialogue = DialogueBuilder::new()
+            .with_program(program_with_lines("Intro", [1]))
+            .with_text_provider(&text_provider)
+            .with_language("fr-CA")
+            .with_start_node("Intro")
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some(&DialogueEvent::Line(1, vec![])),
+            dialogue.start().unwrap().last()
+        );
+    }
+    #[test]
+    fn builds_runners_with_adapters() {
+        #[derive(Debug)]
+        struct Adapter(StringTableTextProvider);
+
+        impl RuntimeAdapter for Adapter {
+            fn text_provider(&self) -> Option<&dyn TextProvider> {
+                Some(&self.0)
+            }
+        }
+
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([(LineId::from("line:1"), "Hello".to_owned())]);
+        let builder = || {
+            DialogueBuilder::new()
+                .with_program(program_with_lines("Start", [1]))
+                .with_adapter(Adapter(text_provider.clone()))
+        };
+        let runner = builder().build_runner().unwrap();
+        assert!(runner.adapter().is_some());
+
+        let error = builder()
+            .with_program(program_with_lines("Outro", [2]))
+            .build_runner()
+            .unwrap_err();
+        assert_eq!(
+            vec![DialogueBuildIssue::MissingText {
+                line_id: "line:2".into(),
+            }],
+            error.issues
+        );
+    }
+}
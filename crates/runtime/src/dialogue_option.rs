@@ -1,10 +1,12 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>, which we split off into multiple files
 
+use crate::prelude::*;
 use core::fmt::Display;
 
 /// An option to be presented to the user.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DialogueOption {
     /// The tag which selects this option.
     pub tag_id: u32,
@@ -28,6 +30,37 @@ pub struct DialogueOption {
     /// This is intended for situations where games wish to show options that the player _could_ have taken,
     /// if some other condition had been met (e.g. having enough "charisma" points).
     pub is_available: bool,
+
+    /// The values of the inline expressions of the option's text, to replace the placeholders `{0}`, `{1}`, etc. with,
+    /// e.g. via [`Dialogue::expand_substitutions`].
+    pub substitutions: Vec<YarnValue>,
+
+    #[cfg(feature = "condition_explanations")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub(crate) unavailability_reason: Option<UnavailabilityReason>,
+}
+
+impl DialogueOption {
+    /// Explains why the condition of this option failed, if [`DialogueOption::is_available`] is `false`.
+    /// Returns `None` for available options and for options that were not created by the [`Dialogue`], e.g. decoded ones.
+    #[cfg(feature = "condition_explanations")]
+    pub fn unavailability_reason(&self) -> Option<&UnavailabilityReason> {
+        self.unavailability_reason.as_ref()
+    }
+
+    /// A stable 64-bit hash identifying this option across runs, e.g. to record choices in analytics.
+    ///
+    /// The [`OptionId`] is only an index into the options currently presented, so the option is instead identified by the name of the node
+    /// it is presented in and its [`DialogueOption::tag_id`]. The hash is computed by a [`StableHasher`] fed with the UTF-8 bytes of the node name,
+    /// a [separator](StableHasher::write_separator) and the little-endian bytes of the tag.
+    pub fn stable_hash(&self, node_name: &str) -> u64 {
+        StableHasher::new()
+            .write(node_name.as_bytes())
+            .write_separator()
+            .write_u32(self.tag_id)
+            .finish()
+    }
 }
 
 /// The identifying number for an option. You should not need to create these yourself, since you get them from [`DialogueOption`]s.
@@ -36,6 +69,7 @@ pub struct DialogueOption {
 /// have [`DialogueOption::is_available`] set to `false`, so the index of an option may not be as it appears in the list of options presented to the user.11
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct OptionId(pub usize);
 
 impl Display for OptionId {
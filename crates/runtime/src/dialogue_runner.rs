@@ -0,0 +1,383 @@
+//! Not part of the original implementation, but inspired by the `DialogueRunner` of Yarn Spinner for Unity.
+//!
+//! [`Dialogue::continue_`] returns batches of events instead of calling handlers, because handlers that call back into the dialogue
+//! cannot be expressed without shared mutable state. This module layers the handler model of the original on top of that:
+//! handlers may request to continue or select an option, and the requests are carried out once the current batch was handled.
+
+use crate::prelude::*;
+use crate::Result;
+use alloc::collections::VecDeque;
+use core::fmt::{self, Debug};
+use core::time::Duration;
+
+type HandlerFn = dyn FnMut(&DialogueEvent, &mut DialogueRunnerContext<'_>) + Send + Sync;
+
+/// Owns a [`Dialogue`] and passes every [`DialogueEvent`] it produces to a list of handlers, like the `DialogueRunner` of Yarn Spinner for Unity.
+///
+/// Handlers cannot access the runner itself. Instead, they receive a [`DialogueRunnerContext`] through which they can request
+/// to continue the dialogue, select an option, jump to a node or stop. These requests are queued and executed in order
+/// after all events of the current batch were passed to all handlers, so handlers never run while the dialogue is executing.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use std::sync::{Arc, Mutex};
+/// let seen = Arc::new(Mutex::new(Vec::new()));
+/// let lines = seen.clone();
+/// let mut runner = DialogueRunner::new(Dialogue::new(Box::new(MemoryVariableStorage::new())))
+///     .with_handler(move |event, context| match event {
+///         DialogueEvent::Line(line_id, ..) => {
+///             lines.lock().unwrap().push(*line_id);
+///             // A game would continue once the line was presented
+///             context.continue_();
+///         }
+///         DialogueEvent::Options(options) => {
+///             context.select_option(options[0].id).continue_();
+///         }
+///         _ => {}
+///     });
+/// # let _ = &mut runner;
+/// ```
+pub struct DialogueRunner {
+    dialogue: Dialogue,
+    handlers: Vec<Box<HandlerFn>>,
+    requests: VecDeque<DialogueRequest>,
+    pub(crate) adapter: Option<Box<dyn RuntimeAdapter>>,
+    pub(crate) last_update: Option<Duration>,
+}
+
+/// A request issued by a handler of a [`DialogueRunner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DialogueRequest {
+    Continue,
+    SelectOption(OptionId),
+    JumpToNode(String),
+    Stop,
+}
+
+/// Passed to the handlers of a [`DialogueRunner`] to inspect the [`Dialogue`] and queue requests.
+/// Requests are executed in the order they were issued, after the current batch of events was handled.
+pub struct DialogueRunnerContext<'a> {
+    dialogue: &'a Dialogue,
+    requests: &'a mut VecDeque<DialogueRequest>,
+    text_provider: Option<&'a dyn TextProvider>,
+    asset_provider: Option<&'a dyn AssetProvider>,
+}
+
+impl DialogueRunnerContext<'_> {
+    /// The dialogue being run. It cannot be modified while events are handled, use the requests of this context instead.
+    pub fn dialogue(&self) -> &Dialogue {
+        self.dialogue
+    }
+
+    /// Looks up the text of the given line via the [`TextProvider`] of the runner's [`RuntimeAdapter`].
+    /// Returns `None` if the line is not known or there is no text provider.
+    pub fn text(&self, line_id: &LineId) -> Option<String> {
+        self.text_provider?.get_text(line_id)
+    }
+
+    /// Resolves whether the given line is a shadow line via the [`TextProvider`] of the runner's [`RuntimeAdapter`], see [`Line::resolve`].
+    /// Without a text provider, the line is treated as its own source.
+    pub fn line(&self, line_id: &LineId) -> Line {
+        match self.text_provider {
+            Some(text_provider) => Line::resolve(line_id.clone(), text_provider),
+            None => Line::new(line_id.clone()),
+        }
+    }
+
+    /// Looks up the assets of the given line, e.g. its voice-over clip, via the [`AssetProvider`] of the runner's [`RuntimeAdapter`].
+    /// Assets are resolved in the language of the adapter's [`TextProvider`], so they match the text, or else in the [`LineParser::language`] of the dialogue.
+    /// Returns an empty list if the line has no assets or there is no asset provider.
+    pub fn assets(&self, line_id: &LineId) -> Vec<AssetHandle> {
+        let Some(asset_provider) = self.asset_provider else {
+            return Vec::new();
+        };
+        let language = self
+            .text_provider
+            .and_then(TextProvider::get_language)
+            .unwrap_or_else(|| self.dialogue.line_parser().language().clone());
+        asset_provider.get_assets(line_id, &language)
+    }
+
+    /// Requests to continue the dialogue, see [`Dialogue::continue_`].
+    pub fn continue_(&mut self) -> &mut Self {
+        self.requests.push_back(DialogueRequest::Continue);
+        self
+    }
+
+    /// Requests to select the option with the given ID, see [`Dialogue::set_selected_option`].
+    /// The dialogue only continues with the selected option once [`DialogueRunnerContext::continue_`] is requested as well.
+    pub fn select_option(&mut self, option_id: OptionId) -> &mut Self {
+        self.requests
+            .push_back(DialogueRequest::SelectOption(option_id));
+        self
+    }
+
+    /// Requests to jump to the given node, see [`Dialogue::jump_to_node`].
+    pub fn jump_to_node(&mut self, node_name: impl Into<String>) -> &mut Self {
+        self.requests
+            .push_back(DialogueRequest::JumpToNode(node_name.into()));
+        self
+    }
+
+    /// Requests to stop the dialogue, see [`Dialogue::stop`]. Requests issued after this one are discarded.
+    pub fn stop(&mut self) -> &mut Self {
+        self.requests.push_back(DialogueRequest::Stop);
+        self
+    }
+}
+
+impl DialogueRunner {
+    /// Creates a runner without any handlers for the given dialogue.
+    pub fn new(dialogue: Dialogue) -> Self {
+        Self {
+            dialogue,
+            handlers: Vec::new(),
+            requests: VecDeque::new(),
+            adapter: None,
+            last_update: None,
+        }
+    }
+
+    /// Adds a handler that is called for every event, after the handlers added before it.
+    #[must_use]
+    pub fn with_handler(
+        mut self,
+        handler: impl FnMut(&DialogueEvent, &mut DialogueRunnerContext<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.add_handler(handler);
+        self
+    }
+
+    /// Adds a handler that is called for every event, after the handlers added before it.
+    pub fn add_handler(
+        &mut self,
+        handler: impl FnMut(&DialogueEvent, &mut DialogueRunnerContext<'_>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    /// The dialogue being run.
+    pub fn dialogue(&self) -> &Dialogue {
+        &self.dialogue
+    }
+
+    /// The dialogue being run. Changes made through this take effect immediately, not after pending requests.
+    pub fn dialogue_mut(&mut self) -> &mut Dialogue {
+        &mut self.dialogue
+    }
+
+    /// Returns the dialogue, discarding the handlers and the adapter.
+    pub fn into_dialogue(self) -> Dialogue {
+        self.dialogue
+    }
+
+    /// Sets the node to start at and continues the dialogue, handling all events until no more requests are pending.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error of the [`Dialogue`]. Pending requests are discarded in that case.
+    pub fn start(&mut self, node_name: impl Into<String>) -> Result<()> {
+        self.dialogue.set_node(node_name)?;
+        self.request_and_run(DialogueRequest::Continue)
+    }
+
+    /// Continues the dialogue, handling all events until no more requests are pending.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error of the [`Dialogue`]. Pending requests are discarded in that case.
+    pub fn continue_(&mut self) -> Result<()> {
+        self.request_and_run(DialogueRequest::Continue)
+    }
+
+    /// Selects an option and continues the dialogue, handling all events until no more requests are pending.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error of the [`Dialogue`]. Pending requests are discarded in that case.
+    pub fn select_option(&mut self, option_id: OptionId) -> Result<()> {
+        self.requests
+            .push_back(DialogueRequest::SelectOption(option_id));
+        self.request_and_run(DialogueRequest::Continue)
+    }
+
+    /// Advances a pending [`DialogueEvent::Wait`], see [`Dialogue::tick`], and handles the events of the dialogue if it continued.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error of the [`Dialogue`]. Pending requests are discarded in that case.
+    pub fn tick(&mut self, delta: Duration) -> Result<()> {
+        let events = self.dialogue.tick(delta)?;
+        self.dispatch(&events);
+        self.run_requests()
+    }
+
+    fn request_and_run(&mut self, request: DialogueRequest) -> Result<()> {
+        self.requests.push_back(request);
+        self.run_requests()
+    }
+
+    fn run_requests(&mut self) -> Result<()> {
+        while let Some(request) = self.requests.pop_front() {
+            let result = self.execute(request);
+            let events = match result {
+                Ok(events) => events,
+                Err(e) => {
+                    self.requests.clear();
+                    return Err(e);
+                }
+            };
+            self.dispatch(&events);
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, request: DialogueRequest) -> Result<Vec<DialogueEvent>> {
+        match request {
+            DialogueRequest::Continue => self.dialogue.continue_(),
+            DialogueRequest::SelectOption(option_id) => {
+                self.dialogue.set_selected_option(option_id)?;
+                Ok(Vec::new())
+            }
+            DialogueRequest::JumpToNode(node_name) => {
+                self.dialogue.jump_to_node(node_name)?;
+                Ok(Vec::new())
+            }
+            DialogueRequest::Stop => {
+                self.requests.clear();
+                Ok(self.dialogue.stop())
+            }
+        }
+    }
+
+    fn dispatch(&mut self, events: &[DialogueEvent]) {
+        let adapter = self.adapter.as_deref();
+        for event in events {
+            let mut context = DialogueRunnerContext {
+                dialogue: &self.dialogue,
+                requests: &mut self.requests,
+                text_provider: adapter.and_then(RuntimeAdapter::text_provider),
+                asset_provider: adapter.and_then(RuntimeAdapter::asset_provider),
+            };
+            if let Some(observer) = adapter.and_then(RuntimeAdapter::observer) {
+                observer.on_event(event);
+            }
+            if let (DialogueEvent::Command(command), Some(dispatcher)) =
+                (event, adapter.and_then(RuntimeAdapter::command_dispatcher))
+            {
+                if dispatcher.dispatch(command, &mut context) {
+                    continue;
+                }
+            }
+            for handler in &mut self.handlers {
+                handler(event, &mut context);
+            }
+        }
+    }
+}
+
+impl Debug for DialogueRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogueRunner")
+            .field("dialogue", &self.dialogue)
+            .field("handlers", &self.handlers.len())
+            .field("requests", &self.requests)
+            .field("adapter", &self.adapter)
+            .field("last_update", &self.last_update)
+            .finish()
+    }
+}
+
+impl Debug for DialogueRunnerContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogueRunnerContext")
+            .field("requests", &self.requests)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use crate::sync::Mutex;
+    use alloc::sync::Arc;
+    use yarnspinner_core::prelude::instruction::*;
+
+    fn run_line(line_id: u32) -> InstructionType {
+        InstructionType::RunLine(RunLineInstruction {
+            line_id,
+            substitution_count: 0,
+        })
+    }
+
+    #[test]
+    fn handlers_can_continue_and_select_options() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 0,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::Pop(PopInstruction {}),
+                run_line(3),
+                InstructionType::RunCommand(RunCommandInstruction {
+                    command_text: "stop".to_owned(),
+                    substitution_count: 0,
+                }),
+                run_line(4),
+            ],
+        ));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let handler_log = log.clone();
+        let mut runner = DialogueRunner::new(dialogue).with_handler(move |event, context| {
+            handler_log.lock().push(event.clone());
+            match event {
+                DialogueEvent::Line(..) => {
+                    assert!(!context.dialogue().is_waiting_for_option_selection());
+                    context.continue_();
+                }
+                DialogueEvent::Options(options) => {
+                    context.select_option(options[0].id).continue_();
+                }
+                DialogueEvent::Command(command) if command.name == "stop" => {
+                    context.stop().continue_();
+                }
+                _ => {}
+            }
+        });
+        runner.start("Start").unwrap();
+
+        assert_eq!(
+            vec![
+                DialogueEvent::NodeStart("Start".into()),
+                DialogueEvent::Line(1, vec![]),
+                DialogueEvent::Options(vec![DialogueOption {
+                    tag_id: 2,
+                    id: OptionId(0),
+                    destination_node: 0,
+                    is_available: true,
+                    substitutions: vec![],
+                    #[cfg(feature = "condition_explanations")]
+                    unavailability_reason: None,
+                }]),
+                DialogueEvent::Line(3, vec![]),
+                DialogueEvent::Command(Command::parse("stop".to_owned()).unwrap()),
+                DialogueEvent::DialogueComplete,
+            ],
+            *log.lock()
+        );
+        assert!(!runner.dialogue().is_active());
+        assert!(runner.continue_().is_err());
+        assert!(runner.requests.is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Not part of the original implementation.
+//!
+//! Lets many [`Dialogue`]s run the same [`Program`] with the same [`Library`] and string table without copying them,
+//! e.g. ambient barks of NPCs running alongside the main conversation.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+
+/// A cheaply cloneable, read-only handle to a loaded [`Program`], shared by every [`Dialogue`] it is set on
+/// via [`Dialogue::set_shared_program`].
+///
+/// Loading a program links it: its instructions are moved out and rewritten into a compact form whose strings are interned,
+/// so that every distinct string is stored only once. The handle therefore only exposes the node names, headers and
+/// initial values of the program. Tools that read instructions, like [`Program::disassemble`], need the original [`Program`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramHandle {
+    pub(crate) program: Arc<Program>,
+    pub(crate) linked: Arc<LinkedProgram>,
+}
+
+impl ProgramHandle {
+    /// Links the program and wraps it in a new handle.
+    pub fn new(mut program: Program) -> Self {
+        let mut linked = LinkedProgram::default();
+        linked.link(&mut program);
+        program.shrink_to_fit();
+        Self {
+            program: Arc::new(program),
+            linked: Arc::new(linked),
+        }
+    }
+
+    /// Returns `true` if both handles refer to the same program.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.program, &other.program)
+    }
+
+    /// The names of the program's nodes, in alphabetical order.
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.program.nodes.keys().map(String::as_str)
+    }
+
+    /// The headers of the node with the given name, or `None` if there is no such node.
+    pub fn headers(&self, node_name: &str) -> Option<&[Header]> {
+        self.program
+            .nodes
+            .get(node_name)
+            .map(|node| node.headers.as_slice())
+    }
+
+    /// The initial values of the variables declared by the program.
+    pub fn initial_values(&self) -> impl Iterator<Item = (&str, &Operand)> {
+        self.program
+            .initial_values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// The approximate number of bytes allocated on the heap by the program, including its linked instructions.
+    pub fn heap_size(&self) -> usize {
+        self.program.heap_size() + self.linked.heap_size()
+    }
+
+    /// Links the given program and merges it into this one, copying both first if they are shared.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if both programs contain a node of the same name.
+    pub(crate) fn merge(&mut self, mut program: Program) {
+        Arc::make_mut(&mut self.linked).link(&mut program);
+        program.shrink_to_fit();
+        let existing = Arc::unwrap_or_clone(core::mem::take(&mut self.program));
+        self.program = Arc::new(Program::combine(vec![existing, program]).unwrap());
+    }
+}
+
+impl From<Program> for ProgramHandle {
+    fn from(program: Program) -> Self {
+        Self::new(program)
+    }
+}
+
+/// Holds a [`Program`], [`Library`] and optionally a string table once and hands out lightweight [`Dialogue`]s that share them.
+///
+/// Each spawned dialogue has its own [`VariableStorage`] and execution state, so any number of them can run at the same time.
+/// A dialogue that calls [`Dialogue::library_mut`] or [`Dialogue::add_program`] gets its own copy of the library or program first,
+/// leaving the others untouched.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # let program = Program::default();
+/// let runtime = DialogueRuntime::new(program).with_string_table(StringTableTextProvider::new());
+/// let main_conversation = runtime.spawn(Box::new(MemoryVariableStorage::new()));
+/// let barks: Vec<_> = (0..20)
+///     .map(|_| runtime.spawn(Box::new(MemoryVariableStorage::new())))
+///     .collect();
+/// assert!(barks[0].program_handle().unwrap().ptr_eq(runtime.program()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DialogueRuntime {
+    program: ProgramHandle,
+    library: Arc<Library>,
+    string_table: Option<Arc<StringTableTextProvider>>,
+}
+
+impl DialogueRuntime {
+    /// Creates a runtime for the given program with the standard library.
+    pub fn new(program: impl Into<ProgramHandle>) -> Self {
+        Self {
+            program: program.into(),
+            library: Arc::new(Library::standard_library()),
+            string_table: None,
+        }
+    }
+
+    /// Adds the functions of the given library to the standard library shared by all spawned dialogues.
+    #[must_use]
+    pub fn with_library(mut self, library: Library) -> Self {
+        Arc::make_mut(&mut self.library).import(library);
+        self
+    }
+
+    /// Sets the string table shared by all spawned dialogues, see [`DialogueRuntime::string_table`].
+    #[must_use]
+    pub fn with_string_table(mut self, string_table: StringTableTextProvider) -> Self {
+        self.string_table = Some(Arc::new(string_table));
+        self
+    }
+
+    /// The shared program.
+    pub fn program(&self) -> &ProgramHandle {
+        &self.program
+    }
+
+    /// The shared library.
+    pub fn library(&self) -> &Library {
+        &self.library
+    }
+
+    /// The shared string table, if one was set. Clone the [`Arc`] into the [`RuntimeAdapter`] of each [`DialogueRunner`]
+    /// to look up the text of all dialogues in it.
+    pub fn string_table(&self) -> Option<&Arc<StringTableTextProvider>> {
+        self.string_table.as_ref()
+    }
+
+    /// Creates a new dialogue running the shared program with the shared library.
+    /// The variable storage receives the initial values of the program's variables.
+    #[must_use]
+    pub fn spawn(&self, variable_storage: Box<dyn VariableStorage>) -> Dialogue {
+        let mut dialogue = Dialogue::with_shared_library(self.library.clone(), variable_storage);
+        dialogue.set_shared_program(&self.program);
+        dialogue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_lines;
+
+    #[test]
+    fn spawns_dialogues_sharing_one_program() {
+        let runtime = DialogueRuntime::new(program_with_lines("Bark", [1, 2]));
+        let mut first = runtime.spawn(Box::new(MemoryVariableStorage::new()));
+        let mut second = runtime.spawn(Box::new(MemoryVariableStorage::new()));
+        assert!(first.program_handle().unwrap().ptr_eq(runtime.program()));
+        assert!(second.program_handle().unwrap().ptr_eq(runtime.program()));
+
+        first.set_node("Bark").unwrap();
+        second.set_node("Bark").unwrap();
+        assert!(first
+            .continue_()
+            .unwrap()
+            .contains(&DialogueEvent::Line(1, vec![])));
+        assert!(first
+            .continue_()
+            .unwrap()
+            .contains(&DialogueEvent::Line(2, vec![])));
+        assert!(second
+            .continue_()
+            .unwrap()
+            .contains(&DialogueEvent::Line(1, vec![])));
+        // `visited` is bound to the variable storage of each dialogue
+        first
+            .set_variable(
+                &Library::generate_unique_visited_variable_for_node("Bark"),
+                1.0,
+            )
+            .unwrap();
+        let visited = |dialogue: &Dialogue| dialogue.evaluate_expression(r#"visited("Bark")"#);
+        assert_eq!(YarnValue::Boolean(true), visited(&first).unwrap());
+        assert_eq!(YarnValue::Boolean(false), visited(&second).unwrap());
+
+        // Changing the library of one dialogue leaves the others untouched
+        first.library_mut().add_function("bark_volume", || 1.0);
+        assert!(first.library().contains_function("bark_volume"));
+        assert!(!second.library().contains_function("bark_volume"));
+        assert!(!runtime.library().contains_function("bark_volume"));
+
+        // So does merging another program into it
+        first.add_program(program_with_lines("Greeting", [3]));
+        assert!(!first.program_handle().unwrap().ptr_eq(runtime.program()));
+        assert!(!second.node_exists("Greeting"));
+    }
+    #[test]
+    fn exposes_nodes_without_instructions() {
+        let mut program = program_with_lines("Bark", [1]);
+        program.nodes.get_mut("Bark").unwrap().headers = vec![Header {
+            key: "tags".to_owned(),
+            value: "ambient".to_owned(),
+        }];
+        program
+            .initial_values
+            .insert("$volume".to_owned(), 1.0.into());
+        program.nodes.extend(program_with_lines("Alarm", [2]).nodes);
+        let handle = ProgramHandle::new(program);
+
+        assert_eq!(
+            vec!["Alarm", "Bark"],
+            handle.node_names().collect::<Vec<_>>()
+        );
+        assert_eq!("ambient", handle.headers("Bark").unwrap()[0].value);
+        assert_eq!(Some(&[][..]), handle.headers("Alarm"));
+        assert_eq!(None, handle.headers("Missing"));
+        assert_eq!(
+            vec![("$volume", &Operand::from(1.0))],
+            handle.initial_values().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Not part of the original implementation.
+//!
+//! Shares an instruction budget between many [`Dialogue`]s running at once, e.g. ambient chatter of NPCs,
+//! so that a single conversation that loops for a long time cannot starve the others.
+
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+
+/// Identifies a [`Dialogue`] owned by a [`DialogueScheduler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScheduledDialogueId(pub usize);
+
+/// Continues many [`Dialogue`]s in a round-robin fashion within a per-frame instruction budget.
+///
+/// Request a dialogue to be continued with [`DialogueScheduler::continue_`], then call [`DialogueScheduler::run_frame`] once per frame.
+/// Each pending dialogue gets up to [`DialogueScheduler::instructions_per_turn`] instructions via [`Dialogue::continue_with_budget`] per turn,
+/// and turns are handed out in order until the frame's budget is used up or no dialogue is pending anymore.
+/// A dialogue whose turn ran out before it produced an event stays pending and resumes in its next turn.
+/// The next frame starts with the dialogue after the last one that ran, so every dialogue gets its turn eventually.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut scheduler = DialogueScheduler::new(100);
+/// let guard = scheduler.insert(Dialogue::new(Box::new(MemoryVariableStorage::new())));
+/// // Once the guard's node was set and its last line was shown:
+/// scheduler.continue_(guard);
+/// let frame = scheduler.run_frame(1000);
+/// for (id, event) in frame.events {
+///     // Present the event for the dialogue with the given ID
+/// }
+/// for (id, error) in frame.errors {
+///     eprintln!("Dialogue {id:?} failed: {error}");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DialogueScheduler {
+    dialogues: BTreeMap<ScheduledDialogueId, ScheduledDialogue>,
+    next_id: usize,
+    next_turn: ScheduledDialogueId,
+    instructions_per_turn: usize,
+}
+
+#[derive(Debug)]
+struct ScheduledDialogue {
+    dialogue: Dialogue,
+    pending: bool,
+}
+
+/// The result of [`DialogueScheduler::run_frame`].
+#[derive(Debug, Default)]
+pub struct ScheduledFrame {
+    /// The events produced in this frame, in the order they were produced.
+    pub events: Vec<(ScheduledDialogueId, DialogueEvent)>,
+    /// The errors returned by dialogues in this frame. A dialogue that errored is no longer pending.
+    pub errors: Vec<(ScheduledDialogueId, DialogueError)>,
+    /// The number of instructions executed in this frame.
+    pub instructions_executed: usize,
+}
+
+impl DialogueScheduler {
+    /// Creates an empty scheduler that runs at most `instructions_per_turn` instructions of a dialogue before moving on to the next one.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `instructions_per_turn` is zero.
+    pub fn new(instructions_per_turn: usize) -> Self {
+        assert!(
+            instructions_per_turn > 0,
+            "A dialogue needs at least one instruction per turn"
+        );
+        Self {
+            dialogues: BTreeMap::new(),
+            next_id: 0,
+            next_turn: ScheduledDialogueId(0),
+            instructions_per_turn,
+        }
+    }
+
+    /// The maximum number of instructions a dialogue may run before the next one gets its turn.
+    pub fn instructions_per_turn(&self) -> usize {
+        self.instructions_per_turn
+    }
+
+    /// Adds a dialogue to the scheduler. It is not pending until [`DialogueScheduler::continue_`] is called.
+    pub fn insert(&mut self, dialogue: Dialogue) -> ScheduledDialogueId {
+        let id = ScheduledDialogueId(self.next_id);
+        self.next_id += 1;
+        self.dialogues.insert(
+            id,
+            ScheduledDialogue {
+                dialogue,
+                pending: false,
+            },
+        );
+        id
+    }
+
+    /// Removes a dialogue from the scheduler and returns it.
+    pub fn remove(&mut self, id: ScheduledDialogueId) -> Option<Dialogue> {
+        self.dialogues
+            .remove(&id)
+            .map(|scheduled| scheduled.dialogue)
+    }
+
+    /// Gets the dialogue with the given ID.
+    pub fn get(&self, id: ScheduledDialogueId) -> Option<&Dialogue> {
+        self.dialogues.get(&id).map(|scheduled| &scheduled.dialogue)
+    }
+
+    /// Gets the dialogue with the given ID, e.g. to set its node or select an option before continuing it.
+    pub fn get_mut(&mut self, id: ScheduledDialogueId) -> Option<&mut Dialogue> {
+        self.dialogues
+            .get_mut(&id)
+            .map(|scheduled| &mut scheduled.dialogue)
+    }
+
+    /// The number of dialogues in the scheduler.
+    pub fn len(&self) -> usize {
+        self.dialogues.len()
+    }
+
+    /// Returns `true` if the scheduler holds no dialogues.
+    pub fn is_empty(&self) -> bool {
+        self.dialogues.is_empty()
+    }
+
+    /// Requests the dialogue with the given ID to be continued in the next [`DialogueScheduler::run_frame`].
+    /// Returns `false` if there is no such dialogue.
+    pub fn continue_(&mut self, id: ScheduledDialogueId) -> bool {
+        let Some(scheduled) = self.dialogues.get_mut(&id) else {
+            return false;
+        };
+        scheduled.pending = true;
+        true
+    }
+
+    /// Returns `true` if the dialogue with the given ID will be continued in the next [`DialogueScheduler::run_frame`].
+    pub fn is_pending(&self, id: ScheduledDialogueId) -> bool {
+        self.dialogues
+            .get(&id)
+            .is_some_and(|scheduled| scheduled.pending)
+    }
+
+    /// Continues pending dialogues in turns until `instruction_budget` instructions were executed or no dialogue is pending anymore.
+    pub fn run_frame(&mut self, instruction_budget: usize) -> ScheduledFrame {
+        let mut frame = ScheduledFrame::default();
+        while frame.instructions_executed < instruction_budget {
+            let Some(id) = self.next_pending() else {
+                break;
+            };
+            self.next_turn = ScheduledDialogueId(id.0 + 1);
+            let scheduled = self.dialogues.get_mut(&id).unwrap();
+            let turn = self
+                .instructions_per_turn
+                .min(instruction_budget - frame.instructions_executed);
+            let instructions_before = scheduled.dialogue.instructions_executed();
+            let result = scheduled.dialogue.continue_with_budget(turn);
+            frame.instructions_executed +=
+                (scheduled.dialogue.instructions_executed() - instructions_before) as usize;
+            match result {
+                Ok(events) => {
+                    scheduled.pending = scheduled.dialogue.is_suspended();
+                    frame
+                        .events
+                        .extend(events.into_iter().map(|event| (id, event)));
+                }
+                Err(e) => {
+                    scheduled.pending = false;
+                    frame.errors.push((id, e));
+                }
+            }
+        }
+        frame
+    }
+
+    /// The first pending dialogue starting at `next_turn`, wrapping around.
+    fn next_pending(&self) -> Option<ScheduledDialogueId> {
+        self.dialogues
+            .range(self.next_turn..)
+            .chain(self.dialogues.range(..self.next_turn))
+            .find(|(_, scheduled)| scheduled.pending)
+            .map(|(id, _)| *id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    fn dialogue_with_instructions(count: usize, line_id: u32) -> Dialogue {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        let busy_work = [
+            InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+            InstructionType::Pop(PopInstruction {}),
+        ];
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            busy_work
+                .iter()
+                .cloned()
+                .cycle()
+                .take(count)
+                .chain([InstructionType::RunLine(RunLineInstruction {
+                    line_id,
+                    substitution_count: 0,
+                })]),
+        ));
+        dialogue.set_node("Start").unwrap();
+        dialogue
+    }
+
+    fn lines(frame: &ScheduledFrame) -> Vec<(ScheduledDialogueId, u32)> {
+        frame
+            .events
+            .iter()
+            .filter_map(|(id, event)| match event {
+                DialogueEvent::Line(line_id, ..) => Some((*id, *line_id)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn long_running_dialogue_does_not_starve_others() {
+        let mut scheduler = DialogueScheduler::new(10);
+        let pathological = scheduler.insert(dialogue_with_instructions(1000, 1));
+        let chatter = scheduler.insert(dialogue_with_instructions(4, 2));
+        let other_chatter = scheduler.insert(dialogue_with_instructions(4, 3));
+        for id in [pathological, chatter, other_chatter] {
+            scheduler.continue_(id);
+        }
+
+        let frame = scheduler.run_frame(30);
+        assert!(frame.errors.is_empty());
+        assert_eq!(30, frame.instructions_executed);
+        assert_eq!(vec![(chatter, 2), (other_chatter, 3)], lines(&frame));
+        assert!(scheduler.is_pending(pathological));
+        assert!(!scheduler.is_pending(chatter));
+        assert!(scheduler.get(pathological).unwrap().is_suspended());
+
+        let mut frames = 0;
+        while scheduler.is_pending(pathological) {
+            let frame = scheduler.run_frame(100);
+            assert!(frame.instructions_executed <= 100);
+            frames += 1;
+        }
+        assert_eq!(10, frames);
+        assert!(!scheduler.get(pathological).unwrap().is_suspended());
+    }
+}
@@ -0,0 +1,124 @@
+//! Not part of the original implementation.
+//!
+//! Exposes the execution state of the virtual machine, so engine adapters don't have to track it themselves.
+
+use crate::prelude::*;
+
+/// The state of a [`Dialogue`], see [`Dialogue::state`].
+///
+/// Use [`Dialogue::can_continue`] to find out whether [`Dialogue::continue_`] may be called,
+/// since that also depends on whether a node was selected and whether a [`CommandCompletionToken`] is still pending.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DialogueState {
+    /// The dialogue is not running a node, either because none was started yet or because the dialogue was completed or stopped.
+    /// A node selected via [`Dialogue::set_node`] is started on the next call to [`Dialogue::continue_`].
+    #[default]
+    Stopped,
+    /// The dialogue is executing the instructions of a node.
+    /// Calls to [`Dialogue::continue_`] run until the dialogue waits for the game, yields or stops,
+    /// so they only leave this state behind when they return an error. The dialogue then stays at the failing instruction.
+    Running,
+    /// [`Dialogue::continue_with_budget`] ran out of instructions in the middle of a node, see [`Dialogue::is_suspended`].
+    /// The next call to [`Dialogue::continue_`] or [`Dialogue::continue_with_budget`] resumes where it left off.
+    Yielded,
+    /// The dialogue delivered its events and waits for [`Dialogue::continue_`] to be called.
+    WaitingForContinue,
+    /// The dialogue sent a [`DialogueEvent::Options`] and waits for [`Dialogue::set_selected_option`] to be called.
+    WaitingOnOptionSelection,
+    /// The dialogue reached a [`Breakpoint`] and resumes on the next call to [`Dialogue::continue_`], see [`Dialogue::is_paused`].
+    Paused,
+}
+
+impl From<ExecutionState> for DialogueState {
+    fn from(state: ExecutionState) -> Self {
+        match state {
+            ExecutionState::Stopped => Self::Stopped,
+            ExecutionState::Running => Self::Running,
+            ExecutionState::Yielded => Self::Yielded,
+            ExecutionState::WaitingForContinue => Self::WaitingForContinue,
+            ExecutionState::WaitingOnOptionSelection => Self::WaitingOnOptionSelection,
+            ExecutionState::Paused => Self::Paused,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn follows_the_execution_of_the_dialogue() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let program = program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 4,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                run_line(3),
+                InstructionType::Stop(StopInstruction {}),
+            ],
+        );
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program);
+        assert_eq!(DialogueState::Stopped, dialogue.state());
+        assert!(!dialogue.can_continue());
+
+        dialogue.set_node("Start").unwrap();
+        assert_eq!(DialogueState::Stopped, dialogue.state());
+        assert!(dialogue.can_continue());
+
+        dialogue.continue_with_budget(0).unwrap();
+        assert_eq!(DialogueState::Yielded, dialogue.state());
+        assert!(dialogue.is_suspended());
+        dialogue.continue_().unwrap();
+        assert_eq!(DialogueState::WaitingForContinue, dialogue.state());
+        dialogue.continue_().unwrap();
+        assert_eq!(DialogueState::WaitingOnOptionSelection, dialogue.state());
+        assert!(!dialogue.can_continue());
+
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        assert_eq!(DialogueState::WaitingForContinue, dialogue.state());
+        assert_eq!(
+            Some(&DialogueEvent::Line(3, vec![])),
+            dialogue.continue_().unwrap().first()
+        );
+        dialogue.continue_().unwrap();
+        assert_eq!(DialogueState::Stopped, dialogue.state());
+        assert!(!dialogue.can_continue());
+    }
+
+    #[test]
+    fn stays_running_at_a_failed_instruction() {
+        let program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushFloat(PushFloatInstruction { value: 0.0 }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "missing".to_owned(),
+                }),
+                InstructionType::Stop(StopInstruction {}),
+            ],
+        );
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program).set_node("Start").unwrap();
+
+        assert!(dialogue.continue_().is_err());
+        assert_eq!(DialogueState::Running, dialogue.state());
+    }
+}
@@ -0,0 +1,731 @@
+//! Not part of the original implementation.
+//!
+//! A compact binary encoding of [`DialogueEvent`]s for mirroring a dialogue from a server to its clients.
+//! Unlike the `serde` implementations, it needs no serialization format crate and keeps packets small:
+//! integers are variable-length, node names are sent once per stream, and option lists only contain what changed since the previous one.
+//!
+//! ## Format
+//!
+//! A packet starts with the format version [`EVENT_CODEC_VERSION`] and the number of events, followed by the events.
+//! Every event is a tag byte followed by its fields. Integers are LEB128 varints, signed ones zigzag-encoded first.
+//! Strings are a varint length followed by UTF-8 bytes. A node name is a varint that is either `0`, followed by the name,
+//! which is then added to the names of the stream, or the index of an earlier name plus one.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::time::Duration;
+use hashbrown::HashMap;
+
+/// The version written at the start of every packet. Packets of other versions are rejected with [`EventDecodeError::UnsupportedVersion`].
+pub const EVENT_CODEC_VERSION: u8 = 5;
+
+const LINE: u8 = 0;
+const LINE_HINTS: u8 = 1;
+const OPTIONS: u8 = 2;
+const COMMAND: u8 = 3;
+const WAIT: u8 = 4;
+const NODE_COMPLETE: u8 = 5;
+const NODE_START: u8 = 6;
+const BREAKPOINT_HIT: u8 = 7;
+const DIALOGUE_COMPLETE: u8 = 8;
+const LINE_INTERRUPTED: u8 = 9;
+const LINE_FILTERED: u8 = 10;
+
+const FILTER_SUPPRESS: u8 = 0;
+const FILTER_REPLACE: u8 = 1;
+const FILTER_ANNOTATE: u8 = 2;
+
+const OPTION_AVAILABLE: u8 = 1;
+/// The tag and destination are the same as those of the option at the same index in the previous list.
+const OPTION_UNCHANGED: u8 = 1 << 1;
+/// The ID is not the index of the option and follows explicitly.
+const OPTION_EXPLICIT_ID: u8 = 1 << 2;
+/// The option has substitutions, which follow as a varint count and the values.
+const OPTION_SUBSTITUTIONS: u8 = 1 << 3;
+
+/// The command is reconstructed by parsing its raw text.
+const COMMAND_RAW: u8 = 0;
+const COMMAND_FULL: u8 = 1;
+
+const NUMBER: u8 = 0;
+const INTEGER: u8 = 1;
+const STRING: u8 = 2;
+const FALSE: u8 = 3;
+const TRUE: u8 = 4;
+
+/// An error returned when decoding a packet created by an [`EventEncoder`] fails.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventDecodeError {
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidTag(u8),
+    InvalidUtf8,
+    VarintOverflow,
+    UnknownNodeName(u64),
+    /// An option refers to the previous option list, which is shorter.
+    MissingPreviousOption(usize),
+    UnexpectedEventCount(usize),
+    TrailingBytes(usize),
+}
+
+impl Error for EventDecodeError {}
+
+impl Display for EventDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use EventDecodeError::*;
+        match self {
+            UnsupportedVersion(version) => write!(f, "Unsupported event codec version {version}, expected {EVENT_CODEC_VERSION}"),
+            Truncated => f.write_str("The packet ended in the middle of an event"),
+            InvalidTag(tag) => write!(f, "Invalid tag {tag}"),
+            InvalidUtf8 => f.write_str("A string is not valid UTF-8"),
+            VarintOverflow => f.write_str("An integer is out of range"),
+            UnknownNodeName(index) => write!(f, "The node name {index} was not sent before. Packets must be decoded in the order they were encoded"),
+            MissingPreviousOption(index) => write!(f, "Option {index} refers to the previous option list, which does not contain it. Packets must be decoded in the order they were encoded"),
+            UnexpectedEventCount(count) => write!(f, "Expected a single event, found {count}"),
+            TrailingBytes(count) => write!(f, "Found {count} bytes after the last event"),
+        }
+    }
+}
+
+impl DialogueEvent {
+    /// Encodes this event as a self-contained packet, see [`EventEncoder`]. Use an [`EventEncoder`] instead to send a stream of events,
+    /// which is smaller because node names and options are not repeated.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let event = DialogueEvent::Line(42, vec![]);
+    /// let packet = event.encode();
+    /// assert_eq!(5, packet.len());
+    /// assert_eq!(event, DialogueEvent::decode(&packet).unwrap());
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        EventEncoder::new().encode(core::slice::from_ref(self))
+    }
+
+    /// Decodes a packet created by [`DialogueEvent::encode`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`EventDecodeError`] if the packet is malformed or does not contain exactly one event.
+    pub fn decode(packet: &[u8]) -> core::result::Result<Self, EventDecodeError> {
+        let mut events = EventDecoder::new().decode(packet)?;
+        if events.len() != 1 {
+            return Err(EventDecodeError::UnexpectedEventCount(events.len()));
+        }
+        Ok(events.remove(0))
+    }
+}
+
+/// Encodes batches of [`DialogueEvent`]s, e.g. those returned by [`Dialogue::continue_`], into packets for an [`EventDecoder`].
+///
+/// Packets depend on the ones encoded before them, so the decoder must receive all of them in order,
+/// e.g. over a reliable connection. Call [`EventEncoder::reset`] and [`EventDecoder::reset`] together to start over, e.g. when a client reconnects.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut encoder = EventEncoder::new();
+/// let mut decoder = EventDecoder::new();
+/// for batch in [
+///     vec![DialogueEvent::NodeStart("Start".into()), DialogueEvent::Line(1, vec![])],
+///     vec![DialogueEvent::NodeComplete("Start".into()), DialogueEvent::DialogueComplete],
+/// ] {
+///     let packet = encoder.encode(&batch);
+///     assert_eq!(batch, decoder.decode(&packet).unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventEncoder {
+    node_names: HashMap<Arc<str>, u64>,
+    previous_options: Vec<DialogueOption>,
+}
+
+impl EventEncoder {
+    /// Creates an encoder for a new stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets all previously sent node names and options.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Encodes the events into a packet.
+    pub fn encode(&mut self, events: &[DialogueEvent]) -> Vec<u8> {
+        let mut packet = vec![EVENT_CODEC_VERSION];
+        write_varint(&mut packet, events.len() as u64);
+        for event in events {
+            self.encode_event(event, &mut packet);
+        }
+        packet
+    }
+
+    fn encode_event(&mut self, event: &DialogueEvent, out: &mut Vec<u8>) {
+        match event {
+            DialogueEvent::Line(line_id, substitutions) => {
+                out.push(LINE);
+                write_varint(out, u64::from(*line_id));
+                write_varint(out, substitutions.len() as u64);
+                for substitution in substitutions {
+                    write_value(out, substitution);
+                }
+            }
+            DialogueEvent::LineInterrupted(line_id) => {
+                out.push(LINE_INTERRUPTED);
+                write_varint(out, u64::from(*line_id));
+            }
+            DialogueEvent::LineFiltered(record) => {
+                out.push(LINE_FILTERED);
+                write_varint(out, u64::from(record.line_id));
+                write_string(out, &record.hashtag);
+                match record.action {
+                    LineFilterAction::Suppress => out.push(FILTER_SUPPRESS),
+                    LineFilterAction::Replace(replacement) => {
+                        out.push(FILTER_REPLACE);
+                        write_varint(out, u64::from(replacement));
+                    }
+                    LineFilterAction::Annotate => out.push(FILTER_ANNOTATE),
+                }
+            }
+            DialogueEvent::LineHints(hints) => {
+                out.push(LINE_HINTS);
+                out.push(
+                    u8::from(hints.is_last_line_before_options)
+                        | u8::from(hints.is_final_line_of_node) << 1
+                        | u8::from(hints.is_final_line_of_dialogue) << 2,
+                );
+            }
+            DialogueEvent::Options(options) => {
+                out.push(OPTIONS);
+                write_varint(out, options.len() as u64);
+                for (index, option) in options.iter().enumerate() {
+                    let unchanged = self.previous_options.get(index).is_some_and(|previous| {
+                        previous.tag_id == option.tag_id
+                            && previous.destination_node == option.destination_node
+                    });
+                    let explicit_id = option.id.0 != index;
+                    let mut flags = u8::from(option.is_available);
+                    if unchanged {
+                        flags |= OPTION_UNCHANGED;
+                    }
+                    if explicit_id {
+                        flags |= OPTION_EXPLICIT_ID;
+                    }
+                    if !option.substitutions.is_empty() {
+                        flags |= OPTION_SUBSTITUTIONS;
+                    }
+                    out.push(flags);
+                    if !unchanged {
+                        write_varint(out, u64::from(option.tag_id));
+                        write_varint(out, zigzag(i64::from(option.destination_node)));
+                    }
+                    if explicit_id {
+                        write_varint(out, option.id.0 as u64);
+                    }
+                    if !option.substitutions.is_empty() {
+                        write_varint(out, option.substitutions.len() as u64);
+                        for substitution in &option.substitutions {
+                            write_value(out, substitution);
+                        }
+                    }
+                }
+                self.previous_options.clone_from(options);
+            }
+            DialogueEvent::Command(command) => {
+                out.push(COMMAND);
+                let reparsed = Command::parse(command.raw.clone());
+                if reparsed.is_some_and(|reparsed| is_identical_command(&reparsed, command)) {
+                    out.push(COMMAND_RAW);
+                    write_string(out, &command.raw);
+                } else {
+                    out.push(COMMAND_FULL);
+                    write_string(out, &command.raw);
+                    write_string(out, &command.name);
+                    write_varint(out, command.parameters.len() as u64);
+                    for parameter in &command.parameters {
+                        write_value(out, parameter);
+                    }
+                }
+            }
+            DialogueEvent::Wait(duration) => {
+                out.push(WAIT);
+                write_varint(out, duration.as_secs());
+                write_varint(out, u64::from(duration.subsec_nanos()));
+            }
+            DialogueEvent::NodeComplete(node_name) => {
+                out.push(NODE_COMPLETE);
+                self.write_node_name(out, node_name);
+            }
+            DialogueEvent::NodeStart(node_name) => {
+                out.push(NODE_START);
+                self.write_node_name(out, node_name);
+            }
+            DialogueEvent::BreakpointHit(breakpoint) => {
+                out.push(BREAKPOINT_HIT);
+                self.write_node_name(out, &breakpoint.node_name);
+                match breakpoint.location {
+                    BreakpointLocation::Line(line_id) => {
+                        out.push(0);
+                        write_varint(out, u64::from(line_id));
+                    }
+                    BreakpointLocation::Instruction(instruction) => {
+                        out.push(1);
+                        write_varint(out, instruction as u64);
+                    }
+                }
+            }
+            DialogueEvent::DialogueComplete => out.push(DIALOGUE_COMPLETE),
+        }
+    }
+
+    fn write_node_name(&mut self, out: &mut Vec<u8>, node_name: &Arc<str>) {
+        if let Some(index) = self.node_names.get(node_name) {
+            write_varint(out, index + 1);
+            return;
+        }
+        write_varint(out, 0);
+        write_string(out, node_name);
+        let index = self.node_names.len() as u64;
+        self.node_names.insert(node_name.clone(), index);
+    }
+}
+
+/// Decodes packets created by an [`EventEncoder`], see there for an example.
+#[derive(Debug, Clone, Default)]
+pub struct EventDecoder {
+    node_names: Vec<Arc<str>>,
+    previous_options: Vec<DialogueOption>,
+}
+
+impl EventDecoder {
+    /// Creates a decoder for a new stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets all previously received node names and options.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Decodes the events of a packet. The packets of a stream must be decoded in the order they were encoded.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`EventDecodeError`] if the packet is malformed. The decoder is left unchanged in that case.
+    pub fn decode(
+        &mut self,
+        packet: &[u8],
+    ) -> core::result::Result<Vec<DialogueEvent>, EventDecodeError> {
+        let node_name_count = self.node_names.len();
+        let result = self.decode_events(&mut Reader(packet));
+        if result.is_err() {
+            self.node_names.truncate(node_name_count);
+        }
+        result
+    }
+
+    fn decode_events(
+        &mut self,
+        reader: &mut Reader<'_>,
+    ) -> core::result::Result<Vec<DialogueEvent>, EventDecodeError> {
+        let version = reader.byte()?;
+        if version != EVENT_CODEC_VERSION {
+            return Err(EventDecodeError::UnsupportedVersion(version));
+        }
+        let count = reader.length()?;
+        let mut events = Vec::new();
+        let mut previous_options = None;
+        for _ in 0..count {
+            let event = self.decode_event(reader, previous_options.as_ref())?;
+            if let DialogueEvent::Options(options) = &event {
+                previous_options = Some(options.clone());
+            }
+            events.push(event);
+        }
+        if !reader.0.is_empty() {
+            return Err(EventDecodeError::TrailingBytes(reader.0.len()));
+        }
+        if let Some(options) = previous_options {
+            self.previous_options = options;
+        }
+        Ok(events)
+    }
+
+    fn decode_event(
+        &mut self,
+        reader: &mut Reader<'_>,
+        previous_options: Option<&Vec<DialogueOption>>,
+    ) -> core::result::Result<DialogueEvent, EventDecodeError> {
+        let event = match reader.byte()? {
+            LINE => {
+                let line_id = reader.u32()?;
+                let substitutions = (0..reader.length()?)
+                    .map(|_| reader.value())
+                    .collect::<core::result::Result<_, _>>()?;
+                DialogueEvent::Line(line_id, substitutions)
+            }
+            LINE_HINTS => {
+                let flags = reader.byte()?;
+                DialogueEvent::LineHints(LineHints {
+                    is_last_line_before_options: flags & 1 != 0,
+                    is_final_line_of_node: flags & 1 << 1 != 0,
+                    is_final_line_of_dialogue: flags & 1 << 2 != 0,
+                })
+            }
+            OPTIONS => {
+                let previous_options = previous_options.unwrap_or(&self.previous_options);
+                let count = reader.length()?;
+                let mut options = Vec::new();
+                for index in 0..count {
+                    let flags = reader.byte()?;
+                    let (tag_id, destination_node) = if flags & OPTION_UNCHANGED != 0 {
+                        let previous = previous_options
+                            .get(index)
+                            .ok_or(EventDecodeError::MissingPreviousOption(index))?;
+                        (previous.tag_id, previous.destination_node)
+                    } else {
+                        let tag_id = reader.u32()?;
+                        let destination_node = i32::try_from(unzigzag(reader.varint()?))
+                            .map_err(|_| EventDecodeError::VarintOverflow)?;
+                        (tag_id, destination_node)
+                    };
+                    let id = if flags & OPTION_EXPLICIT_ID != 0 {
+                        reader.length()?
+                    } else {
+                        index
+                    };
+                    let substitutions = if flags & OPTION_SUBSTITUTIONS != 0 {
+                        (0..reader.length()?)
+                            .map(|_| reader.value())
+                            .collect::<core::result::Result<_, _>>()?
+                    } else {
+                        Vec::new()
+                    };
+                    options.push(DialogueOption {
+                        tag_id,
+                        id: OptionId(id),
+                        destination_node,
+                        is_available: flags & OPTION_AVAILABLE != 0,
+                        substitutions,
+                        #[cfg(feature = "condition_explanations")]
+                        unavailability_reason: None,
+                    });
+                }
+                DialogueEvent::Options(options)
+            }
+            COMMAND => {
+                let encoding = reader.byte()?;
+                let raw = reader.string()?;
+                let command = match encoding {
+                    COMMAND_RAW => {
+                        Command::parse(raw).ok_or(EventDecodeError::InvalidTag(COMMAND_RAW))?
+                    }
+                    COMMAND_FULL => {
+                        let name = reader.string()?;
+                        let count = reader.length()?;
+                        let parameters = (0..count)
+                            .map(|_| reader.value())
+                            .collect::<core::result::Result<_, _>>()?;
+                        Command {
+                            name,
+                            parameters,
+                            raw,
+                            completion_token: None,
+                        }
+                    }
+                    tag => return Err(EventDecodeError::InvalidTag(tag)),
+                };
+                DialogueEvent::Command(command)
+            }
+            WAIT => {
+                let secs = reader.varint()?;
+                let nanos = reader.u32()?;
+                if nanos >= 1_000_000_000 {
+                    return Err(EventDecodeError::VarintOverflow);
+                }
+                DialogueEvent::Wait(Duration::new(secs, nanos))
+            }
+            NODE_COMPLETE => DialogueEvent::NodeComplete(self.read_node_name(reader)?),
+            NODE_START => DialogueEvent::NodeStart(self.read_node_name(reader)?),
+            BREAKPOINT_HIT => {
+                let node_name = self.read_node_name(reader)?;
+                let location = match reader.byte()? {
+                    0 => BreakpointLocation::Line(reader.u32()?),
+                    1 => BreakpointLocation::Instruction(reader.length()?),
+                    tag => return Err(EventDecodeError::InvalidTag(tag)),
+                };
+                DialogueEvent::BreakpointHit(Breakpoint {
+                    node_name,
+                    location,
+                })
+            }
+            DIALOGUE_COMPLETE => DialogueEvent::DialogueComplete,
+            LINE_INTERRUPTED => DialogueEvent::LineInterrupted(reader.u32()?),
+            LINE_FILTERED => {
+                let line_id = reader.u32()?;
+                let hashtag = reader.string()?.into();
+                let action = match reader.byte()? {
+                    FILTER_SUPPRESS => LineFilterAction::Suppress,
+                    FILTER_REPLACE => LineFilterAction::Replace(reader.u32()?),
+                    FILTER_ANNOTATE => LineFilterAction::Annotate,
+                    tag => return Err(EventDecodeError::InvalidTag(tag)),
+                };
+                DialogueEvent::LineFiltered(LineFiltered {
+                    line_id,
+                    hashtag,
+                    action,
+                })
+            }
+            tag => return Err(EventDecodeError::InvalidTag(tag)),
+        };
+        Ok(event)
+    }
+
+    fn read_node_name(
+        &mut self,
+        reader: &mut Reader<'_>,
+    ) -> core::result::Result<Arc<str>, EventDecodeError> {
+        let reference = reader.varint()?;
+        if reference == 0 {
+            let node_name: Arc<str> = reader.string()?.into();
+            self.node_names.push(node_name.clone());
+            return Ok(node_name);
+        }
+        usize::try_from(reference - 1)
+            .ok()
+            .and_then(|index| self.node_names.get(index))
+            .cloned()
+            .ok_or(EventDecodeError::UnknownNodeName(reference - 1))
+    }
+}
+
+/// Whether parsing the raw text of a command restores it exactly, including the variants of its parameters.
+fn is_identical_command(parsed: &Command, command: &Command) -> bool {
+    parsed.name == command.name
+        && parsed.parameters.len() == command.parameters.len()
+        && parsed
+            .parameters
+            .iter()
+            .zip(&command.parameters)
+            .all(|(a, b)| match (a, b) {
+                (YarnValue::Number(a), YarnValue::Number(b)) => a.to_bits() == b.to_bits(),
+                (YarnValue::Integer(a), YarnValue::Integer(b)) => a == b,
+                (YarnValue::String(a), YarnValue::String(b)) => a == b,
+                (YarnValue::Boolean(a), YarnValue::Boolean(b)) => a == b,
+                _ => false,
+            })
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    write_varint(out, string.len() as u64);
+    out.extend_from_slice(string.as_bytes());
+}
+
+fn write_value(out: &mut Vec<u8>, value: &YarnValue) {
+    match value {
+        YarnValue::Number(number) => {
+            out.push(NUMBER);
+            out.extend_from_slice(&number.to_le_bytes());
+        }
+        YarnValue::Integer(integer) => {
+            out.push(INTEGER);
+            write_varint(out, zigzag(*integer));
+        }
+        YarnValue::String(string) => {
+            out.push(STRING);
+            write_string(out, string);
+        }
+        YarnValue::Boolean(false) => out.push(FALSE),
+        YarnValue::Boolean(true) => out.push(TRUE),
+    }
+}
+
+/// The unread rest of a packet.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes(&mut self, count: usize) -> core::result::Result<&[u8], EventDecodeError> {
+        if self.0.len() < count {
+            return Err(EventDecodeError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> core::result::Result<u8, EventDecodeError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn varint(&mut self) -> core::result::Result<u64, EventDecodeError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                return Err(EventDecodeError::VarintOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(EventDecodeError::VarintOverflow)
+    }
+
+    fn u32(&mut self) -> core::result::Result<u32, EventDecodeError> {
+        u32::try_from(self.varint()?).map_err(|_| EventDecodeError::VarintOverflow)
+    }
+
+    fn length(&mut self) -> core::result::Result<usize, EventDecodeError> {
+        usize::try_from(self.varint()?).map_err(|_| EventDecodeError::VarintOverflow)
+    }
+
+    fn string(&mut self) -> core::result::Result<String, EventDecodeError> {
+        let length = self.length()?;
+        let bytes = self.bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| EventDecodeError::InvalidUtf8)
+    }
+
+    fn value(&mut self) -> core::result::Result<YarnValue, EventDecodeError> {
+        let value = match self.byte()? {
+            NUMBER => {
+                let bytes = self.bytes(4)?;
+                YarnValue::Number(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            INTEGER => YarnValue::Integer(unzigzag(self.varint()?)),
+            STRING => YarnValue::String(self.string()?),
+            FALSE => YarnValue::Boolean(false),
+            TRUE => YarnValue::Boolean(true),
+            tag => return Err(EventDecodeError::InvalidTag(tag)),
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_streams_with_deltas() {
+        let option = |tag_id, destination_node, is_available| DialogueOption {
+            tag_id,
+            id: OptionId(tag_id as usize - 1),
+            destination_node,
+            is_available,
+            substitutions: vec![],
+            #[cfg(feature = "condition_explanations")]
+            unavailability_reason: None,
+        };
+        let shop_options = |has_gold| {
+            DialogueEvent::Options(vec![
+                option(1, 10, true),
+                option(2, 14, has_gold),
+                option(3, -1, true),
+            ])
+        };
+        let batches = [
+            vec![
+                DialogueEvent::NodeStart("Shop".into()),
+                DialogueEvent::LineHints(LineHints {
+                    is_final_line_of_node: true,
+                    ..Default::default()
+                }),
+                DialogueEvent::Line(300, vec![YarnValue::Number(1.5), "Ada".into()]),
+                DialogueEvent::LineInterrupted(300),
+                DialogueEvent::LineFiltered(LineFiltered {
+                    line_id: 301,
+                    hashtag: "violence".into(),
+                    action: LineFilterAction::Replace(302),
+                }),
+            ],
+            vec![shop_options(false)],
+            vec![
+                DialogueEvent::Command(
+                    Command::parse("buy \"iron sword\" 2 1.5 true".to_owned()).unwrap(),
+                ),
+                DialogueEvent::Command(Command {
+                    name: "hand_written".to_owned(),
+                    parameters: vec![YarnValue::Integer(-7), YarnValue::Number(2.0)],
+                    raw: "anything".to_owned(),
+                    completion_token: None,
+                }),
+                DialogueEvent::Wait(Duration::from_millis(2500)),
+                shop_options(true),
+            ],
+            vec![
+                DialogueEvent::BreakpointHit(Breakpoint::new(
+                    "Shop",
+                    BreakpointLocation::Instruction(12),
+                )),
+                DialogueEvent::NodeComplete("Shop".into()),
+                DialogueEvent::DialogueComplete,
+            ],
+        ];
+
+        let mut encoder = EventEncoder::new();
+        let mut decoder = EventDecoder::new();
+        let packets: Vec<_> = batches.iter().map(|batch| encoder.encode(batch)).collect();
+        for (batch, packet) in batches.iter().zip(&packets) {
+            assert_eq!(*batch, decoder.decode(packet).unwrap());
+        }
+        assert_eq!(13, packets[1].len());
+        // Only the availability flags are sent for the repeated options
+        assert_eq!(
+            [
+                OPTIONS,
+                3,
+                OPTION_UNCHANGED | 1,
+                OPTION_UNCHANGED | 1,
+                OPTION_UNCHANGED | 1
+            ],
+            packets[2][packets[2].len() - 5..]
+        );
+        // The node name is referenced instead of being sent again
+        assert_eq!([NODE_COMPLETE, 1], packets[3][packets[3].len() - 3..][..2]);
+
+        assert_eq!(
+            Err(EventDecodeError::UnknownNodeName(0)),
+            EventDecoder::new().decode(&packets[3])
+        );
+        assert_eq!(
+            Err(EventDecodeError::UnsupportedVersion(1)),
+            DialogueEvent::decode(&[1, 1, DIALOGUE_COMPLETE])
+        );
+        let priced_option = DialogueOption {
+            substitutions: vec![YarnValue::Integer(30), "iron sword".into()],
+            ..option(2, 14, false)
+        };
+        let event = DialogueEvent::Options(vec![priced_option]);
+        assert_eq!(event, DialogueEvent::decode(&event.encode()).unwrap());
+        assert_eq!(
+            Err(EventDecodeError::Truncated),
+            DialogueEvent::decode(&DialogueEvent::Line(300, vec![]).encode()[..3])
+        );
+    }
+}
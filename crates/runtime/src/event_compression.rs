@@ -0,0 +1,213 @@
+//! Not part of the original implementation.
+//!
+//! Coalesces long sequences of [`DialogueEvent`]s, e.g. those collected while auto-advancing or fast-forwarding through dialogue,
+//! so that a host can handle them in fewer steps.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+
+/// One or more [`DialogueEvent`]s merged by [`compress_events`].
+///
+/// Every variant maps back to the exact events it was created from via [`CompressedDialogueEvent::expand`],
+/// so [`decompress_events`] restores the original sequence:
+///
+/// | Compressed | Original events |
+/// |---|---|
+/// | [`CompressedDialogueEvent::Event`] | The contained event |
+/// | [`CompressedDialogueEvent::Lines`] | A [`DialogueEvent::Line`] without substitutions for each ID, in order |
+/// | [`CompressedDialogueEvent::NodeTransition`] | [`DialogueEvent::NodeComplete`] of `completed`, then [`DialogueEvent::NodeStart`] of `started` |
+/// | [`CompressedDialogueEvent::NodeVisited`] | [`DialogueEvent::NodeStart`], then [`DialogueEvent::NodeComplete`] of the same node |
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompressedDialogueEvent {
+    /// An event that was not merged with its neighbours.
+    Event(DialogueEvent),
+    /// Two or more consecutive [`DialogueEvent::Line`]s without substitutions.
+    Lines(Vec<u32>),
+    /// A node was completed and another one was started right after.
+    NodeTransition {
+        /// The name of the completed node.
+        completed: Arc<str>,
+        /// The name of the started node.
+        started: Arc<str>,
+    },
+    /// A node was started and completed without producing any other events in between.
+    NodeVisited(Arc<str>),
+}
+
+impl CompressedDialogueEvent {
+    /// The number of original events this stands for.
+    pub fn event_count(&self) -> usize {
+        match self {
+            Self::Event(_) => 1,
+            Self::Lines(line_ids) => line_ids.len(),
+            Self::NodeTransition { .. } | Self::NodeVisited(_) => 2,
+        }
+    }
+
+    /// Converts this back into the events it was created from.
+    pub fn expand(self) -> Vec<DialogueEvent> {
+        match self {
+            Self::Event(event) => vec![event],
+            Self::Lines(line_ids) => line_ids
+                .into_iter()
+                .map(|line_id| DialogueEvent::Line(line_id, vec![]))
+                .collect(),
+            Self::NodeTransition { completed, started } => vec![
+                DialogueEvent::NodeComplete(completed),
+                DialogueEvent::NodeStart(started),
+            ],
+            Self::NodeVisited(node_name) => vec![
+                DialogueEvent::NodeStart(node_name.clone()),
+                DialogueEvent::NodeComplete(node_name),
+            ],
+        }
+    }
+}
+
+impl From<DialogueEvent> for CompressedDialogueEvent {
+    fn from(event: DialogueEvent) -> Self {
+        Self::Event(event)
+    }
+}
+
+/// Merges consecutive events as described in [`CompressedDialogueEvent`]. Events are never reordered.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let events = vec![
+///     DialogueEvent::Line(1, vec![]),
+///     DialogueEvent::Line(2, vec![]),
+///     DialogueEvent::NodeComplete("Start".into()),
+///     DialogueEvent::NodeStart("End".into()),
+///     DialogueEvent::DialogueComplete,
+/// ];
+/// let compressed = compress_events(events.clone());
+/// assert_eq!(
+///     vec![
+///         CompressedDialogueEvent::Lines(vec![1, 2]),
+///         CompressedDialogueEvent::NodeTransition {
+///             completed: "Start".into(),
+///             started: "End".into(),
+///         },
+///         CompressedDialogueEvent::Event(DialogueEvent::DialogueComplete),
+///     ],
+///     compressed
+/// );
+/// assert_eq!(events, decompress_events(compressed));
+/// ```
+pub fn compress_events(
+    events: impl IntoIterator<Item = DialogueEvent>,
+) -> Vec<CompressedDialogueEvent> {
+    let mut compressed = Vec::new();
+    let mut events = events.into_iter().peekable();
+    while let Some(event) = events.next() {
+        let merged = match (event, events.peek()) {
+            (DialogueEvent::Line(first, substitutions), Some(DialogueEvent::Line(_, next)))
+                if substitutions.is_empty() && next.is_empty() =>
+            {
+                let mut line_ids = vec![first];
+                while let Some(DialogueEvent::Line(line_id, substitutions)) = events.peek() {
+                    if !substitutions.is_empty() {
+                        break;
+                    }
+                    line_ids.push(*line_id);
+                    events.next();
+                }
+                CompressedDialogueEvent::Lines(line_ids)
+            }
+            (DialogueEvent::NodeComplete(completed), Some(DialogueEvent::NodeStart(_))) => {
+                let Some(DialogueEvent::NodeStart(started)) = events.next() else {
+                    unreachable!()
+                };
+                CompressedDialogueEvent::NodeTransition { completed, started }
+            }
+            (DialogueEvent::NodeStart(started), Some(DialogueEvent::NodeComplete(completed)))
+                if started == *completed =>
+            {
+                events.next();
+                CompressedDialogueEvent::NodeVisited(started)
+            }
+            (event, _) => CompressedDialogueEvent::Event(event),
+        };
+        compressed.push(merged);
+    }
+    compressed
+}
+
+/// Restores the original events of [`compress_events`].
+pub fn decompress_events(
+    events: impl IntoIterator<Item = CompressedDialogueEvent>,
+) -> Vec<DialogueEvent> {
+    events
+        .into_iter()
+        .flat_map(CompressedDialogueEvent::expand)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_is_lossless() {
+        let node_start = |name: &str| DialogueEvent::NodeStart(name.into());
+        let node_complete = |name: &str| DialogueEvent::NodeComplete(name.into());
+        let events = vec![
+            node_start("A"),
+            DialogueEvent::Line(1, vec![]),
+            node_complete("A"),
+            node_start("B"),
+            node_complete("B"),
+            node_start("C"),
+            node_complete("C"),
+            node_start("D"),
+            DialogueEvent::Line(2, vec![]),
+            DialogueEvent::Line(3, vec![]),
+            DialogueEvent::Line(4, vec![]),
+            node_start("E"),
+            node_complete("F"),
+            DialogueEvent::DialogueComplete,
+        ];
+
+        let compressed = compress_events(events.clone());
+        assert_eq!(
+            vec![
+                CompressedDialogueEvent::Event(node_start("A")),
+                CompressedDialogueEvent::Event(DialogueEvent::Line(1, vec![])),
+                CompressedDialogueEvent::NodeTransition {
+                    completed: "A".into(),
+                    started: "B".into(),
+                },
+                CompressedDialogueEvent::NodeTransition {
+                    completed: "B".into(),
+                    started: "C".into(),
+                },
+                CompressedDialogueEvent::NodeTransition {
+                    completed: "C".into(),
+                    started: "D".into(),
+                },
+                CompressedDialogueEvent::Lines(vec![2, 3, 4]),
+                CompressedDialogueEvent::Event(node_start("E")),
+                CompressedDialogueEvent::Event(node_complete("F")),
+                CompressedDialogueEvent::Event(DialogueEvent::DialogueComplete),
+            ],
+            compressed
+        );
+        assert_eq!(
+            events.len(),
+            compressed
+                .iter()
+                .map(CompressedDialogueEvent::event_count)
+                .sum::<usize>()
+        );
+        assert_eq!(events, decompress_events(compressed));
+
+        assert_eq!(
+            vec![CompressedDialogueEvent::NodeVisited("A".into())],
+            compress_events([node_start("A"), node_complete("A")])
+        );
+    }
+}
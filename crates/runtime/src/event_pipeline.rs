@@ -0,0 +1,228 @@
+//! Not part of the original implementation.
+//!
+//! Filters and maps the [`DialogueEvent`]s returned by [`Dialogue::continue_`], so every consumer doesn't have to write the same loop around them.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt::{self, Debug};
+
+/// An event passed through an [`EventPipeline`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PipelineEvent<C> {
+    /// An event that was not mapped, including commands the command mapper returned `None` for.
+    Event(DialogueEvent),
+    /// A command mapped via [`EventPipeline::with_command_mapper`].
+    Command(C),
+}
+
+type EventFilter = Arc<dyn Fn(&DialogueEvent) -> bool + Send + Sync>;
+type CommandMapper<C> = Arc<dyn Fn(&Command) -> Option<C> + Send + Sync>;
+
+/// Filters and maps the events returned by [`Dialogue::continue_`] and its siblings.
+///
+/// The pipeline is applied lazily via [`EventPipeline::apply`], which doesn't allocate besides what the command mapper does.
+/// Its steps are applied to each event in this order:
+/// 1. [`DialogueEvent::NodeStart`] and [`DialogueEvent::NodeComplete`] are dropped, if enabled via [`EventPipeline::without_node_events`].
+/// 2. Events for which a filter set via [`EventPipeline::with_filter`] returns `false` are dropped.
+/// 3. All but the first [`DialogueEvent::DialogueComplete`] of a batch are dropped, if enabled via [`EventPipeline::with_coalesced_dialogue_complete`].
+///    Useful when concatenating the events of several calls, e.g. while fast-forwarding.
+/// 4. Commands are converted via the mapper set with [`EventPipeline::with_command_mapper`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// #[derive(Debug, PartialEq)]
+/// enum GameCommand {
+///     Shake(f32),
+/// }
+///
+/// let pipeline = EventPipeline::new()
+///     .without_node_events()
+///     .with_coalesced_dialogue_complete()
+///     .with_command_mapper(|command| match command.name.as_str() {
+///         "shake" => Some(GameCommand::Shake(command.parameters.first()?.as_number()?)),
+///         _ => None,
+///     });
+/// let shake = Command {
+///     name: "shake".to_owned(),
+///     parameters: vec![YarnValue::Number(0.5)],
+///     raw: "shake 0.5".to_owned(),
+///     completion_token: None,
+/// };
+/// let events = vec![
+///     DialogueEvent::NodeStart("Start".into()),
+///     DialogueEvent::Command(shake),
+///     DialogueEvent::NodeComplete("Start".into()),
+///     DialogueEvent::DialogueComplete,
+///     DialogueEvent::DialogueComplete,
+/// ];
+/// assert_eq!(
+///     vec![
+///         PipelineEvent::Command(GameCommand::Shake(0.5)),
+///         PipelineEvent::Event(DialogueEvent::DialogueComplete),
+///     ],
+///     pipeline.apply(events).collect::<Vec<_>>()
+/// );
+/// ```
+pub struct EventPipeline<C = Command> {
+    without_node_events: bool,
+    coalesce_dialogue_complete: bool,
+    filters: Vec<EventFilter>,
+    command_mapper: Option<CommandMapper<C>>,
+}
+
+impl EventPipeline {
+    /// Creates a pipeline that passes all events through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C> Default for EventPipeline<C> {
+    fn default() -> Self {
+        Self {
+            without_node_events: false,
+            coalesce_dialogue_complete: false,
+            filters: Vec::new(),
+            command_mapper: None,
+        }
+    }
+}
+
+impl<C> Clone for EventPipeline<C> {
+    fn clone(&self) -> Self {
+        Self {
+            without_node_events: self.without_node_events,
+            coalesce_dialogue_complete: self.coalesce_dialogue_complete,
+            filters: self.filters.clone(),
+            command_mapper: self.command_mapper.clone(),
+        }
+    }
+}
+
+impl<C> Debug for EventPipeline<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventPipeline")
+            .field("without_node_events", &self.without_node_events)
+            .field(
+                "coalesce_dialogue_complete",
+                &self.coalesce_dialogue_complete,
+            )
+            .field("filters", &self.filters.len())
+            .field("command_mapper", &self.command_mapper.is_some())
+            .finish()
+    }
+}
+
+impl<C> EventPipeline<C> {
+    /// Drops [`DialogueEvent::NodeStart`] and [`DialogueEvent::NodeComplete`].
+    #[must_use]
+    pub fn without_node_events(mut self) -> Self {
+        self.without_node_events = true;
+        self
+    }
+
+    /// Only keeps the first [`DialogueEvent::DialogueComplete`] of each batch passed to [`EventPipeline::apply`].
+    #[must_use]
+    pub fn with_coalesced_dialogue_complete(mut self) -> Self {
+        self.coalesce_dialogue_complete = true;
+        self
+    }
+
+    /// Drops events for which the predicate returns `false`. Can be called multiple times, in which case an event must pass all filters.
+    #[must_use]
+    pub fn with_filter(
+        mut self,
+        predicate: impl Fn(&DialogueEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filters.push(Arc::new(predicate));
+        self
+    }
+
+    /// Converts commands into a game-defined type, e.g. an enum of everything the game can do.
+    /// Commands for which the mapper returns `None` are passed on as [`PipelineEvent::Event`].
+    /// Replaces any previously set mapper.
+    #[must_use]
+    pub fn with_command_mapper<D>(
+        self,
+        mapper: impl Fn(&Command) -> Option<D> + Send + Sync + 'static,
+    ) -> EventPipeline<D> {
+        EventPipeline {
+            without_node_events: self.without_node_events,
+            coalesce_dialogue_complete: self.coalesce_dialogue_complete,
+            filters: self.filters,
+            command_mapper: Some(Arc::new(mapper)),
+        }
+    }
+
+    /// Returns `true` if the pipeline keeps the event, not counting the coalescing of [`DialogueEvent::DialogueComplete`].
+    pub fn keeps(&self, event: &DialogueEvent) -> bool {
+        let is_node_event = matches!(
+            event,
+            DialogueEvent::NodeStart(_) | DialogueEvent::NodeComplete(_)
+        );
+        !(self.without_node_events && is_node_event)
+            && self.filters.iter().all(|filter| filter(event))
+    }
+
+    /// Passes a batch of events through the pipeline.
+    pub fn apply<'a>(
+        &'a self,
+        events: impl IntoIterator<Item = DialogueEvent> + 'a,
+    ) -> impl Iterator<Item = PipelineEvent<C>> + 'a {
+        let mut completed = false;
+        events.into_iter().filter_map(move |event| {
+            if !self.keeps(&event) {
+                return None;
+            }
+            if matches!(event, DialogueEvent::DialogueComplete) {
+                if completed && self.coalesce_dialogue_complete {
+                    return None;
+                }
+                completed = true;
+            }
+            match (&event, &self.command_mapper) {
+                (DialogueEvent::Command(command), Some(mapper)) => Some(
+                    mapper(command).map_or(PipelineEvent::Event(event), PipelineEvent::Command),
+                ),
+                _ => Some(PipelineEvent::Event(event)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_and_maps_events_in_order() {
+        let command = |text: &str| DialogueEvent::Command(Command::parse(text.to_owned()).unwrap());
+        let pipeline = EventPipeline::new()
+            .with_filter(|event| !matches!(event, DialogueEvent::Line(2, _)))
+            .with_command_mapper(|command| (command.name == "fade").then_some(1));
+        let events = vec![
+            DialogueEvent::NodeStart("Start".into()),
+            DialogueEvent::Line(1, vec![]),
+            DialogueEvent::Line(2, vec![]),
+            command("fade"),
+            command("wave"),
+            DialogueEvent::DialogueComplete,
+            DialogueEvent::DialogueComplete,
+        ];
+        assert_eq!(
+            vec![
+                PipelineEvent::Event(DialogueEvent::NodeStart("Start".into())),
+                PipelineEvent::Event(DialogueEvent::Line(1, vec![])),
+                PipelineEvent::Command(1),
+                PipelineEvent::Event(command("wave")),
+                PipelineEvent::Event(DialogueEvent::DialogueComplete),
+                PipelineEvent::Event(DialogueEvent::DialogueComplete),
+            ],
+            pipeline.apply(events).collect::<Vec<_>>()
+        );
+    }
+}
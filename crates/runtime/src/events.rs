@@ -6,17 +6,38 @@
 //! - Additional newtypes were introduced for strings.
 
 use crate::prelude::*;
+use alloc::sync::Arc;
+use core::time::Duration;
 
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// An event encountered while running [`Dialogue::continue_`]. A caller is expected to handle these events and act accordingly.
 ///
 /// ## Implementation note
 ///
 /// Corresponds to Yarn Spinner's `<EventName>Handler`s.
+///
+/// ## Serialization
+///
+/// With the `serde` feature, events and everything they contain can be serialized, e.g. to replicate a dialogue over the network.
+/// Variants are tagged with their name, so `DialogueEvent::Line(3, vec![])` is written as `{"Line":[3,[]]}` in JSON.
 pub enum DialogueEvent {
-    /// A [`Line`] should be presented to the user.
-    Line(u32),
+    /// A [`Line`] with the given ID should be presented to the user.
+    ///
+    /// The second field holds the values of the line's inline expressions, which replace the placeholders `{0}`, `{1}`, etc. of its text,
+    /// e.g. via [`Dialogue::expand_substitutions`]. It is empty for lines without inline expressions.
+    Line(u32, Vec<YarnValue>),
+    /// The delivery of the [`DialogueEvent::Line`] with the given ID was interrupted via [`Dialogue::interrupt_line`],
+    /// e.g. because the player skipped it. Stop typewriter effects and voice-over of the line and show it in full.
+    LineInterrupted(u32),
+    /// Describes what follows the [`DialogueEvent::Line`] sent right after this event, e.g. to only show a "continue" prompt if no options follow.
+    ///
+    /// Only sent if [`Dialogue::set_line_hints`] was enabled.
+    LineHints(LineHints),
+    /// A rule of the [`ContentFilter`] set via [`Dialogue::set_content_filter`] was applied to a line.
+    /// Sent right before the [`DialogueEvent::Line`] it describes, or instead of it if the line was suppressed.
+    LineFiltered(LineFiltered),
     /// A list of [`DialogueOption`]s should be presented to the user, who in turns must select one of them.
     /// The selected option must be communicated to the [`Dialogue`] via [`Dialogue::set_selected_option`] before calling [`Dialogue::continue_`] again.
     Options(Vec<DialogueOption>),
@@ -25,10 +46,110 @@ pub enum DialogueEvent {
     /// It is not specified whether the command should be finished executing before calling [`Dialogue::continue_`] again or it is run in parallel.
     /// A library wrapping Yarn Spinner for a game engine should specify this.
     Command(Command),
+    /// The dialogue should pause for the given duration before [`Dialogue::continue_`] is called again.
+    ///
+    /// Only sent instead of a [`DialogueEvent::Command`] for `<<wait>>` commands if [`Dialogue::set_wait_command_handling`] was enabled.
+    /// Use [`Dialogue::tick`] to let the dialogue continue automatically once the duration has elapsed.
+    Wait(Duration),
     /// The node with the given name was completed.
-    NodeComplete(String),
+    NodeComplete(Arc<str>),
     /// The node with the given name was entered.
-    NodeStart(String),
+    NodeStart(Arc<str>),
+    /// The dialogue reached the given [`Breakpoint`], set via [`Dialogue::set_breakpoint`], and paused right before it.
+    /// Call [`Dialogue::continue_`] to resume.
+    BreakpointHit(Breakpoint),
     /// The dialogue was completed. Set it to a new node via [`Dialogue::set_node`] before calling [`Dialogue::continue_`] again.
     DialogueComplete,
 }
+
+/// Presentation hints for a line, sent as [`DialogueEvent::LineHints`] before the [`DialogueEvent::Line`] they describe.
+///
+/// The hints are found by looking ahead at the instructions following the line without running them.
+/// The look-ahead ends at the first branch, e.g. an `<<if>>` statement, in which case the outcome is unknown and all hints are `false`.
+/// So a hint that is `true` is always accurate, but one that is `false` may be a false negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(C)]
+pub struct LineHints {
+    /// The line is directly followed by a [`DialogueEvent::Options`].
+    pub is_last_line_before_options: bool,
+    /// The line is the last one of the current node, which is followed by a [`DialogueEvent::NodeComplete`].
+    pub is_final_line_of_node: bool,
+    /// The line is the last one of the dialogue, which is followed by a [`DialogueEvent::DialogueComplete`].
+    pub is_final_line_of_dialogue: bool,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_events_and_variables_through_json() {
+        let events = vec![
+            DialogueEvent::NodeStart("Start".into()),
+            DialogueEvent::LineHints(LineHints {
+                is_last_line_before_options: true,
+                ..Default::default()
+            }),
+            DialogueEvent::Line(3, vec![YarnValue::Number(2.5), "Ada".into()]),
+            DialogueEvent::LineInterrupted(3),
+            DialogueEvent::LineFiltered(LineFiltered {
+                line_id: 3,
+                hashtag: "spoiler".into(),
+                action: LineFilterAction::Replace(4),
+            }),
+            DialogueEvent::Options(vec![DialogueOption {
+                tag_id: 4,
+                id: OptionId(0),
+                destination_node: 12,
+                is_available: false,
+                substitutions: vec![YarnValue::Integer(50)],
+                #[cfg(feature = "condition_explanations")]
+                unavailability_reason: None,
+            }]),
+            DialogueEvent::Command(
+                Command::parse("wave \"both hands\" 2.5 true".to_owned()).unwrap(),
+            ),
+            DialogueEvent::Wait(Duration::from_millis(1500)),
+            DialogueEvent::BreakpointHit(Breakpoint::new("Start", BreakpointLocation::Line(3))),
+            DialogueEvent::NodeComplete("Start".into()),
+            DialogueEvent::DialogueComplete,
+        ];
+        let json = serde_json::to_string(&events).unwrap();
+        assert!(json.starts_with(r#"[{"NodeStart":"Start"},"#));
+        assert_eq!(
+            events,
+            serde_json::from_str::<Vec<DialogueEvent>>(&json).unwrap()
+        );
+
+        let mut storage = MemoryVariableStorage::new();
+        storage
+            .set_typed("$gold", 9_007_199_254_740_993_i64)
+            .unwrap();
+        storage.set_typed("$speed", 1.25).unwrap();
+        storage.set_typed("$name", "Sam").unwrap();
+        storage.set_typed("$met", true).unwrap();
+        let snapshot = serde_json::to_string(&storage.variables()).unwrap();
+        let mut restored = MemoryVariableStorage::new();
+        restored
+            .extend(serde_json::from_str(&snapshot).unwrap())
+            .unwrap();
+        assert_eq!(storage.variables(), restored.variables());
+        assert_eq!(
+            YarnValue::Integer(9_007_199_254_740_993),
+            restored.get("$gold").unwrap()
+        );
+    }
+}
+
+#[cfg(all(test, feature = "defmt"))]
+mod defmt_tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    assert_impl_all!(DialogueEvent: defmt::Format);
+    assert_impl_all!(DialogueError: defmt::Format);
+    assert_impl_all!(MarkupParseError: defmt::Format);
+    assert_impl_all!(YarnValue: defmt::Format);
+}
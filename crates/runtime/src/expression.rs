@@ -0,0 +1,448 @@
+//! Not part of the original implementation.
+//!
+//! An interpreter for Yarn expressions such as `$gold >= 100 and visited("Shop")`, used by [`Dialogue::evaluate_expression`]
+//! and to evaluate the `when:` headers of nodes.
+//! Operators are resolved to the same library functions the compiler emits calls to, e.g. `Number.GreaterThanOrEqualTo`,
+//! so expressions behave exactly like they do inside a node.
+
+use crate::prelude::*;
+use crate::Result;
+use core::any::TypeId;
+
+/// The functions and variables an expression is evaluated against.
+#[derive(Clone, Copy)]
+pub(crate) struct ExpressionContext<'a> {
+    pub(crate) library: &'a Library,
+    /// The functions bound to the dialogue's variable storage, such as `visited`, which are not part of the shared library.
+    pub(crate) storage_functions: &'a Library,
+    pub(crate) variable: &'a dyn Fn(&str) -> Result<YarnValue>,
+}
+
+impl<'a> ExpressionContext<'a> {
+    /// Looks up a function in the library, falling back to the functions bound to the variable storage.
+    pub(crate) fn function(&self, name: &str) -> Option<&'a dyn UntypedYarnFn> {
+        self.library
+            .get(name)
+            .or_else(|| self.storage_functions.get(name))
+    }
+}
+
+/// Evaluates the given expression.
+pub(crate) fn evaluate(context: ExpressionContext<'_>, expression: &str) -> Result<YarnValue> {
+    let tokens = tokenize(expression).map_err(|message| DialogueError::InvalidExpression {
+        expression: expression.to_owned(),
+        message,
+    })?;
+    let mut evaluator = Evaluator {
+        context,
+        expression,
+        tokens,
+        position: 0,
+    };
+    let value = evaluator.expression()?;
+    match evaluator.peek() {
+        None => Ok(value),
+        Some(token) => Err(evaluator.invalid(format!("Unexpected {token:?} after the expression"))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    String(String),
+    Boolean(bool),
+    Variable(String),
+    Identifier(String),
+    Operator(Operator),
+    OpenParenthesis,
+    CloseParenthesis,
+    Comma,
+}
+
+/// The conditions of the `when:` headers of the given node.
+pub(crate) fn when_conditions(node: &Node) -> impl Iterator<Item = &str> {
+    node.headers
+        .iter()
+        .filter(|header| header.key == "when")
+        .map(|header| header.value.trim())
+}
+
+/// Evaluates the condition of a `when:` header of the given node, which is one of `always`, `once`, `once if <expression>` or `<expression>`.
+pub(crate) fn is_when_condition_met(
+    context: ExpressionContext<'_>,
+    node_name: &str,
+    condition: &str,
+) -> Result<bool> {
+    let condition = match condition.strip_prefix("once") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            let visited_variable = Library::generate_unique_visited_variable_for_node(node_name);
+            if let Some(count) = (context.variable)(&visited_variable)
+                .ok()
+                .and_then(|count| count.as_number())
+            {
+                if count > 0.0 {
+                    return Ok(false);
+                }
+            }
+            let rest = rest.trim_start();
+            rest.strip_prefix("if").unwrap_or(rest)
+        }
+        _ => condition,
+    };
+    let condition = condition.trim();
+    if condition.is_empty() || condition == "always" {
+        return Ok(true);
+    }
+    match evaluate(context, condition)? {
+        YarnValue::Boolean(is_met) => Ok(is_met),
+        value => Err(DialogueError::InvalidExpression {
+            expression: condition.to_owned(),
+            message: format!("A `when:` condition must be a boolean, but is {value}"),
+        }),
+    }
+}
+
+/// Symbolic operators. Two-char operators come first so that e.g. `<=` is not read as `<` followed by `=`.
+const OPERATORS: &[(&str, Operator)] = &[
+    ("==", Operator::EqualTo),
+    ("!=", Operator::NotEqualTo),
+    ("<=", Operator::LessThanOrEqualTo),
+    (">=", Operator::GreaterThanOrEqualTo),
+    ("&&", Operator::And),
+    ("||", Operator::Or),
+    ("<", Operator::LessThan),
+    (">", Operator::GreaterThan),
+    ("!", Operator::Not),
+    ("^", Operator::Xor),
+    ("+", Operator::Add),
+    ("-", Operator::Subtract),
+    ("*", Operator::Multiply),
+    ("/", Operator::Divide),
+    ("%", Operator::Modulo),
+];
+
+fn tokenize(expression: &str) -> core::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let position = expression.len() - rest.len();
+        let (token, len) = if let Some((symbol, operator)) = OPERATORS
+            .iter()
+            .find(|(symbol, _)| rest.starts_with(symbol))
+        {
+            (Token::Operator(*operator), symbol.len())
+        } else {
+            match c {
+                '(' => (Token::OpenParenthesis, 1),
+                ')' => (Token::CloseParenthesis, 1),
+                ',' => (Token::Comma, 1),
+                '"' => {
+                    let (string, len) = string_literal(rest).ok_or_else(|| {
+                        format!("Unterminated string literal at position {position}")
+                    })?;
+                    (Token::String(string), len)
+                }
+                '$' => {
+                    let len = 1 + identifier_len(&rest[1..]);
+                    if len == 1 {
+                        return Err(format!("Expected a variable name at position {position}"));
+                    }
+                    (Token::Variable(rest[..len].to_owned()), len)
+                }
+                c if c.is_ascii_digit() => {
+                    let len = rest
+                        .find(|c: char| !c.is_ascii_digit() && c != '.')
+                        .unwrap_or(rest.len());
+                    let number = &rest[..len];
+                    let number = number.parse().map_err(|_| {
+                        format!("Invalid number \"{number}\" at position {position}")
+                    })?;
+                    (Token::Number(number), len)
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let len = identifier_len(rest);
+                    (keyword_or_identifier(&rest[..len]), len)
+                }
+                c => return Err(format!("Unexpected character '{c}' at position {position}")),
+            }
+        };
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn identifier_len(string: &str) -> usize {
+    string
+        .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '.')
+        .unwrap_or(string.len())
+}
+
+fn keyword_or_identifier(word: &str) -> Token {
+    let operator = match word {
+        "true" => return Token::Boolean(true),
+        "false" => return Token::Boolean(false),
+        "is" | "eq" => Operator::EqualTo,
+        "neq" => Operator::NotEqualTo,
+        "lt" => Operator::LessThan,
+        "lte" => Operator::LessThanOrEqualTo,
+        "gt" => Operator::GreaterThan,
+        "gte" => Operator::GreaterThanOrEqualTo,
+        "and" => Operator::And,
+        "or" => Operator::Or,
+        "xor" => Operator::Xor,
+        "not" => Operator::Not,
+        identifier => return Token::Identifier(identifier.to_owned()),
+    };
+    Token::Operator(operator)
+}
+
+/// Reads the string literal at the start of `source`, which begins with a `"`.
+/// Returns its unescaped content and its length in `source` including the quotes, or `None` if it is not terminated.
+fn string_literal(source: &str) -> Option<(String, usize)> {
+    let mut string = String::new();
+    let mut chars = source.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, index + 1)),
+            '\\' => string.push(chars.next()?.1),
+            c => string.push(c),
+        }
+    }
+    None
+}
+
+/// Evaluates the tokens while parsing them by recursive descent, following the precedence of the Yarn grammar.
+struct Evaluator<'a> {
+    context: ExpressionContext<'a>,
+    expression: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Evaluator<'_> {
+    fn expression(&mut self) -> Result<YarnValue> {
+        self.binary(0)
+    }
+
+    /// Operators from the lowest to the highest precedence. Operators on the same level are left-associative.
+    const PRECEDENCE: &'static [&'static [Operator]] = &[
+        &[Operator::And, Operator::Or, Operator::Xor],
+        &[Operator::EqualTo, Operator::NotEqualTo],
+        &[
+            Operator::LessThan,
+            Operator::LessThanOrEqualTo,
+            Operator::GreaterThan,
+            Operator::GreaterThanOrEqualTo,
+        ],
+        &[Operator::Add, Operator::Subtract],
+        &[Operator::Multiply, Operator::Divide, Operator::Modulo],
+    ];
+
+    fn binary(&mut self, level: usize) -> Result<YarnValue> {
+        let Some(operators) = Self::PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(Token::Operator(operator)) = self.peek() {
+            let operator = *operator;
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = self.apply_operator(operator, vec![left, right])?;
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<YarnValue> {
+        let operator = match self.peek() {
+            Some(Token::Operator(Operator::Subtract)) => Operator::UnarySubtract,
+            Some(Token::Operator(Operator::Not)) => Operator::Not,
+            _ => return self.primary(),
+        };
+        self.position += 1;
+        let operand = self.unary()?;
+        self.apply_operator(operator, vec![operand])
+    }
+
+    fn primary(&mut self) -> Result<YarnValue> {
+        let token = self.next()?;
+        match token {
+            Token::Number(number) => Ok(number.into()),
+            Token::String(string) => Ok(string.into()),
+            Token::Boolean(boolean) => Ok(boolean.into()),
+            Token::Variable(name) => (self.context.variable)(&name),
+            Token::OpenParenthesis => {
+                let value = self.expression()?;
+                self.expect(&Token::CloseParenthesis)?;
+                Ok(value)
+            }
+            Token::Identifier(name) => {
+                self.expect(&Token::OpenParenthesis)?;
+                let mut parameters = Vec::new();
+                if self.peek() != Some(&Token::CloseParenthesis) {
+                    parameters.push(self.expression()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        parameters.push(self.expression()?);
+                    }
+                }
+                self.expect(&Token::CloseParenthesis)?;
+                self.call_function(&name, parameters)
+            }
+            token => Err(self.invalid(format!("Unexpected {token:?}"))),
+        }
+    }
+
+    fn apply_operator(&self, operator: Operator, operands: Vec<YarnValue>) -> Result<YarnValue> {
+        let operand_type = value_type(&operands[0]);
+        if let Some(other) = operands[1..]
+            .iter()
+            .map(value_type)
+            .find(|other| *other != operand_type)
+        {
+            return Err(self.invalid(format!(
+                "Cannot apply {operator} to a {operand_type} and a {other}"
+            )));
+        }
+        let function_name = operand_type.get_canonical_name_for_method(&operator.to_string());
+        let Some(function) = self.context.function(&function_name) else {
+            return Err(self.invalid(format!("Cannot apply {operator} to a {operand_type}")));
+        };
+        try_call(&function_name, function, operands)
+    }
+
+    fn call_function(&self, name: &str, parameters: Vec<YarnValue>) -> Result<YarnValue> {
+        let function =
+            self.context
+                .function(name)
+                .ok_or_else(|| DialogueError::FunctionNotFound {
+                    function_name: name.to_owned(),
+                    library: self.context.library.clone(),
+                })?;
+        let parameter_types = function.parameter_types();
+        if parameter_types.len() != parameters.len() {
+            return Err(self.invalid(format!(
+                "Function \"{name}\" expects {} parameters, but received {}",
+                parameter_types.len(),
+                parameters.len()
+            )));
+        }
+        for (parameter, type_id) in parameters.iter().zip(parameter_types) {
+            if let Some(expected) = expected_type(type_id) {
+                if value_type(parameter) != expected {
+                    return Err(self.invalid(format!(
+                        "Function \"{name}\" expects a {expected}, but received {parameter}"
+                    )));
+                }
+            }
+        }
+        try_call(name, function, parameters)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| self.invalid("Unexpected end of the expression".to_owned()))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        match self.next()? {
+            token if token == *expected => Ok(()),
+            token => Err(self.invalid(format!("Expected {expected:?}, found {token:?}"))),
+        }
+    }
+
+    fn invalid(&self, message: String) -> DialogueError {
+        DialogueError::InvalidExpression {
+            expression: self.expression.to_owned(),
+            message,
+        }
+    }
+}
+
+fn try_call(
+    function_name: &str,
+    function: &dyn UntypedYarnFn,
+    parameters: Vec<YarnValue>,
+) -> Result<YarnValue> {
+    function
+        .try_call(parameters)
+        .map_err(|error| DialogueError::FunctionFailed {
+            function_name: function_name.to_owned(),
+            message: error.to_string(),
+        })
+}
+
+fn value_type(value: &YarnValue) -> Type {
+    match value {
+        YarnValue::Number(_) | YarnValue::Integer(_) => Type::Number,
+        YarnValue::String(_) => Type::String,
+        YarnValue::Boolean(_) => Type::Boolean,
+    }
+}
+
+/// The type a function parameter requires, or `None` if it accepts any value.
+fn expected_type(type_id: TypeId) -> Option<Type> {
+    Type::try_from(type_id)
+        .ok()
+        .filter(|expected| *expected != Type::Any)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_expressions_like_the_compiler() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .set_variable("$gold", 120)
+            .unwrap()
+            .set_variable("$name", "Ada")
+            .unwrap()
+            .set_variable("$Yarn.Internal.Visiting.Shop", 1)
+            .unwrap();
+        let evaluate = |expression| dialogue.evaluate_expression(expression).unwrap();
+
+        assert_eq!(YarnValue::Number(7.0), evaluate("1 + 2 * 3"));
+        assert_eq!(YarnValue::Number(-9.0), evaluate("-(1 + 2) * 3 % 10"));
+        assert_eq!(
+            YarnValue::Boolean(true),
+            evaluate(r#"$gold gte 100 && visited("Shop") and visited_count("Shop") == 1"#)
+        );
+        assert_eq!(
+            YarnValue::Boolean(false),
+            evaluate(r#"!($name is "Ada") or 1 > 2 xor false"#)
+        );
+        assert_eq!(
+            YarnValue::String("Hi, \"Ada\"".to_owned()),
+            evaluate(r#""Hi, \"" + $name + "\"""#)
+        );
+
+        for (expression, code) in [
+            ("1 +", DialogueErrorCode::InvalidExpression),
+            ("1 + true", DialogueErrorCode::InvalidExpression),
+            ("-\"text\"", DialogueErrorCode::InvalidExpression),
+            ("visited(1)", DialogueErrorCode::InvalidExpression),
+            ("visited()", DialogueErrorCode::InvalidExpression),
+            ("(1 2", DialogueErrorCode::InvalidExpression),
+            ("1 # 2", DialogueErrorCode::InvalidExpression),
+            ("missing()", DialogueErrorCode::FunctionNotFound),
+            ("$missing", DialogueErrorCode::VariableStorage),
+        ] {
+            let error = dialogue.evaluate_expression(expression).unwrap_err();
+            assert_eq!(code, error.code(), "{expression}: {error}");
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Not part of the original implementation.
+//!
+//! Entry points for the `cargo-fuzz` targets in the `fuzz` directory of the repository. Each one takes arbitrary bytes
+//! and feeds them to a part of the runtime that handles content which may come from mods or localizations. None of them may panic.
+
+use crate::prelude::*;
+
+/// Parses the bytes as a line of markup, both with the default options and with all escapes disabled.
+pub fn parse_markup(data: &[u8]) {
+    let Ok(line) = core::str::from_utf8(data) else {
+        return;
+    };
+    let _ = crate::markup::parse_markup(line);
+    let _ = LineParser::new()
+        .with_options(MarkupParseOptions {
+            nomarkup: false,
+            escapes: false,
+        })
+        .parse_markup(line);
+}
+
+/// Parses the bytes as the text of a command.
+pub fn parse_command(data: &[u8]) {
+    let Ok(text) = core::str::from_utf8(data) else {
+        return;
+    };
+    if let Some(command) = Command::parse(text.to_owned()) {
+        let _ = command.wait_duration();
+    }
+}
+
+/// Decodes the bytes as a compiled program, loads it into a dialogue and runs each of its nodes
+/// until the dialogue completes or fails, always selecting the first option.
+/// Since programs may loop forever, every run is cut off after a fixed number of instructions.
+pub fn load_program(data: &[u8]) {
+    const MAX_INSTRUCTIONS: usize = 1_000;
+    const MAX_CONTINUES: usize = 100;
+
+    let Ok(program) = Program::from_yarnc_bytes(data) else {
+        return;
+    };
+    let node_names: Vec<_> = program.nodes.keys().cloned().collect();
+    let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    dialogue.add_program(program);
+    for node_name in node_names {
+        if dialogue.set_node(node_name).is_err() {
+            continue;
+        }
+        for _ in 0..MAX_CONTINUES {
+            let Ok(events) = dialogue.continue_with_budget(MAX_INSTRUCTIONS) else {
+                break;
+            };
+            let has_options = events
+                .iter()
+                .any(|event| matches!(event, DialogueEvent::Options(_)));
+            if has_options && dialogue.set_selected_option(OptionId(0)).is_err() {
+                break;
+            }
+            if !dialogue.is_active() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_malformed_input() {
+        for data in [
+            &b""[..],
+            b" \t",
+            b"[",
+            b"[/]",
+            b"[a b=]",
+            b"[nomarkup][/b",
+            b"\\",
+            b"\"unterminated \\",
+            b"\xff\xfe",
+            b"\x12\x03\x0a\x01\x24",
+        ] {
+            parse_markup(data);
+            parse_command(data);
+            load_program(data);
+        }
+    }
+
+    #[test]
+    fn runs_malformed_programs() {
+        use prost::Message;
+        use yarnspinner_core::prelude::instruction::*;
+        let node = Node {
+            name: "Start".to_owned(),
+            instructions: [
+                Some(InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 1,
+                    destination: 3,
+                    substitution_count: 2,
+                    has_condition: true,
+                })),
+                Some(InstructionType::ShowOptions(ShowOptionsInstruction {})),
+                Some(InstructionType::PeekAndJump(PeekAndJumpInstruction {})),
+                None,
+                Some(InstructionType::JumpTo(JumpToInstruction {
+                    destination: 0,
+                })),
+            ]
+            .into_iter()
+            .map(|instruction_type| Instruction { instruction_type })
+            .collect(),
+            headers: vec![],
+        };
+        let program = Program {
+            nodes: [("Start".to_owned(), node)].into_iter().collect(),
+            ..Default::default()
+        };
+        load_program(&program.encode_to_vec());
+    }
+}
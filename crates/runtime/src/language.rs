@@ -1,23 +1,120 @@
 use crate::prelude::*;
-use core::fmt::Display;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::str::FromStr;
 use icu_locid::LanguageIdentifier;
 
 /// IETF BCP 47 code.
 /// The default is "en-US".
+///
+/// Codes are validated and canonicalized when parsed, so `en_us` and `EN-us` both result in `en-US`.
+/// Use [`Language::matches`] or [`Language::fallback_chain`] to find the best available localization for a requested language.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let language: Language = "zh_hant_tw".parse().unwrap();
+/// assert_eq!("zh-Hant-TW", language.to_string());
+/// assert_eq!("zh", language.primary_language());
+/// assert_eq!(Some("Hant"), language.script());
+/// assert_eq!(Some("TW"), language.region());
+/// assert!(Language::new("zh-Hant").matches(&language));
+/// assert!("no language".parse::<Language>().is_err());
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[non_exhaustive]
 pub struct Language(pub(crate) LanguageIdentifier);
+
 impl Language {
-    /// Creates a new `Language` from a string. Panics if the string is not a valid IETF BCP 47 code.
+    /// Creates a new `Language` from a string.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the string is not a valid IETF BCP 47 code. Use [`Language::parse`] to handle invalid codes.
     pub fn new(language: impl Into<String>) -> Self {
         let language = language.into();
-        Self(language.parse().unwrap())
+        Self::parse(&language).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Parses and canonicalizes an IETF BCP 47 code. Both `-` and `_` are accepted as separators.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the string is not a valid IETF BCP 47 code.
+    pub fn parse(language: &str) -> core::result::Result<Self, InvalidLanguageError> {
+        language
+            .trim()
+            .parse()
+            .map(Self)
+            .map_err(|_| InvalidLanguageError {
+                language: language.to_owned(),
+            })
+    }
+
+    /// The primary language subtag, e.g. `de` for `de-CH`.
+    pub fn primary_language(&self) -> &str {
+        self.0.language.as_str()
+    }
+
+    /// The script subtag, e.g. `Hant` for `zh-Hant-TW`, if present.
+    pub fn script(&self) -> Option<&str> {
+        self.0.script.as_ref().map(|script| script.as_str())
+    }
+
+    /// The region subtag, e.g. `CH` for `de-CH`, if present.
+    pub fn region(&self) -> Option<&str> {
+        self.0.region.as_ref().map(|region| region.as_str())
+    }
+
+    /// Returns this language with only the primary language subtag, e.g. `de` for `de-CH` and `zh` for `zh-Hant-TW`.
+    #[must_use]
+    pub fn base_language(&self) -> Self {
+        Self(LanguageIdentifier::from(self.0.language))
+    }
+
+    /// Returns `true` if content for this language can be used for the `requested` language,
+    /// i.e. if they share the primary language and every subtag present in this language is also present in the requested one.
+    /// For example, `de` matches `de-CH`, but `de-CH` does not match `de` or `de-AT`.
+    pub fn matches(&self, requested: &Language) -> bool {
+        let (available, requested) = (&self.0, &requested.0);
+        available.language == requested.language
+            && available
+                .script
+                .is_none_or(|script| requested.script == Some(script))
+            && available
+                .region
+                .is_none_or(|region| requested.region == Some(region))
+            && available
+                .variants
+                .iter()
+                .all(|variant| requested.variants.contains(variant))
+    }
+
+    /// Returns the languages to look for content in, from most to least specific, starting with this language itself.
+    /// For example, `zh-Hant-TW` results in `zh-Hant-TW`, `zh-Hant` and `zh`.
+    pub fn fallback_chain(&self) -> Vec<Language> {
+        let mut chain = vec![self.clone()];
+        let mut current = self.0.clone();
+        loop {
+            if !current.variants.is_empty() {
+                current.variants = Default::default();
+            } else if current.region.is_some() {
+                current.region = None;
+            } else if current.script.is_some() {
+                current.script = None;
+            } else {
+                break;
+            }
+            chain.push(Self(current.clone()));
+        }
+        chain
     }
 }
 
 impl Display for Language {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.0.fmt(f)
     }
 }
@@ -28,12 +125,75 @@ impl Default for Language {
     }
 }
 
+impl FromStr for Language {
+    type Err = InvalidLanguageError;
+
+    fn from_str(language: &str) -> core::result::Result<Self, Self::Err> {
+        Self::parse(language)
+    }
+}
+
 impl<T> From<T> for Language
 where
     String: From<T>,
 {
+    /// Converts a string into a `Language`, see [`Language::new`].
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the string is not a valid IETF BCP 47 code.
     fn from(language: T) -> Self {
         let language: String = language.into();
         Self::new(language)
     }
 }
+
+/// The error returned when parsing a [`Language`] from a string that is not a valid IETF BCP 47 code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvalidLanguageError {
+    /// The string that failed to parse.
+    pub language: String,
+}
+
+impl Error for InvalidLanguageError {}
+
+impl Display for InvalidLanguageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" is not a valid IETF BCP 47 language code",
+            self.language
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_fallback_chain() {
+        let chain: Vec<_> = Language::new("sr-Latn-RS")
+            .fallback_chain()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(vec!["sr-Latn-RS", "sr-Latn", "sr"], chain);
+        assert_eq!(
+            vec![Language::new("en")],
+            Language::new("EN").fallback_chain()
+        );
+    }
+
+    #[test]
+    fn matches_less_specific_languages() {
+        let swiss_german = Language::new("de_ch");
+        assert!(Language::new("de").matches(&swiss_german));
+        assert!(swiss_german.matches(&swiss_german));
+        assert!(!swiss_german.matches(&Language::new("de")));
+        assert!(!Language::new("de-AT").matches(&swiss_german));
+        assert!(!Language::new("fr").matches(&swiss_german));
+        assert_eq!(Language::new("de"), swiss_german.base_language());
+    }
+}
@@ -13,15 +13,68 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod adapter;
+mod asset_provider;
+mod bindings;
+mod breakpoint;
+mod capacity;
 mod command;
+mod command_completion;
+mod command_registry;
+#[cfg(feature = "condition_explanations")]
+mod condition_explanation;
+mod content_coverage;
+mod content_filter;
+mod debug_info;
+mod diagnostic;
 mod dialogue;
+mod dialogue_builder;
 mod dialogue_option;
+mod dialogue_runner;
+mod dialogue_runtime;
+mod dialogue_scheduler;
+mod dialogue_state;
+mod event_codec;
+mod event_compression;
+mod event_pipeline;
 mod events;
+mod expression;
+#[doc(hidden)]
+pub mod fuzzing;
 mod language;
 mod line;
+#[cfg(feature = "linebreak")]
+mod line_breaks;
+mod localization_audit;
+mod logger;
 pub mod markup;
+mod node_handle;
+mod node_statistics;
+mod observer;
+mod options_presentation;
+mod plain_text;
+mod pod_event;
+#[cfg(feature = "vm_profiling")]
+mod profiling;
+mod program_validation;
+mod replay;
+mod saliency;
+mod sandbox;
+mod scripted_dialogue_driver;
+mod shared_dialogue;
+mod simulation;
+mod sync;
+#[cfg(feature = "synthetic_programs")]
+mod synthetic_program;
+#[cfg(feature = "test_plan")]
+mod test_plan;
+mod text_provider;
+mod transcript;
+mod value_formatter;
 mod variable_storage;
 mod virtual_machine;
+#[cfg(feature = "wasm-bindgen")]
+mod wasm;
 
 pub use dialogue::Result;
 
@@ -36,17 +89,73 @@ pub mod prelude {
         vec,
         vec::Vec,
     };
+    #[cfg(feature = "serde")]
+    pub(crate) use serde::{Deserialize, Serialize};
 
+    #[cfg(feature = "condition_explanations")]
+    pub use crate::condition_explanation::*;
+    #[cfg(feature = "linebreak")]
+    pub use crate::line_breaks::*;
+    #[cfg(feature = "vm_profiling")]
+    pub use crate::profiling::*;
+    #[cfg(feature = "synthetic_programs")]
+    pub use crate::synthetic_program::*;
+    #[cfg(feature = "test_plan")]
+    pub use crate::test_plan::*;
+    pub(crate) use crate::virtual_machine::*;
+    #[cfg(feature = "wasm-bindgen")]
+    pub use crate::wasm::*;
     pub use crate::{
+        adapter::*,
+        asset_provider::*,
+        bindings::*,
+        breakpoint::*,
+        capacity::*,
         command::*,
+        command_completion::*,
+        command_registry::*,
+        content_coverage::*,
+        content_filter::*,
+        debug_info::*,
+        diagnostic::*,
         dialogue::{Dialogue, DialogueError},
+        dialogue_builder::*,
         dialogue_option::*,
+        dialogue_runner::*,
+        dialogue_runtime::*,
+        dialogue_scheduler::*,
+        dialogue_state::*,
+        event_codec::*,
+        event_compression::*,
+        event_pipeline::*,
         events::*,
         language::*,
         line::*,
-        markup::MarkupParseError,
+        localization_audit::*,
+        logger::*,
+        markup::{
+            parse_markup, AttributeMarkerProcessor, EscapeKind, EscapedSegment, LineParser,
+            MarkupAttribute, MarkupAttributeOffsets, MarkupOverrides, MarkupParseError,
+            MarkupParseOptions, MarkupRewriter, MarkupValue, PacingMarker, PacingSegment,
+            ParsedMarkup, SpanMapping, SpanMappingError, TextNormalizer,
+        },
+        node_handle::*,
+        node_statistics::*,
+        observer::*,
+        options_presentation::*,
+        plain_text::*,
+        pod_event::*,
+        program_validation::*,
+        replay::*,
+        saliency::*,
+        sandbox::*,
+        scripted_dialogue_driver::*,
+        shared_dialogue::*,
+        simulation::*,
+        text_provider::*,
+        transcript::*,
+        value_formatter::*,
         variable_storage::*,
     };
-    pub(crate) use crate::{virtual_machine::*};
     pub(crate) use yarnspinner_core::prelude::*;
 }
@@ -1,7 +1,8 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>, which we split off into multiple files
 //!
 //! ## Implementation notes
-//! Introduced `LineId` newtype for better type safety
+//! Introduced `LineId` newtype for better type safety.
+//! Added `source_id` to support the shadow lines of Yarn Spinner 3.
 
 use crate::prelude::*;
 
@@ -23,6 +24,74 @@ use crate::prelude::*;
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Line {
-    /// The ID of the line in the string table.
+    /// The ID of the line in the string table. This is the line that was delivered,
+    /// so use it to look up line-specific data such as voice-over recordings, see [`AssetProvider`].
     pub id: LineId,
-}
\ No newline at end of file
+    /// The ID of the line the text was taken from. Differs from [`Line::id`] for shadow lines,
+    /// which reuse the text of another line, see [`LineMetadata::shadowed_line`].
+    /// Use it to treat all shadows of a line as the same content, e.g. for analytics.
+    pub source_id: LineId,
+}
+
+impl Line {
+    /// Creates a line that is not a shadow line.
+    pub fn new(id: impl Into<LineId>) -> Self {
+        let id = id.into();
+        Self {
+            source_id: id.clone(),
+            id,
+        }
+    }
+
+    /// Creates a line, resolving its source via [`TextProvider::get_source_id`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # use std::io::Cursor;
+    /// let csv = "id,text,tags\nline:1,Hello!,\nline:2,,#shadow:line:1\n";
+    /// let text_provider = StreamingTextProvider::new(ReaderLineSource::new(Cursor::new(csv)).unwrap());
+    /// let line = Line::resolve("line:2", &text_provider);
+    /// assert!(line.is_shadow());
+    /// assert_eq!("line:1", line.source_id.0);
+    /// assert_eq!(Some("Hello!".to_owned()), text_provider.get_text(&line.id));
+    /// ```
+    pub fn resolve(id: impl Into<LineId>, text_provider: &(impl TextProvider + ?Sized)) -> Self {
+        let id = id.into();
+        Self {
+            source_id: text_provider.get_source_id(&id),
+            id,
+        }
+    }
+
+    /// Returns `true` if the line reuses the text of another line.
+    pub fn is_shadow(&self) -> bool {
+        self.id != self.source_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translations_inherit_the_shadow_lines_of_the_base_language() {
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([(LineId::from("line:1"), "Hello!".to_owned())]);
+        text_provider.extend_base_language_metadata([(
+            LineId::from("line:2"),
+            LineMetadata {
+                hashtags: vec!["shadow:1".to_owned()],
+                ..Default::default()
+            },
+        )]);
+        text_provider.extend_translation("de", [(LineId::from("line:1"), "Hallo!".to_owned())]);
+        text_provider.set_language(Some("de-CH".into()));
+
+        let line = Line::resolve("line:2", &text_provider);
+        assert_eq!(LineId::from("line:1"), line.source_id);
+        assert_eq!(Some("Hallo!".to_owned()), text_provider.get_text(&line.id));
+        assert!(!Line::resolve("line:1", &text_provider).is_shadow());
+    }
+}
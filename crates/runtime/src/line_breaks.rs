@@ -0,0 +1,287 @@
+//! Not part of the original implementation.
+//!
+//! Finds the positions at which a line of text may be wrapped, for UIs that lay out text themselves, e.g. on fixed-width or handheld displays.
+//! Requires the `linebreak` feature.
+
+use crate::prelude::*;
+use core::fmt::Debug;
+use unicode_linebreak::BreakOpportunity;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The soft hyphen, which marks a position at which a word may be hyphenated. It is invisible unless a line is broken at it.
+pub const SOFT_HYPHEN: char = '\u{ad}';
+
+/// A position in the plain text of a [`ParsedMarkup`] at which a line may or must be broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineBreak {
+    /// The position in the plain text before which the line is broken, in characters, like [`MarkupAttribute::position`].
+    pub position: usize,
+    /// The kind of break.
+    pub kind: LineBreakKind,
+}
+
+/// The kind of a [`LineBreak`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineBreakKind {
+    /// The line must be broken here, e.g. after a line feed.
+    Mandatory,
+    /// The line may be broken here, e.g. after a space.
+    Allowed,
+    /// The line may be broken here by hyphenating a word. A hyphen should be displayed at the end of the line if it is broken here.
+    Hyphenation,
+}
+
+/// Splits words into syllables for a language, e.g. by wrapping a dictionary-based hyphenation library.
+pub trait Hyphenator: Debug + Send + Sync {
+    /// Returns the character indices into `word` at which it may be hyphenated, in ascending order.
+    /// For example, `["hy", "phen", "ation"]` would be `[2, 6]`.
+    fn hyphenate(&self, word: &str) -> Vec<usize>;
+}
+
+/// Finds [`LineBreak`]s in parsed lines according to the [Unicode line breaking algorithm](https://www.unicode.org/reports/tr14/)
+/// and, optionally, a [`Hyphenator`] registered for the language of the line.
+///
+/// Positions are reported in the same units as [`MarkupAttribute`] positions, so markup spans stay aligned with the breaks.
+/// Soft hyphens that are already part of the text are reported as [`LineBreakKind::Hyphenation`] as well.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let markup = parse_markup("Mae: [b]Hello[/b] world!\nBye").unwrap();
+/// let breaks = LineBreaker::new().line_breaks(&markup, &Language::default());
+/// let positions: Vec<_> = breaks.iter().map(|line_break| line_break.position).collect();
+/// assert_eq!(vec![5, 11, 18], positions);
+/// assert_eq!(LineBreakKind::Mandatory, breaks[2].kind);
+/// ```
+#[derive(Debug)]
+pub struct LineBreaker {
+    hyphenators: Vec<(Language, Box<dyn Hyphenator>)>,
+    min_hyphenated_word_length: usize,
+}
+
+impl Default for LineBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineBreaker {
+    /// Creates a new line breaker without any hyphenators.
+    pub fn new() -> Self {
+        Self {
+            hyphenators: Vec::new(),
+            min_hyphenated_word_length: 5,
+        }
+    }
+
+    /// Registers a hyphenator for a language.
+    ///
+    /// A hyphenator registered for a less specific language, e.g. `de`, is used for all regions of it, e.g. `de-CH`,
+    /// unless one was registered for a more specific one. See [`Language::fallback_chain`].
+    #[must_use]
+    pub fn with_hyphenator(
+        mut self,
+        language: impl Into<Language>,
+        hyphenator: impl Hyphenator + 'static,
+    ) -> Self {
+        let language = language.into();
+        self.hyphenators
+            .retain(|(existing, _)| *existing != language);
+        self.hyphenators.push((language, Box::new(hyphenator)));
+        self
+    }
+
+    /// Sets the minimum number of characters a word must have to be hyphenated. Defaults to 5.
+    #[must_use]
+    pub fn with_min_hyphenated_word_length(mut self, length: usize) -> Self {
+        self.min_hyphenated_word_length = length;
+        self
+    }
+
+    /// Finds all positions at which the plain text of the line may or must be broken, in ascending order.
+    /// The end of the text is not reported.
+    pub fn line_breaks(&self, markup: &ParsedMarkup, language: &Language) -> Vec<LineBreak> {
+        let text = &markup.text;
+        let char_index = CharIndex::new(text);
+        let mut breaks: Vec<_> = unicode_linebreak::linebreaks(text)
+            .filter(|(byte, _)| *byte < text.len())
+            .map(|(byte, opportunity)| {
+                let kind = match opportunity {
+                    BreakOpportunity::Mandatory => LineBreakKind::Mandatory,
+                    _ if text[..byte].ends_with(SOFT_HYPHEN) => LineBreakKind::Hyphenation,
+                    BreakOpportunity::Allowed => LineBreakKind::Allowed,
+                };
+                LineBreak {
+                    position: char_index.of(byte),
+                    kind,
+                }
+            })
+            .collect();
+        breaks.extend(
+            self.hyphenation_points(text, language)
+                .into_iter()
+                .map(|position| LineBreak {
+                    position,
+                    kind: LineBreakKind::Hyphenation,
+                }),
+        );
+        breaks.sort_by_key(|line_break| line_break.position);
+        breaks.dedup_by_key(|line_break| line_break.position);
+        breaks
+    }
+
+    /// Returns a copy of the line with a [`SOFT_HYPHEN`] inserted at every hyphenation point found by the [`Hyphenator`] of the language,
+    /// for text renderers that support soft hyphens. The attributes and escaped segments are adjusted to the inserted characters.
+    #[must_use]
+    pub fn insert_soft_hyphens(&self, markup: &ParsedMarkup, language: &Language) -> ParsedMarkup {
+        let points = self.hyphenation_points(&markup.text, language);
+        if points.is_empty() {
+            return markup.clone();
+        }
+
+        let mut text =
+            String::with_capacity(markup.text.len() + points.len() * SOFT_HYPHEN.len_utf8());
+        let mut remaining_points = points.iter().peekable();
+        for (index, character) in markup.text.chars().enumerate() {
+            if remaining_points.next_if_eq(&&index).is_some() {
+                text.push(SOFT_HYPHEN);
+            }
+            text.push(character);
+        }
+
+        // Ranges grow by the soft hyphens inserted strictly inside of them
+        let shift_range = |position: usize, length: usize| {
+            let start = position + points.partition_point(|point| *point <= position);
+            let end =
+                position + length + points.partition_point(|point| *point < position + length);
+            (start, end.max(start) - start)
+        };
+
+        let mut edited = markup.clone();
+        edited.text = text;
+        for attribute in &mut edited.attributes {
+            (attribute.position, attribute.length) =
+                shift_range(attribute.position, attribute.length);
+        }
+        for segment in &mut edited.escaped_segments {
+            (segment.position, segment.length) = shift_range(segment.position, segment.length);
+        }
+        edited.refresh_offsets();
+        edited
+    }
+
+    /// Returns the character positions in `text` at which words may be hyphenated, in ascending order.
+    fn hyphenation_points(&self, text: &str, language: &Language) -> Vec<usize> {
+        let Some(hyphenator) = self.hyphenator(language) else {
+            return Vec::new();
+        };
+        let char_index = CharIndex::new(text);
+        text.split_word_bound_indices()
+            .filter(|(_, word)| {
+                word.chars().all(char::is_alphabetic)
+                    && word.chars().count() >= self.min_hyphenated_word_length
+            })
+            .flat_map(|(byte, word)| {
+                let word_start = char_index.of(byte);
+                let word_length = word.chars().count();
+                hyphenator
+                    .hyphenate(word)
+                    .into_iter()
+                    .filter(move |index| (1..word_length).contains(index))
+                    .map(move |index| word_start + index)
+            })
+            .collect()
+    }
+
+    fn hyphenator(&self, language: &Language) -> Option<&dyn Hyphenator> {
+        language.fallback_chain().iter().find_map(|candidate| {
+            self.hyphenators
+                .iter()
+                .find(|(registered, _)| registered == candidate)
+                .map(|(_, hyphenator)| hyphenator.as_ref())
+        })
+    }
+}
+
+/// Converts byte indices into character indices.
+struct CharIndex(Vec<usize>);
+
+impl CharIndex {
+    fn new(text: &str) -> Self {
+        Self(text.char_indices().map(|(byte, _)| byte).collect())
+    }
+
+    fn of(&self, byte: usize) -> usize {
+        self.0.partition_point(|start| *start < byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hyphenates after every third character.
+    #[derive(Debug)]
+    struct EveryThird;
+
+    impl Hyphenator for EveryThird {
+        fn hyphenate(&self, word: &str) -> Vec<usize> {
+            (3..word.chars().count()).step_by(3).collect()
+        }
+    }
+
+    #[test]
+    fn finds_breaks_in_multibyte_text() {
+        let markup = parse_markup("Grüezi [b]mitenand[/b], 日本語").unwrap();
+        let breaks = LineBreaker::new().line_breaks(&markup, &"de-CH".into());
+        let positions: Vec<_> = breaks
+            .iter()
+            .map(|line_break| line_break.position)
+            .collect();
+        // Between CJK ideographs, lines may be broken anywhere
+        assert_eq!(vec![7, 17, 18, 19], positions);
+        assert!(breaks
+            .iter()
+            .all(|line_break| line_break.kind == LineBreakKind::Allowed));
+    }
+
+    #[test]
+    fn hyphenates_with_language_fallback() {
+        let markup = parse_markup("Ein [b]Donaudampfschiff[/b] fährt").unwrap();
+        let line_breaker = LineBreaker::new()
+            .with_hyphenator("de", EveryThird)
+            .with_min_hyphenated_word_length(6);
+
+        let breaks = line_breaker.line_breaks(&markup, &"de-AT".into());
+        let hyphenations: Vec<_> = breaks
+            .iter()
+            .filter(|line_break| line_break.kind == LineBreakKind::Hyphenation)
+            .map(|line_break| line_break.position)
+            .collect();
+        assert_eq!(vec![7, 10, 13, 16, 19], hyphenations);
+        assert!(line_breaker.line_breaks(&markup, &"en".into()).len() == 2);
+
+        let hyphenated = line_breaker.insert_soft_hyphens(&markup, &"de".into());
+        assert_eq!(
+            "Ein Don\u{ad}aud\u{ad}amp\u{ad}fsc\u{ad}hif\u{ad}f fährt",
+            hyphenated.text
+        );
+        assert_eq!(
+            "Don\u{ad}aud\u{ad}amp\u{ad}fsc\u{ad}hif\u{ad}f",
+            hyphenated.text_for_attribute(hyphenated.attribute("b").unwrap())
+        );
+
+        // Existing soft hyphens are reported as hyphenation points
+        let breaks = LineBreaker::new().line_breaks(&hyphenated, &"en".into());
+        assert_eq!(
+            5,
+            breaks
+                .iter()
+                .filter(|line_break| line_break.kind == LineBreakKind::Hyphenation)
+                .count()
+        );
+    }
+}
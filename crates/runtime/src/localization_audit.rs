@@ -0,0 +1,373 @@
+//! Not part of the original implementation.
+//!
+//! Compares the string tables of translations against the base language to find localization breakage before it reaches players.
+
+use crate::markup::{ORDINAL_ATTRIBUTE, PLURAL_ATTRIBUTE, SELECT_ATTRIBUTE};
+use crate::prelude::*;
+use alloc::collections::BTreeSet;
+use core::error::Error;
+use core::fmt::{self, Display};
+use hashbrown::HashMap;
+
+/// A problem with a translated line, see [`LocalizationAudit`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LocalizationIssue {
+    /// The line is in the base language, but not in the translation.
+    MissingLine {
+        /// The language of the translation.
+        language: Language,
+        /// The ID of the line.
+        line_id: LineId,
+    },
+    /// The line is in the translation, but not in the base language, e.g. because it was removed from the script.
+    UnknownLine {
+        /// The language of the translation.
+        language: Language,
+        /// The ID of the line.
+        line_id: LineId,
+    },
+    /// The translation uses different substitution placeholders, such as `{0}`, than the base language.
+    PlaceholderMismatch {
+        /// The language of the translation.
+        language: Language,
+        /// The ID of the line.
+        line_id: LineId,
+        /// The placeholder indices of the base language.
+        expected: Vec<usize>,
+        /// The placeholder indices of the translation.
+        actual: Vec<usize>,
+    },
+    /// The translation uses different markup attributes than the base language, ignoring replacement markers like `[plural]`
+    /// since languages legitimately differ in those.
+    MarkupMismatch {
+        /// The language of the translation.
+        language: Language,
+        /// The ID of the line.
+        line_id: LineId,
+        /// The sorted attribute names of the base language.
+        expected: Vec<String>,
+        /// The sorted attribute names of the translation.
+        actual: Vec<String>,
+    },
+    /// The markup of the line could not be parsed.
+    InvalidMarkup {
+        /// The language of the line, or `None` for the base language.
+        language: Option<Language>,
+        /// The ID of the line.
+        line_id: LineId,
+        /// The parse error.
+        message: String,
+    },
+}
+
+impl LocalizationIssue {
+    /// The ID of the affected line.
+    pub fn line_id(&self) -> &LineId {
+        match self {
+            Self::MissingLine { line_id, .. }
+            | Self::UnknownLine { line_id, .. }
+            | Self::PlaceholderMismatch { line_id, .. }
+            | Self::MarkupMismatch { line_id, .. }
+            | Self::InvalidMarkup { line_id, .. } => line_id,
+        }
+    }
+}
+
+impl Display for LocalizationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingLine { language, line_id } => {
+                write!(f, "{language}, {line_id}: The line is not translated")
+            }
+            Self::UnknownLine { language, line_id } => write!(
+                f,
+                "{language}, {line_id}: The line is not in the base language"
+            ),
+            Self::PlaceholderMismatch {
+                language,
+                line_id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{language}, {line_id}: The translation uses the placeholders {actual:?}, but the base language uses {expected:?}"
+            ),
+            Self::MarkupMismatch {
+                language,
+                line_id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{language}, {line_id}: The translation uses the markup {actual:?}, but the base language uses {expected:?}"
+            ),
+            Self::InvalidMarkup {
+                language,
+                line_id,
+                message,
+            } => match language {
+                Some(language) => write!(f, "{language}, {line_id}: Invalid markup: {message}"),
+                None => write!(f, "{line_id}: Invalid markup: {message}"),
+            },
+        }
+    }
+}
+
+/// All [`LocalizationIssue`]s found by [`LocalizationAudit::run`], ordered by language and line ID, with issues of the base language first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LocalizationAuditReport {
+    /// The issues found.
+    pub issues: Vec<LocalizationIssue>,
+}
+
+impl LocalizationAuditReport {
+    /// Returns `true` if no issues were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Error for LocalizationAuditReport {}
+
+impl Display for LocalizationAuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Found {} localization issues", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n- {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the string tables of translations against the one of the base language, e.g. in a CI step or a debug build.
+///
+/// Reports lines missing from a translation, lines whose substitution placeholders like `{0}` differ from the base language,
+/// and lines whose markup attributes differ.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let report = LocalizationAudit::new([
+///     (LineId::from("line:1"), "Hello, {0}!".to_owned()),
+///     (LineId::from("line:2"), "[b]Bye[/b]".to_owned()),
+/// ])
+/// .with_translation("de", [(LineId::from("line:1"), "Hallo!".to_owned())])
+/// .run();
+///
+/// assert_eq!(2, report.issues.len());
+/// assert!(matches!(report.issues[0], LocalizationIssue::PlaceholderMismatch { .. }));
+/// assert!(matches!(report.issues[1], LocalizationIssue::MissingLine { .. }));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalizationAudit {
+    base_language: HashMap<LineId, String>,
+    translations: Vec<(Language, HashMap<LineId, String>)>,
+}
+
+impl LocalizationAudit {
+    /// Creates an audit for the given lines of the base language.
+    pub fn new(base_language: impl IntoIterator<Item = (LineId, String)>) -> Self {
+        Self {
+            base_language: base_language.into_iter().collect(),
+            translations: Vec::new(),
+        }
+    }
+
+    /// Adds lines of a translation to check. Lines of the same language are combined.
+    #[must_use]
+    pub fn with_translation(
+        mut self,
+        language: impl Into<Language>,
+        lines: impl IntoIterator<Item = (LineId, String)>,
+    ) -> Self {
+        let language = language.into();
+        match self
+            .translations
+            .iter_mut()
+            .find(|(existing_language, _)| *existing_language == language)
+        {
+            Some((_, table)) => table.extend(lines),
+            None => self
+                .translations
+                .push((language, lines.into_iter().collect())),
+        }
+        self
+    }
+
+    /// Checks all translations.
+    pub fn run(&self) -> LocalizationAuditReport {
+        let mut issues = Vec::new();
+        let base_language: HashMap<_, _> = self
+            .base_language
+            .iter()
+            .map(|(line_id, text)| {
+                let markup = markup_attributes(text).map_err(|message| {
+                    issues.push(LocalizationIssue::InvalidMarkup {
+                        language: None,
+                        line_id: line_id.clone(),
+                        message,
+                    })
+                });
+                (line_id, (placeholders(text), markup.ok()))
+            })
+            .collect();
+
+        for (language, translation) in &self.translations {
+            for line_id in base_language.keys() {
+                if !translation.contains_key(*line_id) {
+                    issues.push(LocalizationIssue::MissingLine {
+                        language: language.clone(),
+                        line_id: (*line_id).clone(),
+                    });
+                }
+            }
+            for (line_id, text) in translation {
+                let Some((expected_placeholders, expected_markup)) = base_language.get(line_id)
+                else {
+                    issues.push(LocalizationIssue::UnknownLine {
+                        language: language.clone(),
+                        line_id: line_id.clone(),
+                    });
+                    continue;
+                };
+                let actual_placeholders = placeholders(text);
+                if actual_placeholders != *expected_placeholders {
+                    issues.push(LocalizationIssue::PlaceholderMismatch {
+                        language: language.clone(),
+                        line_id: line_id.clone(),
+                        expected: expected_placeholders.clone(),
+                        actual: actual_placeholders,
+                    });
+                }
+                match markup_attributes(text) {
+                    Ok(actual_markup) => {
+                        if let Some(expected_markup) = expected_markup {
+                            if actual_markup != *expected_markup {
+                                issues.push(LocalizationIssue::MarkupMismatch {
+                                    language: language.clone(),
+                                    line_id: line_id.clone(),
+                                    expected: expected_markup.clone(),
+                                    actual: actual_markup,
+                                });
+                            }
+                        }
+                    }
+                    Err(message) => issues.push(LocalizationIssue::InvalidMarkup {
+                        language: Some(language.clone()),
+                        line_id: line_id.clone(),
+                        message,
+                    }),
+                }
+            }
+        }
+        issues.sort_by(|a, b| {
+            let language = |issue: &LocalizationIssue| match issue {
+                LocalizationIssue::MissingLine { language, .. }
+                | LocalizationIssue::UnknownLine { language, .. }
+                | LocalizationIssue::PlaceholderMismatch { language, .. }
+                | LocalizationIssue::MarkupMismatch { language, .. } => Some(language.to_string()),
+                LocalizationIssue::InvalidMarkup { language, .. } => {
+                    language.as_ref().map(ToString::to_string)
+                }
+            };
+            (language(a), &a.line_id().0).cmp(&(language(b), &b.line_id().0))
+        });
+        LocalizationAuditReport { issues }
+    }
+}
+
+/// The distinct indices of the substitution placeholders like `{0}` in the text, in ascending order.
+fn placeholders(text: &str) -> Vec<usize> {
+    let mut indices = BTreeSet::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        // Placeholders may name a format like `{0:currency}`, see `expand_substitutions`
+        if let Ok(index) = rest[..end].split(':').next().unwrap_or_default().parse() {
+            indices.insert(index);
+        }
+    }
+    indices.into_iter().collect()
+}
+
+/// The sorted names of the markup attributes of the text, without replacement markers.
+fn markup_attributes(text: &str) -> core::result::Result<Vec<String>, String> {
+    // Placeholders are substituted before parsing at runtime, so they may appear inside of markup, e.g. as `[plural value={0} .../]`
+    let mut substituted = text.to_owned();
+    for index in placeholders(text) {
+        substituted = substituted.replace(&format!("{{{index}}}"), "0");
+    }
+    let markup = LineParser::without_marker_processors()
+        .parse_markup(&substituted)
+        .map_err(|error| error.to_string())?;
+    let mut names: Vec<_> = markup
+        .attributes
+        .into_iter()
+        .map(|attribute| attribute.name)
+        .filter(|name| {
+            ![SELECT_ATTRIBUTE, PLURAL_ATTRIBUTE, ORDINAL_ATTRIBUTE].contains(&name.as_str())
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_translation_issues() {
+        let line = |id: &str, text: &str| (LineId::from(id), text.to_owned());
+        let report = LocalizationAudit::new([
+            line("line:1", "Hello, {0}! You have {1} coins."),
+            line("line:2", "[b]Watch out![/b]"),
+            line(
+                "line:3",
+                r#"[plural value={0} one="% apple" other="% apples"/]"#,
+            ),
+            line("line:4", "Bye"),
+        ])
+        .with_translation(
+            "de",
+            [
+                line("line:1", "Du hast {1} Münzen, {0}!"),
+                line("line:2", "[i]Vorsicht![/i]"),
+                line(
+                    "line:3",
+                    r#"[plural value={0} one="% Apfel" other="% Äpfel"/]"#,
+                ),
+                line("line:5", "Tschüss"),
+            ],
+        )
+        .with_translation(
+            "fr",
+            [
+                line("line:1", "Bonjour {0} !"),
+                line("line:2", "[b]Attention !"),
+            ],
+        )
+        .run();
+
+        let rendered: Vec<_> = report.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                r#"de, line:2: The translation uses the markup ["i"], but the base language uses ["b"]"#,
+                "de, line:4: The line is not translated",
+                "de, line:5: The line is not in the base language",
+                "fr, line:1: The translation uses the placeholders [0], but the base language uses [0, 1]",
+                "fr, line:2: Invalid markup: Unterminated marker b in line [b]Attention ! at position 0",
+                "fr, line:3: The line is not translated",
+                "fr, line:4: The line is not translated",
+            ],
+            rendered
+        );
+    }
+}
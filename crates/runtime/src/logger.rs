@@ -0,0 +1,159 @@
+//! Not part of the original implementation.
+//!
+//! Lets the host receive what the [`Dialogue`] is doing as structured records instead of parsing the messages of the `log` crate,
+//! e.g. to present them in a console or an in-game debug overlay.
+
+use crate::prelude::*;
+use core::fmt::{self, Debug, Display};
+
+/// A structured record of something the [`Dialogue`] did, passed to its [`DialogueLogger`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum DialogueLogRecord<'a> {
+    /// A node was loaded via [`Dialogue::set_node`], a jump or an option.
+    NodeLoaded {
+        /// The name of the loaded node.
+        node_name: &'a str,
+    },
+    /// The dialogue detoured into a node and will return to the current one afterwards.
+    NodeDetoured {
+        /// The name of the node that will be returned to.
+        from: &'a str,
+        /// The name of the detoured node.
+        to: &'a str,
+    },
+    /// Options were sent to the game via [`DialogueEvent::Options`].
+    OptionsShown {
+        /// The shown options.
+        options: &'a [DialogueOption],
+    },
+    /// A variable was stored by the Yarn script.
+    VariableStored {
+        /// The name of the variable, including the leading `$`.
+        name: &'a str,
+        /// The stored value.
+        value: &'a YarnValue,
+    },
+    /// The dialogue ran to its end.
+    RunComplete,
+}
+
+impl Display for DialogueLogRecord<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use DialogueLogRecord::*;
+        match self {
+            NodeLoaded { node_name } => write!(f, "Loading node \"{node_name}\""),
+            NodeDetoured { from, to } => write!(f, "Detouring from node \"{from}\" to \"{to}\""),
+            OptionsShown { options } => write!(f, "Showing {} options", options.len()),
+            VariableStored { name, value } => write!(f, "Storing {value} in {name}"),
+            RunComplete => write!(f, "Run complete."),
+        }
+    }
+}
+
+/// Receives the [`DialogueLogRecord`]s of a [`Dialogue`], set via [`Dialogue::set_logger`].
+///
+/// The default is [`LogCrateLogger`], which forwards the records to the `log` crate.
+/// Use [`NoopLogger`] to discard them, e.g. on embedded targets where no `log` implementation is wired up.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use std::sync::Mutex;
+/// #[derive(Debug, Default)]
+/// struct DebugOverlay {
+///     nodes: Mutex<Vec<String>>,
+/// }
+///
+/// impl DialogueLogger for DebugOverlay {
+///     fn log(&self, record: &DialogueLogRecord<'_>) {
+///         if let DialogueLogRecord::NodeLoaded { node_name } = record {
+///             self.nodes.lock().unwrap().push(node_name.to_string());
+///         }
+///     }
+/// }
+///
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.set_logger(DebugOverlay::default());
+/// ```
+pub trait DialogueLogger: Debug + Send + Sync {
+    /// Handles a single record. Called while the dialogue is running, so this should return quickly.
+    fn log(&self, record: &DialogueLogRecord<'_>);
+}
+
+/// Forwards [`DialogueLogRecord`]s to the `log` crate at debug level. This is the default [`DialogueLogger`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LogCrateLogger;
+
+impl DialogueLogger for LogCrateLogger {
+    fn log(&self, record: &DialogueLogRecord<'_>) {
+        log::debug!("{record}");
+    }
+}
+
+/// Discards all [`DialogueLogRecord`]s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NoopLogger;
+
+impl DialogueLogger for NoopLogger {
+    fn log(&self, _record: &DialogueLogRecord<'_>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use crate::sync::Mutex;
+    use alloc::sync::Arc;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingLogger(Arc<Mutex<Vec<String>>>);
+
+    impl DialogueLogger for RecordingLogger {
+        fn log(&self, record: &DialogueLogRecord<'_>) {
+            self.0.lock().push(record.to_string());
+        }
+    }
+
+    #[test]
+    fn passes_records_to_logger() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.set_logger(RecordingLogger(records.clone()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushFloat(PushFloatInstruction { value: 3.0 }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$apples".to_owned(),
+                }),
+                InstructionType::Pop(PopInstruction {}),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 1,
+                    destination: 6,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                InstructionType::Pop(PopInstruction {}),
+            ],
+        ));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        dialogue.continue_().unwrap();
+
+        assert_eq!(
+            vec![
+                "Loading node \"Start\"",
+                "Storing 3 in $apples",
+                "Showing 1 options",
+                "Run complete.",
+            ],
+            *records.lock()
+        );
+    }
+}
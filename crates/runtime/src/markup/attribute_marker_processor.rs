@@ -1,13 +1,30 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/IAttributeMarkerProcessor.cs>
 
+pub(crate) use self::dialogue_text_processor::DialogueTextProcessor;
+pub use self::dialogue_text_processor::{
+    ORDINAL_ATTRIBUTE, PLURAL_ATTRIBUTE, SELECT_ATTRIBUTE, VALUE_PLACEHOLDER, VALUE_PROPERTY,
+};
+use crate::markup::MarkupAttribute;
 use crate::prelude::*;
 use core::fmt::Debug;
 
 mod dialogue_text_processor;
-mod no_markup_text_processor;
 
 /// Provides a mechanism for producing replacement text for a marker.
-pub(crate) trait AttributeMarkerProcessor: Debug + Send + Sync {
+///
+/// Register implementations with [`LineParser::register_marker_processor`](crate::markup::LineParser::register_marker_processor).
+pub trait AttributeMarkerProcessor: Debug + Send + Sync {
+    /// Produces the text that replaces the marker in the parsed line.
+    /// The `marker` has a [`MarkupAttribute::length`] of zero, as the text it applies to is not known yet.
+    /// The `language` is the [`LineParser::locale`](crate::markup::LineParser::locale), which may be pinned to an invariant locale.
+    /// Returns a human-readable message if the marker is malformed, which is reported as a [`MarkupParseError::InvalidReplacementMarker`](crate::markup::MarkupParseError::InvalidReplacementMarker).
+    fn replacement_text_for_marker(
+        &self,
+        marker: &MarkupAttribute,
+        language: &Language,
+    ) -> core::result::Result<String, String>;
+
+    /// Clones the processor into a new box.
     fn clone_box(&self) -> Box<dyn AttributeMarkerProcessor>;
 }
 
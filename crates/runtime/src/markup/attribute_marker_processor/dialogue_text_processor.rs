@@ -0,0 +1,154 @@
+//! Adapted from the `DialogueTextProcessor` in <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>
+//!
+//! ## Implementation notes
+//!
+//! The CLDR plural rules are only available with the `cldr` feature. Without it, the rules for English are used for every language.
+
+use super::AttributeMarkerProcessor;
+use crate::markup::{MarkupAttribute, MarkupValue};
+use crate::prelude::*;
+
+/// The name of the marker that selects a replacement by the value itself, e.g. `[select value=f m="he" f="she" nb="they"/]`.
+pub const SELECT_ATTRIBUTE: &str = "select";
+
+/// The name of the marker that selects a replacement based on the cardinal plural category of its value, e.g. `[plural value=2 one="% cat" other="% cats"/]`.
+pub const PLURAL_ATTRIBUTE: &str = "plural";
+
+/// The name of the marker that selects a replacement based on the ordinal plural category of its value, e.g. `[ordinal value=2 one="%st" two="%nd" few="%rd" other="%th"/]`.
+pub const ORDINAL_ATTRIBUTE: &str = "ordinal";
+
+/// The name of the property holding the value of a replacement marker.
+pub const VALUE_PROPERTY: &str = "value";
+
+/// Occurrences of this character in a replacement are replaced by the marker's value.
+pub const VALUE_PLACEHOLDER: char = '%';
+
+/// A markup text processor that implements the built-in replacement markers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DialogueTextProcessor;
+
+impl DialogueTextProcessor {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl AttributeMarkerProcessor for DialogueTextProcessor {
+    fn replacement_text_for_marker(
+        &self,
+        marker: &MarkupAttribute,
+        language: &Language,
+    ) -> core::result::Result<String, String> {
+        let name = marker.name.as_str();
+        let value = marker
+            .property(VALUE_PROPERTY)
+            .ok_or_else(|| format!("Expected a property \"{VALUE_PROPERTY}\""))?;
+
+        let category = match name {
+            SELECT_ATTRIBUTE => {
+                let case = value.to_string();
+                let replacement = marker
+                    .property(&case)
+                    .ok_or_else(|| format!("No text was provided for the value \"{case}\""))?;
+                return Ok(replacement.to_string().replace(VALUE_PLACEHOLDER, &case));
+            }
+            PLURAL_ATTRIBUTE => plural_category(value, language, PluralKind::Cardinal)?,
+            ORDINAL_ATTRIBUTE => plural_category(value, language, PluralKind::Ordinal)?,
+            _ => return Err(format!("Unknown replacement marker \"{name}\"")),
+        };
+        let replacement = marker.property(category).ok_or_else(|| {
+            format!("No text was provided for the plural category \"{category}\" of the value {value} in language \"{language}\"")
+        })?;
+        Ok(replacement
+            .to_string()
+            .replace(VALUE_PLACEHOLDER, &value.to_string()))
+    }
+
+    fn clone_box(&self) -> Box<dyn AttributeMarkerProcessor> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PluralKind {
+    Cardinal,
+    Ordinal,
+}
+
+/// Returns the CLDR name of the plural category of `value`, e.g. `"one"` or `"other"`.
+fn plural_category(
+    value: &MarkupValue,
+    language: &Language,
+    kind: PluralKind,
+) -> core::result::Result<&'static str, String> {
+    match value {
+        MarkupValue::Integer(_) | MarkupValue::Float(_) => {
+            Ok(category_for_number(value, language, kind))
+        }
+        _ => Err(format!(
+            "Expected a number for the property \"{VALUE_PROPERTY}\", but found a {}",
+            value.type_name()
+        )),
+    }
+}
+
+#[cfg(feature = "cldr")]
+fn category_for_number(value: &MarkupValue, language: &Language, kind: PluralKind) -> &'static str {
+    use fixed_decimal::{FixedDecimal, FloatPrecision};
+    use icu_plurals::{PluralCategory, PluralRules};
+
+    let locale = (&language.0).into();
+    let rules = match kind {
+        PluralKind::Cardinal => PluralRules::try_new_cardinal(&locale),
+        PluralKind::Ordinal => PluralRules::try_new_ordinal(&locale),
+    };
+    let Ok(rules) = rules else {
+        return english_category_for_number(value, kind);
+    };
+    let category = match value {
+        MarkupValue::Integer(integer) => rules.category_for(*integer),
+        MarkupValue::Float(float) => {
+            match FixedDecimal::try_from_f64(f64::from(*float), FloatPrecision::Floating) {
+                Ok(decimal) => rules.category_for(&decimal),
+                Err(_) => PluralCategory::Other,
+            }
+        }
+        _ => PluralCategory::Other,
+    };
+    match category {
+        PluralCategory::Zero => "zero",
+        PluralCategory::One => "one",
+        PluralCategory::Two => "two",
+        PluralCategory::Few => "few",
+        PluralCategory::Many => "many",
+        PluralCategory::Other => "other",
+    }
+}
+
+#[cfg(not(feature = "cldr"))]
+fn category_for_number(
+    value: &MarkupValue,
+    _language: &Language,
+    kind: PluralKind,
+) -> &'static str {
+    english_category_for_number(value, kind)
+}
+
+/// The plural rules of English, used when no CLDR data is available.
+fn english_category_for_number(value: &MarkupValue, kind: PluralKind) -> &'static str {
+    let integer = match value {
+        MarkupValue::Integer(integer) => *integer,
+        // Fractional numbers are always "other" in English
+        _ => return "other",
+    };
+    match kind {
+        PluralKind::Cardinal if integer == 1 => "one",
+        PluralKind::Cardinal => "other",
+        PluralKind::Ordinal => match (integer % 10, integer % 100) {
+            (1, n) if n != 11 => "one",
+            (2, n) if n != 12 => "two",
+            (3, n) if n != 13 => "few",
+            _ => "other",
+        },
+    }
+}
@@ -1,7 +1,12 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/LineParser.cs>
 
-use crate::markup::MarkupParseError;
+use crate::markup::{
+    AttributeMarkerProcessor, DialogueTextProcessor, EscapeKind, EscapedSegment, MarkupAttribute,
+    MarkupAttributeMarker, MarkupParseError, MarkupValue, OffsetTable, ParsedMarkup, TagType,
+    TextNormalizer, ORDINAL_ATTRIBUTE, PLURAL_ATTRIBUTE, SELECT_ATTRIBUTE,
+};
 use crate::prelude::*;
+use hashbrown::HashMap;
 use unicode_normalization::UnicodeNormalization;
 
 /// A result type for the line parser
@@ -12,7 +17,6 @@ pub(crate) fn normalize(string: &str) -> String {
     string.nfc().to_string()
 }
 
-
 /// The name of the implicitly-generated `character` attribute.
 pub const CHARACTER_ATTRIBUTE: &str = "character";
 
@@ -21,4 +25,794 @@ pub const CHARACTER_ATTRIBUTE_NAME_PROPERTY: &str = "name";
 
 /// The name of the property to use to signify that trailing whitespace should be trimmed
 /// if a tag had preceding whitespace or begins the line. This property must be a bool value.
-pub const TRIM_WHITESPACE_PROPERTY: &str = "trimwhitespace";
\ No newline at end of file
+pub const TRIM_WHITESPACE_PROPERTY: &str = "trimwhitespace";
+
+/// The name of the attribute that disables markup parsing for the text it wraps, e.g. `[nomarkup][b][/nomarkup]` produces the text `[b]`.
+pub const NO_MARKUP_ATTRIBUTE: &str = "nomarkup";
+
+/// Parses a line of marked-up text into the plain text and the [`MarkupAttribute`]s that apply to ranges of it.
+///
+/// This does not require a [`Dialogue`], so it can be used by tools that work with Yarn text directly, such as subtitle exporters.
+/// If no explicit `character` attribute is present and the line starts with a name followed by a colon, e.g. `Mae: Hi!`,
+/// an implicit [`CHARACTER_ATTRIBUTE`] covering the name, colon and any whitespace after it is added.
+///
+/// Replacement markers like `[plural]` are evaluated for the default [`Language`]. Use a [`LineParser`] to parse for a different language.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// let markup = parse_markup("Mae: [shout]I'm a cat![/shout]").unwrap();
+/// assert_eq!("Mae: I'm a cat!", markup.text);
+///
+/// let shout = markup.attribute("shout").unwrap();
+/// assert_eq!("I'm a cat!", markup.text_for_attribute(shout));
+///
+/// let character = markup.attribute(CHARACTER_ATTRIBUTE).unwrap();
+/// assert_eq!(Some(&MarkupValue::from("Mae")), character.property(CHARACTER_ATTRIBUTE_NAME_PROPERTY));
+/// ```
+///
+/// ## Errors
+///
+/// Returns a [`MarkupParseError`] if the markup is malformed, e.g. when a marker is never closed.
+pub fn parse_markup(line: &str) -> Result<ParsedMarkup> {
+    LineParser::new().parse_markup(line)
+}
+
+/// Parses lines of marked-up text for a given [`Language`].
+///
+/// The following replacement markers are built in and replaced by text when parsing:
+/// - `[select value=v a="..." b="..."/]` selects the text of the property named like the value `v`, e.g. `[select value={$gender} m="he" f="she" nb="they"/]`.
+/// - `[plural value=n one="..." other="..."/]` selects a text based on the cardinal plural category of `n`, e.g. "1 apple" vs. "2 apples".
+/// - `[ordinal value=n one="..." two="..." few="..." other="..."/]` does the same for the ordinal plural category, e.g. "1st" vs. "2nd".
+///
+/// In the replacement texts, `%` is replaced by the value. The available categories are `zero`, `one`, `two`, `few`, `many` and `other`,
+/// and which of them a language uses is defined by the [CLDR](https://cldr.unicode.org/index/cldr-spec/plural-rules).
+/// The CLDR data is only compiled in with the `cldr` feature. Without it, the rules of English are used for every language.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let parser = LineParser::new().with_language("en-US");
+/// let markup = parser.parse_markup(r#"I have [plural value=3 one="% apple" other="% apples"/]."#).unwrap();
+/// assert_eq!("I have 3 apples.", markup.text);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineParser {
+    language: Language,
+    invariant_locale: Option<Language>,
+    marker_processors: HashMap<String, Box<dyn AttributeMarkerProcessor>>,
+    text_normalizer: TextNormalizer,
+    markup_overrides: MarkupOverrides,
+    options: MarkupParseOptions,
+    precompute_offsets: bool,
+}
+
+/// Controls which escapes a [`LineParser`] honors.
+///
+/// Disabling an escape makes the parser treat its syntax like any other text or markup,
+/// e.g. for lines that were written before the escape was introduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarkupParseOptions {
+    /// Whether the text between `[nomarkup]` and `[/nomarkup]` is taken literally.
+    /// If disabled, `nomarkup` is parsed like any other attribute. Enabled by default.
+    pub nomarkup: bool,
+    /// Whether brackets escaped with a backslash like `\[` are taken literally.
+    /// If disabled, the backslash is kept and the bracket starts or ends a marker. Enabled by default.
+    pub escapes: bool,
+}
+
+impl Default for MarkupParseOptions {
+    fn default() -> Self {
+        Self {
+            nomarkup: true,
+            escapes: true,
+        }
+    }
+}
+
+impl Default for LineParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser {
+    /// Creates a new parser for the default [`Language`] with all built-in replacement markers registered.
+    pub fn new() -> Self {
+        let dialogue_text_processor = Box::new(DialogueTextProcessor::new());
+        let mut line_parser = Self {
+            language: Language::default(),
+            invariant_locale: None,
+            marker_processors: HashMap::new(),
+            text_normalizer: TextNormalizer::new(),
+            markup_overrides: MarkupOverrides::new(),
+            options: MarkupParseOptions::default(),
+            precompute_offsets: false,
+        };
+        line_parser
+            .register_marker_processor(SELECT_ATTRIBUTE, dialogue_text_processor.clone())
+            .register_marker_processor(PLURAL_ATTRIBUTE, dialogue_text_processor.clone())
+            .register_marker_processor(ORDINAL_ATTRIBUTE, dialogue_text_processor);
+        line_parser
+    }
+
+    /// Creates a parser without replacement markers, which are then parsed like any other attribute.
+    pub(crate) fn without_marker_processors() -> Self {
+        let mut line_parser = Self::new();
+        line_parser.marker_processors.clear();
+        line_parser
+    }
+
+    /// Sets the language used to evaluate replacement markers.
+    #[must_use]
+    pub fn with_language(mut self, language: impl Into<Language>) -> Self {
+        self.set_language(language);
+        self
+    }
+
+    /// Sets the language used to evaluate replacement markers.
+    pub fn set_language(&mut self, language: impl Into<Language>) -> &mut Self {
+        self.language = language.into();
+        self
+    }
+
+    /// The language used to evaluate replacement markers.
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    /// Pins all locale-dependent behavior, such as the plural rules of replacement markers, to the given language,
+    /// regardless of [`LineParser::language`]. Use this for deterministic tests and tooling runs.
+    ///
+    /// The [`LineParser::language`] is still used for content that belongs to a localization, such as [`MarkupOverrides`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// let line = r#"[plural value=2 one="% Katze" two="% Katzen (Dual)" other="% Katzen"/]"#;
+    /// let parser = LineParser::new().with_language("sl").with_invariant_locale("en-US");
+    /// assert_eq!("2 Katzen", parser.parse_markup(line).unwrap().text);
+    /// ```
+    #[must_use]
+    pub fn with_invariant_locale(mut self, locale: impl Into<Language>) -> Self {
+        self.set_invariant_locale(Some(locale.into()));
+        self
+    }
+
+    /// Pins all locale-dependent behavior to the given language, or lets it follow [`LineParser::language`] again if `None` is passed.
+    /// See [`LineParser::with_invariant_locale`].
+    pub fn set_invariant_locale(&mut self, locale: Option<Language>) -> &mut Self {
+        self.invariant_locale = locale;
+        self
+    }
+
+    /// The language all locale-dependent behavior is pinned to, if any. See [`LineParser::with_invariant_locale`].
+    pub fn invariant_locale(&self) -> Option<&Language> {
+        self.invariant_locale.as_ref()
+    }
+
+    /// The language used for locale-dependent behavior: the [`LineParser::invariant_locale`] if set, otherwise the [`LineParser::language`].
+    pub fn locale(&self) -> &Language {
+        self.invariant_locale.as_ref().unwrap_or(&self.language)
+    }
+
+    /// Sets which escapes are honored when parsing. Use [`LineParser::parse_markup_with_options`] to override them for a single line.
+    #[must_use]
+    pub fn with_options(mut self, options: MarkupParseOptions) -> Self {
+        self.set_options(options);
+        self
+    }
+
+    /// Sets which escapes are honored when parsing.
+    pub fn set_options(&mut self, options: MarkupParseOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Which escapes are honored when parsing.
+    pub fn options(&self) -> MarkupParseOptions {
+        self.options
+    }
+
+    /// Sets the [`TextNormalizer`] that the text of every parsed line is passed through.
+    /// Defaults to one that only applies NFC normalization.
+    #[must_use]
+    pub fn with_text_normalizer(mut self, text_normalizer: TextNormalizer) -> Self {
+        self.set_text_normalizer(text_normalizer);
+        self
+    }
+
+    /// Sets the [`TextNormalizer`] that the text of every parsed line is passed through.
+    pub fn set_text_normalizer(&mut self, text_normalizer: TextNormalizer) -> &mut Self {
+        self.text_normalizer = text_normalizer;
+        self
+    }
+
+    /// The [`TextNormalizer`] that the text of every parsed line is passed through.
+    pub fn text_normalizer(&self) -> &TextNormalizer {
+        &self.text_normalizer
+    }
+
+    /// Sets the [`MarkupOverrides`] that replace marker properties depending on the [`LineParser::language`].
+    #[must_use]
+    pub fn with_markup_overrides(mut self, markup_overrides: MarkupOverrides) -> Self {
+        self.set_markup_overrides(markup_overrides);
+        self
+    }
+
+    /// Sets the [`MarkupOverrides`] that replace marker properties depending on the [`LineParser::language`].
+    pub fn set_markup_overrides(&mut self, markup_overrides: MarkupOverrides) -> &mut Self {
+        self.markup_overrides = markup_overrides;
+        self
+    }
+
+    /// The [`MarkupOverrides`] that replace marker properties depending on the [`LineParser::language`].
+    pub fn markup_overrides(&self) -> &MarkupOverrides {
+        &self.markup_overrides
+    }
+
+    /// Sets whether [`MarkupAttribute::offsets`] are filled in when parsing, which is cheaper than calling
+    /// [`MarkupAttribute::byte_range`] and [`MarkupAttribute::utf16_range`] for every attribute. Disabled by default.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// let parser = LineParser::new().with_precomputed_offsets(true);
+    /// let markup = parser.parse_markup("😀 [b]ö[/b]").unwrap();
+    /// let offsets = markup.attribute("b").unwrap().offsets.clone().unwrap();
+    /// assert_eq!(5..7, offsets.bytes);
+    /// assert_eq!(3..4, offsets.utf16);
+    /// ```
+    #[must_use]
+    pub fn with_precomputed_offsets(mut self, precompute_offsets: bool) -> Self {
+        self.set_precomputed_offsets(precompute_offsets);
+        self
+    }
+
+    /// Sets whether [`MarkupAttribute::offsets`] are filled in when parsing. See [`LineParser::with_precomputed_offsets`].
+    pub fn set_precomputed_offsets(&mut self, precompute_offsets: bool) -> &mut Self {
+        self.precompute_offsets = precompute_offsets;
+        self
+    }
+
+    /// Registers a processor that produces the replacement text for markers with the given name.
+    /// Replaces any processor previously registered for the name, including the built-in ones.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// # use yarnspinner_runtime::prelude::*;
+    /// #[derive(Debug, Clone)]
+    /// struct Shout;
+    ///
+    /// impl AttributeMarkerProcessor for Shout {
+    ///     fn replacement_text_for_marker(&self, marker: &MarkupAttribute, _language: &Language) -> std::result::Result<String, String> {
+    ///         let text = marker.property("text").ok_or("Expected a property \"text\"")?;
+    ///         Ok(text.to_string().to_uppercase())
+    ///     }
+    ///
+    ///     fn clone_box(&self) -> Box<dyn AttributeMarkerProcessor> {
+    ///         Box::new(self.clone())
+    ///     }
+    /// }
+    ///
+    /// let mut parser = LineParser::new();
+    /// parser.register_marker_processor("shout", Box::new(Shout));
+    /// assert_eq!("HEY!", parser.parse_markup("[shout text=hey/]!").unwrap().text);
+    /// ```
+    pub fn register_marker_processor(
+        &mut self,
+        name: impl Into<String>,
+        processor: Box<dyn AttributeMarkerProcessor>,
+    ) -> &mut Self {
+        self.marker_processors.insert(name.into(), processor);
+        self
+    }
+
+    /// Parses a line of marked-up text. See [`parse_markup`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns a [`MarkupParseError`] if the markup is malformed or a replacement marker could not be evaluated.
+    pub fn parse_markup(&self, line: &str) -> Result<ParsedMarkup> {
+        self.parse_markup_with_options(line, self.options)
+    }
+
+    /// Parses a line of marked-up text like [`LineParser::parse_markup`], but with the given options instead of [`LineParser::options`].
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// let parser = LineParser::new();
+    /// let line = r"C:\[b]Users\[/b]";
+    /// assert_eq!(r"C:[b]Users[/b]", parser.parse_markup(line).unwrap().text);
+    ///
+    /// let options = MarkupParseOptions { escapes: false, ..Default::default() };
+    /// let markup = parser.parse_markup_with_options(line, options).unwrap();
+    /// assert_eq!(r"C:\Users\", markup.text);
+    /// assert_eq!("Users\\", markup.text_for_attribute(markup.attribute("b").unwrap()));
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns a [`MarkupParseError`] if the markup is malformed or a replacement marker could not be evaluated.
+    pub fn parse_markup_with_options(
+        &self,
+        line: &str,
+        options: MarkupParseOptions,
+    ) -> Result<ParsedMarkup> {
+        MarkupParser::new(self, line, options).parse()
+    }
+}
+
+/// The state of parsing a single line.
+///
+/// ## Implementation notes
+///
+/// Positions are counted in `char`s of the normalized input instead of UTF-16 code units.
+#[derive(Debug)]
+struct MarkupParser<'a> {
+    line_parser: &'a LineParser,
+    options: MarkupParseOptions,
+    /// The original input, used for error messages.
+    input: String,
+    /// The normalized input.
+    chars: Vec<char>,
+    /// The index of the next character in [`MarkupParser::chars`].
+    source_position: usize,
+    /// The plain text produced so far.
+    text: String,
+    /// The number of characters in [`MarkupParser::text`].
+    position: usize,
+    /// Plain text that was read but not yet passed through the [`TextNormalizer`] and appended to [`MarkupParser::text`].
+    pending_text: String,
+    escaped_segments: Vec<EscapedSegment>,
+}
+
+impl<'a> MarkupParser<'a> {
+    fn new(line_parser: &'a LineParser, input: &str, options: MarkupParseOptions) -> Self {
+        Self {
+            line_parser,
+            options,
+            input: input.to_owned(),
+            chars: line_parser
+                .text_normalizer
+                .normalize_source(input)
+                .chars()
+                .collect(),
+            source_position: 0,
+            text: String::new(),
+            position: 0,
+            pending_text: String::new(),
+            escaped_segments: Vec::new(),
+        }
+    }
+
+    fn parse(mut self) -> Result<ParsedMarkup> {
+        let mut markers = Vec::new();
+        while let Some(character) = self.read() {
+            match character {
+                '\\' if self.options.escapes && matches!(self.peek(), Some('[' | ']')) => {
+                    // An escaped bracket is taken literally
+                    self.flush_text();
+                    self.escaped_segments.push(EscapedSegment {
+                        kind: EscapeKind::Character,
+                        position: self.position,
+                        length: 1,
+                        source_position: self.source_position - 1,
+                    });
+                    let escaped = self.read().unwrap();
+                    self.push_verbatim_text(escaped);
+                }
+                '[' => {
+                    self.flush_text();
+                    let had_preceding_whitespace_or_line_start =
+                        self.text.chars().last().is_none_or(char::is_whitespace);
+                    let mut marker = self.parse_attribute_marker()?;
+                    self.apply_markup_overrides(&mut marker);
+                    let is_replacement_marker = self.process_replacement_marker(&marker)?;
+
+                    if had_preceding_whitespace_or_line_start
+                        && self.should_trim_whitespace(&marker, is_replacement_marker)?
+                        && self.peek().is_some_and(char::is_whitespace)
+                    {
+                        self.read();
+                    }
+
+                    let is_no_markup_start = self.options.nomarkup
+                        && marker.type_ == TagType::Open
+                        && marker.name.as_deref() == Some(NO_MARKUP_ATTRIBUTE);
+                    markers.push(marker);
+                    if is_no_markup_start {
+                        markers.push(self.parse_no_markup()?);
+                    }
+                }
+                _ => self.push_text(character),
+            }
+        }
+        self.flush_text();
+
+        let mut attributes = self.build_attributes_from_markers(markers)?;
+        if !attributes
+            .iter()
+            .any(|attribute| attribute.name == CHARACTER_ATTRIBUTE)
+        {
+            if let Some(character_attribute) = self.implicit_character_attribute() {
+                attributes.insert(0, character_attribute);
+            }
+        }
+        if self.line_parser.precompute_offsets {
+            let offsets = OffsetTable::new(&self.text);
+            for attribute in &mut attributes {
+                attribute.compute_offsets(&offsets);
+            }
+        }
+        Ok(ParsedMarkup {
+            text: self.text,
+            attributes,
+            escaped_segments: self.escaped_segments,
+        })
+    }
+
+    fn apply_markup_overrides(&self, marker: &mut MarkupAttributeMarker) {
+        if let (Some(name), TagType::Open | TagType::SelfClosing) =
+            (marker.name.as_deref(), marker.type_)
+        {
+            self.line_parser.markup_overrides.apply(
+                &self.line_parser.language,
+                name,
+                &mut marker.properties,
+            );
+        }
+    }
+
+    /// Inserts the replacement text of the marker if a processor is registered for it. Returns whether that was the case.
+    fn process_replacement_marker(&mut self, marker: &MarkupAttributeMarker) -> Result<bool> {
+        let Some(name) = marker.name.as_deref() else {
+            return Ok(false);
+        };
+        let Some(processor) = self.line_parser.marker_processors.get(name) else {
+            return Ok(self.options.nomarkup && name == NO_MARKUP_ATTRIBUTE);
+        };
+        if !matches!(marker.type_, TagType::Open | TagType::SelfClosing) {
+            return Ok(true);
+        }
+        let attribute = MarkupAttribute::from_marker(marker.clone(), 0);
+        let replacement = processor
+            .replacement_text_for_marker(&attribute, self.line_parser.locale())
+            .map_err(|message| MarkupParseError::InvalidReplacementMarker {
+                input: self.input.clone(),
+                name: name.to_owned(),
+                message,
+            })?;
+        self.pending_text = replacement;
+        self.flush_text();
+        Ok(true)
+    }
+
+    fn should_trim_whitespace(
+        &self,
+        marker: &MarkupAttributeMarker,
+        is_replacement_marker: bool,
+    ) -> Result<bool> {
+        match marker.property(TRIM_WHITESPACE_PROPERTY) {
+            Some(MarkupValue::Bool(trim)) => Ok(*trim),
+            Some(value) => Err(MarkupParseError::TrimWhitespaceAttributeIsNotBoolean {
+                input: self.input.clone(),
+                name: marker.name.clone(),
+                position: marker.position,
+                type_: value.type_name().to_owned(),
+            }),
+            // Replacement markers are replaced with text, so they keep the whitespace around them by default
+            None => Ok(marker.type_ == TagType::SelfClosing && !is_replacement_marker),
+        }
+    }
+
+    /// Parses a marker after its opening `[` has been consumed.
+    fn parse_attribute_marker(&mut self) -> Result<MarkupAttributeMarker> {
+        let source_position = self.source_position - 1;
+        let mut marker = MarkupAttributeMarker {
+            name: None,
+            position: self.position,
+            source_position,
+            properties: Default::default(),
+            type_: TagType::Open,
+        };
+
+        self.consume_whitespace();
+        if self.peek() == Some('/') {
+            self.read();
+            self.consume_whitespace();
+            if self.peek() == Some(']') {
+                self.read();
+                marker.type_ = TagType::CloseAll;
+                return Ok(marker);
+            }
+            marker.name = Some(self.parse_id()?);
+            self.consume_whitespace();
+            self.parse_character(']')?;
+            marker.type_ = TagType::Close;
+            return Ok(marker);
+        }
+
+        let name = self.parse_id()?;
+        if self.peek() == Some('=') {
+            // Shorthand property, e.g. `[a=1]` is the same as `[a a=1]`
+            self.read();
+            let value = self.parse_value()?;
+            marker.properties.insert(name.clone(), value);
+        }
+        marker.name = Some(name);
+
+        loop {
+            self.consume_whitespace();
+            match self.peek() {
+                None => {
+                    return Err(MarkupParseError::UnexpectedEndOfLine {
+                        input: self.input.clone(),
+                    })
+                }
+                Some(']') => {
+                    self.read();
+                    marker.type_ = TagType::Open;
+                    return Ok(marker);
+                }
+                Some('/') => {
+                    self.read();
+                    self.consume_whitespace();
+                    self.parse_character(']')?;
+                    marker.type_ = TagType::SelfClosing;
+                    return Ok(marker);
+                }
+                Some(_) => {
+                    let property_name = self.parse_id()?;
+                    self.consume_whitespace();
+                    self.parse_character('=')?;
+                    self.consume_whitespace();
+                    let value = self.parse_value()?;
+                    marker.properties.insert(property_name, value);
+                }
+            }
+        }
+    }
+
+    /// Reads the text following a `[nomarkup]` marker verbatim until the matching close marker, which is returned.
+    fn parse_no_markup(&mut self) -> Result<MarkupAttributeMarker> {
+        let close_marker: Vec<char> = format!("[/{NO_MARKUP_ATTRIBUTE}]").chars().collect();
+        let segment_position = self.position;
+        let segment_source_position = self.source_position;
+        loop {
+            if self.chars[self.source_position..].starts_with(&close_marker) {
+                self.escaped_segments.push(EscapedSegment {
+                    kind: EscapeKind::NoMarkup,
+                    position: segment_position,
+                    length: self.position - segment_position,
+                    source_position: segment_source_position,
+                });
+                let marker = MarkupAttributeMarker {
+                    name: Some(NO_MARKUP_ATTRIBUTE.to_owned()),
+                    position: self.position,
+                    source_position: self.source_position,
+                    properties: Default::default(),
+                    type_: TagType::Close,
+                };
+                self.source_position += close_marker.len();
+                return Ok(marker);
+            }
+            let Some(character) = self.read() else {
+                return Err(MarkupParseError::UnterminatedMarker {
+                    input: self.input.clone(),
+                    name: NO_MARKUP_ATTRIBUTE.to_owned(),
+                    position: self.position,
+                });
+            };
+            self.push_verbatim_text(character);
+        }
+    }
+
+    fn build_attributes_from_markers(
+        &self,
+        markers: Vec<MarkupAttributeMarker>,
+    ) -> Result<Vec<MarkupAttribute>> {
+        let mut unclosed_markers: Vec<MarkupAttributeMarker> = Vec::new();
+        let mut attributes = Vec::new();
+        for marker in markers {
+            match marker.type_ {
+                TagType::Open => unclosed_markers.push(marker),
+                TagType::SelfClosing => attributes.push(MarkupAttribute::from_marker(marker, 0)),
+                TagType::Close => {
+                    let index = unclosed_markers
+                        .iter()
+                        .rposition(|open| open.name == marker.name)
+                        .ok_or_else(|| MarkupParseError::UnmatchedCloseMarker {
+                            input: self.input.clone(),
+                            name: marker.name.clone().unwrap_or_default(),
+                            position: marker.position,
+                        })?;
+                    let open = unclosed_markers.remove(index);
+                    let length = marker.position - open.position;
+                    attributes.push(MarkupAttribute::from_marker(open, length));
+                }
+                TagType::CloseAll => {
+                    attributes.extend(unclosed_markers.drain(..).map(|open| {
+                        let length = marker.position - open.position;
+                        MarkupAttribute::from_marker(open, length)
+                    }));
+                }
+            }
+        }
+        if let Some(unclosed) = unclosed_markers.first() {
+            return Err(MarkupParseError::UnterminatedMarker {
+                input: self.input.clone(),
+                name: unclosed.name.clone().unwrap_or_default(),
+                position: unclosed.position,
+            });
+        }
+        attributes.sort_by_key(|attribute| attribute.source_position);
+        Ok(attributes)
+    }
+
+    /// Creates a `character` attribute for lines like `Mae: Hi!`.
+    fn implicit_character_attribute(&self) -> Option<MarkupAttribute> {
+        let colon = self.text.chars().position(|c| c == ':')?;
+        let trailing_whitespace = self
+            .text
+            .chars()
+            .skip(colon + 1)
+            .take_while(|c| c.is_whitespace())
+            .count();
+        let name: String = self.text.chars().take(colon).collect();
+        Some(MarkupAttribute {
+            position: 0,
+            length: colon + 1 + trailing_whitespace,
+            name: CHARACTER_ATTRIBUTE.to_owned(),
+            properties: [(
+                CHARACTER_ATTRIBUTE_NAME_PROPERTY.to_owned(),
+                MarkupValue::String(name),
+            )]
+            .into_iter()
+            .collect(),
+            source_position: 0,
+            offsets: None,
+        })
+    }
+
+    fn parse_value(&mut self) -> Result<MarkupValue> {
+        match self.peek() {
+            Some('"') => self.parse_string().map(MarkupValue::String),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.parse_number(),
+            Some(_) => {
+                let id = self.parse_id()?;
+                Ok(match id.as_str() {
+                    "true" => MarkupValue::Bool(true),
+                    "false" => MarkupValue::Bool(false),
+                    _ => MarkupValue::String(id),
+                })
+            }
+            None => Err(MarkupParseError::UnexpectedEndOfLine {
+                input: self.input.clone(),
+            }),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<MarkupValue> {
+        let mut number = String::new();
+        if self.peek() == Some('-') {
+            number.push(self.read().unwrap());
+        }
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+            self.read();
+        }
+        if let Ok(integer) = number.parse() {
+            Ok(MarkupValue::Integer(integer))
+        } else if let Ok(float) = number.parse() {
+            Ok(MarkupValue::Float(float))
+        } else {
+            // Not a number after all, e.g. `-` or `1.2.3`
+            Ok(MarkupValue::String(number))
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.parse_character('"')?;
+        let mut string = String::new();
+        loop {
+            match self.read() {
+                None => {
+                    return Err(MarkupParseError::NoStringFound {
+                        input: self.input.clone(),
+                    })
+                }
+                Some('"') => return Ok(string),
+                Some('\\') => match self.read() {
+                    Some(escaped @ ('"' | '\\')) => string.push(escaped),
+                    _ => {
+                        return Err(MarkupParseError::InvalidEscapeSequence {
+                            input: self.input.clone(),
+                        })
+                    }
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_id(&mut self) -> Result<String> {
+        let mut id = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            id.push(c);
+            self.read();
+        }
+        if id.is_empty() {
+            Err(MarkupParseError::NoIdentifierFound {
+                input: self.input.clone(),
+            })
+        } else {
+            Ok(id)
+        }
+    }
+
+    fn parse_character(&mut self, expected: char) -> Result<()> {
+        match self.read() {
+            Some(c) if c == expected => Ok(()),
+            Some(_) => Err(MarkupParseError::UnexpectedCharacter {
+                input: self.input.clone(),
+                character: expected,
+            }),
+            None => Err(MarkupParseError::UnexpectedEndOfLine {
+                input: self.input.clone(),
+            }),
+        }
+    }
+
+    fn consume_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.read();
+        }
+    }
+
+    fn push_text(&mut self, character: char) {
+        self.pending_text.push(character);
+    }
+
+    /// Appends text to the output without passing it through the [`TextNormalizer`].
+    fn push_verbatim_text(&mut self, character: char) {
+        self.text.push(character);
+        self.position += 1;
+    }
+
+    /// Passes the pending text through the [`TextNormalizer`] and appends it to the output.
+    fn flush_text(&mut self) {
+        if self.pending_text.is_empty() {
+            return;
+        }
+        let pending_text = core::mem::take(&mut self.pending_text);
+        let normalizer = &self.line_parser.text_normalizer;
+        let text = if normalizer.has_transforms() {
+            normalizer.transform(&pending_text, self.text.chars().last())
+        } else {
+            pending_text
+        };
+        self.position += text.chars().count();
+        self.text.push_str(&text);
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.source_position).copied()
+    }
+
+    fn read(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.source_position += 1;
+        Some(character)
+    }
+}
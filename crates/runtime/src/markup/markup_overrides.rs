@@ -0,0 +1,153 @@
+//! Not part of the original implementation.
+
+use crate::markup::MarkupValue;
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// A table of marker properties that are replaced for specific languages when parsing, so localizations can tune e.g. the pacing of a line
+/// without changing its markup. Use it with [`LineParser::with_markup_overrides`](crate::markup::LineParser::with_markup_overrides).
+///
+/// Overrides apply to opening and self-closing markers whose name matches. They replace the property if the marker already has it and add it otherwise.
+/// Overrides registered for a less specific language, e.g. `ja`, also apply to all of its regions, e.g. `ja-JP`,
+/// while overrides for more specific languages take precedence over them. See [`Language::fallback_chain`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// let overrides = MarkupOverrides::new().with_property("ja", "pause", "duration", 800);
+/// let line = "Well[pause duration=300/]...";
+///
+/// let parser = LineParser::new().with_markup_overrides(overrides);
+/// let markup = parser.parse_markup(line).unwrap();
+/// assert_eq!(Some(&300.into()), markup.attribute("pause").unwrap().property("duration"));
+///
+/// let parser = parser.with_language("ja-JP");
+/// let markup = parser.parse_markup(line).unwrap();
+/// assert_eq!(Some(&800.into()), markup.attribute("pause").unwrap().property("duration"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarkupOverrides {
+    languages: HashMap<Language, HashMap<String, HashMap<String, MarkupValue>>>,
+}
+
+impl MarkupOverrides {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the property of all markers with the given name when parsing for the given language.
+    #[must_use]
+    pub fn with_property(
+        mut self,
+        language: impl Into<Language>,
+        marker_name: impl Into<String>,
+        property_name: impl Into<String>,
+        value: impl Into<MarkupValue>,
+    ) -> Self {
+        self.set_property(language, marker_name, property_name, value);
+        self
+    }
+
+    /// Overrides the property of all markers with the given name when parsing for the given language.
+    pub fn set_property(
+        &mut self,
+        language: impl Into<Language>,
+        marker_name: impl Into<String>,
+        property_name: impl Into<String>,
+        value: impl Into<MarkupValue>,
+    ) -> &mut Self {
+        self.languages
+            .entry(language.into())
+            .or_default()
+            .entry(marker_name.into())
+            .or_default()
+            .insert(property_name.into(), value.into());
+        self
+    }
+
+    /// Removes all overrides of the given language. Overrides of the language without its region are kept.
+    pub fn remove_language(&mut self, language: &Language) -> &mut Self {
+        self.languages.remove(language);
+        self
+    }
+
+    /// Returns `true` if no overrides are registered.
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+
+    /// Applies the overrides of the given language to the properties of a marker with the given name.
+    pub(crate) fn apply(
+        &self,
+        language: &Language,
+        marker_name: &str,
+        properties: &mut HashMap<String, MarkupValue>,
+    ) {
+        if self.is_empty() {
+            return;
+        }
+        // Apply the general overrides first, so the more specific ones win
+        for language in language.fallback_chain().iter().rev() {
+            let overrides = self
+                .languages
+                .get(language)
+                .and_then(|markers| markers.get(marker_name));
+            for (name, value) in overrides.into_iter().flatten() {
+                properties.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::LineParser;
+
+    #[test]
+    fn exact_language_takes_precedence() {
+        let overrides = MarkupOverrides::new()
+            .with_property("de", "pause", "duration", 400)
+            .with_property("de", "pause", "silent", true)
+            .with_property("de-CH", "pause", "duration", 600);
+        let parser = LineParser::new().with_markup_overrides(overrides);
+        let line = "A[pause duration=200/] [pause]b[/pause] [wait duration=1/]";
+
+        let parse = |language: &str| {
+            parser
+                .clone()
+                .with_language(language)
+                .parse_markup(line)
+                .unwrap()
+        };
+        let markup = parse("de-CH");
+        let pauses: Vec<_> = markup
+            .attributes
+            .iter()
+            .filter(|attribute| attribute.name == "pause")
+            .collect();
+        assert_eq!(2, pauses.len());
+        for pause in pauses {
+            assert_eq!(Some(&600.into()), pause.property("duration"));
+            assert_eq!(Some(&true.into()), pause.property("silent"));
+        }
+        assert_eq!(
+            Some(&1.into()),
+            markup.attribute("wait").unwrap().property("duration")
+        );
+
+        let markup = parse("de-AT");
+        assert_eq!(
+            Some(&400.into()),
+            markup.attribute("pause").unwrap().property("duration")
+        );
+        let markup = parse("en");
+        assert_eq!(
+            Some(&200.into()),
+            markup.attribute("pause").unwrap().property("duration")
+        );
+    }
+}
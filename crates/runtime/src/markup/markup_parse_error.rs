@@ -6,6 +6,7 @@ use core::fmt;
 #[allow(missing_docs)]
 #[derive(Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MarkupParseError {
     TrimWhitespaceAttributeIsNotBoolean {
         input: String,
@@ -42,6 +43,11 @@ pub enum MarkupParseError {
         name: String,
         position: usize,
     },
+    InvalidReplacementMarker {
+        input: String,
+        name: String,
+        message: String,
+    },
 }
 
 impl Error for MarkupParseError {}
@@ -72,6 +78,11 @@ impl fmt::Display for MarkupParseError {
                 name,
                 position,
             } => write!(f, "Unterminated marker {name} in line {input} at position {position}"),
+            InvalidReplacementMarker {
+                input,
+                name,
+                message,
+            } => write!(f, "Failed to replace marker {name} in line {input}: {message}"),
         }
     }
 }
@@ -0,0 +1,237 @@
+//! Not part of the original implementation.
+
+use crate::markup::{MarkupAttribute, ParsedMarkup};
+use crate::prelude::*;
+use core::fmt::{self, Debug};
+use hashbrown::HashMap;
+
+type TagFn<'a> = dyn Fn(&MarkupAttribute) -> Option<(String, String)> + 'a;
+type EscapeFn<'a> = dyn Fn(&str) -> String + 'a;
+
+/// Rewrites [`ParsedMarkup`] into text with inline tags for rich-text backends, e.g. turning `[b]bold[/b]` into `<b>bold</b>` or BBCode.
+///
+/// Every attribute is mapped to an opening and a closing tag by a user-supplied callback. Attributes without a mapping are dropped,
+/// leaving their text untouched. The rewriter takes care of the span math:
+/// - Yarn markup allows overlapping attributes such as `[a]x[b]y[/a]z[/b]`, which most rich-text formats do not.
+///   Such attributes are split so that the output is properly nested: `<a>x<b>y</b></a><b>z</b>`.
+/// - Attribute positions are counted in characters, so callers don't have to convert them to byte or UTF-16 offsets.
+/// - Attributes with a length of zero, e.g. from self-closing markers like `[pause/]`, produce their opening tag directly followed by their closing tag.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// let markup = parse_markup("[b]Bold[/b] and [wave]<wavy>[/wave]").unwrap();
+/// let html = MarkupRewriter::new()
+///     .with_tag("b", |_| Some(("<b>".to_owned(), "</b>".to_owned())))
+///     .with_tag("wave", |_| Some(("<span class=\"wave\">".to_owned(), "</span>".to_owned())))
+///     .with_escape(|text| text.replace('<', "&lt;").replace('>', "&gt;"))
+///     .rewrite(&markup);
+/// assert_eq!("<b>Bold</b> and <span class=\"wave\">&lt;wavy&gt;</span>", html);
+/// ```
+pub struct MarkupRewriter<'a> {
+    tags: HashMap<String, Box<TagFn<'a>>>,
+    fallback: Option<Box<TagFn<'a>>>,
+    escape: Option<Box<EscapeFn<'a>>>,
+}
+
+impl Default for MarkupRewriter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> MarkupRewriter<'a> {
+    /// Creates a new rewriter without any tag mappings.
+    pub fn new() -> Self {
+        Self {
+            tags: HashMap::new(),
+            fallback: None,
+            escape: None,
+        }
+    }
+
+    /// Maps attributes with the given name to an opening and closing tag. Return `None` to drop an attribute.
+    #[must_use]
+    pub fn with_tag(
+        mut self,
+        name: impl Into<String>,
+        tag: impl Fn(&MarkupAttribute) -> Option<(String, String)> + 'a,
+    ) -> Self {
+        self.tags.insert(name.into(), Box::new(tag));
+        self
+    }
+
+    /// Maps all attributes that have no mapping registered via [`MarkupRewriter::with_tag`].
+    #[must_use]
+    pub fn with_fallback(
+        mut self,
+        tag: impl Fn(&MarkupAttribute) -> Option<(String, String)> + 'a,
+    ) -> Self {
+        self.fallback = Some(Box::new(tag));
+        self
+    }
+
+    /// Sets a function that escapes the plain text between tags, e.g. replacing `<` with `&lt;` for HTML.
+    #[must_use]
+    pub fn with_escape(mut self, escape: impl Fn(&str) -> String + 'a) -> Self {
+        self.escape = Some(Box::new(escape));
+        self
+    }
+
+    /// Rewrites the parsed markup into text with tags.
+    pub fn rewrite(&self, markup: &ParsedMarkup) -> String {
+        let characters: Vec<char> = markup.text.chars().collect();
+        let spans = self.spans(markup, characters.len());
+
+        let mut output = String::with_capacity(markup.text.len());
+        let mut text = String::new();
+        let mut open_spans: Vec<usize> = Vec::new();
+        for position in 0..=characters.len() {
+            let closes_here = open_spans
+                .iter()
+                .position(|span| spans[*span].end == position);
+            let starts_here: Vec<_> = (0..spans.len())
+                .filter(|span| spans[*span].start == position)
+                .collect();
+            if closes_here.is_some() || !starts_here.is_empty() {
+                self.flush(&mut text, &mut output);
+            }
+
+            if let Some(lowest) = closes_here {
+                // Close everything down to the lowest ending span and reopen the spans that continue past it
+                let closed: Vec<_> = open_spans.drain(lowest..).collect();
+                for span in closed.iter().rev() {
+                    output.push_str(&spans[*span].close);
+                }
+                for span in closed {
+                    if spans[span].end != position {
+                        output.push_str(&spans[span].open);
+                        open_spans.push(span);
+                    }
+                }
+            }
+
+            let (mut empty, mut opening): (Vec<_>, Vec<_>) = starts_here
+                .into_iter()
+                .partition(|span| spans[*span].end == position);
+            // Open longer spans first so that shorter ones nest inside of them
+            opening.sort_by_key(|span| (core::cmp::Reverse(spans[*span].end), *span));
+            empty.sort_unstable();
+            let first_opening = opening.iter().copied().min().unwrap_or(usize::MAX);
+            for span in empty.iter().filter(|span| **span < first_opening) {
+                output.push_str(&spans[*span].open);
+                output.push_str(&spans[*span].close);
+            }
+            for span in opening {
+                output.push_str(&spans[span].open);
+                open_spans.push(span);
+            }
+            for span in empty.iter().filter(|span| **span > first_opening) {
+                output.push_str(&spans[*span].open);
+                output.push_str(&spans[*span].close);
+            }
+
+            if let Some(character) = characters.get(position) {
+                text.push(*character);
+            }
+        }
+        self.flush(&mut text, &mut output);
+        output
+    }
+
+    /// Returns the mapped attributes, ordered by their position in the source text.
+    fn spans(&self, markup: &ParsedMarkup, text_length: usize) -> Vec<Span> {
+        let mut attributes: Vec<_> = markup.attributes.iter().collect();
+        attributes.sort_by_key(|attribute| attribute.source_position);
+        attributes
+            .into_iter()
+            .filter_map(|attribute| {
+                let tag = self.tags.get(&attribute.name).or(self.fallback.as_ref())?;
+                let (open, close) = tag(attribute)?;
+                let start = attribute.position.min(text_length);
+                let end = (attribute.position + attribute.length).min(text_length);
+                Some(Span {
+                    start,
+                    end,
+                    open,
+                    close,
+                })
+            })
+            .collect()
+    }
+
+    fn flush(&self, text: &mut String, output: &mut String) {
+        if text.is_empty() {
+            return;
+        }
+        match &self.escape {
+            Some(escape) => output.push_str(&escape(text)),
+            None => output.push_str(text),
+        }
+        text.clear();
+    }
+}
+
+impl Debug for MarkupRewriter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarkupRewriter")
+            .field("tags", &self.tags.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.is_some())
+            .field("escape", &self.escape.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct Span {
+    start: usize,
+    end: usize,
+    open: String,
+    close: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::parse_markup;
+
+    #[test]
+    fn splits_overlapping_attributes() {
+        let markup = parse_markup("[a]x[b]y[/a]z[/b]").unwrap();
+        assert_eq!("<a>x<b>y</b></a><b>z</b>", bbcode_like().rewrite(&markup));
+    }
+
+    #[test]
+    fn nests_attributes_starting_at_the_same_position() {
+        let markup = parse_markup("[a][b]x[/b]y[/a]").unwrap();
+        assert_eq!("<a><b>x</b>y</a>", bbcode_like().rewrite(&markup));
+
+        let markup = parse_markup("[b][a]x[/b]y[/a]").unwrap();
+        assert_eq!("<a><b>x</b>y</a>", bbcode_like().rewrite(&markup));
+    }
+
+    #[test]
+    fn handles_multibyte_text_and_empty_attributes() {
+        let markup = parse_markup("ä[a]ö[pause/]ü[/a]").unwrap();
+        assert_eq!("ä<a>ö<pause></pause>ü</a>", bbcode_like().rewrite(&markup));
+    }
+
+    #[test]
+    fn drops_unmapped_attributes() {
+        let markup = parse_markup("Mae: [b]Hi[/b] [i]there[/i]").unwrap();
+        let rewriter = MarkupRewriter::new().with_tag("b", |_| Some(("*".into(), "*".into())));
+        assert_eq!("Mae: *Hi* there", rewriter.rewrite(&markup));
+    }
+
+    fn bbcode_like() -> MarkupRewriter<'static> {
+        MarkupRewriter::new().with_fallback(|attribute| {
+            (attribute.name != "character").then(|| {
+                (
+                    format!("<{}>", attribute.name),
+                    format!("</{}>", attribute.name),
+                )
+            })
+        })
+    }
+}
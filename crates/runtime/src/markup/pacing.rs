@@ -0,0 +1,225 @@
+//! Not part of the original implementation.
+//!
+//! Reads the pacing markers `[pause/]` and `[speed]` of a line as structured data for typewriter effects,
+//! instead of leaving text animation systems to interpret the attributes themselves.
+
+use crate::markup::{MarkupAttribute, MarkupValue, ParsedMarkup};
+use crate::prelude::*;
+use core::time::Duration;
+
+/// The name of the marker that pauses the text animation, e.g. `[pause=500/]` for half a second.
+/// The duration in milliseconds is read from the `pause` property, or from the `duration` property as in `[pause duration=500/]`.
+pub const PAUSE_ATTRIBUTE: &str = "pause";
+
+/// The name of the marker that changes the speed of the text animation, e.g. `[speed=2]fast[/speed]` to show text twice as fast.
+/// The factor is read from the `speed` property. A self-closing marker like `[speed=0.5/]` applies until the end of the line.
+pub const SPEED_ATTRIBUTE: &str = "speed";
+
+/// A pacing marker of a line, see [`ParsedMarkup::pacing_markers`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PacingMarker {
+    /// The text animation pauses before the character at `position`.
+    Pause {
+        /// The position in the plain text, in characters.
+        position: usize,
+        /// How long to pause.
+        duration: Duration,
+    },
+    /// The text animation runs at `factor` times its normal speed for the characters in the range.
+    Speed {
+        /// The position in the plain text where the speed changes, in characters.
+        position: usize,
+        /// The number of characters the speed applies to. Extends to the end of the text for self-closing markers.
+        length: usize,
+        /// The factor to multiply the normal speed with, always greater than zero.
+        factor: f32,
+    },
+}
+
+/// A piece of a line, as split by [`ParsedMarkup::pacing_segments`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PacingSegment<'a> {
+    /// Text to animate at the current speed.
+    Text(&'a str),
+    /// Pause the animation for the given duration.
+    Pause(Duration),
+    /// Animate the following text at the given factor of the normal speed, until the next speed change.
+    Speed(f32),
+}
+
+impl ParsedMarkup {
+    /// The pause and speed markers of the line, ordered by their position in the source text.
+    /// Markers without a valid value, e.g. `[pause=soon/]` or `[speed=0]`, are ignored.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// # use core::time::Duration;
+    /// let markup = parse_markup("Well[pause=500/]... [speed=2]run![/speed]").unwrap();
+    /// assert_eq!(
+    ///     vec![
+    ///         PacingMarker::Pause { position: 4, duration: Duration::from_millis(500) },
+    ///         PacingMarker::Speed { position: 8, length: 4, factor: 2.0 },
+    ///     ],
+    ///     markup.pacing_markers()
+    /// );
+    /// ```
+    pub fn pacing_markers(&self) -> Vec<PacingMarker> {
+        let text_length = self.text.chars().count();
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute.name.as_str() {
+                PAUSE_ATTRIBUTE => {
+                    let milliseconds = number(attribute, PAUSE_ATTRIBUTE)
+                        .or_else(|| number(attribute, "duration"))?;
+                    let duration = Duration::try_from_secs_f32(milliseconds / 1000.0).ok()?;
+                    Some(PacingMarker::Pause {
+                        position: attribute.position,
+                        duration,
+                    })
+                }
+                SPEED_ATTRIBUTE => {
+                    let factor =
+                        number(attribute, SPEED_ATTRIBUTE).filter(|factor| *factor > 0.0)?;
+                    let length = if attribute.length == 0 {
+                        text_length.saturating_sub(attribute.position)
+                    } else {
+                        attribute.length
+                    };
+                    Some(PacingMarker::Speed {
+                        position: attribute.position,
+                        length,
+                        factor,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Splits the text into the pieces a typewriter effect plays one after another: text, pauses and speed changes.
+    /// The text starts at the normal speed of `1.0`, and a [`PacingSegment::Speed`] is only emitted when the speed actually changes.
+    /// Where speed markers overlap, the one that started last applies.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use yarnspinner_runtime::markup::*;
+    /// # use core::time::Duration;
+    /// let markup = parse_markup("Well[pause=500/]... [speed=2]run![/speed]").unwrap();
+    /// assert_eq!(
+    ///     vec![
+    ///         PacingSegment::Text("Well"),
+    ///         PacingSegment::Pause(Duration::from_millis(500)),
+    ///         PacingSegment::Text("... "),
+    ///         PacingSegment::Speed(2.0),
+    ///         PacingSegment::Text("run!"),
+    ///     ],
+    ///     markup.pacing_segments()
+    /// );
+    /// ```
+    pub fn pacing_segments(&self) -> Vec<PacingSegment<'_>> {
+        // Speed changes ending at a position come before anything starting there, the rest stays in source order
+        let mut boundaries: Vec<(usize, bool, usize)> = Vec::new();
+        let markers = self.pacing_markers();
+        for (index, marker) in markers.iter().enumerate() {
+            match *marker {
+                PacingMarker::Pause { position, .. } => boundaries.push((position, true, index)),
+                PacingMarker::Speed {
+                    position, length, ..
+                } => {
+                    boundaries.push((position, true, index));
+                    boundaries.push((position + length, false, index));
+                }
+            }
+        }
+        boundaries.sort_by_key(|(position, is_start, index)| (*position, *is_start, *index));
+
+        let byte_index = |char_index: usize| {
+            self.text
+                .char_indices()
+                .nth(char_index)
+                .map_or(self.text.len(), |(index, _)| index)
+        };
+        let mut segments = Vec::new();
+        let mut active_speeds: Vec<(usize, f32)> = Vec::new();
+        let mut current_speed = 1.0;
+        let mut text_start = 0;
+        for (position, is_start, index) in boundaries {
+            let byte_position = byte_index(position);
+            if byte_position > text_start {
+                segments.push(PacingSegment::Text(&self.text[text_start..byte_position]));
+                text_start = byte_position;
+            }
+            match markers[index] {
+                PacingMarker::Pause { duration, .. } => {
+                    segments.push(PacingSegment::Pause(duration))
+                }
+                PacingMarker::Speed { factor, .. } => {
+                    if is_start {
+                        active_speeds.push((index, factor));
+                    } else {
+                        active_speeds.retain(|(active, _)| *active != index);
+                    }
+                    let speed = active_speeds.last().map_or(1.0, |(_, factor)| *factor);
+                    if speed != current_speed {
+                        current_speed = speed;
+                        segments.push(PacingSegment::Speed(speed));
+                    }
+                }
+            }
+        }
+        if text_start < self.text.len() {
+            segments.push(PacingSegment::Text(&self.text[text_start..]));
+        }
+        // Speed changes after the last character have nothing left to apply to
+        while matches!(segments.last(), Some(PacingSegment::Speed(_))) {
+            segments.pop();
+        }
+        segments
+    }
+}
+
+fn number(attribute: &MarkupAttribute, property: &str) -> Option<f32> {
+    match attribute.property(property)? {
+        MarkupValue::Integer(value) => Some(*value as f32),
+        MarkupValue::Float(value) => Some(*value),
+        MarkupValue::String(_) | MarkupValue::Bool(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::parse_markup;
+
+    #[test]
+    fn splits_overlapping_and_self_closing_speed_markers() {
+        let markup = parse_markup(
+            "[speed=2]a[speed=4]b[/speed]c[/speed]d[speed=0.5/]e[pause duration=250/]f",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                PacingSegment::Speed(2.0),
+                PacingSegment::Text("a"),
+                PacingSegment::Speed(4.0),
+                PacingSegment::Text("b"),
+                PacingSegment::Speed(2.0),
+                PacingSegment::Text("c"),
+                PacingSegment::Speed(1.0),
+                PacingSegment::Text("d"),
+                PacingSegment::Speed(0.5),
+                PacingSegment::Text("e"),
+                PacingSegment::Pause(Duration::from_millis(250)),
+                PacingSegment::Text("f"),
+            ],
+            markup.pacing_segments()
+        );
+        assert!(parse_markup("[pause=soon/][speed=0]x[/speed]")
+            .unwrap()
+            .pacing_markers()
+            .is_empty());
+    }
+}
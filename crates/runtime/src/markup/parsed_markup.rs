@@ -0,0 +1,223 @@
+//! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/MarkupParseResult.cs>
+//! which was split into multiple files.
+
+pub use self::{escaped_segment::*, markup_attribute::*, markup_value::*};
+pub(crate) use self::{markup_attribute_marker::*, tag_type::*};
+use crate::prelude::*;
+
+mod escaped_segment;
+mod markup_attribute;
+mod markup_attribute_marker;
+mod markup_value;
+mod tag_type;
+
+/// The result of parsing a line of marked-up text.
+///
+/// You do not create instances of this struct yourself. It is created by [`parse_markup`](crate::markup::parse_markup).
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParsedMarkup {
+    /// The original text, with all parsed markers removed.
+    pub text: String,
+
+    /// The list of [`MarkupAttribute`]s in this parse result, ordered by their position in the source text.
+    pub attributes: Vec<MarkupAttribute>,
+
+    /// The segments of [`ParsedMarkup::text`] that were escaped in the source text, ordered by their position.
+    pub escaped_segments: Vec<EscapedSegment>,
+}
+
+impl ParsedMarkup {
+    /// Gets the first attribute with the given name, if present.
+    pub fn attribute(&self, name: &str) -> Option<&MarkupAttribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+    }
+
+    /// Gets the substring of [`ParsedMarkup::text`] that the given attribute applies to.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the attribute's range lies outside of the text.
+    pub fn text_for_attribute(&self, attribute: &MarkupAttribute) -> &str {
+        let start = self.byte_index(attribute.position);
+        let end = self.byte_index(attribute.position + attribute.length);
+        &self.text[start..end]
+    }
+
+    /// Returns a copy of this parse result with the text that the given attribute applies to removed.
+    ///
+    /// The given attribute is removed, as is any other attribute whose range lies entirely inside of it.
+    /// Attributes that overlap the removed range are shortened and the positions of following attributes are shifted.
+    /// Attributes that had a length of zero are kept, even if they are positioned inside of the removed range.
+    #[must_use]
+    pub fn delete_range(&self, attribute_to_delete: &MarkupAttribute) -> Self {
+        let start = attribute_to_delete.position;
+        let length = attribute_to_delete.length;
+        let end = start + length;
+
+        let text = self
+            .text
+            .chars()
+            .enumerate()
+            .filter(|(index, _)| !(start..end).contains(index))
+            .map(|(_, character)| character)
+            .collect();
+
+        let attributes = self
+            .attributes
+            .iter()
+            .filter(|attribute| *attribute != attribute_to_delete)
+            .filter_map(|attribute| {
+                let (position, length) =
+                    shift_range(attribute.position, attribute.length, start, length)?;
+                Some(MarkupAttribute {
+                    position,
+                    length,
+                    ..attribute.clone()
+                })
+            })
+            .collect();
+        let escaped_segments = self
+            .escaped_segments
+            .iter()
+            .filter_map(|segment| {
+                let (position, length) =
+                    shift_range(segment.position, segment.length, start, length)?;
+                Some(EscapedSegment {
+                    position,
+                    length,
+                    ..segment.clone()
+                })
+            })
+            .collect();
+
+        let mut edited = Self {
+            text,
+            attributes,
+            escaped_segments,
+        };
+        edited.refresh_offsets();
+        edited
+    }
+
+    /// Returns a copy of this parse result with its text replaced by a transformed version of it, e.g. with furigana inserted or in upper case.
+    /// The ranges of all attributes and escaped segments are moved according to the given mapping from positions in the old text to the new one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the mapping does not match the lengths of the old and new text.
+    pub fn remap(
+        &self,
+        text: impl Into<String>,
+        mapping: &SpanMapping,
+    ) -> core::result::Result<Self, SpanMappingError> {
+        let text = text.into();
+        let old_length = self.text.chars().count();
+        if mapping.old_length() != old_length {
+            return Err(SpanMappingError::OldLengthMismatch {
+                expected: mapping.old_length(),
+                actual: old_length,
+            });
+        }
+        let new_length = text.chars().count();
+        if mapping.new_length() != new_length {
+            return Err(SpanMappingError::NewLengthMismatch {
+                expected: mapping.new_length(),
+                actual: new_length,
+            });
+        }
+
+        let remap = |position: usize, length: usize| {
+            let range = mapping.map_range(position..position + length);
+            (range.start, range.len())
+        };
+        let mut edited = self.clone();
+        edited.text = text;
+        for attribute in &mut edited.attributes {
+            (attribute.position, attribute.length) = remap(attribute.position, attribute.length);
+        }
+        for segment in &mut edited.escaped_segments {
+            (segment.position, segment.length) = remap(segment.position, segment.length);
+        }
+        edited.refresh_offsets();
+        Ok(edited)
+    }
+
+    /// Recomputes precomputed attribute offsets after the text was edited, if there are any.
+    pub(crate) fn refresh_offsets(&mut self) {
+        if self
+            .attributes
+            .iter()
+            .any(|attribute| attribute.offsets.is_some())
+        {
+            let offsets = OffsetTable::new(&self.text);
+            for attribute in &mut self.attributes {
+                if attribute.offsets.is_some() {
+                    attribute.compute_offsets(&offsets);
+                }
+            }
+        }
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain(core::iter::once(self.text.len()))
+            .nth(char_index)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Character index {char_index} is out of bounds for the text \"{}\"",
+                    self.text
+                )
+            })
+    }
+}
+
+/// Returns the new position and length of a range after deleting `length` characters at `start`, or `None` if the range was deleted.
+fn shift_range(
+    range_start: usize,
+    range_length: usize,
+    start: usize,
+    length: usize,
+) -> Option<(usize, usize)> {
+    let end = start + length;
+    let range_end = range_start + range_length;
+    let (mut position, mut edited_length) = (range_start, range_length);
+
+    if length == 0 {
+        // Nothing to shift around
+    } else if range_start <= start {
+        // The range starts before the deleted range
+        if range_end <= start {
+            // ...and ends before it, so it is unaffected
+        } else if range_end <= end {
+            // ...and ends inside of it, so truncate it
+            edited_length = start - range_start;
+            if range_length > 0 && edited_length == 0 {
+                // The range has been reduced to nothing, so drop it
+                return None;
+            }
+        } else {
+            // ...and ends after it, so shrink it
+            edited_length -= length;
+        }
+    } else if range_start >= end {
+        // The range starts after the deleted range, so move it
+        position -= length;
+    } else if range_end <= end {
+        // The range lies entirely within the deleted range, so drop it
+        if range_length > 0 {
+            return None;
+        }
+        position = start;
+    } else {
+        // The range starts inside the deleted range and ends after it, so trim its start
+        let overlap = end - range_start;
+        position = start;
+        edited_length -= overlap;
+    }
+    Some((position, edited_length))
+}
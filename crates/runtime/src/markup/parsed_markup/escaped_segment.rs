@@ -0,0 +1,36 @@
+//! Not part of the original implementation.
+
+#[cfg(feature = "serde")]
+use crate::prelude::*;
+
+/// A range of the plain text of a [`ParsedMarkup`](crate::markup::ParsedMarkup) that was written literally instead of being parsed as markup.
+///
+/// Editors can use these to write the text back with the same escapes the author used.
+/// Only escapes that were honored during parsing are reported, see [`MarkupParseOptions`](crate::markup::MarkupParseOptions).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EscapedSegment {
+    /// How the segment was escaped.
+    pub kind: EscapeKind,
+
+    /// The position in the plain text where this segment begins, in characters.
+    pub position: usize,
+
+    /// The number of characters in the plain text that this segment covers.
+    pub length: usize,
+
+    /// The position in the original source text where the escape begins, in characters.
+    /// For [`EscapeKind::Character`], this is the position of the backslash.
+    /// For [`EscapeKind::NoMarkup`], this is the position of the first character after the `[nomarkup]` marker.
+    pub source_position: usize,
+}
+
+/// The way an [`EscapedSegment`] was escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EscapeKind {
+    /// A bracket escaped with a backslash, like `\[`.
+    Character,
+    /// The contents of a `[nomarkup]` region.
+    NoMarkup,
+}
@@ -0,0 +1,120 @@
+//! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/MarkupParseResult.cs>
+//! which was split into multiple files.
+
+use super::markup_attribute_marker::MarkupAttributeMarker;
+use crate::markup::MarkupValue;
+use crate::prelude::*;
+use core::ops::Range;
+use hashbrown::HashMap;
+
+/// Represents a range of text in a marked-up string.
+///
+/// You do not create instances of this struct yourself. It is created by [`parse_markup`](crate::markup::parse_markup).
+///
+/// ## See also
+/// [`ParsedMarkup`](crate::markup::ParsedMarkup)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarkupAttribute {
+    /// The position in the plain text where this attribute begins, in characters.
+    pub position: usize,
+
+    /// The number of characters in the plain text that this attribute applies to.
+    pub length: usize,
+
+    /// The name of the attribute.
+    pub name: String,
+
+    /// The properties associated with this attribute.
+    pub properties: HashMap<String, MarkupValue>,
+
+    /// The position in the original source text where this attribute begins, in characters.
+    pub source_position: usize,
+
+    /// The range of this attribute in other units, if they were computed by the parser.
+    /// See [`LineParser::with_precomputed_offsets`](crate::markup::LineParser::with_precomputed_offsets).
+    pub offsets: Option<MarkupAttributeOffsets>,
+}
+
+/// The range of a [`MarkupAttribute`] in units other than characters, for slicing or for text renderers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarkupAttributeOffsets {
+    /// The range in bytes of the UTF-8 encoded plain text, which can be used to slice a Rust string.
+    pub bytes: Range<usize>,
+    /// The range in UTF-16 code units of the plain text, as used by many text renderers.
+    pub utf16: Range<usize>,
+}
+
+impl MarkupAttribute {
+    /// Creates a new attribute from the marker that opened it.
+    pub(crate) fn from_marker(marker: MarkupAttributeMarker, length: usize) -> Self {
+        Self {
+            position: marker.position,
+            length,
+            name: marker.name.unwrap_or_default(),
+            properties: marker.properties,
+            source_position: marker.source_position,
+            offsets: None,
+        }
+    }
+
+    /// Gets the property with the given name, if present.
+    pub fn property(&self, name: &str) -> Option<&MarkupValue> {
+        self.properties.get(name)
+    }
+
+    /// The range of this attribute in bytes of the given plain text, e.g. for slicing it.
+    /// The attribute is clamped to the end of the text.
+    pub fn byte_range(&self, text: &str) -> Range<usize> {
+        let byte_index = |char_index: usize| {
+            text.char_indices()
+                .nth(char_index)
+                .map_or(text.len(), |(index, _)| index)
+        };
+        byte_index(self.position)..byte_index(self.position + self.length)
+    }
+
+    /// The range of this attribute in UTF-16 code units of the given plain text.
+    /// The attribute is clamped to the end of the text.
+    pub fn utf16_range(&self, text: &str) -> Range<usize> {
+        let utf16_index =
+            |char_index: usize| text.chars().take(char_index).map(char::len_utf16).sum();
+        utf16_index(self.position)..utf16_index(self.position + self.length)
+    }
+
+    /// Computes [`MarkupAttribute::offsets`] for the given plain text.
+    pub(crate) fn compute_offsets(&mut self, offsets: &OffsetTable) {
+        self.offsets = Some(offsets.offsets(self.position, self.length));
+    }
+}
+
+/// The byte and UTF-16 offsets of every character in a text, for computing [`MarkupAttributeOffsets`] of many attributes at once.
+#[derive(Debug)]
+pub(crate) struct OffsetTable(Vec<(usize, usize)>);
+
+impl OffsetTable {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut utf16 = 0;
+        let mut offsets: Vec<_> = text
+            .char_indices()
+            .map(|(byte, character)| {
+                let offset = (byte, utf16);
+                utf16 += character.len_utf16();
+                offset
+            })
+            .collect();
+        offsets.push((text.len(), utf16));
+        Self(offsets)
+    }
+
+    fn offsets(&self, position: usize, length: usize) -> MarkupAttributeOffsets {
+        let last = self.0.len() - 1;
+        let (start_byte, start_utf16) = self.0[position.min(last)];
+        let (end_byte, end_utf16) = self.0[(position + length).min(last)];
+        MarkupAttributeOffsets {
+            bytes: start_byte..end_byte,
+            utf16: start_utf16..end_utf16,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/MarkupParseResult.cs>
+//! which was split into multiple files.
+
+use super::tag_type::TagType;
+use crate::markup::MarkupValue;
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// Represents a marker (e.g. `[a]`) in line of marked up text.
+///
+/// You do not create instances of this struct yourself. It is created by the line parser.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MarkupAttributeMarker {
+    /// The name of the marker. `None` for the close-all marker `[/]`.
+    pub(crate) name: Option<String>,
+
+    /// The position of the marker in the plain text.
+    pub(crate) position: usize,
+
+    /// The position of the marker in the original text.
+    pub(crate) source_position: usize,
+
+    /// The properties of the marker.
+    pub(crate) properties: HashMap<String, MarkupValue>,
+
+    /// The type of the marker.
+    pub(crate) type_: TagType,
+}
+
+impl MarkupAttributeMarker {
+    /// Gets the property with the given name, if present.
+    pub(crate) fn property(&self, name: &str) -> Option<&MarkupValue> {
+        self.properties.get(name)
+    }
+}
@@ -7,15 +7,9 @@ use core::fmt::Display;
 /// A value associated with a markup name.
 ///
 /// You do not create instances of this struct yourself. It is created
-/// by objects that can parse markup, such as [`Dialogue`]
+/// by objects that can parse markup, such as [`parse_markup`](crate::markup::parse_markup).
 #[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
-#[cfg_attr(
-    all(feature = "bevy", feature = "serde"),
-    reflect(Serialize, Deserialize)
-)]
 pub enum MarkupValue {
     /// An integer value. Note that while Yarn variables make no distinction between integers and floats, markup values do.
     Integer(u32),
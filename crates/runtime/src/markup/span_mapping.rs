@@ -0,0 +1,220 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use core::error::Error;
+use core::fmt;
+use core::ops::Range;
+
+/// Maps character positions in the plain text of a [`ParsedMarkup`] to positions in a transformed version of that text,
+/// e.g. after inserting furigana or changing the case of a line. Use it with [`ParsedMarkup::remap`] to keep attributes aligned with the new text.
+///
+/// A mapping assigns a new position to every character boundary of the old text, from `0` up to and including its length.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// let markup = parse_markup("[ruby]漢字[/ruby]を読む").unwrap();
+/// // Insert the reading "(かんじ)" after "漢字"
+/// let mapping = SpanMapping::from_edits(5, [(2..2, 5)]).unwrap();
+/// let remapped = markup.remap("漢字(かんじ)を読む", &mapping).unwrap();
+/// assert_eq!("漢字(かんじ)", remapped.text_for_attribute(remapped.attribute("ruby").unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpanMapping {
+    boundaries: Vec<usize>,
+}
+
+impl SpanMapping {
+    /// Creates a mapping from the new position of every character boundary of the old text,
+    /// i.e. the `n`th element is the position in the new text that position `n` of the old text corresponds to.
+    /// A text of `n` characters has `n + 1` boundaries.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no boundaries are given or if they are not in ascending order.
+    pub fn new(
+        boundaries: impl IntoIterator<Item = usize>,
+    ) -> core::result::Result<Self, SpanMappingError> {
+        let boundaries: Vec<_> = boundaries.into_iter().collect();
+        if boundaries.is_empty() {
+            return Err(SpanMappingError::Empty);
+        }
+        if let Some(position) = boundaries.windows(2).position(|pair| pair[0] > pair[1]) {
+            return Err(SpanMappingError::NotAscending {
+                position: position + 1,
+            });
+        }
+        Ok(Self { boundaries })
+    }
+
+    /// Creates a mapping from a list of edits to a text of `old_length` characters.
+    /// Each edit replaces a range of characters of the old text with the given number of new characters.
+    /// Edits must be ordered by their position and must not overlap.
+    ///
+    /// - Text inserted with an empty range is attached to the text before it: attributes ending at the insertion point
+    ///   are extended over the inserted text, while attributes starting there begin after it.
+    /// - Positions inside of a replaced range are distributed evenly over the replacement.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`SpanMappingError::InvalidEdit`] if an edit lies outside of the old text, overlaps the previous edit or is out of order.
+    pub fn from_edits(
+        old_length: usize,
+        edits: impl IntoIterator<Item = (Range<usize>, usize)>,
+    ) -> core::result::Result<Self, SpanMappingError> {
+        let mut boundaries = Vec::with_capacity(old_length + 1);
+        let (mut old, mut new) = (0, 0);
+        for (range, replacement_length) in edits {
+            if range.start < old || range.start > range.end || range.end > old_length {
+                return Err(SpanMappingError::InvalidEdit { range });
+            }
+            while old < range.start {
+                boundaries.push(new);
+                old += 1;
+                new += 1;
+            }
+            let replaced_length = range.len();
+            // The boundary at the start of an insertion is pushed with the following text, so it maps to the end of the insertion
+            boundaries.extend(
+                (0..replaced_length)
+                    .map(|offset| new + offset * replacement_length / replaced_length),
+            );
+            old += replaced_length;
+            new += replacement_length;
+        }
+        while old < old_length {
+            boundaries.push(new);
+            old += 1;
+            new += 1;
+        }
+        boundaries.push(new);
+        Ok(Self { boundaries })
+    }
+
+    /// The number of characters of the old text.
+    pub fn old_length(&self) -> usize {
+        self.boundaries.len() - 1
+    }
+
+    /// The number of characters of the new text.
+    pub fn new_length(&self) -> usize {
+        self.boundaries[self.boundaries.len() - 1]
+    }
+
+    /// Maps a position of the old text to the new text.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the position is greater than [`SpanMapping::old_length`].
+    pub fn map_position(&self, position: usize) -> usize {
+        self.boundaries[position]
+    }
+
+    /// Maps a range of the old text to the new text.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the range ends after [`SpanMapping::old_length`].
+    pub fn map_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.map_position(range.start);
+        let end = self.map_position(range.end).max(start);
+        start..end
+    }
+}
+
+/// An error that occurs when creating or applying a [`SpanMapping`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpanMappingError {
+    Empty,
+    NotAscending { position: usize },
+    InvalidEdit { range: Range<usize> },
+    OldLengthMismatch { expected: usize, actual: usize },
+    NewLengthMismatch { expected: usize, actual: usize },
+}
+
+impl Error for SpanMappingError {}
+
+impl fmt::Display for SpanMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SpanMappingError::*;
+        match self {
+            Empty => write!(f, "A span mapping needs at least one boundary"),
+            NotAscending { position } => write!(f, "The boundary at position {position} of the span mapping is smaller than the one before it"),
+            InvalidEdit { range } => write!(f, "The edit of the range {range:?} lies outside of the text, overlaps the previous edit or is out of order"),
+            OldLengthMismatch { expected, actual } => write!(f, "The span mapping expects a text of {expected} characters, but the parsed line has {actual}"),
+            NewLengthMismatch { expected, actual } => write!(f, "The span mapping produces a text of {expected} characters, but the new text has {actual}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::parse_markup;
+
+    #[test]
+    fn maps_edits() {
+        // "straße" -> "STRASSE", then insert "!" at the end
+        let mapping = SpanMapping::from_edits(6, [(4..5, 2), (6..6, 1)]).unwrap();
+        assert_eq!(vec![0, 1, 2, 3, 4, 6, 8], mapping.boundaries);
+        assert_eq!(8, mapping.new_length());
+        assert_eq!(4..6, mapping.map_range(4..5));
+
+        // Positions inside of a replacement are spread over it
+        let mapping = SpanMapping::from_edits(4, [(0..4, 2)]).unwrap();
+        assert_eq!(vec![0, 0, 1, 1, 2], mapping.boundaries);
+
+        assert_eq!(
+            Err(SpanMappingError::InvalidEdit { range: 1..3 }),
+            SpanMapping::from_edits(4, [(2..3, 1), (1..3, 0)])
+        );
+        assert_eq!(
+            Err(SpanMappingError::NotAscending { position: 2 }),
+            SpanMapping::new([0, 2, 1])
+        );
+    }
+
+    #[test]
+    fn remaps_attributes_and_escaped_segments() {
+        let markup = parse_markup(r"[b]straße[/b] \[x\] [i]weit[/i]").unwrap();
+        let new_text = "STRASSE [X] WEIT";
+        let mapping = SpanMapping::from_edits(15, [(4..5, 2)]).unwrap();
+        let remapped = markup.remap(new_text, &mapping).unwrap();
+        assert_eq!(new_text, remapped.text);
+        assert_eq!(
+            "STRASSE",
+            remapped.text_for_attribute(remapped.attribute("b").unwrap())
+        );
+        assert_eq!(
+            "WEIT",
+            remapped.text_for_attribute(remapped.attribute("i").unwrap())
+        );
+        assert_eq!(
+            vec![(8, 1), (10, 1)],
+            remapped
+                .escaped_segments
+                .iter()
+                .map(|segment| (segment.position, segment.length))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Err(SpanMappingError::NewLengthMismatch {
+                expected: 16,
+                actual: 3
+            }),
+            markup.remap("abc", &mapping)
+        );
+        assert_eq!(
+            Err(SpanMappingError::OldLengthMismatch {
+                expected: 3,
+                actual: 15
+            }),
+            markup.remap("abc", &SpanMapping::from_edits(3, []).unwrap())
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Not part of the original implementation, which always normalizes lines to Unicode normalization form C.
+
+use crate::prelude::*;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::fmt::{self, Debug};
+use unicode_normalization::UnicodeNormalization;
+
+type TransformFn = dyn Fn(&str) -> String + Send + Sync;
+
+/// A pipeline of transforms applied to the text of every line parsed by a [`LineParser`], e.g. the ones of a [`Dialogue`](crate::prelude::Dialogue).
+///
+/// The following steps are available:
+/// - NFC normalization, which is enabled by default. It is applied to the whole line before parsing, so markup is normalized as well.
+/// - Smart quotes, which turn `"` and `'` into typographic quotes like `“`, `”`, `‘` and `’`.
+/// - Ellipsis collapsing, which turns `...` into `…`.
+/// - Custom replacements and arbitrary transforms.
+///
+/// All steps but NFC normalization are applied in the order they were added, to each run of plain text between markers
+/// and to the text inserted by replacement markers. Markup itself and the contents of `[nomarkup]` are left untouched,
+/// and the positions of attributes refer to the transformed text.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::*;
+/// let normalizer = TextNormalizer::new()
+///     .with_smart_quotes()
+///     .with_collapsed_ellipses()
+///     .with_replacement("--", "—");
+/// let parser = LineParser::new().with_text_normalizer(normalizer);
+/// let markup = parser.parse_markup(r#"Mae: "I [b]don't[/b] know..." -- she said."#).unwrap();
+/// assert_eq!("Mae: “I don’t know…” — she said.", markup.text);
+/// assert_eq!("don’t", markup.text_for_attribute(markup.attribute("b").unwrap()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextNormalizer {
+    nfc: bool,
+    transforms: Vec<Transform>,
+}
+
+#[derive(Clone)]
+enum Transform {
+    SmartQuotes,
+    CollapseEllipses,
+    Replace { from: String, to: String },
+    Custom(Arc<TransformFn>),
+}
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextNormalizer {
+    /// Creates a new pipeline that only applies NFC normalization.
+    pub fn new() -> Self {
+        Self {
+            nfc: true,
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Sets whether lines are normalized to Unicode normalization form C. Enabled by default.
+    #[must_use]
+    pub fn with_nfc(mut self, nfc: bool) -> Self {
+        self.nfc = nfc;
+        self
+    }
+
+    /// Adds a step that replaces straight quotes with typographic ones.
+    /// A quote opens if it is preceded by whitespace, an opening bracket or nothing, and closes otherwise.
+    /// This turns apostrophes as in `don't` into `’`.
+    #[must_use]
+    pub fn with_smart_quotes(mut self) -> Self {
+        self.transforms.push(Transform::SmartQuotes);
+        self
+    }
+
+    /// Adds a step that replaces three consecutive periods with an ellipsis character.
+    #[must_use]
+    pub fn with_collapsed_ellipses(mut self) -> Self {
+        self.transforms.push(Transform::CollapseEllipses);
+        self
+    }
+
+    /// Adds a step that replaces all occurrences of `from` with `to`.
+    #[must_use]
+    pub fn with_replacement(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let from = from.into();
+        if from.is_empty() {
+            log::warn!("Ignoring a text replacement of an empty string");
+            return self;
+        }
+        self.transforms.push(Transform::Replace {
+            from,
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Adds a step that transforms text with the given function.
+    /// Note that the function is called for each run of plain text separately, not for the whole line.
+    #[must_use]
+    pub fn with_transform(
+        mut self,
+        transform: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.transforms.push(Transform::Custom(Arc::new(transform)));
+        self
+    }
+
+    /// Applies all steps to the given text, which is expected to not contain any markup.
+    pub fn normalize(&self, text: &str) -> String {
+        self.transform(&self.normalize_source(text), None)
+    }
+
+    /// Applies NFC normalization, if enabled.
+    pub(crate) fn normalize_source<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.nfc {
+            Cow::Owned(line.nfc().collect())
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
+    /// Applies all steps but NFC normalization to a run of plain text that follows the character `preceding`.
+    pub(crate) fn transform(&self, text: &str, preceding: Option<char>) -> String {
+        let mut text = text.to_owned();
+        for transform in &self.transforms {
+            text = match transform {
+                Transform::SmartQuotes => smart_quotes(&text, preceding),
+                Transform::CollapseEllipses => text.replace("...", "…"),
+                Transform::Replace { from, to } => text.replace(from.as_str(), to),
+                Transform::Custom(transform) => transform(&text),
+            };
+        }
+        text
+    }
+
+    /// Whether any step but NFC normalization is configured.
+    pub(crate) fn has_transforms(&self) -> bool {
+        !self.transforms.is_empty()
+    }
+}
+
+fn smart_quotes(text: &str, mut preceding: Option<char>) -> String {
+    let mut output = String::with_capacity(text.len());
+    for character in text.chars() {
+        let opens = preceding.is_none_or(|preceding| {
+            preceding.is_whitespace()
+                || matches!(preceding, '(' | '[' | '{' | '“' | '‘' | '—' | '–')
+        });
+        let replaced = match character {
+            '"' if opens => '“',
+            '"' => '”',
+            '\'' if opens => '‘',
+            '\'' => '’',
+            other => other,
+        };
+        output.push(replaced);
+        preceding = Some(replaced);
+    }
+    output
+}
+
+impl Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SmartQuotes => f.write_str("SmartQuotes"),
+            Self::CollapseEllipses => f.write_str("CollapseEllipses"),
+            Self::Replace { from, to } => f
+                .debug_struct("Replace")
+                .field("from", from)
+                .field("to", to)
+                .finish(),
+            Self::Custom(_) => f.write_str("Custom(<fn>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{LineParser, NO_MARKUP_ATTRIBUTE};
+
+    #[test]
+    fn applies_steps_in_order() {
+        let normalizer = TextNormalizer::new()
+            .with_replacement("...", "---")
+            .with_collapsed_ellipses()
+            .with_transform(|text| text.to_uppercase());
+        assert_eq!("WAIT--- WHAT…", normalizer.normalize("Wait... what…"));
+    }
+
+    #[test]
+    fn smart_quotes_look_across_markers() {
+        let parser =
+            LineParser::new().with_text_normalizer(TextNormalizer::new().with_smart_quotes());
+        let markup = parser
+            .parse_markup(r#"[i]"Hi,"[/i] '[b]you[/b]' said [wave]"'ok'"[/wave]"#)
+            .unwrap();
+        assert_eq!("“Hi,” ‘you’ said “‘ok’”", markup.text);
+        assert_eq!(
+            "you",
+            markup.text_for_attribute(markup.attribute("b").unwrap())
+        );
+    }
+
+    #[test]
+    fn leaves_markup_and_nomarkup_untouched() {
+        let parser = LineParser::new().with_text_normalizer(
+            TextNormalizer::new()
+                .with_smart_quotes()
+                .with_collapsed_ellipses(),
+        );
+        let line = format!(
+            r#"[a name="x..."]...[/a] [{NO_MARKUP_ATTRIBUTE}]"..."[/{NO_MARKUP_ATTRIBUTE}]"#
+        );
+        let markup = parser.parse_markup(&line).unwrap();
+        assert_eq!("… \"...\"", markup.text);
+        assert_eq!(
+            Some(&"x...".into()),
+            markup.attribute("a").unwrap().property("name")
+        );
+        assert_eq!(1, markup.attribute("a").unwrap().length);
+    }
+
+    #[test]
+    fn nfc_can_be_disabled() {
+        let decomposed = "e\u{301}";
+        assert_eq!("é", TextNormalizer::new().normalize(decomposed));
+        assert_eq!(
+            decomposed,
+            TextNormalizer::new().with_nfc(false).normalize(decomposed)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt::{self, Display};
+
+/// The name of a node that was verified to exist in the program of a [`Dialogue`], obtained via [`Dialogue::node`].
+///
+/// Pass it to [`Dialogue::set_node`], [`Dialogue::jump_to_node`] or [`Dialogue::detour_to_node`] instead of a plain name.
+/// Since the node was checked once when the handle was created, these can only fail with [`DialogueError::InvalidNode`]
+/// if the program was unloaded or replaced in the meantime, e.g. via [`Dialogue::unload_all`] or [`Dialogue::replace_program`].
+/// Adding programs via [`Dialogue::add_program`] keeps existing handles valid.
+///
+/// Cloning a handle is cheap, as the name is shared with the dialogue.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # let mut program = Program::default();
+/// # program.nodes.insert("ShopIntro".to_owned(), Node { name: "ShopIntro".to_owned(), ..Default::default() });
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.add_program(program);
+///
+/// // Validate names once, e.g. when loading a level, instead of every time the node is started
+/// let shop_intro = dialogue.node("ShopIntro").unwrap();
+/// assert!(dialogue.node("ShopIntr").is_err());
+///
+/// dialogue.set_node(&shop_intro).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeHandle(Arc<str>);
+
+impl NodeHandle {
+    pub(crate) fn new(node_name: Arc<str>) -> Self {
+        Self(node_name)
+    }
+
+    /// The name of the node.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The [`stable_hash`] of the node name.
+impl StableHash for NodeHandle {
+    fn stable_hash(&self) -> u64 {
+        self.name().stable_hash()
+    }
+}
+
+impl Display for NodeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for NodeHandle {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<NodeHandle> for String {
+    fn from(handle: NodeHandle) -> Self {
+        handle.0.as_ref().to_owned()
+    }
+}
+
+impl From<&NodeHandle> for String {
+    fn from(handle: &NodeHandle) -> Self {
+        handle.0.as_ref().to_owned()
+    }
+}
+
+impl From<NodeHandle> for Arc<str> {
+    fn from(handle: NodeHandle) -> Self {
+        handle.0
+    }
+}
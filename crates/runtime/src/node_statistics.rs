@@ -0,0 +1,169 @@
+//! Not part of the original implementation.
+//!
+//! Tracks how often nodes were entered and exited and when they were last visited,
+//! so writers can branch on the history of a conversation without the game keeping books.
+
+use crate::prelude::*;
+
+/// The prefix of the variables in which the statistics of nodes are stored as [`YarnValue::Integer`]s, see [`Dialogue::set_node_statistics_tracking`].
+pub const NODE_STATISTICS_VARIABLE_PREFIX: &str = "$Yarn.Internal.NodeStatistics.";
+
+/// How often a node was entered and exited and when it was last visited, see [`Dialogue::node_statistics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NodeStatistics {
+    /// The number of [`DialogueEvent::NodeStart`]s of the node, including returns to it from a `<<detour>>`.
+    /// Unlike the `visited_count` of the original, which counts how often the node was completed, this counts every entry.
+    pub times_entered: u32,
+    /// The number of [`DialogueEvent::NodeComplete`]s of the node. Detouring from the node doesn't exit it.
+    pub times_exited: u32,
+    /// When the node was last entered, or `None` if it never was.
+    /// Timestamps count the nodes entered by the dialogue, starting at `1`, so they order visits without relying on a clock.
+    pub last_visited: Option<u32>,
+}
+
+impl NodeStatistics {
+    /// Reads the statistics of a node from the variable storage. Nodes without statistics were never visited.
+    pub(crate) fn read(variable_storage: &dyn VariableStorage, node_name: &str) -> Self {
+        let integer = |name: String| read_integer(variable_storage, &name) as u32;
+        let last_visited = integer(last_visited_variable(node_name));
+        Self {
+            times_entered: integer(entered_variable(node_name)),
+            times_exited: integer(exited_variable(node_name)),
+            last_visited: (last_visited > 0).then_some(last_visited),
+        }
+    }
+}
+
+/// The variable counting the nodes entered, which provides the timestamps of [`NodeStatistics::last_visited`].
+pub(crate) fn clock_variable() -> String {
+    format!("{NODE_STATISTICS_VARIABLE_PREFIX}Clock")
+}
+
+pub(crate) fn entered_variable(node_name: &str) -> String {
+    format!("{NODE_STATISTICS_VARIABLE_PREFIX}Entered.{node_name}")
+}
+
+pub(crate) fn exited_variable(node_name: &str) -> String {
+    format!("{NODE_STATISTICS_VARIABLE_PREFIX}Exited.{node_name}")
+}
+
+pub(crate) fn last_visited_variable(node_name: &str) -> String {
+    format!("{NODE_STATISTICS_VARIABLE_PREFIX}LastVisited.{node_name}")
+}
+
+pub(crate) fn read_integer(variable_storage: &dyn VariableStorage, name: &str) -> i64 {
+    variable_storage
+        .get(name)
+        .ok()
+        .and_then(|value| value.as_integer())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn tracks_visits_of_nodes_for_scripts() {
+        let mut program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::DetourToNode(DetourToNodeInstruction {
+                    node_name: "Shop".to_owned(),
+                }),
+                InstructionType::PushString(PushStringInstruction {
+                    value: "Shop".to_owned(),
+                }),
+                InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "times_visited".to_owned(),
+                }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$shop_visits".to_owned(),
+                }),
+                InstructionType::PushString(PushStringInstruction {
+                    value: "Start".to_owned(),
+                }),
+                InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "last_visited".to_owned(),
+                }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$start_visited".to_owned(),
+                }),
+                InstructionType::Stop(StopInstruction {}),
+            ],
+        );
+        program.nodes.extend(
+            program_with_instructions("Shop", [InstructionType::Return(ReturnInstruction {})])
+                .nodes,
+        );
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program)
+            .set_node_statistics_tracking(true)
+            .set_node("Start")
+            .unwrap();
+        dialogue.continue_().unwrap();
+
+        assert!(matches!(
+            dialogue.variable("$shop_visits").unwrap(),
+            YarnValue::Integer(1)
+        ));
+        // Start was entered at 1, Shop at 2, and Start again when returning from the detour at 3
+        assert!(matches!(
+            dialogue.variable("$start_visited").unwrap(),
+            YarnValue::Integer(3)
+        ));
+        assert!(matches!(
+            dialogue.variable(&entered_variable("Start")).unwrap(),
+            YarnValue::Integer(2)
+        ));
+        assert_eq!(
+            NodeStatistics {
+                times_entered: 2,
+                times_exited: 1,
+                last_visited: Some(3),
+            },
+            dialogue.node_statistics("Start")
+        );
+        assert_eq!(
+            NodeStatistics::default(),
+            dialogue.node_statistics("Forest")
+        );
+    }
+
+    #[test]
+    fn functions_fail_while_tracking_is_disabled() {
+        let storage = MemoryVariableStorage::new();
+        let mut dialogue = Dialogue::new(Box::new(storage.clone()));
+        assert!(!dialogue.node_statistics_tracking());
+        dialogue
+            .add_program(program_with_instructions(
+                "Start",
+                [
+                    InstructionType::PushString(PushStringInstruction {
+                        value: "Start".to_owned(),
+                    }),
+                    InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+                    InstructionType::CallFunc(CallFunctionInstruction {
+                        function_name: "times_visited".to_owned(),
+                    }),
+                    InstructionType::Stop(StopInstruction {}),
+                ],
+            ))
+            .set_node("Start")
+            .unwrap();
+
+        assert!(matches!(
+            dialogue.continue_(),
+            Err(DialogueError::FunctionFailed { function_name, .. }) if function_name == "times_visited"
+        ));
+        assert!(storage.variables().is_empty());
+        assert_eq!(NodeStatistics::default(), dialogue.node_statistics("Start"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Not part of the original implementation.
+//!
+//! Lets analytics, auto-subtitling or accessibility layers passively watch the events of a [`Dialogue`]
+//! without owning the loop that calls [`Dialogue::continue_`].
+
+use crate::prelude::*;
+use core::fmt::Debug;
+
+/// Identifies a [`DialogueObserver`] registered via [`Dialogue::add_observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DialogueObserverId(pub usize);
+
+/// Passively receives every [`DialogueEvent`] a [`Dialogue`] returns, registered via [`Dialogue::add_observer`].
+///
+/// Observers are notified in the order they were added, before the events are returned to the caller.
+/// All methods do nothing by default, so implement only the ones you need.
+/// Override [`DialogueObserver::on_event`] instead to receive the events unchanged.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// #[derive(Debug, Default)]
+/// struct LineCounter(Arc<AtomicUsize>);
+///
+/// impl DialogueObserver for LineCounter {
+///     fn line_presented(&self, _line_id: u32) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let lines = Arc::new(AtomicUsize::new(0));
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// let id = dialogue.add_observer(LineCounter(lines.clone()));
+/// // Continue the dialogue as usual
+/// assert!(dialogue.remove_observer(id));
+/// ```
+pub trait DialogueObserver: Debug + Send + Sync {
+    /// Called for every event. By default, this calls the method matching the event.
+    fn on_event(&self, event: &DialogueEvent) {
+        match event {
+            DialogueEvent::Line(line_id, ..) => self.line_presented(*line_id),
+            DialogueEvent::LineInterrupted(line_id) => self.line_interrupted(*line_id),
+            DialogueEvent::LineFiltered(record) => self.line_filtered(record),
+            DialogueEvent::Options(options) => self.options_presented(options),
+            DialogueEvent::Command(command) => self.command_run(command),
+            DialogueEvent::NodeStart(node_name) => self.node_entered(node_name),
+            DialogueEvent::NodeComplete(node_name) => self.node_exited(node_name),
+            DialogueEvent::DialogueComplete => self.dialogue_completed(),
+            DialogueEvent::LineHints(_)
+            | DialogueEvent::Wait(_)
+            | DialogueEvent::BreakpointHit(_) => {}
+        }
+    }
+
+    /// A [`DialogueEvent::Line`] with the given ID is presented.
+    fn line_presented(&self, _line_id: u32) {}
+
+    /// The presentation of the line with the given ID was interrupted, see [`DialogueEvent::LineInterrupted`].
+    fn line_interrupted(&self, _line_id: u32) {}
+
+    /// A rule of the [`ContentFilter`] was applied to a line, see [`DialogueEvent::LineFiltered`].
+    fn line_filtered(&self, _record: &LineFiltered) {}
+
+    /// A [`DialogueEvent::Options`] is presented.
+    fn options_presented(&self, _options: &[DialogueOption]) {}
+
+    /// A [`DialogueEvent::Command`] is run.
+    fn command_run(&self, _command: &Command) {}
+
+    /// The node with the given name was entered, see [`DialogueEvent::NodeStart`].
+    fn node_entered(&self, _node_name: &str) {}
+
+    /// The node with the given name was exited, see [`DialogueEvent::NodeComplete`].
+    fn node_exited(&self, _node_name: &str) {}
+
+    /// The dialogue was completed, see [`DialogueEvent::DialogueComplete`].
+    fn dialogue_completed(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_lines;
+    use crate::sync::Mutex;
+    use alloc::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct Transcript(Arc<Mutex<Vec<String>>>);
+
+    impl DialogueObserver for Transcript {
+        fn line_presented(&self, line_id: u32) {
+            self.0.lock().push(format!("line {line_id}"));
+        }
+
+        fn node_entered(&self, node_name: &str) {
+            self.0.lock().push(format!("enter {node_name}"));
+        }
+
+        fn node_exited(&self, node_name: &str) {
+            self.0.lock().push(format!("exit {node_name}"));
+        }
+
+        fn dialogue_completed(&self) {
+            self.0.lock().push("complete".to_owned());
+        }
+    }
+
+    #[test]
+    fn notifies_every_observer() {
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(Vec::new()));
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_lines("Start", [1, 2]));
+        dialogue.add_observer(Transcript(first.clone()));
+        let second_id = dialogue.add_observer(Transcript(second.clone()));
+
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+        assert!(dialogue.remove_observer(second_id));
+        assert!(!dialogue.remove_observer(second_id));
+        dialogue.continue_().unwrap();
+        dialogue.continue_().unwrap();
+
+        assert_eq!(
+            vec!["enter Start", "line 1", "line 2", "exit Start", "complete"],
+            *first.lock()
+        );
+        assert_eq!(vec!["enter Start", "line 1"], *second.lock());
+    }
+}
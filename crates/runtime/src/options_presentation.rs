@@ -0,0 +1,186 @@
+//! Not part of the original implementation.
+//!
+//! Reorders and limits the options of a [`DialogueEvent::Options`] before they are presented,
+//! so games don't have to map the position of an option back to its [`OptionId`] themselves.
+
+use crate::prelude::*;
+use crate::sync::Mutex;
+use alloc::sync::Arc;
+
+/// How the options of a [`DialogueEvent::Options`] are ordered and how many are presented, set via [`Dialogue::set_options_presentation_policy`].
+///
+/// The policy is applied before the options are sent, and their [`DialogueOption::id`]s are renumbered to match the new order,
+/// so selecting an option via [`Dialogue::set_selected_option`] always runs the option the player saw. Use [`DialogueOption::tag_id`]
+/// to identify an option independent of its position.
+///
+/// The steps are applied in this order:
+/// 1. The options are shuffled, if enabled via [`OptionsPresentationPolicy::with_shuffle`].
+/// 2. Unavailable options are moved behind the available ones, if enabled via [`OptionsPresentationPolicy::with_unavailable_last`].
+/// 3. Options beyond the limit set via [`OptionsPresentationPolicy::with_limit`] are dropped.
+///
+/// The default, [`OptionsPresentationPolicy::stable`], presents the options as written.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.set_options_presentation_policy(
+///     OptionsPresentationPolicy::stable()
+///         .with_shuffle(SimulationRng::new(42))
+///         .with_unavailable_last()
+///         .with_limit(3),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OptionsPresentationPolicy {
+    shuffle: Option<Arc<Mutex<Box<dyn DialogueRng>>>>,
+    unavailable_last: bool,
+    limit: Option<usize>,
+}
+
+impl OptionsPresentationPolicy {
+    /// Presents the options in the order they are written in.
+    pub fn stable() -> Self {
+        Self::default()
+    }
+
+    /// Shuffles the options using a [`DialogueRng`], such as the engine's seeded RNG so that the order is reproducible.
+    /// The RNG is shared with clones of the policy.
+    #[must_use]
+    pub fn with_shuffle(mut self, rng: impl DialogueRng + 'static) -> Self {
+        self.shuffle = Some(Arc::new(Mutex::new(Box::new(rng))));
+        self
+    }
+
+    /// Moves unavailable options, i.e. those whose [`DialogueOption::is_available`] is `false`, behind the available ones,
+    /// keeping the order within both groups.
+    #[must_use]
+    pub fn with_unavailable_last(mut self) -> Self {
+        self.unavailable_last = true;
+        self
+    }
+
+    /// Presents at most `limit` options, but always at least one.
+    #[must_use]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit.max(1));
+        self
+    }
+
+    /// Returns `true` if the options are presented as written.
+    pub fn is_stable(&self) -> bool {
+        self.shuffle.is_none() && !self.unavailable_last && self.limit.is_none()
+    }
+
+    /// Reorders and limits the options, then renumbers their IDs to match their new positions.
+    pub(crate) fn apply<const N: usize>(&self, options: &mut BoundedVec<DialogueOption, N>) {
+        if self.is_stable() {
+            return;
+        }
+        if let Some(rng) = &self.shuffle {
+            let mut rng = rng.lock();
+            // Fisher-Yates
+            for index in (1..options.len()).rev() {
+                let other = (rng.next_u64() % (index as u64 + 1)) as usize;
+                options.swap(index, other);
+            }
+        }
+        if self.unavailable_last {
+            options.sort_by_key(|option| !option.is_available);
+        }
+        if let Some(limit) = self.limit {
+            options.truncate(limit);
+        }
+        for (index, option) in options.iter_mut().enumerate() {
+            option.id = OptionId(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn presents_options_according_to_the_policy() {
+        let add_option = |tag_id, destination, has_condition| {
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id,
+                destination,
+                substitution_count: 0,
+                has_condition,
+            })
+        };
+        let program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushBool(PushBoolInstruction { value: false }),
+                add_option(1, 6, true),
+                add_option(2, 7, false),
+                add_option(3, 8, false),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 10,
+                    substitution_count: 0,
+                }),
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 20,
+                    substitution_count: 0,
+                }),
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 30,
+                    substitution_count: 0,
+                }),
+            ],
+        );
+        let present = |policy: OptionsPresentationPolicy| {
+            let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+            dialogue
+                .add_program(program.clone())
+                .set_options_presentation_policy(policy)
+                .set_node("Start")
+                .unwrap();
+            let events = dialogue.continue_().unwrap();
+            let Some(DialogueEvent::Options(options)) = events.last() else {
+                panic!("Expected options, got {events:?}");
+            };
+            (dialogue, options.clone())
+        };
+        let tags = |options: &[DialogueOption]| -> Vec<_> {
+            options.iter().map(|option| option.tag_id).collect()
+        };
+
+        assert_eq!(
+            vec![1, 2, 3],
+            tags(&present(OptionsPresentationPolicy::stable()).1)
+        );
+
+        let (mut dialogue, options) = present(
+            OptionsPresentationPolicy::stable()
+                .with_unavailable_last()
+                .with_limit(2),
+        );
+        assert_eq!(vec![2, 3], tags(&options));
+        assert_eq!(OptionId(1), options[1].id);
+        dialogue.set_selected_option(options[1].id).unwrap();
+        assert_eq!(
+            Some(&DialogueEvent::Line(30, vec![])),
+            dialogue.continue_().unwrap().first()
+        );
+
+        let shuffled = |seed| {
+            let (_, options) =
+                present(OptionsPresentationPolicy::stable().with_shuffle(SimulationRng::new(seed)));
+            let mut ids: Vec<_> = options.iter().map(|option| option.id.0).collect();
+            ids.sort_unstable();
+            assert_eq!(vec![0, 1, 2], ids);
+            tags(&options)
+        };
+        assert_eq!(shuffled(7), shuffled(7));
+        assert!((0..20).any(|seed| shuffled(seed) != vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,138 @@
+//! Not part of the original implementation.
+//!
+//! Renders lines as plain text with the speaker separated, e.g. for screen readers or text-to-speech.
+
+use crate::markup::{CHARACTER_ATTRIBUTE, CHARACTER_ATTRIBUTE_NAME_PROPERTY};
+use crate::prelude::*;
+
+/// A line rendered by a [`PlainTextRenderer`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlainTextLine {
+    /// The name of the character speaking the line, if the line has a [`CHARACTER_ATTRIBUTE`].
+    pub speaker: Option<String>,
+    /// The text of the line without markup and without the character name.
+    pub text: String,
+    /// The text to announce, i.e. [`PlainTextLine::text`] preceded by the speaker and the separator set via [`PlainTextRenderer::with_speaker_separator`].
+    pub announcement: String,
+}
+
+/// Converts parsed lines into accessible plain text, see [`Dialogue::render_plain_text`].
+///
+/// Since the line is rendered from a [`ParsedMarkup`], replacement markers like `[select]` and `[plural]` are already resolved and all other markup is removed.
+/// In addition, the renderer:
+/// - splits off the character name, e.g. `Mae: Wow!` is rendered as the speaker `Mae` and the text `Wow!`.
+/// - drops the text of attributes hidden via [`PlainTextRenderer::with_hidden_attribute`], e.g. decorative symbols.
+/// - collapses runs of whitespace into a single space and trims the text, as screen readers would otherwise read out line breaks and indentation.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let markup = parse_markup("Mae: Look  at[deco]✦[/deco]\nthat!").unwrap();
+/// let line = PlainTextRenderer::new()
+///     .with_hidden_attribute("deco")
+///     .render(&markup);
+/// assert_eq!(Some("Mae"), line.speaker.as_deref());
+/// assert_eq!("Look at that!", line.text);
+/// assert_eq!("Mae: Look at that!", line.announcement);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlainTextRenderer {
+    speaker_separator: String,
+    hidden_attributes: Vec<String>,
+}
+
+impl Default for PlainTextRenderer {
+    fn default() -> Self {
+        Self {
+            speaker_separator: ": ".to_owned(),
+            hidden_attributes: Vec::new(),
+        }
+    }
+}
+
+impl PlainTextRenderer {
+    /// Creates a renderer that announces lines as `Speaker: text` and hides no attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what is put between the speaker and the text of [`PlainTextLine::announcement`], e.g. `" says: "`.
+    #[must_use]
+    pub fn with_speaker_separator(mut self, separator: impl Into<String>) -> Self {
+        self.speaker_separator = separator.into();
+        self
+    }
+
+    /// Drops the text of all attributes with the given name, e.g. for decorative symbols that make no sense when read aloud.
+    #[must_use]
+    pub fn with_hidden_attribute(mut self, name: impl Into<String>) -> Self {
+        self.hidden_attributes.push(name.into());
+        self
+    }
+
+    /// Renders a parsed line as plain text.
+    pub fn render(&self, markup: &ParsedMarkup) -> PlainTextLine {
+        let character = markup.attribute(CHARACTER_ATTRIBUTE);
+        let speaker = character
+            .and_then(|attribute| attribute.property(CHARACTER_ATTRIBUTE_NAME_PROPERTY))
+            .map(|name| name.to_string());
+
+        let mut markup = match character {
+            Some(character) => markup.delete_range(character),
+            None => markup.clone(),
+        };
+        while let Some(hidden) = markup.attributes.iter().find(|attribute| {
+            attribute.length > 0 && self.hidden_attributes.contains(&attribute.name)
+        }) {
+            markup = markup.delete_range(&hidden.clone());
+        }
+
+        let text = markup.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let announcement = match &speaker {
+            Some(speaker) if !text.is_empty() => {
+                format!("{speaker}{}{text}", self.speaker_separator)
+            }
+            Some(speaker) => speaker.clone(),
+            None => text.clone(),
+        };
+        PlainTextLine {
+            speaker,
+            text,
+            announcement,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_resolved_lines_with_the_speaker_separated() {
+        let dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        let renderer = PlainTextRenderer::new().with_speaker_separator(" says: ");
+        let line = dialogue
+            .render_plain_text(
+                "[b]{0}[/b]: I found [plural value={1} one=\"a [i]cat[/i]\" other=\"% cats\"/]!",
+                &["Sam".into(), YarnValue::Integer(3)],
+                &renderer,
+            )
+            .unwrap();
+        assert_eq!(
+            PlainTextLine {
+                speaker: Some("Sam".to_owned()),
+                text: "I found 3 cats!".to_owned(),
+                announcement: "Sam says: I found 3 cats!".to_owned(),
+            },
+            line
+        );
+
+        let narration = dialogue
+            .render_plain_text("  The door\n creaks.  ", &[], &renderer)
+            .unwrap();
+        assert_eq!(None, narration.speaker);
+        assert_eq!("The door creaks.", narration.announcement);
+    }
+}
@@ -0,0 +1,327 @@
+//! Not part of the original implementation.
+//!
+//! A plain-old-data representation of [`DialogueEvent`]s for engine adapters that copy events into ECS event queues,
+//! where allocating a `String` per event would be wasteful.
+
+use crate::prelude::*;
+use core::time::Duration;
+use hashbrown::HashMap;
+
+/// The ID of a string interned by an [`EventInterner`], e.g. a node name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(transparent)]
+pub struct InternedStringId(pub u32);
+
+/// The ID of a [`Command`] interned by an [`EventInterner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(transparent)]
+pub struct InternedCommandId(pub u32);
+
+/// A [`DialogueEvent`] that is [`Copy`] and borrows nothing, created by [`EventInterner::intern`].
+///
+/// Strings, commands, options and substitutions are replaced by IDs and indices into the [`EventInterner`] that created the event,
+/// which resolves them again via [`EventInterner::resolve`] or its accessors. The layout is `#[repr(C, u8)]`, so it is stable across builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(C, u8)]
+pub enum PodDialogueEvent {
+    /// See [`DialogueEvent::Line`]. The substitutions are retrieved via [`EventInterner::substitutions`].
+    Line {
+        /// The ID of the line.
+        line_id: u32,
+        /// The index of the first substitution in the interner.
+        substitutions_start: u32,
+        /// The number of substitutions.
+        substitutions_len: u32,
+    },
+    /// See [`DialogueEvent::LineHints`].
+    LineHints(LineHints),
+    /// See [`DialogueEvent::Options`]. The options are retrieved via [`EventInterner::options`].
+    Options {
+        /// The index of the first option in the interner.
+        start: u32,
+        /// The number of options.
+        len: u32,
+    },
+    /// See [`DialogueEvent::Command`]. The command is retrieved via [`EventInterner::command`].
+    Command(InternedCommandId),
+    /// See [`DialogueEvent::Wait`].
+    Wait {
+        /// The whole seconds of the duration.
+        secs: u64,
+        /// The fractional part of the duration in nanoseconds.
+        nanos: u32,
+    },
+    /// See [`DialogueEvent::NodeComplete`]. The node name is retrieved via [`EventInterner::string`].
+    NodeComplete(InternedStringId),
+    /// See [`DialogueEvent::NodeStart`]. The node name is retrieved via [`EventInterner::string`].
+    NodeStart(InternedStringId),
+    /// See [`DialogueEvent::BreakpointHit`]. The node name is retrieved via [`EventInterner::string`].
+    BreakpointHit {
+        /// The node of the breakpoint.
+        node_name: InternedStringId,
+        /// Where in the node the breakpoint is.
+        location: BreakpointLocation,
+    },
+    /// See [`DialogueEvent::DialogueComplete`].
+    DialogueComplete,
+    /// See [`DialogueEvent::LineInterrupted`].
+    LineInterrupted(u32),
+    /// See [`DialogueEvent::LineFiltered`]. The hashtag is retrieved via [`EventInterner::string`].
+    LineFiltered {
+        /// The ID of the line the rule was applied to.
+        line_id: u32,
+        /// The hashtag the rule matched.
+        hashtag: InternedStringId,
+        /// What was done with the line.
+        action: LineFilterAction,
+    },
+}
+
+/// Converts [`DialogueEvent`]s into [`PodDialogueEvent`]s and back.
+///
+/// Node names and commands are interned, so each distinct one is only stored once, no matter how often it occurs.
+/// Options are appended to a buffer that grows with every [`DialogueEvent::Options`] until [`EventInterner::clear_options`] is called,
+/// and the substitutions of lines to one that grows with every [`DialogueEvent::Line`] until [`EventInterner::clear_substitutions`] is called.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut interner = EventInterner::new();
+/// let first = interner.intern(DialogueEvent::NodeStart("Start".into()));
+/// let second = interner.intern(DialogueEvent::NodeStart("Start".into()));
+/// assert_eq!(first, second);
+///
+/// let PodDialogueEvent::NodeStart(id) = first else { unreachable!() };
+/// assert_eq!(Some("Start"), interner.string(id));
+/// assert_eq!(Some(DialogueEvent::NodeStart("Start".into())), interner.resolve(first));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventInterner {
+    strings: Vec<String>,
+    string_ids: HashMap<String, InternedStringId>,
+    commands: Vec<Command>,
+    command_ids: HashMap<String, InternedCommandId>,
+    options: Vec<DialogueOption>,
+    substitutions: Vec<YarnValue>,
+}
+
+impl EventInterner {
+    /// Creates an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts an event into its plain-old-data representation, interning its strings and commands.
+    pub fn intern(&mut self, event: DialogueEvent) -> PodDialogueEvent {
+        match event {
+            DialogueEvent::Line(line_id, substitutions) => {
+                let substitutions_start = self.substitutions.len() as u32;
+                let substitutions_len = substitutions.len() as u32;
+                self.substitutions.extend(substitutions);
+                PodDialogueEvent::Line {
+                    line_id,
+                    substitutions_start,
+                    substitutions_len,
+                }
+            }
+            DialogueEvent::LineHints(hints) => PodDialogueEvent::LineHints(hints),
+            DialogueEvent::Options(options) => {
+                let start = self.options.len() as u32;
+                let len = options.len() as u32;
+                self.options.extend(options);
+                PodDialogueEvent::Options { start, len }
+            }
+            DialogueEvent::Command(command) => {
+                PodDialogueEvent::Command(self.intern_command(command))
+            }
+            DialogueEvent::Wait(duration) => PodDialogueEvent::Wait {
+                secs: duration.as_secs(),
+                nanos: duration.subsec_nanos(),
+            },
+            DialogueEvent::NodeComplete(node_name) => {
+                PodDialogueEvent::NodeComplete(self.intern_string(&node_name))
+            }
+            DialogueEvent::NodeStart(node_name) => {
+                PodDialogueEvent::NodeStart(self.intern_string(&node_name))
+            }
+            DialogueEvent::BreakpointHit(breakpoint) => PodDialogueEvent::BreakpointHit {
+                node_name: self.intern_string(&breakpoint.node_name),
+                location: breakpoint.location,
+            },
+            DialogueEvent::DialogueComplete => PodDialogueEvent::DialogueComplete,
+            DialogueEvent::LineInterrupted(line_id) => PodDialogueEvent::LineInterrupted(line_id),
+            DialogueEvent::LineFiltered(record) => PodDialogueEvent::LineFiltered {
+                line_id: record.line_id,
+                hashtag: self.intern_string(&record.hashtag),
+                action: record.action,
+            },
+        }
+    }
+
+    /// Converts a batch of events, e.g. the result of [`Dialogue::continue_`].
+    pub fn intern_all(
+        &mut self,
+        events: impl IntoIterator<Item = DialogueEvent>,
+    ) -> Vec<PodDialogueEvent> {
+        events.into_iter().map(|event| self.intern(event)).collect()
+    }
+
+    /// Interns a string and returns its ID. Interning the same string again returns the same ID.
+    pub fn intern_string(&mut self, string: &str) -> InternedStringId {
+        if let Some(id) = self.string_ids.get(string) {
+            return *id;
+        }
+        let id = InternedStringId(self.strings.len() as u32);
+        self.strings.push(string.to_owned());
+        self.string_ids.insert(string.to_owned(), id);
+        id
+    }
+
+    fn intern_command(&mut self, command: Command) -> InternedCommandId {
+        if let Some(id) = self.command_ids.get(&command.raw) {
+            return *id;
+        }
+        let id = InternedCommandId(self.commands.len() as u32);
+        self.command_ids.insert(command.raw.clone(), id);
+        self.commands.push(command);
+        id
+    }
+
+    /// Gets an interned string, e.g. the node name of a [`PodDialogueEvent::NodeStart`].
+    pub fn string(&self, id: InternedStringId) -> Option<&str> {
+        self.strings.get(id.0 as usize).map(String::as_str)
+    }
+
+    /// Gets the command of a [`PodDialogueEvent::Command`].
+    pub fn command(&self, id: InternedCommandId) -> Option<&Command> {
+        self.commands.get(id.0 as usize)
+    }
+
+    /// Gets the options of a [`PodDialogueEvent::Options`]. Returns `None` if they were cleared via [`EventInterner::clear_options`].
+    pub fn options(&self, start: u32, len: u32) -> Option<&[DialogueOption]> {
+        let start = start as usize;
+        self.options.get(start..start.checked_add(len as usize)?)
+    }
+
+    /// Gets the substitutions of a [`PodDialogueEvent::Line`]. Returns `None` if they were cleared via [`EventInterner::clear_substitutions`].
+    pub fn substitutions(&self, start: u32, len: u32) -> Option<&[YarnValue]> {
+        if len == 0 {
+            return Some(&[]);
+        }
+        let start = start as usize;
+        self.substitutions
+            .get(start..start.checked_add(len as usize)?)
+    }
+
+    /// Discards all interned options, e.g. once an option was selected. [`PodDialogueEvent::Options`] created before can no longer be resolved.
+    /// Interned strings and commands are kept.
+    pub fn clear_options(&mut self) {
+        self.options.clear();
+    }
+
+    /// Discards all interned substitutions, e.g. once the lines they belong to were presented.
+    /// [`PodDialogueEvent::Line`]s with substitutions created before can no longer be resolved.
+    pub fn clear_substitutions(&mut self) {
+        self.substitutions.clear();
+    }
+
+    /// Converts a plain-old-data event back into the [`DialogueEvent`] it was created from.
+    /// Returns `None` if the event was created by another interner or its options or substitutions were cleared.
+    pub fn resolve(&self, event: PodDialogueEvent) -> Option<DialogueEvent> {
+        let event = match event {
+            PodDialogueEvent::Line {
+                line_id,
+                substitutions_start,
+                substitutions_len,
+            } => DialogueEvent::Line(
+                line_id,
+                self.substitutions(substitutions_start, substitutions_len)?
+                    .to_vec(),
+            ),
+            PodDialogueEvent::LineHints(hints) => DialogueEvent::LineHints(hints),
+            PodDialogueEvent::Options { start, len } => {
+                DialogueEvent::Options(self.options(start, len)?.to_vec())
+            }
+            PodDialogueEvent::Command(id) => DialogueEvent::Command(self.command(id)?.clone()),
+            PodDialogueEvent::Wait { secs, nanos } => {
+                DialogueEvent::Wait(Duration::new(secs, nanos))
+            }
+            PodDialogueEvent::NodeComplete(id) => {
+                DialogueEvent::NodeComplete(self.string(id)?.into())
+            }
+            PodDialogueEvent::NodeStart(id) => DialogueEvent::NodeStart(self.string(id)?.into()),
+            PodDialogueEvent::BreakpointHit {
+                node_name,
+                location,
+            } => DialogueEvent::BreakpointHit(Breakpoint::new(self.string(node_name)?, location)),
+            PodDialogueEvent::DialogueComplete => DialogueEvent::DialogueComplete,
+            PodDialogueEvent::LineInterrupted(line_id) => DialogueEvent::LineInterrupted(line_id),
+            PodDialogueEvent::LineFiltered {
+                line_id,
+                hashtag,
+                action,
+            } => DialogueEvent::LineFiltered(LineFiltered {
+                line_id,
+                hashtag: self.string(hashtag)?.into(),
+                action,
+            }),
+        };
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_interned_events() {
+        let option = DialogueOption {
+            tag_id: 3,
+            id: OptionId(0),
+            destination_node: 7,
+            is_available: true,
+            substitutions: vec![],
+            #[cfg(feature = "condition_explanations")]
+            unavailability_reason: None,
+        };
+        let events = vec![
+            DialogueEvent::NodeStart("Start".into()),
+            DialogueEvent::Line(1, vec![YarnValue::Integer(3), "Ada".into()]),
+            DialogueEvent::Command(Command::parse("shake camera 2".to_owned()).unwrap()),
+            DialogueEvent::Wait(Duration::from_millis(1500)),
+            DialogueEvent::Options(vec![option.clone(), option]),
+            DialogueEvent::Command(Command::parse("shake camera 2".to_owned()).unwrap()),
+            DialogueEvent::NodeComplete("Start".into()),
+            DialogueEvent::DialogueComplete,
+        ];
+        let mut interner = EventInterner::new();
+        let pod_events = interner.intern_all(events.clone());
+
+        assert_eq!(pod_events[2], pod_events[5]);
+        assert_eq!(
+            PodDialogueEvent::Options { start: 0, len: 2 },
+            pod_events[4]
+        );
+        assert_eq!(
+            Some(events),
+            pod_events
+                .iter()
+                .map(|event| interner.resolve(*event))
+                .collect::<Option<Vec<_>>>()
+        );
+
+        interner.clear_options();
+        interner.clear_substitutions();
+        assert_eq!(None, interner.resolve(pod_events[4]));
+        assert_eq!(None, interner.resolve(pod_events[1]));
+        assert_eq!(
+            Some(DialogueEvent::NodeStart("Start".into())),
+            interner.resolve(pod_events[0])
+        );
+    }
+}
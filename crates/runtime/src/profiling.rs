@@ -0,0 +1,166 @@
+//! Not part of the original implementation.
+//!
+//! Collects statistics about the instructions run by a [`Dialogue`], e.g. to find nodes that call expensive functions or loop for a long time.
+//! Requires the `vm_profiling` feature.
+
+use crate::prelude::*;
+use core::time::Duration;
+use hashbrown::HashMap;
+
+/// Statistics about the execution of a [`Dialogue`], retrieved via [`Dialogue::profile_report`].
+/// The statistics accumulate over all nodes run since the dialogue was created or [`Dialogue::reset_profile`] was called.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # let dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// let report = dialogue.profile_report();
+/// for (node_name, profile) in report.nodes_by_instruction_count() {
+///     println!("{node_name}: {} instructions in {:?}", profile.instruction_count, profile.time);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProfileReport {
+    /// The statistics per node, keyed by node name.
+    pub nodes: HashMap<String, NodeProfile>,
+    /// How often each function was called via the `CallFunc` instruction, keyed by function name.
+    pub function_calls: HashMap<String, u64>,
+    /// The largest number of values that were on the VM's stack at once.
+    pub stack_high_water_mark: usize,
+}
+
+/// The statistics of a single node in a [`ProfileReport`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeProfile {
+    /// The number of instructions of this node that were executed.
+    pub instruction_count: u64,
+    /// The number of functions called by this node.
+    pub function_call_count: u64,
+    /// The time spent executing the instructions of this node. Always zero without the `std` feature.
+    ///
+    /// This includes the time spent in functions called by the node, but not the time the dialogue spent waiting for the game,
+    /// e.g. between [`DialogueEvent::Line`]s.
+    pub time: Duration,
+}
+
+impl ProfileReport {
+    /// Returns the profiled nodes, ordered by the number of executed instructions, highest first.
+    pub fn nodes_by_instruction_count(&self) -> Vec<(&str, &NodeProfile)> {
+        let mut nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile))
+            .collect();
+        nodes.sort_by(|(a_name, a), (b_name, b)| {
+            b.instruction_count
+                .cmp(&a.instruction_count)
+                .then_with(|| a_name.cmp(b_name))
+        });
+        nodes
+    }
+
+    /// Returns the profiled nodes, ordered by the time spent in them, highest first.
+    pub fn nodes_by_time(&self) -> Vec<(&str, &NodeProfile)> {
+        let mut nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile))
+            .collect();
+        nodes.sort_by(|(a_name, a), (b_name, b)| {
+            b.time.cmp(&a.time).then_with(|| a_name.cmp(b_name))
+        });
+        nodes
+    }
+
+    pub(crate) fn record_instruction(
+        &mut self,
+        node_name: &str,
+        time: Duration,
+        stack_depth: usize,
+    ) {
+        let profile = self.node_mut(node_name);
+        profile.instruction_count += 1;
+        profile.time += time;
+        self.stack_high_water_mark = self.stack_high_water_mark.max(stack_depth);
+    }
+
+    pub(crate) fn record_function_call(&mut self, node_name: &str, function_name: &str) {
+        self.node_mut(node_name).function_call_count += 1;
+        match self.function_calls.get_mut(function_name) {
+            Some(count) => *count += 1,
+            None => {
+                self.function_calls.insert(function_name.to_owned(), 1);
+            }
+        }
+    }
+
+    fn node_mut(&mut self, node_name: &str) -> &mut NodeProfile {
+        // Avoid allocating the name for every instruction
+        if !self.nodes.contains_key(node_name) {
+            self.nodes
+                .insert(node_name.to_owned(), NodeProfile::default());
+        }
+        self.nodes.get_mut(node_name).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn counts_instructions_and_function_calls() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        let call_number = [
+            InstructionType::PushFloat(PushFloatInstruction { value: 2.0 }),
+            InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+            InstructionType::CallFunc(CallFunctionInstruction {
+                function_name: "number".to_owned(),
+            }),
+            InstructionType::Pop(PopInstruction {}),
+        ];
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            call_number
+                .iter()
+                .cloned()
+                .chain(call_number.iter().cloned())
+                .chain([InstructionType::DetourToNode(DetourToNodeInstruction {
+                    node_name: "Other".to_owned(),
+                })]),
+        ));
+        dialogue.add_program(program_with_instructions(
+            "Other",
+            call_number
+                .iter()
+                .cloned()
+                .chain([InstructionType::Stop(StopInstruction {})]),
+        ));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+
+        let report = dialogue.profile_report();
+        assert_eq!(Some(&3), report.function_calls.get("number"));
+        assert_eq!(2, report.stack_high_water_mark);
+        let nodes = report.nodes_by_instruction_count();
+        assert_eq!("Start", nodes[0].0);
+        assert_eq!(9, nodes[0].1.instruction_count);
+        assert_eq!(2, nodes[0].1.function_call_count);
+        assert_eq!(
+            ("Other", 5, 1),
+            (
+                nodes[1].0,
+                nodes[1].1.instruction_count,
+                nodes[1].1.function_call_count
+            )
+        );
+
+        dialogue.reset_profile();
+        assert_eq!(&ProfileReport::default(), dialogue.profile_report());
+    }
+}
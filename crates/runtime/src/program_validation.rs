@@ -0,0 +1,418 @@
+//! Not part of the original implementation.
+//!
+//! Finds calls to functions that are missing from the [`Library`] or are called with the wrong number of parameters
+//! right after loading a program, instead of failing with [`DialogueError::FunctionNotFound`] in front of the player.
+//! Commands are checked against the schemas of a [`CommandRegistry`] the same way.
+
+use crate::prelude::*;
+use core::error::Error;
+use core::fmt::{self, Display};
+use yarnspinner_core::types::TypedValue;
+
+/// A problem with a function call or command of a loaded program, see [`Dialogue::validate_program`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ProgramValidationIssue {
+    /// The called function is neither part of the [`Library`] nor provided by the dialogue itself, like `visited`.
+    FunctionNotFound {
+        /// The `CallFunc` instruction.
+        location: InstructionLocation,
+        /// The name of the called function.
+        function_name: String,
+    },
+    /// The function is called with a different number of parameters than it takes.
+    ParameterCountMismatch {
+        /// The `CallFunc` instruction.
+        location: InstructionLocation,
+        /// The name of the called function.
+        function_name: String,
+        /// The number of parameters the function takes.
+        expected: usize,
+        /// The number of parameters the program passes.
+        actual: usize,
+    },
+    /// The command is not part of the [`CommandRegistry`].
+    UnknownCommand {
+        /// The `RunCommand` instruction.
+        location: InstructionLocation,
+        /// The name of the command.
+        command_name: String,
+    },
+    /// The command is called with fewer or more parameters than its [`CommandSchema`] allows.
+    CommandParameterCountMismatch {
+        /// The `RunCommand` instruction.
+        location: InstructionLocation,
+        /// The name of the command.
+        command_name: String,
+        /// The number of required parameters of the command.
+        min: usize,
+        /// The number of required and optional parameters of the command.
+        max: usize,
+        /// The number of parameters the program passes.
+        actual: usize,
+    },
+    /// A parameter of the command does not have the type required by its [`CommandSchema`].
+    CommandParameterTypeMismatch {
+        /// The `RunCommand` instruction.
+        location: InstructionLocation,
+        /// The name of the command.
+        command_name: String,
+        /// The name of the parameter as given by the [`CommandParameter`].
+        parameter_name: String,
+        /// The type the parameter must have.
+        expected: Type,
+        /// The type of the parameter the program passes.
+        actual: Type,
+    },
+}
+
+impl ProgramValidationIssue {
+    /// The `CallFunc` or `RunCommand` instruction the issue was found at.
+    pub fn location(&self) -> &InstructionLocation {
+        match self {
+            Self::FunctionNotFound { location, .. }
+            | Self::ParameterCountMismatch { location, .. }
+            | Self::UnknownCommand { location, .. }
+            | Self::CommandParameterCountMismatch { location, .. }
+            | Self::CommandParameterTypeMismatch { location, .. } => location,
+        }
+    }
+}
+
+impl Display for ProgramValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FunctionNotFound {
+                location,
+                function_name,
+            } => write!(
+                f,
+                "{}, instruction {}: The function \"{function_name}\" is not in the library",
+                location.node_name, location.instruction
+            ),
+            Self::ParameterCountMismatch {
+                location,
+                function_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}, instruction {}: The function \"{function_name}\" takes {expected} parameters, but is called with {actual}",
+                location.node_name, location.instruction
+            ),
+            Self::UnknownCommand {
+                location,
+                command_name,
+            } => write!(
+                f,
+                "{}, instruction {}: The command \"{command_name}\" is not in the command registry",
+                location.node_name, location.instruction
+            ),
+            Self::CommandParameterCountMismatch {
+                location,
+                command_name,
+                min,
+                max,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "{}, instruction {}: The command \"{command_name}\" takes ",
+                    location.node_name, location.instruction
+                )?;
+                if min == max {
+                    write!(f, "{min}")?;
+                } else {
+                    write!(f, "{min} to {max}")?;
+                }
+                write!(f, " parameters, but is called with {actual}")
+            }
+            Self::CommandParameterTypeMismatch {
+                location,
+                command_name,
+                parameter_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}, instruction {}: The parameter \"{parameter_name}\" of the command \"{command_name}\" must be a {expected}, but is a {actual}",
+                location.node_name, location.instruction
+            ),
+        }
+    }
+}
+
+/// All [`ProgramValidationIssue`]s of the loaded program, ordered by node name and instruction. Created by [`Dialogue::validate_program`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProgramValidationReport {
+    /// The issues found.
+    pub issues: Vec<ProgramValidationIssue>,
+}
+
+impl ProgramValidationReport {
+    /// Returns `true` if no issues were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Error for ProgramValidationReport {}
+
+impl Display for ProgramValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Found {} invalid function calls and commands",
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n- {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every `CallFunc` instruction against the functions returned by `lookup`.
+pub(crate) fn validate_function_calls<'a>(
+    program: &LinkedProgram,
+    lookup: impl Fn(&str) -> Option<&'a dyn UntypedYarnFn>,
+) -> ProgramValidationReport {
+    let mut nodes: Vec<_> = program.nodes().collect();
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut issues = Vec::new();
+    for node in nodes {
+        for (index, instruction) in node.instructions.iter().enumerate() {
+            let LinkedInstruction::CallFunc { function_name } = *instruction else {
+                continue;
+            };
+            let function_name = program.string(function_name);
+            let location = InstructionLocation {
+                node_name: node.name.to_string(),
+                instruction: index,
+            };
+            let Some(function) = lookup(function_name) else {
+                issues.push(ProgramValidationIssue::FunctionNotFound {
+                    location,
+                    function_name: function_name.to_string(),
+                });
+                continue;
+            };
+            // The compiler pushes the number of parameters right before the call
+            let actual = match index.checked_sub(1).map(|index| node.instructions[index]) {
+                Some(LinkedInstruction::PushFloat(count)) => count as usize,
+                _ => continue,
+            };
+            let expected = function.parameter_types().len();
+            if expected != actual {
+                issues.push(ProgramValidationIssue::ParameterCountMismatch {
+                    location,
+                    function_name: function_name.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    ProgramValidationReport { issues }
+}
+
+/// Checks every `RunCommand` instruction against the schemas of the registry.
+///
+/// Parameters that contain inline expressions, like `{$delay}` in `<<wait {$delay}>>`, are only known at runtime, so their type is not checked.
+pub(crate) fn validate_commands(
+    program: &LinkedProgram,
+    registry: &CommandRegistry,
+) -> ProgramValidationReport {
+    let mut nodes: Vec<_> = program.nodes().collect();
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut issues = Vec::new();
+    for node in nodes {
+        for (index, instruction) in node.instructions.iter().enumerate() {
+            let LinkedInstruction::RunCommand { command_text, .. } = *instruction else {
+                continue;
+            };
+            let Some(command) = Command::parse(program.string(command_text).to_string()) else {
+                continue;
+            };
+            let location = InstructionLocation {
+                node_name: node.name.to_string(),
+                instruction: index,
+            };
+            let Some(schema) = registry.get(&command.name) else {
+                issues.push(ProgramValidationIssue::UnknownCommand {
+                    location,
+                    command_name: command.name,
+                });
+                continue;
+            };
+            let count = schema.parameter_count();
+            if !count.contains(&command.parameters.len()) {
+                issues.push(ProgramValidationIssue::CommandParameterCountMismatch {
+                    location,
+                    command_name: command.name,
+                    min: *count.start(),
+                    max: *count.end(),
+                    actual: command.parameters.len(),
+                });
+                continue;
+            }
+            for (parameter, value) in schema.parameters.iter().zip(&command.parameters) {
+                let actual = value.r#type();
+                let is_substituted = matches!(value, YarnValue::String(text) if text.contains('{'));
+                if matches!(parameter.r#type, Type::Any | Type::String)
+                    || parameter.r#type == actual
+                    || is_substituted
+                {
+                    continue;
+                }
+                issues.push(ProgramValidationIssue::CommandParameterTypeMismatch {
+                    location: location.clone(),
+                    command_name: command.name.clone(),
+                    parameter_name: parameter.name.clone(),
+                    expected: parameter.r#type.clone(),
+                    actual,
+                });
+            }
+        }
+    }
+    ProgramValidationReport { issues }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn reports_missing_functions_and_wrong_parameter_counts() {
+        let call = |function_name: &str, parameter_count: f32| {
+            [
+                InstructionType::PushFloat(PushFloatInstruction {
+                    value: parameter_count,
+                }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: function_name.to_owned(),
+                }),
+            ]
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.library_mut().add_function("gold", || 10);
+        dialogue
+            .add_program(program_with_instructions(
+                "Start",
+                [
+                    call("gold", 0.0),
+                    call("visited", 1.0),
+                    call("gold", 1.0),
+                    call("reputation", 0.0),
+                ]
+                .concat(),
+            ))
+            .add_program(program_with_instructions("Shop", call("prices", 2.0)));
+
+        let report = dialogue.validate_program();
+        let location = |node_name: &str, instruction| InstructionLocation {
+            node_name: node_name.to_owned(),
+            instruction,
+        };
+        assert_eq!(
+            vec![
+                ProgramValidationIssue::FunctionNotFound {
+                    location: location("Shop", 1),
+                    function_name: "prices".to_owned(),
+                },
+                ProgramValidationIssue::ParameterCountMismatch {
+                    location: location("Start", 5),
+                    function_name: "gold".to_owned(),
+                    expected: 0,
+                    actual: 1,
+                },
+                ProgramValidationIssue::FunctionNotFound {
+                    location: location("Start", 7),
+                    function_name: "reputation".to_owned(),
+                },
+            ],
+            report.issues
+        );
+        assert!(!report.is_valid());
+
+        dialogue
+            .library_mut()
+            .add_function("reputation", || 0)
+            .add_function("prices", |_: f32, _: f32| 1.0)
+            .add_function("gold", |_: f32| 10);
+        // Now the first call passes too few parameters
+        let locations: Vec<_> = dialogue
+            .validate_program()
+            .issues
+            .iter()
+            .map(|issue| issue.location().clone())
+            .collect();
+        assert_eq!(vec![location("Start", 1)], locations);
+    }
+
+    #[test]
+    fn reports_unknown_commands_and_wrong_parameters() {
+        let command = |command_text: &str| {
+            InstructionType::RunCommand(RunCommandInstruction {
+                command_text: command_text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                command("wait 1.5"),
+                command("wait {0}"),
+                command("set_sprite ship happy"),
+                command("set_sprite ship happy true"),
+                command("wait"),
+                command("shake_camera strong"),
+                command("jump"),
+            ],
+        ));
+        assert!(dialogue.validate_program().is_valid());
+
+        let mut registry = CommandRegistry::new();
+        registry
+            .register(
+                CommandSchema::new("set_sprite")
+                    .with_parameter("character", Type::String)
+                    .with_parameter("sprite", Type::String)
+                    .with_optional_parameter("fade", Type::Boolean),
+            )
+            .register(CommandSchema::new("shake_camera").with_parameter("strength", Type::Number));
+        dialogue.set_command_registry(Some(registry));
+
+        let location = |instruction| InstructionLocation {
+            node_name: "Start".to_owned(),
+            instruction,
+        };
+        assert_eq!(
+            vec![
+                ProgramValidationIssue::CommandParameterCountMismatch {
+                    location: location(4),
+                    command_name: "wait".to_owned(),
+                    min: 1,
+                    max: 1,
+                    actual: 0,
+                },
+                ProgramValidationIssue::CommandParameterTypeMismatch {
+                    location: location(5),
+                    command_name: "shake_camera".to_owned(),
+                    parameter_name: "strength".to_owned(),
+                    expected: Type::Number,
+                    actual: Type::String,
+                },
+                ProgramValidationIssue::UnknownCommand {
+                    location: location(6),
+                    command_name: "jump".to_owned(),
+                },
+            ],
+            dialogue.validate_program().issues
+        );
+    }
+}
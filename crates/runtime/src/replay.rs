@@ -0,0 +1,265 @@
+//! Not part of the original implementation.
+//!
+//! Records the inputs that drive a [`Dialogue`] so that a session can be reproduced exactly, e.g. from a bug report of a playtester.
+
+use crate::prelude::*;
+use crate::Result;
+use hashbrown::HashMap;
+
+/// An input recorded in a [`ReplayLog`], in the order it was given to the [`Dialogue`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ReplayEntry {
+    /// [`Dialogue::set_node`] was called.
+    SetNode(String),
+    /// [`Dialogue::jump_to_node`] was called.
+    JumpToNode(String),
+    /// [`Dialogue::detour_to_node`] was called.
+    DetourToNode(String),
+    /// [`Dialogue::continue_`] or [`Dialogue::continue_with_budget`] was called, also via [`Dialogue::tick`] or [`Dialogue::fast_forward`].
+    Continue {
+        /// The budget passed to [`Dialogue::continue_with_budget`], or `None` for [`Dialogue::continue_`].
+        max_instructions: Option<usize>,
+    },
+    /// [`Dialogue::set_selected_option`] was called.
+    OptionSelected(OptionId),
+    /// [`Dialogue::set_variable`] was called.
+    VariableSet {
+        /// The canonical name of the variable.
+        name: String,
+        /// The new value.
+        value: YarnValue,
+    },
+    /// [`Dialogue::step_back`] was called and stepped back.
+    SteppedBack,
+    /// [`Dialogue::stop`] was called.
+    Stopped,
+    /// A function set via [`Dialogue::set_non_deterministic_functions`] returned a value.
+    FunctionReturned {
+        /// The name of the function.
+        function_name: String,
+        /// The value it returned.
+        value: YarnValue,
+    },
+    /// The [`SaliencyStrategy`] selected the candidate with the given index of a line group or node group, or none of them.
+    ContentSelected(Option<usize>),
+}
+
+impl ReplayEntry {
+    /// Returns `true` for entries that are produced while the dialogue runs instead of being passed in by the caller.
+    fn is_runtime_input(&self) -> bool {
+        matches!(
+            self,
+            ReplayEntry::FunctionReturned { .. } | ReplayEntry::ContentSelected(_)
+        )
+    }
+}
+
+/// The nondeterministic inputs of a [`Dialogue`] session, recorded via [`Dialogue::record_replay`] and reproduced via [`Dialogue::replay`].
+///
+/// The log holds the variables at the start of the recording and every input since, but none of the resulting events,
+/// since those follow from the inputs. Changes made directly through [`Dialogue::variable_storage_mut`] are not recorded,
+/// so game code should use [`Dialogue::set_variable`] instead.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReplayLog {
+    variables: HashMap<String, YarnValue>,
+    entries: Vec<ReplayEntry>,
+}
+
+impl ReplayLog {
+    pub(crate) fn new(variables: HashMap<String, YarnValue>) -> Self {
+        Self {
+            variables,
+            entries: Vec::new(),
+        }
+    }
+
+    /// The variables at the start of the recording.
+    pub fn variables(&self) -> &HashMap<String, YarnValue> {
+        &self.variables
+    }
+
+    /// The recorded inputs in the order they were given.
+    pub fn entries(&self) -> &[ReplayEntry] {
+        &self.entries
+    }
+
+    /// The number of recorded inputs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no inputs were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, entry: ReplayEntry) {
+        self.entries.push(entry);
+    }
+}
+
+/// The position of a [`Dialogue::replay`] in its [`ReplayLog`].
+#[derive(Debug, Clone)]
+pub(crate) struct ReplayCursor {
+    entries: Vec<ReplayEntry>,
+    position: usize,
+}
+
+impl ReplayCursor {
+    pub(crate) fn new(log: &ReplayLog) -> Self {
+        Self {
+            entries: log.entries.clone(),
+            position: 0,
+        }
+    }
+
+    /// Takes the next input the caller has to pass to the dialogue, together with its index in the log.
+    pub(crate) fn next_call(&mut self) -> Result<Option<(usize, ReplayEntry)>> {
+        let Some(entry) = self.entries.get(self.position) else {
+            return Ok(None);
+        };
+        if entry.is_runtime_input() {
+            return Err(self.diverged("the recorded dialogue asked for this input here"));
+        }
+        self.position += 1;
+        Ok(Some((self.position - 1, entry.clone())))
+    }
+
+    /// Takes the next input, which must be one produced while the dialogue runs, as checked by `is_expected`.
+    pub(crate) fn next_runtime_input(
+        &mut self,
+        expected: &str,
+        is_expected: impl FnOnce(&ReplayEntry) -> bool,
+    ) -> Result<ReplayEntry> {
+        match self.entries.get(self.position) {
+            Some(entry) if is_expected(entry) => {
+                self.position += 1;
+                Ok(entry.clone())
+            }
+            _ => Err(self.diverged(&format!("the dialogue asked for {expected}"))),
+        }
+    }
+
+    fn diverged(&self, message: &str) -> DialogueError {
+        DialogueError::ReplayDiverged {
+            entry_index: self.position,
+            message: format!(
+                "{message}, but the log has {:?}",
+                self.entries.get(self.position)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use yarnspinner_core::prelude::instruction::*;
+
+    fn program() -> Program {
+        let run_line = |line_id| {
+            InstructionType::RunLine(RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let add_option = |tag_id, destination| {
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id,
+                destination,
+                substitution_count: 0,
+                has_condition: false,
+            })
+        };
+        program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushFloat(PushFloatInstruction { value: 0.0 }),
+                InstructionType::CallFunc(CallFunctionInstruction {
+                    function_name: "dice".to_owned(),
+                }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$roll".to_owned(),
+                }),
+                InstructionType::Pop(PopInstruction {}),
+                run_line(1),
+                add_option(2, 9),
+                add_option(3, 10),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                run_line(4),
+                run_line(5),
+            ],
+        )
+    }
+
+    fn dice_dialogue(rolls: Arc<AtomicU32>) -> Dialogue {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .library_mut()
+            .add_function("dice", move || rolls.fetch_add(1, Ordering::Relaxed) as f32);
+        dialogue
+            .add_program(program())
+            .set_non_deterministic_functions(["dice"]);
+        dialogue
+    }
+
+    #[test]
+    fn replays_recorded_sessions() {
+        let mut dialogue = dice_dialogue(Arc::new(AtomicU32::new(3)));
+        dialogue.record_replay();
+        dialogue.set_variable("$name", "Player").unwrap();
+        dialogue.set_node("Start").unwrap();
+        let mut events = Vec::new();
+        loop {
+            let batch = dialogue.continue_().unwrap();
+            let complete = batch.contains(&DialogueEvent::DialogueComplete);
+            if batch
+                .iter()
+                .any(|event| matches!(event, DialogueEvent::Options(_)))
+            {
+                dialogue.set_selected_option(OptionId(1)).unwrap();
+            }
+            events.extend(batch);
+            if complete {
+                break;
+            }
+        }
+        assert_eq!(YarnValue::from(3.0), dialogue.variable("$roll").unwrap());
+        let log = dialogue.take_replay_log().unwrap();
+        assert!(log.entries().contains(&ReplayEntry::FunctionReturned {
+            function_name: "dice".to_owned(),
+            value: 3.0.into()
+        }));
+
+        // The dice of the playtester rolled differently
+        let mut replayed = dice_dialogue(Arc::new(AtomicU32::new(5)));
+        assert_eq!(events, replayed.replay(&log).unwrap());
+        assert_eq!(YarnValue::from(3.0), replayed.variable("$roll").unwrap());
+        assert_eq!(
+            YarnValue::from("Player"),
+            replayed.variable("$name").unwrap()
+        );
+    }
+
+    #[test]
+    fn detects_diverging_replays() {
+        let mut dialogue = dice_dialogue(Arc::new(AtomicU32::new(0)));
+        dialogue.set_non_deterministic_functions(Vec::<String>::new());
+        dialogue.record_replay();
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+        let log = dialogue.take_replay_log().unwrap();
+
+        let mut replayed = dice_dialogue(Arc::new(AtomicU32::new(0)));
+        assert!(matches!(
+            replayed.replay(&log),
+            Err(DialogueError::ReplayDiverged { entry_index: 2, .. })
+        ));
+    }
+}
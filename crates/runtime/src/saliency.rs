@@ -0,0 +1,146 @@
+//! Not part of the original implementation, but modeled after the content saliency strategies of Yarn Spinner 3,
+//! which select one candidate of a line group or node group.
+//!
+//! ## Implementation notes
+//!
+//! - Yarn Spinner 3's `ContentWasSelected` callback was removed. The [`Dialogue`] tracks view counts itself and passes them to the strategy.
+//! - Strategies are queried through `&self` so that they can be shared like [`DialogueLogger`]s. Strategies with state use interior mutability.
+
+use crate::prelude::*;
+use crate::sync::Mutex;
+use core::fmt::Debug;
+
+/// The kind of content a [`ContentSaliencyOption`] stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ContentSaliencyContentType {
+    /// A line of a line group, i.e. one of several lines starting with `=>`.
+    Line,
+    /// A node of a node group, i.e. one of several nodes with the same title and `when:` headers.
+    Node,
+}
+
+/// A candidate of a line group or node group that a [`SaliencyStrategy`] can select.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContentSaliencyOption {
+    /// The ID of the content, i.e. the line ID of a line or the name of a node.
+    pub content_id: String,
+    /// Whether the content is a line or a node.
+    pub content_type: ContentSaliencyContentType,
+    /// The number of conditions of the content that are met.
+    pub passing_condition_value_count: usize,
+    /// The number of conditions of the content that are not met. Content with failing conditions must not be selected.
+    pub failing_condition_value_count: usize,
+    /// How specific the conditions of the content are. More specific content is usually preferred.
+    pub complexity_score: i32,
+    /// How often the content was selected before, tracked in the variable named by [`Library::generate_unique_view_count_variable`].
+    pub view_count: u32,
+    /// The instruction in the current node that runs the content.
+    pub destination: usize,
+}
+
+impl ContentSaliencyOption {
+    /// Returns `true` if none of the conditions of the content fail.
+    pub fn is_available(&self) -> bool {
+        self.failing_condition_value_count == 0
+    }
+}
+
+/// Selects which candidate of a line group or node group is run, set via [`Dialogue::set_saliency_strategy`].
+///
+/// The default is [`BestLeastRecentlyViewedSaliencyStrategy`].
+pub trait SaliencyStrategy: Debug + Send + Sync {
+    /// Returns the index of the candidate to run, or `None` to run none of them.
+    /// Implementations must not return a candidate that is not [`ContentSaliencyOption::is_available`].
+    fn query_best_content(&self, candidates: &[ContentSaliencyOption]) -> Option<usize>;
+}
+
+/// Selects the first available candidate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FirstSaliencyStrategy;
+
+impl SaliencyStrategy for FirstSaliencyStrategy {
+    fn query_best_content(&self, candidates: &[ContentSaliencyOption]) -> Option<usize> {
+        candidates
+            .iter()
+            .position(ContentSaliencyOption::is_available)
+    }
+}
+
+/// Selects the available candidate with the highest complexity score, preferring the first one on ties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BestSaliencyStrategy;
+
+impl SaliencyStrategy for BestSaliencyStrategy {
+    fn query_best_content(&self, candidates: &[ContentSaliencyOption]) -> Option<usize> {
+        best_candidates(candidates, |candidate| candidate.complexity_score)
+            .first()
+            .copied()
+    }
+}
+
+/// Selects the available candidate with the highest complexity score, preferring the one viewed the fewest times
+/// and then the first one on ties. Repeatedly running a line group therefore cycles through its lines. This is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BestLeastRecentlyViewedSaliencyStrategy;
+
+impl SaliencyStrategy for BestLeastRecentlyViewedSaliencyStrategy {
+    fn query_best_content(&self, candidates: &[ContentSaliencyOption]) -> Option<usize> {
+        best_candidates(candidates, least_recently_viewed)
+            .first()
+            .copied()
+    }
+}
+
+/// Like [`BestLeastRecentlyViewedSaliencyStrategy`], but picks a random candidate on ties,
+/// using a [`DialogueRng`] such as the engine's seeded RNG so that the choice is reproducible.
+#[derive(Debug)]
+pub struct RandomBestLeastRecentlyViewedSaliencyStrategy {
+    rng: Mutex<Box<dyn DialogueRng>>,
+}
+
+impl RandomBestLeastRecentlyViewedSaliencyStrategy {
+    /// Creates a strategy drawing from the given source of randomness.
+    pub fn new(rng: impl DialogueRng + 'static) -> Self {
+        Self {
+            rng: Mutex::new(Box::new(rng)),
+        }
+    }
+}
+
+impl SaliencyStrategy for RandomBestLeastRecentlyViewedSaliencyStrategy {
+    fn query_best_content(&self, candidates: &[ContentSaliencyOption]) -> Option<usize> {
+        let best = best_candidates(candidates, least_recently_viewed);
+        if best.is_empty() {
+            return None;
+        }
+        let random = self.rng.lock().next_u64();
+        Some(best[(random % best.len() as u64) as usize])
+    }
+}
+
+/// The indices of the available candidates with the highest key.
+fn best_candidates<K: Ord>(
+    candidates: &[ContentSaliencyOption],
+    key: impl Fn(&ContentSaliencyOption) -> K,
+) -> Vec<usize> {
+    let available = || {
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| candidate.is_available())
+    };
+    let best_key = available().map(|(_, candidate)| key(candidate)).max();
+    available()
+        .filter(|(_, candidate)| Some(key(candidate)) == best_key)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn least_recently_viewed(candidate: &ContentSaliencyOption) -> (i32, core::cmp::Reverse<u32>) {
+    (
+        candidate.complexity_score,
+        core::cmp::Reverse(candidate.view_count),
+    )
+}
@@ -0,0 +1,197 @@
+//! Not part of the original implementation.
+//!
+//! Lets games that load community-authored programs, e.g. mods, keep them from calling privileged host functions and commands.
+
+use crate::prelude::*;
+use hashbrown::HashSet;
+
+/// Restricts which functions and commands the programs run by a [`Dialogue`] may use, see [`Dialogue::set_sandbox_policy`].
+///
+/// The functions of the standard library, which includes the operators, as well as `visited`, `visited_count`,
+/// `times_visited` and `last_visited` are always permitted.
+/// Every other function of the [`Library`] and every command must be permitted explicitly.
+/// Running a function or command that is not permitted returns a [`DialogueError::FunctionNotPermitted`] or
+/// [`DialogueError::CommandNotPermitted`]. `<<wait>>` commands that the dialogue handles itself are exempt,
+/// see [`Dialogue::set_wait_command_handling`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue
+///     .library_mut()
+///     .add_function("player_gold", || 100.0)
+///     .add_function("grant_admin_rights", || true);
+/// dialogue.set_sandbox_policy(Some(
+///     SandboxPolicy::new()
+///         .with_permitted_functions(["player_gold"])
+///         .with_permitted_commands(["play_sfx", "set_sprite"]),
+/// ));
+///
+/// let policy = dialogue.sandbox_policy().unwrap();
+/// assert!(policy.permits_function("player_gold"));
+/// assert!(policy.permits_function("Number.Add"));
+/// assert!(!policy.permits_function("grant_admin_rights"));
+/// assert!(!policy.permits_command("quit_game"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SandboxPolicy {
+    standard_functions: HashSet<String>,
+    permitted_functions: HashSet<String>,
+    permitted_commands: HashSet<String>,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SandboxPolicy {
+    /// Creates a policy that only permits the functions of the standard library and no commands.
+    pub fn new() -> Self {
+        Self {
+            standard_functions: Library::standard_library()
+                .names()
+                .map(ToOwned::to_owned)
+                .collect(),
+            permitted_functions: HashSet::new(),
+            permitted_commands: HashSet::new(),
+        }
+    }
+
+    /// Sets the names of the functions that are permitted in addition to the standard library.
+    #[must_use]
+    pub fn with_permitted_functions(
+        mut self,
+        function_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.set_permitted_functions(function_names);
+        self
+    }
+
+    /// Sets the names of the functions that are permitted in addition to the standard library. Replaces the previously set names.
+    pub fn set_permitted_functions(
+        &mut self,
+        function_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.permitted_functions = function_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Gets the names of the functions that are permitted in addition to the standard library.
+    pub fn permitted_functions(&self) -> impl Iterator<Item = &str> {
+        self.permitted_functions.iter().map(String::as_str)
+    }
+
+    /// Sets the names of the permitted commands.
+    #[must_use]
+    pub fn with_permitted_commands(
+        mut self,
+        command_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.set_permitted_commands(command_names);
+        self
+    }
+
+    /// Sets the names of the permitted commands. Replaces the previously set names.
+    pub fn set_permitted_commands(
+        &mut self,
+        command_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.permitted_commands = command_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Gets the names of the permitted commands.
+    pub fn permitted_commands(&self) -> impl Iterator<Item = &str> {
+        self.permitted_commands.iter().map(String::as_str)
+    }
+
+    /// Returns `true` if the function of the [`Library`] with the given name may be called.
+    pub fn permits_function(&self, function_name: &str) -> bool {
+        self.standard_functions.contains(function_name)
+            || self.permitted_functions.contains(function_name)
+    }
+
+    /// Returns `true` if the command with the given name may be run.
+    pub fn permits_command(&self, command_name: &str) -> bool {
+        self.permitted_commands.contains(command_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    fn sandboxed_dialogue(policy: SandboxPolicy) -> Dialogue {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.library_mut().add_function("unlock_all", || true);
+        dialogue.set_sandbox_policy(Some(policy));
+        dialogue
+    }
+
+    fn call(function_name: &str) -> [InstructionType; 2] {
+        [
+            InstructionType::PushFloat(PushFloatInstruction { value: 0.0 }),
+            InstructionType::CallFunc(CallFunctionInstruction {
+                function_name: function_name.to_owned(),
+            }),
+        ]
+    }
+
+    #[test]
+    fn rejects_functions_that_are_not_permitted() {
+        let mut dialogue = sandboxed_dialogue(SandboxPolicy::new());
+        dialogue
+            .add_program(program_with_instructions("Start", call("unlock_all")))
+            .set_node("Start")
+            .unwrap();
+        assert!(matches!(
+            dialogue.continue_(),
+            Err(DialogueError::FunctionNotPermitted { function_name }) if function_name == "unlock_all"
+        ));
+
+        let mut dialogue =
+            sandboxed_dialogue(SandboxPolicy::new().with_permitted_functions(["unlock_all"]));
+        dialogue
+            .add_program(program_with_instructions("Start", call("unlock_all")))
+            .set_node("Start")
+            .unwrap();
+        assert!(dialogue.continue_().is_ok());
+    }
+
+    #[test]
+    fn rejects_commands_that_are_not_permitted() {
+        let run_command = |text: &str| {
+            InstructionType::RunCommand(RunCommandInstruction {
+                command_text: text.to_owned(),
+                substitution_count: 0,
+            })
+        };
+        let mut dialogue =
+            sandboxed_dialogue(SandboxPolicy::new().with_permitted_commands(["play_sfx"]));
+        dialogue.set_wait_command_handling(true);
+        dialogue
+            .add_program(program_with_instructions(
+                "Start",
+                [
+                    run_command("play_sfx door"),
+                    run_command("wait 0"),
+                    run_command("quit_game"),
+                ],
+            ))
+            .set_node("Start")
+            .unwrap();
+        assert!(dialogue.continue_().is_ok());
+        assert!(dialogue.continue_().is_ok());
+        assert!(matches!(
+            dialogue.continue_(),
+            Err(DialogueError::CommandNotPermitted { command_name }) if command_name == "quit_game"
+        ));
+    }
+}
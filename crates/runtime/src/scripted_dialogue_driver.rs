@@ -0,0 +1,381 @@
+//! Not part of the original implementation.
+//!
+//! Drives a [`Dialogue`] through an expected interaction for narrative regression tests, see [`ScriptedDialogueDriver`].
+
+use crate::prelude::*;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::result::Result;
+
+/// Declares an expected interaction with a [`Dialogue`] and checks it via [`ScriptedDialogueDriver::run`].
+///
+/// Every line and command the dialogue produces must match the next expectation of the script, in order, and every set of options
+/// must be answered by a choice. Other events, such as [`DialogueEvent::NodeStart`], are ignored.
+/// Once the script is exhausted the driver stops, so a script only needs to cover the part of the dialogue under test.
+///
+/// Lines and options are matched by their text as returned by the [`TextProvider`] set via [`ScriptedDialogueDriver::with_text_provider`],
+/// looked up as `line:<id>`. Without a text provider, or for lines it doesn't know, the text is the line ID itself.
+///
+/// ## Example
+///
+/// ```
+/// # use yarnspinner_runtime::prelude::*;
+/// # let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// let driver = ScriptedDialogueDriver::new()
+///     .expect_line_containing("Hello")
+///     .choose_option(2)
+///     .expect_command("wait 3")
+///     .expect_end();
+/// // With a program loaded into `dialogue`:
+/// // driver.run(&mut dialogue, "Start").unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct ScriptedDialogueDriver {
+    steps: Vec<ScriptStep>,
+    text_provider: Option<Box<dyn TextProvider>>,
+}
+
+/// An expectation of a [`ScriptedDialogueDriver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptStep {
+    /// A line with exactly the given text.
+    Line(String),
+    /// A line containing the given text.
+    LineContaining(String),
+    /// Any line.
+    AnyLine,
+    /// Options, of which the one with the given one-based index is chosen.
+    ChooseOption(usize),
+    /// Options, of which the first one containing the given text is chosen.
+    ChooseOptionContaining(String),
+    /// A command with exactly the given text, without the surrounding `<<` and `>>`.
+    Command(String),
+    /// The end of the dialogue.
+    End,
+}
+
+impl Display for ScriptStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Line(text) => write!(f, "line \"{text}\""),
+            Self::LineContaining(text) => write!(f, "line containing \"{text}\""),
+            Self::AnyLine => write!(f, "any line"),
+            Self::ChooseOption(index) => write!(f, "options to choose option {index} from"),
+            Self::ChooseOptionContaining(text) => {
+                write!(f, "options to choose the option containing \"{text}\" from")
+            }
+            Self::Command(text) => write!(f, "command <<{text}>>"),
+            Self::End => write!(f, "end of dialogue"),
+        }
+    }
+}
+
+/// An error returned by [`ScriptedDialogueDriver::run`].
+#[derive(Debug)]
+pub enum ScriptedDialogueError {
+    /// The dialogue did not behave as the script expected.
+    Mismatch {
+        /// The one-based index of the step that failed.
+        step: usize,
+        /// What the script expected.
+        expected: String,
+        /// What the dialogue did instead.
+        actual: String,
+        /// The lines, choices and commands up to the failure, one per entry.
+        transcript: Vec<String>,
+    },
+    /// The dialogue returned an error while running the script.
+    Dialogue(DialogueError),
+}
+
+impl Display for ScriptedDialogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch {
+                step,
+                expected,
+                actual,
+                transcript,
+            } => {
+                write!(
+                    f,
+                    "step {step} failed: expected {expected}, but got {actual}"
+                )?;
+                if !transcript.is_empty() {
+                    write!(f, "\ntranscript so far:")?;
+                    for entry in transcript {
+                        write!(f, "\n    {entry}")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Dialogue(error) => write!(f, "dialogue error: {error}"),
+        }
+    }
+}
+
+impl Error for ScriptedDialogueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Dialogue(error) => Some(error),
+            Self::Mismatch { .. } => None,
+        }
+    }
+}
+
+impl From<DialogueError> for ScriptedDialogueError {
+    fn from(error: DialogueError) -> Self {
+        Self::Dialogue(error)
+    }
+}
+
+impl ScriptedDialogueDriver {
+    /// Creates a driver with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`TextProvider`] used to look up the text of lines and options.
+    pub fn with_text_provider(mut self, text_provider: impl TextProvider + 'static) -> Self {
+        self.text_provider = Some(Box::new(text_provider));
+        self
+    }
+
+    /// Appends a step to the script.
+    pub fn expect(mut self, step: ScriptStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Expects a line with exactly the given text.
+    pub fn expect_line(self, text: impl Into<String>) -> Self {
+        self.expect(ScriptStep::Line(text.into()))
+    }
+
+    /// Expects a line containing the given text.
+    pub fn expect_line_containing(self, text: impl Into<String>) -> Self {
+        self.expect(ScriptStep::LineContaining(text.into()))
+    }
+
+    /// Expects a line with any text.
+    pub fn expect_any_line(self) -> Self {
+        self.expect(ScriptStep::AnyLine)
+    }
+
+    /// Expects options and chooses the one with the given one-based index, counting unavailable options too.
+    pub fn choose_option(self, index: usize) -> Self {
+        self.expect(ScriptStep::ChooseOption(index))
+    }
+
+    /// Expects options and chooses the first one containing the given text.
+    pub fn choose_option_containing(self, text: impl Into<String>) -> Self {
+        self.expect(ScriptStep::ChooseOptionContaining(text.into()))
+    }
+
+    /// Expects a command with exactly the given text, e.g. `wait 3` for `<<wait 3>>`.
+    pub fn expect_command(self, text: impl Into<String>) -> Self {
+        self.expect(ScriptStep::Command(text.into()))
+    }
+
+    /// Expects the dialogue to end.
+    pub fn expect_end(self) -> Self {
+        self.expect(ScriptStep::End)
+    }
+
+    /// The steps of the script, in order.
+    pub fn steps(&self) -> &[ScriptStep] {
+        &self.steps
+    }
+
+    /// Starts the dialogue at the given node and runs it until the script is exhausted or the dialogue ends.
+    /// The [`Dialogue`] must already have the program under test loaded.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ScriptedDialogueError::Mismatch`] for the first step the dialogue deviates from,
+    /// including choices of unavailable or missing options and scripts expecting more after the dialogue ended,
+    /// and [`ScriptedDialogueError::Dialogue`] if the dialogue fails.
+    pub fn run(
+        &self,
+        dialogue: &mut Dialogue,
+        start_node: &str,
+    ) -> Result<(), ScriptedDialogueError> {
+        let text = |line_id: u32| {
+            let id = LineId::from(format!("line:{line_id}"));
+            self.text_provider
+                .as_ref()
+                .and_then(|text_provider| text_provider.get_text(&id))
+                .unwrap_or(id.0)
+        };
+        let mut transcript = Vec::new();
+        let mut steps = self.steps.iter().enumerate();
+        let mismatch =
+            |transcript: &Vec<String>, (index, step): (usize, &ScriptStep), actual: String| {
+                Err(ScriptedDialogueError::Mismatch {
+                    step: index + 1,
+                    expected: step.to_string(),
+                    actual,
+                    transcript: transcript.clone(),
+                })
+            };
+
+        dialogue.set_node(start_node)?;
+        while dialogue.can_continue() {
+            for event in dialogue.continue_()? {
+                let step = match event {
+                    DialogueEvent::Line(..)
+                    | DialogueEvent::Options(_)
+                    | DialogueEvent::Command(_)
+                    | DialogueEvent::DialogueComplete => match steps.next() {
+                        Some(step) => step,
+                        None => return Ok(()),
+                    },
+                    _ => continue,
+                };
+                match event {
+                    DialogueEvent::Line(line_id, ..) => {
+                        let actual = text(line_id);
+                        let matches = match step.1 {
+                            ScriptStep::Line(expected) => *expected == actual,
+                            ScriptStep::LineContaining(expected) => {
+                                actual.contains(expected.as_str())
+                            }
+                            ScriptStep::AnyLine => true,
+                            _ => false,
+                        };
+                        if !matches {
+                            return mismatch(&transcript, step, format!("line \"{actual}\""));
+                        }
+                        transcript.push(actual);
+                    }
+                    DialogueEvent::Options(options) => {
+                        let texts: Vec<_> =
+                            options.iter().map(|option| text(option.tag_id)).collect();
+                        let actual = format!(
+                            "options [{}]",
+                            texts
+                                .iter()
+                                .zip(&options)
+                                .map(|(text, option)| if option.is_available {
+                                    format!("\"{text}\"")
+                                } else {
+                                    format!("\"{text}\" (unavailable)")
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                        let chosen = match step.1 {
+                            ScriptStep::ChooseOption(index) => index.checked_sub(1),
+                            ScriptStep::ChooseOptionContaining(expected) => texts
+                                .iter()
+                                .position(|text| text.contains(expected.as_str())),
+                            _ => None,
+                        }
+                        .filter(|&index| {
+                            options.get(index).is_some_and(|option| option.is_available)
+                        });
+                        let Some(chosen) = chosen else {
+                            return mismatch(&transcript, step, actual);
+                        };
+                        transcript.push(format!("> {}", texts[chosen]));
+                        dialogue.set_selected_option(options[chosen].id)?;
+                    }
+                    DialogueEvent::Command(command) => {
+                        if !matches!(step.1, ScriptStep::Command(expected) if *expected == command.raw)
+                        {
+                            return mismatch(
+                                &transcript,
+                                step,
+                                format!("command <<{}>>", command.raw),
+                            );
+                        }
+                        transcript.push(format!("<<{}>>", command.raw));
+                    }
+                    DialogueEvent::DialogueComplete => {
+                        if *step.1 != ScriptStep::End {
+                            return mismatch(&transcript, step, "end of dialogue".to_owned());
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+        match steps.next() {
+            Some(step) => mismatch(&transcript, step, "no more content".to_owned()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::InstructionType;
+
+    #[test]
+    fn reports_the_failing_step_with_a_transcript() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let add_option = |tag_id| {
+            InstructionType::AddOption(instruction::AddOptionInstruction {
+                tag_id,
+                destination: 0,
+                substitution_count: 0,
+                has_condition: false,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                add_option(2),
+                add_option(3),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                InstructionType::RunCommand(instruction::RunCommandInstruction {
+                    command_text: "wait 3".to_owned(),
+                    substitution_count: 0,
+                }),
+                run_line(4),
+                InstructionType::Stop(instruction::StopInstruction {}),
+            ],
+        ));
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([
+            (LineId::from("line:1"), "Hello there!".to_owned()),
+            (LineId::from("line:2"), "Hi".to_owned()),
+            (LineId::from("line:3"), "Bye".to_owned()),
+            (LineId::from("line:4"), "See you.".to_owned()),
+        ]);
+
+        ScriptedDialogueDriver::new()
+            .with_text_provider(text_provider.clone())
+            .expect_line_containing("Hello")
+            .choose_option(2)
+            .expect_command("wait 3")
+            .expect_any_line()
+            .expect_end()
+            .run(&mut dialogue, "Start")
+            .unwrap();
+
+        let error = ScriptedDialogueDriver::new()
+            .with_text_provider(text_provider)
+            .expect_any_line()
+            .choose_option_containing("Bye")
+            .expect_command("wait 3")
+            .expect_line("Goodbye.")
+            .run(&mut dialogue, "Start")
+            .unwrap_err();
+        assert_eq!(
+            "step 4 failed: expected line \"Goodbye.\", but got line \"See you.\"\n\
+             transcript so far:\n    Hello there!\n    > Bye\n    <<wait 3>>",
+            error.to_string()
+        );
+    }
+}
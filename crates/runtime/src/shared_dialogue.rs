@@ -0,0 +1,137 @@
+//! Not part of the original implementation.
+//!
+//! A [`Dialogue`] is [`Send`] and [`Sync`], so it can be moved between threads as is. [`SharedDialogue`] additionally lets
+//! several systems of an ECS schedule hold on to the same dialogue, e.g. the system driving it and the UI reading its state.
+
+use crate::prelude::*;
+use crate::sync::{Mutex, MutexGuard};
+use crate::Result;
+use alloc::sync::Arc;
+
+/// A cheaply cloneable, thread-safe handle to a [`Dialogue`].
+///
+/// Every method locks the dialogue for the duration of the call. The `try_` methods never block: they return `None`
+/// if another thread holds the lock, so a system can skip a frame instead of stalling the scheduler.
+/// A panic while the lock is held does not poison the handle, but it may leave the dialogue halfway through an instruction,
+/// so call [`Dialogue::set_node`] before continuing a dialogue whose call panicked.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// let dialogue = SharedDialogue::new(Dialogue::new(Box::new(MemoryVariableStorage::new())));
+/// let ui_handle = dialogue.clone();
+/// std::thread::spawn(move || {
+///     ui_handle.with(|dialogue| dialogue.set_variable("$seen_intro", true).map(|_| ()))
+/// })
+/// .join()
+/// .unwrap()
+/// .unwrap();
+///
+/// if let Some(seen_intro) = dialogue.try_with(|dialogue| dialogue.variable("$seen_intro")) {
+///     assert_eq!(YarnValue::Boolean(true), seen_intro.unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedDialogue(Arc<Mutex<Dialogue>>);
+
+impl SharedDialogue {
+    /// Wraps the dialogue in a new handle.
+    pub fn new(dialogue: Dialogue) -> Self {
+        Self(Arc::new(Mutex::new(dialogue)))
+    }
+
+    /// Locks the dialogue, blocking until no other thread holds the lock.
+    pub fn lock(&self) -> MutexGuard<'_, Dialogue> {
+        self.0.lock()
+    }
+
+    /// Locks the dialogue if no other thread holds the lock, or returns `None` without blocking.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, Dialogue>> {
+        self.0.try_lock()
+    }
+
+    /// Runs the closure with the locked dialogue, blocking until no other thread holds the lock.
+    pub fn with<T>(&self, f: impl FnOnce(&mut Dialogue) -> T) -> T {
+        f(&mut self.lock())
+    }
+
+    /// Runs the closure with the locked dialogue if no other thread holds the lock, or returns `None` without blocking.
+    pub fn try_with<T>(&self, f: impl FnOnce(&mut Dialogue) -> T) -> Option<T> {
+        self.try_lock().map(|mut dialogue| f(&mut dialogue))
+    }
+
+    /// Calls [`Dialogue::continue_`] if no other thread holds the lock, or returns `None` without blocking.
+    pub fn try_continue(&self) -> Option<Result<Vec<DialogueEvent>>> {
+        self.try_with(Dialogue::continue_)
+    }
+
+    /// Calls [`Dialogue::set_selected_option`] if no other thread holds the lock, or returns `None` without blocking.
+    pub fn try_set_selected_option(&self, option_id: OptionId) -> Option<Result<()>> {
+        self.try_with(|dialogue| dialogue.set_selected_option(option_id).map(|_| ()))
+    }
+
+    /// Returns `true` if both handles refer to the same dialogue.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the dialogue if this is the only handle to it, or the handle otherwise.
+    pub fn try_into_inner(self) -> core::result::Result<Dialogue, Self> {
+        Arc::try_unwrap(self.0).map(Mutex::into_inner).map_err(Self)
+    }
+}
+
+impl From<Dialogue> for SharedDialogue {
+    fn from(dialogue: Dialogue) -> Self {
+        Self::new(dialogue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    assert_impl_all!(Dialogue: Send, Sync, Clone);
+    assert_impl_all!(SharedDialogue: Send, Sync, Clone);
+    assert_impl_all!(DialogueRunner: Send, Sync);
+    assert_impl_all!(DialogueScheduler: Send, Sync);
+    assert_impl_all!(Library: Send, Sync);
+    assert_impl_all!(Box<dyn UntypedYarnFn>: Send, Sync);
+    assert_impl_all!(Box<dyn VariableStorage>: Send, Sync);
+    assert_impl_all!(MemoryVariableStorage: Send, Sync);
+    assert_impl_all!(DialogueEvent: Send, Sync);
+    assert_impl_all!(DialogueError: Send, Sync);
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn does_not_block_on_try_methods() {
+        use crate::dialogue::tests::program_with_lines;
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(program_with_lines("Start", [1]))
+            .set_node("Start")
+            .unwrap();
+        let dialogue = SharedDialogue::new(dialogue);
+        let handle = dialogue.clone();
+        assert!(handle.ptr_eq(&dialogue));
+
+        let guard = dialogue.lock();
+        std::thread::scope(|scope| {
+            scope.spawn(|| assert!(handle.try_continue().is_none()));
+        });
+        drop(guard);
+
+        let events = std::thread::scope(|scope| scope.spawn(|| handle.try_continue()).join())
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(events.contains(&DialogueEvent::Line(1, vec![])));
+
+        let dialogue = dialogue.try_into_inner().unwrap_err();
+        drop(handle);
+        assert!(dialogue.try_into_inner().is_ok());
+    }
+}
@@ -0,0 +1,631 @@
+//! Headless simulation of many randomized playthroughs of a [`Program`], used for balancing and QA.
+//!
+//! Not part of the original implementation.
+
+pub(crate) use self::choice_graph::escape;
+pub use self::{choice_graph::*, choice_policy::*, invariant::*};
+use crate::prelude::*;
+use crate::Result;
+use alloc::collections::BTreeMap;
+use core::fmt::Debug;
+use hashbrown::HashMap;
+
+mod choice_graph;
+mod choice_policy;
+mod invariant;
+
+/// The prefix of variables used by the runtime for internal bookkeeping, e.g. for `visited`.
+const INTERNAL_VARIABLE_PREFIX: &str = "$Yarn.Internal.";
+
+/// Runs a [`Program`] many times without any user interaction and aggregates statistics about the runs into a [`SimulationReport`].
+///
+/// Every playthrough uses a fresh [`Dialogue`] with a [`MemoryVariableStorage`]. Options are selected by the configured [`ChoicePolicy`],
+/// which is fed a [`SimulationRng`] seeded with the simulation's seed plus the index of the playthrough, so each playthrough can be reproduced individually.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # let program = Program::default();
+/// let mut simulator = Simulator::new(program)
+///     .with_seed(42)
+///     .with_choice_policy(RandomChoicePolicy);
+/// // Would run 1000 playthroughs starting at the node "Start"
+/// # let _ = || {
+/// let report = simulator.run(1000).unwrap();
+/// println!("Never reached: {:?}", report.unreached_nodes);
+/// # };
+/// ```
+#[derive(Debug)]
+pub struct Simulator {
+    program: Program,
+    library: Library,
+    start_node: String,
+    seed: u64,
+    max_steps: usize,
+    max_instructions: u64,
+    choice_policy: Box<dyn ChoicePolicy>,
+    invariants: Vec<Invariant>,
+}
+
+impl Simulator {
+    /// The default name of the node every playthrough starts at.
+    pub const DEFAULT_START_NODE: &'static str = "Start";
+
+    /// The default maximum number of [`Dialogue::continue_`] calls per playthrough.
+    pub const DEFAULT_MAX_STEPS: usize = 10_000;
+
+    /// The default maximum number of instructions executed per playthrough.
+    pub const DEFAULT_MAX_INSTRUCTIONS: u64 = 1_000_000;
+
+    /// Creates a new simulator for the given program that starts at [`Simulator::DEFAULT_START_NODE`] and selects random options.
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            library: Library::new(),
+            start_node: Self::DEFAULT_START_NODE.to_owned(),
+            seed: 0,
+            max_steps: Self::DEFAULT_MAX_STEPS,
+            max_instructions: Self::DEFAULT_MAX_INSTRUCTIONS,
+            choice_policy: Box::new(RandomChoicePolicy),
+            invariants: Vec::new(),
+        }
+    }
+
+    /// Sets additional functions the program may call. The standard library is always available.
+    #[must_use]
+    pub fn with_library(mut self, library: Library) -> Self {
+        self.library = library;
+        self
+    }
+
+    /// Sets the node every playthrough starts at.
+    #[must_use]
+    pub fn with_start_node(mut self, start_node: impl Into<String>) -> Self {
+        self.start_node = start_node.into();
+        self
+    }
+
+    /// Sets the seed used to derive the random number generator of each playthrough.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the maximum number of [`Dialogue::continue_`] calls per playthrough.
+    /// Playthroughs that exceed it are aborted and counted in [`SimulationReport::incomplete_playthroughs`], which guards against endless loops.
+    /// Loops that never produce an event are bounded by [`Simulator::with_max_instructions`] instead.
+    #[must_use]
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets the maximum number of instructions executed per playthrough.
+    /// Playthroughs that exceed it are aborted and counted in [`SimulationReport::incomplete_playthroughs`],
+    /// which guards against endless loops that don't produce any events, e.g. a node jumping to itself.
+    #[must_use]
+    pub fn with_max_instructions(mut self, max_instructions: u64) -> Self {
+        self.max_instructions = max_instructions;
+        self
+    }
+
+    /// Sets the policy used to select options.
+    #[must_use]
+    pub fn with_choice_policy(mut self, choice_policy: impl ChoicePolicy + 'static) -> Self {
+        self.choice_policy = Box::new(choice_policy);
+        self
+    }
+
+    /// Adds an [`Invariant`] that is checked during every playthrough. Violations are reported in [`SimulationReport::violations`].
+    #[must_use]
+    pub fn with_invariant(mut self, invariant: Invariant) -> Self {
+        self.invariants.push(invariant);
+        self
+    }
+
+    /// Runs the given number of playthroughs and aggregates their statistics.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first [`DialogueError`] encountered, e.g. if the start node does not exist or a function is missing from the library.
+    pub fn run(&mut self, playthroughs: usize) -> Result<SimulationReport> {
+        let mut report = SimulationReport::new(&self.program);
+        for index in 0..playthroughs {
+            let playthrough = self.run_playthrough(index)?;
+            report.record(&playthrough);
+        }
+        report.finish();
+        Ok(report)
+    }
+
+    /// Runs a single playthrough. The result is identical to the playthrough with the same index in [`Simulator::run`].
+    pub fn run_playthrough(&mut self, index: usize) -> Result<Playthrough> {
+        let mut rng = SimulationRng::new(self.seed.wrapping_add(index as u64));
+        let mut dialogue = self.create_dialogue();
+        dialogue.set_node(self.start_node.clone())?;
+
+        let mut playthrough = Playthrough {
+            index,
+            events: Vec::new(),
+            selections: Vec::new(),
+            completed: false,
+            variables: HashMap::new(),
+            violations: Vec::new(),
+        };
+        // With invariants, the dialogue runs one instruction at a time so that they see the variables right when a node starts
+        // or a variable changes. The first step runs no instruction at all, so the start node is checked before it runs.
+        let mut instruction_budget = (!self.invariants.is_empty()).then_some(0);
+        let mut steps = 0;
+        let start_instructions = dialogue.instructions_executed();
+        while dialogue.can_continue() && steps < self.max_steps {
+            let executed = dialogue.instructions_executed() - start_instructions;
+            let Some(remaining) = self
+                .max_instructions
+                .checked_sub(executed)
+                .filter(|remaining| *remaining > 0)
+            else {
+                break;
+            };
+            let remaining = usize::try_from(remaining).unwrap_or(usize::MAX);
+            let batch_start = playthrough.events.len();
+            let max_instructions = match instruction_budget {
+                Some(max_instructions) => {
+                    instruction_budget = Some(1);
+                    max_instructions.min(remaining)
+                }
+                None => remaining,
+            };
+            let events = dialogue.continue_with_budget(max_instructions)?;
+            // Yielding only splits up what would have been a single call of `continue_`
+            if dialogue.state() != DialogueState::Yielded {
+                steps += 1;
+            }
+            for event in events {
+                match &event {
+                    DialogueEvent::Options(options) => {
+                        let selection = self.choice_policy.choose(options, &mut rng);
+                        dialogue.set_selected_option(selection)?;
+                        playthrough.selections.push(selection);
+                    }
+                    DialogueEvent::DialogueComplete => playthrough.completed = true,
+                    _ => {}
+                }
+                playthrough.events.push(event);
+            }
+            self.check_invariants(&dialogue, &mut playthrough, batch_start);
+            if playthrough.completed {
+                break;
+            }
+        }
+        playthrough.variables = dialogue.variable_storage().variables();
+        Ok(playthrough)
+    }
+
+    fn check_invariants(
+        &self,
+        dialogue: &Dialogue,
+        playthrough: &mut Playthrough,
+        batch_start: usize,
+    ) {
+        for invariant in &self.invariants {
+            let already_violated = playthrough
+                .violations
+                .iter()
+                .any(|violation| violation.invariant == invariant.name());
+            if already_violated {
+                continue;
+            }
+            let started_nodes = playthrough.events[batch_start..]
+                .iter()
+                .filter_map(|event| match event {
+                    DialogueEvent::NodeStart(name) => Some(name.as_ref()),
+                    _ => None,
+                });
+            if invariant.holds(dialogue.variable_storage(), started_nodes) {
+                continue;
+            }
+            playthrough.violations.push(InvariantViolation {
+                invariant: invariant.name().to_owned(),
+                playthrough: playthrough.index,
+                trace: playthrough.events.clone(),
+                selections: playthrough.selections.clone(),
+                variables: dialogue.variable_storage().variables(),
+            });
+        }
+    }
+
+    fn create_dialogue(&self) -> Dialogue {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.library_mut().import(self.library.clone());
+        dialogue.add_program(self.program.clone());
+        dialogue
+    }
+}
+
+/// The record of a single simulated playthrough.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Playthrough {
+    /// The index of this playthrough within the simulation.
+    pub index: usize,
+    /// All events emitted by the [`Dialogue`], in order.
+    pub events: Vec<DialogueEvent>,
+    /// The options selected by the [`ChoicePolicy`], in order.
+    pub selections: Vec<OptionId>,
+    /// Whether the playthrough reached [`DialogueEvent::DialogueComplete`] before hitting the step limit.
+    pub completed: bool,
+    /// The variables stored at the end of the playthrough.
+    pub variables: HashMap<String, YarnValue>,
+    /// The first violation of every [`Invariant`] that did not hold during this playthrough.
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl Playthrough {
+    /// Iterates over the names of the nodes entered during this playthrough, in order.
+    pub fn visited_nodes(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().filter_map(|event| match event {
+            DialogueEvent::NodeStart(name) => Some(name.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// The number of lines delivered during this playthrough.
+    pub fn line_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, DialogueEvent::Line(..)))
+            .count()
+    }
+
+    /// The number of commands run during this playthrough.
+    pub fn command_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|event| matches!(event, DialogueEvent::Command(_)))
+            .count()
+    }
+}
+
+/// Statistics aggregated over all playthroughs of a [`Simulator::run`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimulationReport {
+    /// The number of simulated playthroughs.
+    pub playthroughs: usize,
+    /// The number of playthroughs that were aborted because they exceeded the step or instruction limit.
+    pub incomplete_playthroughs: usize,
+    /// How often each node of the program was entered. Contains an entry for every node, including unreached ones.
+    pub node_visits: BTreeMap<String, usize>,
+    /// The nodes that were never entered, sorted by name.
+    pub unreached_nodes: Vec<String>,
+    /// The total number of lines delivered across all playthroughs.
+    pub total_lines: usize,
+    /// The total number of options selected across all playthroughs.
+    pub total_selections: usize,
+    /// The total number of commands run across all playthroughs.
+    pub total_commands: usize,
+    /// For every variable, how many playthroughs ended with which value. Values are keyed by their textual representation.
+    /// Internal variables used by the runtime are not included.
+    pub variable_distributions: BTreeMap<String, BTreeMap<String, usize>>,
+    /// All [`Invariant`] violations found, ordered by playthrough.
+    pub violations: Vec<InvariantViolation>,
+    /// The transitions between nodes taken across all playthroughs.
+    pub choice_graph: ChoiceGraph,
+}
+
+impl SimulationReport {
+    fn new(program: &Program) -> Self {
+        Self {
+            node_visits: program.nodes.keys().map(|name| (name.clone(), 0)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn record(&mut self, playthrough: &Playthrough) {
+        self.playthroughs += 1;
+        if !playthrough.completed {
+            self.incomplete_playthroughs += 1;
+        }
+        for node in playthrough.visited_nodes() {
+            *self.node_visits.entry(node.to_owned()).or_default() += 1;
+        }
+        self.total_lines += playthrough.line_count();
+        self.total_commands += playthrough.command_count();
+        self.total_selections += playthrough.selections.len();
+        for (name, value) in &playthrough.variables {
+            if name.starts_with(INTERNAL_VARIABLE_PREFIX) {
+                continue;
+            }
+            *self
+                .variable_distributions
+                .entry(name.clone())
+                .or_default()
+                .entry(value.to_string())
+                .or_default() += 1;
+        }
+        self.violations
+            .extend(playthrough.violations.iter().cloned());
+        self.choice_graph.record(playthrough);
+    }
+
+    fn finish(&mut self) {
+        self.unreached_nodes = self
+            .node_visits
+            .iter()
+            .filter(|(_, visits)| **visits == 0)
+            .map(|(name, _)| name.clone())
+            .collect();
+    }
+
+    /// The average number of lines delivered per playthrough, i.e. the average conversation length.
+    pub fn average_lines(&self) -> f32 {
+        self.average(self.total_lines)
+    }
+
+    /// The average number of options selected per playthrough.
+    pub fn average_selections(&self) -> f32 {
+        self.average(self.total_selections)
+    }
+
+    /// Whether no [`Invariant`] was violated in any playthrough.
+    pub fn upholds_invariants(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn average(&self, total: usize) -> f32 {
+        if self.playthroughs == 0 {
+            0.0
+        } else {
+            total as f32 / self.playthroughs as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn reports_unreached_nodes_and_averages() {
+        let mut program = program_with_options();
+        program
+            .nodes
+            .extend(program_with_instructions("Unused", [stop()]).nodes);
+
+        let report = Simulator::new(program).with_seed(7).run(200).unwrap();
+
+        assert_eq!(200, report.playthroughs);
+        assert_eq!(0, report.incomplete_playthroughs);
+        assert_eq!(vec!["Unused".to_owned()], report.unreached_nodes);
+        assert_eq!(200, report.node_visits["Start"]);
+        assert_eq!(200, report.node_visits["A"] + report.node_visits["B"]);
+        assert!(report.node_visits["A"] > 0 && report.node_visits["B"] > 0);
+        assert_eq!(1.0, report.average_lines());
+        assert_eq!(1.0, report.average_selections());
+    }
+
+    #[test]
+    fn playthroughs_are_reproducible() {
+        let mut simulator = Simulator::new(program_with_options()).with_seed(3);
+        let first = simulator.run_playthrough(5).unwrap();
+        let second = simulator.run_playthrough(5).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn first_available_policy_is_deterministic() {
+        let report = Simulator::new(program_with_options())
+            .with_choice_policy(FirstAvailableChoicePolicy)
+            .run(10)
+            .unwrap();
+        assert_eq!(10, report.node_visits["A"]);
+        assert_eq!(vec!["B".to_owned()], report.unreached_nodes);
+    }
+
+    #[test]
+    fn reports_invariant_violations_with_trace() {
+        let mut program = program_with_options();
+        program.nodes.extend(
+            program_with_instructions(
+                "A",
+                [
+                    InstructionType::PushFloat(PushFloatInstruction { value: -5.0 }),
+                    InstructionType::StoreVariable(StoreVariableInstruction {
+                        variable_name: "$gold".to_owned(),
+                    }),
+                    InstructionType::Pop(PopInstruction {}),
+                    stop(),
+                ],
+            )
+            .nodes,
+        );
+        let report = Simulator::new(program)
+            .with_choice_policy(FirstAvailableChoicePolicy)
+            .with_invariant(Invariant::always("$gold never negative", |storage| {
+                !matches!(storage.get("$gold"), Ok(YarnValue::Number(gold)) if gold < 0.0)
+            }))
+            .with_invariant(Invariant::on_node_start(
+                "B requires $gold",
+                "B",
+                |storage| storage.contains("$gold"),
+            ))
+            .run(3)
+            .unwrap();
+
+        assert!(!report.upholds_invariants());
+        assert_eq!(3, report.violations.len());
+        let violation = &report.violations[0];
+        assert_eq!("$gold never negative", violation.invariant);
+        assert_eq!(0, violation.playthrough);
+        assert_eq!(vec![OptionId(0)], violation.selections);
+        assert!(violation
+            .trace
+            .contains(&DialogueEvent::NodeStart("A".into())));
+        assert_eq!(YarnValue::Number(-5.0), violation.variables["$gold"]);
+    }
+
+    #[test]
+    fn node_invariants_are_only_checked_on_entry() {
+        let report = Simulator::new(program_with_options())
+            .with_choice_policy(FirstAvailableChoicePolicy)
+            .with_invariant(Invariant::on_node_start("never B", "B", |_| false))
+            .run(5)
+            .unwrap();
+        assert!(report.upholds_invariants());
+    }
+
+    #[test]
+    fn invariants_see_transient_and_node_entry_state() {
+        let set_gold = |value| {
+            [
+                InstructionType::PushFloat(PushFloatInstruction { value }),
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$gold".to_owned(),
+                }),
+                InstructionType::Pop(PopInstruction {}),
+            ]
+        };
+        let mut program = program_with_options();
+        // A sets $gold before its line and B corrects a negative $gold before anyone sees it
+        for (name, instructions) in [
+            ("A", [set_gold(1.0), set_gold(1.0)]),
+            ("B", [set_gold(-5.0), set_gold(5.0)]),
+        ] {
+            let node = program_with_instructions(name, instructions.into_iter().flatten());
+            program.nodes.extend(node.nodes);
+        }
+        let report = Simulator::new(program)
+            .with_choice_policy(RandomChoicePolicy)
+            .with_seed(1)
+            .with_invariant(Invariant::always("$gold never negative", |storage| {
+                !matches!(storage.get("$gold"), Ok(YarnValue::Number(gold)) if gold < 0.0)
+            }))
+            .with_invariant(Invariant::on_node_start(
+                "A requires $gold",
+                "A",
+                |storage| storage.contains("$gold"),
+            ))
+            .run(20)
+            .unwrap();
+
+        let violated = |name: &str| {
+            report
+                .violations
+                .iter()
+                .any(|violation| violation.invariant == name)
+        };
+        assert!(violated("$gold never negative"));
+        assert!(violated("A requires $gold"));
+        assert!(report
+            .violations
+            .iter()
+            .all(|violation| violation.variables.get("$gold") != Some(&YarnValue::Number(5.0))));
+    }
+
+    #[test]
+    fn aborts_loops_without_events() {
+        let program = program_with_instructions(
+            "Start",
+            [
+                InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+                InstructionType::Pop(PopInstruction {}),
+                InstructionType::JumpTo(JumpToInstruction { destination: 0 }),
+            ],
+        );
+        let simulator = || {
+            Simulator::new(program.clone())
+                .with_max_steps(5)
+                .with_max_instructions(300)
+        };
+        let report = simulator().run(2).unwrap();
+        assert_eq!(2, report.incomplete_playthroughs);
+
+        let report = simulator()
+            .with_invariant(Invariant::always("anything", |_| true))
+            .run(2)
+            .unwrap();
+        assert_eq!(2, report.incomplete_playthroughs);
+        assert!(report.violations.is_empty());
+    }
+
+    #[test]
+    fn exports_choice_graph() {
+        let report = Simulator::new(program_with_options())
+            .with_choice_policy(FirstAvailableChoicePolicy)
+            .run(4)
+            .unwrap();
+        let graph = &report.choice_graph;
+        let to_a = ChoiceEdge {
+            from: "Start".to_owned(),
+            to: "A".to_owned(),
+            option: Some(10),
+        };
+        assert_eq!(4, graph.transitions[&to_a]);
+        assert_eq!(1.0, graph.probability(&to_a));
+        assert_eq!(4, graph.node_visits["A"]);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph choices {\n"));
+        assert!(dot.contains("    \"Start\" -> \"A\" [label=\"line 10: 100.0%\", weight=4];\n"));
+        assert!(dot.contains("    \"A\" -> \"<end>\" [label=\"100.0%\", weight=4];\n"));
+
+        assert_eq!(
+            r#"{"nodes":[{"name":"A","visits":4},{"name":"Start","visits":4}],"transitions":[{"from":"A","to":"<end>","option":null,"count":4,"probability":1.0},{"from":"Start","to":"A","option":10,"count":4,"probability":1.0}]}"#,
+            graph.to_json()
+        );
+    }
+
+    /// `Start` offers two options leading to the nodes `A` and `B`, which deliver one line each.
+    pub(crate) fn program_with_options() -> Program {
+        let mut program = program_with_instructions(
+            "Start",
+            [
+                add_option(10, 4),
+                add_option(11, 6),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                InstructionType::Pop(PopInstruction {}),
+                run_node("A"),
+                InstructionType::Pop(PopInstruction {}),
+                run_node("B"),
+            ],
+        );
+        for (name, line_id) in [("A", 1), ("B", 2)] {
+            let node = program_with_instructions(
+                name,
+                [
+                    InstructionType::RunLine(RunLineInstruction {
+                        line_id,
+                        substitution_count: 0,
+                    }),
+                    stop(),
+                ],
+            );
+            program.nodes.extend(node.nodes);
+        }
+        program
+    }
+
+    fn add_option(tag_id: u32, destination: i32) -> InstructionType {
+        InstructionType::AddOption(AddOptionInstruction {
+            tag_id,
+            destination,
+            substitution_count: 0,
+            has_condition: false,
+        })
+    }
+
+    fn run_node(node_name: &str) -> InstructionType {
+        InstructionType::RunNode(RunNodeInstruction {
+            node_name: node_name.to_owned(),
+        })
+    }
+
+    fn stop() -> InstructionType {
+        InstructionType::Stop(StopInstruction {})
+    }
+}
@@ -0,0 +1,165 @@
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use core::fmt::Write;
+
+/// A weighted graph of the transitions between nodes observed during a [`Simulator::run`].
+///
+/// Every time a playthrough leaves a node, the transition to the next node is counted, labeled with the last option selected in the node, if any.
+/// Playthroughs that finish inside of a node transition to the pseudo-node [`ChoiceGraph::END_NODE`].
+/// The resulting counts show how often players are likely to take each branch under the [`ChoicePolicy`] used for the simulation.
+/// The graph can be exported with [`ChoiceGraph::to_dot`] and [`ChoiceGraph::to_json`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChoiceGraph {
+    /// How often each node was entered.
+    pub node_visits: BTreeMap<String, usize>,
+    /// How often each transition was taken.
+    pub transitions: BTreeMap<ChoiceEdge, usize>,
+}
+
+/// A transition between two nodes in a [`ChoiceGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChoiceEdge {
+    /// The node that was left.
+    pub from: String,
+    /// The node that was entered, or [`ChoiceGraph::END_NODE`] if the dialogue completed.
+    pub to: String,
+    /// The line ID of the last option selected in [`ChoiceEdge::from`] before the transition, if any.
+    pub option: Option<u32>,
+}
+
+impl ChoiceGraph {
+    /// The name of the pseudo-node that represents the end of the dialogue.
+    pub const END_NODE: &'static str = "<end>";
+
+    pub(crate) fn record(&mut self, playthrough: &Playthrough) {
+        let mut selections = playthrough.selections.iter();
+        let mut current_node: Option<&str> = None;
+        let mut selected_option = None;
+        for event in &playthrough.events {
+            match event {
+                DialogueEvent::NodeStart(node) => {
+                    if let Some(from) = current_node {
+                        self.add_transition(from, node, selected_option.take());
+                    }
+                    *self.node_visits.entry(node.to_string()).or_default() += 1;
+                    current_node = Some(node);
+                }
+                DialogueEvent::Options(options) => {
+                    if let Some(selection) = selections.next() {
+                        selected_option = options
+                            .iter()
+                            .find(|option| option.id == *selection)
+                            .map(|option| option.tag_id);
+                    }
+                }
+                DialogueEvent::DialogueComplete => {
+                    if let Some(from) = current_node.take() {
+                        self.add_transition(from, Self::END_NODE, selected_option.take());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_transition(&mut self, from: &str, to: &str, option: Option<u32>) {
+        let edge = ChoiceEdge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            option,
+        };
+        *self.transitions.entry(edge).or_default() += 1;
+    }
+
+    /// Iterates over all transitions leaving the given node together with how often they were taken.
+    pub fn outgoing<'a>(&'a self, node: &'a str) -> impl Iterator<Item = (&'a ChoiceEdge, usize)> {
+        self.transitions
+            .iter()
+            .filter(move |(edge, _)| edge.from == node)
+            .map(|(edge, count)| (edge, *count))
+    }
+
+    /// The share of transitions out of [`ChoiceEdge::from`] that took the given edge, between `0.0` and `1.0`.
+    pub fn probability(&self, edge: &ChoiceEdge) -> f32 {
+        let total: usize = self.outgoing(&edge.from).map(|(_, count)| count).sum();
+        let count = self.transitions.get(edge).copied().unwrap_or_default();
+        if total == 0 {
+            0.0
+        } else {
+            count as f32 / total as f32
+        }
+    }
+
+    /// Renders the graph in the [DOT language](https://graphviz.org/doc/info/lang.html) used by Graphviz.
+    /// Nodes are labeled with their visit count, edges with the selected option and their probability.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph choices {\n");
+        for (node, visits) in &self.node_visits {
+            let node = escape(node);
+            let _ = writeln!(dot, "    \"{node}\" [label=\"{node}\\n{visits}\"];");
+        }
+        for (edge, count) in &self.transitions {
+            let probability = self.probability(edge) * 100.0;
+            let label = match edge.option {
+                Some(option) => format!("line {option}: {probability:.1}%"),
+                None => format!("{probability:.1}%"),
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{label}\", weight={count}];",
+                escape(&edge.from),
+                escape(&edge.to),
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a JSON object of the form
+    /// `{"nodes":[{"name":"Start","visits":2}],"transitions":[{"from":"Start","to":"A","option":10,"count":2,"probability":1.0}]}`.
+    /// `option` is `null` for transitions that did not involve a selection.
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<_> = self
+            .node_visits
+            .iter()
+            .map(|(node, visits)| format!("{{\"name\":\"{}\",\"visits\":{visits}}}", escape(node)))
+            .collect();
+        let transitions: Vec<_> = self
+            .transitions
+            .iter()
+            .map(|(edge, count)| {
+                let option = edge
+                    .option
+                    .map_or_else(|| "null".to_owned(), |option| option.to_string());
+                format!(
+                    "{{\"from\":\"{}\",\"to\":\"{}\",\"option\":{option},\"count\":{count},\"probability\":{:?}}}",
+                    escape(&edge.from),
+                    escape(&edge.to),
+                    self.probability(edge),
+                )
+            })
+            .collect();
+        format!(
+            "{{\"nodes\":[{}],\"transitions\":[{}]}}",
+            nodes.join(","),
+            transitions.join(",")
+        )
+    }
+}
+
+/// Escapes a string for use inside of double quotes in both DOT and JSON.
+pub(crate) fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for character in string.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
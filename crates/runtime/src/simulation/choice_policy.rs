@@ -0,0 +1,74 @@
+use crate::prelude::*;
+use core::fmt::Debug;
+
+/// Decides which option a simulated player selects when the [`Dialogue`] presents [`DialogueEvent::Options`].
+pub trait ChoicePolicy: Debug + Send + Sync {
+    /// Returns the [`OptionId`] of the option to select. `options` is never empty.
+    fn choose(&mut self, options: &[DialogueOption], rng: &mut SimulationRng) -> OptionId;
+}
+
+/// Selects a uniformly random option among the available ones.
+/// Falls back to all options if none of them are available, mirroring a game that does not hide unavailable options.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RandomChoicePolicy;
+
+impl ChoicePolicy for RandomChoicePolicy {
+    fn choose(&mut self, options: &[DialogueOption], rng: &mut SimulationRng) -> OptionId {
+        let available: Vec<_> = options.iter().filter(|o| o.is_available).collect();
+        if available.is_empty() {
+            options[rng.next_index(options.len())].id
+        } else {
+            available[rng.next_index(available.len())].id
+        }
+    }
+}
+
+/// Always selects the first available option, or the first option if none are available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FirstAvailableChoicePolicy;
+
+impl ChoicePolicy for FirstAvailableChoicePolicy {
+    fn choose(&mut self, options: &[DialogueOption], _rng: &mut SimulationRng) -> OptionId {
+        options
+            .iter()
+            .find(|o| o.is_available)
+            .unwrap_or(&options[0])
+            .id
+    }
+}
+
+/// A small, seedable pseudo random number generator used by simulations.
+///
+/// Uses the SplitMix64 algorithm, so a given seed produces the same sequence on every platform.
+/// It is not cryptographically secure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SimulationRng {
+    state: u64,
+}
+
+impl SimulationRng {
+    /// Creates a new generator from the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next pseudo random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a pseudo random index in `0..len`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `len` is zero.
+    pub fn next_index(&mut self, len: usize) -> usize {
+        assert!(len > 0, "Cannot pick an index from an empty range");
+        (self.next_u64() % len as u64) as usize
+    }
+}
@@ -0,0 +1,134 @@
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt::{self, Debug, Display};
+use hashbrown::HashMap;
+
+/// A predicate over the variables of a playthrough. Returns `true` if the state is valid.
+pub type InvariantPredicate = Arc<dyn Fn(&dyn VariableStorage) -> bool + Send + Sync>;
+
+/// A property of the narrative logic that must hold in every playthrough of a [`Simulator`].
+///
+/// Invariants are checked after every instruction, so a violation is caught even if a later instruction of the same node undoes it,
+/// and invariants restricted to a node see the variables at the moment the node is entered, before any of its instructions run.
+/// Each violated invariant is reported at most once per playthrough, together with the trace leading up to it. See [`InvariantViolation`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// let gold_never_negative = Invariant::always("$gold never negative", |storage| {
+///     !matches!(storage.get("$gold"), Ok(YarnValue::Number(gold)) if gold < 0.0)
+/// });
+/// let betrayal_ending = Invariant::on_node_start("Ending_B reachable only if $betrayed", "Ending_B", |storage| {
+///     matches!(storage.get("$betrayed"), Ok(YarnValue::Boolean(true)))
+/// });
+/// # let program = Program::default();
+/// let simulator = Simulator::new(program)
+///     .with_invariant(gold_never_negative)
+///     .with_invariant(betrayal_ending);
+/// ```
+#[derive(Clone)]
+pub struct Invariant {
+    name: String,
+    node: Option<String>,
+    predicate: InvariantPredicate,
+}
+
+impl Invariant {
+    /// Creates an invariant that must hold at all times.
+    pub fn always(
+        name: impl Into<String>,
+        predicate: impl Fn(&dyn VariableStorage) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            node: None,
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Creates an invariant that must hold whenever the given node is entered.
+    /// Use this to express that a node may only be reached under certain conditions.
+    pub fn on_node_start(
+        name: impl Into<String>,
+        node: impl Into<String>,
+        predicate: impl Fn(&dyn VariableStorage) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            node: Some(node.into()),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// The human-readable name of this invariant, used in [`InvariantViolation`]s.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The node this invariant is restricted to, if any.
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    /// Checks the invariant against the given variables. `started_nodes` are the nodes entered since the last check.
+    pub(crate) fn holds<'a>(
+        &self,
+        storage: &dyn VariableStorage,
+        mut started_nodes: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        match &self.node {
+            Some(node) if !started_nodes.any(|started| started == node) => true,
+            _ => (self.predicate)(storage),
+        }
+    }
+}
+
+impl Debug for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Invariant")
+            .field("name", &self.name)
+            .field("node", &self.node)
+            .field("predicate", &"<predicate>")
+            .finish()
+    }
+}
+
+/// A counterexample for an [`Invariant`] found during simulation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvariantViolation {
+    /// The name of the violated invariant.
+    pub invariant: String,
+    /// The index of the playthrough the violation occurred in. Pass it to [`Simulator::run_playthrough`] to reproduce it.
+    pub playthrough: usize,
+    /// All events emitted up to and including the batch that caused the violation.
+    pub trace: Vec<DialogueEvent>,
+    /// The options selected up to the violation.
+    pub selections: Vec<OptionId>,
+    /// The variables stored at the time of the violation.
+    pub variables: HashMap<String, YarnValue>,
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Invariant \"{}\" violated in playthrough {} after {} events",
+            self.invariant,
+            self.playthrough,
+            self.trace.len()
+        )?;
+        for event in &self.trace {
+            match event {
+                DialogueEvent::NodeStart(node) => writeln!(f, "  enter {node}")?,
+                DialogueEvent::Line(line_id, ..) => writeln!(f, "  line {line_id}")?,
+                DialogueEvent::Command(command) => writeln!(f, "  command {}", command.raw)?,
+                _ => {}
+            }
+        }
+        let selections: Vec<_> = self.selections.iter().map(|id| id.0).collect();
+        writeln!(f, "  selections: {selections:?}")
+    }
+}
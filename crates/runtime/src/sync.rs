@@ -0,0 +1,94 @@
+//! Not part of the original implementation.
+//!
+//! Locks backed by `std` when it is available and by spinlocks otherwise, so the runtime builds for `no_std` targets.
+//! A panic while a lock is held does not poison it, so the data behind it stays accessible even if the panic left it half-updated.
+
+#[cfg(feature = "std")]
+use std::sync as imp;
+
+#[cfg(not(feature = "std"))]
+use spin as imp;
+
+pub(crate) type MutexGuard<'a, T> = imp::MutexGuard<'a, T>;
+pub(crate) type RwLockReadGuard<'a, T> = imp::RwLockReadGuard<'a, T>;
+pub(crate) type RwLockWriteGuard<'a, T> = imp::RwLockWriteGuard<'a, T>;
+
+/// A mutual exclusion lock.
+#[derive(Debug, Default)]
+pub(crate) struct Mutex<T>(imp::Mutex<T>);
+
+/// A reader-writer lock.
+#[derive(Debug, Default)]
+pub(crate) struct RwLock<T>(imp::RwLock<T>);
+
+impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(imp::Mutex::new(value))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Mutex<T> {
+    /// Locks the mutex, blocking until no one else holds it.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(imp::PoisonError::into_inner)
+    }
+
+    /// Locks the mutex if no one else holds it, or returns `None` without blocking.
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        match self.0.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(imp::TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(imp::TryLockError::WouldBlock) => None,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0
+            .into_inner()
+            .unwrap_or_else(imp::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> RwLock<T> {
+    /// Locks the data for reading, blocking until no one holds it for writing.
+    pub(crate) fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(imp::PoisonError::into_inner)
+    }
+
+    /// Locks the data for writing, blocking until no one else holds it.
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.write().unwrap_or_else(imp::PoisonError::into_inner)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> Mutex<T> {
+    /// Locks the mutex, spinning until no one else holds it.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock()
+    }
+
+    /// Locks the mutex if no one else holds it, or returns `None` without spinning.
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.0.try_lock()
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> RwLock<T> {
+    /// Locks the data for reading, spinning until no one holds it for writing.
+    pub(crate) fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read()
+    }
+
+    /// Locks the data for writing, spinning until no one else holds it.
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.write()
+    }
+}
@@ -0,0 +1,193 @@
+//! Not part of the original implementation.
+//!
+//! Generates programs of a configurable size without running the compiler, e.g. for benchmarks and stress tests.
+
+use crate::prelude::*;
+use yarnspinner_core::prelude::instruction::{
+    CallFunctionInstruction, InstructionType, PopInstruction, PushFloatInstruction,
+    PushVariableInstruction, RunLineInstruction, RunNodeInstruction, StopInstruction,
+    StoreVariableInstruction,
+};
+
+/// The shape of a program generated by [`SyntheticProgram::generate`].
+///
+/// The nodes are named `Node0`, `Node1` and so on and run one after another, each jumping to the next one after its last line.
+/// The lines have consecutive IDs starting at `0`. Before each line, the number of function calls given by
+/// [`SyntheticProgram::function_calls_per_line`] adds `1` to one of the variables `$synthetic0`, `$synthetic1` and so on,
+/// using the `Number.Add` function of the standard library.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let program = SyntheticProgram {
+///     node_count: 10,
+///     lines_per_node: 100,
+///     ..Default::default()
+/// }
+/// .generate();
+///
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.add_program(program).set_node("Node0").unwrap();
+/// let events = dialogue.continue_().unwrap();
+/// assert!(events.contains(&DialogueEvent::Line(0, vec![])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyntheticProgram {
+    /// The number of nodes. Defaults to `1`.
+    pub node_count: usize,
+    /// The number of lines in every node. Defaults to `10`.
+    pub lines_per_node: usize,
+    /// The number of function calls before every line. Defaults to `1`.
+    pub function_calls_per_line: usize,
+    /// The number of distinct variables the function calls store their results in. Defaults to `1`.
+    /// If `0`, the results are discarded and no variables are accessed.
+    pub variable_count: usize,
+}
+
+impl Default for SyntheticProgram {
+    fn default() -> Self {
+        Self {
+            node_count: 1,
+            lines_per_node: 10,
+            function_calls_per_line: 1,
+            variable_count: 1,
+        }
+    }
+}
+
+impl SyntheticProgram {
+    /// Generates the program. The result is the same for the same shape.
+    pub fn generate(&self) -> Program {
+        let mut program = Program {
+            name: "Synthetic".to_owned(),
+            ..Default::default()
+        };
+        for variable in 0..self.variable_count {
+            program
+                .initial_values
+                .insert(Self::variable_name(variable), 0.0.into());
+        }
+        for node in 0..self.node_count {
+            let name = Self::node_name(node);
+            program.nodes.insert(
+                name.clone(),
+                Node {
+                    name,
+                    instructions: self.instructions(node),
+                    ..Default::default()
+                },
+            );
+        }
+        program
+    }
+
+    /// The name of the node with the given index.
+    pub fn node_name(index: usize) -> String {
+        format!("Node{index}")
+    }
+
+    /// The name of the variable with the given index, including the `$`.
+    pub fn variable_name(index: usize) -> String {
+        format!("$synthetic{index}")
+    }
+
+    /// The total number of lines, whose IDs range from `0` to this number minus one.
+    pub fn line_count(&self) -> usize {
+        self.node_count * self.lines_per_node
+    }
+
+    fn instructions(&self, node: usize) -> Vec<Instruction> {
+        let add = Type::Number.get_canonical_name_for_method(&Operator::Add.to_string());
+        let mut instructions = Vec::new();
+        for line in 0..self.lines_per_node {
+            let line_id = node * self.lines_per_node + line;
+            for call in 0..self.function_calls_per_line {
+                let variable = (self.variable_count > 0)
+                    .then(|| Self::variable_name((line_id + call) % self.variable_count));
+                instructions.push(match &variable {
+                    Some(variable_name) => InstructionType::PushVariable(PushVariableInstruction {
+                        variable_name: variable_name.clone(),
+                    }),
+                    None => InstructionType::PushFloat(PushFloatInstruction { value: 0.0 }),
+                });
+                instructions.extend([
+                    InstructionType::PushFloat(PushFloatInstruction { value: 1.0 }),
+                    // The number of parameters
+                    InstructionType::PushFloat(PushFloatInstruction { value: 2.0 }),
+                    InstructionType::CallFunc(CallFunctionInstruction {
+                        function_name: add.clone(),
+                    }),
+                ]);
+                if let Some(variable_name) = variable {
+                    instructions.push(InstructionType::StoreVariable(StoreVariableInstruction {
+                        variable_name,
+                    }));
+                }
+                instructions.push(InstructionType::Pop(PopInstruction {}));
+            }
+            instructions.push(InstructionType::RunLine(RunLineInstruction {
+                line_id: line_id as u32,
+                substitution_count: 0,
+            }));
+        }
+        instructions.push(if node + 1 < self.node_count {
+            InstructionType::RunNode(RunNodeInstruction {
+                node_name: Self::node_name(node + 1),
+            })
+        } else {
+            InstructionType::Stop(StopInstruction {})
+        });
+        instructions
+            .into_iter()
+            .map(|instruction_type| Instruction {
+                instruction_type: Some(instruction_type),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_every_line_of_every_node() {
+        let shape = SyntheticProgram {
+            node_count: 3,
+            lines_per_node: 4,
+            function_calls_per_line: 2,
+            variable_count: 5,
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue
+            .add_program(shape.generate())
+            .set_node(SyntheticProgram::node_name(0))
+            .unwrap();
+        let mut events = Vec::new();
+        while !events.contains(&DialogueEvent::DialogueComplete) {
+            events.extend(dialogue.continue_().unwrap());
+        }
+        let lines: Vec<_> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                DialogueEvent::Line(line_id, ..) => Some(line_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!((0..12).collect::<Vec<_>>(), lines);
+
+        // 24 calls spread across 5 variables
+        let total: f32 = (0..5)
+            .map(|variable| {
+                f32::try_from(
+                    dialogue
+                        .variable(&SyntheticProgram::variable_name(variable))
+                        .unwrap(),
+                )
+                .unwrap()
+            })
+            .sum();
+        assert_eq!(24.0, total);
+    }
+}
@@ -0,0 +1,446 @@
+//! Not part of the original implementation, but a port of the test plans used by the original's test suite, see `YarnSpinner.Tests/TestPlan.cs`.
+//!
+//! A test plan lists the lines, options and commands a [`Dialogue`] is expected to produce, so that the behavior of this runtime
+//! can be compared against the upstream `.testplan` files.
+
+use crate::prelude::*;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::result::Result;
+
+/// A parsed `.testplan` file, see [`TestPlan::parse`] for the format. Run it against a [`Dialogue`] via [`TestPlan::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestPlan {
+    runs: Vec<TestPlanRun>,
+}
+
+/// One run of a [`TestPlan`], i.e. the steps expected when starting the dialogue at [`TestPlanRun::start_node`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestPlanRun {
+    /// The node the dialogue is started at. Defaults to `Start`.
+    pub start_node: String,
+    /// The expected steps, each paired with the line of the plan it was read from.
+    pub steps: Vec<(usize, TestPlanStep)>,
+}
+
+/// A single expectation of a [`TestPlanRun`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestPlanStep {
+    /// A line with the given text is presented. `None` accepts any line.
+    Line(Option<String>),
+    /// An option with the given text is presented as part of the next options. `None` accepts any option.
+    Option {
+        /// The expected text of the option.
+        text: Option<String>,
+        /// Whether the option is expected to be available, i.e. not marked with `[disabled]`.
+        is_available: bool,
+    },
+    /// The option with the given one-based index is selected.
+    Select(usize),
+    /// A command with the given text is run.
+    Command(String),
+    /// The dialogue completes.
+    Stop,
+}
+
+impl Display for TestPlanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Line(Some(text)) => write!(f, "line `{text}`"),
+            Self::Line(None) => write!(f, "any line"),
+            Self::Option { text, is_available } => {
+                match text {
+                    Some(text) => write!(f, "option `{text}`")?,
+                    None => write!(f, "any option")?,
+                }
+                if !is_available {
+                    write!(f, " [disabled]")?;
+                }
+                Ok(())
+            }
+            Self::Select(index) => write!(f, "selection of option {index}"),
+            Self::Command(text) => write!(f, "command `{text}`"),
+            Self::Stop => write!(f, "end of dialogue"),
+        }
+    }
+}
+
+/// An error returned by [`TestPlan::parse`] and [`TestPlan::run`].
+#[derive(Debug)]
+pub enum TestPlanError {
+    /// The plan could not be parsed.
+    Parse {
+        /// The one-based line of the plan that could not be parsed.
+        line: usize,
+        /// What went wrong.
+        message: String,
+    },
+    /// The dialogue did not behave as the plan expected.
+    Mismatch {
+        /// The one-based line of the plan whose step failed, or `None` if the dialogue produced more than the plan expected.
+        line: Option<usize>,
+        /// The node the failing run started at.
+        start_node: String,
+        /// What the plan expected.
+        expected: String,
+        /// What the dialogue did instead.
+        actual: String,
+    },
+    /// The dialogue returned an error while running the plan.
+    Dialogue(DialogueError),
+}
+
+impl Display for TestPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "line {line} of the test plan: {message}"),
+            Self::Mismatch {
+                line,
+                start_node,
+                expected,
+                actual,
+            } => {
+                write!(f, "run starting at node \"{start_node}\"")?;
+                if let Some(line) = line {
+                    write!(f, ", step at line {line}")?;
+                }
+                write!(f, ": expected {expected}, but got {actual}")
+            }
+            Self::Dialogue(error) => write!(f, "dialogue error: {error}"),
+        }
+    }
+}
+
+impl Error for TestPlanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Dialogue(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DialogueError> for TestPlanError {
+    fn from(error: DialogueError) -> Self {
+        Self::Dialogue(error)
+    }
+}
+
+impl TestPlan {
+    /// Parses a test plan in the upstream format:
+    /// - Every non-empty line that doesn't start with `#` is one step of the form `type: value`.
+    /// - `line: text` expects a line, `option: text` an option, `command: text` a command and `stop` the end of the dialogue.
+    ///   Texts may be wrapped in backticks. A text of `*` accepts any line or option. Options may end with `[disabled]` if they are expected to be unavailable.
+    /// - `select: n` selects the `n`th of the presented options, counted from one.
+    /// - `run: Node` starts the current run at the given node instead of `Start`, and `---` separates runs.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use yarnspinner_runtime::prelude::*;
+    /// let plan = TestPlan::parse(
+    ///     "# Greets the player
+    ///      line: `Hello!`
+    ///      option: `Hi` [disabled]
+    ///      option: `Bye`
+    ///      select: 2
+    ///      stop",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(1, plan.runs().len());
+    /// assert_eq!(TestPlanStep::Select(2), plan.runs()[0].steps[3].1);
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`TestPlanError::Parse`] for unknown step types and malformed values.
+    pub fn parse(source: &str) -> Result<Self, TestPlanError> {
+        let new_run = || TestPlanRun {
+            start_node: "Start".to_owned(),
+            steps: Vec::new(),
+        };
+        let mut runs = vec![new_run()];
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "---" {
+                runs.push(new_run());
+                continue;
+            }
+            let error = |message: String| TestPlanError::Parse {
+                line: line_number,
+                message,
+            };
+            let (kind, value) = line
+                .split_once(':')
+                .map_or((line, ""), |(kind, value)| (kind.trim(), value.trim()));
+            let run = runs.last_mut().unwrap();
+            let step = match kind {
+                "line" => TestPlanStep::Line(parse_text(value)),
+                "option" => {
+                    let (value, is_available) = match value.strip_suffix("[disabled]") {
+                        Some(value) => (value.trim_end(), false),
+                        None => (value, true),
+                    };
+                    TestPlanStep::Option {
+                        text: parse_text(value),
+                        is_available,
+                    }
+                }
+                "select" => match value.parse() {
+                    Ok(index) if index > 0 => TestPlanStep::Select(index),
+                    _ => {
+                        return Err(error(format!(
+                            "expected a one-based option index, found \"{value}\""
+                        )))
+                    }
+                },
+                "command" => TestPlanStep::Command(strip_backticks(value).to_owned()),
+                "stop" => TestPlanStep::Stop,
+                "run" => {
+                    run.start_node = strip_backticks(value).to_owned();
+                    continue;
+                }
+                _ => return Err(error(format!("unknown step type \"{kind}\""))),
+            };
+            run.steps.push((line_number, step));
+        }
+        runs.retain(|run| !run.steps.is_empty());
+        Ok(Self { runs })
+    }
+
+    /// The runs of this plan, in order.
+    pub fn runs(&self) -> &[TestPlanRun] {
+        &self.runs
+    }
+
+    /// Runs every run of the plan against the given [`Dialogue`], which must already have the program under test loaded.
+    /// Lines and options are compared by the text the `text_provider` returns for them, looked up as `line:<id>`.
+    ///
+    /// Only lines, options and commands are compared, all other [`DialogueEvent`]s are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`TestPlanError::Mismatch`] for the first step the dialogue deviates from and [`TestPlanError::Dialogue`] if the dialogue fails.
+    pub fn run(
+        &self,
+        dialogue: &mut Dialogue,
+        text_provider: &dyn TextProvider,
+    ) -> Result<(), TestPlanError> {
+        for run in &self.runs {
+            run.run(dialogue, text_provider)?;
+        }
+        Ok(())
+    }
+}
+
+impl TestPlanRun {
+    fn run(
+        &self,
+        dialogue: &mut Dialogue,
+        text_provider: &dyn TextProvider,
+    ) -> Result<(), TestPlanError> {
+        let text = |line_id: u32| {
+            text_provider
+                .get_text(&LineId::from(format!("line:{line_id}")))
+                .unwrap_or_else(|| format!("[line:{line_id}]"))
+        };
+        let mismatch =
+            |line: Option<usize>, expected: String, actual: String| TestPlanError::Mismatch {
+                line,
+                start_node: self.start_node.clone(),
+                expected,
+                actual,
+            };
+        let plan_ended = |actual: String| mismatch(None, "the plan to continue".to_owned(), actual);
+        let mut steps = self.steps.iter();
+
+        dialogue.set_node(self.start_node.as_str())?;
+        while dialogue.can_continue() {
+            for event in dialogue.continue_()? {
+                match event {
+                    DialogueEvent::Line(line_id, ..) => {
+                        let actual = text(line_id);
+                        let (line, step) = steps
+                            .next()
+                            .ok_or_else(|| plan_ended(format!("line `{actual}`")))?;
+                        match step {
+                            TestPlanStep::Line(None) => {}
+                            TestPlanStep::Line(Some(expected)) if *expected == actual => {}
+                            _ => {
+                                return Err(mismatch(
+                                    Some(*line),
+                                    step.to_string(),
+                                    format!("line `{actual}`"),
+                                ))
+                            }
+                        }
+                    }
+                    DialogueEvent::Options(options) => {
+                        for option in &options {
+                            let actual = TestPlanStep::Option {
+                                text: Some(text(option.tag_id)),
+                                is_available: option.is_available,
+                            };
+                            let (line, step) =
+                                steps.next().ok_or_else(|| plan_ended(actual.to_string()))?;
+                            let matches = match (step, &actual) {
+                                (
+                                    TestPlanStep::Option { text, is_available },
+                                    TestPlanStep::Option {
+                                        text: actual_text,
+                                        is_available: actual_is_available,
+                                    },
+                                ) => {
+                                    is_available == actual_is_available
+                                        && (text.is_none() || text == actual_text)
+                                }
+                                _ => false,
+                            };
+                            if !matches {
+                                return Err(mismatch(
+                                    Some(*line),
+                                    step.to_string(),
+                                    actual.to_string(),
+                                ));
+                            }
+                        }
+                        let actual = format!("{} options", options.len());
+                        let (line, step) =
+                            steps.next().ok_or_else(|| plan_ended(actual.clone()))?;
+                        match step {
+                            TestPlanStep::Select(index) if *index <= options.len() => {
+                                dialogue.set_selected_option(options[index - 1].id)?;
+                            }
+                            _ => return Err(mismatch(Some(*line), step.to_string(), actual)),
+                        }
+                    }
+                    DialogueEvent::Command(command) => {
+                        let actual = format!("command `{}`", command.raw);
+                        let (line, step) =
+                            steps.next().ok_or_else(|| plan_ended(actual.clone()))?;
+                        match step {
+                            TestPlanStep::Command(expected) if *expected == command.raw => {}
+                            _ => return Err(mismatch(Some(*line), step.to_string(), actual)),
+                        }
+                    }
+                    DialogueEvent::DialogueComplete => {
+                        if let Some((line, step)) = steps.next() {
+                            if *step != TestPlanStep::Stop {
+                                return Err(mismatch(
+                                    Some(*line),
+                                    step.to_string(),
+                                    TestPlanStep::Stop.to_string(),
+                                ));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        match steps.next() {
+            Some((line, step)) => Err(mismatch(
+                Some(*line),
+                step.to_string(),
+                "the dialogue to stop".to_owned(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+fn strip_backticks(value: &str) -> &str {
+    value
+        .strip_prefix('`')
+        .and_then(|value| value.strip_suffix('`'))
+        .unwrap_or(value)
+}
+
+fn parse_text(value: &str) -> Option<String> {
+    (value != "*").then(|| strip_backticks(value).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::InstructionType;
+
+    #[test]
+    fn runs_plans_and_reports_the_first_deviation() {
+        let run_line = |line_id| {
+            InstructionType::RunLine(instruction::RunLineInstruction {
+                line_id,
+                substitution_count: 0,
+            })
+        };
+        let add_option = |tag_id, has_condition| {
+            InstructionType::AddOption(instruction::AddOptionInstruction {
+                tag_id,
+                destination: 0,
+                substitution_count: 0,
+                has_condition,
+            })
+        };
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                run_line(1),
+                InstructionType::PushBool(instruction::PushBoolInstruction { value: false }),
+                add_option(2, true),
+                add_option(3, false),
+                InstructionType::ShowOptions(instruction::ShowOptionsInstruction {}),
+                InstructionType::Pop(instruction::PopInstruction {}),
+                InstructionType::RunCommand(instruction::RunCommandInstruction {
+                    command_text: "fade out".to_owned(),
+                    substitution_count: 0,
+                }),
+                InstructionType::Stop(instruction::StopInstruction {}),
+            ],
+        ));
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([
+            (LineId::from("line:1"), "Hello!".to_owned()),
+            (LineId::from("line:2"), "Hi".to_owned()),
+            (LineId::from("line:3"), "Bye".to_owned()),
+        ]);
+
+        let plan = TestPlan::parse(
+            "# Options
+             line: `Hello!`
+             option: `Hi` [disabled]
+             option: *
+             select: 2
+             command: `fade out`
+             stop
+             ---
+             run: Start
+             line: *
+             option: * [disabled]
+             option: `Bye`
+             select: 2
+             command: `fade out`",
+        )
+        .unwrap();
+        assert_eq!(2, plan.runs().len());
+        plan.run(&mut dialogue, &text_provider).unwrap();
+
+        let plan =
+            TestPlan::parse("line: `Hello!`\noption: `Hi`\noption: `Bye`\nselect: 2").unwrap();
+        let error = plan.run(&mut dialogue, &text_provider).unwrap_err();
+        assert_eq!(
+            "run starting at node \"Start\", step at line 2: expected option `Hi`, but got option `Hi` [disabled]",
+            error.to_string()
+        );
+
+        assert!(matches!(
+            TestPlan::parse("line: `Hello!`\nwait: 1"),
+            Err(TestPlanError::Parse { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+//! Not part of the original implementation, but inspired by the `LineProviderBehaviour` of Yarn Spinner for Unity.
+//!
+//! The [`Dialogue`] only deals with line IDs, so resolving them to text in the current [`Language`] is up to the game.
+//! A [`TextProvider`] is the common interface for doing so.
+
+pub use self::binary::*;
+pub use self::line_metadata::*;
+#[cfg(feature = "mmap")]
+pub use self::mmap::*;
+pub use self::streaming::*;
+pub use self::string_table::*;
+pub use self::variants::*;
+use crate::prelude::*;
+use core::fmt::Debug;
+
+mod binary;
+#[cfg(feature = "std")]
+mod csv;
+mod line_metadata;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod streaming;
+mod string_table;
+mod variants;
+
+/// A trait for providing text to the game.
+///
+/// The text is looked up by the [`LineId`] of a line and is expected to be in the [`Language`] set via [`TextProvider::set_language`].
+/// Implementations should fall back to the base language when no language is set.
+pub trait TextProvider: Debug + Send + Sync {
+    /// Returns the text of the given line, or `None` if it is not known.
+    fn get_text(&self, id: &LineId) -> Option<String>;
+
+    /// Sets the language to provide text in. `None` selects the base language.
+    fn set_language(&mut self, language: Option<Language>);
+
+    /// The language text is currently provided in. `None` means the base language.
+    fn get_language(&self) -> Option<Language>;
+
+    /// Returns the [`LineMetadata`] of the given line in the current language, such as its lock,
+    /// or `None` if the line is not known or the provider does not store metadata.
+    /// Implementations should fall back to the base language like [`TextProvider::get_text`]. The default implementation returns `None`.
+    fn get_metadata(&self, _id: &LineId) -> Option<LineMetadata> {
+        None
+    }
+
+    /// Returns the ID of the line whose text is provided for the given line.
+    /// This is the line itself, unless it is a shadow line as marked by [`LineMetadata::shadowed_line`],
+    /// in which case chains of shadows are followed to the original line. Implementations of [`TextProvider::get_text`] should resolve shadow lines this way.
+    /// The default implementation follows the hashtags returned by [`TextProvider::get_metadata`].
+    fn get_source_id(&self, id: &LineId) -> LineId {
+        resolve_shadows(id, |id| self.get_metadata(id))
+    }
+
+    /// Whether the text of the current language is ready to be looked up.
+    /// Implementations that load text asynchronously should return `false` until loading has finished.
+    fn are_lines_available(&self) -> bool {
+        true
+    }
+}
+
+/// A [`TextProvider`] that keeps all text in memory.
+/// It holds a [`StringTable`] for the base language and optionally one for a translation.
+/// The translation is also used for more specific languages, see [`Language::matches`].
+///
+/// Shadow lines resolve to the text of the line they shadow in the current language.
+/// Whether a line is a shadow is looked up in the metadata of the translation first and then in that of the base language,
+/// so translations don't need to repeat the `#shadow` hashtags written by the compiler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringTableTextProvider {
+    base_language_table: StringTable,
+    translation_table: Option<(Language, StringTable)>,
+    language: Option<Language>,
+}
+
+impl StringTableTextProvider {
+    /// Creates a new text provider with empty string tables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The string table of the base language.
+    pub fn base_language_table(&self) -> &StringTable {
+        &self.base_language_table
+    }
+
+    /// The language and string table of the translation, if one was loaded.
+    pub fn translation_table(&self) -> Option<(&Language, &StringTable)> {
+        self.translation_table
+            .as_ref()
+            .map(|(language, table)| (language, table))
+    }
+
+    /// Replaces the string table of the base language.
+    pub fn set_base_language_table(&mut self, table: StringTable) -> &mut Self {
+        self.base_language_table = table;
+        self
+    }
+
+    /// Replaces the translation with the given language and string table.
+    pub fn set_translation_table(
+        &mut self,
+        language: impl Into<Language>,
+        table: StringTable,
+    ) -> &mut Self {
+        self.translation_table = Some((language.into(), table));
+        self
+    }
+
+    /// Adds the given lines to the base language's string table.
+    pub fn extend_base_language(&mut self, lines: impl IntoIterator<Item = (LineId, String)>) {
+        self.base_language_table.extend(lines);
+    }
+
+    /// Adds the given lines to the translation's string table.
+    /// If a translation for a different language was loaded before, it is replaced.
+    pub fn extend_translation(
+        &mut self,
+        language: impl Into<Language>,
+        lines: impl IntoIterator<Item = (LineId, String)>,
+    ) {
+        self.translation_table_mut(language.into()).extend(lines);
+    }
+
+    /// Adds the given [`LineMetadata`] for lines of the base language.
+    pub fn extend_base_language_metadata(
+        &mut self,
+        metadata: impl IntoIterator<Item = (LineId, LineMetadata)>,
+    ) {
+        self.base_language_table.extend_metadata(metadata);
+    }
+
+    /// Adds the given [`LineMetadata`] for lines of the translation.
+    /// If a translation for a different language was loaded before, it is replaced.
+    pub fn extend_translation_metadata(
+        &mut self,
+        language: impl Into<Language>,
+        metadata: impl IntoIterator<Item = (LineId, LineMetadata)>,
+    ) {
+        self.translation_table_mut(language.into())
+            .extend_metadata(metadata);
+    }
+
+    fn translation_table_mut(&mut self, language: Language) -> &mut StringTable {
+        match &mut self.translation_table {
+            Some((current_language, _)) if *current_language == language => {}
+            _ => self.translation_table = Some((language, StringTable::new())),
+        }
+        &mut self.translation_table.as_mut().unwrap().1
+    }
+
+    fn current_translation(&self) -> Option<&StringTable> {
+        let language = self.language.as_ref()?;
+        match &self.translation_table {
+            Some((translation_language, table)) if translation_language.matches(language) => {
+                Some(table)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TextProvider for StringTableTextProvider {
+    fn get_text(&self, id: &LineId) -> Option<String> {
+        let source_id = self.get_source_id(id);
+        if let Some(language) = &self.language {
+            match self.current_translation() {
+                Some(table) => {
+                    if let Some(text) = table.get(&source_id).or_else(|| table.get(id)) {
+                        return Some(text.to_owned());
+                    }
+                }
+                None => log::warn!(
+                    "No translation for language {language} loaded, falling back to the base language"
+                ),
+            }
+        }
+        self.base_language_table
+            .get(&source_id)
+            .or_else(|| self.base_language_table.get(id))
+            .map(ToOwned::to_owned)
+    }
+
+    fn set_language(&mut self, language: Option<Language>) {
+        self.language = language;
+    }
+
+    fn get_language(&self) -> Option<Language> {
+        self.language.clone()
+    }
+
+    fn get_metadata(&self, id: &LineId) -> Option<LineMetadata> {
+        self.current_translation()
+            .and_then(|table| table.get_metadata(id))
+            .or_else(|| self.base_language_table.get_metadata(id))
+            .cloned()
+    }
+}
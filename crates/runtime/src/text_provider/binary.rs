@@ -0,0 +1,408 @@
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+
+/// A compact, read-only string table that is looked up in place, without parsing it into a map first.
+///
+/// This is the recommended format for shipping localized text: loading a table only checks its header,
+/// and looking up a line hashes its ID and compares it against the few entries in one bucket.
+/// The bytes can live anywhere that can be viewed as a slice, e.g. in flash via `include_bytes!`, in a `Vec<u8>` or in a memory-mapped file.
+/// Tables are created with a [`BinaryStringTableBuilder`].
+///
+/// ## Format
+///
+/// All integers are little-endian `u32`s.
+/// - A 16 byte header: the magic bytes `YSST`, the format version, the number of lines and the number of buckets.
+/// - `buckets + 1` entry indices. The entries of bucket `b` are the ones from index `b` up to, but excluding, index `b + 1`.
+/// - `lines` entries of four integers each: the offset and length of the line ID, followed by the offset and length of the text.
+///   Offsets are relative to the start of the string data.
+/// - The string data, holding the UTF-8 encoded IDs and texts.
+///
+/// A line is assigned to the bucket given by the 32-bit FNV-1a hash of its ID, modulo the number of buckets.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// let mut builder = BinaryStringTableBuilder::new();
+/// builder.insert("line:1", "Hello").insert("line:2", "World");
+/// let bytes = builder.build();
+///
+/// let table = BinaryStringTable::new(bytes).unwrap();
+/// assert_eq!(Some("World"), table.get(&"line:2".into()));
+/// ```
+#[derive(Clone)]
+pub struct BinaryStringTable<B> {
+    bytes: B,
+    line_count: usize,
+    bucket_count: usize,
+}
+
+/// Errors that occur when loading a [`BinaryStringTable`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinaryStringTableError {
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    Truncated { expected: usize, actual: usize },
+}
+
+impl Error for BinaryStringTableError {}
+
+impl Display for BinaryStringTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BinaryStringTableError::*;
+        match self {
+            InvalidMagic => f.write_str("The data is not a binary string table"),
+            UnsupportedVersion(version) => write!(f, "Binary string table version {version} is not supported (expected version {})", BinaryStringTable::<()>::VERSION),
+            Truncated { expected, actual } => write!(f, "The binary string table is truncated: expected at least {expected} bytes, but got {actual}"),
+        }
+    }
+}
+
+const MAGIC: &[u8; 4] = b"YSST";
+const HEADER_SIZE: usize = 16;
+const ENTRY_SIZE: usize = 16;
+
+impl<B> BinaryStringTable<B> {
+    /// The version of the format written by [`BinaryStringTableBuilder::build`].
+    pub const VERSION: u32 = 1;
+}
+
+impl<B: AsRef<[u8]>> BinaryStringTable<B> {
+    /// Loads a string table from the given bytes.
+    ///
+    /// Only the header and the size of the index are validated.
+    /// Lines that turn out to be out of bounds or not valid UTF-8 are logged and treated as missing when they are looked up.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the bytes are not a binary string table of a supported version or are too short for the index they declare.
+    pub fn new(bytes: B) -> core::result::Result<Self, BinaryStringTableError> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_SIZE {
+            return Err(BinaryStringTableError::Truncated {
+                expected: HEADER_SIZE,
+                actual: data.len(),
+            });
+        }
+        if &data[..4] != MAGIC {
+            return Err(BinaryStringTableError::InvalidMagic);
+        }
+        let version = read_u32(data, 4).unwrap_or_default();
+        if version != Self::VERSION {
+            return Err(BinaryStringTableError::UnsupportedVersion(version));
+        }
+        let line_count = read_u32(data, 8).unwrap_or_default() as usize;
+        let bucket_count = read_u32(data, 12).unwrap_or_default() as usize;
+
+        let expected = (bucket_count.saturating_add(1))
+            .saturating_mul(4)
+            .saturating_add(line_count.saturating_mul(ENTRY_SIZE))
+            .saturating_add(HEADER_SIZE);
+        if data.len() < expected {
+            return Err(BinaryStringTableError::Truncated {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(Self {
+            bytes,
+            line_count,
+            bucket_count,
+        })
+    }
+
+    /// Gets the text of the given line, or `None` if the table does not contain it.
+    pub fn get(&self, id: &LineId) -> Option<&str> {
+        if self.bucket_count == 0 {
+            return None;
+        }
+        let data = self.bytes.as_ref();
+        let bucket = fnv1a(id.0.as_bytes()) as usize % self.bucket_count;
+        let first = read_u32(data, HEADER_SIZE + bucket * 4)? as usize;
+        let last = read_u32(data, HEADER_SIZE + (bucket + 1) * 4)? as usize;
+        (first..last.min(self.line_count))
+            .find(|entry| self.slice(*entry, 0) == Some(id.0.as_bytes()))
+            .and_then(|entry| self.str(entry, 8, id))
+    }
+
+    /// The number of lines in the table.
+    pub fn len(&self) -> usize {
+        self.line_count
+    }
+
+    /// Whether the table contains no lines.
+    pub fn is_empty(&self) -> bool {
+        self.line_count == 0
+    }
+
+    /// Iterates over all lines in the table, in no particular order.
+    /// Lines with an ID or text that is not valid UTF-8 are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        (0..self.line_count).filter_map(|entry| {
+            let id = core::str::from_utf8(self.slice(entry, 0)?).ok()?;
+            let text = core::str::from_utf8(self.slice(entry, 8)?).ok()?;
+            Some((id, text))
+        })
+    }
+
+    /// Returns the string at the offset and length stored at `field` bytes into the given entry.
+    fn slice(&self, entry: usize, field: usize) -> Option<&[u8]> {
+        let data = self.bytes.as_ref();
+        let entry_start = self.entries_start() + entry * ENTRY_SIZE + field;
+        let offset = read_u32(data, entry_start)? as usize;
+        let length = read_u32(data, entry_start + 4)? as usize;
+        let start = self.strings_start().checked_add(offset)?;
+        data.get(start..start.checked_add(length)?)
+    }
+
+    fn str(&self, entry: usize, field: usize, id: &LineId) -> Option<&str> {
+        let Some(bytes) = self.slice(entry, field) else {
+            log::error!("The text of line {id} lies outside of the binary string table");
+            return None;
+        };
+        core::str::from_utf8(bytes)
+            .map_err(|e| log::error!("The text of line {id} is not valid UTF-8: {e}"))
+            .ok()
+    }
+
+    fn entries_start(&self) -> usize {
+        HEADER_SIZE + (self.bucket_count + 1) * 4
+    }
+
+    fn strings_start(&self) -> usize {
+        self.entries_start() + self.line_count * ENTRY_SIZE
+    }
+}
+
+impl<B: AsRef<[u8]> + Send + Sync> LineSource for BinaryStringTable<B> {
+    fn fetch_line(&self, id: &LineId) -> Option<String> {
+        self.get(id).map(ToOwned::to_owned)
+    }
+}
+
+impl<B: AsRef<[u8]>> Debug for BinaryStringTable<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinaryStringTable")
+            .field("bytes", &self.bytes.as_ref().len())
+            .field("lines", &self.line_count)
+            .field("buckets", &self.bucket_count)
+            .finish()
+    }
+}
+
+/// Creates the bytes of a [`BinaryStringTable`], e.g. from the CSV string table produced by the Yarn Spinner compiler as part of a build step.
+///
+/// Inserting a line with an ID that was already inserted replaces its text.
+/// The output only depends on the inserted lines, not on the order they were inserted in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryStringTableBuilder {
+    lines: BTreeMap<String, String>,
+}
+
+impl BinaryStringTableBuilder {
+    /// Creates a new builder without any lines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a line to the table.
+    pub fn insert(&mut self, id: impl Into<LineId>, text: impl Into<String>) -> &mut Self {
+        self.lines.insert(id.into().0, text.into());
+        self
+    }
+
+    /// The number of lines added so far.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Whether no lines were added so far.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Serializes the lines into a [`BinaryStringTable`].
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the table would be larger than 4 GiB.
+    pub fn build(&self) -> Vec<u8> {
+        let line_count = self.lines.len();
+        let bucket_count = line_count.next_power_of_two();
+
+        let mut buckets: Vec<Vec<(&String, &String)>> = vec![Vec::new(); bucket_count];
+        for (id, text) in &self.lines {
+            buckets[fnv1a(id.as_bytes()) as usize % bucket_count].push((id, text));
+        }
+
+        let mut index = Vec::with_capacity((bucket_count + 1) * 4);
+        let mut entries = Vec::with_capacity(line_count * ENTRY_SIZE);
+        let mut strings = Vec::new();
+        let mut entry_count = 0;
+        for bucket in &buckets {
+            push_u32(&mut index, entry_count);
+            for (id, text) in bucket {
+                for string in [id, text] {
+                    push_u32(&mut entries, strings.len());
+                    push_u32(&mut entries, string.len());
+                    strings.extend_from_slice(string.as_bytes());
+                }
+                entry_count += 1;
+            }
+        }
+        push_u32(&mut index, entry_count);
+
+        let mut bytes =
+            Vec::with_capacity(HEADER_SIZE + index.len() + entries.len() + strings.len());
+        bytes.extend_from_slice(MAGIC);
+        push_u32(&mut bytes, BinaryStringTable::<()>::VERSION as usize);
+        push_u32(&mut bytes, line_count);
+        push_u32(&mut bytes, bucket_count);
+        bytes.extend(index);
+        bytes.extend(entries);
+        bytes.extend(strings);
+        assert!(
+            u32::try_from(bytes.len()).is_ok(),
+            "The binary string table is larger than 4 GiB"
+        );
+        bytes
+    }
+}
+
+impl<I: Into<LineId>, T: Into<String>> Extend<(I, T)> for BinaryStringTableBuilder {
+    fn extend<It: IntoIterator<Item = (I, T)>>(&mut self, lines: It) {
+        for (id, text) in lines {
+            self.insert(id, text);
+        }
+    }
+}
+
+impl<I: Into<LineId>, T: Into<String>> FromIterator<(I, T)> for BinaryStringTableBuilder {
+    fn from_iter<It: IntoIterator<Item = (I, T)>>(lines: It) -> Self {
+        let mut builder = Self::new();
+        builder.extend(lines);
+        builder
+    }
+}
+
+#[cfg(feature = "std")]
+mod csv_import {
+    use super::*;
+    use crate::text_provider::csv;
+    use std::io::{self, BufRead};
+
+    impl BinaryStringTableBuilder {
+        /// Reads all lines of a CSV string table as produced by the Yarn Spinner compiler.
+        ///
+        /// ## Errors
+        ///
+        /// Returns an error if reading fails, the table is not valid UTF-8 or it lacks an `id` or `text` column.
+        pub fn from_csv(mut reader: impl BufRead) -> io::Result<Self> {
+            let (header, _) = csv::read_record(&mut reader)?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "The string table is empty")
+            })?;
+            let id_column = csv::find_column(&header, csv::ID_COLUMN)?;
+            let text_column = csv::find_column(&header, csv::TEXT_COLUMN)?;
+
+            let mut builder = Self::new();
+            while let Some((mut record, _)) = csv::read_record(&mut reader)? {
+                if text_column >= record.len() || record.get(id_column).is_none_or(String::is_empty)
+                {
+                    continue;
+                }
+                let text = record.swap_remove(text_column);
+                builder.insert(record.swap_remove(id_column), text);
+            }
+            Ok(builder)
+        }
+    }
+}
+
+/// The 32-bit FNV-1a hash, which is stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: usize) {
+    let value = u32::try_from(value).expect("The binary string table is larger than 4 GiB");
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_every_line() {
+        let builder: BinaryStringTableBuilder = (0..100)
+            .map(|i| (format!("line:{i}"), format!("Text {i} ⇒ ü")))
+            .collect();
+        let table = BinaryStringTable::new(builder.build()).unwrap();
+
+        assert_eq!(100, table.len());
+        for i in 0..100 {
+            let expected = format!("Text {i} ⇒ ü");
+            assert_eq!(
+                Some(expected.as_str()),
+                table.get(&format!("line:{i}").into())
+            );
+        }
+        assert_eq!(None, table.get(&"line:100".into()));
+        assert_eq!(100, table.iter().count());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn builds_from_csv() {
+        let csv = "id,text,file,node,lineNumber\n\
+            line:1,Hello,a.yarn,Start,3\n\
+            line:2,\"Multi\nline, \"\"quoted\"\"\",a.yarn,Start,4\n\
+            ,No ID,a.yarn,Start,5\n";
+        let builder = BinaryStringTableBuilder::from_csv(csv.as_bytes()).unwrap();
+        let table = BinaryStringTable::new(builder.build()).unwrap();
+
+        assert_eq!(2, table.len());
+        assert_eq!(Some("Hello"), table.get(&"line:1".into()));
+        assert_eq!(Some("Multi\nline, \"quoted\""), table.get(&"line:2".into()));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let empty = BinaryStringTableBuilder::new().build();
+        let table = BinaryStringTable::new(empty.as_slice()).unwrap();
+        assert!(table.is_empty());
+        assert_eq!(None, table.get(&"line:1".into()));
+
+        let bytes = [("line:1", "Hello")]
+            .into_iter()
+            .collect::<BinaryStringTableBuilder>()
+            .build();
+        assert_eq!(
+            Err(BinaryStringTableError::InvalidMagic),
+            BinaryStringTable::new(&b"CSV,text,more,bytes"[..]).map(|_| ())
+        );
+        assert_eq!(
+            Err(BinaryStringTableError::Truncated {
+                expected: 16 + 2 * 4 + 16,
+                actual: 30
+            }),
+            BinaryStringTable::new(&bytes[..30]).map(|_| ())
+        );
+
+        // Out of bounds string data is treated as missing instead of panicking
+        let table = BinaryStringTable::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(None, table.get(&"line:1".into()));
+    }
+}
@@ -0,0 +1,150 @@
+//! A minimal reader for the CSV string tables produced by the Yarn Spinner compiler.
+//!
+//! The tables have a header row with at least the columns `id` and `text`. Fields may be quoted,
+//! in which case they can contain commas, line breaks and quotes escaped as `""`.
+
+use crate::prelude::*;
+use std::io::{self, BufRead};
+
+/// The name of the column holding the line IDs.
+pub(crate) const ID_COLUMN: &str = "id";
+
+/// The name of the column holding the text of the lines.
+pub(crate) const TEXT_COLUMN: &str = "text";
+
+/// Reads the next record. Returns the fields and the number of bytes consumed, or `None` at the end of the input.
+pub(crate) fn read_record(reader: &mut impl BufRead) -> io::Result<Option<(Vec<String>, u64)>> {
+    let mut raw = Vec::new();
+    loop {
+        let read = reader.read_until(b'\n', &mut raw)?;
+        let quotes = raw.iter().filter(|byte| **byte == b'"').count();
+        // An odd number of quotes means that a quoted field spans multiple lines
+        if read == 0 || quotes % 2 == 0 {
+            break;
+        }
+    }
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    let consumed = raw.len() as u64;
+    let record =
+        String::from_utf8(raw).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some((parse_fields(&record), consumed)))
+}
+
+/// Returns the index of the given column in the header row.
+pub(crate) fn find_column(header: &[String], name: &str) -> io::Result<usize> {
+    header
+        .iter()
+        .position(|column| column.trim_start_matches('\u{feff}') == name)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The string table has no column named \"{name}\""),
+            )
+        })
+}
+
+fn parse_fields(record: &str) -> Vec<String> {
+    let record = record.strip_suffix('\n').unwrap_or(record);
+    let record = record.strip_suffix('\r').unwrap_or(record);
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut characters = record.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if in_quotes && characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(core::mem::take(&mut field)),
+            _ => field.push(character),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// The location of a field inside of a CSV table held in memory.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FieldRange {
+    /// The byte index of the first byte of the field's content, excluding quotes.
+    pub(crate) start: usize,
+    /// The byte index after the last byte of the field's content, excluding quotes.
+    pub(crate) end: usize,
+    /// Whether the content contains quotes escaped as `""` that need to be unescaped.
+    pub(crate) escaped: bool,
+}
+
+/// Finds the fields of the record starting at `position` and advances `position` to the next record.
+/// Returns `None` at the end of the input.
+#[cfg(feature = "mmap")]
+pub(crate) fn scan_record(bytes: &[u8], position: &mut usize) -> Option<Vec<FieldRange>> {
+    if *position >= bytes.len() {
+        return None;
+    }
+    let mut fields = Vec::new();
+    loop {
+        let field = if bytes.get(*position) == Some(&b'"') {
+            let start = *position + 1;
+            let mut cursor = start;
+            let mut escaped = false;
+            let end = loop {
+                match bytes[cursor..].iter().position(|byte| *byte == b'"') {
+                    Some(offset) if bytes.get(cursor + offset + 1) == Some(&b'"') => {
+                        escaped = true;
+                        cursor += offset + 2;
+                    }
+                    Some(offset) => break cursor + offset,
+                    None => break bytes.len(),
+                }
+            };
+            // Skip the closing quote and anything up to the next separator
+            *position = end;
+            while *position < bytes.len() && !matches!(bytes[*position], b',' | b'\n') {
+                *position += 1;
+            }
+            FieldRange {
+                start,
+                end,
+                escaped,
+            }
+        } else {
+            let start = *position;
+            while *position < bytes.len() && !matches!(bytes[*position], b',' | b'\n') {
+                *position += 1;
+            }
+            let end = if *position > start && bytes[*position - 1] == b'\r' {
+                *position - 1
+            } else {
+                *position
+            };
+            FieldRange {
+                start,
+                end,
+                escaped: false,
+            }
+        };
+        fields.push(field);
+
+        match bytes.get(*position) {
+            Some(b',') => *position += 1,
+            Some(_) => {
+                *position += 1;
+                return Some(fields);
+            }
+            None => return Some(fields),
+        }
+    }
+}
+
+/// Replaces quotes escaped as `""` with a single quote.
+#[cfg(feature = "mmap")]
+pub(crate) fn unescape(field: &str) -> String {
+    field.replace("\"\"", "\"")
+}
@@ -0,0 +1,123 @@
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+
+/// The name of the string table column holding the lock of a line.
+#[cfg(feature = "std")]
+pub(crate) const LOCK_COLUMN: &str = "lock";
+
+/// The name of the string table column holding the hashtags of a line, separated by whitespace.
+#[cfg(feature = "std")]
+pub(crate) const TAGS_COLUMN: &str = "tags";
+
+/// The prefix of the hashtag that marks a line as a shadow of another line, e.g. `#shadow:line:1` or `#shadow:1`.
+/// A shadow line reuses the text of the line it shadows, but keeps its own ID and metadata, e.g. for a separate voice-over recording.
+pub const SHADOW_HASHTAG_PREFIX: &str = "shadow:";
+
+/// Additional information about a line from the columns of a string table, as returned by [`TextProvider::get_metadata`].
+///
+/// The Yarn Spinner compiler writes a `lock` column with a hash of the line's text. When a line is translated or recorded,
+/// the lock at that time can be stored alongside the translation or recording. If it no longer matches the lock of the line,
+/// the line was changed since and the translation or recording is stale.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # use std::io::Cursor;
+/// let csv = "id,text,lock,comment\nline:1,Hello,8a4f2c1d,Cheerful\n";
+/// let text_provider = StreamingTextProvider::new(ReaderLineSource::new(Cursor::new(csv)).unwrap());
+/// let metadata = text_provider.get_metadata(&"line:1".into()).unwrap();
+/// assert_eq!(Some("8a4f2c1d"), metadata.lock.as_deref());
+/// assert_eq!(Some("Cheerful"), metadata.column("comment"));
+///
+/// let csv = "id,text,node,tags\nline:2,,Start,#shadow:1 #lastline\n";
+/// let text_provider = StreamingTextProvider::new(ReaderLineSource::new(Cursor::new(csv)).unwrap());
+/// let metadata = text_provider.get_metadata(&"line:2".into()).unwrap();
+/// assert_eq!(Some("Start"), metadata.node());
+/// assert!(metadata.has_hashtag("lastline"));
+/// assert_eq!(Some(LineId::from("line:1")), metadata.shadowed_line());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineMetadata {
+    /// The value of the `lock` column, if it is present and not empty.
+    pub lock: Option<String>,
+    /// The hashtags of the line without their leading `#`, e.g. `lastline` or `shadow:line:1`, read from the `tags` column.
+    pub hashtags: Vec<String>,
+    /// The values of all other columns but `id`, `text` and `tags` that are not empty, keyed by the column name.
+    /// For tables produced by the Yarn Spinner compiler, this includes e.g. `file`, `node`, `lineNumber` and `comment`.
+    pub columns: BTreeMap<String, String>,
+}
+
+impl LineMetadata {
+    /// Gets the value of the given column, if it is present and not empty.
+    pub fn column(&self, name: &str) -> Option<&str> {
+        self.columns.get(name).map(String::as_str)
+    }
+
+    /// The name of the node the line is in, read from the `node` column.
+    pub fn node(&self) -> Option<&str> {
+        self.column("node")
+    }
+
+    /// The name of the file the line is in, read from the `file` column.
+    pub fn file(&self) -> Option<&str> {
+        self.column("file")
+    }
+
+    /// Returns `true` if the line has the given hashtag. The leading `#` may be omitted.
+    pub fn has_hashtag(&self, hashtag: &str) -> bool {
+        let hashtag = hashtag.strip_prefix('#').unwrap_or(hashtag);
+        self.hashtags.iter().any(|candidate| candidate == hashtag)
+    }
+
+    /// The ID of the line this line is a shadow of, as marked by a hashtag starting with [`SHADOW_HASHTAG_PREFIX`].
+    /// IDs without the `line:` prefix are completed, so `#shadow:1` and `#shadow:line:1` both refer to `line:1`.
+    pub fn shadowed_line(&self) -> Option<LineId> {
+        let id = self
+            .hashtags
+            .iter()
+            .find_map(|hashtag| hashtag.strip_prefix(SHADOW_HASHTAG_PREFIX))?;
+        let id = if id.starts_with("line:") {
+            id.to_owned()
+        } else {
+            format!("line:{id}")
+        };
+        Some(id.into())
+    }
+
+    /// Returns `true` if the line has no lock, no hashtags and no other metadata.
+    pub fn is_empty(&self) -> bool {
+        self.lock.is_none() && self.hashtags.is_empty() && self.columns.is_empty()
+    }
+
+    /// Creates the metadata from a record of a string table with the given header, skipping the `id` and `text` columns.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_record(
+        header: &[String],
+        record: impl IntoIterator<Item = String>,
+        id_column: usize,
+        text_column: usize,
+    ) -> Self {
+        let mut metadata = Self::default();
+        for (index, (name, value)) in header.iter().zip(record).enumerate() {
+            if index == id_column || index == text_column || value.is_empty() {
+                continue;
+            }
+            let name = name.trim_start_matches('\u{feff}');
+            if name == LOCK_COLUMN {
+                metadata.lock = Some(value);
+            } else if name == TAGS_COLUMN {
+                metadata.hashtags = value
+                    .split_whitespace()
+                    .map(|hashtag| hashtag.trim_start_matches('#').to_owned())
+                    .filter(|hashtag| !hashtag.is_empty())
+                    .collect();
+            } else {
+                metadata.columns.insert(name.to_owned(), value);
+            }
+        }
+        metadata
+    }
+}
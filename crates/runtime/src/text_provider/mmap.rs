@@ -0,0 +1,211 @@
+use crate::prelude::*;
+use crate::text_provider::csv::{self, FieldRange};
+use alloc::borrow::Cow;
+use core::fmt::{self, Debug};
+use hashbrown::HashMap;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// A [`LineSource`] that reads lines from a memory-mapped CSV string table as produced by the Yarn Spinner compiler.
+///
+/// On creation, the table is scanned once to build an index from line IDs to the byte range of their text.
+/// The text itself stays in the mapped file and is only copied when a line is fetched,
+/// so huge localization tables cost little more heap memory than their IDs.
+/// Use [`MmapLineSource::get`] to borrow the text directly from the mapping instead.
+///
+/// Requires the `mmap` feature.
+pub struct MmapLineSource {
+    mmap: Mmap,
+    index: HashMap<LineId, IndexEntry>,
+    header: Vec<String>,
+    id_column: usize,
+    text_column: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    /// The byte index at which the line's record starts, used to read its metadata.
+    record_start: usize,
+    text: FieldRange,
+}
+
+impl MmapLineSource {
+    /// Maps the string table at the given path into memory and indexes it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file cannot be opened or mapped, or if it lacks an `id` or `text` column.
+    ///
+    /// ## Safety
+    ///
+    /// The file must not be modified or truncated while the source is alive, see [`Mmap::map`].
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: Upheld by the caller.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_mmap(mmap)
+    }
+
+    /// Indexes a string table that was already mapped into memory.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the table is empty or lacks an `id` or `text` column.
+    pub fn from_mmap(mmap: Mmap) -> io::Result<Self> {
+        let bytes: &[u8] = &mmap;
+        let mut position = 0;
+        let header = csv::scan_record(bytes, &mut position).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "The string table is empty")
+        })?;
+        let header: Vec<_> = header
+            .iter()
+            .map(|field| String::from_utf8_lossy(&bytes[field.start..field.end]).into_owned())
+            .collect();
+        let id_column = csv::find_column(&header, csv::ID_COLUMN)?;
+        let text_column = csv::find_column(&header, csv::TEXT_COLUMN)?;
+
+        let mut index = HashMap::new();
+        let mut record_start = position;
+        while let Some(record) = csv::scan_record(bytes, &mut position) {
+            let entry_start = core::mem::replace(&mut record_start, position);
+            let (Some(id), Some(text)) = (record.get(id_column), record.get(text_column)) else {
+                continue;
+            };
+            match core::str::from_utf8(&bytes[id.start..id.end]) {
+                Ok("") => {}
+                Ok(id) => {
+                    let entry = IndexEntry {
+                        record_start: entry_start,
+                        text: *text,
+                    };
+                    index.insert(LineId::from(id), entry);
+                }
+                Err(e) => log::warn!("Skipping a line with an ID that is not valid UTF-8: {e}"),
+            }
+        }
+
+        Ok(Self {
+            mmap,
+            index,
+            header,
+            id_column,
+            text_column,
+        })
+    }
+
+    /// Iterates over the IDs of all lines in the table.
+    pub fn line_ids(&self) -> impl Iterator<Item = &LineId> {
+        self.index.keys()
+    }
+
+    /// Gets the text of the given line, or `None` if the table does not contain it.
+    ///
+    /// The text is borrowed from the mapped file unless it contains quotes escaped as `""`,
+    /// in which case an unescaped copy is returned.
+    pub fn get(&self, id: &LineId) -> Option<Cow<'_, str>> {
+        let range = self.index.get(id)?.text;
+        self.field(range)
+            .map_err(|e| log::error!("The text of line {id} is not valid UTF-8: {e}"))
+            .ok()
+    }
+
+    fn field(&self, range: FieldRange) -> core::result::Result<Cow<'_, str>, core::str::Utf8Error> {
+        let text = core::str::from_utf8(&self.mmap[range.start..range.end])?;
+        if range.escaped {
+            Ok(Cow::Owned(csv::unescape(text)))
+        } else {
+            Ok(Cow::Borrowed(text))
+        }
+    }
+}
+
+impl LineSource for MmapLineSource {
+    fn fetch_line(&self, id: &LineId) -> Option<String> {
+        self.get(id).map(Cow::into_owned)
+    }
+
+    fn fetch_metadata(&self, id: &LineId) -> Option<LineMetadata> {
+        let mut position = self.index.get(id)?.record_start;
+        let record = csv::scan_record(&self.mmap, &mut position)?;
+        let fields = record.into_iter().map(|range| {
+            self.field(range)
+                .map(Cow::into_owned)
+                .unwrap_or_else(|_| String::new())
+        });
+        Some(LineMetadata::from_record(
+            &self.header,
+            fields,
+            self.id_column,
+            self.text_column,
+        ))
+    }
+}
+
+impl Debug for MmapLineSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapLineSource")
+            .field("bytes", &self.mmap.len())
+            .field("lines", &self.index.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reads_lines_from_mapped_file() {
+        let path = std::env::temp_dir().join(format!(
+            "yarnspinner_mmap_line_source_{}.csv",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "\u{feff}id,text,file,node,lineNumber,lock\r\n\
+            line:1,Hello,a.yarn,Start,3,\"ab,cd\"\r\n\
+            line:2,\"Multi\nline, \"\"quoted\"\"\",a.yarn,Start,4\r\n\
+            line:3,\"Grüezi, mitenand\",a.yarn,Start,5\r\n\
+            ,Lines without an ID are skipped,a.yarn,Start,6\r\n\
+            line:4,Bye,a.yarn,Start,7",
+        )
+        .unwrap();
+
+        // SAFETY: The file is not modified while it is mapped.
+        let source = unsafe { MmapLineSource::open(&path) }.unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(4, source.line_ids().count());
+        assert_eq!(Some(Cow::Borrowed("Hello")), source.get(&"line:1".into()));
+        assert_eq!(
+            Some(Cow::Borrowed("Grüezi, mitenand")),
+            source.get(&"line:3".into())
+        );
+        assert_eq!(
+            Some("Multi\nline, \"quoted\"".to_owned()),
+            source.fetch_line(&"line:2".into())
+        );
+        assert_eq!(Some("Bye".to_owned()), source.fetch_line(&"line:4".into()));
+        assert_eq!(None, source.get(&"line:5".into()));
+        assert!(matches!(
+            source.get(&"line:1".into()),
+            Some(Cow::Borrowed(_))
+        ));
+        assert!(matches!(source.get(&"line:2".into()), Some(Cow::Owned(_))));
+
+        let metadata = source.fetch_metadata(&"line:1".into()).unwrap();
+        assert_eq!(Some("ab,cd"), metadata.lock.as_deref());
+        assert_eq!(Some("Start"), metadata.column("node"));
+        assert_eq!(None, metadata.column("text"));
+        assert_eq!(
+            Some("7"),
+            source
+                .fetch_metadata(&"line:4".into())
+                .unwrap()
+                .column("lineNumber")
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::fmt::{self, Debug};
+use hashbrown::{HashMap, HashSet};
+
+/// A source that fetches the text of individual lines on demand instead of holding all of them in memory.
+///
+/// Used by the [`StreamingTextProvider`]. Implement this for custom storage such as asset bundles or archives,
+/// or use one of the provided implementations:
+/// - [`CallbackLineSource`] calls a user-supplied function for every lookup.
+/// - [`BinaryStringTable`] looks up lines in a compact binary table, the recommended format for shipping.
+/// - [`ReaderLineSource`] reads lines from a seekable CSV string table.
+/// - `MmapLineSource` reads lines from a memory-mapped CSV string table. Requires the `mmap` feature.
+pub trait LineSource: Debug + Send + Sync {
+    /// Fetches the text of the given line, or `None` if the source does not contain it.
+    fn fetch_line(&self, id: &LineId) -> Option<String>;
+
+    /// Fetches the [`LineMetadata`] of the given line, or `None` if the source does not contain it or does not store metadata.
+    /// The default implementation returns `None`.
+    fn fetch_metadata(&self, _id: &LineId) -> Option<LineMetadata> {
+        None
+    }
+}
+
+/// A [`TextProvider`] that fetches every line from a [`LineSource`] when it is requested.
+///
+/// Only the sources themselves decide what is kept in memory, which makes this suitable for very large localized string tables
+/// on platforms with tight memory budgets. Lines missing from the source of the current language are fetched from the base language's source.
+/// Shadow lines are resolved via [`LineSource::fetch_metadata`], which costs an additional lookup per line for sources that store metadata.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # use std::io::Cursor;
+/// let csv = "id,text\nline:1,Hello\nline:2,\"Hello, \"\"you\"\"\"\n";
+/// let source = ReaderLineSource::new(Cursor::new(csv)).unwrap();
+/// let text_provider = StreamingTextProvider::new(source);
+/// assert_eq!(Some("Hello, \"you\"".to_owned()), text_provider.get_text(&"line:2".into()));
+/// ```
+#[derive(Debug)]
+pub struct StreamingTextProvider {
+    base_language_source: Box<dyn LineSource>,
+    translation_sources: HashMap<Language, Box<dyn LineSource>>,
+    language: Option<Language>,
+}
+
+impl StreamingTextProvider {
+    /// Creates a new text provider that fetches lines of the base language from the given source.
+    pub fn new(base_language_source: impl LineSource + 'static) -> Self {
+        Self {
+            base_language_source: Box::new(base_language_source),
+            translation_sources: HashMap::new(),
+            language: None,
+        }
+    }
+
+    /// Registers the source for the lines of a translation.
+    /// The source is also used for more specific languages, e.g. a source for `de` is used for `de-CH`, unless one was registered for them.
+    #[must_use]
+    pub fn with_translation(
+        mut self,
+        language: impl Into<Language>,
+        source: impl LineSource + 'static,
+    ) -> Self {
+        self.translation_sources
+            .insert(language.into(), Box::new(source));
+        self
+    }
+
+    /// The most specific translation source for the current language, see [`Language::fallback_chain`].
+    fn translation_source(&self) -> Option<&dyn LineSource> {
+        let language = self.language.as_ref()?;
+        language
+            .fallback_chain()
+            .iter()
+            .find_map(|candidate| self.translation_sources.get(candidate))
+            .map(AsRef::as_ref)
+    }
+}
+
+impl TextProvider for StreamingTextProvider {
+    fn get_text(&self, id: &LineId) -> Option<String> {
+        let fetch = |id: &LineId| {
+            self.translation_source()
+                .and_then(|source| source.fetch_line(id))
+                .or_else(|| self.base_language_source.fetch_line(id))
+        };
+        let source_id = self.get_source_id(id);
+        if source_id == *id {
+            fetch(id)
+        } else {
+            fetch(&source_id).or_else(|| fetch(id))
+        }
+    }
+
+    fn set_language(&mut self, language: Option<Language>) {
+        if let Some(language) = &language {
+            if !language
+                .fallback_chain()
+                .iter()
+                .any(|candidate| self.translation_sources.contains_key(candidate))
+            {
+                log::warn!("No translation source for language {language} registered, falling back to the base language");
+            }
+        }
+        self.language = language;
+    }
+
+    fn get_language(&self) -> Option<Language> {
+        self.language.clone()
+    }
+
+    fn get_metadata(&self, id: &LineId) -> Option<LineMetadata> {
+        self.translation_source()
+            .and_then(|source| source.fetch_metadata(id))
+            .or_else(|| self.base_language_source.fetch_metadata(id))
+    }
+}
+
+type FetchLineFn = dyn Fn(&LineId) -> Option<String> + Send + Sync;
+
+/// A [`LineSource`] that calls a function to fetch lines, e.g. from a streaming asset system.
+///
+/// If an index of the available line IDs is given, the function is only called for IDs in it,
+/// which avoids expensive lookups of lines that are known to be missing.
+#[derive(Clone)]
+pub struct CallbackLineSource {
+    index: Option<HashSet<LineId>>,
+    fetch: Arc<FetchLineFn>,
+}
+
+impl CallbackLineSource {
+    /// Creates a new source that calls `fetch` for every requested line.
+    pub fn new(fetch: impl Fn(&LineId) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self {
+            index: None,
+            fetch: Arc::new(fetch),
+        }
+    }
+
+    /// Creates a new source that calls `fetch` only for lines in the given index.
+    pub fn with_index(
+        index: impl IntoIterator<Item = LineId>,
+        fetch: impl Fn(&LineId) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            index: Some(index.into_iter().collect()),
+            fetch: Arc::new(fetch),
+        }
+    }
+}
+
+impl LineSource for CallbackLineSource {
+    fn fetch_line(&self, id: &LineId) -> Option<String> {
+        match &self.index {
+            Some(index) if !index.contains(id) => None,
+            _ => (self.fetch)(id),
+        }
+    }
+}
+
+impl Debug for CallbackLineSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackLineSource")
+            .field("index", &self.index)
+            .field("fetch", &"<fn>")
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::reader::*;
+
+#[cfg(feature = "std")]
+mod reader {
+    use super::*;
+    use crate::text_provider::csv;
+    use std::io::{self, BufReader, Read, Seek, SeekFrom};
+    use std::sync::Mutex;
+
+    /// A [`LineSource`] that reads lines from a CSV string table as produced by the Yarn Spinner compiler, e.g. a file.
+    ///
+    /// On creation, the table is scanned once to build an index from line IDs to the position of their record.
+    /// Only this index is kept in memory; the text of a line is read from the reader every time it is fetched.
+    pub struct ReaderLineSource<R> {
+        reader: Mutex<R>,
+        index: HashMap<LineId, u64>,
+        header: Vec<String>,
+        id_column: usize,
+        text_column: usize,
+    }
+
+    impl<R: Read + Seek + Send> ReaderLineSource<R> {
+        /// Indexes the string table starting at the current position of the reader.
+        ///
+        /// ## Errors
+        ///
+        /// Returns an error if reading fails, the table is not valid UTF-8 or it lacks an `id` or `text` column.
+        pub fn new(mut reader: R) -> io::Result<Self> {
+            let mut position = reader.stream_position()?;
+            let mut buffered = BufReader::new(&mut reader);
+
+            let (header, consumed) = csv::read_record(&mut buffered)?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "The string table is empty")
+            })?;
+            position += consumed;
+            let id_column = csv::find_column(&header, csv::ID_COLUMN)?;
+            let text_column = csv::find_column(&header, csv::TEXT_COLUMN)?;
+
+            let mut index = HashMap::new();
+            while let Some((record, consumed)) = csv::read_record(&mut buffered)? {
+                if let Some(id) = record.get(id_column).filter(|id| !id.is_empty()) {
+                    index.insert(LineId::from(id.as_str()), position);
+                }
+                position += consumed;
+            }
+            drop(buffered);
+
+            Ok(Self {
+                reader: Mutex::new(reader),
+                index,
+                header,
+                id_column,
+                text_column,
+            })
+        }
+
+        /// Iterates over the IDs of all lines in the table.
+        pub fn line_ids(&self) -> impl Iterator<Item = &LineId> {
+            self.index.keys()
+        }
+
+        fn read_record(&self, id: &LineId) -> Option<Vec<String>> {
+            let position = *self.index.get(id)?;
+            self.read_record_at(position).unwrap_or_else(|e| {
+                log::error!("Failed to read line {id} from the string table: {e}");
+                None
+            })
+        }
+
+        fn read_record_at(&self, position: u64) -> io::Result<Option<Vec<String>>> {
+            let mut reader = self.reader.lock().unwrap();
+            reader.seek(SeekFrom::Start(position))?;
+            let record = csv::read_record(&mut BufReader::new(&mut *reader))?;
+            Ok(record.map(|(fields, _)| fields))
+        }
+    }
+
+    impl<R: Read + Seek + Send> LineSource for ReaderLineSource<R> {
+        fn fetch_line(&self, id: &LineId) -> Option<String> {
+            let mut fields = self.read_record(id)?;
+            (self.text_column < fields.len()).then(|| fields.swap_remove(self.text_column))
+        }
+
+        fn fetch_metadata(&self, id: &LineId) -> Option<LineMetadata> {
+            let fields = self.read_record(id)?;
+            Some(LineMetadata::from_record(
+                &self.header,
+                fields,
+                self.id_column,
+                self.text_column,
+            ))
+        }
+    }
+
+    impl<R> Debug for ReaderLineSource<R> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("ReaderLineSource")
+                .field("lines", &self.index.len())
+                .field("text_column", &self.text_column)
+                .finish()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::io::Cursor;
+
+    const STRING_TABLE: &str = "id,text,file,node,lineNumber\n\
+        line:1,Hello,a.yarn,Start,3\n\
+        line:2,\"Multi\nline, \"\"quoted\"\"\",a.yarn,Start,4\r\n\
+        line:3,Bye,a.yarn,Start,5";
+
+    #[test]
+    fn reads_lines_from_seekable_csv() {
+        let source = ReaderLineSource::new(Cursor::new(STRING_TABLE)).unwrap();
+        assert_eq!(3, source.line_ids().count());
+        assert_eq!(Some("Bye".to_owned()), source.fetch_line(&"line:3".into()));
+        assert_eq!(
+            Some("Multi\nline, \"quoted\"".to_owned()),
+            source.fetch_line(&"line:2".into())
+        );
+        assert_eq!(
+            Some("Hello".to_owned()),
+            source.fetch_line(&"line:1".into())
+        );
+        assert_eq!(None, source.fetch_line(&"line:4".into()));
+    }
+
+    #[test]
+    fn falls_back_to_base_language() {
+        let base = ReaderLineSource::new(Cursor::new(STRING_TABLE)).unwrap();
+        let german = ReaderLineSource::new(Cursor::new("id,text\nline:1,Hallo\n")).unwrap();
+        let mut text_provider = StreamingTextProvider::new(base).with_translation("de-CH", german);
+
+        text_provider.set_language(Some("de-CH".into()));
+        assert_eq!(
+            Some("Hallo".to_owned()),
+            text_provider.get_text(&"line:1".into())
+        );
+        assert_eq!(
+            Some("Bye".to_owned()),
+            text_provider.get_text(&"line:3".into())
+        );
+
+        text_provider.set_language(None);
+        assert_eq!(
+            Some("Hello".to_owned()),
+            text_provider.get_text(&"line:1".into())
+        );
+    }
+
+    #[test]
+    fn reads_metadata_of_current_language() {
+        let base = ReaderLineSource::new(Cursor::new(
+            "id,text,lock,comment\nline:1,Hello,aaaa,Greeting\nline:2,Bye,,\n",
+        ))
+        .unwrap();
+        let german =
+            ReaderLineSource::new(Cursor::new("id,lock,text\nline:1,bbbb,Hallo\n")).unwrap();
+        let mut text_provider = StreamingTextProvider::new(base).with_translation("de", german);
+
+        let metadata = text_provider.get_metadata(&"line:1".into()).unwrap();
+        assert_eq!(Some("aaaa"), metadata.lock.as_deref());
+        assert_eq!(Some("Greeting"), metadata.column("comment"));
+        assert!(text_provider
+            .get_metadata(&"line:2".into())
+            .unwrap()
+            .is_empty());
+
+        // The source for "de" is used for "de-CH" as well
+        text_provider.set_language(Some("de-CH".into()));
+        let metadata = text_provider.get_metadata(&"line:1".into()).unwrap();
+        assert_eq!(Some("bbbb"), metadata.lock.as_deref());
+        assert_eq!(None, metadata.column("comment"));
+        assert_eq!(None, text_provider.get_metadata(&"line:3".into()));
+    }
+
+    #[test]
+    fn callback_source_only_fetches_indexed_lines() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let source = CallbackLineSource::with_index(["line:1".into()], move |id| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Some(format!("text of {id}"))
+        });
+
+        assert_eq!(
+            Some("text of line:1".to_owned()),
+            source.fetch_line(&"line:1".into())
+        );
+        assert_eq!(None, source.fetch_line(&"line:2".into()));
+        assert_eq!(1, fetches.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// The text and [`LineMetadata`] of the lines of one language, keyed by their [`LineId`].
+/// Used by the [`StringTableTextProvider`] for the base language and the translation.
+///
+/// Lines marked as a shadow of another line via a hashtag like `#shadow:line:1`, see [`LineMetadata::shadowed_line`],
+/// transparently resolve to the text of the line they shadow, so a shadow line doesn't need a text of its own.
+/// Its metadata is still its own, e.g. to look up a separate voice-over recording.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// let mut table = StringTable::new();
+/// table.insert("line:1", "Hello!");
+/// table.insert_metadata(
+///     "line:2",
+///     LineMetadata {
+///         hashtags: vec!["shadow:line:1".to_owned()],
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(Some("Hello!"), table.get(&"line:2".into()));
+/// assert_eq!(LineId::from("line:1"), table.source_id(&"line:2".into()));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringTable {
+    texts: HashMap<LineId, String>,
+    metadata: HashMap<LineId, LineMetadata>,
+}
+
+impl StringTable {
+    /// Creates an empty string table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text of a line, returning its previous text.
+    pub fn insert(&mut self, id: impl Into<LineId>, text: impl Into<String>) -> Option<String> {
+        self.texts.insert(id.into(), text.into())
+    }
+
+    /// Sets the [`LineMetadata`] of a line, returning its previous metadata.
+    pub fn insert_metadata(
+        &mut self,
+        id: impl Into<LineId>,
+        metadata: LineMetadata,
+    ) -> Option<LineMetadata> {
+        self.metadata.insert(id.into(), metadata)
+    }
+
+    /// Adds the given [`LineMetadata`], replacing the metadata of lines that already have some.
+    pub fn extend_metadata(&mut self, metadata: impl IntoIterator<Item = (LineId, LineMetadata)>) {
+        self.metadata.extend(metadata);
+    }
+
+    /// Gets the text of a line. Shadow lines resolve to the text of the line they shadow, see [`StringTable::source_id`].
+    /// If that line is not in the table, the shadow line's own text is used, if any.
+    pub fn get(&self, id: &LineId) -> Option<&str> {
+        self.texts
+            .get(&self.source_id(id))
+            .or_else(|| self.texts.get(id))
+            .map(String::as_str)
+    }
+
+    /// Gets the [`LineMetadata`] of a line. Shadow lines have their own metadata, so these are not resolved.
+    pub fn get_metadata(&self, id: &LineId) -> Option<&LineMetadata> {
+        self.metadata.get(id)
+    }
+
+    /// The ID of the line whose text is used for the given line.
+    /// This is the line itself unless it is a shadow line, in which case chains of shadows are followed to the original line.
+    pub fn source_id(&self, id: &LineId) -> LineId {
+        resolve_shadows(id, |id| self.metadata.get(id).cloned())
+    }
+
+    /// Returns `true` if the table has a text or metadata for the line.
+    pub fn contains(&self, id: &LineId) -> bool {
+        self.texts.contains_key(id) || self.metadata.contains_key(id)
+    }
+
+    /// The number of lines with a text of their own.
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Returns `true` if no line has a text of its own.
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// Iterates over the lines with a text of their own in arbitrary order, without resolving shadow lines.
+    pub fn iter(&self) -> impl Iterator<Item = (&LineId, &str)> {
+        self.texts.iter().map(|(id, text)| (id, text.as_str()))
+    }
+}
+
+impl Extend<(LineId, String)> for StringTable {
+    fn extend<I: IntoIterator<Item = (LineId, String)>>(&mut self, lines: I) {
+        self.texts.extend(lines);
+    }
+}
+
+impl FromIterator<(LineId, String)> for StringTable {
+    fn from_iter<I: IntoIterator<Item = (LineId, String)>>(lines: I) -> Self {
+        Self {
+            texts: lines.into_iter().collect(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+/// Follows the shadow hashtags of the given line to the line it ultimately shadows, as found by `metadata`.
+/// Cyclic shadows are reported and resolve to the line itself.
+pub(crate) fn resolve_shadows(
+    id: &LineId,
+    metadata: impl Fn(&LineId) -> Option<LineMetadata>,
+) -> LineId {
+    let mut chain = vec![id.clone()];
+    while let Some(shadowed) = metadata(chain.last().unwrap()).and_then(|m| m.shadowed_line()) {
+        if chain.contains(&shadowed) {
+            log::warn!("Line {id} is part of a cycle of shadow lines, using its own text");
+            return id.clone();
+        }
+        chain.push(shadowed);
+    }
+    chain.pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shadow_of(id: &str) -> LineMetadata {
+        LineMetadata {
+            hashtags: vec![format!("{SHADOW_HASHTAG_PREFIX}{id}")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolves_chains_and_cycles_of_shadow_lines() {
+        let mut table: StringTable = [
+            (LineId::from("line:a"), "Original".to_owned()),
+            (LineId::from("line:d"), "Own text".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        table.insert_metadata("line:b", shadow_of("a"));
+        table.insert_metadata("line:c", shadow_of("line:b"));
+        table.insert_metadata("line:d", shadow_of("line:e"));
+        table.insert_metadata("line:e", shadow_of("line:d"));
+
+        assert_eq!(LineId::from("line:a"), table.source_id(&"line:c".into()));
+        assert_eq!(Some("Original"), table.get(&"line:c".into()));
+        assert_eq!(Some("Own text"), table.get(&"line:d".into()));
+        assert_eq!(None, table.get(&"line:e".into()));
+        assert_eq!(2, table.len());
+        assert!(table.contains(&"line:b".into()));
+    }
+}
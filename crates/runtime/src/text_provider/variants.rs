@@ -0,0 +1,202 @@
+use crate::prelude::*;
+use core::fmt::Debug;
+
+/// The prefix of the string table columns holding the variants of a line, see [`VariantTextProvider`].
+pub const VARIANT_COLUMN_PREFIX: &str = "#";
+
+/// Chooses which variant of a line to present, e.g. based on the gender or formality the player picked.
+/// Consulted by a [`VariantTextProvider`] whenever it resolves a line that has variants.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # use std::sync::{Arc, RwLock};
+/// #[derive(Debug)]
+/// struct PlayerGender(Arc<RwLock<String>>);
+///
+/// impl LineVariantSelector for PlayerGender {
+///     fn select_variant(&self, _line_id: &LineId, variants: &[&str]) -> Option<String> {
+///         let gender = self.0.read().unwrap();
+///         variants.contains(&gender.as_str()).then(|| gender.clone())
+///     }
+/// }
+/// ```
+pub trait LineVariantSelector: Debug + Send + Sync {
+    /// Returns the key of the variant to present for the given line, or `None` to present its default text.
+    /// `variants` holds the keys of all variants available for the line in the current language, e.g. `["fem", "masc"]`.
+    fn select_variant(&self, line_id: &LineId, variants: &[&str]) -> Option<String>;
+}
+
+/// A [`TextProvider`] that resolves lines to one of their variants, as chosen by a [`LineVariantSelector`].
+///
+/// The variants of a line are the metadata columns of its string table whose names start with [`VARIANT_COLUMN_PREFIX`],
+/// so a line tagged with e.g. `#fem` and `#masc` in the localization spreadsheet has the columns `#fem` and `#masc` holding the texts of those variants.
+/// Since the metadata is looked up in the current language, each translation can define the variants it needs,
+/// e.g. only languages whose verb forms change with the player character's gender.
+///
+/// Lines without variants, and lines for which the selector chooses none or one that doesn't exist, resolve to their default text.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// # use std::io::Cursor;
+/// #[derive(Debug)]
+/// struct Feminine;
+///
+/// impl LineVariantSelector for Feminine {
+///     fn select_variant(&self, _line_id: &LineId, _variants: &[&str]) -> Option<String> {
+///         Some("fem".to_owned())
+///     }
+/// }
+///
+/// let csv = "id,text,#fem\nline:1,Du bist bereit.,\nline:2,Du bist ein Held.,Du bist eine Heldin.\n";
+/// let source = ReaderLineSource::new(Cursor::new(csv)).unwrap();
+/// let text_provider = VariantTextProvider::new(StreamingTextProvider::new(source), Feminine);
+/// assert_eq!(Some("Du bist bereit.".to_owned()), text_provider.get_text(&"line:1".into()));
+/// assert_eq!(Some("Du bist eine Heldin.".to_owned()), text_provider.get_text(&"line:2".into()));
+/// ```
+#[derive(Debug)]
+pub struct VariantTextProvider<T> {
+    text_provider: T,
+    selector: Box<dyn LineVariantSelector>,
+}
+
+impl<T: TextProvider> VariantTextProvider<T> {
+    /// Wraps the given text provider, resolving variants with the given selector.
+    pub fn new(text_provider: T, selector: impl LineVariantSelector + 'static) -> Self {
+        Self {
+            text_provider,
+            selector: Box::new(selector),
+        }
+    }
+
+    /// Replaces the [`LineVariantSelector`].
+    pub fn set_selector(&mut self, selector: impl LineVariantSelector + 'static) -> &mut Self {
+        self.selector = Box::new(selector);
+        self
+    }
+
+    /// The wrapped text provider.
+    pub fn text_provider(&self) -> &T {
+        &self.text_provider
+    }
+
+    /// See [`VariantTextProvider::text_provider`].
+    pub fn text_provider_mut(&mut self) -> &mut T {
+        &mut self.text_provider
+    }
+
+    /// Returns the wrapped text provider.
+    pub fn into_inner(self) -> T {
+        self.text_provider
+    }
+}
+
+impl<T: TextProvider> TextProvider for VariantTextProvider<T> {
+    fn get_text(&self, id: &LineId) -> Option<String> {
+        let variant = self.text_provider.get_metadata(id).and_then(|metadata| {
+            let variants: Vec<_> = metadata
+                .columns
+                .keys()
+                .filter_map(|column| column.strip_prefix(VARIANT_COLUMN_PREFIX))
+                .collect();
+            if variants.is_empty() {
+                return None;
+            }
+            let key = self.selector.select_variant(id, &variants)?;
+            metadata
+                .column(&format!("{VARIANT_COLUMN_PREFIX}{key}"))
+                .map(ToOwned::to_owned)
+        });
+        variant.or_else(|| self.text_provider.get_text(id))
+    }
+
+    fn set_language(&mut self, language: Option<Language>) {
+        self.text_provider.set_language(language);
+    }
+
+    fn get_language(&self) -> Option<Language> {
+        self.text_provider.get_language()
+    }
+
+    fn get_metadata(&self, id: &LineId) -> Option<LineMetadata> {
+        self.text_provider.get_metadata(id)
+    }
+
+    fn get_source_id(&self, id: &LineId) -> LineId {
+        self.text_provider.get_source_id(id)
+    }
+
+    fn are_lines_available(&self) -> bool {
+        self.text_provider.are_lines_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::RwLock;
+    use alloc::sync::Arc;
+
+    #[derive(Debug)]
+    struct Formality(Arc<RwLock<Option<String>>>);
+
+    impl LineVariantSelector for Formality {
+        fn select_variant(&self, _line_id: &LineId, _variants: &[&str]) -> Option<String> {
+            self.0.read().clone()
+        }
+    }
+
+    #[test]
+    fn selects_variants_per_language() {
+        let metadata = |variants: &[(&str, &str)]| LineMetadata {
+            columns: variants
+                .iter()
+                .map(|(key, text)| (format!("#{key}"), text.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language([(LineId::from("line:1"), "How are you?".to_owned())]);
+        text_provider.extend_translation(
+            "de",
+            [(LineId::from("line:1"), "Wie geht es dir?".to_owned())],
+        );
+        text_provider.extend_translation_metadata(
+            "de",
+            [(
+                LineId::from("line:1"),
+                metadata(&[("formal", "Wie geht es Ihnen?")]),
+            )],
+        );
+        let formality = Arc::new(RwLock::default());
+        *formality.write() = Some("formal".to_owned());
+        let mut text_provider =
+            VariantTextProvider::new(text_provider, Formality(formality.clone()));
+        let line_id = LineId::from("line:1");
+
+        assert_eq!(
+            Some("How are you?".to_owned()),
+            text_provider.get_text(&line_id)
+        );
+        text_provider.set_language(Some("de".into()));
+        assert_eq!(
+            Some("Wie geht es Ihnen?".to_owned()),
+            text_provider.get_text(&line_id)
+        );
+        *formality.write() = None;
+        assert_eq!(
+            Some("Wie geht es dir?".to_owned()),
+            text_provider.get_text(&line_id)
+        );
+        *formality.write() = Some("casual".to_owned());
+        assert_eq!(
+            Some("Wie geht es dir?".to_owned()),
+            text_provider.get_text(&line_id)
+        );
+    }
+}
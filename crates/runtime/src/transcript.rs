@@ -0,0 +1,323 @@
+//! Not part of the original implementation.
+//!
+//! Records what was said and chosen in a [`Dialogue`] as a backlog, e.g. for the history screen of a visual novel.
+
+use crate::prelude::*;
+use crate::simulation::escape;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::fmt::Write;
+
+/// An entry of a [`TranscriptRecorder`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TranscriptEntry {
+    /// A line was presented.
+    Line {
+        /// The ID of the line, as in [`DialogueEvent::Line`].
+        line_id: u32,
+        /// The node the line was presented in.
+        node_name: Option<Arc<str>>,
+        /// The final text of the line as shown to the player, set via [`TranscriptRecorder::set_line_text`].
+        text: Option<String>,
+    },
+    /// An option was chosen via [`Dialogue::set_selected_option`].
+    OptionChosen {
+        /// The chosen option.
+        option: DialogueOption,
+        /// The node the option was presented in.
+        node_name: Option<Arc<str>>,
+        /// The final text of the option as shown to the player, set via [`TranscriptRecorder::set_line_text`].
+        text: Option<String>,
+    },
+    /// A command was run.
+    Command(Command),
+}
+
+impl TranscriptEntry {
+    /// The text of the entry for [`TranscriptRecorder::to_plain_text`]. Lines and options without text are shown by their ID.
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            Self::Line {
+                text: Some(text), ..
+            } => text.clone(),
+            Self::Line { line_id, .. } => format!("[line {line_id}]"),
+            Self::OptionChosen {
+                text: Some(text), ..
+            } => format!("> {text}"),
+            Self::OptionChosen { option, .. } => format!("> [line {}]", option.tag_id),
+            Self::Command(command) => format!("<<{}>>", command.raw),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let string_or_null = |string: Option<&str>| {
+            string.map_or_else(|| "null".to_owned(), |s| format!("\"{}\"", escape(s)))
+        };
+        match self {
+            Self::Line {
+                line_id,
+                node_name,
+                text,
+            } => format!(
+                "{{\"type\":\"line\",\"line_id\":{line_id},\"node\":{},\"text\":{}}}",
+                string_or_null(node_name.as_deref()),
+                string_or_null(text.as_deref()),
+            ),
+            Self::OptionChosen {
+                option,
+                node_name,
+                text,
+            } => format!(
+                "{{\"type\":\"option\",\"line_id\":{},\"option_id\":{},\"node\":{},\"text\":{}}}",
+                option.tag_id,
+                option.id,
+                string_or_null(node_name.as_deref()),
+                string_or_null(text.as_deref()),
+            ),
+            Self::Command(command) => format!(
+                "{{\"type\":\"command\",\"text\":\"{}\"}}",
+                escape(&command.raw)
+            ),
+        }
+    }
+
+    /// The ID of the line or option text, if any.
+    fn line_id(&self) -> Option<u32> {
+        match self {
+            Self::Line { line_id, .. } => Some(*line_id),
+            Self::OptionChosen { option, .. } => Some(option.tag_id),
+            Self::Command(_) => None,
+        }
+    }
+
+    fn text_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            Self::Line { text, .. } | Self::OptionChosen { text, .. } => Some(text),
+            Self::Command(_) => None,
+        }
+    }
+}
+
+/// Records the lines, chosen options and commands of a [`Dialogue`] as a backlog.
+///
+/// Set one via [`Dialogue::set_transcript_recorder`] and the dialogue feeds it with every event it returns and every option selected.
+/// Since the dialogue only knows line IDs, pass the final text of a line, i.e. with its substitutions and markup resolved,
+/// to [`TranscriptRecorder::set_line_text`] once it is presented.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+/// dialogue.set_transcript_recorder(Some(TranscriptRecorder::with_capacity(100)));
+/// // After the dialogue returned DialogueEvent::Line(3, vec![]) and its text was presented:
+/// # dialogue.transcript_recorder_mut().unwrap().record_event(&DialogueEvent::Line(3, vec![]));
+/// let recorder = dialogue.transcript_recorder_mut().unwrap();
+/// recorder.set_line_text(3, "Guard: Halt! Who goes there?");
+///
+/// assert_eq!("Guard: Halt! Who goes there?\n", recorder.to_plain_text());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TranscriptRecorder {
+    entries: VecDeque<TranscriptEntry>,
+    capacity: Option<usize>,
+    current_node: Option<Arc<str>>,
+    pending_options: Vec<DialogueOption>,
+}
+
+impl TranscriptRecorder {
+    /// Creates a recorder that keeps every entry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a recorder that keeps only the last `capacity` entries, discarding the oldest ones.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Default::default()
+        }
+    }
+
+    /// The maximum number of entries kept, if any.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Records the relevant parts of an event. Called by the [`Dialogue`] for every event it returns.
+    pub fn record_event(&mut self, event: &DialogueEvent) {
+        match event {
+            DialogueEvent::Line(line_id, ..) => self.push(TranscriptEntry::Line {
+                line_id: *line_id,
+                node_name: self.current_node.clone(),
+                text: None,
+            }),
+            DialogueEvent::Options(options) => self.pending_options = options.clone(),
+            DialogueEvent::Command(command) => self.push(TranscriptEntry::Command(command.clone())),
+            DialogueEvent::NodeStart(node_name) => self.current_node = Some(node_name.clone()),
+            DialogueEvent::DialogueComplete => self.current_node = None,
+            DialogueEvent::LineInterrupted(_)
+            | DialogueEvent::LineFiltered(_)
+            | DialogueEvent::LineHints(_)
+            | DialogueEvent::Wait(_)
+            | DialogueEvent::BreakpointHit(_)
+            | DialogueEvent::NodeComplete(_) => {}
+        }
+    }
+
+    /// Records the selection of one of the options of the last [`DialogueEvent::Options`]. Called by [`Dialogue::set_selected_option`].
+    pub fn record_selection(&mut self, option_id: OptionId) {
+        let Some(option) = self
+            .pending_options
+            .iter()
+            .find(|option| option.id == option_id)
+            .cloned()
+        else {
+            return;
+        };
+        self.pending_options.clear();
+        self.push(TranscriptEntry::OptionChosen {
+            option,
+            node_name: self.current_node.clone(),
+            text: None,
+        });
+    }
+
+    /// Sets the final text of the most recent line or chosen option with the given ID whose text was not set yet.
+    /// Returns `false` if there is no such entry.
+    pub fn set_line_text(&mut self, line_id: u32, text: impl Into<String>) -> bool {
+        let Some(entry_text) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .filter(|entry| entry.line_id() == Some(line_id))
+            .filter_map(TranscriptEntry::text_mut)
+            .find(|text| text.is_none())
+        else {
+            return false;
+        };
+        *entry_text = Some(text.into());
+        true
+    }
+
+    /// All recorded entries, oldest first.
+    pub fn transcript(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &TranscriptEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    /// The last `n` recorded entries, oldest first.
+    pub fn last_n(
+        &self,
+        n: usize,
+    ) -> impl DoubleEndedIterator<Item = &TranscriptEntry> + ExactSizeIterator {
+        self.entries
+            .iter()
+            .skip(self.entries.len().saturating_sub(n))
+    }
+
+    /// The number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Discards all entries, e.g. when starting a new game.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.pending_options.clear();
+    }
+
+    /// Renders the transcript with one entry per line, see [`TranscriptEntry::to_plain_text`].
+    pub fn to_plain_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            // Writing to a `String` cannot fail
+            let _ = writeln!(text, "{}", entry.to_plain_text());
+        }
+        text
+    }
+
+    /// Renders the transcript as a JSON array of the form
+    /// `[{"type":"line","line_id":3,"node":"Start","text":"Hi!"},{"type":"option","line_id":4,"option_id":0,"node":"Start","text":null},{"type":"command","text":"fade_out"}]`.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<_> = self.entries.iter().map(TranscriptEntry::to_json).collect();
+        format!("[{}]", entries.join(","))
+    }
+
+    fn push(&mut self, entry: TranscriptEntry) {
+        if self.capacity == Some(0) {
+            return;
+        }
+        if Some(self.entries.len()) == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+    use yarnspinner_core::prelude::instruction::*;
+
+    #[test]
+    fn records_lines_options_and_commands() {
+        let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+        dialogue.add_program(program_with_instructions(
+            "Start",
+            [
+                InstructionType::RunLine(RunLineInstruction {
+                    line_id: 1,
+                    substitution_count: 0,
+                }),
+                InstructionType::AddOption(AddOptionInstruction {
+                    tag_id: 2,
+                    destination: 4,
+                    substitution_count: 0,
+                    has_condition: false,
+                }),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                InstructionType::RunCommand(RunCommandInstruction {
+                    command_text: "fade_out".to_owned(),
+                    substitution_count: 0,
+                }),
+            ],
+        ));
+        dialogue.set_transcript_recorder(Some(TranscriptRecorder::with_capacity(2)));
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_().unwrap();
+        let recorder = dialogue.transcript_recorder_mut().unwrap();
+        assert!(recorder.set_line_text(1, "Guard: \"Halt!\""));
+        assert!(!recorder.set_line_text(1, "Guard: Again?"));
+
+        dialogue.continue_().unwrap();
+        dialogue.set_selected_option(OptionId(0)).unwrap();
+        let recorder = dialogue.transcript_recorder_mut().unwrap();
+        recorder.set_line_text(2, "Me.");
+        assert_eq!("Guard: \"Halt!\"\n> Me.\n", recorder.to_plain_text());
+
+        dialogue.continue_().unwrap();
+        let recorder = dialogue.transcript_recorder().unwrap();
+        assert_eq!(2, recorder.len());
+        assert_eq!(
+            vec![TranscriptEntry::Command(
+                Command::parse("fade_out".to_owned()).unwrap()
+            )],
+            recorder.last_n(1).cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            r#"[{"type":"option","line_id":2,"option_id":0,"node":"Start","text":"Me."},{"type":"command","text":"fade_out"}]"#,
+            recorder.to_json()
+        );
+    }
+}
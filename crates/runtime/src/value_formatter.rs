@@ -0,0 +1,250 @@
+//! Not part of the original implementation.
+//!
+//! Formats the values substituted into `{0}`-style placeholders of lines according to the [`Language`] they are presented in.
+
+use crate::prelude::*;
+use core::fmt::Debug;
+
+/// Formats a value substituted into a line, e.g. to use the decimal separator of the player's language or a game-defined currency format.
+///
+/// Placeholders may carry a format name after a colon, e.g. `{0:currency}`. The compiler only emits plain placeholders like `{0}`,
+/// so format names are added by hand in the string tables, which lets translators pick the format that suits their language.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// #[derive(Debug)]
+/// struct Gold;
+///
+/// impl ValueFormatter for Gold {
+///     fn format_value(&self, value: &YarnValue, format: Option<&str>, language: &Language) -> String {
+///         let formatted = LocaleValueFormatter.format_value(value, None, language);
+///         match format {
+///             Some("gold") => format!("{formatted} G"),
+///             _ => formatted,
+///         }
+///     }
+/// }
+///
+/// let text = expand_substitutions("You owe {0:gold}.", &[YarnValue::Number(2.5)], &Language::new("de"), &Gold);
+/// assert_eq!("You owe 2,5 G.", text);
+/// ```
+pub trait ValueFormatter: Debug + Send + Sync {
+    /// Formats the value for a line in the given language. `format` is the format name of the placeholder, if any.
+    fn format_value(&self, value: &YarnValue, format: Option<&str>, language: &Language) -> String;
+}
+
+/// The default [`ValueFormatter`]. Writes numbers with the decimal separator of the language, e.g. `3,5` in German,
+/// and everything else like its [`Display`](core::fmt::Display) implementation. Format names are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LocaleValueFormatter;
+
+impl LocaleValueFormatter {
+    /// Returns the decimal separator of the given language, which is `,` for most of continental Europe and `.` otherwise.
+    pub fn decimal_separator(language: &Language) -> char {
+        const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+            "af", "az", "be", "bg", "bs", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi",
+            "fo", "fr", "gl", "hr", "hu", "hy", "id", "is", "it", "ka", "kk", "ky", "lt", "lv",
+            "mk", "mn", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sq", "sr",
+            "sv", "tr", "uk", "uz", "vi",
+        ];
+        // Regions of the above languages that use a decimal point instead
+        const DECIMAL_POINT_REGIONS: &[(&str, &str)] = &[
+            ("de", "CH"),
+            ("de", "LI"),
+            ("it", "CH"),
+            ("es", "MX"),
+            ("es", "US"),
+            ("es", "PR"),
+            ("es", "DO"),
+            ("es", "GT"),
+            ("es", "HN"),
+            ("es", "NI"),
+            ("es", "PA"),
+            ("es", "PE"),
+            ("es", "SV"),
+        ];
+        let primary_language = language.primary_language();
+        let uses_point_in_region = language
+            .region()
+            .is_some_and(|region| DECIMAL_POINT_REGIONS.contains(&(primary_language, region)));
+        if DECIMAL_COMMA_LANGUAGES.contains(&primary_language) && !uses_point_in_region {
+            ','
+        } else {
+            '.'
+        }
+    }
+}
+
+impl ValueFormatter for LocaleValueFormatter {
+    fn format_value(
+        &self,
+        value: &YarnValue,
+        _format: Option<&str>,
+        language: &Language,
+    ) -> String {
+        match value {
+            YarnValue::Number(number) => {
+                let formatted = number.to_string();
+                match Self::decimal_separator(language) {
+                    '.' => formatted,
+                    separator => formatted.replace('.', separator.encode_utf8(&mut [0; 4])),
+                }
+            }
+            value => value.to_string(),
+        }
+    }
+}
+
+/// A [`ValueFormatter`] that delegates to the formatter registered for the most specific matching [`Language`],
+/// e.g. the one for `de` when formatting for `de-CH` if none was registered for `de-CH` itself.
+/// Falls back to a [`LocaleValueFormatter`] by default.
+#[derive(Debug)]
+pub struct LanguageValueFormatters {
+    formatters: Vec<(Language, Box<dyn ValueFormatter>)>,
+    fallback: Box<dyn ValueFormatter>,
+}
+
+impl Default for LanguageValueFormatters {
+    fn default() -> Self {
+        Self {
+            formatters: Vec::new(),
+            fallback: Box::new(LocaleValueFormatter),
+        }
+    }
+}
+
+impl LanguageValueFormatters {
+    /// Creates a set of formatters that only uses a [`LocaleValueFormatter`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the formatter for the given language, replacing the previous one.
+    #[must_use]
+    pub fn with_formatter(
+        mut self,
+        language: impl Into<Language>,
+        formatter: impl ValueFormatter + 'static,
+    ) -> Self {
+        let language = language.into();
+        self.formatters
+            .retain(|(existing_language, _)| *existing_language != language);
+        self.formatters.push((language, Box::new(formatter)));
+        self
+    }
+
+    /// Sets the formatter used for languages without a registered formatter.
+    #[must_use]
+    pub fn with_fallback(mut self, formatter: impl ValueFormatter + 'static) -> Self {
+        self.fallback = Box::new(formatter);
+        self
+    }
+}
+
+impl ValueFormatter for LanguageValueFormatters {
+    fn format_value(&self, value: &YarnValue, format: Option<&str>, language: &Language) -> String {
+        language
+            .fallback_chain()
+            .iter()
+            .find_map(|candidate| {
+                self.formatters
+                    .iter()
+                    .find(|(language, _)| language == candidate)
+            })
+            .map_or(self.fallback.as_ref(), |(_, formatter)| formatter.as_ref())
+            .format_value(value, format, language)
+    }
+}
+
+/// Replaces the placeholders `{0}`, `{1}`, etc. of a line's text with the formatted substitutions.
+/// Placeholders may name a format like `{0:currency}`, which is passed to the [`ValueFormatter`].
+/// Placeholders without a matching substitution are left untouched.
+pub fn expand_substitutions(
+    text: &str,
+    substitutions: &[YarnValue],
+    language: &Language,
+    formatter: &dyn ValueFormatter,
+) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let substitution = rest.find('}').and_then(|end| {
+            let placeholder = &rest[1..end];
+            let (index, format) = match placeholder.split_once(':') {
+                Some((index, format)) => (index, Some(format)),
+                None => (placeholder, None),
+            };
+            let value = substitutions.get(index.parse::<usize>().ok()?)?;
+            Some((end, formatter.format_value(value, format, language)))
+        });
+        match substitution {
+            Some((end, formatted)) => {
+                expanded.push_str(&formatted);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Seconds;
+
+    impl ValueFormatter for Seconds {
+        fn format_value(
+            &self,
+            value: &YarnValue,
+            format: Option<&str>,
+            language: &Language,
+        ) -> String {
+            match (format, value) {
+                (Some("duration"), YarnValue::Number(seconds)) => {
+                    format!("{}:{:02}", *seconds as u32 / 60, *seconds as u32 % 60)
+                }
+                _ => LocaleValueFormatter.format_value(value, format, language),
+            }
+        }
+    }
+
+    #[test]
+    fn formats_substitutions_per_language() {
+        let formatters = LanguageValueFormatters::new().with_formatter("fr", Seconds);
+        let substitutions = [
+            YarnValue::Number(3.5),
+            YarnValue::Number(75.0),
+            YarnValue::String("Ada".to_owned()),
+        ];
+        let text = "{2}: {0} km in {1:duration} {3} {x}";
+
+        assert_eq!(
+            "Ada: 3.5 km in 75 {3} {x}",
+            expand_substitutions(text, &substitutions, &Language::new("en"), &formatters)
+        );
+        assert_eq!(
+            "Ada: 3,5 km in 75 {3} {x}",
+            expand_substitutions(text, &substitutions, &Language::new("de-AT"), &formatters)
+        );
+        assert_eq!(
+            "Ada: 3.5 km in 75 {3} {x}",
+            expand_substitutions(text, &substitutions, &Language::new("de-CH"), &formatters)
+        );
+        assert_eq!(
+            "Ada: 3,5 km in 1:15 {3} {x}",
+            expand_substitutions(text, &substitutions, &Language::new("fr-CA"), &formatters)
+        );
+    }
+}
@@ -1,10 +1,18 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>, which we split off into multiple files
 use crate::prelude::*;
+use crate::sync::RwLock;
+use alloc::sync::Arc;
 use core::any::Any;
 use core::error::Error;
 use core::fmt::{self, Debug, Display};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use hashbrown::HashMap;
+
+pub use self::{layered::*, read_only::*, validating::*, variable_name::*};
+
+mod layered;
+mod read_only;
+mod validating;
+mod variable_name;
 
 #[allow(missing_docs)]
 pub type Result<T> = core::result::Result<T, VariableStorageError>;
@@ -38,6 +46,20 @@ pub trait VariableStorage: Debug + Send + Sync {
     fn variables(&self) -> HashMap<String, YarnValue>;
     /// Clears all variables in this variable storage.
     fn clear(&mut self);
+    /// Returns the names of all variables in this variable storage.
+    fn keys(&self) -> Vec<String> {
+        self.variables().into_keys().collect()
+    }
+    /// Removes all variables for which the predicate returns `false`.
+    ///
+    /// The default implementation clears the storage and writes the retained variables back.
+    /// Implementations should override this if they can remove variables directly.
+    fn retain(&mut self, predicate: &mut dyn FnMut(&str, &YarnValue) -> bool) -> Result<()> {
+        let mut variables = self.variables();
+        variables.retain(|name, value| predicate(name, value));
+        self.clear();
+        self.extend(variables)
+    }
     /// Gets the [`VariableStorage`] as a trait object.
     /// This allows retrieving the concrete type by downcasting, using the `downcast_ref` method available through the `Any` trait.
     fn as_any(&self) -> &dyn Any;
@@ -46,6 +68,66 @@ pub trait VariableStorage: Debug + Send + Sync {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+/// Typed convenience methods for all [`VariableStorage`]s, including `dyn VariableStorage`.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut storage = MemoryVariableStorage::new();
+/// storage.set_typed("$gold", 10).unwrap();
+/// storage.extend_from([("$name", "Sally"), ("$title", "Captain")]).unwrap();
+/// let gold: u32 = storage.get_as("$gold").unwrap();
+/// assert_eq!(10, gold);
+/// assert!(storage.get_as::<bool>("$name").is_err());
+/// ```
+pub trait VariableStorageExt: VariableStorage {
+    /// Gets the value of a variable converted into `T`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`VariableStorage::get`], or [`VariableStorageError::InvalidCast`] if the value cannot be converted.
+    fn get_as<T>(&self, name: &str) -> Result<T>
+    where
+        T: TryFrom<YarnValue>,
+        T::Error: Error + Send + Sync + 'static,
+    {
+        let value = self.get(name)?;
+        T::try_from(value).map_err(|error| VariableStorageError::InvalidCast {
+            name: name.to_owned(),
+            error: Box::new(error),
+        })
+    }
+
+    /// Sets the value of a variable from anything that converts into a [`YarnValue`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`VariableStorage::set`].
+    fn set_typed(&mut self, name: impl Into<String>, value: impl Into<YarnValue>) -> Result<()> {
+        self.set(name.into(), value.into())
+    }
+
+    /// Sets the values of many variables at once, see [`VariableStorage::extend`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns the errors of [`VariableStorage::extend`].
+    fn extend_from<N, V>(&mut self, values: impl IntoIterator<Item = (N, V)>) -> Result<()>
+    where
+        N: Into<String>,
+        V: Into<YarnValue>,
+    {
+        let values = values
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        self.extend(values)
+    }
+}
+
+impl<T: VariableStorage + ?Sized> VariableStorageExt for T {}
+
 impl Extend<(String, YarnValue)> for Box<dyn VariableStorage> {
     fn extend<T: IntoIterator<Item = (String, YarnValue)>>(&mut self, iter: T) {
         let hash_map = iter.into_iter().collect();
@@ -56,10 +138,33 @@ impl Extend<(String, YarnValue)> for Box<dyn VariableStorage> {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum VariableStorageError {
-    InvalidVariableName { name: String },
-    VariableNotFound { name: String },
-    InternalError { error: Box<dyn Error + Send + Sync> },
+    InvalidVariableName {
+        name: String,
+    },
+    VariableNotFound {
+        name: String,
+    },
+    ReadOnly {
+        name: String,
+    },
+    TypeMismatch {
+        name: String,
+        #[cfg_attr(feature = "defmt", defmt(Display2Format))]
+        expected: Type,
+        #[cfg_attr(feature = "defmt", defmt(Display2Format))]
+        actual: Type,
+    },
+    InvalidCast {
+        name: String,
+        #[cfg_attr(feature = "defmt", defmt(Display2Format))]
+        error: Box<dyn Error + Send + Sync>,
+    },
+    InternalError {
+        #[cfg_attr(feature = "defmt", defmt(Display2Format))]
+        error: Box<dyn Error + Send + Sync>,
+    },
 }
 
 impl Error for VariableStorageError {}
@@ -68,8 +173,12 @@ impl Display for VariableStorageError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use VariableStorageError::*;
         match self {
-            InvalidVariableName { name } => write!(f, "{name} is not a valid variable name: Variable names must start with a \'$\'. (Did you mean to use \'${name}\'?)"),
+            InvalidVariableName { name } if !name.starts_with('$') => write!(f, "{name} is not a valid variable name: Variable names must start with a \'$\'. (Did you mean to use \'${name}\'?)"),
+            InvalidVariableName { name } => write!(f, "{name} is not a valid variable name: After the \'$\', variable names must start with a letter or \'_\' and only contain letters, digits, \'_\' and \'.\'"),
             VariableNotFound { name } => write!(f, "Variable name {name} is not defined"),
+            ReadOnly { name } => write!(f, "Cannot set {name} because the variable storage is read-only"),
+            InvalidCast { name, error } => write!(f, "Cannot convert the value of {name}: {error}"),
+            TypeMismatch { name, expected, actual } => write!(f, "Cannot store a value of type {actual} in {name}, which is declared as {expected}"),
             InternalError { error } => write!(f, "Internal variable storage error: {error}"),
         }
     }
@@ -99,33 +208,40 @@ impl VariableStorage for MemoryVariableStorage {
 
     fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
         Self::validate_name(&name)?;
-        self.0.write().unwrap().insert(name, value);
+        self.0.write().insert(name, value);
         Ok(())
     }
 
     fn get(&self, name: &str) -> Result<YarnValue> {
         Self::validate_name(name)?;
-        self.0.read().unwrap().get(name).cloned().ok_or_else(|| {
-            VariableStorageError::VariableNotFound {
+        self.0
+            .read()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VariableStorageError::VariableNotFound {
                 name: name.to_string(),
-            }
-        })
+            })
     }
 
     fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
         for name in values.keys() {
             Self::validate_name(name)?;
         }
-        self.0.write().unwrap().extend(values);
+        self.0.write().extend(values);
         Ok(())
     }
 
     fn variables(&self) -> HashMap<String, YarnValue> {
-        self.0.read().unwrap().clone()
+        self.0.read().clone()
     }
 
     fn clear(&mut self) {
-        self.0.write().unwrap().clear();
+        self.0.write().clear();
+    }
+
+    fn retain(&mut self, predicate: &mut dyn FnMut(&str, &YarnValue) -> bool) -> Result<()> {
+        self.0.write().retain(|name, value| predicate(name, value));
+        Ok(())
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -140,7 +256,7 @@ impl VariableStorage for MemoryVariableStorage {
 impl MemoryVariableStorage {
     fn validate_name(name: impl AsRef<str>) -> Result<()> {
         let name = name.as_ref();
-        if name.starts_with('$') {
+        if is_valid_variable_name(name) {
             Ok(())
         } else {
             Err(VariableStorageError::InvalidVariableName {
@@ -0,0 +1,301 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::any::Any;
+use core::fmt::{self, Debug};
+use hashbrown::HashMap;
+
+type LayerFn = dyn Fn(&str) -> VariableLayer + Send + Sync;
+
+/// One of the two layers of a [`LayeredVariableStorage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VariableLayer {
+    /// The persistent storage, e.g. the one backing the save game.
+    Base,
+    /// The transient storage on top of it.
+    Overlay,
+}
+
+/// Decides which layer of a [`LayeredVariableStorage`] a variable is written to.
+#[derive(Clone)]
+pub enum LayerPolicy {
+    /// All variables are written to the overlay and the base is never modified,
+    /// e.g. to preview a dialogue without dirtying the save game.
+    Overlay,
+    /// Variables whose name starts with the given prefix, including the `$`, are written to the overlay, all others to the base.
+    /// For example, `$temp_` keeps per-conversation temporaries out of the save game.
+    Prefix(String),
+    /// The layer is chosen by the given function, which is passed the variable name.
+    Custom(Arc<LayerFn>),
+}
+
+impl LayerPolicy {
+    /// Creates a [`LayerPolicy::Custom`] from the given function.
+    pub fn custom(layer_fn: impl Fn(&str) -> VariableLayer + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(layer_fn))
+    }
+
+    /// The layer the variable with the given name is written to.
+    pub fn layer_for(&self, name: &str) -> VariableLayer {
+        match self {
+            Self::Overlay => VariableLayer::Overlay,
+            Self::Prefix(prefix) if name.starts_with(prefix.as_str()) => VariableLayer::Overlay,
+            Self::Prefix(_) => VariableLayer::Base,
+            Self::Custom(layer_fn) => layer_fn(name),
+        }
+    }
+}
+
+impl Debug for LayerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overlay => write!(f, "Overlay"),
+            Self::Prefix(prefix) => f.debug_tuple("Prefix").field(prefix).finish(),
+            Self::Custom(_) => f.debug_tuple("Custom").field(&"<fn>").finish(),
+        }
+    }
+}
+
+/// A [`VariableStorage`] that stacks a transient overlay on top of a persistent base.
+///
+/// Reads check the overlay first and fall through to the base, so a variable in the overlay shadows the one in the base.
+/// Writes go to the layer chosen by the [`LayerPolicy`]. The default policy, [`LayerPolicy::Overlay`], never modifies the base.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// let save_game = MemoryVariableStorage::new();
+/// let mut storage = LayeredVariableStorage::new(Box::new(save_game.clone()))
+///     .with_policy(LayerPolicy::Prefix("$temp_".to_owned()));
+/// storage.set("$gold".to_owned(), 10.0.into()).unwrap();
+/// storage.set("$temp_mood".to_owned(), "grumpy".into()).unwrap();
+///
+/// assert!(save_game.contains("$gold"));
+/// assert!(!save_game.contains("$temp_mood"));
+/// assert!(storage.contains("$temp_mood"));
+///
+/// // Once the conversation is over
+/// storage.clear_overlay();
+/// assert!(!storage.contains("$temp_mood"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LayeredVariableStorage {
+    base: Box<dyn VariableStorage>,
+    overlay: Box<dyn VariableStorage>,
+    policy: LayerPolicy,
+}
+
+impl LayeredVariableStorage {
+    /// Creates a storage on top of the given base, with an empty [`MemoryVariableStorage`] as overlay and the [`LayerPolicy::Overlay`] policy.
+    pub fn new(base: Box<dyn VariableStorage>) -> Self {
+        Self {
+            base,
+            overlay: Box::new(MemoryVariableStorage::new()),
+            policy: LayerPolicy::Overlay,
+        }
+    }
+
+    /// Sets the storage used as overlay.
+    #[must_use]
+    pub fn with_overlay(mut self, overlay: Box<dyn VariableStorage>) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Sets the policy that decides which layer variables are written to.
+    #[must_use]
+    pub fn with_policy(mut self, policy: LayerPolicy) -> Self {
+        self.set_policy(policy);
+        self
+    }
+
+    /// Sets the policy that decides which layer variables are written to.
+    /// Variables that were already written stay in their layer.
+    pub fn set_policy(&mut self, policy: LayerPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The policy that decides which layer variables are written to.
+    pub fn policy(&self) -> &LayerPolicy {
+        &self.policy
+    }
+
+    /// The persistent storage below the overlay.
+    pub fn base(&self) -> &dyn VariableStorage {
+        self.base.as_ref()
+    }
+
+    /// See [`LayeredVariableStorage::base`]. Writes through this bypass the policy.
+    pub fn base_mut(&mut self) -> &mut dyn VariableStorage {
+        self.base.as_mut()
+    }
+
+    /// The transient storage on top of the base.
+    pub fn overlay(&self) -> &dyn VariableStorage {
+        self.overlay.as_ref()
+    }
+
+    /// See [`LayeredVariableStorage::overlay`]. Writes through this bypass the policy.
+    pub fn overlay_mut(&mut self) -> &mut dyn VariableStorage {
+        self.overlay.as_mut()
+    }
+
+    /// Discards all variables in the overlay, e.g. at the end of a conversation or preview.
+    pub fn clear_overlay(&mut self) {
+        self.overlay.clear();
+    }
+
+    /// Writes all variables of the overlay into the base and clears the overlay, e.g. to keep the outcome of a previewed dialogue.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error of the base storage, in which case the overlay is left untouched.
+    pub fn commit_overlay(&mut self) -> Result<()> {
+        VariableStorage::extend(self.base.as_mut(), self.overlay.variables())?;
+        self.overlay.clear();
+        Ok(())
+    }
+
+    fn layer_mut(&mut self, name: &str) -> &mut dyn VariableStorage {
+        match self.policy.layer_for(name) {
+            VariableLayer::Base => self.base.as_mut(),
+            VariableLayer::Overlay => self.overlay.as_mut(),
+        }
+    }
+}
+
+impl VariableStorage for LayeredVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
+        self.layer_mut(&name).set(name, value)
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        match self.overlay.get(name) {
+            Err(VariableStorageError::VariableNotFound { .. }) => self.base.get(name),
+            result => result,
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.overlay.contains(name) || self.base.contains(name)
+    }
+
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        let (overlay_values, base_values): (HashMap<_, _>, HashMap<_, _>) = values
+            .into_iter()
+            .partition(|(name, _)| self.policy.layer_for(name) == VariableLayer::Overlay);
+        VariableStorage::extend(self.base.as_mut(), base_values)?;
+        VariableStorage::extend(self.overlay.as_mut(), overlay_values)
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        let mut variables = self.base.variables();
+        variables.extend(self.overlay.variables());
+        variables
+    }
+
+    /// Clears both layers. Use [`LayeredVariableStorage::clear_overlay`] to only clear the overlay.
+    fn clear(&mut self) {
+        self.overlay.clear();
+        self.base.clear();
+    }
+
+    /// Applies the predicate to both layers separately, so it also sees variables of the base that are shadowed by the overlay.
+    fn retain(&mut self, predicate: &mut dyn FnMut(&str, &YarnValue) -> bool) -> Result<()> {
+        self.overlay.retain(predicate)?;
+        self.base.retain(predicate)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_without_modifying_base() {
+        let mut base = MemoryVariableStorage::new();
+        base.set("$gold".to_owned(), 10.0.into()).unwrap();
+        let mut storage = LayeredVariableStorage::new(Box::new(base.clone()));
+
+        storage.set("$gold".to_owned(), 5.0.into()).unwrap();
+        storage.set("$met_guard".to_owned(), true.into()).unwrap();
+        assert_eq!(YarnValue::Number(5.0), storage.get("$gold").unwrap());
+        assert_eq!(YarnValue::Number(10.0), base.get("$gold").unwrap());
+        assert!(!base.contains("$met_guard"));
+        assert_eq!(2, storage.variables().len());
+        assert!(matches!(
+            storage.get("$unknown"),
+            Err(VariableStorageError::VariableNotFound { .. })
+        ));
+        assert!(matches!(
+            storage.get("gold"),
+            Err(VariableStorageError::InvalidVariableName { .. })
+        ));
+
+        storage.commit_overlay().unwrap();
+        assert_eq!(YarnValue::Number(5.0), base.get("$gold").unwrap());
+        assert!(base.contains("$met_guard"));
+        assert!(storage.overlay().variables().is_empty());
+    }
+
+    #[test]
+    fn retains_in_both_layers() {
+        let mut base = MemoryVariableStorage::new();
+        base.extend_from([("$gold", 10.0), ("$temp_bonus", 1.0)])
+            .unwrap();
+        let mut storage = LayeredVariableStorage::new(Box::new(base.clone()));
+        storage.set_typed("$temp_streak", 2).unwrap();
+
+        storage
+            .retain(&mut |name, _| !name.starts_with("$temp_"))
+            .unwrap();
+        assert_eq!(vec!["$gold".to_owned()], storage.keys());
+        assert_eq!(10.0, storage.get_as::<f32>("$gold").unwrap());
+        assert!(!base.contains("$temp_bonus"));
+    }
+
+    #[test]
+    fn writes_to_layer_chosen_by_policy() {
+        let base = MemoryVariableStorage::new();
+        let mut storage = LayeredVariableStorage::new(Box::new(base.clone())).with_policy(
+            LayerPolicy::custom(|name| {
+                if name.starts_with("$Yarn.Internal") {
+                    VariableLayer::Overlay
+                } else {
+                    VariableLayer::Base
+                }
+            }),
+        );
+        storage
+            .extend(HashMap::from([
+                ("$Yarn.Internal.Visiting.Start".to_owned(), 1.0.into()),
+                ("$gold".to_owned(), 3.0.into()),
+            ]))
+            .unwrap();
+        assert_eq!(
+            vec!["$gold".to_owned()],
+            base.variables().into_keys().collect::<Vec<_>>()
+        );
+        assert!(storage.contains("$Yarn.Internal.Visiting.Start"));
+
+        storage.clear_overlay();
+        assert!(!storage.contains("$Yarn.Internal.Visiting.Start"));
+        assert!(storage.contains("$gold"));
+    }
+}
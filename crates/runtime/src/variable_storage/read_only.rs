@@ -0,0 +1,135 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use core::any::Any;
+use hashbrown::HashMap;
+
+/// A [`VariableStorage`] that allows reading the variables of another storage, but fails all writes with [`VariableStorageError::ReadOnly`].
+///
+/// Useful to run dialogue that must not have side effects, e.g. barks that only check conditions.
+/// Note that running a node also writes the variable tracking its visit count, so such dialogue can only use nodes with tracking disabled,
+/// and [`Dialogue::set_node_statistics_tracking`] must stay disabled.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut save_game = MemoryVariableStorage::new();
+/// save_game.set("$gold".to_owned(), 10.0.into()).unwrap();
+/// let mut storage = ReadOnlyVariableStorage::new(Box::new(save_game));
+/// assert_eq!(YarnValue::Number(10.0), storage.get("$gold").unwrap());
+/// assert!(matches!(
+///     storage.set("$gold".to_owned(), 0.0.into()),
+///     Err(VariableStorageError::ReadOnly { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadOnlyVariableStorage(Box<dyn VariableStorage>);
+
+impl ReadOnlyVariableStorage {
+    /// Wraps the given storage.
+    pub fn new(inner: Box<dyn VariableStorage>) -> Self {
+        Self(inner)
+    }
+
+    /// The wrapped storage.
+    pub fn inner(&self) -> &dyn VariableStorage {
+        self.0.as_ref()
+    }
+
+    /// Returns the wrapped storage, making it writable again.
+    pub fn into_inner(self) -> Box<dyn VariableStorage> {
+        self.0
+    }
+}
+
+impl VariableStorage for ReadOnlyVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, _value: YarnValue) -> Result<()> {
+        Err(VariableStorageError::ReadOnly { name })
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        self.0.get(name)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains(name)
+    }
+
+    /// Fails unless `values` is empty.
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        match values.into_keys().next() {
+            Some(name) => Err(VariableStorageError::ReadOnly { name }),
+            None => Ok(()),
+        }
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        self.0.variables()
+    }
+
+    /// Does nothing besides logging a warning, since this cannot fail.
+    fn clear(&mut self) {
+        log::warn!("Ignoring an attempt to clear a read-only variable storage");
+    }
+
+    /// Fails unless the predicate keeps all variables.
+    fn retain(&mut self, predicate: &mut dyn FnMut(&str, &YarnValue) -> bool) -> Result<()> {
+        match self
+            .0
+            .variables()
+            .into_iter()
+            .find(|(name, value)| !predicate(name, value))
+        {
+            Some((name, _)) => Err(VariableStorageError::ReadOnly { name }),
+            None => Ok(()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_lines;
+
+    #[test]
+    fn runs_nodes_without_writing() {
+        let mut save_game = MemoryVariableStorage::new();
+        save_game.set("$gold".to_owned(), 10.0.into()).unwrap();
+        let storage = ReadOnlyVariableStorage::new(Box::new(save_game.clone()));
+        let mut dialogue = Dialogue::new(Box::new(storage));
+        dialogue
+            .add_program(program_with_lines("Start", [1]))
+            .set_node("Start")
+            .unwrap();
+
+        assert_eq!(
+            Some(&DialogueEvent::Line(1, vec![])),
+            dialogue.continue_().unwrap().last()
+        );
+        dialogue.continue_().unwrap();
+        assert_eq!(DialogueState::Stopped, dialogue.state());
+        assert_eq!(1, save_game.variables().len());
+
+        dialogue.set_node_statistics_tracking(true);
+        assert!(matches!(
+            dialogue.set_node("Start"),
+            Err(DialogueError::VariableStorageError(
+                VariableStorageError::ReadOnly { .. }
+            ))
+        ));
+    }
+}
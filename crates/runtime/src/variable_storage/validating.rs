@@ -0,0 +1,183 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use core::any::Any;
+use hashbrown::HashMap;
+use yarnspinner_core::types::TypedValue;
+
+/// A [`VariableStorage`] that checks the type of every written value against the type the variable was declared with,
+/// failing with [`VariableStorageError::TypeMismatch`] if they differ. Variables without a declaration can be written freely.
+///
+/// The types are usually taken from the initial values of a [`Program`] via [`ValidatingVariableStorage::declare_program`],
+/// which catches e.g. game code storing a string in a variable the Yarn script declared as a number.
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut storage = ValidatingVariableStorage::new(Box::new(MemoryVariableStorage::new()))
+///     .with_declaration("$gold", Type::Number);
+/// storage.set("$gold".to_owned(), 10.0.into()).unwrap();
+/// assert!(matches!(
+///     storage.set("$gold".to_owned(), "lots".into()),
+///     Err(VariableStorageError::TypeMismatch { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ValidatingVariableStorage {
+    inner: Box<dyn VariableStorage>,
+    declarations: HashMap<String, Type>,
+}
+
+impl ValidatingVariableStorage {
+    /// Wraps the given storage without any declarations.
+    pub fn new(inner: Box<dyn VariableStorage>) -> Self {
+        Self {
+            inner,
+            declarations: HashMap::new(),
+        }
+    }
+
+    /// Declares the type of a variable. [`Type::Any`] allows all values.
+    #[must_use]
+    pub fn with_declaration(mut self, name: impl Into<String>, r#type: Type) -> Self {
+        self.declare(name, r#type);
+        self
+    }
+
+    /// Declares the type of a variable. [`Type::Any`] allows all values.
+    pub fn declare(&mut self, name: impl Into<String>, r#type: Type) -> &mut Self {
+        self.declarations.insert(name.into(), r#type);
+        self
+    }
+
+    /// Declares every variable that has an initial value in the given program with the type of that value.
+    #[must_use]
+    pub fn with_program(mut self, program: &Program) -> Self {
+        self.declare_program(program);
+        self
+    }
+
+    /// Declares every variable that has an initial value in the given program with the type of that value.
+    pub fn declare_program(&mut self, program: &Program) -> &mut Self {
+        for (name, value) in &program.initial_values {
+            let value: YarnValue = value.clone().into();
+            self.declare(name.clone(), value.r#type());
+        }
+        self
+    }
+
+    /// The declared type of a variable, if any.
+    pub fn declaration(&self, name: &str) -> Option<&Type> {
+        self.declarations.get(name)
+    }
+
+    /// The wrapped storage.
+    pub fn inner(&self) -> &dyn VariableStorage {
+        self.inner.as_ref()
+    }
+
+    /// Returns the wrapped storage.
+    pub fn into_inner(self) -> Box<dyn VariableStorage> {
+        self.inner
+    }
+
+    fn validate(&self, name: &str, value: &YarnValue) -> Result<()> {
+        match self.declarations.get(name) {
+            Some(expected) if *expected != Type::Any && *expected != value.r#type() => {
+                Err(VariableStorageError::TypeMismatch {
+                    name: name.to_owned(),
+                    expected: expected.clone(),
+                    actual: value.r#type(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl VariableStorage for ValidatingVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
+        self.validate(&name, &value)?;
+        self.inner.set(name, value)
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        self.inner.get(name)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.inner.contains(name)
+    }
+
+    /// Fails without writing anything if any of the values has the wrong type.
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        for (name, value) in &values {
+            self.validate(name, value)?;
+        }
+        VariableStorage::extend(self.inner.as_mut(), values)
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        self.inner.variables()
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    fn retain(&mut self, predicate: &mut dyn FnMut(&str, &YarnValue) -> bool) -> Result<()> {
+        self.inner.retain(predicate)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yarnspinner_core::prelude::Operand;
+
+    #[test]
+    fn validates_against_declarations_of_program() {
+        let program = Program {
+            initial_values: [
+                ("$gold".to_owned(), Operand::from(0.0)),
+                ("$name".to_owned(), Operand::from("Sally".to_owned())),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let mut dialogue = Dialogue::new(Box::new(
+            ValidatingVariableStorage::new(Box::new(MemoryVariableStorage::new()))
+                .with_program(&program),
+        ));
+        dialogue.add_program(program);
+        let storage = dialogue.variable_storage_mut();
+
+        storage.set("$gold".to_owned(), 3.0.into()).unwrap();
+        storage.set("$undeclared".to_owned(), true.into()).unwrap();
+        let error = storage.set("$name".to_owned(), 1.0.into()).unwrap_err();
+        assert!(matches!(
+            error,
+            VariableStorageError::TypeMismatch {
+                expected: Type::String,
+                actual: Type::Number,
+                ..
+            }
+        ));
+        assert_eq!(YarnValue::from("Sally"), storage.get("$name").unwrap());
+    }
+}
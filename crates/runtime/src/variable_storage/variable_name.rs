@@ -0,0 +1,63 @@
+//! Not part of the original implementation.
+
+use crate::prelude::*;
+use alloc::borrow::Cow;
+
+/// How names passed to [`Dialogue::set_variable`] and [`Dialogue::variable`] are canonicalized, see [`canonicalize_variable_name`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VariableNameMode {
+    /// Names must be valid as written in a Yarn script, including the leading `$`.
+    #[default]
+    Strict,
+    /// Surrounding whitespace is trimmed and a missing leading `$` is added, so `gold` refers to `$gold`.
+    Lenient,
+}
+
+/// Returns `true` if the name is a valid variable name: a `$` followed by a letter or `_`,
+/// followed by any number of letters, digits, `_` and `.`.
+pub fn is_valid_variable_name(name: &str) -> bool {
+    let Some(identifier) = name.strip_prefix('$') else {
+        return false;
+    };
+    let mut chars = identifier.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Canonicalizes a variable name according to the given mode. Borrows the name if it is already canonical.
+///
+/// ## Errors
+///
+/// Returns [`VariableStorageError::InvalidVariableName`] if the name is not valid after canonicalization, see [`is_valid_variable_name`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// assert_eq!("$gold", canonicalize_variable_name(" gold ", VariableNameMode::Lenient).unwrap());
+/// assert!(canonicalize_variable_name("gold", VariableNameMode::Strict).is_err());
+/// assert!(canonicalize_variable_name("$gold coins", VariableNameMode::Lenient).is_err());
+/// ```
+pub fn canonicalize_variable_name(name: &str, mode: VariableNameMode) -> Result<Cow<'_, str>> {
+    let canonical = match mode {
+        VariableNameMode::Strict => Cow::Borrowed(name),
+        VariableNameMode::Lenient => {
+            let trimmed = name.trim();
+            if trimmed.starts_with('$') {
+                Cow::Borrowed(trimmed)
+            } else {
+                Cow::Owned(format!("${trimmed}"))
+            }
+        }
+    };
+    if is_valid_variable_name(&canonical) {
+        Ok(canonical)
+    } else {
+        Err(VariableStorageError::InvalidVariableName {
+            name: name.to_owned(),
+        })
+    }
+}
@@ -3,35 +3,78 @@
 //! ## Implementation Notes
 //! The `Operand` extensions and the `Operator` enum were moved into upstream crates to make them not depend on the runtime.
 
-pub(crate) use self::{execution_state::*, state::*};
+pub(crate) use self::{checkpoint::*, execution_state::*, linked_program::*, state::*};
+use crate::expression::{is_when_condition_met, when_conditions, ExpressionContext};
+use crate::node_statistics::{
+    clock_variable, entered_variable, exited_variable, last_visited_variable, read_integer,
+};
 use crate::prelude::*;
 use crate::Result;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
 use core::fmt::Debug;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use hashbrown::HashSet;
 use log::*;
-use yarnspinner_core::prelude::instruction::{AddOptionInstruction, CallFunctionInstruction, InstructionType, JumpIfFalseInstruction, JumpToInstruction, PushBoolInstruction, PushFloatInstruction, PushStringInstruction, PushVariableInstruction, RunCommandInstruction, RunLineInstruction, RunNodeInstruction, StoreVariableInstruction};
 
+mod checkpoint;
 mod execution_state;
+mod linked_program;
 mod state;
 
 #[derive(Debug, Clone)]
 pub(crate) struct VirtualMachine {
-    pub(crate) library: Library,
-    pub(crate) program: Option<Program>,
+    pub(crate) library: Arc<Library>,
+    pub(crate) storage_functions: Library,
+    pub(crate) program: Option<ProgramHandle>,
     pub(crate) variable_storage: Box<dyn VariableStorage>,
-    current_node_name: Option<String>,
+    current_node_name: Option<Arc<str>>,
     state: State,
     execution_state: ExecutionState,
-    current_node: Option<Node>,
-    batched_events: Vec<DialogueEvent>,
+    current_node: Option<Arc<LinkedNode>>,
+    batched_events: BoundedVec<DialogueEvent, EVENT_BATCH_CAPACITY>,
+    last_error_location: Option<InstructionLocation>,
+    pub(crate) wait_command_handling: bool,
+    pub(crate) command_completion: bool,
+    pub(crate) non_blocking_commands: HashSet<String>,
+    /// The name and completion token of the last command, if the dialogue must wait for it to complete.
+    pub(crate) pending_command: Option<(String, CommandCompletionToken)>,
+    /// The ID of the last line, while the dialogue waits for [`VirtualMachine::continue_`] after delivering it.
+    pub(crate) current_line: Option<u32>,
+    pub(crate) line_hints: bool,
+    /// Shared with the `times_visited` and `last_visited` functions, which fail while tracking is disabled.
+    pub(crate) node_statistics_tracking: Arc<AtomicBool>,
+    pub(crate) max_checkpoints: usize,
+    checkpoints: VecDeque<Checkpoint>,
+    pub(crate) breakpoints: Vec<Breakpoint>,
+    saliency_candidates: Vec<ContentSaliencyOption>,
+    pub(crate) saliency_strategy: Arc<dyn SaliencyStrategy>,
+    pub(crate) options_presentation_policy: OptionsPresentationPolicy,
+    pub(crate) sandbox_policy: Option<SandboxPolicy>,
+    pub(crate) non_deterministic_functions: HashSet<String>,
+    pub(crate) replay_log: Option<ReplayLog>,
+    pub(crate) replay_cursor: Option<ReplayCursor>,
+    pub(crate) remaining_wait: Option<Duration>,
+    pub(crate) logger: Arc<dyn DialogueLogger>,
+    pub(crate) instructions_executed: u64,
+    #[cfg(feature = "vm_profiling")]
+    pub(crate) profile: ProfileReport,
+    /// The variables read and functions called since the last statement, explaining the condition of the next option.
+    #[cfg(feature = "condition_explanations")]
+    condition_trace: Vec<ConditionStep>,
 }
 
 impl VirtualMachine {
     pub(crate) fn new(
-        library: Library,
+        library: Arc<Library>,
+        storage_functions: Library,
         variable_storage: Box<dyn VariableStorage>,
+        node_statistics_tracking: Arc<AtomicBool>,
     ) -> Self {
         Self {
             library,
+            storage_functions,
             variable_storage,
             program: Default::default(),
             current_node_name: Default::default(),
@@ -39,6 +82,31 @@ impl VirtualMachine {
             execution_state: Default::default(),
             current_node: Default::default(),
             batched_events: Default::default(),
+            last_error_location: Default::default(),
+            wait_command_handling: Default::default(),
+            command_completion: Default::default(),
+            non_blocking_commands: Default::default(),
+            pending_command: Default::default(),
+            current_line: Default::default(),
+            line_hints: Default::default(),
+            node_statistics_tracking,
+            max_checkpoints: Default::default(),
+            checkpoints: Default::default(),
+            breakpoints: Default::default(),
+            saliency_candidates: Default::default(),
+            saliency_strategy: Arc::new(BestLeastRecentlyViewedSaliencyStrategy),
+            options_presentation_policy: Default::default(),
+            sandbox_policy: Default::default(),
+            non_deterministic_functions: Default::default(),
+            replay_log: Default::default(),
+            replay_cursor: Default::default(),
+            remaining_wait: Default::default(),
+            logger: Arc::new(LogCrateLogger),
+            instructions_executed: Default::default(),
+            #[cfg(feature = "vm_profiling")]
+            profile: Default::default(),
+            #[cfg(feature = "condition_explanations")]
+            condition_trace: Default::default(),
         }
     }
 
@@ -52,7 +120,13 @@ impl VirtualMachine {
 
     pub(crate) fn reset_state(&mut self) {
         self.state = State::default();
+        self.saliency_candidates.clear();
         self.current_node_name = None;
+        self.remaining_wait = None;
+        self.pending_command = None;
+        self.current_line = None;
+        #[cfg(feature = "condition_explanations")]
+        self.condition_trace.clear();
     }
 
     pub(crate) fn set_execution_state(&mut self, execution_state: ExecutionState) -> &mut Self {
@@ -67,26 +141,226 @@ impl VirtualMachine {
     /// The original does not reset the state upon calling this. I suspect that's a bug.
     pub(crate) fn stop(&mut self) -> Vec<DialogueEvent> {
         self.set_execution_state(ExecutionState::Stopped);
-        self.batched_events.push(DialogueEvent::DialogueComplete);
-        core::mem::take(&mut self.batched_events)
+        let mut events = self.batched_events.take();
+        events.push(DialogueEvent::DialogueComplete);
+        events
     }
 
-    pub(crate) fn set_node(&mut self, node_name: impl Into<String>) -> Result<()> {
-        let node_name = node_name.into();
-        debug!("Loading node \"{node_name}\"");
-        let current_node = self.get_node_from_name(&node_name)?;
-        self.current_node = Some(current_node.clone());
+    /// Adds an event to those returned by the current [`VirtualMachine::continue_`], failing if there are too many.
+    fn emit(&mut self, event: DialogueEvent) -> Result<()> {
+        if self.node_statistics_tracking.load(Ordering::Relaxed) {
+            match &event {
+                DialogueEvent::NodeStart(node_name) => self.record_node_entry(node_name)?,
+                DialogueEvent::NodeComplete(node_name) => {
+                    self.increment_variable(exited_variable(node_name))?;
+                }
+                _ => {}
+            }
+        }
+        self.batched_events
+            .try_push(event)
+            .map_err(|_| DialogueError::CapacityExceeded {
+                buffer: CapacityBuffer::Events,
+            })
+    }
+
+    pub(crate) fn set_node(&mut self, node_name: &str) -> Result<()> {
+        self.logger
+            .log(&DialogueLogRecord::NodeLoaded { node_name });
+        let current_node = self.get_linked_node(node_name)?.clone();
 
         self.reset_state();
 
+        let node_name = current_node.name.clone();
         self.current_node_name = Some(node_name.clone());
+        self.current_node = Some(current_node);
+        self.emit(DialogueEvent::NodeStart(node_name))?;
 
-        self.batched_events
-            .push(DialogueEvent::NodeStart(node_name));
+        Ok(())
+    }
+
+    /// Completes the current node, if any, and starts the given one like the `<<jump>>` statement. Clears the call stack.
+    pub(crate) fn jump_to_node(&mut self, node_name: &str) -> Result<()> {
+        self.get_linked_node(node_name)?;
+        if let Some(current_node_name) = self.current_node_name.clone() {
+            self.emit(DialogueEvent::NodeComplete(current_node_name))?;
+        }
+        self.set_node(node_name)
+    }
+
+    /// Starts the given node like the `<<detour>>` statement, keeping the current state.
+    /// Execution resumes in the current node at `return_program_counter` once the detoured node returns.
+    pub(crate) fn detour_to_node(
+        &mut self,
+        node_name: &str,
+        return_program_counter: usize,
+    ) -> Result<()> {
+        let Some(current_node_name) = self.current_node_name.clone() else {
+            return Err(DialogueError::NoNodeSelectedOnContinue);
+        };
+        let node = self.get_linked_node(node_name)?.clone();
+        self.logger.log(&DialogueLogRecord::NodeDetoured {
+            from: &current_node_name,
+            to: node_name,
+        });
+        self.state.call_stack.push(ReturnSite {
+            node_name: current_node_name,
+            program_counter: return_program_counter,
+        });
+        self.enter_node_keeping_state(node, 0)
+    }
 
+    /// Completes the current node and resumes the node that detoured into it, or stops the dialogue if there is none.
+    fn return_from_node(&mut self) -> Result<()> {
+        let current_node_name = self.current_node_name.clone().unwrap();
+        self.emit(DialogueEvent::NodeComplete(current_node_name))?;
+        let Some(return_site) = self.state.call_stack.pop() else {
+            self.emit(DialogueEvent::DialogueComplete)?;
+            self.set_execution_state(ExecutionState::Stopped);
+            return Ok(());
+        };
+        let node = self.get_linked_node(&return_site.node_name)?.clone();
+        self.enter_node_keeping_state(node, return_site.program_counter)
+    }
+
+    fn enter_node_keeping_state(
+        &mut self,
+        node: Arc<LinkedNode>,
+        program_counter: usize,
+    ) -> Result<()> {
+        self.current_node_name = Some(node.name.clone());
+        self.state.program_counter = program_counter;
+        self.emit(DialogueEvent::NodeStart(node.name.clone()))?;
+        self.current_node = Some(node);
         Ok(())
     }
 
+    /// Looks ahead from the given instruction of the current node to find out what follows a line, without running anything.
+    /// Unconditional jumps are followed, but any other branch ends the look-ahead with all hints being `false`.
+    fn line_hints_after(&self, mut program_counter: usize) -> LineHints {
+        let Some(node) = self.current_node.as_ref() else {
+            return LineHints::default();
+        };
+        let end_of_node = LineHints {
+            is_final_line_of_node: true,
+            // Running off the end of a node resumes the node that detoured into it
+            is_final_line_of_dialogue: self.state.call_stack.is_empty(),
+            ..Default::default()
+        };
+        // Jumps may loop, so never look at more instructions than the node has
+        for _ in 0..node.instructions.len() {
+            let Some(instruction) = node.instructions.get(program_counter) else {
+                return end_of_node;
+            };
+            match instruction {
+                LinkedInstruction::JumpTo { destination } => {
+                    program_counter = *destination as usize;
+                    continue;
+                }
+                LinkedInstruction::AddOption { .. } | LinkedInstruction::ShowOptions => {
+                    return LineHints {
+                        is_last_line_before_options: true,
+                        ..Default::default()
+                    };
+                }
+                LinkedInstruction::Stop => {
+                    return LineHints {
+                        is_final_line_of_node: true,
+                        is_final_line_of_dialogue: true,
+                        ..Default::default()
+                    };
+                }
+                LinkedInstruction::Return => return end_of_node,
+                LinkedInstruction::RunNode { .. } | LinkedInstruction::PeekAndRunNode => {
+                    return LineHints {
+                        is_final_line_of_node: true,
+                        ..Default::default()
+                    };
+                }
+                LinkedInstruction::PushString(_)
+                | LinkedInstruction::PushFloat(_)
+                | LinkedInstruction::PushBool(_)
+                | LinkedInstruction::PushVariable { .. }
+                | LinkedInstruction::Pop
+                | LinkedInstruction::CallFunc { .. }
+                | LinkedInstruction::StoreVariable { .. }
+                | LinkedInstruction::RunCommand { .. } => program_counter += 1,
+                _ => return LineHints::default(),
+            }
+        }
+        LineHints::default()
+    }
+
+    /// Remembers the current state so that [`VirtualMachine::step_back`] can return to it, if checkpointing is enabled.
+    /// Must be called before the current instruction changes the state.
+    fn take_checkpoint(&mut self) {
+        if self.max_checkpoints == 0 {
+            return;
+        }
+        let Some(node_name) = self.current_node_name.clone() else {
+            return;
+        };
+        if self.checkpoints.len() >= self.max_checkpoints {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints
+            .push_back(Checkpoint::new(node_name, self.state.clone()));
+    }
+
+    /// The latest checkpoint belongs to the line or options presented last, so stepping back returns to the one before.
+    pub(crate) fn can_step_back(&self) -> bool {
+        self.checkpoints.len() >= 2
+    }
+
+    /// Restores the state right before the line or options presented before the current ones, undoing all variable writes since.
+    /// Returns `false` if there is no such checkpoint.
+    pub(crate) fn step_back(&mut self) -> Result<bool> {
+        if !self.can_step_back() {
+            return Ok(false);
+        }
+        let mut latest = self.checkpoints.pop_back().unwrap();
+        latest.undo_writes(self.variable_storage.as_mut())?;
+        let mut checkpoint = self.checkpoints.pop_back().unwrap();
+        checkpoint.undo_writes(self.variable_storage.as_mut())?;
+        let node = self.get_linked_node(&checkpoint.node_name)?.clone();
+        self.current_node = Some(node);
+        self.current_node_name = Some(checkpoint.node_name);
+        self.state = checkpoint.state;
+        self.remaining_wait = None;
+        self.pending_command = None;
+        self.current_line = None;
+        self.batched_events.clear();
+        self.execution_state = ExecutionState::WaitingForContinue;
+        Ok(true)
+    }
+
+    pub(crate) fn clear_checkpoints(&mut self) {
+        self.checkpoints.clear();
+    }
+
+    pub(crate) fn program_counter(&self) -> usize {
+        self.state.program_counter
+    }
+
+    /// The shared copy of the name of the given node of the loaded program.
+    pub(crate) fn node_name(&self, node_name: &str) -> Result<Arc<str>> {
+        Ok(self.get_linked_node(node_name)?.name.clone())
+    }
+
+    fn get_linked_node(&self, node_name: &str) -> Result<&Arc<LinkedNode>> {
+        let program = self
+            .program
+            .as_ref()
+            .ok_or_else(|| DialogueError::NoProgramLoaded)?;
+
+        program
+            .linked
+            .node(node_name)
+            .ok_or_else(|| DialogueError::InvalidNode {
+                node_name: node_name.to_owned(),
+            })
+    }
+
     fn get_node_from_name(&self, node_name: &str) -> Result<&Node> {
         let program = self
             .program
@@ -94,6 +368,7 @@ impl VirtualMachine {
             .ok_or_else(|| DialogueError::NoProgramLoaded)?;
 
         program
+            .program
             .nodes
             .get(node_name)
             .ok_or_else(|| DialogueError::InvalidNode {
@@ -102,32 +377,218 @@ impl VirtualMachine {
     }
 
     /// Resumes execution.
+    ///
+    /// If `max_instructions` is set, execution is suspended after that many instructions even if no event requires the game's attention yet.
+    /// The VM is then [`ExecutionState::Yielded`] and the next call resumes where it left off.
     pub(crate) fn continue_(
         &mut self,
-        mut instruction_fn: impl FnMut(&mut Self, &Instruction) -> crate::Result<()>,
+        max_instructions: Option<usize>,
+        mut instruction_fn: impl FnMut(&mut Self, &LinkedInstruction) -> crate::Result<()>,
     ) -> crate::Result<Vec<DialogueEvent>> {
+        self.last_error_location = None;
         self.assert_can_continue()?;
+        // Continuing manually cuts a pending wait short
+        self.remaining_wait = None;
+        self.pending_command = None;
+        self.current_line = None;
+        // Resuming from a breakpoint must not hit it again right away
+        let mut skip_breakpoints = self.execution_state == ExecutionState::Paused;
         self.set_execution_state(ExecutionState::Running);
 
+        let mut remaining_instructions = max_instructions;
         while self.execution_state == ExecutionState::Running {
-            let current_node = self.current_node.clone().unwrap();
-            let current_instruction = &current_node.instructions[self.state.program_counter];
-            instruction_fn(self, current_instruction)?;
+            if let Some(remaining) = remaining_instructions.as_mut() {
+                if *remaining == 0 {
+                    self.execution_state = ExecutionState::Yielded;
+                    break;
+                }
+                *remaining -= 1;
+            }
+            // Linked instructions are `Copy`, so the node stays borrowed only for this lookup
+            let Some(&current_instruction) = self
+                .current_node
+                .as_ref()
+                .unwrap()
+                .instructions
+                .get(self.state.program_counter)
+            else {
+                // Empty nodes and detours returning to the end of their node start past it
+                self.return_from_node()?;
+                if self.execution_state == ExecutionState::Stopped {
+                    self.logger.log(&DialogueLogRecord::RunComplete);
+                }
+                continue;
+            };
+            if !core::mem::take(&mut skip_breakpoints)
+                && self.pause_at_breakpoint(&current_instruction)?
+            {
+                break;
+            }
+            self.instructions_executed += 1;
+            #[cfg(feature = "vm_profiling")]
+            let node_name = self.current_node.as_ref().unwrap().name.clone();
+            #[cfg(all(
+                feature = "vm_profiling",
+                feature = "std",
+                not(all(target_arch = "wasm32", target_os = "unknown"))
+            ))]
+            let start = std::time::Instant::now();
+            let result = instruction_fn(self, &current_instruction);
+            #[cfg(feature = "vm_profiling")]
+            {
+                // `std::time::Instant` panics on `wasm32-unknown-unknown`
+                #[cfg(all(
+                    feature = "std",
+                    not(all(target_arch = "wasm32", target_os = "unknown"))
+                ))]
+                let elapsed = start.elapsed();
+                #[cfg(not(all(
+                    feature = "std",
+                    not(all(target_arch = "wasm32", target_os = "unknown"))
+                )))]
+                let elapsed = Duration::ZERO;
+                let stack_depth = self.state.stack.len();
+                self.profile
+                    .record_instruction(&node_name, elapsed, stack_depth);
+            }
+            if let Err(e) = result {
+                // Failing instructions never leave their node
+                self.last_error_location = Some(InstructionLocation {
+                    node_name: self.current_node.as_ref().unwrap().name.to_string(),
+                    instruction: self.state.program_counter,
+                });
+                return Err(e);
+            }
             // ## Implementation note
             // The original increments the program counter here, but that leads to intentional underflow on [`OpCode::RunNode`],
             // so we do the incrementation in [`VirtualMachine::run_instruction`] instead.
 
-            if self.state.program_counter < current_node.instructions.len() {
+            // The instruction may have moved execution to a different node or stopped the dialogue
+            let node_length = self
+                .current_node
+                .as_ref()
+                .map_or(0, |node| node.instructions.len());
+            if self.state.program_counter < node_length
+                || self.execution_state == ExecutionState::Stopped
+            {
                 continue;
             }
 
-            self.batched_events
-                .push(DialogueEvent::NodeComplete(current_node.name.clone()));
-            self.set_execution_state(ExecutionState::Stopped);
-            self.batched_events.push(DialogueEvent::DialogueComplete);
-            debug!("Run complete.");
+            // Running off the end of a node returns from a detour like the `Return` instruction, or ends the dialogue
+            self.return_from_node()?;
+            if self.execution_state == ExecutionState::Stopped {
+                self.logger.log(&DialogueLogRecord::RunComplete);
+            }
         }
-        Ok(core::mem::take(&mut self.batched_events))
+        Ok(self.batched_events.take())
+    }
+
+    /// How often the content with the given ID was selected by a [`SaliencyStrategy`].
+    fn view_count(&self, content_id: &str) -> u32 {
+        let name = Library::generate_unique_view_count_variable(content_id);
+        self.variable_storage
+            .get(&name)
+            .ok()
+            .and_then(|count| count.as_number())
+            .map_or(0, |count| count as u32)
+    }
+
+    /// Adds an entry to the [`ReplayLog`] if one is being recorded.
+    pub(crate) fn record_replay_entry(&mut self, entry: impl FnOnce() -> ReplayEntry) {
+        if let Some(replay_log) = self.replay_log.as_mut() {
+            replay_log.push(entry());
+        }
+    }
+
+    /// Updates the [`NodeStatistics`] of a node that is being entered.
+    fn record_node_entry(&mut self, node_name: &str) -> Result<()> {
+        let timestamp = self.increment_variable(clock_variable())?;
+        self.increment_variable(entered_variable(node_name))?;
+        self.set_tracked_variable(last_visited_variable(node_name), timestamp)
+    }
+
+    /// Adds one to an integer variable, treating unset variables as zero, and returns its new value.
+    fn increment_variable(&mut self, name: String) -> Result<i64> {
+        let value = read_integer(self.variable_storage.as_ref(), &name) + 1;
+        self.set_tracked_variable(name, value)?;
+        Ok(value)
+    }
+
+    /// Sets a variable the runtime tracks on its own, so that [`VirtualMachine::step_back`] reverts it.
+    fn set_tracked_variable(&mut self, name: String, value: i64) -> Result<()> {
+        if let Some(checkpoint) = self.checkpoints.back_mut() {
+            checkpoint.record_write(&name, self.variable_storage.as_ref());
+        }
+        self.variable_storage.set(name, YarnValue::Integer(value))?;
+        Ok(())
+    }
+
+    fn record_view(&mut self, candidate: &ContentSaliencyOption) -> Result<()> {
+        let name = Library::generate_unique_view_count_variable(&candidate.content_id);
+        if let Some(checkpoint) = self.checkpoints.back_mut() {
+            checkpoint.record_write(&name, self.variable_storage.as_ref());
+        }
+        let view_count = candidate.view_count.saturating_add(1) as f32;
+        self.variable_storage.set(name, view_count.into())?;
+        Ok(())
+    }
+
+    /// The candidate for a node of a node group. Every `when:` header is one condition,
+    /// and every condition other than `always` adds one to the complexity score.
+    fn node_saliency_candidate(
+        &self,
+        node_name: &str,
+        destination: usize,
+    ) -> Result<ContentSaliencyOption> {
+        let node = self.get_node_from_name(node_name)?;
+        let variable = |name: &str| Ok(self.variable_storage.get(name)?);
+        let context = ExpressionContext {
+            library: &self.library,
+            storage_functions: &self.storage_functions,
+            variable: &variable,
+        };
+        let mut candidate = ContentSaliencyOption {
+            content_id: node_name.to_owned(),
+            content_type: ContentSaliencyContentType::Node,
+            passing_condition_value_count: 0,
+            failing_condition_value_count: 0,
+            complexity_score: 0,
+            view_count: self.view_count(node_name),
+            destination,
+        };
+        for condition in when_conditions(node) {
+            if condition != "always" {
+                candidate.complexity_score += 1;
+            }
+            if is_when_condition_met(context, node_name, condition)? {
+                candidate.passing_condition_value_count += 1;
+            } else {
+                candidate.failing_condition_value_count += 1;
+            }
+        }
+        Ok(candidate)
+    }
+
+    /// Pauses execution with a [`DialogueEvent::BreakpointHit`] if a breakpoint is set at the given instruction, which is about to be run.
+    fn pause_at_breakpoint(&mut self, instruction: &LinkedInstruction) -> Result<bool> {
+        if self.breakpoints.is_empty() {
+            return Ok(false);
+        }
+        let Some(node_name) = self.current_node_name.as_deref() else {
+            return Ok(false);
+        };
+        let program_counter = self.state.program_counter;
+        let Some(breakpoint) = self
+            .breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.matches(node_name, program_counter, instruction))
+            .cloned()
+        else {
+            return Ok(false);
+        };
+        self.emit(DialogueEvent::BreakpointHit(breakpoint))?;
+        self.set_execution_state(ExecutionState::Paused);
+        Ok(true)
     }
 
     /// Runs a series of tests to see if the [`VirtualMachine`] is in a state where [`VirtualMachine::r#continue`] can be called. Panics if it can't.
@@ -136,6 +597,14 @@ impl VirtualMachine {
             Err(DialogueError::NoNodeSelectedOnContinue)
         } else if self.execution_state == ExecutionState::WaitingOnOptionSelection {
             Err(DialogueError::ContinueOnOptionSelectionError)
+        } else if let Some((command_name, _)) = self
+            .pending_command
+            .as_ref()
+            .filter(|(_, token)| !token.is_complete())
+        {
+            Err(DialogueError::CommandNotComplete {
+                command_name: command_name.clone(),
+            })
         } else {
             // ## Implementation note:
             // The other checks the original did are not needed because our relevant handlers cannot be `None` per our API.
@@ -143,8 +612,69 @@ impl VirtualMachine {
         }
     }
 
+    /// Returns `true` if the last [`VirtualMachine::continue_`] ran out of instructions before producing an event that needs to be waited on.
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.execution_state == ExecutionState::Yielded
+    }
+
     pub(crate) fn unload_programs(&mut self) {
-        self.program = None
+        self.program = None;
+        self.checkpoints.clear();
+    }
+
+    pub(crate) fn set_program(&mut self, program: ProgramHandle) {
+        self.program = Some(program);
+    }
+
+    /// Swaps in a new program while keeping the execution state of the current node, if any.
+    ///
+    /// The current node is looked up by name in the new program. The program counter is clamped
+    /// to the new node's instructions and pending options whose destination no longer exists are dropped.
+    pub(crate) fn replace_program(&mut self, program: Program) -> Result<()> {
+        self.set_program(ProgramHandle::new(program));
+        // Checkpoints may point at instructions that no longer exist
+        self.checkpoints.clear();
+        let Some(node_name) = self.current_node_name.clone() else {
+            self.reset_state();
+            return Ok(());
+        };
+        let Ok(node) = self.get_linked_node(&node_name).cloned() else {
+            self.current_node = None;
+            self.set_execution_state(ExecutionState::Stopped);
+            return Err(DialogueError::CurrentNodeRemovedOnReload {
+                node_name: node_name.to_string(),
+            });
+        };
+
+        let instruction_count = node.instructions.len();
+        if self.state.program_counter >= instruction_count {
+            warn!(
+                "Program counter {} is out of bounds for reloaded node \"{node_name}\" with {instruction_count} instructions, clamping it",
+                self.state.program_counter
+            );
+            self.state.program_counter = instruction_count.saturating_sub(1);
+        }
+        let option_count = self.state.current_options.len();
+        self.state
+            .current_options
+            .retain(|option| (option.destination_node as usize) < instruction_count);
+        for (index, option) in self.state.current_options.iter_mut().enumerate() {
+            option.id = OptionId(index);
+        }
+        self.current_node = Some(node);
+
+        let options_changed = self.state.current_options.len() != option_count;
+        if options_changed && self.execution_state == ExecutionState::WaitingOnOptionSelection {
+            let options = self.state.current_options.to_vec();
+            if options.is_empty() {
+                self.set_execution_state(ExecutionState::Stopped);
+            }
+            return Err(DialogueError::OptionsChangedOnReload {
+                node_name: node_name.to_string(),
+                options,
+            });
+        }
+        Ok(())
     }
 
     pub(crate) fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<()> {
@@ -160,9 +690,8 @@ impl VirtualMachine {
 
         // We now know what number option was selected; push the
         // corresponding node name to the stack.
-        let destination_node = self.state.current_options[selected_option_id.0]
-            .destination_node;
-        self.state.push(destination_node);
+        let destination_node = self.state.current_options[selected_option_id.0].destination_node;
+        self.state.push(destination_node)?;
 
         // We no longer need the accumulated list of options; clear it
         // so that it's ready for the next one
@@ -177,36 +706,64 @@ impl VirtualMachine {
         self.execution_state != ExecutionState::Stopped
     }
 
+    pub(crate) fn execution_state(&self) -> ExecutionState {
+        self.execution_state
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.execution_state == ExecutionState::Paused
+    }
+
     pub(crate) fn is_waiting_for_option_selection(&self) -> bool {
         self.execution_state == ExecutionState::WaitingOnOptionSelection
     }
 
-    pub(crate) fn current_node(&self) -> Option<String> {
+    pub(crate) fn current_node(&self) -> Option<Arc<str>> {
         self.current_node_name.clone()
     }
 
+    /// The instruction that caused the last error returned by [`VirtualMachine::continue_`], if any.
+    pub(crate) fn last_error_location(&self) -> Option<&InstructionLocation> {
+        self.last_error_location.as_ref()
+    }
+
     /// ## Implementation note
     ///
     /// Increments the program counter here instead of in `continue_` for cleaner code
     pub(crate) fn run_instruction(
         &mut self,
-        instruction: &Instruction,
-        mut function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> YarnValue,
+        instruction: &LinkedInstruction,
+        mut function_call_fn: impl FnMut(
+            &dyn UntypedYarnFn,
+            Vec<YarnValue>,
+        ) -> core::result::Result<YarnValue, YarnFnError>,
     ) -> crate::Result<()> {
-        let Some(instruction_type) = &instruction.instruction_type else {
-            panic!("Instruction type is None");
-        };
-
-        match instruction_type {
-            InstructionType::JumpTo(JumpToInstruction { destination }) => {
+        // Only expressions contribute to the condition of an option, anything else starts a new statement
+        #[cfg(feature = "condition_explanations")]
+        if !matches!(
+            instruction,
+            LinkedInstruction::PushString(_)
+                | LinkedInstruction::PushFloat(_)
+                | LinkedInstruction::PushBool(_)
+                | LinkedInstruction::PushVariable { .. }
+                | LinkedInstruction::CallFunc { .. }
+                | LinkedInstruction::AddOption { .. }
+        ) {
+            self.condition_trace.clear();
+        }
+        match *instruction {
+            LinkedInstruction::JumpTo { destination } => {
                 // Jumps to a named label
-                self.state.program_counter = *destination as usize;
+                self.state.program_counter = destination as usize;
             }
-            InstructionType::PeekAndJump(_) => {
-                let jump_destination: usize = self.state.peek();
+            LinkedInstruction::PeekAndJump => {
+                let jump_destination: usize = self.state.peek()?;
                 self.state.program_counter = jump_destination;
             }
-            InstructionType::RunLine(RunLineInstruction { line_id, substitution_count }) => {
+            LinkedInstruction::RunLine {
+                line_id,
+                substitution_count,
+            } => {
                 // Looks up a string from the string table and passes it to the client as a line
 
                 // The second operand, if provided (compilers prior
@@ -214,11 +771,19 @@ impl VirtualMachine {
                 // of expressions in the line. We need to pop these
                 // values off the stack and deliver them to the
                 // line handler.
-                for _ in 0..*substitution_count {
-                    self.state.pop_value();
-                }
+                self.take_checkpoint();
+                // The expressions were pushed in order, so the last one is on top of the stack
+                let mut substitutions = (0..substitution_count)
+                    .map(|_| self.state.pop_value().map(YarnValue::from))
+                    .collect::<Result<Vec<_>>>()?;
+                substitutions.reverse();
 
-                self.batched_events.push(DialogueEvent::Line(*line_id));
+                if self.line_hints {
+                    let hints = self.line_hints_after(self.state.program_counter + 1);
+                    self.emit(DialogueEvent::LineHints(hints))?;
+                }
+                self.emit(DialogueEvent::Line(line_id, substitutions))?;
+                self.current_line = Some(line_id);
 
                 // Implementation note:
                 // In the original, this is only done if `execution_state` is still `DeliveringContent`,
@@ -228,17 +793,53 @@ impl VirtualMachine {
                 self.set_execution_state(ExecutionState::WaitingForContinue);
                 self.state.program_counter += 1;
             }
-            InstructionType::RunCommand(RunCommandInstruction { command_text, substitution_count }) => {
+            LinkedInstruction::RunCommand {
+                command_text,
+                substitution_count,
+            } => {
                 // Passes a string to the client as a custom command
-                let command_text = (0..*substitution_count)
+                let command_text = self.string(command_text);
+                let command_text = (0..substitution_count)
                     .map(|_| self.state.pop::<String>())
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
                     .enumerate()
-                    .fold(command_text.to_owned(), |command_text, (i, substitution)| {
-                        command_text.replace(&format!("{{{i}}}"), &substitution)
-                    });
-                let command = Command::parse(command_text);
+                    .fold(
+                        command_text.to_string(),
+                        |command_text, (i, substitution)| {
+                            command_text.replace(&format!("{{{i}}}"), &substitution)
+                        },
+                    );
+                let mut command =
+                    Command::parse(command_text).ok_or(DialogueError::EmptyCommand)?;
 
-                self.batched_events.push(DialogueEvent::Command(command));
+                match self
+                    .wait_command_handling
+                    .then(|| command.wait_duration())
+                    .flatten()
+                {
+                    Some(duration) => {
+                        self.remaining_wait = Some(duration);
+                        self.emit(DialogueEvent::Wait(duration))?;
+                    }
+                    None => {
+                        if let Some(policy) = &self.sandbox_policy {
+                            if !policy.permits_command(&command.name) {
+                                return Err(DialogueError::CommandNotPermitted {
+                                    command_name: command.name,
+                                });
+                            }
+                        }
+                        if self.command_completion
+                            && !self.non_blocking_commands.contains(&command.name)
+                        {
+                            let token = CommandCompletionToken::new();
+                            self.pending_command = Some((command.name.clone(), token.clone()));
+                            command.completion_token = Some(token);
+                        }
+                        self.emit(DialogueEvent::Command(command))?;
+                    }
+                }
 
                 // Implementation note:
                 // In the original, this is only done if `execution_state` is still `DeliveringContent`,
@@ -248,44 +849,66 @@ impl VirtualMachine {
                 self.set_execution_state(ExecutionState::WaitingForContinue);
                 self.state.program_counter += 1;
             }
-            InstructionType::AddOption(AddOptionInstruction { tag_id, destination, has_condition, .. }) => {
-                // TODO: Do something with substitution_count
+            LinkedInstruction::AddOption {
+                tag_id,
+                destination,
+                substitution_count,
+                has_condition,
+            } => {
+                // The expressions of the option's text were pushed after its condition and in order,
+                // so the last one is on top of the stack
+                let mut substitutions = (0..substitution_count)
+                    .map(|_| self.state.pop_value().map(YarnValue::from))
+                    .collect::<Result<Vec<_>>>()?;
+                substitutions.reverse();
 
                 // Indicates whether the VM believes that the
                 // option should be shown to the user, based on any
                 // conditions that were attached to the option.
-                let line_condition_passed = if *has_condition {
+                let line_condition_passed = if has_condition {
                     // The fourth operand is a bool that indicates
                     // whether this option had a condition or not.
                     // If it does, then a bool value will exist on
                     // the stack indicating whether the condition
                     // passed or not. We pass that information to
                     // the game.
-                    self.state.pop()
+                    self.state.pop()?
                 } else {
                     true
                 };
-                
+                #[cfg(feature = "condition_explanations")]
+                let condition_trace = core::mem::take(&mut self.condition_trace);
+
                 let index = self.state.current_options.len();
                 // ## Implementation note:
                 // The original calculates the ID in the `ShowOptions` opcode,
                 // but this way is cleaner because it allows us to store a `DialogueOption` instead of a bunch of values in a big tuple.
-                self.state.current_options.push(DialogueOption {
-                    tag_id: *tag_id, // 
+                self.state.add_option(DialogueOption {
+                    tag_id, //
                     id: OptionId(index),
-                    destination_node: *destination,
+                    destination_node: destination as i32,
                     is_available: line_condition_passed,
-                });
+                    substitutions,
+                    #[cfg(feature = "condition_explanations")]
+                    unavailability_reason: (!line_condition_passed).then_some(
+                        UnavailabilityReason {
+                            steps: condition_trace,
+                        },
+                    ),
+                })?;
                 self.state.program_counter += 1;
             }
-            InstructionType::ShowOptions(_) => {
+            LinkedInstruction::ShowOptions => {
                 // If we have no options to show, immediately stop.
                 if self.state.current_options.is_empty() {
-                    self.batched_events.push(DialogueEvent::DialogueComplete);
+                    self.emit(DialogueEvent::DialogueComplete)?;
                     self.set_execution_state(ExecutionState::Stopped);
                     self.state.program_counter += 1;
                     return Ok(());
                 }
+                self.take_checkpoint();
+                self.options_presentation_policy
+                    .apply(&mut self.state.current_options);
 
                 // We can't continue until our client tell us which option to pick
                 self.set_execution_state(ExecutionState::WaitingOnOptionSelection);
@@ -293,75 +916,119 @@ impl VirtualMachine {
                 // Pass the options set to the client, as well as a
                 // delegate for them to call when the user has made
                 // a selection
-                let current_options = self.state.current_options.clone();
-                self.batched_events
-                    .push(DialogueEvent::Options(current_options));
+                let current_options = self.state.current_options.to_vec();
+                self.logger.log(&DialogueLogRecord::OptionsShown {
+                    options: &current_options,
+                });
+                self.emit(DialogueEvent::Options(current_options))?;
 
                 // Implementation note:
                 // Not checking the execution state now since we have no line handler to call `continue_` from.
                 self.state.program_counter += 1;
             }
-            InstructionType::PushString(PushStringInstruction { value }) => {
+            LinkedInstruction::PushString(value) => {
                 // Pushes a string value onto the stack.
-                self.state.push(value.to_owned());
+                self.state.push(self.string(value).to_string())?;
                 self.state.program_counter += 1;
             }
-            InstructionType::PushFloat(PushFloatInstruction { value }) => {
+            LinkedInstruction::PushFloat(value) => {
                 // Pushes a floating point onto the stack.
-                self.state.push(*value);
+                self.state.push(value)?;
                 self.state.program_counter += 1;
             }
-            InstructionType::PushBool(PushBoolInstruction { value }) => {
+            LinkedInstruction::PushBool(value) => {
                 // Pushes a boolean value onto the stack.
-                self.state.push(*value);
+                self.state.push(value)?;
                 self.state.program_counter += 1;
             }
 
-            InstructionType::JumpIfFalse(JumpIfFalseInstruction { destination }) => {
+            LinkedInstruction::JumpIfFalse { destination } => {
                 // Jumps to a named label if the value on the top of the stack evaluates to the boolean value 'false'.
-                let is_top_value_true: bool = self.state.peek();
+                let is_top_value_true: bool = self.state.peek()?;
                 if is_top_value_true {
                     self.state.program_counter += 1;
                 } else {
-                    self.state.program_counter = *destination as usize;
+                    self.state.program_counter = destination as usize;
                 }
             }
-            InstructionType::Pop(_) => {
+            LinkedInstruction::Pop => {
                 // Pops a value from the stack.
-                self.state.pop_value();
+                self.state.pop_value()?;
                 self.state.program_counter += 1;
             }
-            InstructionType::CallFunc(CallFunctionInstruction { function_name }) => {
-                let actual_parameter_count: usize = self.state.pop();
+            LinkedInstruction::CallFunc { function_name } => {
+                let function_name = self.string(function_name);
+                if let Some(policy) = &self.sandbox_policy {
+                    if self.library.contains_function(&function_name)
+                        && !policy.permits_function(&function_name)
+                    {
+                        return Err(DialogueError::FunctionNotPermitted {
+                            function_name: function_name.to_string(),
+                        });
+                    }
+                }
+                let actual_parameter_count: usize = self.state.pop()?;
                 // Get the parameters, which were pushed in reverse
                 let parameters = {
-                    let mut parameters: Vec<_> = (0..actual_parameter_count)
-                        .map(|_| self.state.pop_value().raw_value)
-                        .collect();
+                    let mut parameters = (0..actual_parameter_count)
+                        .map(|_| self.state.pop_value().map(|value| value.raw_value))
+                        .collect::<Result<Vec<_>>>()?;
                     parameters.reverse();
                     parameters
                 };
 
+                // During a replay, non-deterministic functions are not called, but return the recorded value instead.
+                let non_deterministic = self.non_deterministic_functions.contains(&*function_name);
+                let replayed_value = match self.replay_cursor.as_mut() {
+                    Some(cursor) if non_deterministic => {
+                        match cursor.next_runtime_input(
+                            &format!("the return value of {function_name}"),
+                            |entry| matches!(entry, ReplayEntry::FunctionReturned { function_name: name, .. } if **name == *function_name),
+                        )? {
+                            ReplayEntry::FunctionReturned { value, .. } => Some(value),
+                            _ => unreachable!(),
+                        }
+                    }
+                    _ => None,
+                };
+
                 // Call a function, whose parameters are expected to be on the stack. Pushes the function's return value, if it returns one.
-                let function =
-                    self.library
-                        .get(function_name)
-                        .ok_or(DialogueError::FunctionNotFound {
-                            function_name: function_name.to_string(),
-                            library: self.library.clone(),
-                        })?;
+                let function = self
+                    .library
+                    .get(&function_name)
+                    .or_else(|| self.storage_functions.get(&function_name))
+                    .ok_or_else(|| DialogueError::FunctionNotFound {
+                        function_name: function_name.to_string(),
+                        library: Library::clone(&self.library),
+                    })?;
+
+                #[cfg(feature = "vm_profiling")]
+                if let Some(node_name) = &self.current_node_name {
+                    self.profile.record_function_call(node_name, &function_name);
+                }
 
                 // Expect the compiler to have placed the number of parameters
                 // actually passed at the top of the stack.
                 let expected_parameter_count = function.parameter_types().len();
 
-                assert_eq!(
-                    expected_parameter_count, actual_parameter_count,
-                    "Function {function_name} expected {expected_parameter_count} parameters, but received {actual_parameter_count}",
-                );
+                if expected_parameter_count != actual_parameter_count {
+                    return Err(DialogueError::InvalidInstruction {
+                        message: format!("Function {function_name} expected {expected_parameter_count} parameters, but received {actual_parameter_count}"),
+                    });
+                }
 
+                #[cfg(feature = "condition_explanations")]
+                let traced_parameters = parameters.clone();
                 // Invoke the function
-                let return_value = function_call_fn(function, parameters);
+                let return_value = match replayed_value {
+                    Some(value) => value,
+                    None => function_call_fn(function, parameters).map_err(|error| {
+                        DialogueError::FunctionFailed {
+                            function_name: function_name.to_string(),
+                            message: error.to_string(),
+                        }
+                    })?,
+                };
                 let return_type = function
                     .return_type()
                     .try_into()
@@ -370,92 +1037,183 @@ impl VirtualMachine {
                     raw_value: return_value,
                     type_: return_type,
                 };
+                #[cfg(feature = "condition_explanations")]
+                self.condition_trace.push(ConditionStep::FunctionCall {
+                    function_name: function_name.to_string(),
+                    parameters: traced_parameters,
+                    result: typed_return_value.raw_value.clone(),
+                });
                 // ## Implementation note:
                 // The original code first checks whether the return type is `void`. This is vestigial from the v1 compiler.
                 // In current Yarn, every function MUST return a valid typed value, so we skip that check.
-                self.state.push(typed_return_value);
+                if non_deterministic {
+                    self.record_replay_entry(|| ReplayEntry::FunctionReturned {
+                        function_name: function_name.to_string(),
+                        value: typed_return_value.raw_value.clone(),
+                    });
+                }
+                self.state.push(typed_return_value)?;
                 self.state.program_counter += 1;
             }
-            InstructionType::PushVariable(PushVariableInstruction { variable_name }) => {
+            LinkedInstruction::PushVariable { variable_name } => {
                 // Get the contents of a variable, push that onto the stack.
+                let variable_name = self.string(variable_name);
                 let loaded_value = self
                     .variable_storage
-                    .get(variable_name)
+                    .get(&variable_name)
                     .or_else(|e| {
                         if let VariableStorageError::VariableNotFound { .. } = e {
                             // We don't have a value for this. The initial
                             // value may be found in the program. (If it's
                             // not, then the variable's value is undefined,
                             // which isn't allowed.)
-                            let initial_value = self
+                            let Some(initial_value) = self
                                 .program
                                 .as_ref()
                                 .unwrap()
-                                .initial_values
-                                .get(variable_name)
-                                .unwrap_or_else(|| panic!("The loaded program does not contain an initial value for the variable {variable_name}"))
-                                .clone();
+                                .program.initial_values
+                                .get(&*variable_name)
+                                .cloned()
+                            else {
+                                // `<<once>>` flags are unset until their content runs, whether the compiler declared them or not
+                                if variable_name.starts_with(Library::ONCE_VARIABLE_PREFIX) {
+                                    return Ok(false.into());
+                                }
+                                return Err(DialogueError::InvalidInstruction {
+                                    message: format!("The loaded program does not contain an initial value for the variable {variable_name}"),
+                                });
+                            };
 
                             // Store the initial value in the variable_storage
-                            self.variable_storage.set(variable_name.clone(), initial_value.clone().into())?;
+                            self.variable_storage.set(variable_name.to_string(), initial_value.clone().into())?;
 
                             Ok(initial_value.into())
                         } else {
-                            Err(e)
+                            Err(e.into())
                         }
                     })?;
-                self.state.push(loaded_value);
+                #[cfg(feature = "condition_explanations")]
+                self.condition_trace.push(ConditionStep::Variable {
+                    name: variable_name.to_string(),
+                    value: loaded_value.clone(),
+                });
+                self.state.push(loaded_value)?;
                 self.state.program_counter += 1;
             }
-            InstructionType::StoreVariable(StoreVariableInstruction { variable_name }) => {
+            LinkedInstruction::StoreVariable { variable_name } => {
                 // Store the top value on the stack in a variable.
-                let top_value = self.state.peek_value().clone();
-                self.variable_storage.set(variable_name.to_owned(), top_value.into())?;
+                let variable_name = self.string(variable_name);
+                let top_value: YarnValue = self.state.peek_value()?.clone().into();
+                if let Some(checkpoint) = self.checkpoints.back_mut() {
+                    checkpoint.record_write(&variable_name, self.variable_storage.as_ref());
+                }
+                self.logger.log(&DialogueLogRecord::VariableStored {
+                    name: &variable_name,
+                    value: &top_value,
+                });
+                self.variable_storage
+                    .set(variable_name.to_string(), top_value)?;
                 self.state.program_counter += 1;
             }
-            InstructionType::Stop(_) => {
+            LinkedInstruction::Stop => {
                 // Immediately stop execution, and report that fact.
                 let current_node_name = self.current_node_name.clone().unwrap();
-                self.batched_events
-                    .push(DialogueEvent::NodeComplete(current_node_name));
-                self.batched_events.push(DialogueEvent::DialogueComplete);
+                self.emit(DialogueEvent::NodeComplete(current_node_name))?;
+                self.emit(DialogueEvent::DialogueComplete)?;
                 self.set_execution_state(ExecutionState::Stopped);
 
                 self.state.program_counter += 1;
             }
-            InstructionType::RunNode(RunNodeInstruction { node_name }) => {
+            LinkedInstruction::RunNode { node_name } => {
                 // Run a node
+                self.jump_to_node(&self.string(node_name))?;
 
-                self.batched_events
-                    .push(DialogueEvent::NodeComplete(node_name.to_owned()));
-                self.set_node(node_name)?;
-
-                // No need to increment the program counter, since otherwise we'd skip the first instruction
-                // TODO: Reset program counter?
+                // No need to increment the program counter, since setting the node resets it
             }
-            InstructionType::PeekAndRunNode(_) => {
-                let node_name: String = self.state.pop();
-                self.set_node(node_name)?;
+            LinkedInstruction::PeekAndRunNode => {
+                let node_name: String = self.state.pop()?;
+                self.jump_to_node(&node_name)?;
             }
-            InstructionType::DetourToNode(_) => {
-                unimplemented!("DetourToNode is not implemented yet")
+            LinkedInstruction::DetourToNode { node_name } => {
+                let return_program_counter = self.state.program_counter + 1;
+                self.detour_to_node(&self.string(node_name), return_program_counter)?;
             }
-            InstructionType::PeekAndDetourToNode(_) => {
-                unimplemented!("PeekAndDetourToNode is not implemented yet")
+            LinkedInstruction::PeekAndDetourToNode => {
+                let node_name: String = self.state.pop()?;
+                let return_program_counter = self.state.program_counter + 1;
+                self.detour_to_node(&node_name, return_program_counter)?;
             }
-            InstructionType::Return(_) => {
-                unimplemented!("Return is not implemented yet")
+            LinkedInstruction::Return => {
+                self.return_from_node()?;
             }
-            InstructionType::AddSaliencyCandidate(_) => {
-                unimplemented!("AddSaliencyCandidate is not implemented yet")
+            LinkedInstruction::AddSaliencyCandidate {
+                content_id,
+                complexity_score,
+                destination,
+            } => {
+                // Adds a line of a line group as a candidate, whose condition is on the stack.
+                let content_id = self.string(content_id);
+                let condition: bool = self.state.pop()?;
+                self.saliency_candidates.push(ContentSaliencyOption {
+                    content_id: content_id.to_string(),
+                    content_type: ContentSaliencyContentType::Line,
+                    passing_condition_value_count: usize::from(condition),
+                    failing_condition_value_count: usize::from(!condition),
+                    complexity_score,
+                    view_count: self.view_count(&content_id),
+                    destination: destination as usize,
+                });
+                self.state.program_counter += 1;
             }
-            InstructionType::AddSaliencyCandidateFromNode(_) => {
-                unimplemented!("AddSaliencyCandidateFromNode is not implemented yet")
+            LinkedInstruction::AddSaliencyCandidateFromNode {
+                node_name,
+                destination,
+            } => {
+                // Adds a node of a node group as a candidate, whose conditions are its `when:` headers.
+                let candidate =
+                    self.node_saliency_candidate(&self.string(node_name), destination as usize)?;
+                self.saliency_candidates.push(candidate);
+                self.state.program_counter += 1;
+            }
+            LinkedInstruction::SelectSaliencyCandidate => {
+                // Lets the saliency strategy pick one of the candidates added since the last selection.
+                // Pushes its destination and `true` if one was picked, or only `false` otherwise.
+                // During a replay, the recorded selection is used instead, as the strategy may be random.
+                let candidates = core::mem::take(&mut self.saliency_candidates);
+                let index = match self.replay_cursor.as_mut() {
+                    Some(cursor) => match cursor
+                        .next_runtime_input("a saliency selection", |entry| {
+                            matches!(entry, ReplayEntry::ContentSelected(_))
+                        })? {
+                        ReplayEntry::ContentSelected(index) => index,
+                        _ => unreachable!(),
+                    },
+                    None => self.saliency_strategy.query_best_content(&candidates),
+                };
+                self.record_replay_entry(|| ReplayEntry::ContentSelected(index));
+                let selected = index
+                    .and_then(|index| candidates.get(index))
+                    .filter(|candidate| candidate.is_available());
+                if let Some(candidate) = selected {
+                    self.record_view(candidate)?;
+                    self.state.push(candidate.destination)?;
+                    self.state.push(true)?;
+                } else {
+                    self.state.push(false)?;
+                }
+                self.state.program_counter += 1;
             }
-            InstructionType::SelectSaliencyCandidate(_) => {
-                unimplemented!("SelectSaliencyCandidate is not implemented yet")
+            LinkedInstruction::Missing => {
+                return Err(DialogueError::InvalidInstruction {
+                    message: "Instruction has no type".to_owned(),
+                });
             }
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// The shared copy of a string operand of the current program's instructions.
+    fn string(&self, id: StringId) -> Arc<str> {
+        self.program.as_ref().unwrap().linked.string(id).clone()
+    }
+}
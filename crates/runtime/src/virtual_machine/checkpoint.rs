@@ -0,0 +1,42 @@
+//! Not part of the original implementation.
+
+use super::State;
+use crate::prelude::*;
+use alloc::sync::Arc;
+
+/// The state of the [`VirtualMachine`] right before a line or options were presented, used by [`VirtualMachine::step_back`].
+#[derive(Debug, Clone)]
+pub(crate) struct Checkpoint {
+    pub(crate) node_name: Arc<str>,
+    pub(crate) state: State,
+    /// The variables written since this checkpoint was taken and their previous values, oldest first.
+    /// `None` means that the variable did not exist before.
+    undo_log: Vec<(String, Option<YarnValue>)>,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(node_name: Arc<str>, state: State) -> Self {
+        Self {
+            node_name,
+            state,
+            undo_log: Vec::new(),
+        }
+    }
+
+    /// Remembers the value of a variable before it is overwritten.
+    pub(crate) fn record_write(&mut self, name: &str, storage: &dyn VariableStorage) {
+        self.undo_log
+            .push((name.to_owned(), storage.get(name).ok()));
+    }
+
+    /// Restores the variables written since this checkpoint was taken.
+    pub(crate) fn undo_writes(&mut self, storage: &mut dyn VariableStorage) -> Result<()> {
+        for (name, previous_value) in self.undo_log.drain(..).rev() {
+            match previous_value {
+                Some(value) => storage.set(name, value)?,
+                None => storage.retain(&mut |variable_name, _| variable_name != name)?,
+            }
+        }
+        Ok(())
+    }
+}
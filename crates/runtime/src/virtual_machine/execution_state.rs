@@ -25,4 +25,12 @@ pub(crate) enum ExecutionState {
 
     /// The VirtualMachine is in the middle of executing code.
     Running,
+
+    /// The VirtualMachine ran out of its instruction budget in the middle of a node
+    /// and resumes where it left off on the next call to [`VirtualMachine::continue_`].
+    Yielded,
+
+    /// The VirtualMachine reached a [`Breakpoint`] and resumes on the next call to
+    /// [`VirtualMachine::continue_`] without hitting it again.
+    Paused,
 }
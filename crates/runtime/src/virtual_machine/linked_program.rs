@@ -0,0 +1,343 @@
+//! Not part of the original implementation.
+//!
+//! The decoded protobuf [`Program`] stores every operand as its own `String`, so the name of a variable used by a hundred instructions
+//! is allocated a hundred times, and every instruction is a nested enum of structs behind an `Option`.
+//! When a program is loaded, a linking pass moves the instructions out of it, interns all strings into one shared table
+//! and rewrites the instructions into a flat, `Copy` representation whose string operands are indices into that table.
+
+use crate::prelude::*;
+use alloc::sync::Arc;
+use core::mem::size_of;
+use hashbrown::HashMap;
+use yarnspinner_core::prelude::instruction::{
+    AddOptionInstruction, AddSaliencyCandidateFromNodeInstruction, AddSaliencyCandidateInstruction,
+    CallFunctionInstruction, DetourToNodeInstruction, InstructionType, JumpIfFalseInstruction,
+    JumpToInstruction, PushBoolInstruction, PushFloatInstruction, PushStringInstruction,
+    PushVariableInstruction, RunCommandInstruction, RunLineInstruction, RunNodeInstruction,
+    StoreVariableInstruction,
+};
+
+/// The index of a string in a [`LinkedProgram`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct StringId(u32);
+
+/// One shared copy of every distinct string of a program.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct InternedStrings {
+    strings: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, StringId>,
+}
+
+impl InternedStrings {
+    fn intern(&mut self, string: &str) -> StringId {
+        if let Some(id) = self.ids.get(string) {
+            return *id;
+        }
+        let id = StringId(self.strings.len() as u32);
+        let string: Arc<str> = Arc::from(string);
+        self.strings.push(string.clone());
+        self.ids.insert(string, id);
+        id
+    }
+
+    fn heap_size(&self) -> usize {
+        let strings: usize = self.strings.iter().map(|string| string.len()).sum();
+        strings
+            + self.strings.capacity() * size_of::<Arc<str>>()
+            + self.ids.capacity() * size_of::<(Arc<str>, StringId)>()
+    }
+}
+
+/// An instruction of a [`LinkedNode`]. Mirrors [`InstructionType`], but with its operands flattened and strings replaced by [`StringId`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LinkedInstruction {
+    JumpTo {
+        destination: u32,
+    },
+    PeekAndJump,
+    RunLine {
+        line_id: u32,
+        substitution_count: u32,
+    },
+    RunCommand {
+        command_text: StringId,
+        substitution_count: u32,
+    },
+    AddOption {
+        tag_id: u32,
+        destination: u32,
+        substitution_count: u32,
+        has_condition: bool,
+    },
+    ShowOptions,
+    PushString(StringId),
+    PushFloat(f32),
+    PushBool(bool),
+    JumpIfFalse {
+        destination: u32,
+    },
+    Pop,
+    CallFunc {
+        function_name: StringId,
+    },
+    PushVariable {
+        variable_name: StringId,
+    },
+    StoreVariable {
+        variable_name: StringId,
+    },
+    Stop,
+    RunNode {
+        node_name: StringId,
+    },
+    PeekAndRunNode,
+    DetourToNode {
+        node_name: StringId,
+    },
+    PeekAndDetourToNode,
+    Return,
+    AddSaliencyCandidate {
+        content_id: StringId,
+        complexity_score: i32,
+        destination: u32,
+    },
+    AddSaliencyCandidateFromNode {
+        node_name: StringId,
+        destination: u32,
+    },
+    SelectSaliencyCandidate,
+    /// An instruction without an [`InstructionType`], which fails with [`DialogueError::InvalidInstruction`] when run.
+    Missing,
+}
+
+/// A node whose instructions were linked.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LinkedNode {
+    /// The shared copy of the node's name, also handed out in events.
+    pub(crate) name: Arc<str>,
+    pub(crate) instructions: Box<[LinkedInstruction]>,
+}
+
+/// The linked instructions of all nodes of a program and the strings they refer to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct LinkedProgram {
+    strings: InternedStrings,
+    nodes: HashMap<Arc<str>, Arc<LinkedNode>>,
+}
+
+impl LinkedProgram {
+    /// Moves the instructions out of every node of the program and links them, replacing already linked nodes of the same name.
+    /// The program keeps its node names, headers and initial values.
+    pub(crate) fn link(&mut self, program: &mut Program) {
+        for (node_name, node) in &mut program.nodes {
+            let id = self.strings.intern(node_name);
+            let name = self.string(id).clone();
+            let instructions = core::mem::take(&mut node.instructions)
+                .into_iter()
+                .map(|instruction| self.link_instruction(instruction))
+                .collect();
+            self.nodes
+                .insert(name.clone(), Arc::new(LinkedNode { name, instructions }));
+        }
+    }
+
+    fn link_instruction(&mut self, instruction: Instruction) -> LinkedInstruction {
+        let Some(instruction_type) = instruction.instruction_type else {
+            return LinkedInstruction::Missing;
+        };
+        let strings = &mut self.strings;
+        match instruction_type {
+            InstructionType::JumpTo(JumpToInstruction { destination }) => {
+                LinkedInstruction::JumpTo {
+                    destination: destination as u32,
+                }
+            }
+            InstructionType::PeekAndJump(_) => LinkedInstruction::PeekAndJump,
+            InstructionType::RunLine(RunLineInstruction {
+                line_id,
+                substitution_count,
+            }) => LinkedInstruction::RunLine {
+                line_id,
+                substitution_count: substitution_count as u32,
+            },
+            InstructionType::RunCommand(RunCommandInstruction {
+                command_text,
+                substitution_count,
+            }) => LinkedInstruction::RunCommand {
+                command_text: strings.intern(&command_text),
+                substitution_count: substitution_count as u32,
+            },
+            InstructionType::AddOption(AddOptionInstruction {
+                tag_id,
+                destination,
+                substitution_count,
+                has_condition,
+            }) => LinkedInstruction::AddOption {
+                tag_id,
+                destination: destination as u32,
+                substitution_count: substitution_count as u32,
+                has_condition,
+            },
+            InstructionType::ShowOptions(_) => LinkedInstruction::ShowOptions,
+            InstructionType::PushString(PushStringInstruction { value }) => {
+                LinkedInstruction::PushString(strings.intern(&value))
+            }
+            InstructionType::PushFloat(PushFloatInstruction { value }) => {
+                LinkedInstruction::PushFloat(value)
+            }
+            InstructionType::PushBool(PushBoolInstruction { value }) => {
+                LinkedInstruction::PushBool(value)
+            }
+            InstructionType::JumpIfFalse(JumpIfFalseInstruction { destination }) => {
+                LinkedInstruction::JumpIfFalse {
+                    destination: destination as u32,
+                }
+            }
+            InstructionType::Pop(_) => LinkedInstruction::Pop,
+            InstructionType::CallFunc(CallFunctionInstruction { function_name }) => {
+                LinkedInstruction::CallFunc {
+                    function_name: strings.intern(&function_name),
+                }
+            }
+            InstructionType::PushVariable(PushVariableInstruction { variable_name }) => {
+                LinkedInstruction::PushVariable {
+                    variable_name: strings.intern(&variable_name),
+                }
+            }
+            InstructionType::StoreVariable(StoreVariableInstruction { variable_name }) => {
+                LinkedInstruction::StoreVariable {
+                    variable_name: strings.intern(&variable_name),
+                }
+            }
+            InstructionType::Stop(_) => LinkedInstruction::Stop,
+            InstructionType::RunNode(RunNodeInstruction { node_name }) => {
+                LinkedInstruction::RunNode {
+                    node_name: strings.intern(&node_name),
+                }
+            }
+            InstructionType::PeekAndRunNode(_) => LinkedInstruction::PeekAndRunNode,
+            InstructionType::DetourToNode(DetourToNodeInstruction { node_name }) => {
+                LinkedInstruction::DetourToNode {
+                    node_name: strings.intern(&node_name),
+                }
+            }
+            InstructionType::PeekAndDetourToNode(_) => LinkedInstruction::PeekAndDetourToNode,
+            InstructionType::Return(_) => LinkedInstruction::Return,
+            InstructionType::AddSaliencyCandidate(AddSaliencyCandidateInstruction {
+                content_id,
+                complexity_score,
+                destination,
+            }) => LinkedInstruction::AddSaliencyCandidate {
+                content_id: strings.intern(&content_id),
+                complexity_score,
+                destination: destination as u32,
+            },
+            InstructionType::AddSaliencyCandidateFromNode(
+                AddSaliencyCandidateFromNodeInstruction {
+                    node_name,
+                    destination,
+                },
+            ) => LinkedInstruction::AddSaliencyCandidateFromNode {
+                node_name: strings.intern(&node_name),
+                destination: destination as u32,
+            },
+            InstructionType::SelectSaliencyCandidate(_) => {
+                LinkedInstruction::SelectSaliencyCandidate
+            }
+        }
+    }
+
+    /// The string with the given ID, which must come from this program.
+    pub(crate) fn string(&self, id: StringId) -> &Arc<str> {
+        &self.strings.strings[id.0 as usize]
+    }
+
+    pub(crate) fn node(&self, node_name: &str) -> Option<&Arc<LinkedNode>> {
+        self.nodes.get(node_name)
+    }
+
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = &Arc<LinkedNode>> {
+        self.nodes.values()
+    }
+
+    /// The names of the nodes that can be run after starting at `from`, see [`Program::reachable_nodes`].
+    pub(crate) fn reachable_nodes<'a>(&'a self, from: &'a str) -> Vec<&'a str> {
+        reachable_nodes(from, |node_name| {
+            let node = self.nodes.get(node_name)?;
+            Some(
+                node.instructions
+                    .iter()
+                    .filter_map(|instruction| match *instruction {
+                        LinkedInstruction::RunNode { node_name }
+                        | LinkedInstruction::DetourToNode { node_name }
+                        | LinkedInstruction::AddSaliencyCandidateFromNode { node_name, .. } => {
+                            Some(&**self.string(node_name))
+                        }
+                        _ => None,
+                    }),
+            )
+        })
+    }
+
+    /// The approximate number of bytes allocated on the heap by the linked instructions and the string table.
+    pub(crate) fn heap_size(&self) -> usize {
+        let nodes: usize = self
+            .nodes
+            .values()
+            .map(|node| size_of::<LinkedNode>() + size_of_val(&*node.instructions))
+            .sum();
+        nodes
+            + self.nodes.capacity() * size_of::<(Arc<str>, Arc<LinkedNode>)>()
+            + self.strings.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::tests::program_with_instructions;
+
+    #[test]
+    fn interns_strings_and_moves_instructions_out() {
+        let store = |variable_name: &str| {
+            InstructionType::StoreVariable(StoreVariableInstruction {
+                variable_name: variable_name.to_owned(),
+            })
+        };
+        let mut program =
+            program_with_instructions("Start", [store("$gold"), store("$gold"), store("$xp")]);
+        let mut linked = LinkedProgram::default();
+        linked.link(&mut program);
+
+        assert!(program.nodes["Start"].instructions.is_empty());
+        let node = linked.node("Start").unwrap();
+        let LinkedInstruction::StoreVariable { variable_name } = node.instructions[0] else {
+            panic!("Expected a StoreVariable instruction");
+        };
+        assert_eq!(node.instructions[0], node.instructions[1]);
+        assert_ne!(node.instructions[0], node.instructions[2]);
+        assert_eq!("$gold", &**linked.string(variable_name));
+        // The node name, `$gold` and `$xp`
+        assert_eq!(3, linked.strings.strings.len());
+        assert!(Arc::ptr_eq(&node.name, linked.string(StringId(0))));
+        assert!(size_of::<LinkedInstruction>() <= 16);
+    }
+    #[test]
+    fn deduplicates_strings_of_loaded_programs() {
+        let mut program = program_with_instructions(
+            "Start",
+            (0..100).map(|_| {
+                InstructionType::StoreVariable(StoreVariableInstruction {
+                    variable_name: "$gold_collected_in_the_mines".to_owned(),
+                })
+            }),
+        );
+        program.shrink_to_fit();
+        let decoded_size = program.heap_size();
+        let handle = ProgramHandle::new(program);
+
+        // The node name and the variable name
+        assert_eq!(2, handle.linked.strings.strings.len());
+        assert!(handle.heap_size() < decoded_size / 2);
+    }
+}
@@ -1,6 +1,8 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/VirtualMachine.cs>, which we split into multiple files
 
 use crate::prelude::*;
+use crate::Result;
+use alloc::sync::Arc;
 use core::fmt::Debug;
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -11,59 +13,125 @@ pub(crate) struct State {
 
     /// The current list of options that will be delivered
     /// when the next RunOption instruction is encountered.
-    pub(crate) current_options: Vec<DialogueOption>,
+    pub(crate) current_options: BoundedVec<DialogueOption, OPTIONS_CAPACITY>,
 
     /// The value stack.
-    pub(crate) stack: Vec<InternalValue>,
+    pub(crate) stack: BoundedVec<InternalValue, STACK_CAPACITY>,
+
+    /// The nodes and program counters to return to after a detour, innermost last.
+    pub(crate) call_stack: Vec<ReturnSite>,
+}
+
+/// The place at which execution resumes when a detoured node returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct ReturnSite {
+    pub(crate) node_name: Arc<str>,
+    pub(crate) program_counter: usize,
 }
 
 impl State {
-    pub(crate) fn push(&mut self, value: impl Into<InternalValue>) {
-        self.stack.push(value.into())
+    /// Pushes a value onto the stack, failing if it is full.
+    pub(crate) fn push(&mut self, value: impl Into<InternalValue>) -> Result<()> {
+        self.stack
+            .try_push(value.into())
+            .map_err(|_| DialogueError::CapacityExceeded {
+                buffer: CapacityBuffer::Stack,
+            })
+    }
+
+    /// Adds an option to be shown by the next `ShowOptions` instruction, failing if there are too many.
+    pub(crate) fn add_option(&mut self, option: DialogueOption) -> Result<()> {
+        self.current_options
+            .try_push(option)
+            .map_err(|_| DialogueError::CapacityExceeded {
+                buffer: CapacityBuffer::Options,
+            })
     }
 
     /// Pops a value from the stack and tries to convert it to the specified type.
     ///
-    /// ## Panics
-    /// - Panics on an empty stack to mirror C# behavior.
-    /// - Panics if the value cannot be converted to the specified type.
-    pub(crate) fn pop<T>(&mut self) -> T
+    /// ## Implementation notes
+    /// The original throws on an empty stack or a value of the wrong type. We return a [`DialogueError::InvalidInstruction`] instead,
+    /// since both can only happen for malformed programs, which must not crash the game.
+    pub(crate) fn pop<T>(&mut self) -> Result<T>
     where
         T: TryFrom<InternalValue>,
         <T as TryFrom<InternalValue>>::Error: Debug,
     {
-        self.pop_value()
-            .try_into()
-            .unwrap_or_else(|e| panic!("Failed to convert popped value: {e:?}",))
+        convert(self.pop_value()?)
     }
 
-    /// Pops a value from the stack. Panics on an empty stack to mirror C# behavior.
-    pub(crate) fn pop_value(&mut self) -> InternalValue {
+    /// Pops a value from the stack, failing on an empty stack.
+    pub(crate) fn pop_value(&mut self) -> Result<InternalValue> {
         self.stack
             .pop()
-            .unwrap_or_else(|| panic!("Tried to pop value, but the stack was empty."))
+            .ok_or_else(|| DialogueError::InvalidInstruction {
+                message: "Tried to pop a value, but the stack was empty".to_owned(),
+            })
     }
 
-    /// Peeks the top value of the stack. Panics on an empty stack to mirror C# behavior.
-    pub(crate) fn peek<T>(&self) -> T
+    /// Copies the top value of the stack and tries to convert it to the specified type, failing like [`State::pop`].
+    pub(crate) fn peek<T>(&self) -> Result<T>
     where
         T: TryFrom<InternalValue>,
         <T as TryFrom<InternalValue>>::Error: Debug,
     {
-        self.peek_value()
-            .clone()
-            .try_into()
-            .unwrap_or_else(|e| panic!("Failed to convert popped value: {e:?}",))
+        convert(self.peek_value()?.clone())
     }
 
-    /// Copies the top value of the stack and tries to convert it to the specified type.
-    ///
-    /// ## Panics
-    /// - Panics on an empty stack to mirror C# behavior.
-    /// - Panics if the value cannot be converted to the specified type.
-    pub(crate) fn peek_value(&self) -> &InternalValue {
+    /// Peeks the top value of the stack, failing on an empty stack.
+    pub(crate) fn peek_value(&self) -> Result<&InternalValue> {
         self.stack
             .last()
-            .unwrap_or_else(|| panic!("Tried to peek value, but the stack was empty."))
+            .ok_or_else(|| DialogueError::InvalidInstruction {
+                message: "Tried to peek a value, but the stack was empty".to_owned(),
+            })
+    }
+}
+
+fn convert<T>(value: InternalValue) -> Result<T>
+where
+    T: TryFrom<InternalValue>,
+    <T as TryFrom<InternalValue>>::Error: Debug,
+{
+    value
+        .try_into()
+        .map_err(|e| DialogueError::InvalidInstruction {
+            message: format!("Failed to convert a value from the stack: {e:?}"),
+        })
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut state = State {
+            program_counter: 7,
+            call_stack: vec![ReturnSite {
+                node_name: "Start".into(),
+                program_counter: 3,
+            }],
+            ..Default::default()
+        };
+        state.push(2.5).unwrap();
+        state.push("text".to_owned()).unwrap();
+        state.push(true).unwrap();
+        state
+            .add_option(DialogueOption {
+                tag_id: 1,
+                id: OptionId(0),
+                destination_node: 9,
+                is_available: true,
+                substitutions: vec![],
+                #[cfg(feature = "condition_explanations")]
+                unavailability_reason: None,
+            })
+            .unwrap();
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(state, serde_json::from_str(&json).unwrap());
     }
 }
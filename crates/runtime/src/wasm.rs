@@ -0,0 +1,456 @@
+//! Not part of the original implementation.
+//!
+//! JavaScript bindings for web-based narrative tools and browser games, see [`WasmDialogue`].
+//!
+//! `wasm32-unknown-unknown` has no clock and no threads: `SystemClock` is not available there, so use [`JsClock`] instead,
+//! and the timings of the `vm_profiling` feature are always zero.
+
+use crate::prelude::*;
+use core::any::{Any, TypeId};
+use core::fmt::{self, Display};
+use core::result::Result;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use hashbrown::HashMap;
+use js_sys::{Array, Date, Function, Map, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+/// A [`Dialogue`] for JavaScript, exported as `Dialogue`.
+///
+/// ## Example
+///
+/// Build for `wasm32-unknown-unknown` with the `wasm-bindgen` feature and generate the glue code with `wasm-bindgen` or `wasm-pack`:
+///
+/// ```js
+/// import init, { Dialogue } from "./yarnspinner_runtime.js";
+/// await init();
+/// const variables = new Map();
+/// const dialogue = new Dialogue(variables);
+/// dialogue.loadProgram(new Uint8Array(await (await fetch("game.yarnc")).arrayBuffer()));
+/// dialogue.addFunction("roll", 1, "number", (sides) => Math.ceil(Math.random() * sides));
+/// dialogue.setNode("Start");
+/// for (const event of dialogue.continue()) {
+///     if (event.type === "options") dialogue.selectOption(event.options[0].id);
+/// }
+/// console.log(variables.get("$gold"));
+/// ```
+#[wasm_bindgen(js_name = Dialogue)]
+#[derive(Debug)]
+pub struct WasmDialogue {
+    dialogue: Dialogue,
+}
+
+#[wasm_bindgen(js_class = Dialogue)]
+impl WasmDialogue {
+    /// Creates a dialogue with the standard library. Variables are stored in the given `Map`, see [`JsVariableStorage`],
+    /// or in memory if there is none.
+    #[wasm_bindgen(constructor)]
+    pub fn new(variables: Option<Map>) -> Self {
+        let variable_storage: Box<dyn VariableStorage> = match variables {
+            Some(map) => Box::new(JsVariableStorage::new(map)),
+            None => Box::new(MemoryVariableStorage::new()),
+        };
+        Self {
+            dialogue: Dialogue::new(variable_storage),
+        }
+    }
+
+    /// Loads a compiled program, i.e. the contents of a `.yarnc` file, adding its nodes to the ones loaded before.
+    /// Throws if the bytes are not a valid program or one of its nodes is already loaded.
+    #[wasm_bindgen(js_name = loadProgram)]
+    pub fn load_program(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        let program =
+            Program::from_yarnc_bytes(bytes).map_err(|error| JsError::new(&error.to_string()))?;
+        if let Some(node_name) = self
+            .dialogue
+            .node_names()
+            .into_iter()
+            .flatten()
+            .find(|node_name| program.nodes.contains_key(*node_name))
+        {
+            return Err(JsError::new(&format!(
+                "A node named {node_name} is already loaded"
+            )));
+        }
+        self.dialogue.add_program(program);
+        Ok(())
+    }
+
+    /// Registers a JavaScript function that Yarn scripts can call with `parameter_count` parameters.
+    /// `return_type` is `"number"`, `"string"` or `"boolean"`. Calls fail if the function throws or returns another type.
+    #[wasm_bindgen(js_name = addFunction)]
+    pub fn add_function(
+        &mut self,
+        name: String,
+        parameter_count: usize,
+        return_type: &str,
+        function: Function,
+    ) -> Result<(), JsError> {
+        let return_type = match return_type {
+            "number" => TypeId::of::<f32>(),
+            "string" => TypeId::of::<String>(),
+            "boolean" => TypeId::of::<bool>(),
+            _ => {
+                return Err(JsError::new(&format!(
+                    "Unknown return type \"{return_type}\", expected \"number\", \"string\" or \"boolean\""
+                )))
+            }
+        };
+        let function: Box<dyn UntypedYarnFn> = Box::new(JsFunction {
+            name: name.clone(),
+            function,
+            parameter_count,
+            return_type,
+        });
+        self.dialogue
+            .library_mut()
+            .extend([(name.into(), function)]);
+        Ok(())
+    }
+
+    /// See [`Dialogue::set_node`].
+    #[wasm_bindgen(js_name = setNode)]
+    pub fn set_node(&mut self, node_name: &str) -> Result<(), JsError> {
+        self.dialogue.set_node(node_name)?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::continue_`]. Returns the events as plain objects with a `type`, e.g. `{ type: "line", lineId: 3, substitutions: [] }`.
+    #[wasm_bindgen(js_name = continue)]
+    pub fn continue_(&mut self) -> Result<Array, JsError> {
+        let events = self.dialogue.continue_()?;
+        Ok(events.iter().map(event_to_js).collect())
+    }
+
+    /// See [`Dialogue::interrupt_line`]. Returns a `lineInterrupted` event, followed by the new events if `autoAdvance` is `true`.
+    #[wasm_bindgen(js_name = interruptLine)]
+    pub fn interrupt_line(&mut self, auto_advance: bool) -> Result<Array, JsError> {
+        let events = self.dialogue.interrupt_line(auto_advance)?;
+        Ok(events.iter().map(event_to_js).collect())
+    }
+
+    /// See [`Dialogue::can_continue`].
+    #[wasm_bindgen(js_name = canContinue)]
+    pub fn can_continue(&self) -> bool {
+        self.dialogue.can_continue()
+    }
+
+    /// Selects the option with the given `id` after an `options` event, see [`Dialogue::set_selected_option`].
+    #[wasm_bindgen(js_name = selectOption)]
+    pub fn select_option(&mut self, option_id: usize) -> Result<(), JsError> {
+        self.dialogue.set_selected_option(OptionId(option_id))?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::set_variable`]. The value must be a number, string or boolean.
+    #[wasm_bindgen(js_name = setVariable)]
+    pub fn set_variable(&mut self, name: &str, value: JsValue) -> Result<(), JsError> {
+        let value = yarn_value_from_js(&value)
+            .ok_or_else(|| JsError::new("Variables can only hold numbers, strings and booleans"))?;
+        self.dialogue.set_variable(name, value)?;
+        Ok(())
+    }
+
+    /// See [`Dialogue::variable`].
+    pub fn variable(&self, name: &str) -> Result<JsValue, JsError> {
+        Ok(yarn_value_to_js(&self.dialogue.variable(name)?))
+    }
+}
+
+/// A [`VariableStorage`] backed by a JavaScript `Map` from variable names to numbers, strings and booleans,
+/// so the page can read and write the variables of a running dialogue directly.
+#[derive(Debug, Clone)]
+pub struct JsVariableStorage(Map);
+
+// SAFETY: `wasm32-unknown-unknown` is single-threaded, so the map is never accessed from another thread.
+unsafe impl Send for JsVariableStorage {}
+unsafe impl Sync for JsVariableStorage {}
+
+impl JsVariableStorage {
+    /// Stores the variables in the given map.
+    pub fn new(map: Map) -> Self {
+        Self(map)
+    }
+
+    /// The map holding the variables.
+    pub fn map(&self) -> &Map {
+        &self.0
+    }
+
+    fn validate_name(name: &str) -> crate::variable_storage::Result<()> {
+        if is_valid_variable_name(name) {
+            Ok(())
+        } else {
+            Err(VariableStorageError::InvalidVariableName {
+                name: name.to_owned(),
+            })
+        }
+    }
+}
+
+impl VariableStorage for JsVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> crate::variable_storage::Result<()> {
+        Self::validate_name(&name)?;
+        self.0.set(&name.into(), &yarn_value_to_js(&value));
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> crate::variable_storage::Result<YarnValue> {
+        Self::validate_name(name)?;
+        let value = self.0.get(&name.into());
+        if value.is_undefined() {
+            return Err(VariableStorageError::VariableNotFound {
+                name: name.to_owned(),
+            });
+        }
+        yarn_value_from_js(&value).ok_or_else(|| VariableStorageError::InvalidCast {
+            name: name.to_owned(),
+            error: format!("{value:?} is not a number, string or boolean").into(),
+        })
+    }
+
+    fn extend(
+        &mut self,
+        values: HashMap<String, YarnValue>,
+    ) -> crate::variable_storage::Result<()> {
+        for name in values.keys() {
+            Self::validate_name(name)?;
+        }
+        for (name, value) in values {
+            self.0.set(&name.into(), &yarn_value_to_js(&value));
+        }
+        Ok(())
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        let mut variables = HashMap::new();
+        self.0.for_each(&mut |value, name| {
+            if let (Some(name), Some(value)) = (name.as_string(), yarn_value_from_js(&value)) {
+                variables.insert(name, value);
+            }
+        });
+        variables
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A [`DialogueClock`] backed by `Date.now()`, for `wasm32-unknown-unknown` where `SystemClock` is not available.
+/// Starts at zero when created and never decreases, even if the system time is set back.
+#[derive(Debug)]
+pub struct JsClock {
+    start: f64,
+    /// The latest time returned, in microseconds.
+    latest: AtomicU64,
+}
+
+impl Default for JsClock {
+    fn default() -> Self {
+        Self {
+            start: Date::now(),
+            latest: AtomicU64::new(0),
+        }
+    }
+}
+
+impl DialogueClock for JsClock {
+    fn now(&self) -> Duration {
+        let elapsed = ((Date::now() - self.start).max(0.0) * 1000.0) as u64;
+        let latest = self
+            .latest
+            .fetch_max(elapsed, Ordering::Relaxed)
+            .max(elapsed);
+        Duration::from_micros(latest)
+    }
+}
+
+/// A JavaScript function registered with [`WasmDialogue::add_function`].
+#[derive(Debug, Clone)]
+struct JsFunction {
+    name: String,
+    function: Function,
+    parameter_count: usize,
+    return_type: TypeId,
+}
+
+// SAFETY: `wasm32-unknown-unknown` is single-threaded, so the function is never called from another thread.
+unsafe impl Send for JsFunction {}
+unsafe impl Sync for JsFunction {}
+
+impl Display for JsFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (JavaScript function)", self.name)
+    }
+}
+
+impl UntypedYarnFn for JsFunction {
+    fn call(&self, input: Vec<YarnValue>) -> YarnValue {
+        self.try_call(input)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_call(&self, input: Vec<YarnValue>) -> Result<YarnValue, YarnFnError> {
+        let arguments: Array = input.iter().map(yarn_value_to_js).collect();
+        let result = self
+            .function
+            .apply(&JsValue::NULL, &arguments)
+            .map_err(|error| YarnFnError::Failed(format!("{} threw {error:?}", self.name)))?;
+        let value = yarn_value_from_js(&result).filter(|value| {
+            let expected = match value {
+                YarnValue::Number(_) | YarnValue::Integer(_) => TypeId::of::<f32>(),
+                YarnValue::String(_) => TypeId::of::<String>(),
+                YarnValue::Boolean(_) => TypeId::of::<bool>(),
+            };
+            expected == self.return_type
+        });
+        value.ok_or_else(|| {
+            YarnFnError::Failed(format!(
+                "{} returned {result:?}, which does not match its return type",
+                self.name
+            ))
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn UntypedYarnFn> {
+        Box::new(self.clone())
+    }
+
+    fn parameter_types(&self) -> Vec<TypeId> {
+        vec![TypeId::of::<YarnValue>(); self.parameter_count]
+    }
+
+    fn return_type(&self) -> TypeId {
+        self.return_type
+    }
+}
+
+fn yarn_value_to_js(value: &YarnValue) -> JsValue {
+    match value {
+        YarnValue::Number(number) => JsValue::from_f64(f64::from(*number)),
+        YarnValue::Integer(integer) => JsValue::from_f64(*integer as f64),
+        YarnValue::String(string) => JsValue::from_str(string),
+        YarnValue::Boolean(boolean) => JsValue::from_bool(*boolean),
+    }
+}
+
+/// Converts a JavaScript number, string or boolean. Numbers become [`YarnValue::Number`], as every number is in Yarn.
+fn yarn_value_from_js(value: &JsValue) -> Option<YarnValue> {
+    if let Some(boolean) = value.as_bool() {
+        Some(YarnValue::Boolean(boolean))
+    } else if let Some(number) = value.as_f64() {
+        Some(YarnValue::Number(number as f32))
+    } else {
+        value.as_string().map(YarnValue::String)
+    }
+}
+
+fn event_to_js(event: &DialogueEvent) -> JsValue {
+    let object = Object::new();
+    let set = |key: &str, value: JsValue| {
+        // Setting a property of a plain object cannot fail
+        let _ = Reflect::set(&object, &key.into(), &value);
+    };
+    let event_type = match event {
+        DialogueEvent::Line(line_id, substitutions) => {
+            set("lineId", (*line_id).into());
+            let substitutions: Array = substitutions.iter().map(yarn_value_to_js).collect();
+            set("substitutions", substitutions.into());
+            "line"
+        }
+        DialogueEvent::LineInterrupted(line_id) => {
+            set("lineId", (*line_id).into());
+            "lineInterrupted"
+        }
+        DialogueEvent::LineFiltered(record) => {
+            set("lineId", record.line_id.into());
+            set("hashtag", record.hashtag.as_ref().into());
+            let action = match record.action {
+                LineFilterAction::Suppress => "suppress",
+                LineFilterAction::Replace(replacement) => {
+                    set("replacementLineId", replacement.into());
+                    "replace"
+                }
+                LineFilterAction::Annotate => "annotate",
+            };
+            set("action", action.into());
+            "lineFiltered"
+        }
+        DialogueEvent::LineHints(hints) => {
+            set(
+                "isLastLineBeforeOptions",
+                hints.is_last_line_before_options.into(),
+            );
+            set("isFinalLineOfNode", hints.is_final_line_of_node.into());
+            set(
+                "isFinalLineOfDialogue",
+                hints.is_final_line_of_dialogue.into(),
+            );
+            "lineHints"
+        }
+        DialogueEvent::Options(options) => {
+            let options: Array = options
+                .iter()
+                .map(|option| {
+                    let object = Object::new();
+                    let _ = Reflect::set(&object, &"id".into(), &option.id.0.into());
+                    let _ = Reflect::set(&object, &"lineId".into(), &option.tag_id.into());
+                    let _ =
+                        Reflect::set(&object, &"isAvailable".into(), &option.is_available.into());
+                    let substitutions: Array =
+                        option.substitutions.iter().map(yarn_value_to_js).collect();
+                    let _ = Reflect::set(&object, &"substitutions".into(), &substitutions.into());
+                    JsValue::from(object)
+                })
+                .collect();
+            set("options", options.into());
+            "options"
+        }
+        DialogueEvent::Command(command) => {
+            set("name", command.name.as_str().into());
+            let parameters: Array = command.parameters.iter().map(yarn_value_to_js).collect();
+            set("parameters", parameters.into());
+            set("raw", command.raw.as_str().into());
+            "command"
+        }
+        DialogueEvent::Wait(duration) => {
+            set(
+                "milliseconds",
+                JsValue::from_f64(duration.as_secs_f64() * 1000.0),
+            );
+            "wait"
+        }
+        DialogueEvent::NodeComplete(node_name) => {
+            set("nodeName", node_name.as_ref().into());
+            "nodeComplete"
+        }
+        DialogueEvent::NodeStart(node_name) => {
+            set("nodeName", node_name.as_ref().into());
+            "nodeStart"
+        }
+        DialogueEvent::BreakpointHit(breakpoint) => {
+            set("nodeName", breakpoint.node_name.as_ref().into());
+            match breakpoint.location {
+                BreakpointLocation::Line(line_id) => set("lineId", line_id.into()),
+                BreakpointLocation::Instruction(index) => set("instruction", index.into()),
+            }
+            "breakpointHit"
+        }
+        DialogueEvent::DialogueComplete => "dialogueComplete",
+    };
+    set("type", event_type.into());
+    object.into()
+}
@@ -14,27 +14,24 @@ pub mod prelude {
         Program as YarnProgram, YarnFn, YarnValue,
     };
     pub use crate::runtime::{
-        Command as YarnCommand,
-        Dialogue, DialogueError, DialogueEvent, DialogueOption,
-        Language, Line as YarnLine, OptionId,
-        Result as YarnRuntimeResult, VariableStorage,
+        Command as YarnCommand, Dialogue, DialogueError, DialogueEvent, DialogueOption, Language,
+        Line as YarnLine, OptionId, Result as YarnRuntimeResult, VariableStorage,
     };
 }
 
 pub mod core {
     //! Core types and traits that are used by both the compiler and runtime.
     pub use yarnspinner_core::prelude::{
-        optionality, yarn_fn_type, yarn_library, Header, Instruction,
+        optionality, stable_hash, yarn_fn_type, yarn_library, Header, Instruction,
         IntoYarnValueFromNonYarnValue, InvalidOpCodeError, Library, LineId, Node, Position,
-        Program, Type, UntypedYarnFn, YarnFn, YarnFnParam, YarnFnParamItem, YarnValue,
-        YarnValueCastError, YarnValueWrapper, YarnValueWrapperIter,
+        Program, StableHash, StableHasher, Type, UntypedYarnFn, YarnFn, YarnFnParam,
+        YarnFnParamItem, YarnValue, YarnValueCastError, YarnValueWrapper, YarnValueWrapperIter,
     };
 }
 pub mod runtime {
     //! Types and traits used by the runtime, in particular the [`Dialogue`] struct.
     pub use yarnspinner_runtime::markup::{
-        CHARACTER_ATTRIBUTE,
-        CHARACTER_ATTRIBUTE_NAME_PROPERTY, TRIM_WHITESPACE_PROPERTY,
+        CHARACTER_ATTRIBUTE, CHARACTER_ATTRIBUTE_NAME_PROPERTY, TRIM_WHITESPACE_PROPERTY,
     };
     pub use yarnspinner_runtime::prelude::*;
     pub use yarnspinner_runtime::Result;
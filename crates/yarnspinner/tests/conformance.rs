@@ -0,0 +1,102 @@
+//! Runs `.testplan` files against this runtime.
+//!
+//! The test cases are read from the directory in `YARNSPINNER_TEST_CASES`, e.g. the upstream `Tests/TestCases`,
+//! defaulting to the test cases committed in `tests/test_cases`.
+//! Every `Foo.testplan` is run against the compiled program `Foo.yarnc` and the string table `Foo-Lines.csv` next to it.
+//! Since this repository contains no compiler, test plans without compiled output are skipped.
+//!
+//! The committed programs were compiled by hand, see `tests/test_cases/mod.rs`, so by default this is a self-consistency test
+//! of the runtime and its test plan runner, not a conformance test against upstream. CI does not set `YARNSPINNER_TEST_CASES`.
+//! Parity with the original is only checked when it points to test cases compiled by the upstream compiler,
+//! in which case the plans that fail document the deviations.
+
+mod test_cases;
+
+use prost::Message;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use yarnspinner::core::Program;
+use yarnspinner::runtime::{
+    Dialogue, MemoryVariableStorage, ReaderLineSource, StreamingTextProvider, TestPlan,
+};
+
+fn committed_test_cases_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test_cases")
+}
+
+fn test_cases_dir() -> PathBuf {
+    std::env::var_os("YARNSPINNER_TEST_CASES")
+        .map(PathBuf::from)
+        .unwrap_or_else(committed_test_cases_dir)
+}
+
+fn run_test_plan(plan_path: &Path) -> anyhow::Result<Option<()>> {
+    let program_path = plan_path.with_extension("yarnc");
+    let stem = plan_path.file_stem().unwrap().to_string_lossy();
+    let lines_path = plan_path.with_file_name(format!("{stem}-Lines.csv"));
+    if !program_path.exists() || !lines_path.exists() {
+        return Ok(None);
+    }
+
+    let plan = TestPlan::parse(&fs::read_to_string(plan_path)?)?;
+    let program = Program::decode(fs::read(program_path)?.as_slice())?;
+    let text_provider = StreamingTextProvider::new(ReaderLineSource::new(File::open(lines_path)?)?);
+    let mut dialogue = Dialogue::new(Box::new(MemoryVariableStorage::new()));
+    dialogue.add_program(program);
+    plan.run(&mut dialogue, &text_provider)?;
+    Ok(Some(()))
+}
+
+#[test]
+fn test_plans_pass() {
+    let dir = test_cases_dir();
+    let entries = fs::read_dir(&dir)
+        .unwrap_or_else(|error| panic!("Failed to read test cases in {}: {error}", dir.display()));
+    let mut plan_paths: Vec<_> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "testplan")
+        })
+        .collect();
+    plan_paths.sort();
+
+    let mut passed = 0;
+    let mut skipped = 0;
+    let mut failures = Vec::new();
+    for plan_path in &plan_paths {
+        match run_test_plan(plan_path) {
+            Ok(Some(())) => passed += 1,
+            Ok(None) => skipped += 1,
+            Err(error) => failures.push(format!("{}: {error}", plan_path.display())),
+        }
+    }
+    eprintln!(
+        "Conformance: {passed} passed, {} failed, {skipped} skipped for lack of compiled programs",
+        failures.len()
+    );
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+    assert!(
+        passed > 0,
+        "No test plan in {} has a compiled program",
+        dir.display()
+    );
+}
+
+#[test]
+fn committed_programs_match_their_sources() {
+    let dir = committed_test_cases_dir();
+    let bless = std::env::var_os("YARNSPINNER_BLESS").is_some();
+    for (name, program) in test_cases::compiled_programs() {
+        let path = dir.join(format!("{name}.yarnc"));
+        let encoded = program.encode_to_vec();
+        if bless {
+            fs::write(&path, &encoded).unwrap();
+        }
+        assert!(
+            fs::read(&path).is_ok_and(|committed| committed == encoded),
+            "{} is out of date, rerun with YARNSPINNER_BLESS=1",
+            path.display()
+        );
+    }
+}
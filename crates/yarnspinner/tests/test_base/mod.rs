@@ -207,6 +207,8 @@ impl TestBase {
                             Some(StepValue::Command(command.raw))
                         );
                     }
+                    DialogueEvent::LineHints(_) => {}
+                    DialogueEvent::BreakpointHit(_) => {}
                     DialogueEvent::NodeComplete(_) => {}
                     DialogueEvent::NodeStart(_) => {}
                     DialogueEvent::DialogueComplete => {
@@ -0,0 +1,197 @@
+//! The programs of the test cases in this directory, compiled by hand from the `.yarn` files next to them.
+//!
+//! They are written to mirror the instructions the upstream compiler emits for the same source, so that the committed `.yarnc` files
+//! can be reproduced without a compiler, but they were never checked against its output. Tests running them therefore only show
+//! that the runtime agrees with these programs, not with upstream.
+//! Run the conformance tests with `YARNSPINNER_BLESS=1` to rewrite them after a change.
+
+use yarnspinner::core::{Instruction, Node, Program};
+use yarnspinner_core::prelude::instruction::*;
+use yarnspinner_core::prelude::Operand;
+
+/// The name of every test case and its compiled program.
+pub fn compiled_programs() -> Vec<(&'static str, Program)> {
+    vec![
+        ("Commands", commands()),
+        ("IfStatements", if_statements()),
+        ("Lines", lines()),
+        ("Options", options()),
+    ]
+}
+
+fn lines() -> Program {
+    program(
+        [node(
+            "Start",
+            [run_line(1), run_line(2), run_line(3), stop()],
+        )],
+        [],
+    )
+}
+
+fn commands() -> Program {
+    program(
+        [node(
+            "Start",
+            [
+                run_command("fade_out 1.5"),
+                run_line(1),
+                run_command("play_sound \"thunder\""),
+                run_command("wait 2"),
+                stop(),
+            ],
+        )],
+        [],
+    )
+}
+
+fn options() -> Program {
+    program(
+        [node(
+            "Start",
+            [
+                run_line(1),
+                add_option(2, 7, false),
+                push_variable("$sneaky"),
+                add_option(3, 9, true),
+                add_option(4, 11, false),
+                InstructionType::ShowOptions(ShowOptionsInstruction {}),
+                InstructionType::PeekAndJump(PeekAndJumpInstruction {}),
+                // 7: A traveller.
+                run_line(5),
+                jump_to(13),
+                // 9: Nobody.
+                run_line(6),
+                jump_to(13),
+                // 11: A knight.
+                run_line(7),
+                jump_to(13),
+                // 13: End of the option group, pop the destination of the selected option
+                pop(),
+                run_line(8),
+                stop(),
+            ],
+        )],
+        [("$sneaky", false.into())],
+    )
+}
+
+fn if_statements() -> Program {
+    program(
+        [
+            node(
+                "Start",
+                [
+                    push_variable("$door_open"),
+                    jump_if_false(5),
+                    pop(),
+                    run_line(1),
+                    jump_to(10),
+                    // 5: else
+                    pop(),
+                    run_line(2),
+                    InstructionType::PushBool(PushBoolInstruction { value: true }),
+                    InstructionType::StoreVariable(StoreVariableInstruction {
+                        variable_name: "$door_open".to_owned(),
+                    }),
+                    pop(),
+                    // 10: endif
+                    InstructionType::RunNode(RunNodeInstruction {
+                        node_name: "Hallway".to_owned(),
+                    }),
+                    stop(),
+                ],
+            ),
+            node(
+                "Hallway",
+                [
+                    push_variable("$door_open"),
+                    jump_if_false(5),
+                    pop(),
+                    run_line(3),
+                    jump_to(6),
+                    // 5: end of the clause
+                    pop(),
+                    // 6: endif
+                    stop(),
+                ],
+            ),
+        ],
+        [("$door_open", false.into())],
+    )
+}
+
+fn program(
+    nodes: impl IntoIterator<Item = Node>,
+    initial_values: impl IntoIterator<Item = (&'static str, Operand)>,
+) -> Program {
+    Program {
+        nodes: nodes
+            .into_iter()
+            .map(|node| (node.name.clone(), node))
+            .collect(),
+        initial_values: initial_values
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn node(name: &str, instructions: impl IntoIterator<Item = InstructionType>) -> Node {
+    Node {
+        name: name.to_owned(),
+        instructions: instructions
+            .into_iter()
+            .map(|instruction_type| Instruction {
+                instruction_type: Some(instruction_type),
+            })
+            .collect(),
+        headers: vec![],
+    }
+}
+
+fn run_line(line_id: u32) -> InstructionType {
+    InstructionType::RunLine(RunLineInstruction {
+        line_id,
+        substitution_count: 0,
+    })
+}
+
+fn run_command(command_text: &str) -> InstructionType {
+    InstructionType::RunCommand(RunCommandInstruction {
+        command_text: command_text.to_owned(),
+        substitution_count: 0,
+    })
+}
+
+fn add_option(tag_id: u32, destination: i32, has_condition: bool) -> InstructionType {
+    InstructionType::AddOption(AddOptionInstruction {
+        tag_id,
+        destination,
+        substitution_count: 0,
+        has_condition,
+    })
+}
+
+fn push_variable(variable_name: &str) -> InstructionType {
+    InstructionType::PushVariable(PushVariableInstruction {
+        variable_name: variable_name.to_owned(),
+    })
+}
+
+fn jump_to(destination: i32) -> InstructionType {
+    InstructionType::JumpTo(JumpToInstruction { destination })
+}
+
+fn jump_if_false(destination: i32) -> InstructionType {
+    InstructionType::JumpIfFalse(JumpIfFalseInstruction { destination })
+}
+
+fn pop() -> InstructionType {
+    InstructionType::Pop(PopInstruction {})
+}
+
+fn stop() -> InstructionType {
+    InstructionType::Stop(StopInstruction {})
+}